@@ -0,0 +1,56 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use tao::{
+  clipboard::Clipboard,
+  event::{ElementState, Event, MouseButton, WindowEvent},
+  event_loop::{ControlFlow, EventLoop},
+  window::WindowBuilder,
+};
+
+fn main() {
+  env_logger::init();
+  let event_loop = EventLoop::new();
+
+  let _window = WindowBuilder::new()
+    .with_title("Copy something, or click to copy a greeting")
+    .build(&event_loop)
+    .unwrap();
+
+  let clipboard = Clipboard::new(&event_loop);
+  let mut clicks = 0;
+
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::ClipboardUpdated => {
+        if let Some(text) = clipboard.read_text() {
+          println!("Text: {:?}", text);
+        }
+        if let Some(image) = clipboard.read_image() {
+          println!("Image: {}x{}", image.width, image.height);
+        }
+        let files = clipboard.read_files();
+        if !files.is_empty() {
+          println!("Files: {:?}", files);
+        }
+      }
+      Event::WindowEvent { event, .. } => match event {
+        WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+        WindowEvent::MouseInput {
+          state: ElementState::Released,
+          button: MouseButton::Left,
+          ..
+        } => {
+          clicks += 1;
+          if let Err(e) = clipboard.write_text(&format!("Hello from tao, {} clicks", clicks)) {
+            eprintln!("Couldn't copy: {}", e);
+          }
+        }
+        _ => (),
+      },
+      _ => (),
+    }
+  });
+}
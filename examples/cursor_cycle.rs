@@ -0,0 +1,84 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use instant::Instant;
+use std::time::Duration;
+
+use tao::{
+  event::{Event, StartCause, WindowEvent},
+  event_loop::{ControlFlow, EventLoop},
+  window::{CursorIcon, WindowBuilder},
+};
+
+#[allow(clippy::single_match)]
+fn main() {
+  env_logger::init();
+  let event_loop = EventLoop::new();
+
+  let window = WindowBuilder::new()
+    .with_title("Hover here to see every cursor icon")
+    .build(&event_loop)
+    .unwrap();
+
+  let interval = Duration::from_millis(1500);
+  let mut cursor_idx = 0;
+
+  event_loop.run(move |event, _, control_flow| match event {
+    Event::NewEvents(StartCause::Init) | Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
+      let cursor = CURSORS[cursor_idx];
+      window.set_title(&format!(
+        "{:?} ({}/{})",
+        cursor,
+        cursor_idx + 1,
+        CURSORS.len()
+      ));
+      window.set_cursor_icon(cursor);
+      cursor_idx = (cursor_idx + 1) % CURSORS.len();
+      *control_flow = ControlFlow::WaitUntil(Instant::now() + interval);
+    }
+    Event::WindowEvent {
+      event: WindowEvent::CloseRequested,
+      ..
+    } => *control_flow = ControlFlow::Exit,
+    _ => (),
+  });
+}
+
+const CURSORS: &[CursorIcon] = &[
+  CursorIcon::Default,
+  CursorIcon::Crosshair,
+  CursorIcon::Hand,
+  CursorIcon::Arrow,
+  CursorIcon::Move,
+  CursorIcon::Text,
+  CursorIcon::Wait,
+  CursorIcon::Help,
+  CursorIcon::Progress,
+  CursorIcon::NotAllowed,
+  CursorIcon::ContextMenu,
+  CursorIcon::Cell,
+  CursorIcon::VerticalText,
+  CursorIcon::Alias,
+  CursorIcon::Copy,
+  CursorIcon::NoDrop,
+  CursorIcon::Grab,
+  CursorIcon::Grabbing,
+  CursorIcon::AllScroll,
+  CursorIcon::ZoomIn,
+  CursorIcon::ZoomOut,
+  CursorIcon::EResize,
+  CursorIcon::NResize,
+  CursorIcon::NeResize,
+  CursorIcon::NwResize,
+  CursorIcon::SResize,
+  CursorIcon::SeResize,
+  CursorIcon::SwResize,
+  CursorIcon::WResize,
+  CursorIcon::EwResize,
+  CursorIcon::NsResize,
+  CursorIcon::NeswResize,
+  CursorIcon::NwseResize,
+  CursorIcon::ColResize,
+  CursorIcon::RowResize,
+];
@@ -2,6 +2,9 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
+//! Sends a user event every second from another thread. With `--headless`, the event loop is
+//! headless and exits after three events, so it also runs without a display server on Linux.
+
 #[allow(clippy::single_match)]
 fn main() {
   env_logger::init();
@@ -16,12 +19,23 @@ fn main() {
     Timer,
   }
 
-  let event_loop = EventLoopBuilder::<CustomEvent>::with_user_event().build();
+  let headless = std::env::args().any(|arg| arg == "--headless");
+  let event_loop = EventLoopBuilder::<CustomEvent>::with_user_event()
+    .with_headless(headless)
+    .build();
 
-  let _window = WindowBuilder::new()
+  let window = WindowBuilder::new()
     .with_title("A fantastic window!")
-    .build(&event_loop)
-    .unwrap();
+    .build(&event_loop);
+  // Headless event loops have no windows without a display server.
+  let _window = match window {
+    Ok(window) => Some(window),
+    Err(e) if headless => {
+      println!("no window: {}", e);
+      None
+    }
+    Err(e) => panic!("{}", e),
+  };
 
   // `EventLoopProxy` allows you to dispatch custom events to the main Tao event
   // loop from any thread.
@@ -36,11 +50,19 @@ fn main() {
     }
   });
 
+  let mut user_events = 0;
+
   event_loop.run(move |event, _, control_flow| {
     *control_flow = ControlFlow::Wait;
 
     match event {
-      Event::UserEvent(event) => println!("user event: {:?}", event),
+      Event::UserEvent(event) => {
+        println!("user event: {:?}", event);
+        user_events += 1;
+        if headless && user_events == 3 {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
       Event::WindowEvent {
         event: WindowEvent::CloseRequested,
         ..
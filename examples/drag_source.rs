@@ -0,0 +1,62 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use tao::{
+  dpi::PhysicalPosition,
+  event::{ElementState, Event, MouseButton, WindowEvent},
+  event_loop::{ControlFlow, EventLoop},
+  window::{DragData, DragImage, WindowBuilder},
+};
+
+#[allow(clippy::single_match)]
+fn main() {
+  env_logger::init();
+  let event_loop = EventLoop::new();
+
+  let window = WindowBuilder::new()
+    .with_title("Drag with the left button for a file, the right one for text")
+    .build(&event_loop)
+    .unwrap();
+
+  // A 32x32 square, opaque at the center and fading to the edges.
+  let size = 32u32;
+  let mut rgba = Vec::with_capacity((size * size * 4) as usize);
+  for y in 0..size {
+    for x in 0..size {
+      let edge = x.min(y).min(size - 1 - x).min(size - 1 - y);
+      rgba.extend_from_slice(&[30, 144, 255, (edge * 16).min(255) as u8]);
+    }
+  }
+  let image = DragImage::from_rgba(rgba, size, size, PhysicalPosition::new(16, 16)).unwrap();
+  let file = PathBuf::from(file!()).canonicalize().unwrap();
+
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::WindowEvent { event, .. } => match event {
+        WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+        WindowEvent::MouseInput {
+          state: ElementState::Pressed,
+          button,
+          ..
+        } => {
+          let data = match button {
+            MouseButton::Left => DragData::Files(vec![file.clone()]),
+            MouseButton::Right => DragData::Text("Dragged from tao".into()),
+            _ => return,
+          };
+          if let Err(e) = window.start_drag(data, image.clone()) {
+            eprintln!("Failed to start the drag: {}", e);
+          }
+        }
+        WindowEvent::DragFinished(result) => println!("Drag finished: {:?}", result),
+        _ => (),
+      },
+      _ => (),
+    }
+  });
+}
@@ -60,9 +60,11 @@ fn main() {
 
           if Key::Character("f") == logical_key {
             if window.fullscreen().is_some() {
-              window.set_fullscreen(None);
+              window.try_set_fullscreen(None).unwrap();
             } else {
-              window.set_fullscreen(fullscreen.clone());
+              if let Err(e) = window.try_set_fullscreen(fullscreen.clone()) {
+                eprintln!("can't enter fullscreen: {}", e);
+              }
             }
           }
 
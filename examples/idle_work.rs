@@ -0,0 +1,72 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::{Duration, Instant};
+
+use tao::{
+  event::{Event, WindowEvent},
+  event_loop::{ControlFlow, EventLoop},
+  window::WindowBuilder,
+};
+
+/// The longest slice of work done in one idle callback, so events aren't delayed.
+const SLICE: Duration = Duration::from_millis(5);
+
+#[allow(clippy::single_match)]
+fn main() {
+  env_logger::init();
+  let event_loop = EventLoop::new();
+
+  let _window = WindowBuilder::new()
+    .with_title("Idle work, move the cursor over the window")
+    .build(&event_loop)
+    .unwrap();
+
+  // Some low-priority work, like generating thumbnails, done in idle slices.
+  let total = 20_000u64;
+  let mut next = 0u64;
+  let mut checksum = 0u64;
+  let waker = event_loop.create_waker();
+  event_loop.set_idle_callback(Some(Box::new(move |deadline| {
+    if next == total {
+      return;
+    }
+    let budget = deadline
+      .time_remaining()
+      .map_or(SLICE, |remaining| remaining.min(SLICE));
+    let start = Instant::now();
+    while next < total && start.elapsed() < budget {
+      checksum = (0..1_000).fold(checksum, |sum, i| {
+        sum.wrapping_mul(31).wrapping_add(next ^ i)
+      });
+      next += 1;
+    }
+    println!("Idle slice: {}/{} items done", next, total);
+    if next < total {
+      // Ask for another slice once the events that arrived meanwhile are handled.
+      waker.wake();
+    } else {
+      println!("Work finished, checksum {:x}", checksum);
+    }
+  })));
+
+  event_loop.run(move |event, event_loop, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::WindowEvent {
+        event: WindowEvent::CloseRequested,
+        ..
+      } => *control_flow = ControlFlow::Exit,
+      Event::WindowEvent {
+        event: WindowEvent::KeyboardInput { .. },
+        ..
+      } => {
+        println!("Key pressed, the idle work stops");
+        event_loop.set_idle_callback(None);
+      }
+      _ => (),
+    }
+  });
+}
@@ -0,0 +1,53 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use tao::{
+  dpi::{LogicalSize, PhysicalPosition, Rect},
+  event::{ElementState, Event, Ime, WindowEvent},
+  event_loop::{ControlFlow, EventLoop},
+  window::WindowBuilder,
+};
+
+fn main() {
+  env_logger::init();
+  let event_loop = EventLoop::new();
+
+  let window = WindowBuilder::new()
+    .with_title("Type with an input method, click to move the candidates")
+    .build(&event_loop)
+    .unwrap();
+  window.set_ime_allowed(true);
+
+  let mut cursor_position = PhysicalPosition::new(0.0, 0.0);
+  let mut text = String::new();
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    if let Event::WindowEvent { event, .. } = event {
+      match event {
+        WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+        WindowEvent::CursorMoved { position, .. } => cursor_position = position,
+        WindowEvent::MouseInput {
+          state: ElementState::Released,
+          ..
+        } => {
+          // The area of a text cursor of the size of a line.
+          window.set_ime_cursor_area(Rect::new(cursor_position, LogicalSize::new(2.0, 20.0)));
+        }
+        WindowEvent::Ime(Ime::Preedit(preedit, cursor)) => {
+          println!("{}[{}] cursor: {:?}", text, preedit, cursor);
+        }
+        WindowEvent::Ime(Ime::Commit(committed)) => {
+          text.push_str(&committed);
+          println!("{}", text);
+        }
+        WindowEvent::Ime(ime) => println!("{:?}", ime),
+        WindowEvent::ReceivedImeText(received) => {
+          text.push_str(&received);
+          println!("{}", text);
+        }
+        _ => (),
+      }
+    }
+  });
+}
@@ -0,0 +1,74 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use tao::{
+  event::{ElementState, Event, MouseButton, WindowEvent},
+  event_loop::{ControlFlow, EventLoop},
+  menu::{Menu, MenuItem, Submenu},
+  window::WindowBuilder,
+};
+
+fn main() {
+  env_logger::init();
+  let event_loop = EventLoop::new();
+
+  let window = WindowBuilder::new()
+    .with_title("Right click for the context menu")
+    .build(&event_loop)
+    .unwrap();
+
+  let quit = MenuItem::new("Quit");
+  let file = Submenu::new("File");
+  file.append_item(&quit);
+
+  let bold = MenuItem::new_check("Bold", false);
+  let disable = MenuItem::new_check("Disable Bold", false);
+  let edit = Submenu::new("Edit");
+  edit.append_item(&bold);
+  edit.append_separator();
+  edit.append_item(&disable);
+
+  let menu = Menu::new();
+  menu.append_submenu(&file);
+  menu.append_submenu(&edit);
+  window.set_menu(Some(&menu));
+
+  let clicks = MenuItem::new("Clicked 0 times");
+  let context_menu = Submenu::new("");
+  context_menu.append_item(&clicks);
+  let mut count = 0;
+  let mut cursor_position = None;
+
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::MenuEvent { menu_id } => {
+        println!("{:?}", menu_id);
+        if menu_id == quit.id() {
+          *control_flow = ControlFlow::Exit;
+        } else if menu_id == disable.id() {
+          bold.set_enabled(!disable.is_checked());
+        } else if menu_id == clicks.id() {
+          count += 1;
+          clicks.set_title(&format!("Clicked {} times", count));
+        }
+      }
+      Event::WindowEvent { event, .. } => match event {
+        WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+        WindowEvent::CursorMoved { position, .. } => cursor_position = Some(position),
+        WindowEvent::MouseInput {
+          state: ElementState::Released,
+          button: MouseButton::Right,
+          ..
+        } => {
+          if let Some(position) = cursor_position {
+            window.show_context_menu(&context_menu, position);
+          }
+        }
+        _ => (),
+      },
+      _ => (),
+    }
+  });
+}
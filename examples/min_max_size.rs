@@ -54,22 +54,22 @@ fn main() {
         "e" => {
           size_constraints.min_width =
             (!size_constraints.min_width.is_some()).then_some(LogicalPixel::new(min_width).into());
-          window.set_inner_size_constraints(size_constraints);
+          window.set_inner_size_constraints(size_constraints).unwrap();
         }
         "f" => {
           size_constraints.max_width =
             (!size_constraints.max_width.is_some()).then_some(LogicalPixel::new(max_width).into());
-          window.set_inner_size_constraints(size_constraints);
+          window.set_inner_size_constraints(size_constraints).unwrap();
         }
         "p" => {
           size_constraints.min_height = (!size_constraints.min_height.is_some())
             .then_some(LogicalPixel::new(min_height).into());
-          window.set_inner_size_constraints(size_constraints);
+          window.set_inner_size_constraints(size_constraints).unwrap();
         }
         "v" => {
           size_constraints.max_height = (!size_constraints.max_height.is_some())
             .then_some(LogicalPixel::new(max_height).into());
-          window.set_inner_size_constraints(size_constraints);
+          window.set_inner_size_constraints(size_constraints).unwrap();
         }
         _ => {}
       },
@@ -12,7 +12,7 @@ fn main() {
     event::{ElementState, Event, KeyEvent, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     keyboard::{Key, ModifiersState},
-    window::{CursorIcon, Fullscreen, WindowBuilder},
+    window::{CursorGrabMode, CursorIcon, Fullscreen, WindowBuilder},
   };
 
   const WINDOW_COUNT: usize = 3;
@@ -74,20 +74,37 @@ fn main() {
               // WARNING: Consider using `key_without_modifers()` if available on your platform.
               // See the `key_binding` example
               Character(string) => match string.to_lowercase().as_str() {
-                "a" => window.set_always_on_top(state),
+                "a" => {
+                  if let Err(e) = window.try_set_always_on_top(state) {
+                    println!("Can't keep the window on top: {}", e);
+                  }
+                }
                 "c" => window.set_cursor_icon(match state {
                   true => CursorIcon::Progress,
                   false => CursorIcon::Default,
                 }),
                 "d" => window.set_decorations(!state),
-                "f" => window.set_fullscreen(match (state, modifiers.alt_key()) {
-                  (true, false) => Some(Fullscreen::Borderless(None)),
-                  (true, true) => Some(Fullscreen::Exclusive(
-                    video_modes.iter().nth(video_mode_id).unwrap().clone(),
-                  )),
-                  (false, _) => None,
-                }),
-                "g" => window.set_cursor_grab(state).unwrap(),
+                "f" => {
+                  let fullscreen = match (state, modifiers.alt_key()) {
+                    (true, false) => Some(Fullscreen::Borderless(None)),
+                    (true, true) => Some(Fullscreen::Exclusive(
+                      video_modes.iter().nth(video_mode_id).unwrap().clone(),
+                    )),
+                    (false, _) => None,
+                  };
+                  if let Err(e) = window.try_set_fullscreen(fullscreen) {
+                    println!("Can't change the fullscreen state: {}", e);
+                  }
+                }
+                "g" => {
+                  let mode = match state {
+                    true => CursorGrabMode::Confined,
+                    false => CursorGrabMode::None,
+                  };
+                  if let Err(e) = window.set_cursor_grab(mode) {
+                    println!("Can't grab the cursor: {}", e);
+                  }
+                }
                 "h" => window.set_cursor_visible(!state),
                 "i" => {
                   println!("Info:");
@@ -97,10 +114,12 @@ fn main() {
                   println!("-> inner_size     : {:?}", window.inner_size());
                   println!("-> fullscreen     : {:?}", window.fullscreen());
                 }
-                "l" => window.set_min_inner_size(match state {
-                  true => Some(WINDOW_SIZE),
-                  false => None,
-                }),
+                "l" => window
+                  .set_min_inner_size(match state {
+                    true => Some(WINDOW_SIZE),
+                    false => None,
+                  })
+                  .unwrap(),
                 "m" => window.set_maximized(state),
                 "p" => window.set_outer_position({
                   let mut position = window.outer_position().unwrap();
@@ -111,10 +130,15 @@ fn main() {
                 }),
                 "q" => window.request_redraw(),
                 "r" => window.set_resizable(state),
-                "s" => window.set_inner_size(match state {
-                  true => PhysicalSize::new(WINDOW_SIZE.width + 100, WINDOW_SIZE.height + 100),
-                  false => WINDOW_SIZE,
-                }),
+                "s" => {
+                  let size = match state {
+                    true => PhysicalSize::new(WINDOW_SIZE.width + 100, WINDOW_SIZE.height + 100),
+                    false => WINDOW_SIZE,
+                  };
+                  if let Err(e) = window.try_set_inner_size(size) {
+                    println!("Can't resize the window: {}", e);
+                  }
+                }
                 "w" => {
                   if let Size::Physical(size) = WINDOW_SIZE.into() {
                     window
@@ -0,0 +1,198 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Follows every finger on a touch screen and keeps a trail for each contact, keyed by its touch
+//! id. Put at least three fingers down at once to see them tracked separately.
+//!
+//! The trails are drawn on Windows, and printed on the other platforms.
+
+use std::collections::HashMap;
+#[cfg(windows)]
+use std::{num::NonZeroU32, rc::Rc};
+
+use tao::{
+  dpi::PhysicalPosition,
+  event::{Event, Touch, TouchPhase, WindowEvent},
+  event_loop::{ControlFlow, EventLoop},
+  window::WindowBuilder,
+};
+
+struct Trail {
+  points: Vec<PhysicalPosition<f64>>,
+  /// Force of the last point, between 0.0 and 1.0 when the device reports it.
+  force: Option<f64>,
+  active: bool,
+}
+
+#[cfg(windows)]
+const COLORS: [u32; 6] = [
+  0x00e6_4a19,
+  0x0043_a047,
+  0x001e_88e5,
+  0x00fd_d835,
+  0x008e_24aa,
+  0x0000_acc1,
+];
+
+/// Draws a line of `width` pixels between `from` and `to`.
+#[cfg(windows)]
+#[allow(clippy::too_many_arguments)]
+fn draw_line(
+  buffer: &mut [u32],
+  stride: u32,
+  height: u32,
+  from: PhysicalPosition<f64>,
+  to: PhysicalPosition<f64>,
+  width: i32,
+  color: u32,
+) {
+  let steps = (to.x - from.x)
+    .abs()
+    .max((to.y - from.y).abs())
+    .ceil()
+    .max(1.0) as i32;
+  for step in 0..=steps {
+    let t = step as f64 / steps as f64;
+    let x = (from.x + (to.x - from.x) * t) as i32;
+    let y = (from.y + (to.y - from.y) * t) as i32;
+    for dy in -width / 2..=width / 2 {
+      for dx in -width / 2..=width / 2 {
+        let (x, y) = (x + dx, y + dy);
+        if x >= 0 && y >= 0 && (x as u32) < stride && (y as u32) < height {
+          buffer[(y as u32 * stride + x as u32) as usize] = color;
+        }
+      }
+    }
+  }
+}
+
+#[allow(clippy::single_match)]
+fn main() {
+  env_logger::init();
+  let event_loop = EventLoop::new();
+
+  let window = WindowBuilder::new()
+    .with_title("Touch the window with several fingers")
+    .build(&event_loop)
+    .unwrap();
+
+  #[cfg(windows)]
+  let (window, _context, mut surface) = {
+    let window = Rc::new(window);
+    let context = softbuffer::Context::new(window.clone()).unwrap();
+    let surface = softbuffer::Surface::new(&context, window.clone()).unwrap();
+    (window, context, surface)
+  };
+
+  let mut trails: HashMap<u64, Trail> = HashMap::new();
+  let mut most_contacts = 0;
+
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::WindowEvent {
+        event: WindowEvent::CloseRequested,
+        ..
+      } => *control_flow = ControlFlow::Exit,
+
+      Event::WindowEvent {
+        event:
+          WindowEvent::Touch(Touch {
+            phase,
+            location,
+            force,
+            id,
+            ..
+          }),
+        ..
+      } => {
+        let force = force.map(|force| force.normalized());
+        match phase {
+          TouchPhase::Started => {
+            // Ids are never reused, so a new contact always starts a new trail.
+            assert!(!trails.contains_key(&id), "touch id {} was reused", id);
+            trails.insert(
+              id,
+              Trail {
+                points: vec![location],
+                force,
+                active: true,
+              },
+            );
+          }
+          TouchPhase::Moved => {
+            if let Some(trail) = trails.get_mut(&id) {
+              trail.points.push(location);
+              trail.force = force;
+            }
+          }
+          TouchPhase::Ended | TouchPhase::Cancelled => {
+            if let Some(trail) = trails.get_mut(&id) {
+              trail.points.push(location);
+              trail.active = false;
+            }
+          }
+          _ => (),
+        }
+
+        let contacts = trails.values().filter(|trail| trail.active).count();
+        if contacts > most_contacts {
+          most_contacts = contacts;
+          println!("{} simultaneous contacts", contacts);
+        }
+        if let (false, Some(trail)) = (phase == TouchPhase::Moved, trails.get(&id)) {
+          println!(
+            "contact {} {:?} at {:?}, force {:?}, {} points",
+            id,
+            phase,
+            location,
+            trail.force,
+            trail.points.len()
+          );
+        }
+
+        window.request_redraw();
+      }
+
+      #[cfg(windows)]
+      Event::RedrawRequested(_) => {
+        let (width, height) = {
+          let size = window.inner_size();
+          (size.width, size.height)
+        };
+        match (NonZeroU32::new(width), NonZeroU32::new(height)) {
+          (Some(width), Some(height)) => surface.resize(width, height).unwrap(),
+          _ => return,
+        }
+
+        let mut buffer = surface.buffer_mut().unwrap();
+        buffer.fill(0x0020_2020);
+        for (id, trail) in &trails {
+          let color = COLORS[(*id % COLORS.len() as u64) as usize];
+          // Pressing harder thickens the line of active contacts.
+          let line_width = match (trail.active, trail.force) {
+            (true, Some(force)) => 2 + (force * 10.0) as i32,
+            (true, None) => 5,
+            (false, _) => 2,
+          };
+          for segment in trail.points.windows(2) {
+            draw_line(
+              &mut buffer,
+              width,
+              height,
+              segment[0],
+              segment[1],
+              line_width,
+              color,
+            );
+          }
+        }
+        buffer.present().unwrap();
+      }
+
+      _ => (),
+    }
+  });
+}
@@ -0,0 +1,72 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+#[cfg(target_os = "macos")]
+#[allow(clippy::single_match)]
+fn main() {
+  use tao::{
+    dpi::LogicalSize,
+    event::{ElementState, Event, MouseButton, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::macos::WindowExtMacOS,
+    window::WindowBuilder,
+  };
+
+  env_logger::init();
+  let event_loop = EventLoop::new();
+
+  let window = WindowBuilder::new()
+    .with_title("Click to present the sheet")
+    .build(&event_loop)
+    .unwrap();
+  let sheet = WindowBuilder::new()
+    .with_decorations(false)
+    .with_visible(false)
+    .with_inner_size(LogicalSize::new(300.0, 150.0))
+    .build(&event_loop)
+    .unwrap();
+  let mut presented = false;
+
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::WindowEvent {
+        event, window_id, ..
+      } => match event {
+        WindowEvent::CloseRequested if window_id == window.id() => {
+          *control_flow = ControlFlow::Exit
+        }
+        // Clicking the sheet ends it with the button as the response.
+        WindowEvent::MouseInput {
+          state: ElementState::Pressed,
+          button,
+          ..
+        } => {
+          if window_id == sheet.id() && presented {
+            let response = match button {
+              MouseButton::Left => 1,
+              _ => 0,
+            };
+            window.end_sheet_with_response(&sheet, response);
+          } else if window_id == window.id() && !presented {
+            window.begin_sheet(&sheet);
+            presented = true;
+          }
+        }
+        WindowEvent::SheetEnded { response, .. } => {
+          println!("Sheet ended with the response {}", response);
+          presented = false;
+        }
+        _ => (),
+      },
+      _ => (),
+    }
+  });
+}
+
+#[cfg(not(target_os = "macos"))]
+fn main() {
+  println!("This example is only supported on macOS.");
+}
@@ -0,0 +1,43 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use tao::{
+  event::{Event, WindowEvent},
+  event_loop::{ControlFlow, EventLoopBuilder},
+  window::WindowBuilder,
+};
+
+#[allow(clippy::single_match)]
+fn main() {
+  env_logger::init();
+  // Launching the example again forwards its arguments here and exits.
+  let event_loop = EventLoopBuilder::new()
+    .with_single_instance("app.tauri.tao.SingleInstance")
+    .build();
+
+  let window = WindowBuilder::new()
+    .with_title("Run this example again, with some arguments")
+    .build(&event_loop)
+    .unwrap();
+
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::WindowEvent {
+        event: WindowEvent::CloseRequested,
+        ..
+      } => *control_flow = ControlFlow::Exit,
+      Event::SecondInstance {
+        args,
+        cwd,
+        activation_token,
+      } => {
+        println!("Second instance in {:?}: {:?}", cwd, args);
+        window.set_focus_with_token(activation_token);
+      }
+      _ => (),
+    }
+  });
+}
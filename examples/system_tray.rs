@@ -0,0 +1,62 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+extern crate image;
+
+use tao::{
+  event::{Event, MouseButton, SystemTrayEvent},
+  event_loop::{ControlFlow, EventLoop},
+  menu::{MenuItem, Submenu},
+  system_tray::SystemTrayBuilder,
+  window::Icon,
+};
+
+fn main() {
+  env_logger::init();
+
+  let path = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/icon.png");
+  let image = image::open(path)
+    .expect("Failed to open icon path")
+    .into_rgba8();
+  let (width, height) = image.dimensions();
+  let icon = Icon::from_rgba(image.into_raw(), width, height).expect("Failed to open icon");
+
+  let event_loop = EventLoop::new();
+
+  let tray = SystemTrayBuilder::new(icon)
+    .with_tooltip("Left click to count, right click for the menu")
+    .build(&event_loop)
+    .unwrap();
+  let menu = Submenu::new("Tray");
+  let reset = MenuItem::new("Reset");
+  let quit = MenuItem::new("Quit");
+  menu.append_item(&reset);
+  menu.append_separator();
+  menu.append_item(&quit);
+  tray.set_menu(Some(&menu));
+  let mut clicks = 0;
+
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::SystemTrayEvent { tray_id, event } => {
+        println!("{:?}: {:?}", tray_id, event);
+        if let SystemTrayEvent::Click {
+          button: MouseButton::Left,
+          ..
+        } = event
+        {
+          clicks += 1;
+          tray.set_tooltip(Some(&format!("Clicked {} times", clicks)));
+        }
+      }
+      Event::MenuEvent { menu_id } if menu_id == reset.id() => {
+        clicks = 0;
+        tray.set_tooltip(None);
+      }
+      Event::MenuEvent { menu_id } if menu_id == quit.id() => *control_flow = ControlFlow::Exit,
+      _ => (),
+    }
+  });
+}
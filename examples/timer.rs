@@ -2,26 +2,39 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
+//! Prints a line every second. With `--headless`, the event loop is headless and exits after
+//! three timers, so it also runs without a display server on Linux.
+
 use instant::Instant;
 use std::time::Duration;
 
 use tao::{
   event::{Event, StartCause, WindowEvent},
-  event_loop::{ControlFlow, EventLoop},
+  event_loop::{ControlFlow, EventLoopBuilder},
   window::WindowBuilder,
 };
 
 #[allow(clippy::single_match)]
 fn main() {
   env_logger::init();
-  let event_loop = EventLoop::new();
+  let headless = std::env::args().any(|arg| arg == "--headless");
+  let event_loop = EventLoopBuilder::new().with_headless(headless).build();
 
-  let _window = WindowBuilder::new()
+  let window = WindowBuilder::new()
     .with_title("A fantastic window!")
-    .build(&event_loop)
-    .unwrap();
+    .build(&event_loop);
+  // Headless event loops have no windows without a display server.
+  let _window = match window {
+    Ok(window) => Some(window),
+    Err(e) if headless => {
+      println!("no window: {}", e);
+      None
+    }
+    Err(e) => panic!("{}", e),
+  };
 
   let timer_length = Duration::new(1, 0);
+  let mut timers = 0;
 
   event_loop.run(move |event, _, control_flow| {
     println!("{:?}", event);
@@ -33,6 +46,10 @@ fn main() {
       Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
         *control_flow = ControlFlow::WaitUntil(Instant::now() + timer_length);
         println!("\nTimer\n");
+        timers += 1;
+        if headless && timers == 3 {
+          *control_flow = ControlFlow::Exit;
+        }
       }
       Event::WindowEvent {
         event: WindowEvent::CloseRequested,
@@ -17,7 +17,9 @@ use tao::{
 fn main() {
   env_logger::init();
   let event_loop = EventLoop::new();
-  event_loop.set_device_event_filter(DeviceEventFilter::Never);
+  if let Err(e) = event_loop.set_device_event_filter(DeviceEventFilter::Never) {
+    eprintln!("Failed to report device events while unfocused: {}", e);
+  }
 
   let window = WindowBuilder::new()
     .with_title("A fantastic window!")
@@ -99,24 +101,30 @@ fn main() {
             .video_modes()
             .max_by(|a, b| area(a.size()).cmp(&area(b.size())))
           {
-            window.set_fullscreen(Some(Fullscreen::Exclusive(mode)));
+            if let Err(e) = window.try_set_fullscreen(Some(Fullscreen::Exclusive(mode))) {
+              eprintln!("can't enter exclusive fullscreen: {}", e);
+            }
           } else {
             eprintln!("no video modes available");
           }
         }
         "f" => {
           if window.fullscreen().is_some() {
-            window.set_fullscreen(None);
+            window.try_set_fullscreen(None).unwrap();
           } else {
             let monitor = window.current_monitor();
-            window.set_fullscreen(Some(Fullscreen::Borderless(monitor)));
+            window
+              .try_set_fullscreen(Some(Fullscreen::Borderless(monitor)))
+              .unwrap();
           }
         }
         "p" => {
           if window.fullscreen().is_some() {
-            window.set_fullscreen(None);
+            window.try_set_fullscreen(None).unwrap();
           } else {
-            window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+            window
+              .try_set_fullscreen(Some(Fullscreen::Borderless(None)))
+              .unwrap();
           }
         }
         "m" => {
@@ -134,7 +142,9 @@ fn main() {
         }
         "t" => {
           always_on_top = !always_on_top;
-          window.set_always_on_top(always_on_top);
+          if let Err(e) = window.try_set_always_on_top(always_on_top) {
+            eprintln!("can't keep the window on top: {}", e);
+          }
         }
         "b" => {
           always_on_bottom = !always_on_bottom;
@@ -0,0 +1,67 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use tao::{
+  dpi::{LogicalPosition, LogicalSize, Rect},
+  event::{ElementState, Event, MouseButton, WindowEvent},
+  event_loop::{ControlFlow, EventLoop},
+  window::{WindowBuilder, WindowShape},
+};
+
+#[allow(clippy::single_match)]
+fn main() {
+  env_logger::init();
+  let event_loop = EventLoop::new();
+
+  let window = WindowBuilder::new()
+    .with_title("Click to switch shapes, drag with the right button")
+    .with_decorations(false)
+    .with_transparent(true)
+    .with_inner_size(LogicalSize::new(400.0, 300.0))
+    .build(&event_loop)
+    .unwrap();
+
+  let shapes = [
+    Some(WindowShape::RoundedRect { radius: 24.0 }),
+    // A cross, made of two overlapping rectangles.
+    Some(WindowShape::Region(vec![
+      Rect::new(
+        LogicalPosition::new(150.0, 0.0),
+        LogicalSize::new(100.0, 300.0),
+      ),
+      Rect::new(
+        LogicalPosition::new(0.0, 100.0),
+        LogicalSize::new(400.0, 100.0),
+      ),
+    ])),
+    None,
+  ];
+  let mut current = 0;
+  window.set_shape(shapes[current].clone()).unwrap();
+
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::WindowEvent { event, .. } => match event {
+        WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+        WindowEvent::MouseInput {
+          state: ElementState::Pressed,
+          button,
+          ..
+        } => match button {
+          MouseButton::Left => {
+            current = (current + 1) % shapes.len();
+            println!("Shape: {:?}", shapes[current]);
+            window.set_shape(shapes[current].clone()).unwrap();
+          }
+          MouseButton::Right => window.drag_window().unwrap(),
+          _ => (),
+        },
+        _ => (),
+      },
+      _ => (),
+    }
+  });
+}
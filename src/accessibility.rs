@@ -0,0 +1,99 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Types for the integration of accessibility adapters, like the ones of `accesskit`.
+//!
+//! An adapter exposes the tree of the accessible elements drawn by the application to the
+//! assistive technologies of the system, and receives their action requests, like focusing or
+//! clicking an element. tao gives it:
+//!
+//! - the hooks of the platform for the tree: `WindowExtWindows::set_wm_getobject_handler` for
+//!   the `WM_GETOBJECT` messages on Windows, and `WindowExtMacOS::set_accessibility_provider`
+//!   for the accessibility methods of the `NSView` on macOS. On Linux, the adapter registers
+//!   itself with AT-SPI on the session bus, tao has nothing to hook.
+//! - an [`AccessibilityActionSender`] to deliver the action requests, which the adapter gets on
+//!   other threads, as [`Event::AccessibilityAction`] on the thread of the event loop.
+//!
+//! [`Event::AccessibilityAction`]: crate::event::Event::AccessibilityAction
+
+use std::{any::Any, fmt, sync::Arc};
+
+use crate::{event_loop::EventLoopClosed, platform_impl, window::WindowId};
+
+/// An action request of an accessibility adapter, delivered as it was sent.
+///
+/// tao doesn't depend on the adapter, the request is any value given to
+/// [`AccessibilityActionSender::send`], and read back with [`downcast_ref`](Self::downcast_ref).
+#[derive(Clone)]
+pub struct AccessibilityActionRequest(Arc<dyn Any + Send + Sync>);
+
+impl AccessibilityActionRequest {
+  /// Wraps `request`.
+  pub fn new<R: Any + Send + Sync>(request: R) -> Self {
+    Self(Arc::new(request))
+  }
+
+  /// Returns the request if it's of type `R`.
+  pub fn downcast_ref<R: Any>(&self) -> Option<&R> {
+    self.0.downcast_ref()
+  }
+}
+
+impl fmt::Debug for AccessibilityActionRequest {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.pad("AccessibilityActionRequest { .. }")
+  }
+}
+
+/// Compares the identity of the requests, a clone is equal to its original.
+impl PartialEq for AccessibilityActionRequest {
+  fn eq(&self, other: &Self) -> bool {
+    Arc::ptr_eq(&self.0, &other.0)
+  }
+}
+
+/// Sends the action requests of an accessibility adapter to the event loop of a window, see
+/// [`Window::accessibility_action_sender`].
+///
+/// It can be used from any thread.
+///
+/// [`Window::accessibility_action_sender`]: crate::window::Window::accessibility_action_sender
+#[derive(Clone)]
+pub struct AccessibilityActionSender {
+  pub(crate) window_id: WindowId,
+  pub(crate) sender: platform_impl::AccessibilityActionSender,
+}
+
+impl AccessibilityActionSender {
+  /// Returns the id of the window of the requests.
+  pub fn window_id(&self) -> WindowId {
+    self.window_id
+  }
+
+  /// Delivers `request` as an [`Event::AccessibilityAction`] of the window, once the event loop
+  /// is done with the events already queued.
+  ///
+  /// Returns an `Err` if the window or its event loop no longer exists. A request sent right
+  /// before the window is destroyed is dropped with it.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Only the event loop is checked, the request of a window that no longer exists
+  ///   is dropped by the event loop.
+  ///
+  /// [`Event::AccessibilityAction`]: crate::event::Event::AccessibilityAction
+  pub fn send<R: Any + Send + Sync>(
+    &self,
+    request: R,
+  ) -> Result<(), EventLoopClosed<AccessibilityActionRequest>> {
+    self.sender.send(AccessibilityActionRequest::new(request))
+  }
+}
+
+impl fmt::Debug for AccessibilityActionSender {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("AccessibilityActionSender")
+      .field("window_id", &self.window_id)
+      .finish_non_exhaustive()
+  }
+}
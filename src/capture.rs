@@ -0,0 +1,105 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! The captures requested with
+//! [`EventLoopWindowTarget::request_window_capture`] and
+//! [`EventLoopWindowTarget::request_monitor_capture`], the same on every platform: a request makes
+//! the event loop run an iteration with its [`EventLoopWaker`], and the capture is taken and
+//! delivered as `Event::CaptureCompleted` right after the next `Event::NewEvents`.
+
+use std::{
+  cell::{Cell, RefCell},
+  mem,
+  sync::{Arc, Weak},
+};
+
+use crate::{
+  dpi::Rect,
+  event::Event,
+  event_loop::{ControlFlow, EventLoopWaker, EventLoopWindowTarget},
+  monitor::MonitorHandle,
+  platform_impl,
+  window::{CaptureId, RgbaImage},
+};
+
+pub(crate) enum CaptureSource {
+  /// Weak, so that a pending capture doesn't keep the window alive.
+  Window(Weak<platform_impl::Window>),
+  Monitor(MonitorHandle),
+}
+
+impl CaptureSource {
+  pub(crate) fn window(window: &Arc<platform_impl::Window>) -> Self {
+    Self::Window(Arc::downgrade(window))
+  }
+
+  /// Captures `rect` of the source, all of it with `None`, or `None` if it failed.
+  fn capture(&self, rect: Option<Rect>) -> Option<RgbaImage> {
+    let result = match self {
+      Self::Window(window) => {
+        // Dropped since the request.
+        let window = window.upgrade()?;
+        window.capture(false).and_then(|image| match rect {
+          Some(rect) => image.crop(rect, window.scale_factor()),
+          None => Ok(image),
+        })
+      }
+      Self::Monitor(monitor) => match rect {
+        Some(rect) => monitor.capture_rect(rect),
+        None => monitor.capture(),
+      },
+    };
+    result
+      .map_err(|e| log::warn!("Failed to capture: {}", e))
+      .ok()
+  }
+}
+
+/// The pending captures of an event loop, owned by its `EventLoopWindowTarget`.
+#[derive(Default)]
+pub(crate) struct Captures {
+  pending: RefCell<Vec<(CaptureId, CaptureSource, Option<Rect>)>>,
+  next_id: Cell<u32>,
+  /// Created along with the first request, since it needs an `EventLoopWaker`.
+  waker: RefCell<Option<EventLoopWaker>>,
+}
+
+impl Captures {
+  pub(crate) fn request<T>(
+    &self,
+    target: &EventLoopWindowTarget<T>,
+    source: CaptureSource,
+    rect: Option<Rect>,
+  ) -> CaptureId {
+    let id = CaptureId(self.next_id.get());
+    self.next_id.set(self.next_id.get().wrapping_add(1));
+    self.pending.borrow_mut().push((id, source, rect));
+    self
+      .waker
+      .borrow_mut()
+      .get_or_insert_with(|| target.create_waker())
+      .wake();
+    id
+  }
+}
+
+/// Wraps the event handler of an event loop to take the pending captures and deliver them after
+/// each `Event::NewEvents`. Captures requested meanwhile wait for the next iteration.
+pub(crate) fn wrap_handler<T, F>(
+  mut handler: F,
+) -> impl FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow)
+where
+  F: FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+{
+  move |event, target, control_flow| {
+    let new_events = matches!(event, Event::NewEvents(_));
+    handler(event, target, control_flow);
+    if new_events {
+      let pending = mem::take(&mut *target.captures.pending.borrow_mut());
+      for (id, source, rect) in pending {
+        let image = source.capture(rect);
+        handler(Event::CaptureCompleted { id, image }, target, control_flow);
+      }
+    }
+  }
+}
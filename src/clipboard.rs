@@ -0,0 +1,80 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! The [`Clipboard`] struct, to read and write the system clipboard.
+//!
+//! While a [`Clipboard`] exists, the event loop it was created with delivers
+//! [`Event::ClipboardUpdated`] whenever the content of the clipboard changes, including by the
+//! writes of the application itself.
+//!
+//! [`Event::ClipboardUpdated`]: crate::event::Event::ClipboardUpdated
+
+use std::path::PathBuf;
+
+use crate::{
+  error::ExternalError, event_loop::EventLoopWindowTarget, platform_impl, window::RgbaImage,
+};
+
+/// The system clipboard, see the [module-level docs](self).
+///
+/// ## Platform-specific
+///
+/// - **macOS:** Changes are noticed by polling the pasteboard twice a second, so
+///   [`Event::ClipboardUpdated`] can come up to half a second after the change.
+/// - **Linux:** Reading runs the main loop of GTK until the owner of the clipboard answers.
+/// - **iOS / Android:** Unsupported, nothing can be read and writing returns
+///   [`ExternalError::NotSupported`].
+///
+/// [`Event::ClipboardUpdated`]: crate::event::Event::ClipboardUpdated
+pub struct Clipboard {
+  clipboard: platform_impl::Clipboard,
+}
+
+impl Clipboard {
+  /// Opens the clipboard, delivering [`Event::ClipboardUpdated`] to `event_loop` until it's
+  /// dropped.
+  ///
+  /// [`Event::ClipboardUpdated`]: crate::event::Event::ClipboardUpdated
+  #[inline]
+  pub fn new<T: 'static>(event_loop: &EventLoopWindowTarget<T>) -> Self {
+    Self {
+      clipboard: platform_impl::Clipboard::new(&event_loop.p),
+    }
+  }
+
+  /// Returns the text in the clipboard, if any.
+  #[inline]
+  pub fn read_text(&self) -> Option<String> {
+    self.clipboard.read_text()
+  }
+
+  /// Replaces the content of the clipboard with `text`.
+  #[inline]
+  pub fn write_text(&self, text: &str) -> Result<(), ExternalError> {
+    self.clipboard.write_text(text)
+  }
+
+  /// Returns the image in the clipboard, if any.
+  #[inline]
+  pub fn read_image(&self) -> Option<RgbaImage> {
+    self.clipboard.read_image()
+  }
+
+  /// Replaces the content of the clipboard with `image`.
+  #[inline]
+  pub fn write_image(&self, image: &RgbaImage) -> Result<(), ExternalError> {
+    self.clipboard.write_image(image)
+  }
+
+  /// Returns the files in the clipboard, empty if there are none.
+  #[inline]
+  pub fn read_files(&self) -> Vec<PathBuf> {
+    self.clipboard.read_files()
+  }
+
+  /// Replaces the content of the clipboard with `files`.
+  #[inline]
+  pub fn write_files(&self, files: &[PathBuf]) -> Result<(), ExternalError> {
+    self.clipboard.write_files(files)
+  }
+}
@@ -397,3 +397,32 @@ impl Size {
     PhysicalSize::new(width, height).into()
   }
 }
+
+/// A rectangle made of a [`Position`] and a [`Size`], each of which can be physical or logical.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rect {
+  pub position: Position,
+  pub size: Size,
+}
+
+impl Rect {
+  pub fn new<P: Into<Position>, S: Into<Size>>(position: P, size: S) -> Rect {
+    Rect {
+      position: position.into(),
+      size: size.into(),
+    }
+  }
+
+  /// Returns whether `point` lies within the rectangle, using `scale_factor` to convert
+  /// between logical and physical pixels.
+  pub fn contains<P: Into<Position>>(&self, point: P, scale_factor: f64) -> bool {
+    let point = point.into().to_physical::<f64>(scale_factor);
+    let position = self.position.to_physical::<f64>(scale_factor);
+    let size = self.size.to_physical::<f64>(scale_factor);
+    point.x >= position.x
+      && point.y >= position.y
+      && point.x < position.x + size.width
+      && point.y < position.y + size.height
+  }
+}
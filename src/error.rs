@@ -15,6 +15,20 @@ pub enum ExternalError {
   NotSupported(NotSupportedError),
   /// The OS cannot perform the operation.
   Os(OsError),
+  /// The arguments of the operation are invalid.
+  InvalidInput(&'static str),
+}
+
+/// An error returned by the `try_*` operations of a [`Window`](crate::window::Window).
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum WindowOperationError {
+  /// The operation is not supported by the backend.
+  NotSupported(NotSupportedError),
+  /// The window can't perform the operation in its current state.
+  InvalidState(&'static str),
+  /// The OS cannot perform the operation.
+  Os(OsError),
 }
 
 /// The error type for when the requested operation is not supported by the backend.
@@ -67,10 +81,33 @@ impl fmt::Display for ExternalError {
     match self {
       ExternalError::NotSupported(e) => e.fmt(f),
       ExternalError::Os(e) => e.fmt(f),
+      ExternalError::InvalidInput(message) => f.pad(message),
+    }
+  }
+}
+
+impl fmt::Display for WindowOperationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+    match self {
+      WindowOperationError::NotSupported(e) => e.fmt(f),
+      WindowOperationError::InvalidState(message) => f.pad(message),
+      WindowOperationError::Os(e) => e.fmt(f),
     }
   }
 }
 
+impl From<NotSupportedError> for WindowOperationError {
+  fn from(error: NotSupportedError) -> Self {
+    WindowOperationError::NotSupported(error)
+  }
+}
+
+impl From<OsError> for WindowOperationError {
+  fn from(error: OsError) -> Self {
+    WindowOperationError::Os(error)
+  }
+}
+
 impl fmt::Debug for NotSupportedError {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
     f.debug_struct("NotSupportedError").finish()
@@ -86,3 +123,4 @@ impl fmt::Display for NotSupportedError {
 impl error::Error for OsError {}
 impl error::Error for ExternalError {}
 impl error::Error for NotSupportedError {}
+impl error::Error for WindowOperationError {}
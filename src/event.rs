@@ -38,13 +38,27 @@
 //!
 //! [event_loop_run]: crate::event_loop::EventLoop::run
 use instant::Instant;
-use std::path::PathBuf;
+use std::{
+  collections::HashMap,
+  ffi::OsString,
+  path::PathBuf,
+  sync::atomic::{AtomicU64, Ordering},
+};
 
 use crate::{
+  accessibility::AccessibilityActionRequest,
   dpi::{PhysicalPosition, PhysicalSize},
+  event_loop::{AccessibilityPrefs, GlobalShortcutId, PowerInfo, SystemAppearance},
   keyboard::{self, ModifiersState},
+  menu::MenuId,
+  monitor::MonitorHandle,
+  notification::NotificationId,
   platform_impl,
-  window::{Theme, WindowId},
+  system_tray::SystemTrayId,
+  window::{
+    ActivationToken, CaptureId, DragResult, DropData, DropFormat, Fullscreen, RgbaImage, Theme,
+    WindowId,
+  },
 };
 
 /// Describes a generic event.
@@ -78,12 +92,52 @@ pub enum Event<'a, T: 'static> {
   /// Emitted when an event is sent from [`EventLoopProxy::send_event`](crate::event_loop::EventLoopProxy::send_event)
   UserEvent(T),
 
+  /// Emitted when a file descriptor watched through
+  /// [`EventLoopWindowTargetExtUnix::add_fd_watch`] is ready, and contains the token it was
+  /// registered with.
+  ///
+  /// The readiness is level-triggered: the event is emitted again in later iterations until the
+  /// file descriptor has been read from or written to.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows / macOS / iOS / Android:** Unsupported.
+  ///
+  /// [`EventLoopWindowTargetExtUnix::add_fd_watch`]: crate::platform::unix::EventLoopWindowTargetExtUnix::add_fd_watch
+  FdReady(u64),
+
   /// Emitted when the application has been suspended.
   Suspended,
 
   /// Emitted when the application has been resumed.
   Resumed,
 
+  /// Emitted when the native surface of the window has been created, and rendering surfaces can
+  /// be created from its raw window handle.
+  ///
+  /// The raw window handle stays valid until [`Event::SurfaceDestroyed`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Android:** The activity must call the `surface` function generated by
+  ///   `android_binding!` with the surface given to `SurfaceHolder.Callback.surfaceCreated`.
+  /// - **Windows / Linux / macOS / iOS:** Unsupported, the native window lives as long as the
+  ///   `Window`.
+  SurfaceCreated(WindowId),
+
+  /// Emitted when the native surface of the window is about to be destroyed.
+  ///
+  /// Rendering surfaces created from the raw window handle must be dropped while handling this
+  /// event, the handle is invalid once it returns and until the next [`Event::SurfaceCreated`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Android:** The activity must call the `surface` function generated by
+  ///   `android_binding!` with `null` from `SurfaceHolder.Callback.surfaceDestroyed`, which waits
+  ///   for this event to be handled.
+  /// - **Windows / Linux / macOS / iOS:** Unsupported.
+  SurfaceDestroyed(WindowId),
+
   /// Emitted when all of the event loop's input events have been processed and redraw processing
   /// is about to begin.
   ///
@@ -134,8 +188,239 @@ pub enum Event<'a, T: 'static> {
   /// gets emitted. You generally want to treat this as an "do on quit" event.
   LoopDestroyed,
 
-  /// Emitted when the app is open by external resources, like opening a file or deeplink.
-  Opened { urls: Vec<url::Url> },
+  /// Emitted when the app is asked to open external resources, like a file or a deep link.
+  ///
+  /// Items that arrive before the event loop starts are delivered right after
+  /// `NewEvents(StartCause::Init)`. See [`OpenedItem`] for how to register the app as a handler.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Items passed on the command line are delivered once at startup.
+  /// - **Linux:** Delivers the files and URIs the `GApplication` is asked to open over D-Bus,
+  ///   which needs [`EventLoopBuilder::with_single_instance`] for the application to own its id.
+  ///   The command line of the process isn't interpreted, its arguments stay in
+  ///   [`std::env::args`].
+  /// - **macOS:** URLs sent with a `kAEGetURL` Apple event are delivered as
+  ///   [`Event::OpenedUrl`] instead.
+  /// - **Android:** Unsupported.
+  ///
+  /// [`EventLoopBuilder::with_single_instance`]: crate::event_loop::EventLoopBuilder::with_single_instance
+  Opened { items: Vec<OpenedItem> },
+
+  /// Emitted when the app is asked to open a URL with a `kAEGetURL` Apple event, with the
+  /// application that sent it.
+  ///
+  /// The event is delivered even if its descriptor is malformed, with the missing fields set to
+  /// `None`. A well-formed URL is first passed to the handler set with
+  /// [`EventLoopBuilderExtMacOS::with_apple_event_handler`] as [`AppleEvent::OpenUrls`], which
+  /// can skip the event.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS only.**
+  ///
+  /// [`EventLoopBuilderExtMacOS::with_apple_event_handler`]: crate::platform::macos::EventLoopBuilderExtMacOS::with_apple_event_handler
+  /// [`AppleEvent::OpenUrls`]: crate::platform::macos::AppleEvent::OpenUrls
+  OpenedUrl(OpenedUrl),
+
+  /// Emitted when the application is reopened, e.g. by clicking its Dock icon.
+  ///
+  /// It's delivered before the application is reopened as
+  /// [`EventLoopWindowTargetExtMacOS::set_default_reopen_behavior`] says, so a behavior set
+  /// while handling the event already applies to this reopen. It stays set for the next ones.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS only.**
+  ///
+  /// [`EventLoopWindowTargetExtMacOS::set_default_reopen_behavior`]: crate::platform::macos::EventLoopWindowTargetExtMacOS::set_default_reopen_behavior
+  Reopen { has_visible_windows: bool },
+
+  /// Emitted when another application asks for a service registered with
+  /// [`EventLoopWindowTargetExtMacOS::register_service`] through the Services menu, with the
+  /// selection it sent.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS only.**
+  ///
+  /// [`EventLoopWindowTargetExtMacOS::register_service`]: crate::platform::macos::EventLoopWindowTargetExtMacOS::register_service
+  ServiceRequested(ServiceRequest),
+
+  /// Emitted when a shortcut registered with
+  /// [`EventLoopWindowTarget::register_global_shortcut`] was pressed.
+  ///
+  /// [`EventLoopWindowTarget::register_global_shortcut`]: crate::event_loop::EventLoopWindowTarget::register_global_shortcut
+  GlobalShortcut(GlobalShortcutId),
+
+  /// Emitted when the icon of a [`SystemTray`] was clicked.
+  ///
+  /// [`SystemTray`]: crate::system_tray::SystemTray
+  SystemTrayEvent {
+    tray_id: SystemTrayId,
+    event: SystemTrayEvent,
+  },
+
+  /// Emitted when a [`MenuItem`] was chosen, in the menu bar of a window, in a context menu or
+  /// in the menu of a system tray.
+  ///
+  /// [`MenuItem`]: crate::menu::MenuItem
+  MenuEvent { menu_id: MenuId },
+
+  /// Emitted when a [`Notification`] was clicked, with the `id` of the clicked action, or `None`
+  /// for a click on the notification itself.
+  ///
+  /// [`Notification`]: crate::notification::Notification
+  NotificationAction {
+    id: NotificationId,
+    action: Option<String>,
+  },
+
+  /// Emitted when an accessibility adapter sent an action request for a window with its
+  /// [`AccessibilityActionSender`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`AccessibilityActionSender`]: crate::accessibility::AccessibilityActionSender
+  AccessibilityAction {
+    window_id: WindowId,
+    request: AccessibilityActionRequest,
+  },
+
+  /// Emitted when one of the system accessibility preferences changed, with the new preferences.
+  ///
+  /// See [`EventLoopWindowTarget::accessibility_preferences`] for the preferences at startup.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`EventLoopWindowTarget::accessibility_preferences`]: crate::event_loop::EventLoopWindowTarget::accessibility_preferences
+  AccessibilityPreferencesChanged(AccessibilityPrefs),
+
+  /// Emitted when the theme, the accent color or the accessibility preferences of the system
+  /// changed, with the new appearance.
+  ///
+  /// See [`EventLoopWindowTarget::system_appearance`] for the appearance at startup.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`EventLoopWindowTarget::system_appearance`]: crate::event_loop::EventLoopWindowTarget::system_appearance
+  SystemAppearanceChanged(SystemAppearance),
+
+  /// Emitted when the system switched between battery and external power, or turned its low
+  /// power mode on or off, with the new power information.
+  ///
+  /// Changes of the battery charge alone are not reported, see
+  /// [`EventLoopWindowTarget::power_info`] to read it.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`EventLoopWindowTarget::power_info`]: crate::event_loop::EventLoopWindowTarget::power_info
+  PowerInfoChanged(PowerInfo),
+
+  /// Emitted when the session of the user running the application was locked or unlocked, or
+  /// another session took over the console and the other way around.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Disconnecting from the console or from a remote desktop session is
+  ///   [`SessionChange::SwitchedAway`], connecting to either [`SessionChange::SwitchedBack`].
+  /// - **Linux:** Read from logind on the system D-Bus, the lock state is the `LockedHint` set by
+  ///   the screen locker of the desktop environment.
+  /// - **iOS / Android:** Unsupported.
+  SessionChanged(SessionChange),
+
+  /// Emitted when a monitor was connected, or turned on.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  MonitorConnected(MonitorHandle),
+
+  /// Emitted when a monitor was disconnected, or turned off.
+  ///
+  /// The handle compares equal to the ones taken while the monitor was connected, e.g. to move
+  /// windows off of it, but its size and position aren't meaningful anymore.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Monitors are told apart by [`MonitorHandle::id`], which includes the
+  ///   connector the display is plugged into.
+  /// - **iOS / Android:** Unsupported.
+  MonitorDisconnected(MonitorHandle),
+
+  /// Emitted when the content of the clipboard changed, while a [`Clipboard`] exists.
+  ///
+  /// [`Clipboard`]: crate::clipboard::Clipboard
+  ClipboardUpdated,
+
+  /// Emitted when the user switched to another keyboard layout, or the active one was changed.
+  ///
+  /// Keys resolved with [`EventLoopWindowTarget::key_for_keycode`] may differ from then on, and
+  /// [`EventLoopWindowTarget::keyboard_layout`] returns the new layout.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Only emitted while a window of the application has focus, the layout is
+  ///   switched for the focused application.
+  /// - **macOS:** A switch made while the application is inactive is emitted once it becomes
+  ///   active again.
+  /// - **Linux (Wayland):** Only emitted when the compositor sends a new keymap, switching
+  ///   between the layouts of one keymap isn't noticed.
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`EventLoopWindowTarget::key_for_keycode`]: crate::event_loop::EventLoopWindowTarget::key_for_keycode
+  /// [`EventLoopWindowTarget::keyboard_layout`]: crate::event_loop::EventLoopWindowTarget::keyboard_layout
+  KeyboardLayoutChanged,
+
+  /// Emitted when another instance of a single-instance application was launched, with its
+  /// arguments, including the executable as the first one, and its working directory.
+  ///
+  /// `activation_token` can be passed to [`Window::set_focus_with_token`] to bring a window of
+  /// this instance to the front. See [`EventLoopBuilder::with_single_instance`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows / macOS:** `activation_token` is always `None`.
+  /// - **Linux:** `activation_token` is the XDG activation token, or on X11 the startup
+  ///   notification id, the second instance was launched with, if any. `cwd` is empty when the
+  ///   second instance couldn't tell its working directory.
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`Window::set_focus_with_token`]: crate::window::Window::set_focus_with_token
+  /// [`EventLoopBuilder::with_single_instance`]: crate::event_loop::EventLoopBuilder::with_single_instance
+  SecondInstance {
+    args: Vec<OsString>,
+    cwd: PathBuf,
+    activation_token: Option<ActivationToken>,
+  },
+
+  /// Emitted with the image of a capture requested with
+  /// [`EventLoopWindowTarget::request_window_capture`] or
+  /// [`EventLoopWindowTarget::request_monitor_capture`], which returned `id`.
+  ///
+  /// `image` is `None` when the capture failed, for the reasons [`Window::capture`] and
+  /// [`MonitorHandle::capture`] return an error, or when the window was dropped meanwhile.
+  ///
+  /// [`EventLoopWindowTarget::request_window_capture`]: crate::event_loop::EventLoopWindowTarget::request_window_capture
+  /// [`EventLoopWindowTarget::request_monitor_capture`]: crate::event_loop::EventLoopWindowTarget::request_monitor_capture
+  /// [`Window::capture`]: crate::window::Window::capture
+  CaptureCompleted {
+    id: CaptureId,
+    image: Option<RgbaImage>,
+  },
+
+  /// Emitted with a payload that native code posted with `tao_post_user_payload`, see
+  /// [`ffi_bridge`](crate::ffi_bridge).
+  #[cfg(feature = "ffi-bridge")]
+  NativeMessage(Vec<u8>),
 }
 
 impl<T: Clone> Clone for Event<'static, T> {
@@ -147,6 +432,7 @@ impl<T: Clone> Clone for Event<'static, T> {
         event: event.clone(),
       },
       UserEvent(event) => UserEvent(event.clone()),
+      FdReady(token) => FdReady(*token),
       DeviceEvent { device_id, event } => DeviceEvent {
         device_id: *device_id,
         event: event.clone(),
@@ -158,16 +444,67 @@ impl<T: Clone> Clone for Event<'static, T> {
       LoopDestroyed => LoopDestroyed,
       Suspended => Suspended,
       Resumed => Resumed,
-      Opened { urls } => Opened { urls: urls.clone() },
+      SurfaceCreated(wid) => SurfaceCreated(*wid),
+      SurfaceDestroyed(wid) => SurfaceDestroyed(*wid),
+      Opened { items } => Opened {
+        items: items.clone(),
+      },
+      OpenedUrl(url) => OpenedUrl(url.clone()),
+      ServiceRequested(request) => ServiceRequested(request.clone()),
+      GlobalShortcut(id) => GlobalShortcut(*id),
+      SystemTrayEvent { tray_id, event } => SystemTrayEvent {
+        tray_id: *tray_id,
+        event: *event,
+      },
+      MenuEvent { menu_id } => MenuEvent { menu_id: *menu_id },
+      NotificationAction { id, action } => NotificationAction {
+        id: *id,
+        action: action.clone(),
+      },
+      AccessibilityAction { window_id, request } => AccessibilityAction {
+        window_id: *window_id,
+        request: request.clone(),
+      },
+      Reopen {
+        has_visible_windows,
+      } => Reopen {
+        has_visible_windows: *has_visible_windows,
+      },
+      AccessibilityPreferencesChanged(prefs) => AccessibilityPreferencesChanged(*prefs),
+      SystemAppearanceChanged(appearance) => SystemAppearanceChanged(*appearance),
+      PowerInfoChanged(info) => PowerInfoChanged(*info),
+      SessionChanged(change) => SessionChanged(*change),
+      MonitorConnected(monitor) => MonitorConnected(monitor.clone()),
+      MonitorDisconnected(monitor) => MonitorDisconnected(monitor.clone()),
+      ClipboardUpdated => ClipboardUpdated,
+      KeyboardLayoutChanged => KeyboardLayoutChanged,
+      SecondInstance {
+        args,
+        cwd,
+        activation_token,
+      } => SecondInstance {
+        args: args.clone(),
+        cwd: cwd.clone(),
+        activation_token: activation_token.clone(),
+      },
+      CaptureCompleted { id, image } => CaptureCompleted {
+        id: *id,
+        image: image.clone(),
+      },
+      #[cfg(feature = "ffi-bridge")]
+      NativeMessage(payload) => NativeMessage(payload.clone()),
     }
   }
 }
 
 impl<'a, T> Event<'a, T> {
+  // The event is handed back as is, boxing it would only add an allocation.
+  #[allow(clippy::result_large_err)]
   pub fn map_nonuser_event<U>(self) -> Result<Event<'a, U>, Event<'a, T>> {
     use self::Event::*;
     match self {
       UserEvent(_) => Err(self),
+      FdReady(token) => Ok(FdReady(token)),
       WindowEvent { window_id, event } => Ok(WindowEvent { window_id, event }),
       DeviceEvent { device_id, event } => Ok(DeviceEvent { device_id, event }),
       NewEvents(cause) => Ok(NewEvents(cause)),
@@ -177,7 +514,41 @@ impl<'a, T> Event<'a, T> {
       LoopDestroyed => Ok(LoopDestroyed),
       Suspended => Ok(Suspended),
       Resumed => Ok(Resumed),
-      Opened { urls } => Ok(Opened { urls }),
+      SurfaceCreated(wid) => Ok(SurfaceCreated(wid)),
+      SurfaceDestroyed(wid) => Ok(SurfaceDestroyed(wid)),
+      Opened { items } => Ok(Opened { items }),
+      OpenedUrl(url) => Ok(OpenedUrl(url)),
+      ServiceRequested(request) => Ok(ServiceRequested(request)),
+      GlobalShortcut(id) => Ok(GlobalShortcut(id)),
+      SystemTrayEvent { tray_id, event } => Ok(SystemTrayEvent { tray_id, event }),
+      MenuEvent { menu_id } => Ok(MenuEvent { menu_id }),
+      NotificationAction { id, action } => Ok(NotificationAction { id, action }),
+      AccessibilityAction { window_id, request } => Ok(AccessibilityAction { window_id, request }),
+      Reopen {
+        has_visible_windows,
+      } => Ok(Reopen {
+        has_visible_windows,
+      }),
+      AccessibilityPreferencesChanged(prefs) => Ok(AccessibilityPreferencesChanged(prefs)),
+      SystemAppearanceChanged(appearance) => Ok(SystemAppearanceChanged(appearance)),
+      PowerInfoChanged(info) => Ok(PowerInfoChanged(info)),
+      SessionChanged(change) => Ok(SessionChanged(change)),
+      MonitorConnected(monitor) => Ok(MonitorConnected(monitor)),
+      MonitorDisconnected(monitor) => Ok(MonitorDisconnected(monitor)),
+      ClipboardUpdated => Ok(ClipboardUpdated),
+      KeyboardLayoutChanged => Ok(KeyboardLayoutChanged),
+      SecondInstance {
+        args,
+        cwd,
+        activation_token,
+      } => Ok(SecondInstance {
+        args,
+        cwd,
+        activation_token,
+      }),
+      CaptureCompleted { id, image } => Ok(CaptureCompleted { id, image }),
+      #[cfg(feature = "ffi-bridge")]
+      NativeMessage(payload) => Ok(NativeMessage(payload)),
     }
   }
 
@@ -190,6 +561,7 @@ impl<'a, T> Event<'a, T> {
         .to_static()
         .map(|event| WindowEvent { window_id, event }),
       UserEvent(event) => Some(UserEvent(event)),
+      FdReady(token) => Some(FdReady(token)),
       DeviceEvent { device_id, event } => Some(DeviceEvent { device_id, event }),
       NewEvents(cause) => Some(NewEvents(cause)),
       MainEventsCleared => Some(MainEventsCleared),
@@ -198,22 +570,196 @@ impl<'a, T> Event<'a, T> {
       LoopDestroyed => Some(LoopDestroyed),
       Suspended => Some(Suspended),
       Resumed => Some(Resumed),
-      Opened { urls } => Some(Opened { urls }),
+      SurfaceCreated(wid) => Some(SurfaceCreated(wid)),
+      SurfaceDestroyed(wid) => Some(SurfaceDestroyed(wid)),
+      Opened { items } => Some(Opened { items }),
+      OpenedUrl(url) => Some(OpenedUrl(url)),
+      ServiceRequested(request) => Some(ServiceRequested(request)),
+      GlobalShortcut(id) => Some(GlobalShortcut(id)),
+      SystemTrayEvent { tray_id, event } => Some(SystemTrayEvent { tray_id, event }),
+      MenuEvent { menu_id } => Some(MenuEvent { menu_id }),
+      NotificationAction { id, action } => Some(NotificationAction { id, action }),
+      AccessibilityAction { window_id, request } => {
+        Some(AccessibilityAction { window_id, request })
+      }
+      Reopen {
+        has_visible_windows,
+      } => Some(Reopen {
+        has_visible_windows,
+      }),
+      AccessibilityPreferencesChanged(prefs) => Some(AccessibilityPreferencesChanged(prefs)),
+      SystemAppearanceChanged(appearance) => Some(SystemAppearanceChanged(appearance)),
+      PowerInfoChanged(info) => Some(PowerInfoChanged(info)),
+      SessionChanged(change) => Some(SessionChanged(change)),
+      MonitorConnected(monitor) => Some(MonitorConnected(monitor)),
+      MonitorDisconnected(monitor) => Some(MonitorDisconnected(monitor)),
+      ClipboardUpdated => Some(ClipboardUpdated),
+      KeyboardLayoutChanged => Some(KeyboardLayoutChanged),
+      SecondInstance {
+        args,
+        cwd,
+        activation_token,
+      } => Some(SecondInstance {
+        args,
+        cwd,
+        activation_token,
+      }),
+      CaptureCompleted { id, image } => Some(CaptureCompleted { id, image }),
+      #[cfg(feature = "ffi-bridge")]
+      NativeMessage(payload) => Some(NativeMessage(payload)),
+    }
+  }
+}
+
+/// An item the application was asked to open, see [`Event::Opened`].
+///
+/// The OS only hands items to applications that registered themselves as a handler for them:
+///
+/// - **macOS / iOS:** Declare URL schemes under `CFBundleURLTypes` and file types under
+///   `CFBundleDocumentTypes` in the bundle's `Info.plist`.
+/// - **Windows:** Register the URL scheme under `HKEY_CLASSES_ROOT\<scheme>` with an empty
+///   `URL Protocol` value, and associate file types through a ProgID. In both cases the
+///   `shell\open\command` must pass the item as an argument, e.g. `"C:\path\app.exe" "%1"`.
+/// - **Linux:** List the MIME types and `x-scheme-handler/<scheme>` entries in the `MimeType` key
+///   of the application's `.desktop` file, and set `DBusActivatable=true` so the desktop sends
+///   the items over D-Bus. The name of the `.desktop` file must be the id of the application,
+///   see [`EventLoopBuilder::with_single_instance`].
+///
+/// [`EventLoopBuilder::with_single_instance`]: crate::event_loop::EventLoopBuilder::with_single_instance
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OpenedItem {
+  /// A URL, e.g. `myapp://some/deep/link`.
+  Url(String),
+  /// A file or directory on the local file system.
+  Path(PathBuf),
+}
+
+impl OpenedItem {
+  /// Collects the URLs and existing paths from the process' command-line arguments.
+  #[cfg(target_os = "windows")]
+  #[cfg_attr(tao_headless, allow(dead_code))]
+  pub(crate) fn from_command_line() -> Vec<OpenedItem> {
+    std::env::args_os()
+      .skip(1)
+      .filter_map(|arg| {
+        if let Some(url) = arg.to_str().and_then(|arg| url::Url::parse(arg).ok()) {
+          // Single letter schemes are actually drive letters, e.g. `C:\file.txt`.
+          if url.scheme().len() > 1 {
+            return Some(Self::from_url(url));
+          }
+        }
+        let path = PathBuf::from(arg);
+        path.exists().then(|| OpenedItem::Path(path))
+      })
+      .collect()
+  }
+
+  /// Turns `file://` URLs into [`OpenedItem::Path`] and keeps everything else as a URL.
+  #[cfg(any(target_os = "windows", target_os = "macos", target_os = "ios"))]
+  #[cfg_attr(tao_headless, allow(dead_code))]
+  pub(crate) fn from_url(url: url::Url) -> OpenedItem {
+    if url.scheme() == "file" {
+      if let Ok(path) = url.to_file_path() {
+        return OpenedItem::Path(path);
+      }
     }
+    OpenedItem::Url(url.into())
   }
 }
 
+/// A URL sent to the application with a `kAEGetURL` Apple event, see [`Event::OpenedUrl`].
+///
+/// The sender fields let the application tell a click in a browser from a script sending the
+/// event. Each field is `None` when the descriptor of the event doesn't hold it.
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OpenedUrl {
+  /// The URL as sent, it isn't parsed.
+  pub url: Option<String>,
+  /// The bundle identifier of the sending application.
+  pub source_bundle_id: Option<String>,
+  /// The process identifier of the sending application.
+  pub source_pid: Option<i32>,
+  /// The four-char code of the event class, `'GURL'` for `kInternetEventClass`.
+  pub event_class: Option<u32>,
+  /// The four-char code of the event id, `'GURL'` for `kAEGetURL`.
+  pub event_id: Option<u32>,
+}
+
+/// A request for a service of the application, see [`Event::ServiceRequested`].
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ServiceRequest {
+  /// The `NSMessage` of the service, as registered.
+  pub message: String,
+  /// The `NSUserData` of the service in the `Info.plist` of the application.
+  pub user_data: Option<String>,
+  /// The selected text, if the service takes text.
+  pub text: Option<String>,
+  /// The selected files, if the service takes files.
+  pub files: Vec<PathBuf>,
+}
+
+/// Describes a click on the icon of a [`SystemTray`], see [`Event::SystemTrayEvent`].
+///
+/// [`SystemTray`]: crate::system_tray::SystemTray
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SystemTrayEvent {
+  /// The icon was clicked and the button released.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** The tray host decides which button activates the icon, the primary activation
+  ///   is reported as [`MouseButton::Left`], the context menu request as [`MouseButton::Right`]
+  ///   and the secondary activation as [`MouseButton::Middle`].
+  Click {
+    button: MouseButton,
+    /// The position of the cursor on the screen.
+    position: PhysicalPosition<f64>,
+  },
+  /// The icon was double-clicked with the left button, after the [`SystemTrayEvent::Click`] of
+  /// the first click.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Unsupported.
+  DoubleClick {
+    /// The position of the cursor on the screen.
+    position: PhysicalPosition<f64>,
+  },
+}
+
+/// Describes a change of the session of the user, see [`Event::SessionChanged`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionChange {
+  /// The session was locked.
+  Locked,
+  /// The session was unlocked.
+  Unlocked,
+  /// Another session, or none, took over the console.
+  SwitchedAway,
+  /// The session is on the console again.
+  SwitchedBack,
+}
+
 /// Describes the reason the event loop is resuming.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum StartCause {
   /// Sent if the time specified by `ControlFlow::WaitUntil` has been reached. Contains the
-  /// moment the timeout was requested and the requested resume time. The actual resume time is
-  /// guaranteed to be equal to or after the requested resume time.
+  /// moment the timeout was requested, the requested resume time and the actual resume time.
+  /// The actual resume time is guaranteed to be equal to or after the requested resume time.
   #[non_exhaustive]
   ResumeTimeReached {
     start: Instant,
     requested_resume: Instant,
+    resumed: Instant,
   },
 
   /// Sent if the OS has new events to send to the window, after a wait was requested. Contains
@@ -239,18 +785,114 @@ pub enum WindowEvent<'a> {
   /// The size of the window has changed. Contains the client area's new dimensions.
   Resized(PhysicalSize<u32>),
 
-  /// The position of the window has changed. Contains the window's new position.
+  /// The position of the window has changed. Contains the window's new position, which is the
+  /// same point as [`Window::outer_position`](crate::window::Window::outer_position).
   ///
   /// ## Platform-specific
   ///
   /// - **Linux(Wayland)**: will always be (0, 0) since Wayland doesn't support a global cordinate system.
   Moved(PhysicalPosition<i32>),
 
-  /// The window has been requested to close.
+  /// The user started moving or resizing the window interactively, by dragging its title bar or
+  /// borders, or through [`Window::drag_window`](crate::window::Window::drag_window) and
+  /// [`Window::drag_resize_window`](crate::window::Window::drag_resize_window).
+  ///
+  /// `resize` is `true` for a resize and `false` for a move. [`WindowEvent::Moved`] and
+  /// [`WindowEvent::Resized`] keep arriving during the drag, at most about once per frame, until
+  /// [`WindowEvent::DragResizeEnded`]. Applications can use this to pause expensive rendering.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Only sent for resizes.
+  /// - **Linux:** Only sent for drags started with `drag_window` and `drag_resize_window`, or by
+  ///   the borders of undecorated windows. The drag is considered ended once the window receives
+  ///   cursor events again.
+  /// - **Android / iOS:** Unsupported.
+  DragResizeStarted { resize: bool },
+
+  /// The interactive move or resize started with [`WindowEvent::DragResizeStarted`] ended.
+  DragResizeEnded { resize: bool },
+
+  /// The window has been maximized (`true`) or restored from its maximized state (`false`).
+  ///
+  /// This is emitted whether the change was made by the user or through
+  /// [`Window::set_maximized`](crate::window::Window::set_maximized), and always agrees with
+  /// [`Window::is_maximized`](crate::window::Window::is_maximized). Minimizing a maximized window
+  /// doesn't change its maximized state.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Android / iOS:** Unsupported.
+  Maximized(bool),
+
+  /// The window has been shown (`true`) or hidden (`false`).
+  ///
+  /// This is emitted whether the change was made through
+  /// [`Window::set_visible`](crate::window::Window::set_visible) or by the system, like when the
+  /// application is hidden, with the value
+  /// [`Window::is_visible`](crate::window::Window::is_visible) returns after the change.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Minimizing the window hides it. The change is noticed through the occlusion
+  ///   state of the window, which AppKit reports asynchronously, so `is_visible` may return the
+  ///   new value a little before the event is emitted.
+  /// - **Windows:** Minimizing the window doesn't hide it.
+  /// - **Linux:** Minimizing the window doesn't hide it, but the window manager unmapping it
+  ///   otherwise, like when it moves the window to another workspace, does.
+  /// - **Android / iOS:** Unsupported.
+  VisibilityChanged(bool),
+
+  /// The window became completely hidden from view (`true`), or partly visible again (`false`).
+  ///
+  /// Applications can stop rendering while the window is occluded. It's only reported once it
+  /// changes, a window starts as not occluded.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Only hiding, minimizing or cloaking the window occludes it, like when it's on
+  ///   another virtual desktop. Other windows covering it don't.
+  /// - **Linux:** Hiding or minimizing the window occludes it. Other windows covering it only do
+  ///   on X11 without a compositing window manager.
+  /// - **Android / iOS:** Unsupported.
+  Occluded(bool),
+
+  /// The factor the system scales text by on top of the scale factor of the window changed, with
+  /// the new factor.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** See `WindowExtUnix::text_scale_factor`.
+  /// - **Windows / macOS / Android / iOS:** Unsupported.
+  TextScaleFactorChanged(f64),
+
+  /// The window finished entering or leaving fullscreen, and contains its new fullscreen state.
+  ///
+  /// This is emitted whether the change was made by the user or through
+  /// [`Window::try_set_fullscreen`](crate::window::Window::try_set_fullscreen). Several requests
+  /// made while a transition is in progress are coalesced, so only the state that is finally
+  /// applied is reported.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** The state is always reported as [`Fullscreen::Borderless`] on the current
+  ///   monitor.
+  /// - **Android / iOS:** Unsupported.
+  ///
+  /// [`Fullscreen::Borderless`]: crate::window::Fullscreen::Borderless
+  FullscreenChanged(Option<Fullscreen>),
+
+  /// The window has been requested to close, either by the user or by
+  /// [`Window::close`](crate::window::Window::close).
+  ///
+  /// The window stays open unless it is dropped, so ignoring this event vetoes the close.
   CloseRequested,
 
   /// The window has been destroyed.
   ///
+  /// This is the last event delivered for the window's [`WindowId`]; events that were still queued
+  /// for it are discarded.
+  ///
   /// ## Platform-specific
   ///
   /// - **Windows / Linux:** Only fired if the [`crate::window::Window`] is dropped.
@@ -275,9 +917,47 @@ pub enum WindowEvent<'a> {
   /// hovered.
   HoveredFileCancelled,
 
+  /// A drag started with [`Window::start_drag`](crate::window::Window::start_drag) ended.
+  DragFinished(DragResult),
+
+  /// Data is dragged over the window, at `position` in the client area, and is offered in
+  /// `formats`.
+  ///
+  /// Delivered when the drag enters the window, and each time it moves over it. What a drop does
+  /// with the data can be chosen while handling it, see `WindowExtWindows::set_drop_effect`.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux / macOS / iOS / Android:** Unsupported.
+  DragOver {
+    position: PhysicalPosition<f64>,
+    formats: Vec<DropFormat>,
+  },
+
+  /// The drag of [`WindowEvent::DragOver`] left the window without being dropped.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux / macOS / iOS / Android:** Unsupported.
+  DragLeft,
+
+  /// Data other than files with a path was dropped into the window.
+  ///
+  /// When the drop holds several items, like several virtual files, this event will be emitted
+  /// for each of them separately, after the [`WindowEvent::DroppedFile`] events of the files.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux / macOS / iOS / Android:** Unsupported.
+  DroppedData(DropData),
+
   /// The window received a unicode character.
   ReceivedImeText(String),
 
+  /// The input method editor composed text, only delivered while
+  /// [`Window::set_ime_allowed`](crate::window::Window::set_ime_allowed) is enabled.
+  Ime(Ime),
+
   /// The window gained or lost focus.
   ///
   /// The parameter is true if the window has gained focus, and false if it has lost focus.
@@ -304,9 +984,17 @@ pub enum WindowEvent<'a> {
     ///
     /// Otherwise, this value is always `false`.
     is_synthetic: bool,
+    /// When the OS generated the event, comparable with [`Instant::now`]. Synthetic events carry
+    /// the time of the event that triggered them.
+    timestamp: Instant,
   },
 
   /// The keyboard modifiers have changed.
+  ///
+  /// When a modifier key is pressed, this is sent right before its
+  /// [`WindowEvent::KeyboardInput`], and when it's released, right after it. Handlers that read
+  /// a cached modifiers state on each key event always see the state that applies to it, but
+  /// [`KeyEvent::modifiers`] also carries it.
   ModifiersChanged(ModifiersState),
 
   /// The cursor has moved on the window.
@@ -317,6 +1005,8 @@ pub enum WindowEvent<'a> {
     /// limited by the display area and it may have been transformed by the OS to implement effects such as cursor
     /// acceleration, it should not be used to implement non-cursor-like interactions such as 3D camera control.
     position: PhysicalPosition<f64>,
+    /// When the OS generated the event, comparable with [`Instant::now`].
+    timestamp: Instant,
     #[deprecated = "Deprecated in favor of WindowEvent::ModifiersChanged"]
     modifiers: ModifiersState,
   },
@@ -332,6 +1022,8 @@ pub enum WindowEvent<'a> {
     device_id: DeviceId,
     delta: MouseScrollDelta,
     phase: TouchPhase,
+    /// When the OS generated the event, comparable with [`Instant::now`].
+    timestamp: Instant,
     #[deprecated = "Deprecated in favor of WindowEvent::ModifiersChanged"]
     modifiers: ModifiersState,
   },
@@ -341,6 +1033,14 @@ pub enum WindowEvent<'a> {
     device_id: DeviceId,
     state: ElementState,
     button: MouseButton,
+    /// The number of consecutive clicks of `button`, `2` for a double click and `3` for a
+    /// triple click, following the double-click time and distance configured in the system.
+    ///
+    /// A release reports the count of the press it ends. The count restarts at `1` when the
+    /// cursor moved too far from the previous press or another button was pressed.
+    click_count: u32,
+    /// When the OS generated the event, comparable with [`Instant::now`].
+    timestamp: Instant,
     #[deprecated = "Deprecated in favor of WindowEvent::ModifiersChanged"]
     modifiers: ModifiersState,
   },
@@ -356,6 +1056,49 @@ pub enum WindowEvent<'a> {
     stage: i64,
   },
 
+  /// Two-finger pinch gesture on a touchpad, usually used for zooming.
+  ///
+  /// `delta` is the change of the magnification since the previous event. Positive values mean
+  /// zooming in. The gesture starts with [`TouchPhase::Started`], reports changes with
+  /// [`TouchPhase::Moved`] and finishes with [`TouchPhase::Ended`] or [`TouchPhase::Cancelled`].
+  ///
+  /// Gesture events are only delivered while the window has focus.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows / Android / iOS:** Unsupported.
+  TouchpadMagnify {
+    device_id: DeviceId,
+    delta: f64,
+    phase: TouchPhase,
+  },
+
+  /// Two-finger rotation gesture on a touchpad.
+  ///
+  /// `delta` is the change of the rotation since the previous event, in degrees. Positive values
+  /// mean counterclockwise rotation. The phases follow [`WindowEvent::TouchpadMagnify`].
+  ///
+  /// Gesture events are only delivered while the window has focus.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows / Android / iOS:** Unsupported.
+  TouchpadRotate {
+    device_id: DeviceId,
+    delta: f32,
+    phase: TouchPhase,
+  },
+
+  /// Smart magnification gesture on a touchpad, i.e. a two-finger double tap.
+  ///
+  /// Applications usually toggle between zooming into the content under the cursor and the
+  /// default zoom level. Only delivered while the window has focus.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows / Linux / Android / iOS:** Unsupported.
+  SmartMagnify { device_id: DeviceId },
+
   /// Motion on some analog axis. May report data redundant to other, more specific events.
   AxisMotion {
     device_id: DeviceId,
@@ -366,6 +1109,23 @@ pub enum WindowEvent<'a> {
   /// Touch event has been received
   Touch(Touch),
 
+  /// A pen came in range of the window, moved over it, touched it or left it, with the pressure,
+  /// tilt and buttons it reports.
+  ///
+  /// Pen events are also delivered while the pen hovers over the window, before it touches the
+  /// surface. The pen still moves the cursor, so its `CursorMoved` and `MouseInput` events are
+  /// delivered as well.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** From the pointer API, on Windows 8+. Pens are also reported as
+  ///   [`WindowEvent::Touch`].
+  /// - **macOS:** From the tablet events of `NSEvent`.
+  /// - **Linux:** From the GDK devices whose source is a pen or an eraser.
+  /// - **iOS / Android:** Unsupported, the Apple Pencil is reported as [`WindowEvent::Touch`] with
+  ///   [`Force::Calibrated`].
+  Pen(Pen),
+
   /// The window's scale factor has changed.
   ///
   /// The following user actions can cause DPI changes:
@@ -384,6 +1144,22 @@ pub enum WindowEvent<'a> {
     new_inner_size: &'a mut PhysicalSize<u32>,
   },
 
+  /// The window is now on a different monitor.
+  ///
+  /// This is emitted once per change, whether the window was moved or monitors were connected,
+  /// disconnected or rearranged. The handle is equal to the matching entry of
+  /// [`Window::available_monitors`](crate::window::Window::available_monitors). When the scale
+  /// factor changes because of the same move, this event is delivered before
+  /// [`WindowEvent::ScaleFactorChanged`].
+  ///
+  /// Compare [`MonitorHandle::id`] with a previously saved id to tell whether the window is back
+  /// on the same physical display after it was reconnected.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Android / iOS:** Unsupported.
+  MonitorChanged(MonitorHandle),
+
   /// The system window theme has changed.
   ///
   /// Applications might wish to react to this to change the theme of the content of the window
@@ -400,6 +1176,38 @@ pub enum WindowEvent<'a> {
   ///
   /// - **Linux / macOS / Android / iOS:** Unsupported
   DecorationsClick,
+
+  /// A sheet presented by this window with
+  /// [`WindowExtMacOS::begin_sheet`](crate::platform::macos::WindowExtMacOS::begin_sheet) ended.
+  ///
+  /// `response` is the code given to
+  /// [`WindowExtMacOS::end_sheet_with_response`](crate::platform::macos::WindowExtMacOS::end_sheet_with_response).
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows / Linux / Android / iOS:** Unsupported.
+  SheetEnded { sheet: WindowId, response: isize },
+
+  /// The user pressed the back button or made the back gesture.
+  ///
+  /// The system doesn't act on it by itself: the application decides whether to navigate back
+  /// within its content, or to leave, for example by exiting the event loop.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows / Linux / macOS / iOS:** Unsupported.
+  BackRequested,
+
+  /// The soft keyboard was shown or hidden.
+  ///
+  /// `height` is the height in physical pixels of the keyboard over the bottom of the window, so
+  /// layouts can keep their content out of it. It is `0` when the keyboard is hidden.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Android:** The keyboard is reported as hidden while the activity is paused.
+  /// - **Windows / Linux / macOS / iOS:** Unsupported.
+  ImeVisibilityChanged { visible: bool, height: u32 },
 }
 
 impl Clone for WindowEvent<'static> {
@@ -408,21 +1216,38 @@ impl Clone for WindowEvent<'static> {
     return match self {
       Resized(size) => Resized(*size),
       Moved(pos) => Moved(*pos),
+      DragResizeStarted { resize } => DragResizeStarted { resize: *resize },
+      DragResizeEnded { resize } => DragResizeEnded { resize: *resize },
+      Maximized(maximized) => Maximized(*maximized),
+      VisibilityChanged(visible) => VisibilityChanged(*visible),
+      Occluded(occluded) => Occluded(*occluded),
+      TextScaleFactorChanged(factor) => TextScaleFactorChanged(*factor),
+      FullscreenChanged(fullscreen) => FullscreenChanged(fullscreen.clone()),
       CloseRequested => CloseRequested,
       Destroyed => Destroyed,
       DroppedFile(file) => DroppedFile(file.clone()),
       HoveredFile(file) => HoveredFile(file.clone()),
       HoveredFileCancelled => HoveredFileCancelled,
+      DragFinished(result) => DragFinished(*result),
+      DragOver { position, formats } => DragOver {
+        position: *position,
+        formats: formats.clone(),
+      },
+      DragLeft => DragLeft,
+      DroppedData(data) => DroppedData(data.clone()),
       ReceivedImeText(c) => ReceivedImeText(c.clone()),
+      Ime(ime) => Ime(ime.clone()),
       Focused(f) => Focused(*f),
       KeyboardInput {
         device_id,
         event,
         is_synthetic,
+        timestamp,
       } => KeyboardInput {
         device_id: *device_id,
         event: event.clone(),
         is_synthetic: *is_synthetic,
+        timestamp: *timestamp,
       },
 
       ModifiersChanged(modifiers) => ModifiersChanged(*modifiers),
@@ -430,10 +1255,12 @@ impl Clone for WindowEvent<'static> {
       CursorMoved {
         device_id,
         position,
+        timestamp,
         modifiers,
       } => CursorMoved {
         device_id: *device_id,
         position: *position,
+        timestamp: *timestamp,
         modifiers: *modifiers,
       },
       CursorEntered { device_id } => CursorEntered {
@@ -447,11 +1274,13 @@ impl Clone for WindowEvent<'static> {
         device_id,
         delta,
         phase,
+        timestamp,
         modifiers,
       } => MouseWheel {
         device_id: *device_id,
         delta: *delta,
         phase: *phase,
+        timestamp: *timestamp,
         modifiers: *modifiers,
       },
       #[allow(deprecated)]
@@ -459,11 +1288,15 @@ impl Clone for WindowEvent<'static> {
         device_id,
         state,
         button,
+        click_count,
+        timestamp,
         modifiers,
       } => MouseInput {
         device_id: *device_id,
         state: *state,
         button: *button,
+        click_count: *click_count,
+        timestamp: *timestamp,
         modifiers: *modifiers,
       },
       TouchpadPressure {
@@ -475,6 +1308,27 @@ impl Clone for WindowEvent<'static> {
         pressure: *pressure,
         stage: *stage,
       },
+      TouchpadMagnify {
+        device_id,
+        delta,
+        phase,
+      } => TouchpadMagnify {
+        device_id: *device_id,
+        delta: *delta,
+        phase: *phase,
+      },
+      TouchpadRotate {
+        device_id,
+        delta,
+        phase,
+      } => TouchpadRotate {
+        device_id: *device_id,
+        delta: *delta,
+        phase: *phase,
+      },
+      SmartMagnify { device_id } => SmartMagnify {
+        device_id: *device_id,
+      },
       AxisMotion {
         device_id,
         axis,
@@ -485,11 +1339,22 @@ impl Clone for WindowEvent<'static> {
         value: *value,
       },
       Touch(touch) => Touch(*touch),
+      Pen(pen) => Pen(*pen),
+      MonitorChanged(monitor) => MonitorChanged(monitor.clone()),
       ThemeChanged(theme) => ThemeChanged(*theme),
       ScaleFactorChanged { .. } => {
         unreachable!("Static event can't be about scale factor changing")
       }
       DecorationsClick => DecorationsClick,
+      SheetEnded { sheet, response } => SheetEnded {
+        sheet: *sheet,
+        response: *response,
+      },
+      BackRequested => BackRequested,
+      ImeVisibilityChanged { visible, height } => ImeVisibilityChanged {
+        visible: *visible,
+        height: *height,
+      },
     };
   }
 }
@@ -500,31 +1365,47 @@ impl<'a> WindowEvent<'a> {
     match self {
       Resized(size) => Some(Resized(size)),
       Moved(position) => Some(Moved(position)),
+      DragResizeStarted { resize } => Some(DragResizeStarted { resize }),
+      DragResizeEnded { resize } => Some(DragResizeEnded { resize }),
+      Maximized(maximized) => Some(Maximized(maximized)),
+      VisibilityChanged(visible) => Some(VisibilityChanged(visible)),
+      Occluded(occluded) => Some(Occluded(occluded)),
+      TextScaleFactorChanged(factor) => Some(TextScaleFactorChanged(factor)),
+      FullscreenChanged(fullscreen) => Some(FullscreenChanged(fullscreen)),
       CloseRequested => Some(CloseRequested),
       Destroyed => Some(Destroyed),
       DroppedFile(file) => Some(DroppedFile(file)),
       HoveredFile(file) => Some(HoveredFile(file)),
       HoveredFileCancelled => Some(HoveredFileCancelled),
+      DragFinished(result) => Some(DragFinished(result)),
+      DragOver { position, formats } => Some(DragOver { position, formats }),
+      DragLeft => Some(DragLeft),
+      DroppedData(data) => Some(DroppedData(data)),
       ReceivedImeText(c) => Some(ReceivedImeText(c)),
+      Ime(ime) => Some(Ime(ime)),
       Focused(focused) => Some(Focused(focused)),
       KeyboardInput {
         device_id,
         event,
         is_synthetic,
+        timestamp,
       } => Some(KeyboardInput {
         device_id,
         event,
         is_synthetic,
+        timestamp,
       }),
       ModifiersChanged(modifiers) => Some(ModifiersChanged(modifiers)),
       #[allow(deprecated)]
       CursorMoved {
         device_id,
         position,
+        timestamp,
         modifiers,
       } => Some(CursorMoved {
         device_id,
         position,
+        timestamp,
         modifiers,
       }),
       CursorEntered { device_id } => Some(CursorEntered { device_id }),
@@ -534,11 +1415,13 @@ impl<'a> WindowEvent<'a> {
         device_id,
         delta,
         phase,
+        timestamp,
         modifiers,
       } => Some(MouseWheel {
         device_id,
         delta,
         phase,
+        timestamp,
         modifiers,
       }),
       #[allow(deprecated)]
@@ -546,11 +1429,15 @@ impl<'a> WindowEvent<'a> {
         device_id,
         state,
         button,
+        click_count,
+        timestamp,
         modifiers,
       } => Some(MouseInput {
         device_id,
         state,
         button,
+        click_count,
+        timestamp,
         modifiers,
       }),
       TouchpadPressure {
@@ -562,6 +1449,25 @@ impl<'a> WindowEvent<'a> {
         pressure,
         stage,
       }),
+      TouchpadMagnify {
+        device_id,
+        delta,
+        phase,
+      } => Some(TouchpadMagnify {
+        device_id,
+        delta,
+        phase,
+      }),
+      TouchpadRotate {
+        device_id,
+        delta,
+        phase,
+      } => Some(TouchpadRotate {
+        device_id,
+        delta,
+        phase,
+      }),
+      SmartMagnify { device_id } => Some(SmartMagnify { device_id }),
       AxisMotion {
         device_id,
         axis,
@@ -572,9 +1478,14 @@ impl<'a> WindowEvent<'a> {
         value,
       }),
       Touch(touch) => Some(Touch(touch)),
+      Pen(pen) => Some(Pen(pen)),
+      MonitorChanged(monitor) => Some(MonitorChanged(monitor)),
       ThemeChanged(theme) => Some(ThemeChanged(theme)),
       ScaleFactorChanged { .. } => None,
       DecorationsClick => Some(DecorationsClick),
+      SheetEnded { sheet, response } => Some(SheetEnded { sheet, response }),
+      BackRequested => Some(BackRequested),
+      ImeVisibilityChanged { visible, height } => Some(ImeVisibilityChanged { visible, height }),
     }
   }
 }
@@ -616,6 +1527,12 @@ pub enum DeviceEvent {
   /// Change in physical position of a pointing device.
   ///
   /// This represents raw, unfiltered physical motion. Not to be confused with `WindowEvent::CursorMoved`.
+  /// It's still reported while the cursor is locked with `CursorGrabMode::Locked`.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Only on X11, from the XInput2 raw motion events.
+  /// - **iOS / Android:** Unsupported.
   #[non_exhaustive]
   MouseMotion {
     /// (x, y) change in position in unspecified units.
@@ -718,8 +1635,24 @@ pub struct KeyEvent {
 
   pub location: keyboard::KeyLocation,
   pub state: ElementState,
+
+  /// Whether or not this key is a key repeat event generated by the OS while the key is held
+  /// down.
+  ///
+  /// This is always `false` for [`ElementState::Released`] events.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** GTK doesn't report auto-repeat, so tao tracks which physical keys are held
+  ///   down and marks any press of an already pressed key as a repeat.
   pub repeat: bool,
 
+  /// The modifiers held down when the key event happened.
+  ///
+  /// For a modifier key, this includes the key itself both when it's pressed and when it's
+  /// released, matching the order of [`WindowEvent::ModifiersChanged`] around this event.
+  pub modifiers: ModifiersState,
+
   pub(crate) platform_specific: platform_impl::KeyEventExtra,
 }
 
@@ -760,6 +1693,27 @@ impl KeyEvent {
   }
 }
 
+/// A step of the composition of text with the input method editor (IME), see
+/// [`WindowEvent::Ime`].
+///
+/// While the IME composes, the text isn't final and should be shown with its cursor, typically
+/// underlined, in place of the selection. An empty [`Ime::Preedit`] clears it, it's delivered
+/// when the composition is committed or cancelled.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Ime {
+  /// The window started reporting the composition.
+  Enabled,
+  /// The text being composed, with the range of its cursor as byte offsets in the text, `None`
+  /// to hide the cursor.
+  Preedit(String, Option<(usize, usize)>),
+  /// The composition was committed as this text.
+  Commit(String),
+  /// The window stopped reporting the composition, no other event follows until it's enabled
+  /// again.
+  Disabled,
+}
+
 /// Describes touch-screen input state.
 #[non_exhaustive]
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
@@ -780,9 +1734,9 @@ pub enum TouchPhase {
 /// After a `Start` event has been emitted, there may be zero or more `Move`
 /// events when the finger is moved or the touch pressure changes.
 ///
-/// The finger id may be reused by the system after an `End` event. The user
-/// should assume that a new `Start` event received with the same id has nothing
-/// to do with the old finger and is a new finger.
+/// The finger id is never reused, not even after the `End` or `Cancelled` event
+/// of the finger has been delivered, and no two windows share an id, so ids can be
+/// used as keys to track several simultaneous contacts across windows.
 ///
 /// A `Cancelled` event is emitted when the system has canceled tracking this
 /// touch, such as when the window loses focus, or on iOS if the user moves the
@@ -797,10 +1751,105 @@ pub struct Touch {
   ///
   /// ## Platform-specific
   ///
-  /// - Only available on **iOS** 9.0+ and **Windows** 8+.
+  /// - **iOS:** Calibrated, available on 9.0+ with a device that supports it.
+  /// - **Windows:** Normalized, available on 8+ for touch screens and pens reporting it.
+  /// - **Linux / Android:** Normalized, when the device reports a pressure axis.
+  /// - **macOS:** Touch screens aren't supported.
   pub force: Option<Force>,
-  /// Unique identifier of a finger.
+  /// The orientation of the contact area, in degrees clockwise from the vertical axis of the
+  /// window, from `0.0` to `360.0`. `None` if the platform doesn't report it.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Available for touch screens reporting it, through `WM_POINTER`.
+  /// - **Android:** Available for touch screens reporting it, in steps of `180.0` for the same
+  ///   contact since the direction of the major axis isn't known.
+  /// - **iOS / Linux:** Unsupported, always `None`.
+  pub rotation: Option<f64>,
+  /// Unique identifier of a finger, see the [`Touch`] docs for its lifecycle.
   pub id: u64,
+  /// When the OS generated the event, comparable with [`Instant::now`].
+  pub timestamp: Instant,
+}
+
+/// Describes the phase of a [`Pen`] event.
+#[non_exhaustive]
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PenPhase {
+  /// The pen came in range over the window.
+  Entered,
+  /// The pen moved, hovering or touching the surface, or its pressure, tilt or buttons changed.
+  Moved,
+  /// The tip of the pen touched the surface.
+  Pressed,
+  /// The tip of the pen was lifted from the surface.
+  Released,
+  /// The pen went out of range or left the window, or the system stopped tracking it.
+  Left,
+}
+
+/// Represents a pen event, see [`WindowEvent::Pen`].
+///
+/// The values a pen doesn't report are `None`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pen {
+  pub device_id: DeviceId,
+  pub phase: PenPhase,
+  pub location: PhysicalPosition<f64>,
+  /// Whether the tip of the pen touches the surface.
+  pub in_contact: bool,
+  /// How hard the tip is pressed, from `0.0` to `1.0`.
+  pub pressure: Option<f64>,
+  /// The tilt of the pen along the x and y axes, in degrees from `-90.0` to `90.0`, `0.0` when
+  /// it's perpendicular to the surface. Positive values tilt it to the right and toward the user.
+  pub tilt: Option<(f64, f64)>,
+  /// The rotation of the pen around its own axis, in degrees from `0.0` to `360.0`.
+  pub twist: Option<f64>,
+  /// Whether a button on the barrel of the pen is pressed.
+  pub barrel_button: bool,
+  /// Whether the eraser end of the pen is used.
+  pub eraser: bool,
+  /// When the OS generated the event, comparable with [`Instant::now`].
+  pub timestamp: Instant,
+}
+
+/// The next id of a [`Touch`], shared by the windows so their ids never collide.
+#[cfg_attr(any(target_os = "macos", tao_headless), allow(dead_code))]
+static NEXT_TOUCH_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Maps the contact identifiers of the platform, which may be recycled as soon as a finger is
+/// lifted, to the ids of [`Touch`] events, which are never reused. Each window has its own, since
+/// the identifiers of the platform may only be unique within a window.
+#[cfg_attr(any(target_os = "macos", tao_headless), allow(dead_code))]
+#[derive(Debug, Default)]
+pub(crate) struct TouchIds {
+  ids: HashMap<u64, u64>,
+}
+
+#[cfg_attr(any(target_os = "macos", tao_headless), allow(dead_code))]
+impl TouchIds {
+  /// Returns the id of the contact the platform calls `key`, forgetting it on its last phase.
+  pub(crate) fn id(&mut self, key: u64, phase: TouchPhase) -> u64 {
+    let id = match phase {
+      // A contact that was never ended is replaced by the new one.
+      TouchPhase::Started => None,
+      TouchPhase::Moved => self.ids.get(&key).copied(),
+      TouchPhase::Ended | TouchPhase::Cancelled => {
+        return self.ids.remove(&key).unwrap_or_else(|| self.allocate())
+      }
+    };
+    id.unwrap_or_else(|| {
+      let id = self.allocate();
+      self.ids.insert(key, id);
+      id
+    })
+  }
+
+  fn allocate(&mut self) -> u64 {
+    NEXT_TOUCH_ID.fetch_add(1, Ordering::Relaxed)
+  }
 }
 
 /// Describes the force of a touch event
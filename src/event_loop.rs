@@ -14,11 +14,29 @@
 //! [event_loop_proxy]: crate::event_loop::EventLoopProxy
 //! [send_event]: crate::event_loop::EventLoopProxy::send_event
 use instant::Instant;
-use std::{error, fmt, marker::PhantomData, ops::Deref};
+use std::{
+  cell::{Cell, RefCell},
+  collections::HashMap,
+  error, fmt,
+  marker::PhantomData,
+  ops::Deref,
+  rc::Rc,
+  sync::{
+    atomic::{AtomicU32, AtomicUsize, Ordering},
+    Arc,
+  },
+  time::Duration,
+};
 
 use crate::{
-  dpi::PhysicalPosition, error::ExternalError, event::Event, monitor::MonitorHandle, platform_impl,
-  window::ProgressBarState,
+  capture::CaptureSource,
+  dpi::{PhysicalPosition, Rect},
+  error::ExternalError,
+  event::Event,
+  keyboard::{Key, KeyCode, KeyboardLayout, ModifiersState},
+  monitor::MonitorHandle,
+  platform_impl,
+  window::{CaptureId, Color, ProgressBarState, Theme, Window, WindowId},
 };
 
 /// Provides a way to retrieve events from the system and from the windows that were registered to
@@ -36,6 +54,13 @@ use crate::{
 ///
 pub struct EventLoop<T: 'static> {
   pub(crate) event_loop: platform_impl::EventLoop<T>,
+  #[cfg(feature = "ffi-bridge")]
+  pub(crate) ffi_bridge: Option<std::sync::Arc<crate::ffi_bridge::Bridge>>,
+  pub(crate) motion_coalescing: MotionCoalescing,
+  /// The `ControlFlow` of the event handler between two calls to `pump_events`, `None` before
+  /// the first one and after an exit.
+  #[cfg(any(not(target_os = "ios"), tao_headless))]
+  pub(crate) pumped_control_flow: Option<ControlFlow>,
   pub(crate) _marker: ::std::marker::PhantomData<*mut ()>, // Not Send nor Sync
 }
 
@@ -48,6 +73,19 @@ pub struct EventLoop<T: 'static> {
 #[derive(Clone)]
 pub struct EventLoopWindowTarget<T: 'static> {
   pub(crate) p: platform_impl::EventLoopWindowTarget<T>,
+  /// The code passed to `exit_with_code`, or the one of the `ControlFlow::ExitWithCode` set by
+  /// the event handler.
+  pub(crate) exit: Cell<Option<i32>>,
+  /// The futures of `EventLoopExtAsync::spawn`.
+  pub(crate) executor: Rc<crate::executor::Executor>,
+  /// The captures of `request_window_capture` and `request_monitor_capture`.
+  pub(crate) captures: Rc<crate::capture::Captures>,
+  /// The motion coalescing of the windows built with `WindowBuilder::with_motion_coalescing`.
+  pub(crate) window_motion_coalescing: Rc<RefCell<HashMap<WindowId, MotionCoalescing>>>,
+  /// The windows of `window_from_id`.
+  pub(crate) window_registry: crate::window_registry::RegistryHandle,
+  /// The user events sent through the proxies and not delivered yet.
+  pub(crate) user_events: Arc<UserEventCount>,
   pub(crate) _marker: ::std::marker::PhantomData<*mut ()>, // Not Send nor Sync
 }
 
@@ -70,6 +108,10 @@ impl<T> fmt::Debug for EventLoopWindowTarget<T> {
 #[derive(Default)]
 pub struct EventLoopBuilder<T: 'static> {
   pub(crate) platform_specific: platform_impl::PlatformSpecificEventLoopAttributes,
+  #[cfg(feature = "ffi-bridge")]
+  ffi_bridge: bool,
+  motion_coalescing: MotionCoalescing,
+  user_event_capacity: Option<usize>,
   _p: PhantomData<T>,
 }
 impl EventLoopBuilder<()> {
@@ -86,9 +128,129 @@ impl<T> EventLoopBuilder<T> {
   pub fn with_user_event() -> Self {
     Self {
       platform_specific: Default::default(),
+      #[cfg(feature = "ffi-bridge")]
+      ffi_bridge: false,
+      motion_coalescing: MotionCoalescing::None,
+      user_event_capacity: None,
       _p: PhantomData,
     }
   }
+
+  /// Builds a headless event loop, for automated tests on machines without a desktop session.
+  ///
+  /// The windows of a headless event loop are never shown: they're created hidden whatever
+  /// [`WindowBuilder::with_visible`] says, and [`Window::set_visible`]`(true)` is ignored.
+  ///
+  /// When no display server can be reached, the event loop still runs: it delivers
+  /// [`Event::NewEvents`] (including [`StartCause::ResumeTimeReached`] for
+  /// [`ControlFlow::WaitUntil`]), [`Event::UserEvent`], [`Event::MainEventsCleared`],
+  /// [`Event::RedrawEventsCleared`] and [`Event::LoopDestroyed`]. Creating a window returns an
+  /// error, there are no monitors and [`EventLoopWindowTarget::set_progress_bar`] does nothing.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Runs without a display server when neither X11 nor Wayland can be opened.
+  /// - **macOS:** The application also gets no Dock icon and never activates. A window server
+  ///   is still required.
+  /// - **Windows:** A desktop session is still required.
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`WindowBuilder::with_visible`]: crate::window::WindowBuilder::with_visible
+  /// [`Window::set_visible`]: crate::window::Window::set_visible
+  /// [`StartCause::ResumeTimeReached`]: crate::event::StartCause::ResumeTimeReached
+  /// [`Event::NewEvents`]: crate::event::Event::NewEvents
+  /// [`Event::UserEvent`]: crate::event::Event::UserEvent
+  /// [`Event::MainEventsCleared`]: crate::event::Event::MainEventsCleared
+  /// [`Event::RedrawEventsCleared`]: crate::event::Event::RedrawEventsCleared
+  /// [`Event::LoopDestroyed`]: crate::event::Event::LoopDestroyed
+  #[inline]
+  pub fn with_headless(&mut self, headless: bool) -> &mut Self {
+    self.platform_specific.headless = headless;
+    self
+  }
+
+  /// Makes the application single instance, `identifier` naming the application among the
+  /// running processes, e.g. `com.example.App`.
+  ///
+  /// When an instance with the same identifier is already running, [`build`](Self::build)
+  /// forwards the arguments and the working directory of this process to it, along with the
+  /// activation token it was launched with, and exits the process with code `0`. The running
+  /// instance receives them as [`Event::SecondInstance`]. When two instances are launched at the
+  /// same time, exactly one of them keeps running.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Uses a named mutex of the session and `WM_COPYDATA`. The forwarding process
+  ///   allows the running one to take the foreground, there is no activation token.
+  /// - **Linux:** Uses the D-Bus name of the `GApplication`, characters that aren't allowed in
+  ///   an application id are replaced with `_`. Ignored by a headless event loop without a
+  ///   display server. Arguments that aren't valid UTF-8 are forwarded lossily.
+  /// - **macOS:** Uses a `CFMessagePort`, a sandboxed application must prefix the identifier
+  ///   with its application group. There is no activation token.
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`Event::SecondInstance`]: crate::event::Event::SecondInstance
+  #[inline]
+  pub fn with_single_instance(&mut self, identifier: impl Into<String>) -> &mut Self {
+    self.platform_specific.single_instance = Some(identifier.into());
+    self
+  }
+
+  /// Sets the monitors of the synthetic backend, the first one being the primary monitor. The
+  /// event loop has a single [`VirtualMonitor::default`] one without them, or with an empty
+  /// list.
+  ///
+  /// See the [`headless`](crate::headless) module.
+  ///
+  /// [`VirtualMonitor::default`]: crate::headless::VirtualMonitor::default
+  #[cfg(tao_headless)]
+  #[inline]
+  pub fn with_virtual_monitors(
+    &mut self,
+    monitors: Vec<crate::headless::VirtualMonitor>,
+  ) -> &mut Self {
+    self.platform_specific.virtual_monitors = monitors;
+    self
+  }
+
+  /// Lets the native code of the application post payloads to the event loop from any thread,
+  /// with the C function `tao_post_user_payload` of the [`ffi_bridge`](crate::ffi_bridge)
+  /// module. They are delivered in order as [`Event::NativeMessage`].
+  ///
+  /// Only the last event loop built with the bridge gets the payloads. Payloads posted between
+  /// two calls of `run_return` are delivered by the next one. Once the event loop is dropped, or
+  /// `run` destroyed it, `tao_post_user_payload` returns
+  /// [`TAO_BRIDGE_CLOSED`](crate::ffi_bridge::TAO_BRIDGE_CLOSED).
+  ///
+  /// [`Event::NativeMessage`]: crate::event::Event::NativeMessage
+  #[cfg(feature = "ffi-bridge")]
+  #[inline]
+  pub fn with_ffi_bridge(&mut self, enabled: bool) -> &mut Self {
+    self.ffi_bridge = enabled;
+    self
+  }
+
+  /// Sets how cursor motions piling up while the event handler is busy are delivered, see
+  /// [`MotionCoalescing`]. Every motion is delivered by default.
+  #[inline]
+  pub fn with_motion_coalescing(&mut self, coalescing: MotionCoalescing) -> &mut Self {
+    self.motion_coalescing = coalescing;
+    self
+  }
+
+  /// Bounds the number of user events sent with [`EventLoopProxy::try_send`] and waiting to be
+  /// delivered, so a producer faster than the event loop is told to back off with
+  /// [`TrySendError::Full`] instead of queuing events forever. User events are unbounded by
+  /// default.
+  ///
+  /// The events of [`EventLoopProxy::send_event`] count toward the capacity, but are always
+  /// queued.
+  #[inline]
+  pub fn with_user_event_capacity(&mut self, capacity: usize) -> &mut Self {
+    self.user_event_capacity = Some(capacity);
+    self
+  }
+
   /// Builds a new event loop.
   ///
   /// ***For cross-platform compatibility, the `EventLoop` must be created on the main thread.***
@@ -103,18 +265,122 @@ impl<T> EventLoopBuilder<T> {
   /// If it is not set, winit will try to connect to a wayland connection, and if it fails will
   /// fallback on x11. If this variable is set with any other value, winit will panic.
   ///
+  /// Several event loops can exist at once, where the platform supports it.
+  ///
   /// ## Platform-specific
   ///
   /// - **iOS:** Can only be called on the main thread.
+  /// - **Windows:** An event loop can run on each thread created with
+  ///   `EventLoopBuilderExtWindows::with_any_thread`. Only the first one receives `DeviceEvent`s.
+  /// - **Linux:** Every event loop has to be created on the same thread, the one GTK runs on,
+  ///   creating one on another thread panics. See [`try_build`](Self::try_build) to get an error
+  ///   instead.
+  /// - **macOS:** Can only be called on the main thread.
   #[inline]
   pub fn build(&mut self) -> EventLoop<T> {
+    let event_loop = platform_impl::EventLoop::new(&mut self.platform_specific);
+    self.finish(event_loop)
+  }
+
+  /// Same as [`build`](Self::build), but returns an error instead of panicking when the platform
+  /// can't run another event loop on this thread.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Returns [`ExternalError::NotSupported`] when GTK already runs on another
+  ///   thread, GTK can't run on several threads.
+  #[inline]
+  pub fn try_build(&mut self) -> Result<EventLoop<T>, ExternalError> {
+    let event_loop = platform_impl::EventLoop::try_new(&mut self.platform_specific)?;
+    Ok(self.finish(event_loop))
+  }
+
+  fn finish(&mut self, event_loop: platform_impl::EventLoop<T>) -> EventLoop<T> {
+    #[cfg(feature = "ffi-bridge")]
+    let ffi_bridge = if self.ffi_bridge {
+      Some(crate::ffi_bridge::Bridge::register(
+        event_loop.window_target().create_waker(),
+      ))
+    } else {
+      None
+    };
+    if let Some(capacity) = self.user_event_capacity {
+      event_loop
+        .window_target()
+        .user_events
+        .capacity
+        .store(capacity, Ordering::Relaxed);
+    }
     EventLoop {
-      event_loop: platform_impl::EventLoop::new(&mut self.platform_specific),
+      event_loop,
+      #[cfg(feature = "ffi-bridge")]
+      ffi_bridge,
+      motion_coalescing: self.motion_coalescing,
+      #[cfg(any(not(target_os = "ios"), tao_headless))]
+      pumped_control_flow: None,
       _marker: PhantomData,
     }
   }
 }
 
+/// How cursor motions are delivered when several of them are pending, see
+/// [`EventLoopBuilder::with_motion_coalescing`].
+///
+/// This applies to [`WindowEvent::CursorMoved`] and [`DeviceEvent::MouseMotion`]. Motions may be
+/// delivered later than they arrived, but never after the event that follows them when it's
+/// another kind of event, like a button press or a wheel movement, so those always see the
+/// position the cursor had when they happened. Every motion still pending is delivered before
+/// [`Event::MainEventsCleared`].
+///
+/// A window built with [`WindowBuilder::with_motion_coalescing`] overrides the coalescing of
+/// the event loop for its own `CursorMoved` events.
+///
+/// [`WindowEvent::CursorMoved`]: crate::event::WindowEvent::CursorMoved
+/// [`DeviceEvent::MouseMotion`]: crate::event::DeviceEvent::MouseMotion
+/// [`Event::MainEventsCleared`]: crate::event::Event::MainEventsCleared
+/// [`WindowBuilder::with_motion_coalescing`]: crate::window::WindowBuilder::with_motion_coalescing
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MotionCoalescing {
+  /// Every motion is delivered.
+  None,
+  /// Consecutive cursor motions of a device over a window are delivered as a single
+  /// `CursorMoved` with the last position, and consecutive `MouseMotion`s of a device as a
+  /// single one with the sum of their deltas.
+  Latest,
+  /// `CursorMoved` events older than the duration when they're delivered are dropped, except the
+  /// last one before another kind of event, so the final position is never lost. `MouseMotion`
+  /// has no timestamp and is always delivered.
+  MaxAge(Duration),
+}
+
+impl Default for MotionCoalescing {
+  fn default() -> Self {
+    MotionCoalescing::None
+  }
+}
+
+/// The statistics of the iterations of an event loop, see
+/// [`EventLoopWindowTarget::loop_stats`].
+///
+/// The values other than `iterations` are rolling averages, in which the last iterations weigh
+/// the most.
+#[cfg(feature = "tracing")]
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LoopStats {
+  /// The number of iterations so far.
+  pub iterations: u64,
+  /// The number of events delivered per iteration.
+  pub events: f64,
+  /// The time the loop waited for events before an iteration.
+  pub wait_duration: Duration,
+  /// The time the event handler took per iteration.
+  pub callback_duration: Duration,
+  /// The time an iteration took, including the work of Tao between the calls of the handler.
+  pub iteration_duration: Duration,
+}
+
 /// Set by the user callback given to the `EventLoop::run` method.
 ///
 /// Indicates the desired behavior of the event loop after [`Event::RedrawEventsCleared`][events_cleared]
@@ -197,10 +463,18 @@ impl<T> EventLoop<T> {
   ///
   /// Any values not passed to this function will *not* be dropped.
   ///
+  /// Once the loop exits, `Event::LoopDestroyed` is delivered and the process terminates with the
+  /// code of [`ControlFlow::ExitWithCode`]. Use
+  /// [`run_return`](crate::platform::run_return::EventLoopExtRunReturn::run_return) to get the
+  /// code back instead.
+  ///
   /// ## Platform-specific
   ///
   /// - **Unix**: The program terminates with exit code 1 if the display server
   ///   disconnects.
+  /// - **macOS**: The code is also kept when the application is terminated from the Dock or
+  ///   the application menu, if the handler sets it while receiving `Event::LoopDestroyed`.
+  /// - **iOS / Android**: The exit code is unused.
   ///
   /// [`ControlFlow`]: crate::event_loop::ControlFlow
   #[inline]
@@ -208,6 +482,17 @@ impl<T> EventLoop<T> {
   where
     F: 'static + FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
   {
+    #[cfg(feature = "tracing")]
+    let event_handler = crate::instrumentation::wrap_handler(event_handler);
+    let event_handler =
+      crate::motion_coalescing::wrap_handler(self.motion_coalescing, event_handler);
+    let event_handler = crate::executor::wrap_handler(event_handler);
+    let event_handler = crate::capture::wrap_handler(event_handler);
+    let event_handler = wrap_user_event_handler(self.user_events.clone(), event_handler);
+    #[cfg(feature = "ffi-bridge")]
+    let event_handler = crate::ffi_bridge::wrap_handler(self.ffi_bridge, true, event_handler);
+    let event_handler = wrap_exit_handler(event_handler);
+    let event_handler = crate::window_registry::wrap_handler(event_handler);
     self.event_loop.run(event_handler)
   }
 
@@ -215,6 +500,7 @@ impl<T> EventLoop<T> {
   pub fn create_proxy(&self) -> EventLoopProxy<T> {
     EventLoopProxy {
       event_loop_proxy: self.event_loop.create_proxy(),
+      user_events: self.user_events.clone(),
     }
   }
 }
@@ -227,14 +513,74 @@ impl<T> Deref for EventLoop<T> {
 }
 
 impl<T> EventLoopWindowTarget<T> {
+  /// Returns the statistics of the iterations of the event loop, e.g. for a debug overlay.
+  ///
+  /// An iteration goes from `Event::NewEvents` to `Event::RedrawEventsCleared`. Each one is also
+  /// reported as a `tracing` event, and each call of the event handler runs in a `tao::event`
+  /// span with the kind of the event and its window id.
+  #[cfg(feature = "tracing")]
+  #[inline]
+  pub fn loop_stats(&self) -> LoopStats {
+    crate::instrumentation::loop_stats()
+  }
+
+  /// Exits the event loop with the code `0` once the current iteration ends, see
+  /// [`exit_with_code`](Self::exit_with_code).
+  #[inline]
+  pub fn exit(&self) {
+    self.exit_with_code(0)
+  }
+
+  /// Exits the event loop with `code` once the current iteration ends, as if the event handler
+  /// set [`ControlFlow::ExitWithCode`]`(code)`, without needing the `&mut ControlFlow`.
+  ///
+  /// The `ControlFlow` is kept to `ExitWithCode(code)` whatever the event handler writes to it
+  /// afterwards. The events of the current iteration are still delivered, followed by
+  /// `Event::LoopDestroyed` once. Only the first code is kept.
+  ///
+  /// The target is neither `Send` nor `Sync`, so only the thread of the event loop can call it.
+  /// Use an [`EventLoopProxy`] to ask for an exit from another thread.
+  #[inline]
+  pub fn exit_with_code(&self, code: i32) {
+    if self.exit.get().is_none() {
+      self.exit.set(Some(code));
+    }
+  }
+
+  /// Returns `true` once the event loop is going to exit, because of [`exit`](Self::exit),
+  /// [`exit_with_code`](Self::exit_with_code) or a `ControlFlow::ExitWithCode` set by the event
+  /// handler.
+  #[inline]
+  pub fn is_exiting(&self) -> bool {
+    self.exit.get().is_some()
+  }
+
+  /// Returns `true` if the event loop was built with [`EventLoopBuilder::with_headless`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Always returns `false`.
+  #[inline]
+  pub fn is_headless(&self) -> bool {
+    self.p.is_headless()
+  }
+
   /// Returns the list of all the monitors available on the system.
+  ///
+  /// The order is stable: the primary monitor comes first, followed by the others sorted by
+  /// [`position`](MonitorHandle::position), left to right and then top to bottom. Use
+  /// [`MonitorHandle::id`] rather than the index to remember a monitor, as the index changes
+  /// when the arrangement does.
   #[inline]
   pub fn available_monitors(&self) -> impl Iterator<Item = MonitorHandle> {
-    self
+    let mut monitors: Vec<_> = self
       .p
       .available_monitors()
       .into_iter()
       .map(|inner| MonitorHandle { inner })
+      .collect();
+    crate::monitor::sort_monitors(&mut monitors, self.primary_monitor().as_ref());
+    monitors.into_iter()
   }
 
   /// Returns the primary monitor of the system.
@@ -258,32 +604,240 @@ impl<T> EventLoopWindowTarget<T> {
       .map(|inner| MonitorHandle { inner })
   }
 
+  /// Requests a capture of the client area of `window` like [`Window::capture`], or only of
+  /// `rect` in it like [`Window::capture_rect`], without blocking.
+  ///
+  /// The image is taken in the next iteration of the event loop, on its thread, and delivered as
+  /// [`Event::CaptureCompleted`] with the returned id right after [`Event::NewEvents`]. The
+  /// captures requested by the same iteration are delivered in order.
+  ///
+  /// ## Platform-specific
+  ///
+  /// See [`Window::capture`]. The capture itself still blocks the event loop while it's taken,
+  /// as no backend reads it back from another thread.
+  ///
+  /// [`Event::CaptureCompleted`]: crate::event::Event::CaptureCompleted
+  /// [`Event::NewEvents`]: crate::event::Event::NewEvents
+  pub fn request_window_capture(&self, window: &Window, rect: Option<Rect>) -> CaptureId {
+    self
+      .captures
+      .request(self, CaptureSource::window(&window.window), rect)
+  }
+
+  /// Requests a capture of what `monitor` shows like [`MonitorHandle::capture`], or only of
+  /// `rect` in it like [`MonitorHandle::capture_rect`], without blocking.
+  ///
+  /// The image is delivered as [`Event::CaptureCompleted`], like the one of
+  /// [`request_window_capture`](Self::request_window_capture).
+  ///
+  /// ## Platform-specific
+  ///
+  /// See [`MonitorHandle::capture`].
+  ///
+  /// [`Event::CaptureCompleted`]: crate::event::Event::CaptureCompleted
+  pub fn request_monitor_capture(&self, monitor: &MonitorHandle, rect: Option<Rect>) -> CaptureId {
+    self
+      .captures
+      .request(self, CaptureSource::Monitor(monitor.clone()), rect)
+  }
+
   /// Change [`DeviceEvent`] filter mode.
   ///
   /// Since the [`DeviceEvent`] capture can lead to high CPU usage for unfocused windows, tao
   /// will ignore them by default for unfocused windows. This method allows changing
   /// this filter at runtime to explicitly capture them again.
   ///
+  /// With [`DeviceEventFilter::Never`], [`DeviceEvent::Key`] is reported for every key
+  /// regardless of focus, e.g. to notice modifiers released in another application.
+  ///
   /// ## Platform-specific
   ///
-  /// - **Linux / macOS / iOS / Android:** Unsupported.
+  /// - **macOS:** Only key events are reported while unfocused. This needs an event tap, which
+  ///   requires the Input Monitoring permission, an error is returned when it is missing.
+  /// - **Linux:** Only key events are supported, on X11 only.
+  /// - **Windows:** Fails with `NotSupported` on the event loops created after the first one,
+  ///   which don't receive the device events.
+  /// - **iOS / Android:** Unsupported.
   ///
   /// [`DeviceEvent`]: crate::event::DeviceEvent
-  pub fn set_device_event_filter(&self, _filter: DeviceEventFilter) {
-    #[cfg(target_os = "windows")]
-    self.p.set_device_event_filter(_filter);
+  /// [`DeviceEvent::Key`]: crate::event::DeviceEvent::Key
+  pub fn set_device_event_filter(&self, filter: DeviceEventFilter) -> Result<(), ExternalError> {
+    self.p.set_device_event_filter(filter)
   }
 
-  /// Returns the current cursor position
+  /// Returns the current cursor position in desktop coordinates, the same coordinates as
+  /// [`Window::outer_position`](crate::window::Window::outer_position).
   ///
   /// ## Platform-specific
   ///
-  /// - **iOS / Android / Linux(Wayland)**: Unsupported, returns `0,0`.
+  /// - **Linux(Wayland):** Wayland doesn't expose desktop coordinates. The position is relative to
+  ///   the surface under the cursor, and an error is returned when the cursor isn't over one of
+  ///   the windows of the application.
+  /// - **iOS / Android**: Unsupported, returns `0,0`.
   #[inline]
   pub fn cursor_position(&self) -> Result<PhysicalPosition<f64>, ExternalError> {
     self.p.cursor_position()
   }
 
+  /// Returns the key that the physical key `code` produces under the keyboard layout that is
+  /// currently active, without any modifiers applied.
+  ///
+  /// The layout is queried on every call, so the result follows layout switches done by the
+  /// user. Returns `None` if the key doesn't produce anything on the current layout.
+  ///
+  /// Use [`KeyCode::from_scancode`] and [`KeyCode::to_scancode`] to convert between platform
+  /// scancodes and [`KeyCode`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux (Wayland):** Only the first group of the active keymap is consulted.
+  /// - **iOS / Android:** Unsupported, always returns `None`.
+  #[inline]
+  pub fn key_for_keycode(&self, code: KeyCode) -> Option<Key<'static>> {
+    self.p.key_for_keycode(code)
+  }
+
+  /// Returns the keyboard layout that is currently active, `None` if it can't be identified.
+  ///
+  /// [`Event::KeyboardLayoutChanged`] is emitted when it changes, use
+  /// [`key_for_keycode`](Self::key_for_keycode) to map keys under it.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux (Wayland) / iOS / Android:** Unsupported, always returns `None`.
+  #[inline]
+  pub fn keyboard_layout(&self) -> Option<KeyboardLayout> {
+    self.p.keyboard_layout()
+  }
+
+  /// Registers a system-wide shortcut: pressing `key` while holding exactly `modifiers` delivers
+  /// [`Event::GlobalShortcut`] with the returned id, even when no window of the application has
+  /// focus.
+  ///
+  /// Fails when another application already registered the same shortcut.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Grabs the key on the root window, NumLock and CapsLock are ignored.
+  /// - **Linux (Wayland) / iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  ///
+  /// [`Event::GlobalShortcut`]: crate::event::Event::GlobalShortcut
+  pub fn register_global_shortcut(
+    &self,
+    modifiers: ModifiersState,
+    key: KeyCode,
+  ) -> Result<GlobalShortcutId, ExternalError> {
+    static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    self.p.register_global_shortcut(id, modifiers, key)?;
+    Ok(GlobalShortcutId(id))
+  }
+
+  /// Unregisters a shortcut registered with
+  /// [`register_global_shortcut`](Self::register_global_shortcut).
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux (Wayland) / iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  #[inline]
+  pub fn unregister_global_shortcut(&self, id: GlobalShortcutId) -> Result<(), ExternalError> {
+    self.p.unregister_global_shortcut(id.0)
+  }
+
+  /// Returns the window of `window_id`, so that event handlers don't need to keep their own map
+  /// from [`WindowId`] to [`Window`].
+  ///
+  /// The [`Window`] returned shares the platform window of the one built by the application, and
+  /// behaves the same. The window is found until the application drops the [`Window`] it built,
+  /// so this already returns `None` in the handler of the [`WindowEvent::Destroyed`] that
+  /// follows.
+  ///
+  /// Windows are only lent out to the event handler: this returns `None` outside of it, e.g.
+  /// once `run_return` returned, and on a clone of the `EventLoopWindowTarget` it was given.
+  ///
+  /// [`WindowEvent::Destroyed`]: crate::event::WindowEvent::Destroyed
+  #[inline]
+  pub fn window_from_id(&self, window_id: WindowId) -> Option<&Window> {
+    self.window_registry.get(window_id)
+  }
+
+  /// Returns `true` while the window of `window_id` exists.
+  ///
+  /// The window is forgotten before its [`WindowEvent::Destroyed`] is delivered, so this
+  /// already returns `false` in the handler of that event. Lets event handlers check an id, e.g.
+  /// one read back with [`WindowId::from_raw`], without keeping their own set of windows.
+  ///
+  /// Use [`window_from_id`](Self::window_from_id) to reach the window itself.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS:** Unsupported, always returns `false`.
+  /// - **Android:** Every window is the one of the activity, so this returns `true` for its id.
+  ///
+  /// [`WindowEvent::Destroyed`]: crate::event::WindowEvent::Destroyed
+  #[inline]
+  pub fn has_window(&self, window_id: WindowId) -> bool {
+    self.p.has_window(window_id.0)
+  }
+
+  /// Returns the accessibility preferences of the system.
+  ///
+  /// They are queried on every call, and can be read before any window is created, e.g. to
+  /// decide whether to play a startup animation. [`Event::AccessibilityPreferencesChanged`] is
+  /// emitted when they change.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** `inverted_colors` follows the inverted color filters of Windows 10 and later.
+  /// - **Linux:** Only `reduce_motion` and `high_contrast` are supported, read from the
+  ///   `reduced-motion` and `contrast` settings of the desktop portal, or from the GTK settings
+  ///   `gtk-enable-animations` and `gtk-theme-name` without it.
+  /// - **Android:** Unsupported, returns the default preferences.
+  #[inline]
+  pub fn accessibility_preferences(&self) -> AccessibilityPrefs {
+    self.p.accessibility_preferences()
+  }
+
+  /// Returns the theme, accent color and accessibility preferences of the system.
+  ///
+  /// The theme is the one of the system, even while windows force one with
+  /// [`Window::set_theme`]. [`Event::SystemAppearanceChanged`] is emitted when any of them
+  /// changes.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The accent color is the one of the Settings app, read from the registry.
+  /// - **macOS:** The accent color is `controlAccentColor`, available since macOS 10.14.
+  /// - **Linux:** The theme and the accent color are read from the settings portal on the
+  ///   session D-Bus, the theme falling back to the name of the GTK theme without a portal. Not
+  ///   every portal implements the accent color.
+  /// - **iOS:** Only the accessibility preferences are supported.
+  /// - **Android:** Unsupported, returns the default appearance.
+  ///
+  /// [`Window::set_theme`]: crate::window::Window::set_theme
+  #[inline]
+  pub fn system_appearance(&self) -> SystemAppearance {
+    self.p.system_appearance()
+  }
+
+  /// Returns the power source and battery state of the system.
+  ///
+  /// Fields the system doesn't report are `None`, e.g. `percent` on a machine without a
+  /// battery. [`Event::PowerInfoChanged`] is emitted when the power source or the low power
+  /// mode changes.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** `power_saver` is the battery saver, or the energy saver on Windows 11.
+  /// - **macOS:** `power_saver` is the low power mode, available since macOS 12.
+  /// - **Linux:** Read from UPower and power-profiles-daemon over the system D-Bus, the fields
+  ///   are `None` when they aren't running.
+  /// - **iOS / Android:** Unsupported, returns `None` fields.
+  #[inline]
+  pub fn power_info(&self) -> PowerInfo {
+    self.p.power_info()
+  }
+
   /// Sets the progress bar state
   ///
   /// ## Platform-specific
@@ -296,6 +850,112 @@ impl<T> EventLoopWindowTarget<T> {
     #[cfg(any(target_os = "linux", target_os = "macos"))]
     self.p.set_progress_bar(_progress)
   }
+
+  /// Creates an [`EventLoopWaker`] that can wake this event loop up from any thread.
+  ///
+  /// This lets external event sources, like the I/O driver of an async runtime, get the event loop
+  /// to run once they have work for it, without going through a user event.
+  #[inline]
+  pub fn create_waker(&self) -> EventLoopWaker {
+    EventLoopWaker {
+      waker: self.p.create_waker(),
+    }
+  }
+
+  /// Sets `callback` to run whenever the event loop is about to wait with nothing left to
+  /// handle, or removes it with `None`. This lets low-priority work, like evicting caches,
+  /// happen while the application is idle without [`ControlFlow::Poll`].
+  ///
+  /// The callback runs after [`Event::RedrawEventsCleared`], and only if no event is pending,
+  /// user events included. It gets an [`IdleDeadline`] telling how long until the event loop
+  /// wakes up on its own, and events arriving while it runs wait for it to return, so it should
+  /// do its work in short slices. The event loop then waits as the [`ControlFlow`] says: create
+  /// an [`EventLoopWaker`] and wake it from the callback to get another slice once the next
+  /// iteration is done. The callback is no longer called once the control flow is
+  /// [`ControlFlow::ExitWithCode`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`Event::RedrawEventsCleared`]: crate::event::Event::RedrawEventsCleared
+  #[inline]
+  pub fn set_idle_callback(&self, callback: Option<Box<dyn FnMut(IdleDeadline)>>) {
+    self.p.set_idle_callback(callback)
+  }
+
+  /// Runs a nested event loop until `predicate` returns `true`, so that the event handler can
+  /// wait on something, like a native modal dialog, without freezing the windows.
+  ///
+  /// The event handler is still running and can't be called again, so the events of the nested
+  /// loop are delivered to `handler` instead, in iterations like the ones of the event loop, from
+  /// [`Event::NewEvents`] to [`Event::RedrawEventsCleared`], redraws included. Forward them to the
+  /// logic of the event handler as needed.
+  ///
+  /// The [`ControlFlow`] of `handler` starts as [`ControlFlow::Wait`] and only applies to the
+  /// nested loop, which blocks until the next event like the event loop does. An exit asked by
+  /// `handler` ends the nested loop, then the event loop once the event handler returns. The
+  /// futures of
+  /// [`EventLoopExtAsync::spawn`](crate::platform::executor::EventLoopExtAsync::spawn) keep
+  /// running.
+  ///
+  /// `predicate` is checked before each iteration, so it may wait on state changed by `handler`,
+  /// native callbacks or other threads. Wake the event loop with an [`EventLoopWaker`] after
+  /// changing that state from another thread.
+  ///
+  /// Returns [`ExternalError::InvalidInput`] inside 8 nested loops already, to stop runaway
+  /// recursion.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Returns early when the application is asked to quit, leaving `WM_QUIT` to the
+  ///   outer loop. The hook of `EventLoopBuilderExtWindows::with_msg_hook` isn't called.
+  /// - **macOS:** Runs in `NSModalPanelRunLoopMode`, like the run loop of a modal panel.
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  ///
+  /// [`Event::NewEvents`]: crate::event::Event::NewEvents
+  /// [`Event::RedrawEventsCleared`]: crate::event::Event::RedrawEventsCleared
+  pub fn run_nested_until<P, F>(&self, mut predicate: P, handler: F) -> Result<(), ExternalError>
+  where
+    P: FnMut() -> bool,
+    F: FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+  {
+    let depth = NESTED_EVENT_LOOPS.with(|depth| depth.get());
+    if depth >= MAX_NESTED_EVENT_LOOPS {
+      return Err(ExternalError::InvalidInput("too many nested event loops"));
+    }
+    let _guard = NestedEventLoopGuard::enter(depth);
+    let handler = crate::executor::wrap_handler(handler);
+    let handler = crate::capture::wrap_handler(handler);
+    let handler = wrap_user_event_handler(self.user_events.clone(), handler);
+    let handler = wrap_exit_handler(handler);
+    let mut handler = crate::window_registry::wrap_handler(handler);
+    self.p.run_nested_until(self, &mut predicate, &mut handler)
+  }
+}
+
+const MAX_NESTED_EVENT_LOOPS: u32 = 8;
+
+thread_local! {
+  static NESTED_EVENT_LOOPS: Cell<u32> = Cell::new(0);
+}
+
+/// Counts a nested event loop until dropped, even if the predicate or the handler panics.
+struct NestedEventLoopGuard {
+  depth: u32,
+}
+
+impl NestedEventLoopGuard {
+  fn enter(depth: u32) -> Self {
+    NESTED_EVENT_LOOPS.with(|nested| nested.set(depth + 1));
+    Self { depth }
+  }
+}
+
+impl Drop for NestedEventLoopGuard {
+  fn drop(&mut self) {
+    NESTED_EVENT_LOOPS.with(|nested| nested.set(self.depth));
+  }
 }
 
 #[cfg(feature = "rwh_05")]
@@ -328,15 +988,81 @@ impl<T> rwh_06::HasDisplayHandle for EventLoopWindowTarget<T> {
   }
 }
 
+/// Wakes the event loop up from any thread, without sending it an event.
+///
+/// Created with [`EventLoopWindowTarget::create_waker`]. Unlike [`EventLoopProxy`], it carries no
+/// payload, so it can be handed to code that doesn't know the user event type, like the reactor
+/// of an async runtime.
+#[derive(Clone)]
+pub struct EventLoopWaker {
+  waker: platform_impl::EventLoopWaker,
+}
+
+impl EventLoopWaker {
+  /// Makes the event loop run a new iteration, which starts with
+  /// `Event::NewEvents(StartCause::WaitCancelled)` if it was waiting.
+  ///
+  /// Wakes that happen before the event loop gets to run are merged into a single iteration.
+  /// Waking an event loop that no longer exists does nothing.
+  #[inline]
+  pub fn wake(&self) {
+    self.waker.wake()
+  }
+}
+
+impl fmt::Debug for EventLoopWaker {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.pad("EventLoopWaker { .. }")
+  }
+}
+
+/// The time left to the callback of [`EventLoopWindowTarget::set_idle_callback`] before the
+/// event loop wakes up on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdleDeadline {
+  deadline: Option<Instant>,
+}
+
+impl IdleDeadline {
+  /// Returns the deadline of an idle callback run with `control_flow`, `None` once the event
+  /// loop exits.
+  #[allow(dead_code)] // Not used on every platform
+  pub(crate) fn new(control_flow: ControlFlow) -> Option<Self> {
+    let deadline = match control_flow {
+      ControlFlow::Poll => Some(Instant::now()),
+      ControlFlow::Wait => None,
+      ControlFlow::WaitUntil(instant) => Some(instant),
+      ControlFlow::ExitWithCode(_) => return None,
+    };
+    Some(Self { deadline })
+  }
+
+  /// Returns when the event loop wakes up on its own, the time of [`ControlFlow::WaitUntil`].
+  /// `None` if it waits for an event.
+  pub fn deadline(&self) -> Option<Instant> {
+    self.deadline
+  }
+
+  /// Returns how long until the event loop wakes up on its own, zero with [`ControlFlow::Poll`]
+  /// or once the deadline passed. `None` if it waits for an event.
+  pub fn time_remaining(&self) -> Option<Duration> {
+    self
+      .deadline
+      .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+  }
+}
+
 /// Used to send custom events to `EventLoop`.
 pub struct EventLoopProxy<T: 'static> {
   event_loop_proxy: platform_impl::EventLoopProxy<T>,
+  user_events: Arc<UserEventCount>,
 }
 
 impl<T: 'static> Clone for EventLoopProxy<T> {
   fn clone(&self) -> Self {
     Self {
       event_loop_proxy: self.event_loop_proxy.clone(),
+      user_events: self.user_events.clone(),
     }
   }
 }
@@ -347,8 +1073,55 @@ impl<T: 'static> EventLoopProxy<T> {
   /// function.
   ///
   /// Returns an `Err` if the associated `EventLoop` no longer exists.
+  ///
+  /// The event is queued even when the event loop is busy, see [`try_send`](Self::try_send) to
+  /// bound the queue.
   pub fn send_event(&self, event: T) -> Result<(), EventLoopClosed<T>> {
-    self.event_loop_proxy.send_event(event)
+    self.user_events.pending.fetch_add(1, Ordering::Relaxed);
+    self.event_loop_proxy.send_event(event).map_err(|error| {
+      self.user_events.release();
+      error
+    })
+  }
+
+  /// Sends an event like [`send_event`](Self::send_event), unless the capacity set with
+  /// [`EventLoopBuilder::with_user_event_capacity`] is reached, in which case the event is
+  /// returned with [`TrySendError::Full`]. Without a capacity, this is the same as `send_event`.
+  ///
+  /// Sending several events before the event loop wakes up only wakes it up once.
+  pub fn try_send(&self, event: T) -> Result<(), TrySendError<T>> {
+    let capacity = self.user_events.capacity.load(Ordering::Relaxed);
+    let reserved =
+      self
+        .user_events
+        .pending
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pending| {
+          (pending < capacity).then(|| pending + 1)
+        });
+    if reserved.is_err() {
+      return Err(TrySendError::Full(event));
+    }
+    self
+      .event_loop_proxy
+      .send_event(event)
+      .map_err(|EventLoopClosed(event)| {
+        self.user_events.release();
+        TrySendError::Closed(event)
+      })
+  }
+
+  /// Delivers `event` to the window `window_id` as if the system had sent it, once the event
+  /// loop is done with the events already queued. The state of the window follows the event,
+  /// see the [`headless`](crate::headless) module.
+  ///
+  /// Returns an `Err` if the associated `EventLoop` no longer exists.
+  #[cfg(tao_headless)]
+  pub fn inject(
+    &self,
+    window_id: crate::window::WindowId,
+    event: crate::event::WindowEvent<'static>,
+  ) -> Result<(), EventLoopClosed<crate::event::WindowEvent<'static>>> {
+    self.event_loop_proxy.inject(window_id.0, event)
   }
 }
 
@@ -371,6 +1144,89 @@ impl<T> fmt::Display for EventLoopClosed<T> {
 
 impl<T: fmt::Debug> error::Error for EventLoopClosed<T> {}
 
+/// The error returned by [`EventLoopProxy::try_send`], with the event that wasn't sent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TrySendError<T> {
+  /// The capacity set with [`EventLoopBuilder::with_user_event_capacity`] is reached, the event
+  /// can be sent again once the event loop delivered some of the pending ones.
+  Full(T),
+  /// The `EventLoop` no longer exists.
+  Closed(T),
+}
+
+impl<T> TrySendError<T> {
+  /// Returns the event that wasn't sent.
+  pub fn into_inner(self) -> T {
+    match self {
+      Self::Full(event) | Self::Closed(event) => event,
+    }
+  }
+}
+
+impl<T> From<EventLoopClosed<T>> for TrySendError<T> {
+  fn from(error: EventLoopClosed<T>) -> Self {
+    Self::Closed(error.0)
+  }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Full(_) => f.write_str("The user events of the `EventLoop` are at capacity"),
+      Self::Closed(_) => f.write_str("Tried to wake up a closed `EventLoop`"),
+    }
+  }
+}
+
+impl<T: fmt::Debug> error::Error for TrySendError<T> {}
+
+/// The user events sent through the proxies of an event loop and not delivered yet.
+#[derive(Debug)]
+pub(crate) struct UserEventCount {
+  /// See `EventLoopBuilder::with_user_event_capacity`, `usize::MAX` without a capacity.
+  capacity: AtomicUsize,
+  pending: AtomicUsize,
+}
+
+impl Default for UserEventCount {
+  fn default() -> Self {
+    Self {
+      capacity: AtomicUsize::new(usize::MAX),
+      pending: AtomicUsize::new(0),
+    }
+  }
+}
+
+impl UserEventCount {
+  fn release(&self) {
+    let _ = self
+      .pending
+      .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pending| {
+        pending.checked_sub(1)
+      });
+  }
+}
+
+/// Wraps the event handler of an event loop to count the user events it receives as delivered.
+pub(crate) fn wrap_user_event_handler<T, F>(
+  user_events: Arc<UserEventCount>,
+  mut handler: F,
+) -> impl FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow)
+where
+  F: FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+{
+  move |event, target, control_flow| {
+    if let Event::UserEvent(_) = event {
+      user_events.release();
+    }
+    handler(event, target, control_flow)
+  }
+}
+
+/// Identifies a shortcut registered with [`EventLoopWindowTarget::register_global_shortcut`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct GlobalShortcutId(pub(crate) u32);
+
 /// Fiter controlling the propagation of device events.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum DeviceEventFilter {
@@ -387,3 +1243,63 @@ impl Default for DeviceEventFilter {
     Self::Unfocused
   }
 }
+
+/// The accessibility preferences of the system, see
+/// [`EventLoopWindowTarget::accessibility_preferences`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccessibilityPrefs {
+  /// The user asked for less motion, animations should be shortened or replaced by fades.
+  pub reduce_motion: bool,
+  /// The user asked for less transparency, translucent surfaces should be made opaque.
+  pub reduce_transparency: bool,
+  /// A high contrast mode is on.
+  pub high_contrast: bool,
+  /// The colors of the display are inverted.
+  pub inverted_colors: bool,
+}
+
+/// The appearance of the system, see [`EventLoopWindowTarget::system_appearance`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SystemAppearance {
+  /// Whether the system uses a dark or a light theme.
+  pub theme: Theme,
+  /// The accent color picked by the user, `None` when the system doesn't have one.
+  pub accent_color: Option<Color>,
+  /// The accessibility preferences, also reported by [`Event::AccessibilityPreferencesChanged`].
+  pub accessibility: AccessibilityPrefs,
+}
+
+/// The power state of the system, see [`EventLoopWindowTarget::power_info`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerInfo {
+  /// Whether the system runs on battery rather than on external power, `None` when unknown.
+  pub on_battery: Option<bool>,
+  /// The remaining battery charge in percent, `None` without a battery.
+  pub percent: Option<u8>,
+  /// Whether a low power mode is on, which asks applications to save energy.
+  pub power_saver: bool,
+}
+
+/// Wraps the event handler of an event loop to keep its `ControlFlow` to the exit asked with
+/// `EventLoopWindowTarget::exit_with_code`, and to remember an exit set by the handler.
+pub(crate) fn wrap_exit_handler<T, F>(
+  mut handler: F,
+) -> impl FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow)
+where
+  F: FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+{
+  move |event, target, control_flow| {
+    handler(event, target, control_flow);
+    match (target.exit.get(), *control_flow) {
+      (Some(code), _) => *control_flow = ControlFlow::ExitWithCode(code),
+      (None, ControlFlow::ExitWithCode(code)) => target.exit.set(Some(code)),
+      _ => (),
+    }
+  }
+}
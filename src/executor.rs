@@ -0,0 +1,134 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! The executor of the futures spawned with
+//! [`EventLoopExtAsync::spawn`](crate::platform::executor::EventLoopExtAsync::spawn), the same on
+//! every platform: a woken future makes the event loop run an iteration with its
+//! [`EventLoopWaker`], and is polled right after the next `Event::NewEvents`.
+
+use std::{
+  cell::{Cell, RefCell},
+  collections::HashMap,
+  future::Future,
+  mem,
+  pin::Pin,
+  sync::{Arc, Mutex, PoisonError},
+  task::{Context, Wake, Waker},
+};
+
+use crate::{
+  event::Event,
+  event_loop::{ControlFlow, EventLoopWaker, EventLoopWindowTarget},
+};
+
+type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+/// The futures of an event loop, owned by its `EventLoopWindowTarget`.
+#[derive(Default)]
+pub(crate) struct Executor {
+  tasks: RefCell<HashMap<u64, LocalFuture>>,
+  next_id: Cell<u64>,
+  /// Created along with the first task, since it needs an `EventLoopWaker`.
+  ready: RefCell<Option<Arc<ReadyTasks>>>,
+}
+
+/// The tasks to poll, shared with their wakers which may be on any thread.
+struct ReadyTasks {
+  ids: Mutex<Vec<u64>>,
+  waker: EventLoopWaker,
+}
+
+impl ReadyTasks {
+  fn schedule(&self, id: u64) {
+    self
+      .ids
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .push(id);
+    self.waker.wake();
+  }
+}
+
+struct TaskWaker {
+  id: u64,
+  ready: Arc<ReadyTasks>,
+}
+
+impl Wake for TaskWaker {
+  fn wake(self: Arc<Self>) {
+    self.wake_by_ref()
+  }
+
+  fn wake_by_ref(self: &Arc<Self>) {
+    self.ready.schedule(self.id)
+  }
+}
+
+impl Executor {
+  pub(crate) fn spawn<T>(
+    &self,
+    target: &EventLoopWindowTarget<T>,
+    future: impl Future<Output = ()> + 'static,
+  ) {
+    let id = self.next_id.get();
+    self.next_id.set(id + 1);
+    self.tasks.borrow_mut().insert(id, Box::pin(future));
+    let ready = self
+      .ready
+      .borrow_mut()
+      .get_or_insert_with(|| {
+        Arc::new(ReadyTasks {
+          ids: Default::default(),
+          waker: target.create_waker(),
+        })
+      })
+      .clone();
+    ready.schedule(id);
+  }
+
+  /// Polls the woken tasks once. Tasks woken meanwhile, or spawned by them, wait for the next
+  /// iteration of the event loop.
+  fn poll(&self) {
+    let ready = match &*self.ready.borrow() {
+      Some(ready) => ready.clone(),
+      None => return,
+    };
+    let ids = mem::take(&mut *ready.ids.lock().unwrap_or_else(PoisonError::into_inner));
+    for id in ids {
+      // The task isn't borrowed while it's polled, so it can spawn others. A task woken twice
+      // may have finished already.
+      let mut future = match self.tasks.borrow_mut().remove(&id) {
+        Some(future) => future,
+        None => continue,
+      };
+      let waker = Waker::from(Arc::new(TaskWaker {
+        id,
+        ready: ready.clone(),
+      }));
+      if future
+        .as_mut()
+        .poll(&mut Context::from_waker(&waker))
+        .is_pending()
+      {
+        self.tasks.borrow_mut().insert(id, future);
+      }
+    }
+  }
+}
+
+/// Wraps the event handler of an event loop to poll the woken futures after each
+/// `Event::NewEvents`.
+pub(crate) fn wrap_handler<T, F>(
+  mut handler: F,
+) -> impl FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow)
+where
+  F: FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+{
+  move |event, target, control_flow| {
+    let new_events = matches!(event, Event::NewEvents(_));
+    handler(event, target, control_flow);
+    if new_events {
+      target.executor.poll();
+    }
+  }
+}
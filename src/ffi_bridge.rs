@@ -0,0 +1,147 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! A C ABI for the native code of an application, like Objective-C delegates, to post messages
+//! to the event loop, enabled by the `ffi-bridge` feature.
+//!
+//! Once an event loop is built with
+//! [`EventLoopBuilder::with_ffi_bridge`](crate::event_loop::EventLoopBuilder::with_ffi_bridge),
+//! native code declares
+//!
+//! ```c
+//! int32_t tao_post_user_payload(const uint8_t *ptr, size_t len);
+//! ```
+//!
+//! and calls it from any thread. The bytes are copied, and delivered in the order they were
+//! posted as [`Event::NativeMessage`] right after the next [`Event::NewEvents`]. The function
+//! returns one of the `TAO_BRIDGE_*` codes.
+
+use std::{
+  collections::VecDeque,
+  mem, slice,
+  sync::{Arc, Mutex, PoisonError, Weak},
+};
+
+use crate::{
+  event::Event,
+  event_loop::{ControlFlow, EventLoopWaker, EventLoopWindowTarget},
+};
+
+/// The payload was queued.
+pub const TAO_BRIDGE_OK: i32 = 0;
+/// No event loop takes payloads: none was built with the bridge, or it was destroyed.
+pub const TAO_BRIDGE_CLOSED: i32 = 1;
+/// `ptr` is null while `len` isn't zero.
+pub const TAO_BRIDGE_INVALID: i32 = 2;
+
+lazy_static! {
+  /// The bridge of the last event loop built with it, owned by that event loop.
+  static ref BRIDGE: Mutex<Weak<Bridge>> = Mutex::new(Weak::new());
+}
+
+/// Posts the `len` bytes at `ptr` to the event loop, see the [module](self) documentation.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` readable bytes, or be null if `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn tao_post_user_payload(ptr: *const u8, len: usize) -> i32 {
+  if ptr.is_null() && len != 0 {
+    return TAO_BRIDGE_INVALID;
+  }
+  // Panicking would unwind into native code, a poisoned lock still holds a valid bridge.
+  let bridge = match BRIDGE
+    .lock()
+    .unwrap_or_else(PoisonError::into_inner)
+    .upgrade()
+  {
+    Some(bridge) => bridge,
+    None => return TAO_BRIDGE_CLOSED,
+  };
+  let payload = if len == 0 {
+    Vec::new()
+  } else {
+    slice::from_raw_parts(ptr, len).to_vec()
+  };
+  bridge.post(payload)
+}
+
+pub(crate) struct Bridge {
+  state: Mutex<BridgeState>,
+  waker: EventLoopWaker,
+}
+
+#[derive(Default)]
+struct BridgeState {
+  payloads: VecDeque<Vec<u8>>,
+  closed: bool,
+}
+
+impl Bridge {
+  /// Makes a new bridge the one `tao_post_user_payload` posts to, for as long as it's alive.
+  pub(crate) fn register(waker: EventLoopWaker) -> Arc<Self> {
+    let bridge = Arc::new(Self {
+      state: Default::default(),
+      waker,
+    });
+    *BRIDGE.lock().unwrap_or_else(PoisonError::into_inner) = Arc::downgrade(&bridge);
+    bridge
+  }
+
+  fn post(&self, payload: Vec<u8>) -> i32 {
+    {
+      let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+      if state.closed {
+        return TAO_BRIDGE_CLOSED;
+      }
+      state.payloads.push_back(payload);
+    }
+    self.waker.wake();
+    TAO_BRIDGE_OK
+  }
+
+  fn take_payloads(&self) -> VecDeque<Vec<u8>> {
+    let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+    mem::take(&mut state.payloads)
+  }
+
+  /// Refuses the payloads posted from now on, and drops the pending ones.
+  fn close(&self) {
+    let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+    state.closed = true;
+    state.payloads.clear();
+  }
+}
+
+/// Wraps the event handler of an event loop to deliver the payloads of `bridge` after each
+/// `Event::NewEvents`.
+///
+/// With `last_run`, the event loop can't run again after `Event::LoopDestroyed`, which closes
+/// `bridge`. Otherwise, payloads posted between two runs wait for the next one, and the bridge
+/// closes once the event loop is dropped.
+pub(crate) fn wrap_handler<T, F>(
+  bridge: Option<Arc<Bridge>>,
+  last_run: bool,
+  mut handler: F,
+) -> impl FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow)
+where
+  F: FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+{
+  move |event, target, control_flow| {
+    let bridge = match &bridge {
+      Some(bridge) => bridge,
+      None => return handler(event, target, control_flow),
+    };
+    let new_events = matches!(event, Event::NewEvents(_));
+    let destroyed = matches!(event, Event::LoopDestroyed);
+    handler(event, target, control_flow);
+    if new_events {
+      for payload in bridge.take_payloads() {
+        handler(Event::NativeMessage(payload), target, control_flow);
+      }
+    }
+    if destroyed && last_run {
+      bridge.close();
+    }
+  }
+}
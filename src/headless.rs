@@ -0,0 +1,172 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! A synthetic backend for automated tests, only built by the `tao-headless` package.
+//!
+//! `tao-headless` compiles the sources of tao with this backend instead of the native one: tao
+//! doesn't connect to any display server, monitors are described by [`VirtualMonitor`]s and
+//! windows only exist as their state in the process, so tests of an application built on tao run
+//! on CI machines without an X server or a macOS runner.
+//!
+//! The backend replaces the native one for the whole build, which a Cargo feature can't do
+//! without breaking the other crates of the build, so it's a separate package. Its library is
+//! named `tao` as well: the tests of an application depend on it instead of `tao`, e.g. from a
+//! test crate of their workspace. [`EventLoop::new`](crate::event_loop::EventLoop::new) builds a
+//! synthetic event loop there. The extension traits of the [`platform`](crate::platform)
+//! modules, other than `run_return`, `pump_events`, `executor` and `inject`, aren't available.
+//!
+//! # Monitors
+//!
+//! The monitors are the ones given to
+//! [`EventLoopBuilder::with_virtual_monitors`](crate::event_loop::EventLoopBuilder::with_virtual_monitors),
+//! or a single [`VirtualMonitor::default`] one. The first monitor is the primary one, and each
+//! has a single video mode, at its size and refresh rate.
+//!
+//! # Windows
+//!
+//! Windows have no frame, so their inner and outer positions and sizes are the same. The
+//! position defaults to the top left corner of the primary monitor and the size to 800x600
+//! logical pixels. The `Window` methods apply right away and queue the events a window manager
+//! would send, which are delivered in the next iteration of the event loop:
+//!
+//! - [`WindowEvent::Resized`] and [`WindowEvent::Moved`] when the geometry changes, including
+//!   when maximizing or going fullscreen, which fill the monitor.
+//! - [`WindowEvent::ScaleFactorChanged`] and [`WindowEvent::MonitorChanged`] when a window is
+//!   moved to a monitor with another scale factor, or to another monitor.
+//! - [`WindowEvent::Focused`] for the window taking the focus and the one losing it, when a
+//!   visible window is created focused or with [`Window::set_focus`].
+//! - [`WindowEvent::Maximized`], [`WindowEvent::FullscreenChanged`],
+//!   [`WindowEvent::VisibilityChanged`] and [`WindowEvent::ThemeChanged`].
+//! - [`WindowEvent::CloseRequested`] for [`Window::close`], and [`WindowEvent::Destroyed`] once the
+//!   `Window` is dropped.
+//!
+//! [`Window::request_redraw`] is honored, and [`Window::capture`] returns an image filled with
+//! the background color of the window. The other requests to the system, like drag and drop or
+//! raw window handles, return [`NotSupported`] or do nothing.
+//!
+//! # Input
+//!
+//! [`EventLoopProxy::inject`](crate::event_loop::EventLoopProxy::inject) delivers a
+//! [`WindowEvent`] as if the system had sent it, and the state of the window follows the events
+//! that change it, like [`WindowEvent::Resized`], [`WindowEvent::Moved`],
+//! [`WindowEvent::Focused`] or [`WindowEvent::CursorMoved`]. [`device_id`] and [`key_event`] build
+//! the parts of the input events that can't be built outside of tao.
+//!
+//! # Timing
+//!
+//! The event loop never sleeps: [`ControlFlow::WaitUntil`] resumes right away with
+//! [`StartCause::ResumeTimeReached`] unless an event is already pending, so a test runs the same
+//! way whatever the speed of the machine. [`ControlFlow::Wait`] still waits for an event sent
+//! from another thread, like a user event or an injected one.
+//!
+//! The clipboard is kept in memory and shared by the whole process.
+//!
+//! [`WindowEvent`]: crate::event::WindowEvent
+//! [`WindowEvent::Resized`]: crate::event::WindowEvent::Resized
+//! [`WindowEvent::Moved`]: crate::event::WindowEvent::Moved
+//! [`WindowEvent::ScaleFactorChanged`]: crate::event::WindowEvent::ScaleFactorChanged
+//! [`WindowEvent::MonitorChanged`]: crate::event::WindowEvent::MonitorChanged
+//! [`WindowEvent::Focused`]: crate::event::WindowEvent::Focused
+//! [`WindowEvent::Maximized`]: crate::event::WindowEvent::Maximized
+//! [`WindowEvent::FullscreenChanged`]: crate::event::WindowEvent::FullscreenChanged
+//! [`WindowEvent::VisibilityChanged`]: crate::event::WindowEvent::VisibilityChanged
+//! [`WindowEvent::ThemeChanged`]: crate::event::WindowEvent::ThemeChanged
+//! [`WindowEvent::CloseRequested`]: crate::event::WindowEvent::CloseRequested
+//! [`WindowEvent::Destroyed`]: crate::event::WindowEvent::Destroyed
+//! [`WindowEvent::CursorMoved`]: crate::event::WindowEvent::CursorMoved
+//! [`Window::set_focus`]: crate::window::Window::set_focus
+//! [`Window::close`]: crate::window::Window::close
+//! [`Window::request_redraw`]: crate::window::Window::request_redraw
+//! [`Window::capture`]: crate::window::Window::capture
+//! [`NotSupported`]: crate::error::ExternalError::NotSupported
+//! [`ControlFlow::WaitUntil`]: crate::event_loop::ControlFlow::WaitUntil
+//! [`ControlFlow::Wait`]: crate::event_loop::ControlFlow::Wait
+//! [`StartCause::ResumeTimeReached`]: crate::event::StartCause::ResumeTimeReached
+
+use crate::{
+  dpi::{PhysicalPosition, PhysicalSize},
+  event::{DeviceId, ElementState, KeyEvent},
+  keyboard::{Key, KeyCode, KeyLocation, ModifiersState},
+  platform_impl,
+};
+
+/// A monitor of the synthetic backend, see the [module-level docs](self).
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualMonitor {
+  /// The name of the monitor, which also identifies it, so each monitor needs its own.
+  pub name: String,
+  /// The position of the top left corner of the monitor on the desktop.
+  pub position: PhysicalPosition<i32>,
+  /// The size of the monitor.
+  pub size: PhysicalSize<u32>,
+  /// The scale factor of the monitor.
+  pub scale_factor: f64,
+  /// The refresh rate of the only video mode of the monitor.
+  pub refresh_rate: u16,
+}
+
+impl VirtualMonitor {
+  /// Creates a monitor with a refresh rate of 60 Hz.
+  pub fn new(
+    name: impl Into<String>,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    scale_factor: f64,
+  ) -> Self {
+    Self {
+      name: name.into(),
+      position,
+      size,
+      scale_factor,
+      refresh_rate: 60,
+    }
+  }
+}
+
+impl Default for VirtualMonitor {
+  /// A 1920x1080 monitor at the origin, with a scale factor of 1.
+  fn default() -> Self {
+    Self::new(
+      "Virtual Monitor",
+      PhysicalPosition::new(0, 0),
+      PhysicalSize::new(1920, 1080),
+      1.0,
+    )
+  }
+}
+
+/// Returns the device of the injected input events.
+pub fn device_id() -> DeviceId {
+  DeviceId(platform_impl::DeviceId)
+}
+
+/// Builds a key event to inject with a [`WindowEvent::KeyboardInput`].
+///
+/// The text of a pressed key is the one of a [`Key::Character`], and the key has no location
+/// and no modifiers. The public fields can be changed afterwards.
+///
+/// [`WindowEvent::KeyboardInput`]: crate::event::WindowEvent::KeyboardInput
+pub fn key_event(
+  physical_key: KeyCode,
+  logical_key: Key<'static>,
+  state: ElementState,
+) -> KeyEvent {
+  let text = match (&logical_key, state) {
+    (Key::Character(text), ElementState::Pressed) => Some(*text),
+    _ => None,
+  };
+  KeyEvent {
+    physical_key,
+    logical_key: logical_key.clone(),
+    text,
+    location: KeyLocation::Standard,
+    state,
+    repeat: false,
+    modifiers: ModifiersState::empty(),
+    platform_specific: platform_impl::KeyEventExtra {
+      text_with_all_modifiers: text,
+      key_without_modifiers: logical_key,
+    },
+  }
+}
@@ -4,6 +4,8 @@
 
 use crate::platform_impl::PlatformIcon;
 use std::{error::Error, fmt, io, mem};
+#[cfg(feature = "image")]
+use std::{fs, path::Path};
 
 #[repr(C)]
 #[derive(Debug)]
@@ -18,7 +20,8 @@ pub(crate) const PIXEL_SIZE: usize = mem::size_of::<Pixel>();
 
 #[non_exhaustive]
 #[derive(Debug)]
-/// An error produced when using `Icon::from_rgba` with invalid arguments.
+/// An error produced when using `Icon::from_rgba`, `Icon::from_rgba_set` or `Icon::from_file`
+/// with invalid arguments.
 pub enum BadIcon {
   /// Produced when the length of the `rgba` argument isn't divisible by 4, thus `rgba` can't be
   /// safely interpreted as 32bpp RGBA pixels.
@@ -35,6 +38,17 @@ pub enum BadIcon {
   },
   /// Produced when underlying OS functionality failed to create the icon
   OsError(io::Error),
+  /// Produced when an icon of a set isn't square.
+  #[non_exhaustive]
+  NotSquare { width: u32, height: u32 },
+  /// Produced when an icon of a set has a width or height of 0.
+  #[non_exhaustive]
+  InvalidDimensions { width: u32, height: u32 },
+  /// Produced when an icon set, or an icon file, contains no icons.
+  NoIcons,
+  /// Produced when an icon file isn't a PNG or ICO image, or can't be decoded.
+  #[cfg(feature = "image")]
+  Decode(image::ImageError),
 }
 
 impl fmt::Display for BadIcon {
@@ -54,6 +68,17 @@ impl fmt::Display for BadIcon {
                 width, height, pixel_count, width_x_height,
             ),
             BadIcon::OsError(e) => write!(f, "OS error when instantiating the icon: {:?}", e),
+            BadIcon::NotSquare { width, height } => write!(f,
+                "The icon isn't square ({:?}x{:?}).",
+                width, height,
+            ),
+            BadIcon::InvalidDimensions { width, height } => write!(f,
+                "The icon dimensions ({:?}x{:?}) are invalid.",
+                width, height,
+            ),
+            BadIcon::NoIcons => write!(f, "The icon set contains no icons."),
+            #[cfg(feature = "image")]
+            BadIcon::Decode(e) => write!(f, "Failed to decode the icon file: {}", e),
         }
   }
 }
@@ -64,13 +89,87 @@ impl Error for BadIcon {
   }
 }
 
+/// 32bpp RGBA data of one size of an [`Icon`], see [`Icon::from_rgba_set`].
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct RgbaIcon {
+pub struct RgbaIcon {
   pub(crate) rgba: Vec<u8>,
   pub(crate) width: u32,
   pub(crate) height: u32,
 }
 
+impl RgbaIcon {
+  /// Creates an `RgbaIcon` from 32bpp RGBA data.
+  ///
+  /// The length of `rgba` must be divisible by 4, and `width * height` must equal
+  /// `rgba.len() / 4`. Otherwise, this will return a `BadIcon` error.
+  pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, BadIcon> {
+    if rgba.len() % PIXEL_SIZE != 0 {
+      return Err(BadIcon::ByteCountNotDivisibleBy4 {
+        byte_count: rgba.len(),
+      });
+    }
+    let pixel_count = rgba.len() / PIXEL_SIZE;
+    if pixel_count != (width * height) as usize {
+      Err(BadIcon::DimensionsVsPixelCount {
+        width,
+        height,
+        width_x_height: (width * height) as usize,
+        pixel_count,
+      })
+    } else {
+      Ok(RgbaIcon {
+        rgba,
+        width,
+        height,
+      })
+    }
+  }
+
+  /// The width of the icon in pixels.
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  /// The height of the icon in pixels.
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  /// The RGBA data of the icon.
+  pub fn rgba(&self) -> &[u8] {
+    &self.rgba
+  }
+}
+
+/// Checks the icons of a set and sorts them from the smallest to the largest.
+#[allow(dead_code)] // Not used on every platform
+pub(crate) fn validate_icon_set(mut icons: Vec<RgbaIcon>) -> Result<Vec<RgbaIcon>, BadIcon> {
+  if icons.is_empty() {
+    return Err(BadIcon::NoIcons);
+  }
+  for icon in &icons {
+    let (width, height) = (icon.width, icon.height);
+    if width == 0 || height == 0 {
+      return Err(BadIcon::InvalidDimensions { width, height });
+    }
+    if width != height {
+      return Err(BadIcon::NotSquare { width, height });
+    }
+  }
+  icons.sort_by_key(|icon| icon.width);
+  Ok(icons)
+}
+
+/// Returns the smallest icon of a sorted set that is at least `size` pixels wide, or the
+/// largest one.
+#[allow(dead_code)] // Not used on every platform
+pub(crate) fn best_icon_for_size(icons: &[RgbaIcon], size: u32) -> &RgbaIcon {
+  icons
+    .iter()
+    .find(|icon| icon.width >= size)
+    .unwrap_or_else(|| icons.last().expect("icon sets are never empty"))
+}
+
 /// For platforms which don't have window icons (e.g. web)
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct NoIcon;
@@ -79,41 +178,17 @@ pub(crate) struct NoIcon;
 mod constructors {
   use super::*;
 
-  impl RgbaIcon {
-    /// Creates an `Icon` from 32bpp RGBA data.
-    ///
-    /// The length of `rgba` must be divisible by 4, and `width * height` must equal
-    /// `rgba.len() / 4`. Otherwise, this will return a `BadIcon` error.
-    pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, BadIcon> {
-      if rgba.len() % PIXEL_SIZE != 0 {
-        return Err(BadIcon::ByteCountNotDivisibleBy4 {
-          byte_count: rgba.len(),
-        });
-      }
-      let pixel_count = rgba.len() / PIXEL_SIZE;
-      if pixel_count != (width * height) as usize {
-        Err(BadIcon::DimensionsVsPixelCount {
-          width,
-          height,
-          width_x_height: (width * height) as usize,
-          pixel_count,
-        })
-      } else {
-        Ok(RgbaIcon {
-          rgba,
-          width,
-          height,
-        })
-      }
-    }
-  }
-
   impl NoIcon {
     pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, BadIcon> {
       // Create the rgba icon anyway to validate the input
       let _ = RgbaIcon::from_rgba(rgba, width, height)?;
       Ok(NoIcon)
     }
+
+    pub fn from_rgba_set(icons: Vec<RgbaIcon>) -> Result<Self, BadIcon> {
+      validate_icon_set(icons)?;
+      Ok(NoIcon)
+    }
   }
 }
 
@@ -139,4 +214,98 @@ impl Icon {
       inner: PlatformIcon::from_rgba(rgba, width, height)?,
     })
   }
+
+  /// Creates an `Icon` from several sizes of the same image, letting the platform pick the
+  /// best size for each use.
+  ///
+  /// The icons must be square and not empty. Otherwise, this will return a `BadIcon` error.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The closest sizes to `ICON_SMALL` and `ICON_BIG` are used, so the title bar
+  ///   and the taskbar each get a sharp icon.
+  /// - **Linux:** All sizes are given to the window manager as the icon list.
+  /// - **macOS:** Windows have no icon, all sizes are used by
+  ///   [`EventLoopWindowTargetExtMacOS::set_application_icon`](crate::platform::macos::EventLoopWindowTargetExtMacOS::set_application_icon).
+  pub fn from_rgba_set(icons: Vec<RgbaIcon>) -> Result<Self, BadIcon> {
+    Ok(Icon {
+      inner: PlatformIcon::from_rgba_set(icons)?,
+    })
+  }
+
+  /// Creates an `Icon` from a PNG or ICO file. Every size of an ICO file is kept, as with
+  /// [`Icon::from_rgba_set`].
+  ///
+  /// The image must be square. Otherwise, this will return a `BadIcon` error.
+  ///
+  /// Requires the `image` feature. On Windows,
+  /// [`IconExtWindows::from_path`](crate::platform::windows::IconExtWindows::from_path) loads an
+  /// icon with the system loader instead.
+  #[cfg(feature = "image")]
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, BadIcon> {
+    let bytes = fs::read(path).map_err(BadIcon::OsError)?;
+    let icons = match image::guess_format(&bytes).map_err(BadIcon::Decode)? {
+      image::ImageFormat::Ico => decode_ico(&bytes)?,
+      image::ImageFormat::Png => vec![decode_image(&bytes, image::ImageFormat::Png)?],
+      format => {
+        return Err(BadIcon::Decode(image::ImageError::Unsupported(
+          image::error::UnsupportedError::from_format_and_kind(
+            format.into(),
+            image::error::UnsupportedErrorKind::Format(format.into()),
+          ),
+        )))
+      }
+    };
+    Self::from_rgba_set(icons)
+  }
+}
+
+#[cfg(feature = "image")]
+fn decode_image(bytes: &[u8], format: image::ImageFormat) -> Result<RgbaIcon, BadIcon> {
+  let image = image::load_from_memory_with_format(bytes, format)
+    .map_err(BadIcon::Decode)?
+    .into_rgba8();
+  let (width, height) = image.dimensions();
+  RgbaIcon::from_rgba(image.into_raw(), width, height)
+}
+
+/// Decodes every entry of an ICO file. The decoder of `image` only returns the largest one, so
+/// each entry is wrapped in a file of its own.
+#[cfg(feature = "image")]
+fn decode_ico(bytes: &[u8]) -> Result<Vec<RgbaIcon>, BadIcon> {
+  const HEADER_SIZE: usize = 6;
+  const ENTRY_SIZE: usize = 16;
+
+  let truncated = || {
+    BadIcon::Decode(image::ImageError::Decoding(
+      image::error::DecodingError::new(image::ImageFormat::Ico.into(), "truncated ICO file"),
+    ))
+  };
+  let u32_at = |offset: usize| -> Result<usize, BadIcon> {
+    let field = bytes.get(offset..offset + 4).ok_or_else(truncated)?;
+    Ok(u32::from_le_bytes([field[0], field[1], field[2], field[3]]) as usize)
+  };
+
+  let count = bytes.get(4..HEADER_SIZE).ok_or_else(truncated)?;
+  let count = u16::from_le_bytes([count[0], count[1]]) as usize;
+  let mut icons = Vec::with_capacity(count);
+  for index in 0..count {
+    let entry_offset = HEADER_SIZE + index * ENTRY_SIZE;
+    let entry = bytes
+      .get(entry_offset..entry_offset + ENTRY_SIZE)
+      .ok_or_else(truncated)?;
+    let data_size = u32_at(entry_offset + 8)?;
+    let data_offset = u32_at(entry_offset + 12)?;
+    let data = bytes
+      .get(data_offset..data_offset + data_size)
+      .ok_or_else(truncated)?;
+
+    let mut file = Vec::with_capacity(HEADER_SIZE + ENTRY_SIZE + data_size);
+    file.extend_from_slice(&[0, 0, 1, 0, 1, 0]);
+    file.extend_from_slice(&entry[..12]);
+    file.extend_from_slice(&((HEADER_SIZE + ENTRY_SIZE) as u32).to_le_bytes());
+    file.extend_from_slice(data);
+    icons.push(decode_image(&file, image::ImageFormat::Ico)?);
+  }
+  Ok(icons)
 }
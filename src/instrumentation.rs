@@ -0,0 +1,164 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `tracing` spans around the event handler, and the [`LoopStats`] of
+//! [`EventLoopWindowTarget::loop_stats`], enabled by the `tracing` feature.
+
+use std::{cell::Cell, time::Duration};
+
+use instant::Instant;
+use tracing::field;
+
+use crate::{
+  event::Event,
+  event_loop::{ControlFlow, EventLoopWindowTarget, LoopStats},
+  window::WindowId,
+};
+
+/// The weight of the last iteration in the rolling averages.
+const WEIGHT: f64 = 1.0 / 32.0;
+
+thread_local! {
+  /// The statistics of the event loop running on this thread.
+  static STATS: Cell<LoopStats> = Cell::new(LoopStats::default());
+}
+
+pub(crate) fn loop_stats() -> LoopStats {
+  STATS.with(Cell::get)
+}
+
+fn event_kind<T>(event: &Event<'_, T>) -> &'static str {
+  match event {
+    Event::NewEvents(_) => "NewEvents",
+    Event::WindowEvent { .. } => "WindowEvent",
+    Event::DeviceEvent { .. } => "DeviceEvent",
+    Event::UserEvent(_) => "UserEvent",
+    Event::FdReady(_) => "FdReady",
+    Event::Suspended => "Suspended",
+    Event::Resumed => "Resumed",
+    Event::SurfaceCreated(_) => "SurfaceCreated",
+    Event::SurfaceDestroyed(_) => "SurfaceDestroyed",
+    Event::MainEventsCleared => "MainEventsCleared",
+    Event::RedrawRequested(_) => "RedrawRequested",
+    Event::RedrawEventsCleared => "RedrawEventsCleared",
+    Event::LoopDestroyed => "LoopDestroyed",
+    Event::Opened { .. } => "Opened",
+    Event::OpenedUrl(_) => "OpenedUrl",
+    Event::Reopen { .. } => "Reopen",
+    Event::ServiceRequested(_) => "ServiceRequested",
+    Event::GlobalShortcut(_) => "GlobalShortcut",
+    Event::SystemTrayEvent { .. } => "SystemTrayEvent",
+    Event::MenuEvent { .. } => "MenuEvent",
+    Event::NotificationAction { .. } => "NotificationAction",
+    Event::AccessibilityAction { .. } => "AccessibilityAction",
+    Event::AccessibilityPreferencesChanged(_) => "AccessibilityPreferencesChanged",
+    Event::SystemAppearanceChanged(_) => "SystemAppearanceChanged",
+    Event::PowerInfoChanged(_) => "PowerInfoChanged",
+    Event::SessionChanged(_) => "SessionChanged",
+    Event::MonitorConnected(_) => "MonitorConnected",
+    Event::MonitorDisconnected(_) => "MonitorDisconnected",
+    Event::ClipboardUpdated => "ClipboardUpdated",
+    Event::KeyboardLayoutChanged => "KeyboardLayoutChanged",
+    Event::SecondInstance { .. } => "SecondInstance",
+    Event::CaptureCompleted { .. } => "CaptureCompleted",
+    #[cfg(feature = "ffi-bridge")]
+    Event::NativeMessage(_) => "NativeMessage",
+  }
+}
+
+fn event_window_id<T>(event: &Event<'_, T>) -> Option<WindowId> {
+  match event {
+    Event::WindowEvent { window_id, .. }
+    | Event::SurfaceCreated(window_id)
+    | Event::SurfaceDestroyed(window_id)
+    | Event::RedrawRequested(window_id) => Some(*window_id),
+    _ => None,
+  }
+}
+
+/// The iteration in progress, from `Event::NewEvents` to `Event::RedrawEventsCleared`.
+struct Iteration {
+  start: Instant,
+  wait: Duration,
+  events: u32,
+  callback: Duration,
+}
+
+fn average(average: Duration, last: Duration) -> Duration {
+  Duration::from_secs_f64(average.as_secs_f64() * (1.0 - WEIGHT) + last.as_secs_f64() * WEIGHT)
+}
+
+fn record(iteration: Iteration) {
+  let duration = iteration.start.elapsed();
+  tracing::trace!(
+    events = iteration.events,
+    wait_us = iteration.wait.as_micros() as u64,
+    callback_us = iteration.callback.as_micros() as u64,
+    iteration_us = duration.as_micros() as u64,
+    "event loop iteration"
+  );
+  STATS.with(|stats| {
+    let mut s = stats.get();
+    if s.iterations == 0 {
+      s.events = iteration.events as f64;
+      s.wait_duration = iteration.wait;
+      s.callback_duration = iteration.callback;
+      s.iteration_duration = duration;
+    } else {
+      s.events = s.events * (1.0 - WEIGHT) + iteration.events as f64 * WEIGHT;
+      s.wait_duration = average(s.wait_duration, iteration.wait);
+      s.callback_duration = average(s.callback_duration, iteration.callback);
+      s.iteration_duration = average(s.iteration_duration, duration);
+    }
+    s.iterations += 1;
+    stats.set(s);
+  });
+}
+
+/// Wraps the event handler of an event loop to run each call in a span, and to measure the
+/// iterations of the loop.
+pub(crate) fn wrap_handler<T, F>(
+  mut handler: F,
+) -> impl FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow)
+where
+  F: FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+{
+  let mut iteration: Option<Iteration> = None;
+  // When the last iteration ended, the loop waits for events from then on.
+  let mut idle_since: Option<Instant> = None;
+  move |event, target, control_flow| {
+    let start = Instant::now();
+    let ends_iteration = matches!(event, Event::RedrawEventsCleared | Event::LoopDestroyed);
+    if let Event::NewEvents(_) = event {
+      iteration = Some(Iteration {
+        start,
+        wait: idle_since
+          .take()
+          .map_or(Duration::ZERO, |since| start - since),
+        events: 0,
+        callback: Duration::ZERO,
+      });
+    }
+
+    let span = tracing::trace_span!(
+      "tao::event",
+      kind = event_kind(&event),
+      window_id = field::Empty
+    );
+    if let Some(window_id) = event_window_id(&event) {
+      span.record("window_id", &field::debug(window_id));
+    }
+    span.in_scope(|| handler(event, target, control_flow));
+
+    if let Some(iteration) = &mut iteration {
+      iteration.events += 1;
+      iteration.callback += start.elapsed();
+    }
+    if ends_iteration {
+      if let Some(iteration) = iteration.take() {
+        record(iteration);
+      }
+      idle_since = Some(Instant::now());
+    }
+  }
+}
@@ -106,6 +106,10 @@ bitflags! {
     /// Represents the current state of the keyboard modifiers
     ///
     /// Each flag represents a modifier and is set if this modifier is active.
+    ///
+    /// With the `serde` feature, this is (de)serialized as a struct of booleans named
+    /// `shift_key`, `control_key`, `alt_key` and `super_key` rather than as raw bits. Missing
+    /// fields default to `false`.
     #[derive(Default)]
     pub struct ModifiersState: u32 {
         // left and right modifiers are currently commented out, but we should be able to support
@@ -204,6 +208,10 @@ pub enum NativeKeyCode {
 /// - The key that the specification calls "Super" is reported as `Unidentified` here.
 /// - The `Unidentified` variant here, can still identifiy a key through it's `NativeKeyCode`.
 ///
+/// With the `serde` feature, variants are (de)serialized by their name, e.g. `"KeyA"` or
+/// `"ArrowLeft"`. These names are part of the public API and won't change when new variants are
+/// added.
+///
 /// [`KeyboardEvent.code`]: https://w3c.github.io/uievents-code/#code-value-tables
 #[non_exhaustive]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -842,6 +850,11 @@ impl fmt::Display for KeyCode {
 /// - The `Dead` variant here, can specify the character which is inserted when pressing the
 ///   dead-key twice.
 ///
+/// With the `serde` feature, variants are (de)serialized by their name like [`KeyCode`]. The
+/// variants carrying data are externally tagged, e.g. `{"Character": "a"}`. Deserializing
+/// `Key<'static>` borrows the string of `Character`, so it needs a format that can hand out
+/// `'static` strings.
+///
 /// [`KeyboardEvent.key`]: https://w3c.github.io/uievents-key/
 #[non_exhaustive]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -1594,3 +1607,20 @@ pub enum KeyLocation {
   Right,
   Numpad,
 }
+
+/// A keyboard layout of the system, see [`EventLoopWindowTarget::keyboard_layout`].
+///
+/// [`EventLoopWindowTarget::keyboard_layout`]: crate::event_loop::EventLoopWindowTarget::keyboard_layout
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyboardLayout {
+  /// The identifier of the layout, which is the same in every session of the system.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The keyboard layout identifier, e.g. `00000409` for US English.
+  /// - **macOS:** The input source identifier, e.g. `com.apple.keylayout.US`.
+  /// - **Linux:** The name of the active XKB group, e.g. `English (US)`.
+  pub id: String,
+}
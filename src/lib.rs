@@ -103,9 +103,22 @@
 //!
 //! Tao doesn't directly provide any methods for drawing on a [`Window`]. However it allows you to
 //! retrieve the raw handle of the window and display (see the [`platform`] module and/or the
-//! [`raw_window_handle`] and [`raw_display_handle`] methods), which in turn allows you to create an
+//! [`raw-window-handle`] traits), which in turn allows you to create an
 //! OpenGL/Vulkan/DirectX/Metal/etc. context that can be used to render graphics.
 //!
+//! Each supported version of [`raw-window-handle`] has its own cargo feature, and any combination
+//! of them can be enabled at the same time:
+//!
+//! - `rwh_06` (enabled by default): [`Window`] implements `HasWindowHandle` and `HasDisplayHandle`,
+//!   and [`EventLoop`] and [`EventLoopWindowTarget`] implement `HasDisplayHandle`. The returned
+//!   handles borrow from the object they were obtained from. This is what `wgpu` 0.19+ expects,
+//!   see the examples of the `wgpu-examples` package of the repository.
+//! - `rwh_05`: [`Window`] implements `HasRawWindowHandle` and `HasRawDisplayHandle`, and
+//!   [`EventLoop`] and [`EventLoopWindowTarget`] implement `HasRawDisplayHandle`.
+//! - `rwh_04`: [`Window`] implements `HasRawWindowHandle`.
+//!
+//! The enabled versions are re-exported as `tao::rwh_04`, `tao::rwh_05` and `tao::rwh_06`.
+//!
 //! Note that many platforms will display garbage data in the window's client area if the
 //! application doesn't render anything to the window by the time the desktop compositor is ready to
 //! display the window to the user. If you notice this happening, you should create the window with
@@ -132,8 +145,8 @@
 //! [`UserEvent`]: event::Event::UserEvent
 //! [`LoopDestroyed`]: event::Event::LoopDestroyed
 //! [`platform`]: platform
-//! [`raw_window_handle`]: ./window/struct.Window.html#method.raw_window_handle
-//! [`raw_display_handle`]: ./window/struct.Window.html#method.raw_display_handle
+//! [`EventLoopWindowTarget`]: event_loop::EventLoopWindowTarget
+//! [`raw-window-handle`]: https://docs.rs/raw-window-handle
 #![allow(
   clippy::match_str_case_mismatch,
   clippy::upper_case_acronyms,
@@ -169,16 +182,32 @@ extern crate bitflags;
 #[macro_use]
 extern crate objc;
 
+pub mod accessibility;
+mod capture;
+pub mod clipboard;
 pub mod dpi;
 #[macro_use]
 pub mod error;
 pub mod event;
 pub mod event_loop;
+mod executor;
+#[cfg(feature = "ffi-bridge")]
+pub mod ffi_bridge;
+#[cfg(tao_headless)]
+pub mod headless;
 mod icon;
+#[cfg(feature = "tracing")]
+mod instrumentation;
 pub mod keyboard;
+pub mod menu;
 pub mod monitor;
+mod motion_coalescing;
+pub mod notification;
 mod platform_impl;
+pub mod power;
+pub mod system_tray;
 
 pub mod window;
+mod window_registry;
 
 pub mod platform;
@@ -0,0 +1,170 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! The [`Menu`] struct and associated types.
+//!
+//! A [`Menu`] is the menu bar of a window, set with [`Window::set_menu`], holding [`Submenu`]s.
+//! A [`Submenu`] holds [`MenuItem`]s and other submenus, and can be shown on its own as a context
+//! menu with [`Window::show_context_menu`]. Choosing an item delivers
+//! [`Event::MenuEvent`] with its [`MenuId`].
+//!
+//! The native menus are built as the items are appended, and an item or submenu belongs to the
+//! first one it's appended to. Keep the [`MenuItem`]s around to change them later, dropping them
+//! leaves them in their menu.
+//!
+//! [`Window::set_menu`]: crate::window::Window::set_menu
+//! [`Window::show_context_menu`]: crate::window::Window::show_context_menu
+//! [`Event::MenuEvent`]: crate::event::Event::MenuEvent
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::platform_impl;
+
+/// Identifies a [`MenuItem`] in the [`Event::MenuEvent`] of its activations.
+///
+/// [`Event::MenuEvent`]: crate::event::Event::MenuEvent
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct MenuId(pub(crate) u32);
+
+/// A menu bar, see the [module-level docs](self).
+///
+/// ## Platform-specific
+///
+/// - **macOS:** The menu bar belongs to the application, the first submenu is shown as the
+///   application menu under the name of the application, whatever its title.
+/// - **Linux:** Without a display, as with a headless event loop, menus stay empty and are never
+///   shown.
+/// - **iOS / Android:** Unsupported.
+pub struct Menu {
+  pub(crate) menu: platform_impl::Menu,
+}
+
+impl Menu {
+  /// Creates an empty menu bar.
+  #[inline]
+  pub fn new() -> Self {
+    Self {
+      menu: platform_impl::Menu::new(),
+    }
+  }
+
+  /// Appends a submenu, which belongs to this menu bar from then on.
+  #[inline]
+  pub fn append_submenu(&self, submenu: &Submenu) {
+    self.menu.append_submenu(&submenu.submenu)
+  }
+}
+
+impl Default for Menu {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// A submenu, in a [`Menu`], in another submenu, or shown as a context menu.
+pub struct Submenu {
+  pub(crate) submenu: platform_impl::Submenu,
+}
+
+impl Submenu {
+  /// Creates an empty submenu with the title shown in its parent.
+  #[inline]
+  pub fn new(title: &str) -> Self {
+    Self {
+      submenu: platform_impl::Submenu::new(title),
+    }
+  }
+
+  /// Appends an item, which belongs to this submenu from then on.
+  #[inline]
+  pub fn append_item(&self, item: &MenuItem) {
+    self.submenu.append_item(&item.item)
+  }
+
+  /// Appends a submenu, which belongs to this submenu from then on.
+  #[inline]
+  pub fn append_submenu(&self, submenu: &Submenu) {
+    self.submenu.append_submenu(&submenu.submenu)
+  }
+
+  /// Appends a separator line.
+  #[inline]
+  pub fn append_separator(&self) {
+    self.submenu.append_separator()
+  }
+
+  /// Changes the title shown in the parent.
+  #[inline]
+  pub fn set_title(&self, title: &str) {
+    self.submenu.set_title(title)
+  }
+
+  /// Enables or disables the submenu, a disabled submenu is greyed out and can't be opened.
+  #[inline]
+  pub fn set_enabled(&self, enabled: bool) {
+    self.submenu.set_enabled(enabled)
+  }
+}
+
+/// An item of a [`Submenu`], delivering [`Event::MenuEvent`] when it's chosen.
+///
+/// [`Event::MenuEvent`]: crate::event::Event::MenuEvent
+pub struct MenuItem {
+  id: MenuId,
+  pub(crate) item: platform_impl::MenuItem,
+}
+
+impl MenuItem {
+  /// Creates an enabled item.
+  #[inline]
+  pub fn new(title: &str) -> Self {
+    Self::with_check(title, None)
+  }
+
+  /// Creates an enabled item with a check mark, toggled whenever the item is chosen.
+  #[inline]
+  pub fn new_check(title: &str, checked: bool) -> Self {
+    Self::with_check(title, Some(checked))
+  }
+
+  fn with_check(title: &str, checked: Option<bool>) -> Self {
+    static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    Self {
+      id: MenuId(id),
+      item: platform_impl::MenuItem::new(id, title, checked),
+    }
+  }
+
+  /// Returns the identifier of the item in its events.
+  #[inline]
+  pub fn id(&self) -> MenuId {
+    self.id
+  }
+
+  /// Changes the title.
+  #[inline]
+  pub fn set_title(&self, title: &str) {
+    self.item.set_title(title)
+  }
+
+  /// Enables or disables the item, a disabled item is greyed out and can't be chosen.
+  #[inline]
+  pub fn set_enabled(&self, enabled: bool) {
+    self.item.set_enabled(enabled)
+  }
+
+  /// Checks or unchecks the item, without delivering an event. Does nothing unless the item
+  /// was created with [`MenuItem::new_check`].
+  #[inline]
+  pub fn set_checked(&self, checked: bool) {
+    self.item.set_checked(checked)
+  }
+
+  /// Returns whether the item is checked, `false` unless it was created with
+  /// [`MenuItem::new_check`].
+  #[inline]
+  pub fn is_checked(&self) -> bool {
+    self.item.is_checked()
+  }
+}
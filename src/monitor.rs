@@ -14,8 +14,10 @@
 //! [loop_get]: crate::event_loop::EventLoopWindowTarget::available_monitors
 //! [window_get]: crate::window::Window::available_monitors
 use crate::{
-  dpi::{PhysicalPosition, PhysicalSize},
+  dpi::{PhysicalPosition, PhysicalSize, Rect},
+  error::ExternalError,
   platform_impl,
+  window::RgbaImage,
 };
 
 /// Describes a fullscreen video mode of a monitor.
@@ -121,12 +123,44 @@ pub struct MonitorHandle {
 impl MonitorHandle {
   /// Returns a human-readable name of the monitor.
   ///
+  /// The name comes from the monitor itself (its EDID) where the platform exposes it, e.g.
+  /// `"DELL U2720Q"`, rather than the name of the output it's plugged into.
+  ///
   /// Returns `None` if the monitor doesn't exist anymore.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Falls back to the GDI device name (e.g. `\\.\DISPLAY1`) when the display
+  ///   configuration can't be queried.
+  /// - **macOS:** The localized name of the screen, available on macOS 10.15+. Falls back to
+  ///   `Monitor #<model number>` on older versions.
+  /// - **Linux:** `"<manufacturer> <model>"` as reported by GDK.
   #[inline]
   pub fn name(&self) -> Option<String> {
     self.inner.name()
   }
 
+  /// Returns an identifier for the physical display behind this monitor.
+  ///
+  /// Unlike the order of [`available_monitors`] or the platform handle, the id stays the same
+  /// when the display is disconnected and reconnected, or across application runs, so it can be
+  /// persisted to put a window back on the "same" display later.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Derived from the monitor device path, which encodes the EDID manufacturer and
+  ///   product codes and the connector the display is plugged into.
+  /// - **macOS:** Derived from the display UUID (`CGDisplayCreateUUIDFromDisplayID`).
+  /// - **Linux:** Derived from the manufacturer and model reported by GDK. Two identical
+  ///   displays share an id.
+  /// - **iOS / Android:** Derived from [`name`](Self::name).
+  ///
+  /// [`available_monitors`]: crate::event_loop::EventLoopWindowTarget::available_monitors
+  #[inline]
+  pub fn id(&self) -> u64 {
+    self.inner.id()
+  }
+
   /// Returns the monitor's resolution.
   #[inline]
   pub fn size(&self) -> PhysicalSize<u32> {
@@ -152,12 +186,87 @@ impl MonitorHandle {
     self.inner.scale_factor()
   }
 
+  /// Returns the refresh rate of the current video mode of the monitor, in millihertz, or
+  /// `None` if the platform doesn't tell it.
+  ///
+  /// Unlike [`VideoMode::refresh_rate`], it's exact enough to pace frames, e.g. `59940` for a
+  /// 59.94 Hz mode. It's read from the system on each call, so it follows the changes of mode.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Read from the active display configuration.
+  /// - **macOS:** Read from the current display mode, or from the nominal refresh period of the
+  ///   display for the built-in displays that don't report a rate.
+  /// - **Linux:** As reported by GDK.
+  /// - **iOS:** The `maximumFramesPerSecond` of the screen, on iOS 10.3+.
+  /// - **Android:** Always returns `None`.
+  #[inline]
+  pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+    self.inner.refresh_rate_millihertz()
+  }
+
   /// Returns all fullscreen video modes supported by this monitor.
   ///
   /// ## Platform-specific
-  /// - **Linux:** Unsupported. This will always return empty iterator.
+  /// - **Linux:** Read with RandR on X11. This will always return an empty iterator on Wayland.
   #[inline]
   pub fn video_modes(&self) -> impl Iterator<Item = VideoMode> {
     self.inner.video_modes()
   }
+
+  /// Captures what the monitor shows, in physical pixels.
+  ///
+  /// This blocks until the pixels are read back, use
+  /// [`EventLoopWindowTarget::request_monitor_capture`] to get them through the event loop
+  /// instead.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Copies the screen with `BitBlt` rather than DXGI desktop duplication, the
+  ///   windows excluded from capture with `set_content_protection` show up black.
+  /// - **macOS:** Uses `CGDisplayCreateImage`, deprecated since macOS 14, as ScreenCaptureKit
+  ///   isn't used. It requires the screen recording permission to include the windows of other
+  ///   applications.
+  /// - **Linux:** Reads the root window, only supported on X11. Returns
+  ///   [`ExternalError::NotSupported`] on Wayland.
+  /// - **iOS / Android / headless:** Unsupported, returns [`ExternalError::NotSupported`].
+  ///
+  /// [`EventLoopWindowTarget::request_monitor_capture`]: crate::event_loop::EventLoopWindowTarget::request_monitor_capture
+  #[inline]
+  pub fn capture(&self) -> Result<RgbaImage, ExternalError> {
+    self.inner.capture()
+  }
+
+  /// Same as [`MonitorHandle::capture`], but only captures `rect`, relative to the top-left
+  /// corner of the monitor.
+  ///
+  /// The part of `rect` outside of the monitor is left out of the image, and
+  /// [`ExternalError::InvalidInput`] is returned when nothing is left.
+  #[inline]
+  pub fn capture_rect(&self, rect: Rect) -> Result<RgbaImage, ExternalError> {
+    self.inner.capture()?.crop(rect, self.scale_factor())
+  }
+}
+
+/// Sorts `monitors` in the order documented on
+/// [`EventLoopWindowTarget::available_monitors`](crate::event_loop::EventLoopWindowTarget::available_monitors):
+/// the primary monitor first, then the others from left to right and top to bottom.
+pub(crate) fn sort_monitors(monitors: &mut [MonitorHandle], primary: Option<&MonitorHandle>) {
+  monitors.sort_by_cached_key(|monitor| {
+    let position = monitor.position();
+    (
+      Some(monitor) != primary,
+      position.x,
+      position.y,
+      monitor.id(),
+    )
+  });
+}
+
+/// 64-bit FNV-1a hash of `bytes`, used by the backends to turn the platform's display
+/// identifier into a [`MonitorHandle::id`] that doesn't depend on the std hasher.
+pub(crate) fn stable_id(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+    (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+  })
 }
@@ -0,0 +1,194 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! The [`MotionCoalescing`] of the event handler, applied the same way on every platform.
+
+use std::collections::HashMap;
+
+use crate::{
+  event::{DeviceEvent, DeviceId, Event, WindowEvent},
+  event_loop::{ControlFlow, EventLoopWindowTarget, MotionCoalescing},
+  window::WindowId,
+};
+
+/// What a pending motion can be merged with.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MotionKey {
+  Cursor(WindowId, DeviceId),
+  Raw(DeviceId),
+}
+
+fn motion_key<T>(event: &Event<'_, T>) -> Option<MotionKey> {
+  match event {
+    Event::WindowEvent {
+      window_id,
+      event: WindowEvent::CursorMoved { device_id, .. },
+    } => Some(MotionKey::Cursor(*window_id, *device_id)),
+    Event::DeviceEvent {
+      device_id,
+      event: DeviceEvent::MouseMotion { .. },
+    } => Some(MotionKey::Raw(*device_id)),
+    _ => None,
+  }
+}
+
+/// Whether the motion waits for a newer one instead of being delivered right away.
+fn holds<T>(coalescing: MotionCoalescing, event: &Event<'_, T>) -> bool {
+  match coalescing {
+    MotionCoalescing::None => false,
+    MotionCoalescing::Latest => true,
+    MotionCoalescing::MaxAge(max_age) => matches!(
+      event,
+      Event::WindowEvent {
+        event: WindowEvent::CursorMoved { timestamp, .. },
+        ..
+      } if timestamp.elapsed() > max_age
+    ),
+  }
+}
+
+/// Merges `newer` into the pending motion `older` with the same key.
+fn merge<T>(older: Event<'static, T>, newer: Event<'static, T>) -> Event<'static, T> {
+  match (older, newer) {
+    (
+      Event::DeviceEvent {
+        event: DeviceEvent::MouseMotion { delta: (x, y) },
+        ..
+      },
+      Event::DeviceEvent {
+        device_id,
+        event: DeviceEvent::MouseMotion { delta: (dx, dy) },
+      },
+    ) => Event::DeviceEvent {
+      device_id,
+      event: DeviceEvent::MouseMotion {
+        delta: (x + dx, y + dy),
+      },
+    },
+    (_, newer) => newer,
+  }
+}
+
+/// The coalescing of the motion of `key`, the one of its window in `windows` when it has its own.
+fn coalescing_of(
+  coalescing: MotionCoalescing,
+  key: MotionKey,
+  windows: &HashMap<WindowId, MotionCoalescing>,
+) -> MotionCoalescing {
+  match key {
+    MotionKey::Cursor(window_id, _) => windows.get(&window_id).copied().unwrap_or(coalescing),
+    MotionKey::Raw(_) => coalescing,
+  }
+}
+
+/// Wraps the event handler of an event loop to coalesce the motions as `coalescing` says, or as
+/// the window they're over says.
+pub(crate) fn wrap_handler<T: 'static, F>(
+  coalescing: MotionCoalescing,
+  mut handler: F,
+) -> impl FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow)
+where
+  F: FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+{
+  // In the order they arrived, at most one per key.
+  let mut pending: Vec<Event<'static, T>> = Vec::new();
+  move |event, target, control_flow| {
+    if let Event::WindowEvent {
+      window_id,
+      event: WindowEvent::Destroyed,
+    } = event
+    {
+      target
+        .window_motion_coalescing
+        .borrow_mut()
+        .remove(&window_id);
+    }
+    let key = match motion_key(&event) {
+      Some(key) => key,
+      None => {
+        for motion in pending.drain(..) {
+          handler(motion, target, control_flow);
+        }
+        return handler(event, target, control_flow);
+      }
+    };
+    let coalescing = coalescing_of(coalescing, key, &target.window_motion_coalescing.borrow());
+
+    let older = pending
+      .iter()
+      .position(|motion| motion_key(motion) == Some(key))
+      .map(|index| pending.remove(index));
+    if holds(coalescing, &event) {
+      // Motions are always static.
+      if let Some(event) = event.to_static() {
+        pending.push(match older {
+          Some(older) => merge(older, event),
+          None => event,
+        });
+      }
+    } else {
+      // A fresh motion supersedes a stale one of the same key.
+      handler(event, target, control_flow);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{collections::HashMap, time::Duration};
+
+  use super::{coalescing_of, merge, MotionKey};
+  use crate::{
+    event::{DeviceEvent, DeviceId, Event},
+    event_loop::MotionCoalescing,
+    window::WindowId,
+  };
+
+  #[test]
+  fn window_coalescing_overrides_the_event_loop_one() {
+    let device_id = unsafe { DeviceId::dummy() };
+    let (first, second) = (WindowId::from_raw(1), WindowId::from_raw(2));
+    let windows = HashMap::from([(first, MotionCoalescing::None)]);
+    let event_loop = MotionCoalescing::MaxAge(Duration::from_millis(8));
+
+    assert_eq!(
+      coalescing_of(event_loop, MotionKey::Cursor(first, device_id), &windows),
+      MotionCoalescing::None
+    );
+    assert_eq!(
+      coalescing_of(event_loop, MotionKey::Cursor(second, device_id), &windows),
+      event_loop
+    );
+  }
+
+  #[test]
+  fn raw_motions_use_the_event_loop_coalescing() {
+    let device_id = unsafe { DeviceId::dummy() };
+    let windows = HashMap::from([(WindowId::from_raw(1), MotionCoalescing::None)]);
+
+    assert_eq!(
+      coalescing_of(
+        MotionCoalescing::Latest,
+        MotionKey::Raw(device_id),
+        &windows
+      ),
+      MotionCoalescing::Latest
+    );
+  }
+
+  #[test]
+  fn merged_raw_motions_add_up() {
+    let device_id = unsafe { DeviceId::dummy() };
+    let motion = |delta| -> Event<'static, ()> {
+      Event::DeviceEvent {
+        device_id,
+        event: DeviceEvent::MouseMotion { delta },
+      }
+    };
+
+    assert_eq!(
+      merge(motion((1.0, -2.0)), motion((0.5, 3.0))),
+      motion((1.5, 1.0))
+    );
+  }
+}
@@ -0,0 +1,145 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! The [`Notification`] struct and associated types.
+//!
+//! A notification is shown by the notification center of the system: the toasts of the action
+//! center on Windows, the Notification Center on macOS, or the notification server of the
+//! desktop on Linux. Clicks on it and on its actions are delivered as
+//! [`Event::NotificationAction`] to the event loop that showed it, on its thread.
+//!
+//! [`Event::NotificationAction`]: crate::event::Event::NotificationAction
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{error::ExternalError, event_loop::EventLoopWindowTarget, platform_impl, window::Icon};
+
+/// Identifies a [`Notification`] in the [`Event::NotificationAction`] of its clicks.
+///
+/// [`Event::NotificationAction`]: crate::event::Event::NotificationAction
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct NotificationId(pub(crate) u32);
+
+/// A button of a notification, see [`NotificationBuilder::with_action`].
+#[allow(dead_code)] // Not read on every platform
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NotificationAction {
+  pub(crate) id: String,
+  pub(crate) label: String,
+}
+
+/// The content of a notification, given to the platform to show it.
+#[allow(dead_code)] // Not read on every platform
+#[derive(Debug, Clone)]
+pub(crate) struct NotificationAttributes {
+  pub(crate) title: String,
+  pub(crate) body: Option<String>,
+  pub(crate) icon: Option<Icon>,
+  pub(crate) actions: Vec<NotificationAction>,
+}
+
+/// Object that allows building a notification.
+#[derive(Debug, Clone)]
+pub struct NotificationBuilder {
+  attributes: NotificationAttributes,
+}
+
+impl NotificationBuilder {
+  /// Creates a builder for a notification with `title`.
+  #[inline]
+  pub fn new<S: Into<String>>(title: S) -> Self {
+    Self {
+      attributes: NotificationAttributes {
+        title: title.into(),
+        body: None,
+        icon: None,
+        actions: Vec::new(),
+      },
+    }
+  }
+
+  /// Sets the text under the title.
+  #[inline]
+  pub fn with_body<S: Into<String>>(mut self, body: S) -> Self {
+    self.attributes.body = Some(body.into());
+    self
+  }
+
+  /// Sets the image shown next to the text, instead of the icon of the application.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Shown as an attachment, the icon of the application is always shown.
+  #[inline]
+  pub fn with_icon(mut self, icon: Icon) -> Self {
+    self.attributes.icon = Some(icon);
+    self
+  }
+
+  /// Adds a button showing `label`. A click on it is delivered with `id` as the `action` of the
+  /// [`Event::NotificationAction`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** At most 5 actions are shown.
+  /// - **Linux:** Depends on the notification server, some of them don't show actions.
+  ///
+  /// [`Event::NotificationAction`]: crate::event::Event::NotificationAction
+  #[inline]
+  pub fn with_action<I: Into<String>, L: Into<String>>(mut self, id: I, label: L) -> Self {
+    self.attributes.actions.push(NotificationAction {
+      id: id.into(),
+      label: label.into(),
+    });
+    self
+  }
+
+  /// Shows the notification.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The toast is shown for the application user model id of the process, set
+  ///   with `SetCurrentProcessExplicitAppUserModelID`, which needs a shortcut in the start menu
+  ///   for the system to show it. Without one, the toast is shown for Windows PowerShell.
+  /// - **macOS:** Needs the application to be bundled, and asks the user for the permission the
+  ///   first time. Returns an [`ExternalError::NotSupported`] outside of a bundle.
+  /// - **Linux:** Needs a notification server implementing `org.freedesktop.Notifications`. The
+  ///   server is called from another thread, so that it doesn't block the event loop, and a
+  ///   failure to reach it is only logged.
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  pub fn show<T: 'static>(
+    self,
+    window_target: &EventLoopWindowTarget<T>,
+  ) -> Result<Notification, ExternalError> {
+    static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let notification = platform_impl::Notification::new(&window_target.p, id, self.attributes)?;
+    Ok(Notification {
+      id: NotificationId(id),
+      notification,
+    })
+  }
+}
+
+/// A notification shown by the system.
+///
+/// The notification stays in the notification center once dropped, and its clicks are still
+/// delivered to the event loop.
+pub struct Notification {
+  id: NotificationId,
+  notification: platform_impl::Notification,
+}
+
+impl Notification {
+  /// Returns the identifier of the notification in its events.
+  #[inline]
+  pub fn id(&self) -> NotificationId {
+    self.id
+  }
+
+  /// Removes the notification from the screen and from the notification center.
+  #[inline]
+  pub fn close(&self) {
+    self.notification.close()
+  }
+}
@@ -2,7 +2,7 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(target_os = "android")]
+#![cfg(all(target_os = "android", not(tao_headless)))]
 
 pub mod prelude {
   pub use crate::platform_impl::ndk_glue::*;
@@ -13,7 +13,7 @@ use crate::{
   event_loop::{EventLoop, EventLoopWindowTarget},
   window::{Window, WindowBuilder},
 };
-use ndk::configuration::Configuration;
+use ndk::{configuration::Configuration, native_window::NativeWindow};
 
 /// Additional methods on `EventLoop` that are specific to Android.
 pub trait EventLoopExtAndroid {}
@@ -28,6 +28,14 @@ pub trait WindowExtAndroid {
   fn content_rect(&self) -> Rect;
 
   fn config(&self) -> Configuration;
+
+  /// Returns the native window of the surface, or `None` between
+  /// [`Event::SurfaceDestroyed`](crate::event::Event::SurfaceDestroyed) and
+  /// [`Event::SurfaceCreated`](crate::event::Event::SurfaceCreated).
+  ///
+  /// The returned window holds a reference to the native window, but the surface behind it can't
+  /// be rendered to after `Event::SurfaceDestroyed`.
+  fn native_window(&self) -> Option<NativeWindow>;
 }
 
 impl WindowExtAndroid for Window {
@@ -38,6 +46,10 @@ impl WindowExtAndroid for Window {
   fn config(&self) -> Configuration {
     self.window.config()
   }
+
+  fn native_window(&self) -> Option<NativeWindow> {
+    self.window.native_window()
+  }
 }
 
 impl<T> EventLoopWindowTargetExtAndroid for EventLoopWindowTarget<T> {}
@@ -0,0 +1,41 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::future::Future;
+
+use crate::event_loop::EventLoopWindowTarget;
+
+/// Additional methods on `EventLoopWindowTarget`, and so on `EventLoop`, to run futures on the
+/// thread of the event loop without a separate runtime.
+pub trait EventLoopExtAsync {
+  /// Runs `future` on the thread of the event loop until it completes.
+  ///
+  /// The future is polled right after [`Event::NewEvents`], the first time in the next
+  /// iteration of the event loop, then whenever its waker is woken, from any thread. The future
+  /// doesn't need to be `Send`, so it can hold windows and other values of the main thread.
+  ///
+  /// The event loop only polls the futures: a future waiting on I/O or timers needs a reactor
+  /// that wakes it up, like the one of the library it comes from. The futures left when the
+  /// event loop is dropped are dropped with it.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The event loop is woken up with a thread message.
+  /// - **macOS / iOS:** The event loop is woken up with a `CFRunLoopSource`.
+  /// - **Linux:** The event loop is woken up through the glib main context.
+  ///
+  /// [`Event::NewEvents`]: crate::event::Event::NewEvents
+  fn spawn<F>(&self, future: F)
+  where
+    F: Future<Output = ()> + 'static;
+}
+
+impl<T> EventLoopExtAsync for EventLoopWindowTarget<T> {
+  #[inline]
+  fn spawn<F>(&self, future: F)
+  where
+    F: Future<Output = ()> + 'static,
+  {
+    self.executor.spawn(self, future)
+  }
+}
@@ -0,0 +1,73 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+  dpi::PhysicalPosition,
+  error::ExternalError,
+  event::{ElementState, MouseButton, MouseScrollDelta},
+  event_loop::EventLoopWindowTarget,
+  keyboard::KeyCode,
+};
+
+/// An input event synthesized with [`EventLoopExtInject::inject_input`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntheticInput {
+  /// Presses or releases the physical key `code`, mapped with the same tables as
+  /// [`KeyEvent::physical_key`] so that captured keys can be injected back.
+  ///
+  /// What the key types follows the keyboard layout that is currently active, like for a key
+  /// of the keyboard, see [`EventLoopWindowTarget::key_for_keycode`].
+  ///
+  /// [`KeyEvent::physical_key`]: crate::event::KeyEvent::physical_key
+  Key { code: KeyCode, state: ElementState },
+  /// Types `text`, whatever the keyboard layout.
+  Text(String),
+  /// Moves the cursor to `position`, in the coordinates of [`MonitorHandle::position`].
+  ///
+  /// [`MonitorHandle::position`]: crate::monitor::MonitorHandle::position
+  CursorMoved(PhysicalPosition<f64>),
+  /// Presses or releases a button of the mouse, where the cursor is.
+  MouseInput {
+    button: MouseButton,
+    state: ElementState,
+  },
+  /// Scrolls the wheel of the mouse, where the cursor is.
+  MouseWheel(MouseScrollDelta),
+}
+
+/// Additional methods on `EventLoopWindowTarget` to synthesize input events in the session of
+/// the user, as if they came from the keyboard and the mouse.
+pub trait EventLoopExtInject {
+  /// Synthesizes `input` in the session of the user.
+  ///
+  /// The input goes to whatever is under the cursor or has the focus, which may be another
+  /// application, and the events of the windows of this application follow once the OS
+  /// dispatched it.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Uses `SendInput`, which fails for the windows of processes with a higher
+  ///   integrity level. `MouseButton::Other` only supports the `X` buttons `1` and `2`, and
+  ///   `MouseScrollDelta::PixelDelta` is unsupported.
+  /// - **macOS:** Posts `CGEvent`s, which needs the accessibility permission.
+  /// - **Linux (X11):** Uses the `XTest` extension. `SyntheticInput::Text` only types the
+  ///   characters of the current layout, pressing Shift and AltGr for the characters of the
+  ///   upper levels, and `MouseScrollDelta::PixelDelta` is unsupported.
+  /// - **Linux (Wayland):** Unsupported. Neither `uinput`, which needs access to `/dev/uinput`,
+  ///   nor the remote desktop portal is used.
+  /// - **iOS / Android:** Unsupported.
+  /// - **headless:** The input is delivered to the windows of the synthetic desktop: the keys
+  ///   and text to the focused window, the text as an [`Ime::Commit`], and the mouse events to
+  ///   the window under the cursor.
+  ///
+  /// [`Ime::Commit`]: crate::event::Ime::Commit
+  fn inject_input(&self, input: SyntheticInput) -> Result<(), ExternalError>;
+}
+
+impl<T> EventLoopExtInject for EventLoopWindowTarget<T> {
+  #[inline]
+  fn inject_input(&self, input: SyntheticInput) -> Result<(), ExternalError> {
+    self.p.inject_input(input)
+  }
+}
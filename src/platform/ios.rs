@@ -2,7 +2,7 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(target_os = "ios")]
+#![cfg(all(target_os = "ios", not(tao_headless)))]
 
 use std::os::raw::c_void;
 
@@ -2,4 +2,4 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(target_os = "linux")]
+#![cfg(all(target_os = "linux", not(tao_headless)))]
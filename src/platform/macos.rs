@@ -2,16 +2,18 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(target_os = "macos")]
+#![cfg(all(target_os = "macos", not(tao_headless)))]
 
 use std::os::raw::c_void;
 
 use crate::{
-  dpi::{LogicalSize, Position},
-  event_loop::{EventLoop, EventLoopWindowTarget},
+  dpi::{LogicalSize, Position, Rect},
+  error::ExternalError,
+  event::Event,
+  event_loop::{EventLoop, EventLoopBuilder, EventLoopWindowTarget},
   monitor::MonitorHandle,
   platform_impl::{get_aux_state_mut, Parent},
-  window::{Window, WindowBuilder},
+  window::{Icon, Window, WindowBuilder},
 };
 
 use cocoa::appkit::{
@@ -74,8 +76,107 @@ pub trait WindowExtMacOS {
 
   /// Returns the window's tabbing identifier.
   fn tabbing_identifier(&self) -> String;
+
+  /// Sets the screen rectangle, such as the status item, that hiding the window zooms toward.
+  ///
+  /// This is the macOS name of [`Window::set_minimize_target`].
+  fn set_miniaturize_proxy_rect(&self, rect: Option<Rect>) -> Result<(), ExternalError>;
+
+  /// Presents `sheet` as a sheet attached to this window.
+  ///
+  /// `sheet` is an ordinary window, preferably undecorated, that is shown by this call. Both
+  /// windows keep receiving events while the sheet is presented. Once it's ended, this window
+  /// receives [`WindowEvent::SheetEnded`] with the response of [`end_sheet_with_response`]. The
+  /// sheet is ended with [`SHEET_RESPONSE_ABORT`] when either window is closed.
+  ///
+  /// If this window already presents a sheet, `sheet` is presented once that one ends.
+  ///
+  /// [`WindowEvent::SheetEnded`]: crate::event::WindowEvent::SheetEnded
+  /// [`end_sheet_with_response`]: WindowExtMacOS::end_sheet_with_response
+  fn begin_sheet(&self, sheet: &Window);
+
+  /// Ends `sheet`, presented with [`begin_sheet`](WindowExtMacOS::begin_sheet), with the
+  /// response [`SHEET_RESPONSE_STOP`].
+  fn end_sheet(&self, sheet: &Window);
+
+  /// Ends `sheet`, presented with [`begin_sheet`](WindowExtMacOS::begin_sheet), with a response
+  /// code chosen by the application.
+  fn end_sheet_with_response(&self, sheet: &Window, response: isize);
+
+  /// Backs the content view with a `CAMetalLayer` configured with `options`, and returns the
+  /// layer.
+  ///
+  /// The layer already backing the view is kept if it's a `CAMetalLayer`, so this can be called
+  /// again to change the options. The view and the layer are set up on the main thread, this
+  /// blocks until it's done when called from another thread.
+  ///
+  /// [`Window::ns_view`](WindowExtMacOS::ns_view) and the raw window handle are valid as soon as
+  /// the window is built, but the layer only exists once this returns. Call it before creating
+  /// the surface of a graphics crate: crates like `wgpu` render to the `CAMetalLayer` backing the
+  /// view instead of creating their own.
+  fn set_metal_layer_options(&self, options: MetalLayerOptions) -> *mut c_void;
+
+  /// Lets `provider` answer the accessibility methods of the `NSView` of the window, or stops
+  /// with `None`.
+  ///
+  /// This is the hook of accessibility adapters, see the [`accessibility`] module.
+  ///
+  /// ## Panics
+  ///
+  /// Panics when called from another thread than the main thread, as the provider is only used
+  /// there.
+  ///
+  /// [`accessibility`]: crate::accessibility
+  fn set_accessibility_provider(&self, provider: Option<Box<dyn AccessibilityProviderMacOS>>);
+
+  /// Lets `responder` see the events of the window before tao does.
+  ///
+  /// `responder` gets the `NSEvent` passed to a responder method of the `NSView` of the window,
+  /// like `keyDown:`, `flagsChanged:`, `mouseDown:`, `scrollWheel:` or `magnifyWithEvent:`, and
+  /// returns `true` for the events it handles, which the view doesn't handle then. The responders
+  /// are called in the order they were added, until one of them returns `true`.
+  ///
+  /// This is the way to see the events tao handles without passing them on, like the ones of
+  /// an IME or of a drag protocol, instead of replacing the class of the view, which breaks tao.
+  /// The events the `NSWindow` handles itself, like the ones of its title bar, never reach the
+  /// view.
+  ///
+  /// ## Panics
+  ///
+  /// Panics when called from another thread than the main thread, as the responders are only
+  /// used there.
+  fn add_view_responder(&self, responder: Box<dyn Fn(*mut c_void) -> bool>);
+
+  /// Removes the responders added with [`add_view_responder`](Self::add_view_responder).
+  ///
+  /// ## Panics
+  ///
+  /// Panics when called from another thread than the main thread.
+  fn clear_view_responders(&self);
 }
 
+/// The accessibility tree of a window, see [`WindowExtMacOS::set_accessibility_provider`].
+///
+/// The methods are called on the main thread by the `NSView` of the window. They return an
+/// autoreleased object, or null to let the view answer itself.
+pub trait AccessibilityProviderMacOS {
+  /// Returns the `NSArray` of `accessibilityChildren`, the elements of the root of the tree.
+  fn children(&self) -> *mut c_void;
+
+  /// Returns the element of `accessibilityFocusedUIElement`.
+  fn focused_element(&self) -> *mut c_void;
+
+  /// Returns the element of `accessibilityHitTest:` at `(x, y)`, in screen coordinates.
+  fn hit_test(&self, x: f64, y: f64) -> *mut c_void;
+}
+
+/// The response of a sheet ended with [`WindowExtMacOS::end_sheet`], `NSModalResponseStop`.
+pub const SHEET_RESPONSE_STOP: isize = -1000;
+
+/// The response of a sheet ended because its window or its parent was closed,
+/// `NSModalResponseAbort`.
+pub const SHEET_RESPONSE_ABORT: isize = -1001;
+
 impl WindowExtMacOS for Window {
   #[inline]
   fn ns_window(&self) -> *mut c_void {
@@ -141,6 +242,87 @@ impl WindowExtMacOS for Window {
   fn tabbing_identifier(&self) -> String {
     self.window.tabbing_identifier()
   }
+
+  #[inline]
+  fn set_miniaturize_proxy_rect(&self, rect: Option<Rect>) -> Result<(), ExternalError> {
+    self.window.set_minimize_target(rect)
+  }
+
+  #[inline]
+  fn begin_sheet(&self, sheet: &Window) {
+    self.window.begin_sheet(sheet)
+  }
+
+  #[inline]
+  fn end_sheet(&self, sheet: &Window) {
+    self.window.end_sheet(sheet)
+  }
+
+  #[inline]
+  fn end_sheet_with_response(&self, sheet: &Window, response: isize) {
+    self.window.end_sheet_with_response(sheet, response)
+  }
+
+  #[inline]
+  fn set_metal_layer_options(&self, options: MetalLayerOptions) -> *mut c_void {
+    self.window.set_metal_layer_options(options)
+  }
+
+  #[inline]
+  fn set_accessibility_provider(&self, provider: Option<Box<dyn AccessibilityProviderMacOS>>) {
+    self.window.set_accessibility_provider(provider)
+  }
+
+  #[inline]
+  fn add_view_responder(&self, responder: Box<dyn Fn(*mut c_void) -> bool>) {
+    self.window.add_view_responder(responder)
+  }
+
+  #[inline]
+  fn clear_view_responders(&self) {
+    self.window.clear_view_responders()
+  }
+}
+
+/// The configuration of the `CAMetalLayer` backing a window, see
+/// [`WindowExtMacOS::set_metal_layer_options`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetalLayerOptions {
+  /// Keeps the `contentsScale` of the layer at the scale factor of the window, including after
+  /// it moved to a monitor with another scale factor. Defaults to `true`.
+  pub contents_scale_follows_window: bool,
+  /// The `displaySyncEnabled` of the layer, whether presenting waits for the vertical blank.
+  /// Defaults to `true`, and is ignored before macOS 10.13.
+  pub display_sync: bool,
+  /// The `wantsExtendedDynamicRangeContent` of the layer. Defaults to `false`.
+  pub extended_dynamic_range: bool,
+}
+
+impl Default for MetalLayerOptions {
+  fn default() -> Self {
+    Self {
+      contents_scale_follows_window: true,
+      display_sync: true,
+      extended_dynamic_range: false,
+    }
+  }
+}
+
+/// Queues `event` to be delivered to the event handler, for native callbacks like the ones of the
+/// application delegate that run outside of it.
+///
+/// The event is delivered in order with the other pending events once the current iteration of
+/// the event loop ends, or right after `NewEvents(StartCause::Init)` when it's called before the
+/// event loop started. [`Event::UserEvent`]s are handed back, send them with an
+/// [`EventLoopProxy`](crate::event_loop::EventLoopProxy) instead.
+///
+/// # Panics
+///
+/// Panics if it's not called on the main thread.
+#[allow(clippy::result_large_err)]
+pub fn queue_event_from_delegate<T>(event: Event<'static, T>) -> Result<(), Event<'static, T>> {
+  crate::platform_impl::queue_event_from_delegate(event)
 }
 
 /// Corresponds to `NSApplicationActivationPolicy`.
@@ -161,6 +343,42 @@ impl Default for ActivationPolicy {
   }
 }
 
+/// An Apple event sent to the application, see
+/// [`EventLoopBuilderExtMacOS::with_apple_event_handler`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppleEvent {
+  /// The application is asked to open `urls`, e.g. for a URL scheme it registered.
+  ///
+  /// The URL of a `kAEGetURL` Apple event comes alone, and only if it's well-formed.
+  OpenUrls(Vec<url::Url>),
+  /// The application is reopened, e.g. by clicking its Dock icon.
+  Reopen {
+    /// Whether the application has visible windows.
+    has_visible_windows: bool,
+  },
+}
+
+/// What the application does when it's reopened, e.g. by clicking its Dock icon, see
+/// [`EventLoopWindowTargetExtMacOS::set_default_reopen_behavior`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReopenBehavior {
+  /// Leaves the windows as they are.
+  None,
+  /// Shows and focuses the window that was focused last, or another window if that one was
+  /// destroyed.
+  ShowLastFocusedWindow,
+  /// Shows every window, and focuses the one that was focused last.
+  ShowAllWindows,
+}
+
+impl Default for ReopenBehavior {
+  fn default() -> Self {
+    ReopenBehavior::None
+  }
+}
+
 impl From<ActivationPolicy> for NSApplicationActivationPolicy {
   fn from(act_pol: ActivationPolicy) -> Self {
     match act_pol {
@@ -335,6 +553,72 @@ impl<T> EventLoopExtMacOS for EventLoop<T> {
   }
 }
 
+/// The menus of the default menu bar, see [`EventLoopBuilderExtMacOS::with_default_menu_config`].
+///
+/// The application menu always ends with a "Quit" item, whose Command-Q key equivalent terminates
+/// the application.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DefaultMenuConfig {
+  /// The "About", "Hide", "Hide Others" and "Show All" items of the application menu.
+  pub app_menu: bool,
+  /// The "Edit" menu, with the standard undo, redo and pasteboard items and their key
+  /// equivalents.
+  pub edit_menu: bool,
+  /// The "Window" menu, with the "Minimize", "Zoom" and "Bring All to Front" items, followed by
+  /// the windows of the application.
+  pub window_menu: bool,
+  /// The "Services" submenu of the application menu.
+  pub services: bool,
+}
+
+impl Default for DefaultMenuConfig {
+  fn default() -> Self {
+    Self {
+      app_menu: true,
+      edit_menu: true,
+      window_menu: true,
+      services: true,
+    }
+  }
+}
+
+/// Additional methods on `EventLoopBuilder` that are specific to macOS.
+pub trait EventLoopBuilderExtMacOS {
+  /// Sets the menu bar of the application to a default one with the menus of `config` once it
+  /// has finished launching, before `NewEvents(StartCause::Init)`.
+  ///
+  /// Without it, the application has no menu bar until it sets one. Ignored by headless event
+  /// loops.
+  fn with_default_menu_config(&mut self, config: DefaultMenuConfig) -> &mut Self;
+  /// Sets the handler of the Apple events of the application, called on the main thread before
+  /// Tao handles them.
+  ///
+  /// Returning `false` from it stops Tao from handling the event: the URLs aren't delivered
+  /// through `Event::Opened` or `Event::OpenedUrl`, and the application isn't reopened as
+  /// [`EventLoopWindowTargetExtMacOS::set_default_reopen_behavior`] says.
+  fn with_apple_event_handler<F>(&mut self, handler: F) -> &mut Self
+  where
+    F: Fn(AppleEvent) -> bool + 'static;
+}
+
+impl<T> EventLoopBuilderExtMacOS for EventLoopBuilder<T> {
+  #[inline]
+  fn with_default_menu_config(&mut self, config: DefaultMenuConfig) -> &mut Self {
+    self.platform_specific.default_menu = Some(config);
+    self
+  }
+
+  #[inline]
+  fn with_apple_event_handler<F>(&mut self, handler: F) -> &mut Self
+  where
+    F: Fn(AppleEvent) -> bool + 'static,
+  {
+    self.platform_specific.apple_event_handler = Some(Box::new(handler));
+    self
+  }
+}
+
 /// Additional methods on `MonitorHandle` that are specific to MacOS.
 pub trait MonitorHandleExtMacOS {
   /// Returns the identifier of the monitor for Cocoa.
@@ -357,36 +641,71 @@ impl MonitorHandleExtMacOS for MonitorHandle {
 /// Additional methods on `EventLoopWindowTarget` that are specific to macOS.
 pub trait EventLoopWindowTargetExtMacOS {
   /// Hide the entire application. In most applications this is typically triggered with Command-H.
+  ///
+  /// The key window gets [`WindowEvent::Focused(false)`](crate::event::WindowEvent::Focused).
+  /// If the application hasn't finished launching yet, it is hidden as soon as it has.
   fn hide_application(&self);
-  /// Show the entire application.
+  /// Show the entire application, with the windows that were visible when it was hidden in the
+  /// same order.
+  ///
+  /// Before the application has finished launching, this cancels an earlier `hide_application`.
   fn show_application(&self);
   /// Hide the other applications. In most applications this is typically triggered with Command+Option-H.
+  ///
+  /// The other applications stay hidden until the user shows them. Requested before the
+  /// application has finished launching, they are hidden once it has.
   fn hide_other_applications(&self);
+  /// Activates the application, e.g. to bring its windows forward from a tray menu.
+  ///
+  /// With `ignoring_other_apps`, the application is activated even if another one is active.
+  /// Called before the event loop runs, the activation happens at launch, before
+  /// [`StartCause::Init`](crate::event::StartCause::Init) is delivered and right after the
+  /// one tao does by default.
+  fn activate(&self, ignoring_other_apps: bool);
   /// Sets the activation policy for the application. It is set to
   /// `NSApplicationActivationPolicyRegular` by default.
   ///
   /// To set the activation policy before the app starts running, see
   /// [`EventLoopExtMacOS::set_activation_policy`](crate::platform::macos::EventLoopExtMacOS::set_activation_policy).
   fn set_activation_policy_at_runtime(&self, activation_policy: ActivationPolicy);
+  /// Sets the application icon shown in the Dock and the application switcher, or restores the
+  /// icon of the bundle with `None`.
+  ///
+  /// Every size of an icon created with [`Icon::from_rgba_set`] is kept, and the system picks
+  /// the best one for the display.
+  fn set_application_icon(&self, icon: Option<Icon>);
+  /// Sets what the application does with its windows when it's reopened, e.g. by clicking its
+  /// Dock icon. It's [`ReopenBehavior::None`] by default.
+  ///
+  /// Hidden and minimized windows are shown again, and the application is activated. The
+  /// handler set with [`EventLoopBuilderExtMacOS::with_apple_event_handler`] runs first, and
+  /// returning `false` from it skips this.
+  fn set_default_reopen_behavior(&self, behavior: ReopenBehavior);
+  /// Makes the application provide the service whose `NSMessage` is `message` in the Services
+  /// menu of the other applications, and delivers its requests as
+  /// [`Event::ServiceRequested`](crate::event::Event::ServiceRequested).
+  ///
+  /// The service must also be declared in the `NSServices` of the `Info.plist` of the
+  /// application, with the pasteboard types it takes. The request doesn't wait for the event
+  /// handler, so a service can't return a result to the other application.
+  fn register_service(&self, message: &str);
 }
 
 impl<T> EventLoopWindowTargetExtMacOS for EventLoopWindowTarget<T> {
   fn hide_application(&self) {
-    let cls = objc::runtime::Class::get("NSApplication").unwrap();
-    let app: cocoa::base::id = unsafe { msg_send![cls, sharedApplication] };
-    unsafe { msg_send![app, hide: 0] }
+    self.p.hide_application()
   }
 
   fn show_application(&self) {
-    let cls = objc::runtime::Class::get("NSApplication").unwrap();
-    let app: cocoa::base::id = unsafe { msg_send![cls, sharedApplication] };
-    unsafe { msg_send![app, unhide: 0] }
+    self.p.show_application()
   }
 
   fn hide_other_applications(&self) {
-    let cls = objc::runtime::Class::get("NSApplication").unwrap();
-    let app: cocoa::base::id = unsafe { msg_send![cls, sharedApplication] };
-    unsafe { msg_send![app, hideOtherApplications: 0] }
+    self.p.hide_other_applications()
+  }
+
+  fn activate(&self, ignoring_other_apps: bool) {
+    self.p.activate(ignoring_other_apps)
   }
 
   fn set_activation_policy_at_runtime(&self, activation_policy: ActivationPolicy) {
@@ -395,4 +714,22 @@ impl<T> EventLoopWindowTargetExtMacOS for EventLoopWindowTarget<T> {
     let ns_activation_policy: NSApplicationActivationPolicy = activation_policy.into();
     unsafe { msg_send![app, setActivationPolicy: ns_activation_policy] }
   }
+
+  fn set_application_icon(&self, icon: Option<Icon>) {
+    let cls = objc::runtime::Class::get("NSApplication").unwrap();
+    let app: cocoa::base::id = unsafe { msg_send![cls, sharedApplication] };
+    let image = match icon {
+      Some(icon) => unsafe { icon.inner.to_ns_image() },
+      None => cocoa::base::nil,
+    };
+    unsafe { msg_send![app, setApplicationIconImage: image] }
+  }
+
+  fn set_default_reopen_behavior(&self, behavior: ReopenBehavior) {
+    self.p.set_default_reopen_behavior(behavior)
+  }
+
+  fn register_service(&self, message: &str) {
+    self.p.register_service(message)
+  }
 }
@@ -13,16 +13,24 @@
 //!  - `linux`
 //!  - `windows`
 //!
-//! And the following platform-specific module:
+//! And the following platform-specific modules:
 //!
 //! - `run_return` (available on `windows`, `unix`, `macos`, and `android`)
+//! - `pump_events` (available on `windows`, `unix`, `macos`, and `android`)
+//! - `executor` (available on all platforms)
+//! - `inject` (available on all platforms)
 //!
 //! However only the module corresponding to the platform you're compiling to will be available.
+//! With the synthetic backend of `tao-headless`, only `run_return`, `pump_events`, `executor` and `inject` are
+//! available.
 
 pub mod android;
+pub mod executor;
+pub mod inject;
 pub mod ios;
 pub mod linux;
 pub mod macos;
+pub mod pump_events;
 pub mod run_return;
 pub mod unix;
 pub mod windows;
@@ -0,0 +1,187 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(any(not(target_os = "ios"), tao_headless))]
+
+use std::time::{Duration, Instant};
+
+use crate::{
+  event::{Event, StartCause},
+  event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
+  platform::run_return::EventLoopExtRunReturn,
+};
+
+/// The result of [`EventLoopExtPumpEvents::pump_events`].
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PumpStatus {
+  /// The event loop can be pumped again.
+  Continue,
+  /// The event loop exited with this code, after delivering `Event::LoopDestroyed`.
+  Exit(i32),
+}
+
+/// Additional methods on `EventLoop` to drive it from the loop of a host application.
+pub trait EventLoopExtPumpEvents {
+  /// A type provided by the user that can be passed through `Event::UserEvent`.
+  type UserEvent;
+
+  /// Runs a single iteration of the `tao` event loop and returns, so that another loop can
+  /// drive it, like the main loop of a game or the one of the application embedding `tao`.
+  ///
+  /// The iteration dispatches the pending events. Without any, it waits for one until `timeout`
+  /// elapses, or as long as needed with `None`. The `ControlFlow` of the event handler is kept
+  /// between calls and can only shorten that wait: `Poll` makes it return right away, and
+  /// `WaitUntil` returns at the earliest of both deadlines.
+  ///
+  /// The first call delivers `StartCause::Init`, the next iterations start with the cause of their
+  /// wake-up, or `StartCause::Poll` for the events pending since the last call. Once the handler
+  /// exits the event loop, `Event::LoopDestroyed` is delivered and [`PumpStatus::Exit`] is
+  /// returned, and the next call starts over with `StartCause::Init`.
+  ///
+  /// # Caveats
+  /// This is built on [`run_return`](crate::platform::run_return::EventLoopExtRunReturn::run_return)
+  /// and has the same caveats: it doesn't return on Windows or macOS while a window is getting
+  /// resized, and the event loop of the OS is set up again on each call, which costs more than
+  /// an iteration of `run`.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS:** Unsupported, the event loop of the OS never returns.
+  fn pump_events<F>(&mut self, timeout: Option<Duration>, event_handler: F) -> PumpStatus
+  where
+    F: FnMut(Event<'_, Self::UserEvent>, &EventLoopWindowTarget<Self::UserEvent>, &mut ControlFlow);
+}
+
+impl<T> EventLoopExtPumpEvents for EventLoop<T> {
+  type UserEvent = T;
+
+  fn pump_events<F>(&mut self, timeout: Option<Duration>, mut event_handler: F) -> PumpStatus
+  where
+    F: FnMut(Event<'_, Self::UserEvent>, &EventLoopWindowTarget<Self::UserEvent>, &mut ControlFlow),
+  {
+    let mut pump = Pump {
+      started: self.pumped_control_flow.is_some(),
+      control_flow: self.pumped_control_flow.unwrap_or_default(),
+      timeout,
+      phase: Phase::Pending,
+      held_new_events: false,
+      status: PumpStatus::Continue,
+    };
+    self.run_return(|event, target, control_flow| {
+      pump.handle(event, target, control_flow, &mut event_handler)
+    });
+    self.pumped_control_flow = match pump.status {
+      PumpStatus::Continue => Some(pump.control_flow),
+      PumpStatus::Exit(_) => None,
+    };
+    pump.status
+  }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Phase {
+  /// The iteration started by `run_return`, with the events pending since the last call.
+  Pending,
+  /// The iteration after waiting for an event, when there weren't any pending.
+  Waited,
+  /// The iteration is over and `run_return` is exiting.
+  Done,
+}
+
+/// The state of a call to `pump_events`, which turns the iterations of `run_return` into one
+/// iteration for the event handler.
+struct Pump {
+  started: bool,
+  /// The `ControlFlow` of the event handler, the one of `run_return` ends the call.
+  control_flow: ControlFlow,
+  timeout: Option<Duration>,
+  phase: Phase,
+  /// `Event::NewEvents` of the iteration of pending events, only delivered with one of them.
+  held_new_events: bool,
+  status: PumpStatus,
+}
+
+impl Pump {
+  fn handle<T, F>(
+    &mut self,
+    event: Event<'_, T>,
+    target: &EventLoopWindowTarget<T>,
+    control_flow: &mut ControlFlow,
+    handler: &mut F,
+  ) where
+    F: FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+  {
+    match (self.phase, event) {
+      (Phase::Done, Event::LoopDestroyed) => {
+        if let PumpStatus::Exit(_) = self.status {
+          handler(Event::LoopDestroyed, target, &mut self.control_flow);
+        }
+        return;
+      }
+      (Phase::Done, _) => return,
+      (Phase::Pending, Event::NewEvents(_)) if self.started => {
+        self.held_new_events = true;
+        return;
+      }
+      // Nothing was pending, so this iteration is left out and the next one waits.
+      (Phase::Pending, Event::MainEventsCleared) if self.held_new_events && self.waits() => return,
+      (Phase::Pending, Event::RedrawEventsCleared) if self.held_new_events && self.waits() => {
+        self.wait(control_flow);
+        return;
+      }
+      (_, event) => {
+        if self.held_new_events {
+          self.held_new_events = false;
+          handler(
+            Event::NewEvents(StartCause::Poll),
+            target,
+            &mut self.control_flow,
+          );
+        }
+        let redraw_events_cleared = matches!(event, Event::RedrawEventsCleared);
+        handler(event, target, &mut self.control_flow);
+        // The rest of the iteration is still delivered after an exit.
+        let exit = target.exit.get().or(match self.control_flow {
+          ControlFlow::ExitWithCode(code) => Some(code),
+          _ => None,
+        });
+        if let Some(code) = exit {
+          self.status = PumpStatus::Exit(code);
+        }
+        if redraw_events_cleared {
+          self.phase = Phase::Done;
+        }
+      }
+    }
+    *control_flow = match (self.phase, self.status) {
+      (_, PumpStatus::Exit(code)) => ControlFlow::ExitWithCode(code),
+      (Phase::Done, PumpStatus::Continue) => ControlFlow::Exit,
+      _ => ControlFlow::Poll,
+    };
+  }
+
+  /// Whether an iteration without any events is followed by a wait, or delivered.
+  fn waits(&self) -> bool {
+    self.timeout != Some(Duration::ZERO) && self.control_flow != ControlFlow::Poll
+  }
+
+  /// Waits for an event after an iteration without any, within the timeout and the
+  /// `ControlFlow` of the event handler.
+  fn wait(&mut self, control_flow: &mut ControlFlow) {
+    self.held_new_events = false;
+    self.phase = Phase::Waited;
+    let deadline = self
+      .timeout
+      .and_then(|timeout| Instant::now().checked_add(timeout));
+    *control_flow = match (self.control_flow, deadline) {
+      (ControlFlow::WaitUntil(resume), Some(deadline)) => {
+        ControlFlow::WaitUntil(resume.min(deadline))
+      }
+      (ControlFlow::WaitUntil(resume), None) => ControlFlow::WaitUntil(resume),
+      (_, Some(deadline)) => ControlFlow::WaitUntil(deadline),
+      (_, None) => ControlFlow::Wait,
+    };
+  }
+}
@@ -2,7 +2,7 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(not(target_os = "ios"))]
+#![cfg(any(not(target_os = "ios"), tao_headless))]
 
 use crate::{
   event::Event,
@@ -45,6 +45,21 @@ impl<T> EventLoopExtRunReturn for EventLoop<T> {
   where
     F: FnMut(Event<'_, Self::UserEvent>, &EventLoopWindowTarget<Self::UserEvent>, &mut ControlFlow),
   {
+    #[cfg(feature = "tracing")]
+    let event_handler = crate::instrumentation::wrap_handler(event_handler);
+    let event_handler =
+      crate::motion_coalescing::wrap_handler(self.motion_coalescing, event_handler);
+    let event_handler = crate::executor::wrap_handler(event_handler);
+    let event_handler = crate::capture::wrap_handler(event_handler);
+    let event_handler =
+      crate::event_loop::wrap_user_event_handler(self.user_events.clone(), event_handler);
+    #[cfg(feature = "ffi-bridge")]
+    let event_handler =
+      crate::ffi_bridge::wrap_handler(self.ffi_bridge.clone(), false, event_handler);
+    let event_handler = crate::event_loop::wrap_exit_handler(event_handler);
+    let event_handler = crate::window_registry::wrap_handler(event_handler);
+    // A previous run may have exited.
+    self.exit.set(None);
     self.event_loop.run_return(event_handler)
   }
 }
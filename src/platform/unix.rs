@@ -2,15 +2,22 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(any(
-  target_os = "linux",
-  target_os = "dragonfly",
-  target_os = "freebsd",
-  target_os = "netbsd",
-  target_os = "openbsd"
+#![cfg(all(
+  any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  ),
+  not(tao_headless)
 ))]
 
-use std::{os::raw::c_int, sync::Arc};
+use std::{
+  ffi::c_void,
+  os::{raw::c_int, unix::io::RawFd},
+  sync::Arc,
+};
 
 // XConnection utilities
 #[doc(hidden)]
@@ -18,13 +25,26 @@ pub use crate::platform_impl::x11;
 
 pub use crate::platform_impl::EventLoop as UnixEventLoop;
 use crate::{
+  error::ExternalError,
+  event::DeviceId,
   event_loop::{EventLoopBuilder, EventLoopWindowTarget},
+  monitor::MonitorHandle,
   platform_impl::{x11::xdisplay::XError, Parent},
   window::{Window, WindowBuilder},
 };
 
 use self::x11::xdisplay::XConnection;
 
+/// A window of another application to embed a window into, see
+/// [`WindowBuilderExtUnix::with_embed_parent`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EmbedParent {
+  /// The id of an X11 window, like the one an XEmbed host gives to its clients, or
+  /// `XSCREENSAVER_WINDOW` for a screensaver preview.
+  Xlib(u64),
+}
+
 /// Additional methods on `EventLoop` that are specific to Unix.
 pub trait EventLoopBuilderExtUnix {
   /// Whether to allow the event loop to be created off of the main thread.
@@ -37,7 +57,32 @@ pub trait EventLoopBuilderExtUnix {
   /// Note that any `Window` created on the new thread will be destroyed when the thread
   /// terminates. Attempting to use a `Window` after its parent thread terminates has
   /// unspecified, although explicitly not undefined, behavior.
+  ///
+  /// GTK only runs on the thread it was initialized on: every event loop of the process must be
+  /// created on the same thread, creating one on another thread panics.
+  /// [`EventLoopBuilder::try_build`] returns an error instead.
   fn with_any_thread(&mut self, any_thread: bool) -> &mut Self;
+
+  /// Sets the app id that windows use when they don't set one with
+  /// [`WindowBuilderExtUnix::with_app_id`].
+  ///
+  /// It is also used for the Unity launcher entry when [`ProgressBarState::unity_uri`] is `None`,
+  /// as `application://{app_id}.desktop`.
+  ///
+  /// [`ProgressBarState::unity_uri`]: crate::window::ProgressBarState::unity_uri
+  fn with_default_app_id(&mut self, app_id: &str) -> &mut Self;
+
+  /// Forces the integer scale GDK renders the windows at, overriding the `GDK_SCALE`
+  /// environment variable and the window scaling factor of the desktop.
+  ///
+  /// `1` renders one window pixel per screen pixel, e.g. to show pixel content 1:1. The scale
+  /// then is the [`Window::scale_factor`] of every window, text keeps following
+  /// [`WindowExtUnix::text_scale_factor`].
+  ///
+  /// Only supported on X11, on Wayland the compositor sets the scale of each output.
+  ///
+  /// [`Window::scale_factor`]: crate::window::Window::scale_factor
+  fn with_gdk_scale(&mut self, scale: i32) -> &mut Self;
 }
 
 impl<T> EventLoopBuilderExtUnix for EventLoopBuilder<T> {
@@ -46,6 +91,18 @@ impl<T> EventLoopBuilderExtUnix for EventLoopBuilder<T> {
     self.platform_specific.any_thread = any_thread;
     self
   }
+
+  #[inline]
+  fn with_default_app_id(&mut self, app_id: &str) -> &mut Self {
+    self.platform_specific.default_app_id = Some(app_id.into());
+    self
+  }
+
+  #[inline]
+  fn with_gdk_scale(&mut self, scale: i32) -> &mut Self {
+    self.platform_specific.gdk_scale = Some(scale);
+    self
+  }
 }
 
 /// Additional methods on `Window` that are specific to Unix.
@@ -58,7 +115,70 @@ pub trait WindowExtUnix {
   fn default_vbox(&self) -> Option<&gtk::Box>;
 
   /// Whether to show the window icon in the taskbar or not.
+  ///
+  /// Same as [`Window::set_skip_taskbar`], ignoring the error on Wayland.
   fn set_skip_taskbar(&self, skip: bool);
+
+  /// Returns the integer scale GDK renders the window at, which is its
+  /// [`Window::scale_factor`](crate::window::Window::scale_factor).
+  ///
+  /// It's the `GDK_SCALE` environment variable when it's set. Otherwise it's the window scaling
+  /// factor of the desktop on X11, and the scale of the output the window is on on Wayland.
+  ///
+  /// Fractional scales of Wayland, like `1.5`, are rounded up: GTK 3 only renders at integer
+  /// scales, and the compositor scales the window down.
+  fn gdk_scale(&self) -> i32;
+
+  /// Returns the factor GTK scales text by on top of [`gdk_scale`](Self::gdk_scale), like
+  /// `1.25` when the text scaling factor of the desktop is `1.25`.
+  ///
+  /// It's computed from the first of these that is set, multiplied by the `GDK_DPI_SCALE`
+  /// environment variable:
+  ///
+  /// - The `gtk-xft-dpi` GTK setting, which the settings daemon sets from the text scaling factor
+  ///   of the desktop.
+  /// - On X11, the `Xft.dpi` X resource, usually set with `xrdb`.
+  /// - 96 DPI, a factor of `1.0`.
+  ///
+  /// Changes of the setting are reported with
+  /// [`WindowEvent::TextScaleFactorChanged`](crate::event::WindowEvent::TextScaleFactorChanged).
+  fn text_scale_factor(&self) -> f64;
+
+  /// Lets `hook` see the events of the window before tao does, or stops with `None`.
+  ///
+  /// `hook` is called by the first handler of the `event` signal of the
+  /// [`gtk_window`](Self::gtk_window), and returns `true` for the events it handles, which
+  /// neither tao nor the signals of the specific events, like `button-press-event`, see then.
+  ///
+  /// This is the way to see the events tao handles without passing them on, like the ones of
+  /// an IME or of a drag protocol, as the handlers connected to the window after its creation
+  /// run after the ones of tao.
+  ///
+  /// A hook is only set on the thread of the event loop, as it isn't `Send`, and is ignored with
+  /// a warning elsewhere. It takes over from the previous one once the event loop handles the
+  /// request.
+  fn set_event_hook(
+    &self,
+    hook: Option<Box<dyn Fn(&gtk::ApplicationWindow, &gtk::gdk::Event) -> bool>>,
+  );
+
+  /// Returns the id of the X11 window, for another application to embed it with XEmbed, or
+  /// `None` on Wayland.
+  ///
+  /// Hidden windows are realized when they're created, so they have an id too.
+  fn xid(&self) -> Option<u64>;
+
+  /// Embeds the window into `parent`, like [`WindowBuilderExtUnix::with_embed_parent`], or makes
+  /// it a top-level window again with `None`.
+  ///
+  /// The event loop embeds the window, a parent that doesn't exist is only logged. With `None`,
+  /// the decorations and the taskbar and pager hints the window had before it was embedded are
+  /// given back.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Wayland:** Returns an [`ExternalError::NotSupported`].
+  fn set_embed_parent(&self, parent: Option<EmbedParent>) -> Result<(), ExternalError>;
 }
 
 impl WindowExtUnix for Window {
@@ -71,12 +191,37 @@ impl WindowExtUnix for Window {
   }
 
   fn set_skip_taskbar(&self, skip: bool) {
-    self.window.set_skip_taskbar(skip);
+    let _ = self.window.set_skip_taskbar(skip);
+  }
+
+  fn gdk_scale(&self) -> i32 {
+    self.window.gdk_scale()
+  }
+
+  fn text_scale_factor(&self) -> f64 {
+    self.window.text_scale_factor()
+  }
+
+  fn set_event_hook(
+    &self,
+    hook: Option<Box<dyn Fn(&gtk::ApplicationWindow, &gtk::gdk::Event) -> bool>>,
+  ) {
+    self.window.set_event_hook(hook.map(Into::into))
+  }
+
+  fn xid(&self) -> Option<u64> {
+    self.window.xid()
+  }
+
+  fn set_embed_parent(&self, parent: Option<EmbedParent>) -> Result<(), ExternalError> {
+    self.window.set_embed_parent(parent)
   }
 }
 
 pub trait WindowBuilderExtUnix {
   /// Whether to create the window icon with the taskbar icon or not.
+  ///
+  /// Same as [`WindowBuilder::with_skip_taskbar`].
   fn with_skip_taskbar(self, skip: bool) -> WindowBuilder;
   /// Set this window as a transient dialog for `parent`
   /// <https://gtk-rs.org/gtk3-rs/stable/latest/docs/gdk/struct.Window.html#method.set_transient_for>
@@ -116,11 +261,44 @@ pub trait WindowBuilderExtUnix {
   /// Whether to create a vertical `gtk::Box` and add it as the sole child of this window.
   /// Created by default.
   fn with_default_vbox(self, add: bool) -> WindowBuilder;
+
+  /// Sets the id desktop environments use to match the window with its `.desktop` file, which
+  /// should be named `{app_id}.desktop`.
+  ///
+  /// It is the app id on Wayland, and both the class and the instance of `WM_CLASS` on X11 unless
+  /// [`WindowBuilderExtUnix::with_x11_instance`] is used. It is also used for the Unity launcher
+  /// entry when [`ProgressBarState::unity_uri`] is `None`.
+  ///
+  /// Defaults to the app id set with [`EventLoopBuilderExtUnix::with_default_app_id`], or to one
+  /// derived from the binary name.
+  ///
+  /// [`ProgressBarState::unity_uri`]: crate::window::ProgressBarState::unity_uri
+  fn with_app_id(self, app_id: &str) -> WindowBuilder;
+
+  /// Sets the instance of `WM_CLASS` on X11, when it should differ from the app id.
+  fn with_x11_instance(self, instance: &str) -> WindowBuilder;
+
+  /// Creates the window inside `parent`, a window of another application, like a plugin panel
+  /// of its host or a screensaver preview, instead of a top-level window.
+  ///
+  /// The window fills `parent`, without decorations, and is resized with it. Its application
+  /// gives it the focus with the XEmbed protocol, reported with
+  /// [`WindowEvent::Focused`](crate::event::WindowEvent::Focused). The window manager doesn't
+  /// manage it, so the attributes it applies, like the maximized state, have no effect.
+  ///
+  /// Replaces [`WindowBuilderExtUnix::with_transient_for`]. Building the window fails if
+  /// `parent` doesn't exist.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Wayland:** Unsupported, building the window fails. GTK 3 can't create a window inside
+  ///   the surface of another client.
+  fn with_embed_parent(self, parent: EmbedParent) -> WindowBuilder;
 }
 
 impl WindowBuilderExtUnix for WindowBuilder {
   fn with_skip_taskbar(mut self, skip: bool) -> WindowBuilder {
-    self.platform_specific.skip_taskbar = skip;
+    self.window.skip_taskbar = skip;
     self
   }
 
@@ -159,6 +337,21 @@ impl WindowBuilderExtUnix for WindowBuilder {
     self.platform_specific.default_vbox = add;
     self
   }
+
+  fn with_app_id(mut self, app_id: &str) -> WindowBuilder {
+    self.platform_specific.app_id = Some(app_id.into());
+    self
+  }
+
+  fn with_x11_instance(mut self, instance: &str) -> WindowBuilder {
+    self.platform_specific.x11_instance = Some(instance.into());
+    self
+  }
+
+  fn with_embed_parent(mut self, parent: EmbedParent) -> WindowBuilder {
+    self.platform_specific.parent = Parent::EmbedIn(parent);
+    self
+  }
 }
 
 /// Additional methods on `EventLoopWindowTarget` that are specific to Unix.
@@ -171,13 +364,30 @@ pub trait EventLoopWindowTargetExtUnix {
 
   fn xlib_xconnection(&self) -> Option<Arc<XConnection>>;
 
-  // /// Returns a pointer to the `wl_display` object of wayland that is used by this
-  // /// `EventLoopWindowTarget`.
-  // ///
-  // /// Returns `None` if the `EventLoop` doesn't use wayland (if it uses xlib for example).
-  // ///
-  // /// The pointer will become invalid when the winit `EventLoop` is destroyed.
-  // fn wayland_display(&self) -> Option<*mut raw::c_void>;
+  /// Watches `fd` on the event loop, and emits [`Event::FdReady`] with `token` whenever it meets
+  /// `interest`, for example `IOCondition::IN` for readability.
+  ///
+  /// The watch stays active until the returned `SourceId` is passed to
+  /// [`gtk::glib::source::SourceId::remove`]. The file descriptor must stay open until then.
+  ///
+  /// [`Event::FdReady`]: crate::event::Event::FdReady
+  fn add_fd_watch(
+    &self,
+    fd: RawFd,
+    interest: gtk::glib::IOCondition,
+    token: u64,
+  ) -> gtk::glib::SourceId;
+
+  /// Returns a pointer to the `wl_display` object of Wayland that is used by this
+  /// `EventLoopWindowTarget`.
+  ///
+  /// Returns `None` if the `EventLoop` doesn't use Wayland, e.g. when it uses X11.
+  ///
+  /// The pointer becomes invalid when the `EventLoop` is destroyed.
+  ///
+  /// The `wl_surface` of a window is available through its raw window handle. Its
+  /// `xdg_toplevel` isn't: GDK 3 doesn't expose it.
+  fn wayland_display(&self) -> Option<*mut c_void>;
 }
 
 impl<T> EventLoopWindowTargetExtUnix for EventLoopWindowTarget<T> {
@@ -204,16 +414,71 @@ impl<T> EventLoopWindowTargetExtUnix for EventLoopWindowTarget<T> {
     }
   }
 
-  // #[inline]
-  // fn wayland_display(&self) -> Option<*mut raw::c_void> {
-  //     match self.p {
-  //         LinuxEventLoopWindowTarget::Wayland(ref p) => {
-  //             Some(p.display().get_display_ptr() as *mut _)
-  //         }
-  //         #[cfg(feature = "x11")]
-  //         _ => None,
-  //     }
-  // }
+  #[inline]
+  fn add_fd_watch(
+    &self,
+    fd: RawFd,
+    interest: gtk::glib::IOCondition,
+    token: u64,
+  ) -> gtk::glib::SourceId {
+    self.p.add_fd_watch(fd, interest, token)
+  }
+
+  #[inline]
+  fn wayland_display(&self) -> Option<*mut c_void> {
+    self.p.wl_display()
+  }
+}
+
+/// Additional methods on `MonitorHandle` that are specific to Unix.
+pub trait MonitorHandleExtUnix {
+  /// Returns the `gdk::Monitor` from gtk crate that backs this monitor.
+  fn gdk_monitor(&self) -> &gtk::gdk::Monitor;
+
+  /// Returns a pointer to the `wl_output` object of Wayland of this monitor, e.g. to place a
+  /// layer shell surface on it.
+  ///
+  /// Returns `None` if the monitor isn't a Wayland one. The pointer becomes invalid when the
+  /// monitor is disconnected.
+  ///
+  /// tao doesn't create layer shell surfaces itself, that needs the `gtk-layer-shell` library.
+  fn wayland_output(&self) -> Option<*mut c_void>;
+}
+
+impl MonitorHandleExtUnix for MonitorHandle {
+  #[inline]
+  fn gdk_monitor(&self) -> &gtk::gdk::Monitor {
+    &self.inner.monitor
+  }
+
+  #[inline]
+  fn wayland_output(&self) -> Option<*mut c_void> {
+    self.inner.wl_output()
+  }
+}
+
+/// Additional methods on `DeviceId` that are specific to Unix.
+///
+/// The input of every seat, a Wayland seat or an X11 MPX master pointer and keyboard pair, is
+/// reported with a distinct `DeviceId`, which stays the same for the lifetime of the process.
+/// The raw [`DeviceEvent`]s and the input without a seat share a dummy `DeviceId`.
+///
+/// [`DeviceEvent`]: crate::event::DeviceEvent
+pub trait DeviceIdExtUnix {
+  /// Returns the name of the seat of the device, `None` for the dummy `DeviceId`.
+  ///
+  /// This is the name GDK gives to the master pointer of the seat, or to its keyboard when it
+  /// has no pointer, like `Virtual core pointer` on X11. On Wayland, every seat is named
+  /// `Core Pointer` or `Core Keyboard` whatever its `wl_seat` name, so only the `DeviceId` tells
+  /// two seats apart.
+  fn seat_name(&self) -> Option<String>;
+}
+
+impl DeviceIdExtUnix for DeviceId {
+  #[inline]
+  fn seat_name(&self) -> Option<String> {
+    self.0.seat_name()
+  }
 }
 
 unsafe extern "C" fn x_error_callback(
@@ -2,23 +2,73 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#![cfg(target_os = "windows")]
+#![cfg(all(target_os = "windows", not(tao_headless)))]
 
 use std::path::Path;
 
 use crate::{
-  dpi::PhysicalSize,
+  dpi::{PhysicalSize, Rect},
+  error::ExternalError,
   event::DeviceId,
   event_loop::EventLoopBuilder,
   monitor::MonitorHandle,
   platform_impl::{Parent, WinIcon},
   window::{BadIcon, Icon, Theme, Window, WindowBuilder},
 };
-use windows::Win32::UI::Input::KeyboardAndMouse::*;
-
 pub type HWND = isize;
 pub type HMENU = isize;
 
+/// The DPI awareness of the process, see [`EventLoopBuilderExtWindows::with_dpi_awareness`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DpiAwareness {
+  /// Windows follow the DPI of the monitor they are on, emitting
+  /// [`WindowEvent::ScaleFactorChanged`] when it changes, and their title bar and menus are
+  /// scaled as well.
+  ///
+  /// Falls back to [`PerMonitor`](Self::PerMonitor) before Windows 10 Creators Update (1703).
+  ///
+  /// [`WindowEvent::ScaleFactorChanged`]: crate::event::WindowEvent::ScaleFactorChanged
+  PerMonitorV2,
+  /// Windows follow the DPI of the monitor they are on, their title bar and menus keep the size
+  /// they have at the DPI of the primary monitor.
+  PerMonitor,
+  /// Windows keep the DPI the primary monitor had at login, and the system stretches them on
+  /// monitors of another DPI.
+  System,
+  /// The awareness is left as is, unaware unless the manifest declares it. Windows then always
+  /// have a scale factor of `1.0`, and the system stretches them on monitors above 96 DPI.
+  Unaware,
+}
+
+impl Default for DpiAwareness {
+  fn default() -> Self {
+    Self::PerMonitorV2
+  }
+}
+
+/// What a drop into a window does with its data, see [`WindowExtWindows::set_drop_effect`].
+///
+/// The source of the drag shows it with the cursor, and gets it back once the data is dropped.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DropEffect {
+  /// The data can't be dropped.
+  None,
+  /// The data is copied. The source keeps it.
+  Copy,
+  /// The data is moved. The source should remove it.
+  Move,
+  /// The data is linked to, like with a shortcut to a file.
+  Link,
+}
+
+impl Default for DropEffect {
+  fn default() -> Self {
+    Self::Copy
+  }
+}
+
 /// Additional methods on `EventLoop` that are specific to Windows.
 pub trait EventLoopBuilderExtWindows {
   /// Whether to allow the event loop to be created off of the main thread.
@@ -31,6 +81,13 @@ pub trait EventLoopBuilderExtWindows {
   /// Note that any `Window` created on the new thread will be destroyed when the thread
   /// terminates. Attempting to use a `Window` after its parent thread terminates has
   /// unspecified, although explicitly not undefined, behavior.
+  ///
+  /// # Multiple event loops
+  ///
+  /// Each thread has its own message queue, so an event loop can run on each thread along with
+  /// the windows created from it, e.g. for helper windows owned by a service thread. Only the
+  /// first event loop receives the `DeviceEvent`s, since Windows sends the raw input of the
+  /// process to a single window, and `set_device_event_filter` fails on the others.
   fn with_any_thread(&mut self, any_thread: bool) -> &mut Self;
 
   /// Whether to enable process-wide DPI awareness.
@@ -54,8 +111,17 @@ pub trait EventLoopBuilderExtWindows {
   /// let event_loop = builder.build();
   /// # }
   /// ```
+  ///
+  /// Same as [`with_dpi_awareness`](Self::with_dpi_awareness) with
+  /// [`DpiAwareness::PerMonitorV2`] or [`DpiAwareness::Unaware`].
   fn with_dpi_aware(&mut self, dpi_aware: bool) -> &mut Self;
 
+  /// Sets the process-wide DPI awareness, [`DpiAwareness::PerMonitorV2`] by default.
+  ///
+  /// It is set when the event loop is built, and only once per process. It has no effect when
+  /// the awareness is declared in the manifest of the application.
+  fn with_dpi_awareness(&mut self, awareness: DpiAwareness) -> &mut Self;
+
   /// A callback to be executed before dispatching a win32 message to the window procedure.
   /// Return true to disable tao's internal message dispatching.
   ///
@@ -110,7 +176,17 @@ impl<T> EventLoopBuilderExtWindows for EventLoopBuilder<T> {
 
   #[inline]
   fn with_dpi_aware(&mut self, dpi_aware: bool) -> &mut Self {
-    self.platform_specific.dpi_aware = dpi_aware;
+    self.platform_specific.dpi_awareness = if dpi_aware {
+      DpiAwareness::PerMonitorV2
+    } else {
+      DpiAwareness::Unaware
+    };
+    self
+  }
+
+  #[inline]
+  fn with_dpi_awareness(&mut self, awareness: DpiAwareness) -> &mut Self {
+    self.platform_specific.dpi_awareness = awareness;
     self
   }
 
@@ -172,6 +248,8 @@ pub trait WindowExtWindows {
   fn begin_resize_drag(&self, edge: isize, button: u32, x: i32, y: i32);
 
   /// Whether to show the window icon in the taskbar or not.
+  ///
+  /// Same as [`Window::set_skip_taskbar`], ignoring the error.
   fn set_skip_taskbar(&self, skip: bool);
 
   /// Shows or hides the background drop shadow for undecorated windows.
@@ -183,6 +261,54 @@ pub trait WindowExtWindows {
   ///
   /// Enabling this mainly flips the orientation of menus and title bar buttons
   fn set_rtl(&self, rtl: bool);
+
+  /// Sets the screen rectangle, such as the tray icon, that hiding the window animates toward.
+  ///
+  /// This is the Windows name of [`Window::set_minimize_target`].
+  fn set_iconic_preview_rect(&self, rect: Option<Rect>) -> Result<(), ExternalError>;
+
+  /// Lets `handler` answer the `WM_GETOBJECT` messages of the window, or stops with `None`.
+  ///
+  /// This is the hook of accessibility adapters, which return the `LRESULT` of
+  /// `UiaReturnRawElementProvider` for the root element of their tree. `handler` gets the WPARAM
+  /// and LPARAM of the message, and returns `None` to let tao answer it. It runs on the thread
+  /// of the event loop, outside of the event handler, and it can call other methods of the
+  /// window.
+  ///
+  /// See the [`accessibility`](crate::accessibility) module.
+  fn set_wm_getobject_handler(
+    &self,
+    handler: Option<Box<dyn Fn(usize, isize) -> Option<isize> + Send + Sync>>,
+  );
+
+  /// Lets `handler` see the messages of the window before tao does, or stops with `None`.
+  ///
+  /// `handler` gets the HWND, the message, its WPARAM and its LPARAM, and returns the `LRESULT`
+  /// of the messages it handles, which tao doesn't see then, or `None` to let tao handle them.
+  /// It runs on the thread of the event loop, outside of the event handler, and it can call
+  /// other methods of the window. It isn't called for `WM_NCDESTROY`.
+  ///
+  /// This is the way to see the messages tao handles without passing them on, like the ones of
+  /// an IME or of DirectManipulation, instead of replacing the window procedure with
+  /// `SetWindowLongPtrW`, which breaks tao.
+  fn set_subclass_handler(
+    &self,
+    handler: Option<Box<dyn Fn(HWND, u32, usize, isize) -> Option<isize> + Send + Sync>>,
+  );
+
+  /// Sets what a drop into the window does with the data dragged over it, [`DropEffect::Copy`]
+  /// by default.
+  ///
+  /// Call it while handling a [`WindowEvent::DragOver`] to choose the effect from the position
+  /// and formats of the drag. It's kept for the next drags, until it's set again. An effect the
+  /// source of the drag doesn't allow is shown as [`DropEffect::None`], and the data can't be
+  /// dropped then.
+  ///
+  /// Only applies to windows created with drag and drop enabled, see
+  /// [`WindowBuilderExtWindows::with_drag_and_drop`].
+  ///
+  /// [`WindowEvent::DragOver`]: crate::event::WindowEvent::DragOver
+  fn set_drop_effect(&self, effect: DropEffect);
 }
 
 impl WindowExtWindows for Window {
@@ -198,9 +324,7 @@ impl WindowExtWindows for Window {
 
   #[inline]
   fn set_enable(&self, enabled: bool) {
-    unsafe {
-      EnableWindow(self.window.hwnd(), enabled);
-    }
+    self.window.set_enable(enabled)
   }
 
   #[inline]
@@ -225,7 +349,7 @@ impl WindowExtWindows for Window {
 
   #[inline]
   fn set_skip_taskbar(&self, skip: bool) {
-    self.window.set_skip_taskbar(skip);
+    let _ = self.window.set_skip_taskbar(skip);
   }
 
   #[inline]
@@ -237,6 +361,34 @@ impl WindowExtWindows for Window {
   fn set_rtl(&self, rtl: bool) {
     self.window.set_rtl(rtl)
   }
+
+  #[inline]
+  fn set_iconic_preview_rect(&self, rect: Option<Rect>) -> Result<(), ExternalError> {
+    self.window.set_minimize_target(rect)
+  }
+
+  #[inline]
+  fn set_wm_getobject_handler(
+    &self,
+    handler: Option<Box<dyn Fn(usize, isize) -> Option<isize> + Send + Sync>>,
+  ) {
+    self
+      .window
+      .set_wm_getobject_handler(handler.map(Into::into))
+  }
+
+  #[inline]
+  fn set_subclass_handler(
+    &self,
+    handler: Option<Box<dyn Fn(HWND, u32, usize, isize) -> Option<isize> + Send + Sync>>,
+  ) {
+    self.window.set_subclass_handler(handler.map(Into::into))
+  }
+
+  #[inline]
+  fn set_drop_effect(&self, effect: DropEffect) {
+    self.window.set_drop_effect(effect)
+  }
 }
 
 /// Additional methods on `WindowBuilder` that are specific to Windows.
@@ -286,6 +438,8 @@ pub trait WindowBuilderExtWindows {
   fn with_drag_and_drop(self, flag: bool) -> WindowBuilder;
 
   /// Whether to create the window icon with the taskbar icon or not.
+  ///
+  /// Same as [`WindowBuilder::with_skip_taskbar`].
   fn with_skip_taskbar(self, skip: bool) -> WindowBuilder;
 
   /// Customize the window class name.
@@ -299,6 +453,29 @@ pub trait WindowBuilderExtWindows {
 
   /// Sets right-to-left layout.
   fn with_rtl(self, rtl: bool) -> WindowBuilder;
+
+  /// Skips erasing the background on `WM_ERASEBKGND`, so a resize doesn't flash the class
+  /// background before the next frame is presented.
+  ///
+  /// Use this when the whole client area is drawn by a swapchain or a webview. Areas the app
+  /// doesn't draw show stale content instead of the background. Disabled by default.
+  fn with_no_background_erase(self, no_erase: bool) -> WindowBuilder;
+
+  /// Delivers [`Event::RedrawRequested`] synchronously from inside the `WM_SIZE` and `WM_MOVING`
+  /// messages of the modal loop that runs while the user resizes or moves the window, so the app
+  /// can present a frame of the new size before the system shows it. Disabled by default.
+  ///
+  /// The event handler is called from inside the modal loop, before the message returns. It
+  /// must present the frame right away, and shouldn't block. Resizing the window from that
+  /// redraw, or resizing from inside the event handler, doesn't recurse: the next redraw is then
+  /// delivered by `WM_PAINT` as usual.
+  ///
+  /// [`Event::RedrawRequested`]: crate::event::Event::RedrawRequested
+  fn with_resize_redraw_sync(self, sync: bool) -> WindowBuilder;
+
+  /// Uses `scale_factor` for the window instead of the one of the monitor it is on.
+  #[deprecated = "Deprecated in favor of WindowBuilder::with_scale_factor_override"]
+  fn with_scale_factor_override(self, scale_factor: f64) -> WindowBuilder;
 }
 
 impl WindowBuilderExtWindows for WindowBuilder {
@@ -340,7 +517,7 @@ impl WindowBuilderExtWindows for WindowBuilder {
 
   #[inline]
   fn with_skip_taskbar(mut self, skip: bool) -> WindowBuilder {
-    self.platform_specific.skip_taskbar = skip;
+    self.window.skip_taskbar = skip;
     self
   }
 
@@ -361,6 +538,23 @@ impl WindowBuilderExtWindows for WindowBuilder {
     self.platform_specific.rtl = rtl;
     self
   }
+
+  #[inline]
+  fn with_no_background_erase(mut self, no_erase: bool) -> WindowBuilder {
+    self.platform_specific.no_background_erase = no_erase;
+    self
+  }
+
+  #[inline]
+  fn with_resize_redraw_sync(mut self, sync: bool) -> WindowBuilder {
+    self.platform_specific.resize_redraw_sync = sync;
+    self
+  }
+
+  #[inline]
+  fn with_scale_factor_override(self, scale_factor: f64) -> WindowBuilder {
+    WindowBuilder::with_scale_factor_override(self, scale_factor)
+  }
 }
 
 /// Additional methods on `MonitorHandle` that are specific to Windows.
@@ -4,12 +4,13 @@
 
 #![cfg(target_os = "android")]
 use crate::{
-  dpi::{PhysicalPosition, PhysicalSize, Position, Size},
+  dpi::{PhysicalPosition, PhysicalSize, Position, Rect, Size},
   error, event,
   event_loop::{self, ControlFlow},
-  keyboard::{Key, KeyCode, KeyLocation, NativeKeyCode},
+  keyboard::{Key, KeyCode, KeyLocation, KeyboardLayout, ModifiersState, NativeKeyCode},
   monitor,
-  window::{self, ResizeDirection, Theme, WindowSizeConstraints},
+  platform::inject::SyntheticInput,
+  window::{self, ImePurpose, ResizeDirection, Theme, WindowLevel, WindowSizeConstraints},
 };
 use crossbeam_channel::{Receiver, Sender};
 use ndk::{
@@ -26,7 +27,7 @@ use std::{
 };
 
 pub mod ndk_glue;
-use ndk_glue::{Event, Rect};
+use ndk_glue::{Event, ImeInsets, Rect};
 
 lazy_static! {
   static ref CONFIG: RwLock<Configuration> = RwLock::new(Configuration::new());
@@ -62,10 +63,18 @@ pub struct EventLoop<T: 'static> {
   start_cause: event::StartCause,
   looper: ThreadLooper,
   running: bool,
+  resumed: bool,
+  /// The soft keyboard state last sent to the event handler.
+  ime: ImeInsets,
+  /// Ids of the touch contacts, as Android reuses pointer ids right away.
+  touch_ids: event::TouchIds,
 }
 
-#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub(crate) struct PlatformSpecificEventLoopAttributes {}
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct PlatformSpecificEventLoopAttributes {
+  pub(crate) headless: bool,
+  pub(crate) single_instance: Option<String>,
+}
 
 macro_rules! call_event_handler {
   ( $event_handler:expr, $window_target:expr, $cf:expr, $event:expr ) => {{
@@ -78,7 +87,13 @@ macro_rules! call_event_handler {
 }
 
 impl<T: 'static> EventLoop<T> {
-  pub(crate) fn new(_: &PlatformSpecificEventLoopAttributes) -> Self {
+  pub(crate) fn new(attributes: &PlatformSpecificEventLoopAttributes) -> Self {
+    if attributes.headless {
+      warn!("`EventLoopBuilder::with_headless` is ignored on Android");
+    }
+    if attributes.single_instance.is_some() {
+      warn!("`EventLoopBuilder::with_single_instance` is ignored on Android");
+    }
     let (sender, receiver) = crossbeam_channel::unbounded();
 
     Self {
@@ -86,6 +101,12 @@ impl<T: 'static> EventLoop<T> {
         p: EventLoopWindowTarget {
           _marker: std::marker::PhantomData,
         },
+        exit: Default::default(),
+        executor: Default::default(),
+        captures: Default::default(),
+        window_motion_coalescing: Default::default(),
+        window_registry: Default::default(),
+        user_events: Default::default(),
         _marker: std::marker::PhantomData,
       },
       sender_to_clone: sender,
@@ -94,9 +115,18 @@ impl<T: 'static> EventLoop<T> {
       start_cause: event::StartCause::Init,
       looper: ThreadLooper::for_thread().unwrap(),
       running: false,
+      resumed: false,
+      ime: ImeInsets::default(),
+      touch_ids: Default::default(),
     }
   }
 
+  pub(crate) fn try_new(
+    attributes: &PlatformSpecificEventLoopAttributes,
+  ) -> Result<Self, error::ExternalError> {
+    Ok(Self::new(attributes))
+  }
+
   pub fn run<F>(mut self, event_handler: F) -> !
   where
     F:
@@ -122,20 +152,58 @@ impl<T: 'static> EventLoop<T> {
 
       let mut redraw = false;
       let mut resized = false;
+      let mut ime_changed = false;
 
       match self.first_event.take() {
         Some(EventSource::Callback) => match ndk_glue::poll_events().unwrap() {
           Event::Resume => {
+            self.resumed = true;
             call_event_handler!(
               event_handler,
               self.window_target(),
               control_flow,
               event::Event::Resumed
             );
+            // The keyboard may have changed while the activity was paused.
+            ime_changed = true;
+          }
+          Event::WindowCreated => {
+            call_event_handler!(
+              event_handler,
+              self.window_target(),
+              control_flow,
+              event::Event::SurfaceCreated(window::WindowId(WindowId::ACTIVITY))
+            );
+          }
+          Event::WindowDestroyed => {
+            call_event_handler!(
+              event_handler,
+              self.window_target(),
+              control_flow,
+              event::Event::SurfaceDestroyed(window::WindowId(WindowId::ACTIVITY))
+            );
+            ndk_glue::release_native_window();
           }
           Event::WindowResized => resized = true,
           Event::WindowRedrawNeeded => redraw = true,
           Event::Pause => {
+            self.resumed = false;
+            // The keyboard is dismissed with the activity, which doesn't always report it.
+            if self.ime != ImeInsets::default() {
+              self.ime = ImeInsets::default();
+              call_event_handler!(
+                event_handler,
+                self.window_target(),
+                control_flow,
+                event::Event::WindowEvent {
+                  window_id: window::WindowId(WindowId::ACTIVITY),
+                  event: event::WindowEvent::ImeVisibilityChanged {
+                    visible: false,
+                    height: 0,
+                  },
+                }
+              );
+            }
             call_event_handler!(
               event_handler,
               self.window_target(),
@@ -155,7 +223,7 @@ impl<T: 'static> EventLoop<T> {
             // if (scale_factor - old_scale_factor).abs() < f64::EPSILON {
             //   let mut size = MonitorHandle.size();
             //   let event = event::Event::WindowEvent {
-            //     window_id: window::WindowId(WindowId),
+            //     window_id: window::WindowId(WindowId::ACTIVITY),
             //     event: event::WindowEvent::ScaleFactorChanged {
             //       new_inner_size: &mut size,
             //       scale_factor,
@@ -170,7 +238,7 @@ impl<T: 'static> EventLoop<T> {
               self.window_target(),
               control_flow,
               event::Event::WindowEvent {
-                window_id: window::WindowId(WindowId),
+                window_id: window::WindowId(WindowId::ACTIVITY),
                 event: event::WindowEvent::Focused(true),
               }
             );
@@ -181,11 +249,23 @@ impl<T: 'static> EventLoop<T> {
               self.window_target(),
               control_flow,
               event::Event::WindowEvent {
-                window_id: window::WindowId(WindowId),
+                window_id: window::WindowId(WindowId::ACTIVITY),
                 event: event::WindowEvent::Focused(false),
               }
             );
           }
+          Event::BackPressed => {
+            call_event_handler!(
+              event_handler,
+              self.window_target(),
+              control_flow,
+              event::Event::WindowEvent {
+                window_id: window::WindowId(WindowId::ACTIVITY),
+                event: event::WindowEvent::BackRequested,
+              }
+            );
+          }
+          Event::ImeInsetsChanged => ime_changed = true,
           _ => {}
         },
         Some(EventSource::InputQueue) => {
@@ -193,7 +273,7 @@ impl<T: 'static> EventLoop<T> {
             while let Ok(Some(event)) = input_queue.get_event() {
               if let Some(event) = input_queue.pre_dispatch(event) {
                 let mut handled = true;
-                let window_id = window::WindowId(WindowId);
+                let window_id = window::WindowId(WindowId::ACTIVITY);
                 let device_id = event::DeviceId(DeviceId);
                 match &event {
                   InputEvent::MotionEvent(motion_event) => {
@@ -227,14 +307,20 @@ impl<T: 'static> EventLoop<T> {
                           x: pointer.x() as _,
                           y: pointer.y() as _,
                         };
+                        let id = self.touch_ids.id(pointer.pointer_id() as u64, phase);
                         let event = event::Event::WindowEvent {
                           window_id,
                           event: event::WindowEvent::Touch(event::Touch {
                             device_id,
                             phase,
                             location,
-                            id: pointer.pointer_id() as u64,
-                            force: None,
+                            id,
+                            force: Some(event::Force::Normalized(pointer.pressure() as f64)),
+                            // Radians from the vertical axis, from -π/2 to π/2.
+                            rotation: Some(
+                              (pointer.orientation() as f64).to_degrees().rem_euclid(360.),
+                            ),
+                            timestamp: Instant::now(),
                           }),
                         };
                         call_event_handler!(
@@ -265,6 +351,12 @@ impl<T: 'static> EventLoop<T> {
                     let native = NativeKeyCode::Android(keycode_u32);
                     let logical_key = keycode_to_logical(keycode, native);
                     // TODO: maybe use getUnicodeChar to get the logical key
+                    let meta_state = key.meta_state();
+                    let mut modifiers = ModifiersState::empty();
+                    modifiers.set(ModifiersState::SHIFT, meta_state.shift_on());
+                    modifiers.set(ModifiersState::CONTROL, meta_state.ctrl_on());
+                    modifiers.set(ModifiersState::ALT, meta_state.alt_on());
+                    modifiers.set(ModifiersState::SUPER, meta_state.meta_on());
 
                     let event = event::Event::WindowEvent {
                       window_id,
@@ -276,10 +368,12 @@ impl<T: 'static> EventLoop<T> {
                           logical_key,
                           location: keycode_to_location(keycode),
                           repeat: key.repeat_count() > 0,
+                          modifiers,
                           text: None,
                           platform_specific: KeyEventExtra {},
                         },
                         is_synthetic: false,
+                        timestamp: Instant::now(),
                       },
                     };
                     call_event_handler!(event_handler, self.window_target(), control_flow, event);
@@ -303,6 +397,25 @@ impl<T: 'static> EventLoop<T> {
         None => {}
       }
 
+      if ime_changed && self.resumed {
+        let ime = ndk_glue::ime_insets();
+        if ime != self.ime {
+          self.ime = ime;
+          call_event_handler!(
+            event_handler,
+            self.window_target(),
+            control_flow,
+            event::Event::WindowEvent {
+              window_id: window::WindowId(WindowId::ACTIVITY),
+              event: event::WindowEvent::ImeVisibilityChanged {
+                visible: ime.visible,
+                height: ime.height,
+              },
+            }
+          );
+        }
+      }
+
       call_event_handler!(
         event_handler,
         self.window_target(),
@@ -313,14 +426,14 @@ impl<T: 'static> EventLoop<T> {
       if resized && self.running {
         let size = MonitorHandle.size();
         let event = event::Event::WindowEvent {
-          window_id: window::WindowId(WindowId),
+          window_id: window::WindowId(WindowId::ACTIVITY),
           event: event::WindowEvent::Resized(size),
         };
         call_event_handler!(event_handler, self.window_target(), control_flow, event);
       }
 
       if redraw && self.running {
-        let event = event::Event::RedrawRequested(window::WindowId(WindowId));
+        let event = event::Event::RedrawRequested(window::WindowId(WindowId::ACTIVITY));
         call_event_handler!(event_handler, self.window_target(), control_flow, event);
       }
 
@@ -378,6 +491,7 @@ impl<T: 'static> EventLoop<T> {
             event::StartCause::ResumeTimeReached {
               start,
               requested_resume: instant,
+              resumed: Instant::now(),
             }
           }
         }
@@ -419,12 +533,37 @@ impl<T> Clone for EventLoopProxy<T> {
   }
 }
 
+#[derive(Clone)]
+pub struct EventLoopWaker {
+  looper: ForeignLooper,
+}
+
+impl EventLoopWaker {
+  pub fn wake(&self) {
+    self.looper.wake();
+  }
+}
+
 #[derive(Clone)]
 pub struct EventLoopWindowTarget<T: 'static> {
   _marker: std::marker::PhantomData<T>,
 }
 
 impl<T: 'static> EventLoopWindowTarget<T> {
+  pub fn create_waker(&self) -> EventLoopWaker {
+    EventLoopWaker {
+      looper: ForeignLooper::for_thread().expect("called from event loop thread"),
+    }
+  }
+
+  pub fn is_headless(&self) -> bool {
+    false
+  }
+
+  pub fn has_window(&self, window_id: WindowId) -> bool {
+    window_id == WindowId::ACTIVITY
+  }
+
   pub fn primary_monitor(&self) -> Option<monitor::MonitorHandle> {
     Some(monitor::MonitorHandle {
       inner: MonitorHandle,
@@ -461,14 +600,93 @@ impl<T: 'static> EventLoopWindowTarget<T> {
     debug!("`EventLoopWindowTarget::cursor_position` is ignored on Android");
     Ok((0, 0).into())
   }
+
+  pub fn set_device_event_filter(
+    &self,
+    _filter: event_loop::DeviceEventFilter,
+  ) -> Result<(), error::ExternalError> {
+    debug!("`EventLoopWindowTarget::set_device_event_filter` is ignored on Android");
+    Ok(())
+  }
+
+  pub fn accessibility_preferences(&self) -> event_loop::AccessibilityPrefs {
+    event_loop::AccessibilityPrefs::default()
+  }
+
+  pub fn system_appearance(&self) -> event_loop::SystemAppearance {
+    event_loop::SystemAppearance::default()
+  }
+
+  pub fn power_info(&self) -> event_loop::PowerInfo {
+    event_loop::PowerInfo::default()
+  }
+
+  pub fn set_idle_callback(&self, _callback: Option<Box<dyn FnMut(event_loop::IdleDeadline)>>) {}
+
+  pub fn run_nested_until(
+    &self,
+    _target: &event_loop::EventLoopWindowTarget<T>,
+    _predicate: &mut dyn FnMut() -> bool,
+    _handler: &mut dyn FnMut(
+      event::Event<'_, T>,
+      &event_loop::EventLoopWindowTarget<T>,
+      &mut ControlFlow,
+    ),
+  ) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn key_for_keycode(&self, _code: KeyCode) -> Option<Key<'static>> {
+    None
+  }
+
+  pub fn keyboard_layout(&self) -> Option<KeyboardLayout> {
+    None
+  }
+
+  pub fn inject_input(&self, _input: SyntheticInput) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn register_global_shortcut(
+    &self,
+    _id: u32,
+    _modifiers: ModifiersState,
+    _key: KeyCode,
+  ) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn unregister_global_shortcut(&self, _id: u32) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub struct WindowId;
+pub struct WindowId(u64);
 
 impl WindowId {
+  /// The id of the native window of the activity, which every `Window` shares.
+  const ACTIVITY: Self = WindowId(1);
+
   pub fn dummy() -> Self {
-    WindowId
+    WindowId(0)
+  }
+
+  pub fn into_raw(self) -> u64 {
+    self.0
+  }
+
+  pub fn from_raw(raw: u64) -> Self {
+    WindowId(raw)
   }
 }
 
@@ -497,7 +715,7 @@ impl Window {
   }
 
   pub fn id(&self) -> WindowId {
-    WindowId
+    WindowId::ACTIVITY
   }
 
   pub fn primary_monitor(&self) -> Option<monitor::MonitorHandle> {
@@ -532,6 +750,12 @@ impl Window {
     // TODO
   }
 
+  pub fn request_redraw_synced(&self) {
+    self.request_redraw()
+  }
+
+  pub fn request_frame_callbacks(&self, _enabled: bool) {}
+
   pub fn inner_position(&self) -> Result<PhysicalPosition<i32>, error::NotSupportedError> {
     Err(error::NotSupportedError::new())
   }
@@ -548,17 +772,33 @@ impl Window {
     self.outer_size()
   }
 
-  pub fn set_inner_size(&self, _size: Size) {
+  pub fn set_inner_size(&self, _size: Size) -> Result<(), error::WindowOperationError> {
     warn!("Cannot set window size on Android");
+    Err(error::NotSupportedError::new().into())
   }
 
   pub fn outer_size(&self) -> PhysicalSize<u32> {
     MonitorHandle.size()
   }
 
-  pub fn set_min_inner_size(&self, _: Option<Size>) {}
-  pub fn set_max_inner_size(&self, _: Option<Size>) {}
-  pub fn set_inner_size_constraints(&self, _: WindowSizeConstraints) {}
+  pub fn set_min_inner_size(&self, _: Option<Size>) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+  pub fn set_max_inner_size(&self, _: Option<Size>) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+  pub fn set_inner_size_constraints(
+    &self,
+    _: WindowSizeConstraints,
+  ) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
 
   pub fn set_title(&self, _title: &str) {}
   pub fn title(&self) -> String {
@@ -567,11 +807,23 @@ impl Window {
 
   pub fn set_visible(&self, _visibility: bool) {}
 
+  pub fn close(&self) {
+    warn!("`Window::close` is ignored on Android");
+  }
+
   pub fn set_focus(&self) {
     //FIXME: implementation goes here
     warn!("set_focus not yet implemented on Android");
   }
 
+  pub fn set_focus_with_token(
+    &self,
+    _token: Option<window::ActivationToken>,
+  ) -> window::FocusResult {
+    warn!("`Window::set_focus_with_token` is ignored on Android");
+    window::FocusResult::Failed
+  }
+
   pub fn is_focused(&self) -> bool {
     log::warn!("`Window::is_focused` is ignored on Android");
     false
@@ -605,6 +857,10 @@ impl Window {
     false
   }
 
+  pub fn is_visible_on_all_workspaces(&self) -> bool {
+    false
+  }
+
   pub fn is_visible(&self) -> bool {
     log::warn!("`Window::is_visible` is ignored on Android");
     false
@@ -635,8 +891,12 @@ impl Window {
     false
   }
 
-  pub fn set_fullscreen(&self, _monitor: Option<window::Fullscreen>) {
+  pub fn set_fullscreen(
+    &self,
+    _monitor: Option<window::Fullscreen>,
+  ) -> Result<(), error::WindowOperationError> {
     warn!("Cannot set fullscreen on Android");
+    Err(error::NotSupportedError::new().into())
   }
 
   pub fn fullscreen(&self) -> Option<window::Fullscreen> {
@@ -645,25 +905,88 @@ impl Window {
 
   pub fn set_decorations(&self, _decorations: bool) {}
 
+  pub fn raise(&self) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn lower(&self) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn restack_above(&self, _sibling: &Window) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_parent(&self, _parent: Option<&Window>) {}
+
   pub fn set_always_on_bottom(&self, _always_on_bottom: bool) {}
 
-  pub fn set_always_on_top(&self, _always_on_top: bool) {}
+  pub fn set_always_on_top(&self, _always_on_top: bool) -> Result<(), error::WindowOperationError> {
+    Err(error::NotSupportedError::new().into())
+  }
+
+  pub fn set_window_level(&self, _level: WindowLevel) {}
 
   pub fn set_window_icon(&self, _window_icon: Option<crate::icon::Icon>) {}
 
-  pub fn set_ime_position(&self, _position: Position) {}
+  pub fn set_menu(&self, _menu: Option<&Menu>) {}
+
+  pub fn show_context_menu(&self, _menu: &Submenu, _position: Position) {}
+
+  pub fn set_ime_cursor_area(&self, _area: Rect) {}
+
+  pub fn set_ime_allowed(&self, _allowed: bool) {}
+
+  pub fn set_ime_purpose(&self, _purpose: ImePurpose) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_ime_visible(&self, visible: bool) -> Result<(), error::ExternalError> {
+    set_soft_input_visible(visible).map_err(|e| {
+      warn!("Failed to set the soft keyboard visibility: {}", e);
+      error::ExternalError::Os(os_error!(OsError))
+    })
+  }
 
   pub fn request_user_attention(&self, _request_type: Option<window::UserAttentionType>) {}
 
+  pub fn is_attention_requested(&self) -> bool {
+    false
+  }
+
+  pub fn reset_dead_keys(&self) {}
+
   pub fn set_cursor_icon(&self, _: window::CursorIcon) {}
 
+  pub fn cursor_icon(&self) -> window::CursorKind {
+    window::CursorKind::default()
+  }
+
+  pub fn set_custom_cursor(
+    &self,
+    _: window::RgbaIcon,
+    _: PhysicalPosition<u32>,
+  ) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
   pub fn set_cursor_position(&self, _: Position) -> Result<(), error::ExternalError> {
     Err(error::ExternalError::NotSupported(
       error::NotSupportedError::new(),
     ))
   }
 
-  pub fn set_cursor_grab(&self, _: bool) -> Result<(), error::ExternalError> {
+  pub fn set_cursor_grab(&self, _: window::CursorGrabMode) -> Result<(), error::ExternalError> {
     Err(error::ExternalError::NotSupported(
       error::NotSupportedError::new(),
     ))
@@ -686,12 +1009,80 @@ impl Window {
     ))
   }
 
+  pub fn start_drag(
+    &self,
+    _data: window::DragData,
+    _image: window::DragImage,
+  ) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
   pub fn set_ignore_cursor_events(&self, _ignore: bool) -> Result<(), error::ExternalError> {
     Err(error::ExternalError::NotSupported(
       error::NotSupportedError::new(),
     ))
   }
 
+  pub fn set_minimize_target(&self, _target: Option<Rect>) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn capture(&self, _outer: bool) -> Result<window::RgbaImage, error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_skip_taskbar(&self, _skip: bool) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_cursor_hittest_region(
+    &self,
+    _region: Option<&[Rect]>,
+  ) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_shape(&self, _shape: Option<window::WindowShape>) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_hit_test_callback(
+    &self,
+    _callback: Option<window::HitTestCallback>,
+  ) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn accessibility_action_sender(
+    &self,
+  ) -> Result<AccessibilityActionSender, error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_opacity(&self, _opacity: f32) {}
+
+  pub fn set_background_color(&self, _color: Option<window::Color>) {}
+
+  pub fn opacity(&self) -> f32 {
+    1.0
+  }
+
   pub fn cursor_position(&self) -> Result<PhysicalPosition<f64>, error::ExternalError> {
     debug!("`Window::cursor_position` is ignored on Android");
     Ok((0, 0).into())
@@ -699,24 +1090,22 @@ impl Window {
 
   #[cfg(feature = "rwh_04")]
   pub fn raw_window_handle_rwh_04(&self) -> rwh_04::RawWindowHandle {
-    // TODO: Use main activity instead?
     let mut handle = rwh_04::AndroidNdkHandle::empty();
-    if let Some(w) = ndk_glue::window_manager() {
-      handle.a_native_window = w.as_obj().as_raw() as *mut _;
+    if let Some(w) = ndk_glue::native_window().as_ref() {
+      handle.a_native_window = w.ptr().as_ptr() as *mut _;
     } else {
-      panic!("Cannot get the native window, it's null and will always be null before Event::Resumed and after Event::Suspended. Make sure you only call this function between those events.");
+      panic!("Cannot get the native window, it's null and will always be null before Event::SurfaceCreated and after Event::SurfaceDestroyed. Make sure you only call this function between those events, or use `WindowExtAndroid::native_window`.");
     };
     rwh_04::RawWindowHandle::AndroidNdk(handle)
   }
 
   #[cfg(feature = "rwh_05")]
   pub fn raw_window_handle_rwh_05(&self) -> rwh_05::RawWindowHandle {
-    // TODO: Use main activity instead?
     let mut handle = rwh_05::AndroidNdkWindowHandle::empty();
-    if let Some(w) = ndk_glue::window_manager() {
-      handle.a_native_window = w.as_obj().as_raw() as *mut _;
+    if let Some(w) = ndk_glue::native_window().as_ref() {
+      handle.a_native_window = w.ptr().as_ptr() as *mut _;
     } else {
-      panic!("Cannot get the native window, it's null and will always be null before Event::Resumed and after Event::Suspended. Make sure you only call this function between those events.");
+      panic!("Cannot get the native window, it's null and will always be null before Event::SurfaceCreated and after Event::SurfaceDestroyed. Make sure you only call this function between those events, or use `WindowExtAndroid::native_window`.");
     };
     rwh_05::RawWindowHandle::AndroidNdk(handle)
   }
@@ -728,12 +1117,8 @@ impl Window {
 
   #[cfg(feature = "rwh_06")]
   pub fn raw_window_handle_rwh_06(&self) -> Result<rwh_06::RawWindowHandle, rwh_06::HandleError> {
-    // TODO: Use main activity instead?
-    if let Some(w) = ndk_glue::window_manager() {
-      let native_window =
-        unsafe { std::ptr::NonNull::new_unchecked(w.as_obj().as_raw() as *mut _) };
-      // native_window shuldn't be null
-      let handle = rwh_06::AndroidNdkWindowHandle::new(native_window);
+    if let Some(w) = ndk_glue::native_window().as_ref() {
+      let handle = rwh_06::AndroidNdkWindowHandle::new(w.ptr().cast());
       Ok(rwh_06::RawWindowHandle::AndroidNdk(handle))
     } else {
       Err(rwh_06::HandleError::Unavailable)
@@ -754,9 +1139,17 @@ impl Window {
     ndk_glue::content_rect()
   }
 
+  pub fn native_window(&self) -> Option<ndk::native_window::NativeWindow> {
+    ndk_glue::native_window().clone()
+  }
+
   pub fn theme(&self) -> Theme {
     Theme::Light
   }
+
+  pub fn set_theme(&self, _theme: Option<Theme>) {}
+
+  pub fn set_enable(&self, _enabled: bool) {}
 }
 
 #[derive(Default, Clone, Debug)]
@@ -771,6 +1164,221 @@ impl Display for OsError {
 
 pub(crate) use crate::icon::NoIcon as PlatformIcon;
 
+/// Android has no notifications.
+pub enum Notification {}
+
+impl Notification {
+  pub fn new<T>(
+    _window_target: &EventLoopWindowTarget<T>,
+    _id: u32,
+    _attributes: crate::notification::NotificationAttributes,
+  ) -> Result<Self, error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn close(&self) {
+    match *self {}
+  }
+}
+
+/// Android has no window to deliver accessibility action requests to.
+#[derive(Clone)]
+pub enum AccessibilityActionSender {}
+
+impl AccessibilityActionSender {
+  pub fn send(
+    &self,
+    _request: crate::accessibility::AccessibilityActionRequest,
+  ) -> Result<(), event_loop::EventLoopClosed<crate::accessibility::AccessibilityActionRequest>> {
+    match *self {}
+  }
+}
+
+/// Android has no system tray.
+pub enum SystemTray {}
+
+impl SystemTray {
+  pub fn new<T>(
+    _window_target: &EventLoopWindowTarget<T>,
+    _id: u32,
+    _icon: crate::icon::Icon,
+    _tooltip: Option<String>,
+  ) -> Result<Self, error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_icon(&self, _icon: crate::icon::Icon) {
+    match *self {}
+  }
+
+  pub fn set_tooltip(&self, _tooltip: Option<&str>) {
+    match *self {}
+  }
+
+  pub fn set_menu(&self, _menu: Option<&Submenu>) {
+    match *self {}
+  }
+}
+
+/// Android has no menus, they are built but never shown.
+pub struct Menu;
+
+impl Menu {
+  pub fn new() -> Self {
+    Menu
+  }
+
+  pub fn append_submenu(&self, _submenu: &Submenu) {}
+}
+
+pub struct Submenu;
+
+impl Submenu {
+  pub fn new(_title: &str) -> Self {
+    Submenu
+  }
+
+  pub fn append_item(&self, _item: &MenuItem) {}
+
+  pub fn append_submenu(&self, _submenu: &Submenu) {}
+
+  pub fn append_separator(&self) {}
+
+  pub fn set_title(&self, _title: &str) {}
+
+  pub fn set_enabled(&self, _enabled: bool) {}
+}
+
+pub struct MenuItem;
+
+impl MenuItem {
+  pub fn new(_id: u32, _title: &str, _checked: Option<bool>) -> Self {
+    MenuItem
+  }
+
+  pub fn set_title(&self, _title: &str) {}
+
+  pub fn set_enabled(&self, _enabled: bool) {}
+
+  pub fn set_checked(&self, _checked: bool) {}
+
+  pub fn is_checked(&self) -> bool {
+    false
+  }
+}
+
+/// Android has no clipboard support, nothing can be read and nothing is written.
+pub struct Clipboard;
+
+impl Clipboard {
+  pub fn new<T>(_window_target: &EventLoopWindowTarget<T>) -> Self {
+    Clipboard
+  }
+
+  pub fn read_text(&self) -> Option<String> {
+    None
+  }
+
+  pub fn write_text(&self, _text: &str) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn read_image(&self) -> Option<crate::window::RgbaImage> {
+    None
+  }
+
+  pub fn write_image(&self, _image: &crate::window::RgbaImage) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn read_files(&self) -> Vec<std::path::PathBuf> {
+    Vec::new()
+  }
+
+  pub fn write_files(&self, _files: &[std::path::PathBuf]) -> Result<(), error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+}
+
+/// Android has no power assertions.
+pub struct PowerAssertion;
+
+impl PowerAssertion {
+  pub fn keep_display_awake<T>(
+    _window_target: &EventLoopWindowTarget<T>,
+    _reason: &str,
+  ) -> Result<Self, error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+}
+
+/// Shows or hides the soft keyboard for the focused view of the activity through its
+/// `InputMethodManager`.
+fn set_soft_input_visible(visible: bool) -> jni::errors::Result<()> {
+  use jni::objects::{JObject, JValue};
+
+  let ctx = ndk_context::android_context();
+  let vm = unsafe { jni::JavaVM::from_raw(ctx.vm().cast()) }?;
+  let mut env = vm.attach_current_thread()?;
+  let activity = unsafe { JObject::from_raw(ctx.context().cast()) };
+
+  let window = env
+    .call_method(&activity, "getWindow", "()Landroid/view/Window;", &[])?
+    .l()?;
+  // The input method only shows up for the view with the focus, the content of the native
+  // activity has it unless the application moved it.
+  let mut view = env
+    .call_method(&window, "getCurrentFocus", "()Landroid/view/View;", &[])?
+    .l()?;
+  if view.is_null() {
+    view = env
+      .call_method(&window, "getDecorView", "()Landroid/view/View;", &[])?
+      .l()?;
+    env.call_method(&view, "requestFocus", "()Z", &[])?;
+  }
+  let service = JObject::from(env.new_string("input_method")?);
+  let input_method_manager = env
+    .call_method(
+      &activity,
+      "getSystemService",
+      "(Ljava/lang/String;)Ljava/lang/Object;",
+      &[JValue::Object(&service)],
+    )?
+    .l()?;
+
+  if visible {
+    env.call_method(
+      &input_method_manager,
+      "showSoftInput",
+      "(Landroid/view/View;I)Z",
+      &[JValue::Object(&view), JValue::Int(0)],
+    )?;
+  } else {
+    let token = env
+      .call_method(&view, "getWindowToken", "()Landroid/os/IBinder;", &[])?
+      .l()?;
+    env.call_method(
+      &input_method_manager,
+      "hideSoftInputFromWindow",
+      "(Landroid/os/IBinder;I)Z",
+      &[JValue::Object(&token), JValue::Int(0)],
+    )?;
+  }
+  Ok(())
+}
+
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct MonitorHandle;
 
@@ -779,6 +1387,10 @@ impl MonitorHandle {
     Some("Android Device".to_owned())
   }
 
+  pub fn id(&self) -> u64 {
+    crate::monitor::stable_id(self.name().unwrap_or_default().as_bytes())
+  }
+
   pub fn size(&self) -> PhysicalSize<u32> {
     // TODO decide how to get JNIENV
     if let Some(w) = ndk_glue::window_manager() {
@@ -829,6 +1441,16 @@ impl MonitorHandle {
       .unwrap_or(1.0)
   }
 
+  pub fn capture(&self) -> Result<window::RgbaImage, error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+    None
+  }
+
   pub fn video_modes(&self) -> impl Iterator<Item = monitor::VideoMode> {
     let size = self.size().into();
     let mut v = Vec::new();
@@ -5,7 +5,7 @@
 pub use jni::{
   self,
   objects::{GlobalRef, JClass, JMap, JObject, JString},
-  sys::jobject,
+  sys::{jboolean, jint, jobject},
   JNIEnv,
 };
 use log::Level;
@@ -13,6 +13,7 @@ pub use ndk;
 use ndk::{
   input_queue::InputQueue,
   looper::{FdEvent, ForeignLooper, ThreadLooper},
+  native_window::NativeWindow,
 };
 use once_cell::sync::{Lazy, OnceCell};
 use std::{
@@ -20,8 +21,10 @@ use std::{
   fs::File,
   io::{BufRead, BufReader},
   os::unix::prelude::*,
+  ptr::NonNull,
   sync::{Arc, Condvar, Mutex, RwLock, RwLockReadGuard},
   thread,
+  time::Duration,
 };
 
 /// Android pacakge name that could be used to reference classes
@@ -44,6 +47,11 @@ pub static PACKAGE: OnceCell<&str> = OnceCell::new();
 ///       - `private external fun destroy()`
 ///       - `private external fun memory()`
 ///       - `private external fun focus(focus: Boolean)`
+///       - `private external fun back()`, called from an `OnBackPressedCallback`
+///       - `private external fun ime(visible: Boolean, height: Int)`, called with the `ime()` insets
+///         of the decor view whenever they change
+///       - `private external fun surface(surface: Surface?)`, called with the surface from
+///         `SurfaceHolder.Callback.surfaceCreated` and with `null` from `surfaceDestroyed`
 /// 4. a one time setup function that will be ran once after tao has created its event loop in the `create` function above.
 /// 5. the main entry point of your android application.
 #[macro_export]
@@ -75,6 +83,9 @@ macro_rules! android_binding {
     android_fn!($domain, $package, $activity, destroy, [JObject]);
     android_fn!($domain, $package, $activity, memory, [JObject]);
     android_fn!($domain, $package, $activity, focus, [i32]);
+    android_fn!($domain, $package, $activity, back, [JObject]);
+    android_fn!($domain, $package, $activity, ime, [jboolean, jint]);
+    android_fn!($domain, $package, $activity, surface, [JObject]);
   }};
 }
 
@@ -107,6 +118,13 @@ static WINDOW_MANGER: OnceCell<GlobalRef> = OnceCell::new();
 static INPUT_QUEUE: Lazy<RwLock<Option<InputQueue>>> = Lazy::new(|| Default::default());
 static CONTENT_RECT: Lazy<RwLock<Rect>> = Lazy::new(|| Default::default());
 static LOOPER: Lazy<Mutex<Option<ForeignLooper>>> = Lazy::new(|| Default::default());
+static IME_INSETS: Lazy<RwLock<ImeInsets>> = Lazy::new(|| Default::default());
+static NATIVE_WINDOW: Lazy<RwLock<Option<NativeWindow>>> = Lazy::new(|| Default::default());
+/// Set while the activity waits in `surfaceDestroyed` for the event loop to release the window.
+static SURFACE_DESTROYING: Lazy<(Mutex<bool>, Condvar)> = Lazy::new(|| Default::default());
+
+/// How long `surfaceDestroyed` waits for the event loop, staying well below the ANR timeout.
+const SURFACE_DESTROYED_TIMEOUT: Duration = Duration::from_secs(2);
 
 pub fn window_manager() -> Option<&'static GlobalRef> {
   WINDOW_MANGER.get()
@@ -120,6 +138,24 @@ pub fn content_rect() -> Rect {
   CONTENT_RECT.read().unwrap().clone()
 }
 
+pub fn ime_insets() -> ImeInsets {
+  *IME_INSETS.read().unwrap()
+}
+
+/// The native window of the surface, `None` between `WindowDestroyed` and `WindowCreated`.
+pub fn native_window() -> RwLockReadGuard<'static, Option<NativeWindow>> {
+  NATIVE_WINDOW.read().unwrap()
+}
+
+/// Drops the native window once the event loop has handled `WindowDestroyed`, letting
+/// `surfaceDestroyed` return.
+pub fn release_native_window() {
+  *NATIVE_WINDOW.write().unwrap() = None;
+  let (destroying, released) = &*SURFACE_DESTROYING;
+  *destroying.lock().unwrap() = false;
+  released.notify_all();
+}
+
 static PIPE: Lazy<[RawFd; 2]> = Lazy::new(|| {
   let mut pipe: [RawFd; 2] = Default::default();
   unsafe { libc::pipe(pipe.as_mut_ptr()) };
@@ -153,6 +189,13 @@ pub struct Rect {
   pub bottom: u32,
 }
 
+/// Visibility and height in pixels of the soft keyboard, as last reported by the activity.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ImeInsets {
+  pub visible: bool,
+  pub height: u32,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[repr(u8)]
 pub enum Event {
@@ -173,6 +216,8 @@ pub enum Event {
   InputQueueCreated,
   InputQueueDestroyed,
   ContentRectChanged,
+  BackPressed,
+  ImeInsetsChanged,
 }
 
 pub unsafe fn create(
@@ -287,6 +332,49 @@ pub unsafe fn stop(_: JNIEnv, _: JClass, _: JObject) {
   wake(Event::Stop);
 }
 
+pub unsafe fn surface(env: JNIEnv, _: JClass, surface: JObject) {
+  if surface.is_null() {
+    if NATIVE_WINDOW.read().unwrap().is_none() {
+      return;
+    }
+    let (destroying, released) = &*SURFACE_DESTROYING;
+    let mut destroying = destroying.lock().unwrap();
+    *destroying = true;
+    wake(Event::WindowDestroyed);
+    // The surface must not be used once this returns, so wait for the event loop to let go.
+    let (_destroying, timeout) = released
+      .wait_timeout_while(destroying, SURFACE_DESTROYED_TIMEOUT, |destroying| {
+        *destroying
+      })
+      .unwrap();
+    if timeout.timed_out() {
+      log::warn!("The event loop didn't handle the destroyed surface in time");
+      *NATIVE_WINDOW.write().unwrap() = None;
+    }
+  } else {
+    let window = ndk_sys::ANativeWindow_fromSurface(env.get_raw().cast(), surface.as_raw().cast());
+    match NonNull::new(window) {
+      Some(window) => {
+        *NATIVE_WINDOW.write().unwrap() = Some(NativeWindow::from_ptr(window));
+        wake(Event::WindowCreated);
+      }
+      None => log::warn!("Failed to get the native window of the surface"),
+    }
+  }
+}
+
+pub unsafe fn back(_: JNIEnv, _: JClass, _: JObject) {
+  wake(Event::BackPressed);
+}
+
+pub unsafe fn ime(_: JNIEnv, _: JClass, visible: jboolean, height: jint) {
+  *IME_INSETS.write().unwrap() = ImeInsets {
+    visible: visible != 0,
+    height: height.max(0) as u32,
+  };
+  wake(Event::ImeInsetsChanged);
+}
+
 ///////////////////////////////////////////////
 // Events below are not used by event loop yet.
 ///////////////////////////////////////////////
@@ -0,0 +1,117 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  path::PathBuf,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex, Weak,
+  },
+};
+
+use crate::{error::ExternalError, window::RgbaImage};
+
+use super::{
+  event_loop::{Context, Pending},
+  EventLoopWindowTarget,
+};
+
+enum Content {
+  Empty,
+  Text(String),
+  Image(RgbaImage),
+  Files(Vec<PathBuf>),
+}
+
+struct State {
+  content: Content,
+  /// The event loops of the open clipboards, by clipboard.
+  listeners: Vec<(u64, Weak<Context>)>,
+}
+
+lazy_static! {
+  /// The clipboard of the process.
+  static ref STATE: Mutex<State> = Mutex::new(State {
+    content: Content::Empty,
+    listeners: Vec::new(),
+  });
+}
+
+static CLIPBOARD_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A clipboard kept in memory, shared by the whole process.
+pub struct Clipboard {
+  id: u64,
+}
+
+impl Clipboard {
+  pub fn new<T>(window_target: &EventLoopWindowTarget<T>) -> Self {
+    let id = CLIPBOARD_ID.fetch_add(1, Ordering::Relaxed);
+    STATE
+      .lock()
+      .unwrap()
+      .listeners
+      .push((id, Arc::downgrade(&window_target.context)));
+    Clipboard { id }
+  }
+
+  /// Replaces the content and queues `Event::ClipboardUpdated` for the open clipboards.
+  fn write(&self, content: Content) -> Result<(), ExternalError> {
+    let mut state = STATE.lock().unwrap();
+    state.content = content;
+    // Each event loop is told once, even with several clipboards.
+    let mut notified = Vec::new();
+    for (_, context) in &state.listeners {
+      if let Some(context) = context.upgrade() {
+        if !notified.iter().any(|other| Arc::ptr_eq(other, &context)) {
+          context.update(|shared| shared.pending.push_back(Pending::ClipboardUpdated));
+          notified.push(context);
+        }
+      }
+    }
+    Ok(())
+  }
+
+  pub fn read_text(&self) -> Option<String> {
+    match &STATE.lock().unwrap().content {
+      Content::Text(text) => Some(text.clone()),
+      _ => None,
+    }
+  }
+
+  pub fn write_text(&self, text: &str) -> Result<(), ExternalError> {
+    self.write(Content::Text(text.to_string()))
+  }
+
+  pub fn read_image(&self) -> Option<RgbaImage> {
+    match &STATE.lock().unwrap().content {
+      Content::Image(image) => Some(image.clone()),
+      _ => None,
+    }
+  }
+
+  pub fn write_image(&self, image: &RgbaImage) -> Result<(), ExternalError> {
+    self.write(Content::Image(image.clone()))
+  }
+
+  pub fn read_files(&self) -> Vec<PathBuf> {
+    match &STATE.lock().unwrap().content {
+      Content::Files(files) => files.clone(),
+      _ => Vec::new(),
+    }
+  }
+
+  pub fn write_files(&self, files: &[PathBuf]) -> Result<(), ExternalError> {
+    self.write(Content::Files(files.to_vec()))
+  }
+}
+
+impl Drop for Clipboard {
+  fn drop(&mut self) {
+    STATE
+      .lock()
+      .unwrap()
+      .listeners
+      .retain(|(id, _)| *id != self.id);
+  }
+}
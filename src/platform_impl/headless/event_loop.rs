@@ -0,0 +1,685 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  cell::RefCell,
+  collections::{HashMap, VecDeque},
+  rc::Rc,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, MutexGuard,
+  },
+  time::Instant,
+};
+
+use crossbeam_channel::{Receiver, Select, Sender};
+
+use crate::{
+  accessibility::AccessibilityActionRequest,
+  dpi::{PhysicalPosition, PhysicalSize},
+  error::{ExternalError, NotSupportedError},
+  event::{Event, Ime, StartCause, TouchPhase, WindowEvent},
+  event_loop::{
+    AccessibilityPrefs, ControlFlow, DeviceEventFilter, EventLoopClosed,
+    EventLoopWindowTarget as RootELW, IdleDeadline, PowerInfo, SystemAppearance,
+  },
+  headless::{self, VirtualMonitor},
+  keyboard::{Key, KeyCode, KeyboardLayout, ModifiersState, NativeKeyCode},
+  monitor::MonitorHandle as RootMonitorHandle,
+  platform::inject::SyntheticInput,
+  window::{ProgressBarState, WindowId as RootWindowId},
+};
+
+use super::{
+  monitor::MonitorHandle,
+  window::{WindowId, WindowState},
+};
+
+#[derive(Default, Debug, Clone)]
+pub(crate) struct PlatformSpecificEventLoopAttributes {
+  pub(crate) headless: bool,
+  pub(crate) single_instance: Option<String>,
+  pub(crate) virtual_monitors: Vec<VirtualMonitor>,
+}
+
+/// An event queued for the event loop by its windows, clipboards and proxies.
+pub(crate) enum Pending {
+  /// An event of the synthetic window manager, the state of the window already follows it.
+  Window(WindowId, WindowEvent<'static>),
+  /// An event given to `EventLoopProxy::inject`, applied to the state of the window once it's
+  /// delivered.
+  Injected(WindowId, WindowEvent<'static>),
+  /// `WindowEvent::ScaleFactorChanged` with the suggested inner size.
+  ScaleFactorChanged(WindowId, f64, PhysicalSize<u32>),
+  ClipboardUpdated,
+  AccessibilityAction(WindowId, AccessibilityActionRequest),
+}
+
+/// The state of the synthetic desktop, shared by the event loop with its windows, which may live
+/// on other threads.
+pub(crate) struct Shared {
+  /// The monitors, the first one is the primary monitor. There is always at least one.
+  pub(crate) monitors: Vec<MonitorHandle>,
+  pub(crate) windows: HashMap<WindowId, WindowState>,
+  pub(crate) focused: Option<WindowId>,
+  /// The position of the cursor in desktop coordinates.
+  pub(crate) cursor_position: PhysicalPosition<f64>,
+  pub(crate) pending: VecDeque<Pending>,
+  /// The windows to deliver `Event::RedrawRequested` to, in the order of the requests.
+  pub(crate) redraws: Vec<WindowId>,
+}
+
+impl Shared {
+  pub(crate) fn primary_monitor(&self) -> MonitorHandle {
+    self.monitors[0].clone()
+  }
+
+  pub(crate) fn monitor_from_point(&self, x: f64, y: f64) -> Option<MonitorHandle> {
+    self
+      .monitors
+      .iter()
+      .find(|monitor| monitor.contains(x, y))
+      .cloned()
+  }
+
+  pub(crate) fn push(&mut self, window_id: WindowId, event: WindowEvent<'static>) {
+    self.pending.push_back(Pending::Window(window_id, event));
+  }
+
+  /// Updates the state of a window from an event given to `EventLoopProxy::inject`.
+  fn apply(&mut self, window_id: WindowId, event: &WindowEvent<'static>) {
+    let state = match self.windows.get_mut(&window_id) {
+      Some(state) => state,
+      None => return,
+    };
+    match event {
+      WindowEvent::Resized(size) => {
+        state.size = *size;
+        self.update_monitor(window_id);
+      }
+      WindowEvent::Moved(position) => {
+        state.position = *position;
+        self.update_monitor(window_id);
+      }
+      WindowEvent::CursorMoved { position, .. } => {
+        state.cursor_position = *position;
+        self.cursor_position = PhysicalPosition::new(
+          state.position.x as f64 + position.x,
+          state.position.y as f64 + position.y,
+        );
+      }
+      WindowEvent::Focused(true) => self.focused = Some(window_id),
+      WindowEvent::Focused(false) if self.focused == Some(window_id) => self.focused = None,
+      WindowEvent::Maximized(maximized) => state.maximized = *maximized,
+      WindowEvent::FullscreenChanged(fullscreen) => state.fullscreen = fullscreen.clone(),
+      WindowEvent::VisibilityChanged(visible) => state.visible = *visible,
+      WindowEvent::ThemeChanged(theme) => state.theme = *theme,
+      _ => (),
+    }
+  }
+}
+
+impl Shared {
+  /// Delivers an input given to `EventLoopExtInject::inject_input`: the keyboard goes to the
+  /// focused window, the mouse to the window under the cursor.
+  fn inject_input(&mut self, input: SyntheticInput) {
+    let device_id = headless::device_id();
+    let timestamp = Instant::now();
+    let window_id = match input {
+      SyntheticInput::Key { .. } | SyntheticInput::Text(_) => self.focused,
+      _ => self.window_at(self.cursor_position.x, self.cursor_position.y),
+    };
+    #[allow(deprecated)]
+    let event = match input {
+      SyntheticInput::Key { code, state } => WindowEvent::KeyboardInput {
+        device_id,
+        event: headless::key_event(code, Key::Unidentified(NativeKeyCode::Unidentified), state),
+        is_synthetic: false,
+        timestamp,
+      },
+      SyntheticInput::Text(text) => WindowEvent::Ime(Ime::Commit(text)),
+      SyntheticInput::CursorMoved(position) => {
+        self.cursor_position = position;
+        let window_id = match self.window_at(position.x, position.y) {
+          Some(window_id) => window_id,
+          None => return,
+        };
+        let state = self.windows.get_mut(&window_id).unwrap();
+        state.cursor_position = PhysicalPosition::new(
+          position.x - state.position.x as f64,
+          position.y - state.position.y as f64,
+        );
+        let event = WindowEvent::CursorMoved {
+          device_id,
+          position: state.cursor_position,
+          timestamp,
+          modifiers: ModifiersState::empty(),
+        };
+        return self.push(window_id, event);
+      }
+      SyntheticInput::MouseInput { button, state } => WindowEvent::MouseInput {
+        device_id,
+        state,
+        button,
+        click_count: 1,
+        timestamp,
+        modifiers: ModifiersState::empty(),
+      },
+      SyntheticInput::MouseWheel(delta) => WindowEvent::MouseWheel {
+        device_id,
+        delta,
+        phase: TouchPhase::Moved,
+        timestamp,
+        modifiers: ModifiersState::empty(),
+      },
+    };
+    if let Some(window_id) = window_id {
+      self.push(window_id, event);
+    }
+  }
+}
+
+/// The handle on the shared state given to the windows.
+pub(crate) struct Context {
+  shared: Mutex<Shared>,
+  /// Wakes the event loop up, the channel holds a single message so wakes are merged.
+  waker: Sender<()>,
+  /// Set once the event loop is dropped.
+  closed: AtomicBool,
+}
+
+impl Context {
+  pub(crate) fn lock(&self) -> MutexGuard<'_, Shared> {
+    self.shared.lock().unwrap()
+  }
+
+  /// Runs `f` on the shared state, and wakes the event loop up if it has something to deliver.
+  pub(crate) fn update<R>(&self, f: impl FnOnce(&mut Shared) -> R) -> R {
+    let mut shared = self.lock();
+    let result = f(&mut shared);
+    if !shared.pending.is_empty() || !shared.redraws.is_empty() {
+      let _ = self.waker.try_send(());
+    }
+    result
+  }
+}
+
+pub struct EventLoop<T: 'static> {
+  window_target: RootELW<T>,
+  user_tx: Sender<T>,
+}
+
+impl<T: 'static> EventLoop<T> {
+  pub(crate) fn new(attributes: &mut PlatformSpecificEventLoopAttributes) -> Self {
+    if attributes.single_instance.is_some() {
+      warn!("`EventLoopBuilder::with_single_instance` is ignored by the headless backend");
+    }
+    let mut monitors: Vec<_> = attributes
+      .virtual_monitors
+      .iter()
+      .cloned()
+      .map(|monitor| MonitorHandle { monitor })
+      .collect();
+    if monitors.is_empty() {
+      monitors.push(MonitorHandle {
+        monitor: VirtualMonitor::default(),
+      });
+    }
+    let (user_tx, user_rx) = crossbeam_channel::unbounded();
+    let (wake_tx, wake_rx) = crossbeam_channel::bounded(1);
+    let context = Arc::new(Context {
+      shared: Mutex::new(Shared {
+        monitors,
+        windows: HashMap::new(),
+        focused: None,
+        cursor_position: PhysicalPosition::new(0.0, 0.0),
+        pending: VecDeque::new(),
+        redraws: Vec::new(),
+      }),
+      waker: wake_tx,
+      closed: AtomicBool::new(false),
+    });
+
+    Self {
+      window_target: RootELW {
+        p: EventLoopWindowTarget {
+          context,
+          user_rx,
+          wake_rx,
+          headless: attributes.headless,
+          idle_callback: Default::default(),
+          _marker: std::marker::PhantomData,
+        },
+        exit: Default::default(),
+        executor: Default::default(),
+        captures: Default::default(),
+        window_motion_coalescing: Default::default(),
+        window_registry: Default::default(),
+        user_events: Default::default(),
+        _marker: std::marker::PhantomData,
+      },
+      user_tx,
+    }
+  }
+
+  pub(crate) fn try_new(
+    attributes: &mut PlatformSpecificEventLoopAttributes,
+  ) -> Result<Self, ExternalError> {
+    Ok(Self::new(attributes))
+  }
+
+  pub fn run<F>(mut self, callback: F) -> !
+  where
+    F: 'static + FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
+  {
+    let exit_code = self.run_return(callback);
+    std::process::exit(exit_code)
+  }
+
+  pub(crate) fn run_return<F>(&mut self, mut callback: F) -> i32
+  where
+    F: FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
+  {
+    let mut control_flow = ControlFlow::default();
+    let mut cause = StartCause::Init;
+
+    let exit_code = loop {
+      iterate(&self.window_target, cause, &mut callback, &mut control_flow);
+      if let ControlFlow::ExitWithCode(code) = control_flow {
+        break code;
+      }
+      cause = self.window_target.p.wait(control_flow);
+    };
+
+    callback(Event::LoopDestroyed, &self.window_target, &mut control_flow);
+    exit_code
+  }
+
+  pub fn window_target(&self) -> &RootELW<T> {
+    &self.window_target
+  }
+
+  pub fn create_proxy(&self) -> EventLoopProxy<T> {
+    EventLoopProxy {
+      user_tx: self.user_tx.clone(),
+      context: self.window_target.p.context.clone(),
+    }
+  }
+}
+
+/// Runs an iteration of the event loop, from `NewEvents` to `RedrawEventsCleared`.
+fn iterate<T>(
+  target: &RootELW<T>,
+  cause: StartCause,
+  callback: &mut dyn FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
+  control_flow: &mut ControlFlow,
+) {
+  // Wakes until now are handled by this iteration.
+  let _ = target.p.wake_rx.try_recv();
+  callback(Event::NewEvents(cause), target, control_flow);
+
+  while let Ok(event) = target.p.user_rx.try_recv() {
+    callback(Event::UserEvent(event), target, control_flow);
+  }
+  // The lock is released before calling the event handler, which may use the windows.
+  loop {
+    let pending = target.p.context.lock().pending.pop_front();
+    match pending {
+      Some(pending) => dispatch(target, pending, callback, control_flow),
+      None => break,
+    }
+  }
+  callback(Event::MainEventsCleared, target, control_flow);
+
+  let redraws = std::mem::take(&mut target.p.context.lock().redraws);
+  for window_id in redraws {
+    callback(
+      Event::RedrawRequested(RootWindowId(window_id)),
+      target,
+      control_flow,
+    );
+  }
+  callback(Event::RedrawEventsCleared, target, control_flow);
+}
+
+fn dispatch<T>(
+  target: &RootELW<T>,
+  pending: Pending,
+  callback: &mut dyn FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
+  control_flow: &mut ControlFlow,
+) {
+  let context = &target.p.context;
+  let (window_id, event) = match pending {
+    Pending::Window(window_id, event) => (window_id, event),
+    Pending::Injected(window_id, event) => {
+      context.lock().apply(window_id, &event);
+      (window_id, event)
+    }
+    Pending::ScaleFactorChanged(window_id, scale_factor, mut size) => {
+      callback(
+        Event::WindowEvent {
+          window_id: RootWindowId(window_id),
+          event: WindowEvent::ScaleFactorChanged {
+            scale_factor,
+            new_inner_size: &mut size,
+          },
+        },
+        target,
+        control_flow,
+      );
+      // The window takes the size set by the event handler, as it would on a real desktop.
+      context.update(|shared| {
+        if let Some(state) = shared.windows.get_mut(&window_id) {
+          if state.size != size {
+            state.size = size;
+            shared
+              .pending
+              .push_front(Pending::Window(window_id, WindowEvent::Resized(size)));
+          }
+        }
+      });
+      return;
+    }
+    Pending::ClipboardUpdated => {
+      callback(Event::ClipboardUpdated, target, control_flow);
+      return;
+    }
+    Pending::AccessibilityAction(window_id, request) => {
+      callback(
+        Event::AccessibilityAction {
+          window_id: RootWindowId(window_id),
+          request,
+        },
+        target,
+        control_flow,
+      );
+      return;
+    }
+  };
+  callback(
+    Event::WindowEvent {
+      window_id: RootWindowId(window_id),
+      event,
+    },
+    target,
+    control_flow,
+  );
+}
+
+impl<T> Drop for EventLoop<T> {
+  fn drop(&mut self) {
+    self
+      .window_target
+      .p
+      .context
+      .closed
+      .store(true, Ordering::Relaxed);
+  }
+}
+
+pub struct EventLoopProxy<T: 'static> {
+  user_tx: Sender<T>,
+  context: Arc<Context>,
+}
+
+impl<T: 'static> Clone for EventLoopProxy<T> {
+  fn clone(&self) -> Self {
+    EventLoopProxy {
+      user_tx: self.user_tx.clone(),
+      context: self.context.clone(),
+    }
+  }
+}
+
+impl<T: 'static> EventLoopProxy<T> {
+  pub fn send_event(&self, event: T) -> Result<(), EventLoopClosed<T>> {
+    if self.context.closed.load(Ordering::Relaxed) {
+      return Err(EventLoopClosed(event));
+    }
+    self
+      .user_tx
+      .send(event)
+      .map_err(|error| EventLoopClosed(error.0))?;
+    let _ = self.context.waker.try_send(());
+    Ok(())
+  }
+
+  pub fn inject(
+    &self,
+    window_id: WindowId,
+    event: WindowEvent<'static>,
+  ) -> Result<(), EventLoopClosed<WindowEvent<'static>>> {
+    if self.context.closed.load(Ordering::Relaxed) {
+      return Err(EventLoopClosed(event));
+    }
+    self.context.update(|shared| {
+      shared
+        .pending
+        .push_back(Pending::Injected(window_id, event))
+    });
+    Ok(())
+  }
+}
+
+/// Queues the accessibility action requests of a window with the events of the synthetic
+/// window manager.
+#[derive(Clone)]
+pub struct AccessibilityActionSender {
+  pub(crate) window_id: WindowId,
+  pub(crate) context: Arc<Context>,
+}
+
+impl AccessibilityActionSender {
+  pub fn send(
+    &self,
+    request: AccessibilityActionRequest,
+  ) -> Result<(), EventLoopClosed<AccessibilityActionRequest>> {
+    if self.context.closed.load(Ordering::Relaxed) {
+      return Err(EventLoopClosed(request));
+    }
+    self.context.update(|shared| {
+      if !shared.windows.contains_key(&self.window_id) {
+        return Err(EventLoopClosed(request));
+      }
+      shared
+        .pending
+        .push_back(Pending::AccessibilityAction(self.window_id, request));
+      Ok(())
+    })
+  }
+}
+
+#[derive(Clone)]
+pub struct EventLoopWaker {
+  context: Arc<Context>,
+}
+
+impl EventLoopWaker {
+  pub fn wake(&self) {
+    let _ = self.context.waker.try_send(());
+  }
+}
+
+#[derive(Clone)]
+pub struct EventLoopWindowTarget<T> {
+  pub(crate) context: Arc<Context>,
+  user_rx: Receiver<T>,
+  wake_rx: Receiver<()>,
+  /// Whether the event loop was built with `EventLoopBuilder::with_headless`
+  pub(crate) headless: bool,
+  /// See `set_idle_callback`
+  idle_callback: Rc<RefCell<Option<Box<dyn FnMut(IdleDeadline)>>>>,
+  _marker: std::marker::PhantomData<T>,
+}
+
+impl<T> EventLoopWindowTarget<T> {
+  fn has_events(&self) -> bool {
+    let shared = self.context.lock();
+    !self.user_rx.is_empty() || !shared.pending.is_empty() || !shared.redraws.is_empty()
+  }
+
+  /// Waits as `control_flow` asks and returns the cause of the next iteration. Only
+  /// `ControlFlow::Wait` blocks, until another thread sends an event or wakes the event loop up.
+  fn wait(&self, control_flow: ControlFlow) -> StartCause {
+    let start = Instant::now();
+    if control_flow == ControlFlow::Poll {
+      return StartCause::Poll;
+    }
+    let requested_resume = match control_flow {
+      ControlFlow::WaitUntil(requested_resume) => Some(requested_resume),
+      _ => None,
+    };
+    if self.has_events() {
+      return StartCause::WaitCancelled {
+        start,
+        requested_resume,
+      };
+    }
+
+    if let Some(deadline) = IdleDeadline::new(control_flow) {
+      if let Some(idle_callback) = &mut *self.idle_callback.borrow_mut() {
+        idle_callback(deadline);
+      }
+    }
+    match requested_resume {
+      Some(requested_resume) if !self.has_events() => StartCause::ResumeTimeReached {
+        start,
+        requested_resume,
+        resumed: Instant::now(),
+      },
+      _ => {
+        if !self.has_events() && self.wake_rx.is_empty() {
+          let mut select = Select::new();
+          select.recv(&self.user_rx);
+          select.recv(&self.wake_rx);
+          select.ready();
+        }
+        StartCause::WaitCancelled {
+          start,
+          requested_resume,
+        }
+      }
+    }
+  }
+
+  pub fn create_waker(&self) -> EventLoopWaker {
+    EventLoopWaker {
+      context: self.context.clone(),
+    }
+  }
+
+  #[inline]
+  pub fn is_headless(&self) -> bool {
+    self.headless
+  }
+
+  #[inline]
+  pub fn has_window(&self, window_id: WindowId) -> bool {
+    self.context.lock().windows.contains_key(&window_id)
+  }
+
+  #[inline]
+  pub fn primary_monitor(&self) -> Option<RootMonitorHandle> {
+    Some(RootMonitorHandle {
+      inner: self.context.lock().primary_monitor(),
+    })
+  }
+
+  #[inline]
+  pub fn monitor_from_point(&self, x: f64, y: f64) -> Option<MonitorHandle> {
+    self.context.lock().monitor_from_point(x, y)
+  }
+
+  #[inline]
+  pub fn available_monitors(&self) -> VecDeque<MonitorHandle> {
+    self.context.lock().monitors.iter().cloned().collect()
+  }
+
+  #[cfg(feature = "rwh_05")]
+  #[inline]
+  pub fn raw_display_handle_rwh_05(&self) -> rwh_05::RawDisplayHandle {
+    rwh_05::RawDisplayHandle::Web(rwh_05::WebDisplayHandle::empty())
+  }
+
+  #[cfg(feature = "rwh_06")]
+  #[inline]
+  pub fn raw_display_handle_rwh_06(&self) -> Result<rwh_06::RawDisplayHandle, rwh_06::HandleError> {
+    Err(rwh_06::HandleError::NotSupported)
+  }
+
+  pub fn cursor_position(&self) -> Result<PhysicalPosition<f64>, ExternalError> {
+    Ok(self.context.lock().cursor_position)
+  }
+
+  pub fn set_device_event_filter(&self, _filter: DeviceEventFilter) -> Result<(), ExternalError> {
+    Ok(())
+  }
+
+  pub fn accessibility_preferences(&self) -> AccessibilityPrefs {
+    AccessibilityPrefs::default()
+  }
+
+  pub fn system_appearance(&self) -> SystemAppearance {
+    SystemAppearance::default()
+  }
+
+  pub fn power_info(&self) -> PowerInfo {
+    PowerInfo::default()
+  }
+
+  pub fn set_progress_bar(&self, _progress: ProgressBarState) {}
+
+  pub fn set_idle_callback(&self, callback: Option<Box<dyn FnMut(IdleDeadline)>>) {
+    *self.idle_callback.borrow_mut() = callback;
+  }
+
+  pub fn run_nested_until(
+    &self,
+    target: &RootELW<T>,
+    predicate: &mut dyn FnMut() -> bool,
+    handler: &mut dyn FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
+  ) -> Result<(), ExternalError> {
+    let mut control_flow = ControlFlow::Wait;
+    let mut cause = StartCause::WaitCancelled {
+      start: Instant::now(),
+      requested_resume: None,
+    };
+    while !predicate() {
+      iterate(target, cause, handler, &mut control_flow);
+      if let ControlFlow::ExitWithCode(_) = control_flow {
+        break;
+      }
+      if predicate() {
+        break;
+      }
+      cause = self.wait(control_flow);
+    }
+    Ok(())
+  }
+
+  pub fn key_for_keycode(&self, _code: KeyCode) -> Option<Key<'static>> {
+    None
+  }
+
+  pub fn keyboard_layout(&self) -> Option<KeyboardLayout> {
+    None
+  }
+
+  pub fn inject_input(&self, input: SyntheticInput) -> Result<(), ExternalError> {
+    self.context.update(|shared| shared.inject_input(input));
+    Ok(())
+  }
+
+  pub fn register_global_shortcut(
+    &self,
+    _id: u32,
+    _modifiers: ModifiersState,
+    _key: KeyCode,
+  ) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn unregister_global_shortcut(&self, _id: u32) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+}
@@ -0,0 +1,174 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! The synthetic backend of `tao-headless`, see the `headless` module for its behavior.
+
+mod clipboard;
+mod event_loop;
+// The scancodes are the X11 keycodes, like with the Linux backend.
+#[path = "../linux/keycode.rs"]
+mod keycode;
+mod monitor;
+mod window;
+
+pub use clipboard::Clipboard;
+pub(crate) use event_loop::PlatformSpecificEventLoopAttributes;
+pub use event_loop::{
+  AccessibilityActionSender, EventLoop, EventLoopProxy, EventLoopWaker, EventLoopWindowTarget,
+};
+pub use keycode::{keycode_from_scancode, keycode_to_scancode};
+pub use monitor::{MonitorHandle, VideoMode};
+pub use window::{Window, WindowId};
+
+use crate::{error, keyboard::Key};
+
+pub(crate) use crate::icon::NoIcon as PlatformIcon;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct KeyEventExtra {
+  pub text_with_all_modifiers: Option<&'static str>,
+  pub key_without_modifiers: Key<'static>,
+}
+
+/// The parent of a window, only used to build the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Parent;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlatformSpecificWindowBuilderAttributes {
+  pub parent: Parent,
+}
+
+#[derive(Debug, Clone)]
+pub struct OsError;
+
+impl std::fmt::Display for OsError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str("Headless OS Error")
+  }
+}
+
+/// The only device, the one of the injected input events.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DeviceId;
+
+impl DeviceId {
+  pub fn dummy() -> Self {
+    DeviceId
+  }
+}
+
+/// The synthetic backend has no notifications.
+pub enum Notification {}
+
+impl Notification {
+  pub fn new<T>(
+    _window_target: &EventLoopWindowTarget<T>,
+    _id: u32,
+    _attributes: crate::notification::NotificationAttributes,
+  ) -> Result<Self, error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn close(&self) {
+    match *self {}
+  }
+}
+
+/// The synthetic backend has no system tray.
+pub enum SystemTray {}
+
+impl SystemTray {
+  pub fn new<T>(
+    _window_target: &EventLoopWindowTarget<T>,
+    _id: u32,
+    _icon: crate::icon::Icon,
+    _tooltip: Option<String>,
+  ) -> Result<Self, error::ExternalError> {
+    Err(error::ExternalError::NotSupported(
+      error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_icon(&self, _icon: crate::icon::Icon) {
+    match *self {}
+  }
+
+  pub fn set_tooltip(&self, _tooltip: Option<&str>) {
+    match *self {}
+  }
+
+  pub fn set_menu(&self, _menu: Option<&Submenu>) {
+    match *self {}
+  }
+}
+
+/// Menus are built but never shown.
+pub struct Menu;
+
+impl Menu {
+  pub fn new() -> Self {
+    Menu
+  }
+
+  pub fn append_submenu(&self, _submenu: &Submenu) {}
+}
+
+pub struct Submenu;
+
+impl Submenu {
+  pub fn new(_title: &str) -> Self {
+    Submenu
+  }
+
+  pub fn append_item(&self, _item: &MenuItem) {}
+
+  pub fn append_submenu(&self, _submenu: &Submenu) {}
+
+  pub fn append_separator(&self) {}
+
+  pub fn set_title(&self, _title: &str) {}
+
+  pub fn set_enabled(&self, _enabled: bool) {}
+}
+
+/// A menu item, which keeps its checked state since nothing else can change it.
+pub struct MenuItem {
+  checked: std::sync::atomic::AtomicBool,
+}
+
+impl MenuItem {
+  pub fn new(_id: u32, _title: &str, checked: Option<bool>) -> Self {
+    MenuItem {
+      checked: checked.unwrap_or(false).into(),
+    }
+  }
+
+  pub fn set_title(&self, _title: &str) {}
+
+  pub fn set_enabled(&self, _enabled: bool) {}
+
+  pub fn set_checked(&self, checked: bool) {
+    self
+      .checked
+      .store(checked, std::sync::atomic::Ordering::Relaxed)
+  }
+
+  pub fn is_checked(&self) -> bool {
+    self.checked.load(std::sync::atomic::Ordering::Relaxed)
+  }
+}
+
+/// Nothing sleeps, so the assertion does nothing.
+pub struct PowerAssertion;
+
+impl PowerAssertion {
+  pub fn keep_display_awake<T>(
+    _window_target: &EventLoopWindowTarget<T>,
+    _reason: &str,
+  ) -> Result<Self, error::ExternalError> {
+    Ok(PowerAssertion)
+  }
+}
@@ -0,0 +1,126 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  cmp::Ordering,
+  hash::{Hash, Hasher},
+};
+
+use crate::{
+  dpi::{PhysicalPosition, PhysicalSize},
+  error::{ExternalError, NotSupportedError},
+  headless::VirtualMonitor,
+  monitor,
+  window::RgbaImage,
+};
+
+/// A monitor of the event loop, identified by its name.
+#[derive(Debug, Clone)]
+pub struct MonitorHandle {
+  pub(crate) monitor: VirtualMonitor,
+}
+
+impl PartialEq for MonitorHandle {
+  fn eq(&self, other: &Self) -> bool {
+    self.monitor.name == other.monitor.name
+  }
+}
+
+impl Eq for MonitorHandle {}
+
+impl PartialOrd for MonitorHandle {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for MonitorHandle {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.monitor.name.cmp(&other.monitor.name)
+  }
+}
+
+impl Hash for MonitorHandle {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.monitor.name.hash(state)
+  }
+}
+
+impl MonitorHandle {
+  pub fn name(&self) -> Option<String> {
+    Some(self.monitor.name.clone())
+  }
+
+  pub fn id(&self) -> u64 {
+    crate::monitor::stable_id(self.monitor.name.as_bytes())
+  }
+
+  pub fn size(&self) -> PhysicalSize<u32> {
+    self.monitor.size
+  }
+
+  pub fn position(&self) -> PhysicalPosition<i32> {
+    self.monitor.position
+  }
+
+  pub fn scale_factor(&self) -> f64 {
+    self.monitor.scale_factor
+  }
+
+  pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+    Some(u32::from(self.monitor.refresh_rate) * 1000)
+  }
+
+  pub fn capture(&self) -> Result<RgbaImage, ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn video_modes(&self) -> impl Iterator<Item = monitor::VideoMode> {
+    std::iter::once(monitor::VideoMode {
+      video_mode: VideoMode {
+        size: self.size().into(),
+        bit_depth: 32,
+        refresh_rate: self.monitor.refresh_rate,
+        monitor: self.clone(),
+      },
+    })
+  }
+
+  /// Returns whether the point, in desktop coordinates, is on the monitor.
+  pub(crate) fn contains(&self, x: f64, y: f64) -> bool {
+    let position = self.position();
+    let size = self.size();
+    x >= position.x as f64
+      && y >= position.y as f64
+      && x < position.x as f64 + size.width as f64
+      && y < position.y as f64 + size.height as f64
+  }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct VideoMode {
+  pub(crate) size: (u32, u32),
+  pub(crate) bit_depth: u16,
+  pub(crate) refresh_rate: u16,
+  pub(crate) monitor: MonitorHandle,
+}
+
+impl VideoMode {
+  pub fn size(&self) -> PhysicalSize<u32> {
+    self.size.into()
+  }
+
+  pub fn bit_depth(&self) -> u16 {
+    self.bit_depth
+  }
+
+  pub fn refresh_rate(&self) -> u16 {
+    self.refresh_rate
+  }
+
+  pub fn monitor(&self) -> monitor::MonitorHandle {
+    monitor::MonitorHandle {
+      inner: self.monitor.clone(),
+    }
+  }
+}
@@ -0,0 +1,859 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{
+  atomic::{AtomicU64, Ordering},
+  Arc,
+};
+
+use crate::{
+  dpi::{LogicalSize, PhysicalPosition, PhysicalSize, Position, Rect, Size},
+  error::{self, ExternalError, NotSupportedError, WindowOperationError},
+  event::WindowEvent,
+  icon::{Icon, RgbaIcon},
+  monitor,
+  window::{
+    ActivationToken, Color, CursorGrabMode, CursorIcon, CursorKind, DragData, DragImage,
+    FocusResult, Fullscreen, HitTestCallback, ImePurpose, ProgressBarState, ResizeDirection,
+    RgbaImage, Theme, UserAttentionType, WindowAttributes, WindowLevel, WindowShape,
+    WindowSizeConstraints,
+  },
+};
+
+use super::{
+  event_loop::{AccessibilityActionSender, Context, Pending, Shared},
+  monitor::MonitorHandle,
+  EventLoopWindowTarget, Menu, Parent, PlatformSpecificWindowBuilderAttributes, Submenu,
+};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct WindowId(u64);
+
+impl WindowId {
+  pub fn dummy() -> Self {
+    WindowId(0)
+  }
+
+  pub fn into_raw(self) -> u64 {
+    self.0
+  }
+
+  pub fn from_raw(raw: u64) -> Self {
+    WindowId(raw)
+  }
+}
+
+/// The state of a window, which stands for the window itself.
+pub(crate) struct WindowState {
+  pub(crate) title: String,
+  pub(crate) position: PhysicalPosition<i32>,
+  pub(crate) size: PhysicalSize<u32>,
+  /// The position and size to go back to when leaving the maximized or fullscreen state.
+  restore: Option<(PhysicalPosition<i32>, PhysicalSize<u32>)>,
+  constraints: WindowSizeConstraints,
+  monitor: MonitorHandle,
+  pub(crate) scale_factor: f64,
+  pub(crate) visible: bool,
+  minimized: bool,
+  pub(crate) maximized: bool,
+  pub(crate) fullscreen: Option<Fullscreen>,
+  resizable: bool,
+  minimizable: bool,
+  maximizable: bool,
+  closable: bool,
+  decorations: bool,
+  window_level: WindowLevel,
+  preferred_theme: Option<Theme>,
+  pub(crate) theme: Theme,
+  opacity: f32,
+  background_color: Option<Color>,
+  cursor_icon: CursorIcon,
+  /// Set by `set_custom_cursor`, until the next `set_cursor_icon`.
+  custom_cursor: bool,
+  cursor_visible: bool,
+  /// The position of the cursor relative to the window.
+  pub(crate) cursor_position: PhysicalPosition<f64>,
+  attention_requested: bool,
+  skip_taskbar: bool,
+  visible_on_all_workspaces: bool,
+}
+
+impl WindowState {
+  fn clamp_size(&self, size: Size) -> PhysicalSize<u32> {
+    Size::clamp(
+      size,
+      self
+        .constraints
+        .min_size_physical::<u32>(self.scale_factor)
+        .into(),
+      self
+        .constraints
+        .max_size_physical::<u32>(self.scale_factor)
+        .into(),
+      self.scale_factor,
+    )
+    .to_physical(self.scale_factor)
+  }
+
+  /// Whether the window fills its monitor, so its geometry is the one of the monitor.
+  fn fills_monitor(&self) -> bool {
+    self.maximized || self.fullscreen.is_some()
+  }
+}
+
+impl Shared {
+  /// Moves and resizes a window, and queues the events for the changes.
+  fn set_outer_rect(
+    &mut self,
+    window_id: WindowId,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+  ) {
+    let state = match self.windows.get_mut(&window_id) {
+      Some(state) => state,
+      None => return,
+    };
+    let moved = state.position != position;
+    let resized = state.size != size;
+    state.position = position;
+    state.size = size;
+    if moved {
+      self.push(window_id, WindowEvent::Moved(position));
+    }
+    if resized {
+      self.push(window_id, WindowEvent::Resized(size));
+    }
+    if moved || resized {
+      self.update_monitor(window_id);
+    }
+  }
+
+  /// Moves a window to the monitor containing its center, and queues
+  /// `WindowEvent::ScaleFactorChanged` and `WindowEvent::MonitorChanged` if it changed monitor.
+  pub(crate) fn update_monitor(&mut self, window_id: WindowId) {
+    let (x, y) = match self.windows.get(&window_id) {
+      Some(state) => (
+        state.position.x as f64 + state.size.width as f64 / 2.,
+        state.position.y as f64 + state.size.height as f64 / 2.,
+      ),
+      None => return,
+    };
+    let monitor = match self.monitor_from_point(x, y) {
+      Some(monitor) => monitor,
+      None => return,
+    };
+    let state = self.windows.get_mut(&window_id).unwrap();
+    if state.monitor == monitor {
+      return;
+    }
+    state.monitor = monitor.clone();
+    let scale_factor = monitor.scale_factor();
+    if scale_factor != state.scale_factor {
+      let size = state
+        .size
+        .to_logical::<f64>(state.scale_factor)
+        .to_physical(scale_factor);
+      state.scale_factor = scale_factor;
+      self
+        .pending
+        .push_back(Pending::ScaleFactorChanged(window_id, scale_factor, size));
+    }
+    self.push(
+      window_id,
+      WindowEvent::MonitorChanged(monitor::MonitorHandle { inner: monitor }),
+    );
+  }
+
+  /// The window under a point of the desktop, the focused one being above the others.
+  pub(crate) fn window_at(&self, x: f64, y: f64) -> Option<WindowId> {
+    let contains = |state: &WindowState| {
+      state.visible
+        && !state.minimized
+        && x >= state.position.x as f64
+        && y >= state.position.y as f64
+        && x < state.position.x as f64 + state.size.width as f64
+        && y < state.position.y as f64 + state.size.height as f64
+    };
+    self
+      .focused
+      .filter(|id| self.windows.get(id).map_or(false, contains))
+      .or_else(|| {
+        self
+          .windows
+          .iter()
+          .filter(|(_, state)| contains(state))
+          .map(|(id, _)| *id)
+          .min()
+      })
+  }
+
+  /// Gives the focus to a window, or takes it from the focused one with `None`.
+  fn set_focus(&mut self, window_id: Option<WindowId>) {
+    if self.focused == window_id {
+      return;
+    }
+    if let Some(focused) = self.focused.take() {
+      self.push(focused, WindowEvent::Focused(false));
+    }
+    if let Some(window_id) = window_id {
+      self.focused = Some(window_id);
+      self.push(window_id, WindowEvent::Focused(true));
+    }
+  }
+
+  /// Applies the maximized and fullscreen states of a window to its geometry.
+  fn update_fill(&mut self, window_id: WindowId) {
+    let state = match self.windows.get_mut(&window_id) {
+      Some(state) => state,
+      None => return,
+    };
+    let (position, size) = if state.fills_monitor() {
+      let monitor = match &state.fullscreen {
+        Some(Fullscreen::Exclusive(video_mode)) => video_mode.video_mode.monitor.clone(),
+        Some(Fullscreen::Borderless(Some(monitor))) => monitor.inner.clone(),
+        _ => state.monitor.clone(),
+      };
+      if state.restore.is_none() {
+        state.restore = Some((state.position, state.size));
+      }
+      (monitor.position(), monitor.size())
+    } else {
+      match state.restore.take() {
+        Some(restore) => restore,
+        None => return,
+      }
+    };
+    self.set_outer_rect(window_id, position, size);
+  }
+}
+
+static WINDOW_ID: AtomicU64 = AtomicU64::new(1);
+
+pub struct Window {
+  id: WindowId,
+  context: Arc<Context>,
+  /// Whether the window belongs to a headless event loop, so it's never shown.
+  headless: bool,
+}
+
+impl Window {
+  pub fn new<T: 'static>(
+    event_loop_window_target: &EventLoopWindowTarget<T>,
+    attributes: WindowAttributes,
+    _pl_attribs: PlatformSpecificWindowBuilderAttributes,
+  ) -> Result<Self, error::OsError> {
+    let id = WindowId(WINDOW_ID.fetch_add(1, Ordering::Relaxed));
+    let context = event_loop_window_target.context.clone();
+
+    context.update(|shared| {
+      let monitor = shared.primary_monitor();
+      let scale_factor = monitor.scale_factor();
+      let position = attributes
+        .position
+        .map(|position| position.to_physical(scale_factor))
+        .unwrap_or_else(|| monitor.position());
+      let monitor = shared
+        .monitor_from_point(position.x as f64, position.y as f64)
+        .unwrap_or(monitor);
+      let scale_factor = monitor.scale_factor();
+      let theme = attributes.preferred_theme.unwrap_or(Theme::Light);
+      let visible = attributes.visible && !event_loop_window_target.headless;
+
+      let mut state = WindowState {
+        title: attributes.title,
+        position,
+        size: PhysicalSize::new(0, 0),
+        restore: None,
+        constraints: attributes.inner_size_constraints,
+        monitor,
+        scale_factor,
+        visible,
+        minimized: false,
+        maximized: attributes.maximized,
+        fullscreen: attributes.fullscreen,
+        resizable: attributes.resizable,
+        minimizable: attributes.minimizable,
+        maximizable: attributes.maximizable,
+        closable: attributes.closable,
+        decorations: attributes.decorations,
+        window_level: attributes.window_level,
+        preferred_theme: attributes.preferred_theme,
+        theme,
+        opacity: attributes.opacity,
+        background_color: attributes.background_color,
+        cursor_icon: CursorIcon::Default,
+        custom_cursor: false,
+        cursor_visible: true,
+        cursor_position: PhysicalPosition::new(0., 0.),
+        attention_requested: false,
+        skip_taskbar: attributes.skip_taskbar,
+        visible_on_all_workspaces: attributes.visible_on_all_workspaces,
+      };
+      state.size = state.clamp_size(
+        attributes
+          .inner_size
+          .unwrap_or_else(|| LogicalSize::new(800, 600).into()),
+      );
+      shared.windows.insert(id, state);
+      // The window is created with its final geometry, without events.
+      let pending = shared.pending.len();
+      shared.update_fill(id);
+      shared.pending.truncate(pending);
+
+      if visible && attributes.focused {
+        shared.set_focus(Some(id));
+      }
+    });
+
+    Ok(Self {
+      id,
+      context,
+      headless: event_loop_window_target.headless,
+    })
+  }
+
+  pub fn id(&self) -> WindowId {
+    self.id
+  }
+
+  /// Runs `f` on the state of the window and the shared state.
+  fn update<R>(&self, f: impl FnOnce(&mut Shared, WindowId) -> R) -> R {
+    self.context.update(|shared| f(shared, self.id))
+  }
+
+  /// Reads the state of the window.
+  fn with_state<R>(&self, f: impl FnOnce(&WindowState) -> R) -> R {
+    f(&self.context.lock().windows[&self.id])
+  }
+
+  /// Changes the state of the window, without any event.
+  fn set_state(&self, f: impl FnOnce(&mut WindowState)) {
+    f(self.context.lock().windows.get_mut(&self.id).unwrap())
+  }
+
+  pub(crate) fn as_parent(&self) -> Parent {
+    Parent
+  }
+
+  pub fn is_wayland(&self) -> bool {
+    false
+  }
+
+  pub fn primary_monitor(&self) -> Option<monitor::MonitorHandle> {
+    Some(monitor::MonitorHandle {
+      inner: self.context.lock().primary_monitor(),
+    })
+  }
+
+  pub fn available_monitors(&self) -> std::collections::VecDeque<MonitorHandle> {
+    self.context.lock().monitors.iter().cloned().collect()
+  }
+
+  #[inline]
+  pub fn monitor_from_point(&self, x: f64, y: f64) -> Option<monitor::MonitorHandle> {
+    self
+      .context
+      .lock()
+      .monitor_from_point(x, y)
+      .map(|inner| monitor::MonitorHandle { inner })
+  }
+
+  pub fn current_monitor(&self) -> Option<monitor::MonitorHandle> {
+    Some(monitor::MonitorHandle {
+      inner: self.with_state(|state| state.monitor.clone()),
+    })
+  }
+
+  pub fn scale_factor(&self) -> f64 {
+    self.with_state(|state| state.scale_factor)
+  }
+
+  pub fn request_redraw(&self) {
+    self.update(|shared, id| {
+      if !shared.redraws.contains(&id) {
+        shared.redraws.push(id);
+      }
+    })
+  }
+
+  pub fn request_redraw_synced(&self) {
+    self.request_redraw()
+  }
+
+  pub fn request_frame_callbacks(&self, _enabled: bool) {}
+
+  pub fn inner_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
+    self.outer_position()
+  }
+
+  pub fn outer_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
+    Ok(self.with_state(|state| state.position))
+  }
+
+  pub fn set_outer_position(&self, position: Position) {
+    self.update(|shared, id| {
+      let state = &shared.windows[&id];
+      if state.fills_monitor() {
+        return;
+      }
+      let size = state.size;
+      let position = position.to_physical(state.scale_factor);
+      shared.set_outer_rect(id, position, size);
+    })
+  }
+
+  pub fn inner_size(&self) -> PhysicalSize<u32> {
+    self.with_state(|state| state.size)
+  }
+
+  pub fn set_inner_size(&self, size: Size) -> Result<(), WindowOperationError> {
+    self.update(|shared, id| {
+      let state = &shared.windows[&id];
+      if state.fills_monitor() {
+        return;
+      }
+      let position = state.position;
+      let size = state.clamp_size(size);
+      shared.set_outer_rect(id, position, size);
+    });
+    Ok(())
+  }
+
+  pub fn outer_size(&self) -> PhysicalSize<u32> {
+    self.inner_size()
+  }
+
+  pub fn set_min_inner_size(&self, min_size: Option<Size>) -> Result<(), ExternalError> {
+    let mut constraints = self.with_state(|state| state.constraints);
+    constraints.min_width = min_size.map(|size| size.width());
+    constraints.min_height = min_size.map(|size| size.height());
+    self.set_inner_size_constraints(constraints)
+  }
+
+  pub fn set_max_inner_size(&self, max_size: Option<Size>) -> Result<(), ExternalError> {
+    let mut constraints = self.with_state(|state| state.constraints);
+    constraints.max_width = max_size.map(|size| size.width());
+    constraints.max_height = max_size.map(|size| size.height());
+    self.set_inner_size_constraints(constraints)
+  }
+
+  pub fn set_inner_size_constraints(
+    &self,
+    constraints: WindowSizeConstraints,
+  ) -> Result<(), ExternalError> {
+    constraints.validate(self.scale_factor())?;
+    self.update(|shared, id| {
+      let state = shared.windows.get_mut(&id).unwrap();
+      state.constraints = constraints;
+      if state.fills_monitor() {
+        return;
+      }
+      let position = state.position;
+      let size = state.clamp_size(state.size.into());
+      shared.set_outer_rect(id, position, size);
+    });
+    Ok(())
+  }
+
+  pub fn set_title(&self, title: &str) {
+    self.set_state(|state| state.title = title.to_string())
+  }
+
+  pub fn title(&self) -> String {
+    self.with_state(|state| state.title.clone())
+  }
+
+  pub fn set_visible(&self, visible: bool) {
+    if visible && self.headless {
+      return;
+    }
+    self.update(|shared, id| {
+      let state = shared.windows.get_mut(&id).unwrap();
+      if state.visible == visible {
+        return;
+      }
+      state.visible = visible;
+      shared.push(id, WindowEvent::VisibilityChanged(visible));
+      if !visible && shared.focused == Some(id) {
+        shared.set_focus(None);
+      }
+    })
+  }
+
+  pub fn close(&self) {
+    self.update(|shared, id| shared.push(id, WindowEvent::CloseRequested))
+  }
+
+  pub fn set_focus(&self) {
+    self.update(|shared, id| {
+      let state = shared.windows.get_mut(&id).unwrap();
+      if !state.visible {
+        return;
+      }
+      state.minimized = false;
+      shared.set_focus(Some(id));
+    })
+  }
+
+  pub fn set_focus_with_token(&self, _token: Option<ActivationToken>) -> FocusResult {
+    self.set_focus();
+    if self.is_focused() {
+      FocusResult::Focused
+    } else {
+      FocusResult::Failed
+    }
+  }
+
+  pub fn is_focused(&self) -> bool {
+    self.context.lock().focused == Some(self.id)
+  }
+
+  pub fn set_resizable(&self, resizable: bool) {
+    self.set_state(|state| state.resizable = resizable)
+  }
+
+  pub fn set_minimizable(&self, minimizable: bool) {
+    self.set_state(|state| state.minimizable = minimizable)
+  }
+
+  pub fn set_maximizable(&self, maximizable: bool) {
+    self.set_state(|state| state.maximizable = maximizable)
+  }
+
+  pub fn set_closable(&self, closable: bool) {
+    self.set_state(|state| state.closable = closable)
+  }
+
+  pub fn set_minimized(&self, minimized: bool) {
+    self.update(|shared, id| {
+      shared.windows.get_mut(&id).unwrap().minimized = minimized;
+      if minimized && shared.focused == Some(id) {
+        shared.set_focus(None);
+      }
+    })
+  }
+
+  pub fn set_maximized(&self, maximized: bool) {
+    self.update(|shared, id| {
+      let state = shared.windows.get_mut(&id).unwrap();
+      if state.maximized == maximized {
+        return;
+      }
+      state.maximized = maximized;
+      shared.push(id, WindowEvent::Maximized(maximized));
+      shared.update_fill(id);
+    })
+  }
+
+  pub fn is_maximized(&self) -> bool {
+    self.with_state(|state| state.maximized)
+  }
+
+  pub fn is_minimized(&self) -> bool {
+    self.with_state(|state| state.minimized)
+  }
+
+  pub fn is_visible(&self) -> bool {
+    self.with_state(|state| state.visible)
+  }
+
+  pub fn is_resizable(&self) -> bool {
+    self.with_state(|state| state.resizable)
+  }
+
+  pub fn is_minimizable(&self) -> bool {
+    self.with_state(|state| state.minimizable)
+  }
+
+  pub fn is_maximizable(&self) -> bool {
+    self.with_state(|state| state.maximizable)
+  }
+
+  pub fn is_closable(&self) -> bool {
+    self.with_state(|state| state.closable)
+  }
+
+  pub fn is_decorated(&self) -> bool {
+    self.with_state(|state| state.decorations)
+  }
+
+  pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) -> Result<(), WindowOperationError> {
+    self.update(|shared, id| {
+      let state = shared.windows.get_mut(&id).unwrap();
+      if state.fullscreen == fullscreen {
+        return;
+      }
+      state.fullscreen = fullscreen.clone();
+      shared.push(id, WindowEvent::FullscreenChanged(fullscreen));
+      shared.update_fill(id);
+    });
+    Ok(())
+  }
+
+  pub fn fullscreen(&self) -> Option<Fullscreen> {
+    self.with_state(|state| state.fullscreen.clone())
+  }
+
+  pub fn set_decorations(&self, decorations: bool) {
+    self.set_state(|state| state.decorations = decorations)
+  }
+
+  pub fn raise(&self) -> Result<(), ExternalError> {
+    Ok(())
+  }
+
+  pub fn lower(&self) -> Result<(), ExternalError> {
+    Ok(())
+  }
+
+  pub fn restack_above(&self, _sibling: &Window) -> Result<(), ExternalError> {
+    Ok(())
+  }
+
+  pub fn set_parent(&self, _parent: Option<&Window>) {}
+
+  pub fn set_always_on_bottom(&self, always_on_bottom: bool) {
+    self.set_window_level(if always_on_bottom {
+      WindowLevel::AlwaysOnBottom
+    } else {
+      WindowLevel::Normal
+    })
+  }
+
+  pub fn set_always_on_top(&self, always_on_top: bool) -> Result<(), WindowOperationError> {
+    self.set_window_level(if always_on_top {
+      WindowLevel::AlwaysOnTop
+    } else {
+      WindowLevel::Normal
+    });
+    Ok(())
+  }
+
+  pub fn set_window_level(&self, level: WindowLevel) {
+    self.set_state(|state| state.window_level = level)
+  }
+
+  pub fn set_window_icon(&self, _window_icon: Option<Icon>) {}
+
+  pub fn set_menu(&self, _menu: Option<&Menu>) {}
+
+  pub fn show_context_menu(&self, _menu: &Submenu, _position: Position) {}
+
+  pub fn set_ime_cursor_area(&self, _area: Rect) {}
+
+  pub fn set_ime_allowed(&self, _allowed: bool) {}
+
+  pub fn set_ime_purpose(&self, _purpose: ImePurpose) -> Result<(), ExternalError> {
+    Ok(())
+  }
+
+  pub fn set_ime_visible(&self, _visible: bool) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_progress_bar(&self, _progress: ProgressBarState) {}
+
+  pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+    self.set_state(|state| state.attention_requested = request_type.is_some())
+  }
+
+  pub fn is_attention_requested(&self) -> bool {
+    self.with_state(|state| state.attention_requested)
+  }
+
+  pub fn reset_dead_keys(&self) {}
+
+  pub fn set_cursor_icon(&self, cursor: CursorIcon) {
+    self.set_state(|state| {
+      state.cursor_icon = cursor;
+      state.custom_cursor = false;
+    })
+  }
+
+  pub fn cursor_icon(&self) -> CursorKind {
+    self.with_state(|state| {
+      if !state.cursor_visible {
+        CursorKind::Hidden
+      } else if state.custom_cursor {
+        CursorKind::Custom
+      } else {
+        CursorKind::Icon(state.cursor_icon)
+      }
+    })
+  }
+
+  pub fn set_custom_cursor(
+    &self,
+    _icon: RgbaIcon,
+    _hotspot: PhysicalPosition<u32>,
+  ) -> Result<(), ExternalError> {
+    self.set_state(|state| state.custom_cursor = true);
+    Ok(())
+  }
+
+  pub fn set_cursor_position(&self, position: Position) -> Result<(), ExternalError> {
+    let mut shared = self.context.lock();
+    let state = shared.windows.get_mut(&self.id).unwrap();
+    let position = position.to_physical::<f64>(state.scale_factor);
+    state.cursor_position = position;
+    shared.cursor_position = PhysicalPosition::new(
+      state.position.x as f64 + position.x,
+      state.position.y as f64 + position.y,
+    );
+    Ok(())
+  }
+
+  pub fn set_cursor_grab(&self, _mode: CursorGrabMode) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_cursor_visible(&self, visible: bool) {
+    self.set_state(|state| state.cursor_visible = visible)
+  }
+
+  pub fn drag_window(&self) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn drag_resize_window(&self, _direction: ResizeDirection) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn start_drag(&self, _data: DragData, _image: DragImage) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_ignore_cursor_events(&self, _ignore: bool) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_cursor_hittest_region(&self, _region: Option<&[Rect]>) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn accessibility_action_sender(&self) -> Result<AccessibilityActionSender, ExternalError> {
+    Ok(AccessibilityActionSender {
+      window_id: self.id,
+      context: self.context.clone(),
+    })
+  }
+
+  pub fn set_hit_test_callback(
+    &self,
+    _callback: Option<HitTestCallback>,
+  ) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_shape(&self, _shape: Option<WindowShape>) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_minimize_target(&self, _target: Option<Rect>) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  /// The windows have no content, so the capture is filled with the background color, which is
+  /// transparent without one.
+  pub fn capture(&self, _outer: bool) -> Result<RgbaImage, ExternalError> {
+    let (size, minimized, color) =
+      self.with_state(|state| (state.size, state.minimized, state.background_color));
+    if minimized {
+      return Err(ExternalError::InvalidInput(
+        "a minimized window can't be captured",
+      ));
+    }
+    let color = color.unwrap_or_default();
+    Ok(RgbaImage {
+      width: size.width,
+      height: size.height,
+      stride: size.width * 4,
+      data: [color.r, color.g, color.b, color.a].repeat((size.width * size.height) as usize),
+    })
+  }
+
+  pub fn set_skip_taskbar(&self, skip: bool) -> Result<(), ExternalError> {
+    self.set_state(|state| state.skip_taskbar = skip);
+    Ok(())
+  }
+
+  pub fn set_visible_on_all_workspaces(&self, visible: bool) {
+    self.set_state(|state| state.visible_on_all_workspaces = visible)
+  }
+
+  pub fn is_visible_on_all_workspaces(&self) -> bool {
+    self.with_state(|state| state.visible_on_all_workspaces)
+  }
+
+  pub fn set_opacity(&self, opacity: f32) {
+    self.set_state(|state| state.opacity = opacity)
+  }
+
+  pub fn opacity(&self) -> f32 {
+    self.with_state(|state| state.opacity)
+  }
+
+  pub fn set_background_color(&self, color: Option<Color>) {
+    self.set_state(|state| state.background_color = color)
+  }
+
+  pub fn cursor_position(&self) -> Result<PhysicalPosition<f64>, ExternalError> {
+    Ok(self.with_state(|state| state.cursor_position))
+  }
+
+  pub fn theme(&self) -> Theme {
+    self.with_state(|state| state.theme)
+  }
+
+  pub fn set_theme(&self, theme: Option<Theme>) {
+    self.update(|shared, id| {
+      let state = shared.windows.get_mut(&id).unwrap();
+      state.preferred_theme = theme;
+      let theme = theme.unwrap_or(Theme::Light);
+      if state.theme != theme {
+        state.theme = theme;
+        shared.push(id, WindowEvent::ThemeChanged(theme));
+      }
+    })
+  }
+
+  pub fn set_enable(&self, _enabled: bool) {}
+
+  #[cfg(feature = "rwh_04")]
+  pub fn raw_window_handle_rwh_04(&self) -> rwh_04::RawWindowHandle {
+    rwh_04::RawWindowHandle::Web(rwh_04::WebHandle::empty())
+  }
+
+  #[cfg(feature = "rwh_05")]
+  pub fn raw_window_handle_rwh_05(&self) -> rwh_05::RawWindowHandle {
+    rwh_05::RawWindowHandle::Web(rwh_05::WebWindowHandle::empty())
+  }
+
+  #[cfg(feature = "rwh_05")]
+  pub fn raw_display_handle_rwh_05(&self) -> rwh_05::RawDisplayHandle {
+    rwh_05::RawDisplayHandle::Web(rwh_05::WebDisplayHandle::empty())
+  }
+
+  #[cfg(feature = "rwh_06")]
+  pub fn raw_window_handle_rwh_06(&self) -> Result<rwh_06::RawWindowHandle, rwh_06::HandleError> {
+    Err(rwh_06::HandleError::NotSupported)
+  }
+
+  #[cfg(feature = "rwh_06")]
+  pub fn raw_display_handle_rwh_06(&self) -> Result<rwh_06::RawDisplayHandle, rwh_06::HandleError> {
+    Err(rwh_06::HandleError::NotSupported)
+  }
+}
+
+impl Drop for Window {
+  fn drop(&mut self) {
+    self.update(|shared, id| {
+      shared.windows.remove(&id);
+      shared.redraws.retain(|window_id| *window_id != id);
+      if shared.focused == Some(id) {
+        shared.focused = None;
+      }
+      shared.push(id, WindowEvent::Destroyed);
+    })
+  }
+}
@@ -287,10 +287,12 @@ impl AppState {
           start,
         },
       ) => {
-        let event = if Instant::now() >= requested_resume {
+        let now = Instant::now();
+        let event = if now >= requested_resume {
           EventWrapper::StaticEvent(Event::NewEvents(StartCause::ResumeTimeReached {
             start,
             requested_resume,
+            resumed: now,
           }))
         } else {
           EventWrapper::StaticEvent(Event::NewEvents(StartCause::WaitCancelled {
@@ -14,11 +14,15 @@ use crossbeam_channel::{self as channel, Receiver, Sender};
 
 use crate::{
   dpi::{LogicalSize, PhysicalPosition},
-  error::ExternalError,
+  error::{ExternalError, NotSupportedError},
   event::Event,
-  event_loop::{ControlFlow, EventLoopClosed, EventLoopWindowTarget as RootEventLoopWindowTarget},
+  event_loop::{
+    AccessibilityPrefs, ControlFlow, DeviceEventFilter, EventLoopClosed,
+    EventLoopWindowTarget as RootEventLoopWindowTarget, IdleDeadline, PowerInfo, SystemAppearance,
+  },
+  keyboard::{Key, KeyCode, KeyboardLayout, ModifiersState},
   monitor::MonitorHandle as RootMonitorHandle,
-  platform::ios::Idiom,
+  platform::{inject::SyntheticInput, ios::Idiom},
 };
 
 use crate::platform_impl::platform::{
@@ -29,9 +33,11 @@ use crate::platform_impl::platform::{
     CFRunLoopActivity, CFRunLoopAddObserver, CFRunLoopAddSource, CFRunLoopGetMain,
     CFRunLoopObserverCreate, CFRunLoopObserverRef, CFRunLoopSourceContext, CFRunLoopSourceCreate,
     CFRunLoopSourceInvalidate, CFRunLoopSourceRef, CFRunLoopSourceSignal, CFRunLoopWakeUp,
-    NSStringRust, UIApplicationMain, UIUserInterfaceIdiom,
+    NSStringRust, UIAccessibilityDarkerSystemColorsEnabled, UIAccessibilityIsInvertColorsEnabled,
+    UIAccessibilityIsReduceMotionEnabled, UIAccessibilityIsReduceTransparencyEnabled,
+    UIApplicationMain, UIUserInterfaceIdiom,
   },
-  monitor, view, MonitorHandle,
+  monitor, view, MonitorHandle, WindowId,
 };
 
 #[non_exhaustive]
@@ -58,6 +64,18 @@ pub struct EventLoopWindowTarget<T: 'static> {
 }
 
 impl<T: 'static> EventLoopWindowTarget<T> {
+  pub fn create_waker(&self) -> EventLoopWaker {
+    EventLoopWaker::new()
+  }
+
+  pub fn is_headless(&self) -> bool {
+    false
+  }
+
+  pub fn has_window(&self, _window_id: WindowId) -> bool {
+    false
+  }
+
   pub fn available_monitors(&self) -> VecDeque<MonitorHandle> {
     // guaranteed to be on main thread
     unsafe { monitor::uiscreens() }
@@ -94,17 +112,89 @@ impl<T: 'static> EventLoopWindowTarget<T> {
     debug!("`EventLoopWindowTarget::cursor_position` is ignored on iOS");
     Ok((0, 0).into())
   }
+
+  pub fn set_device_event_filter(&self, _filter: DeviceEventFilter) -> Result<(), ExternalError> {
+    debug!("`EventLoopWindowTarget::set_device_event_filter` is ignored on iOS");
+    Ok(())
+  }
+
+  pub fn accessibility_preferences(&self) -> AccessibilityPrefs {
+    unsafe {
+      AccessibilityPrefs {
+        reduce_motion: UIAccessibilityIsReduceMotionEnabled(),
+        reduce_transparency: UIAccessibilityIsReduceTransparencyEnabled(),
+        high_contrast: UIAccessibilityDarkerSystemColorsEnabled(),
+        inverted_colors: UIAccessibilityIsInvertColorsEnabled(),
+      }
+    }
+  }
+
+  pub fn system_appearance(&self) -> SystemAppearance {
+    SystemAppearance {
+      accessibility: self.accessibility_preferences(),
+      ..Default::default()
+    }
+  }
+
+  pub fn power_info(&self) -> PowerInfo {
+    PowerInfo::default()
+  }
+
+  pub fn set_idle_callback(&self, _callback: Option<Box<dyn FnMut(IdleDeadline)>>) {}
+
+  pub fn run_nested_until(
+    &self,
+    _target: &RootEventLoopWindowTarget<T>,
+    _predicate: &mut dyn FnMut() -> bool,
+    _handler: &mut dyn FnMut(Event<'_, T>, &RootEventLoopWindowTarget<T>, &mut ControlFlow),
+  ) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn key_for_keycode(&self, _code: KeyCode) -> Option<Key<'static>> {
+    None
+  }
+
+  pub fn keyboard_layout(&self) -> Option<KeyboardLayout> {
+    None
+  }
+
+  pub fn inject_input(&self, _input: SyntheticInput) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn register_global_shortcut(
+    &self,
+    _id: u32,
+    _modifiers: ModifiersState,
+    _key: KeyCode,
+  ) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn unregister_global_shortcut(&self, _id: u32) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
 }
 
-#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub(crate) struct PlatformSpecificEventLoopAttributes {}
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct PlatformSpecificEventLoopAttributes {
+  pub(crate) headless: bool,
+  pub(crate) single_instance: Option<String>,
+}
 
 pub struct EventLoop<T: 'static> {
   window_target: RootEventLoopWindowTarget<T>,
 }
 
 impl<T: 'static> EventLoop<T> {
-  pub(crate) fn new(_: &PlatformSpecificEventLoopAttributes) -> EventLoop<T> {
+  pub(crate) fn new(attributes: &PlatformSpecificEventLoopAttributes) -> EventLoop<T> {
+    if attributes.headless {
+      warn!("`EventLoopBuilder::with_headless` is ignored on iOS");
+    }
+    if attributes.single_instance.is_some() {
+      warn!("`EventLoopBuilder::with_single_instance` is ignored on iOS");
+    }
     static mut SINGLETON_INIT: bool = false;
     unsafe {
       assert_main_thread!("`EventLoop` can only be created on the main thread on iOS");
@@ -128,11 +218,23 @@ impl<T: 'static> EventLoop<T> {
           receiver,
           sender_to_clone,
         },
+        exit: Default::default(),
+        executor: Default::default(),
+        captures: Default::default(),
+        window_motion_coalescing: Default::default(),
+        window_registry: Default::default(),
+        user_events: Default::default(),
         _marker: PhantomData,
       },
     }
   }
 
+  pub(crate) fn try_new(
+    attributes: &PlatformSpecificEventLoopAttributes,
+  ) -> Result<Self, ExternalError> {
+    Ok(Self::new(attributes))
+  }
+
   pub fn run<F>(self, event_handler: F) -> !
   where
     F: 'static + FnMut(Event<'_, T>, &RootEventLoopWindowTarget<T>, &mut ControlFlow),
@@ -178,6 +280,54 @@ impl<T: 'static> EventLoop<T> {
   }
 }
 
+pub struct EventLoopWaker {
+  source: CFRunLoopSourceRef,
+}
+
+unsafe impl Send for EventLoopWaker {}
+unsafe impl Sync for EventLoopWaker {}
+
+impl Clone for EventLoopWaker {
+  fn clone(&self) -> EventLoopWaker {
+    EventLoopWaker::new()
+  }
+}
+
+impl Drop for EventLoopWaker {
+  fn drop(&mut self) {
+    unsafe {
+      CFRunLoopSourceInvalidate(self.source);
+      CFRelease(self.source as _);
+    }
+  }
+}
+
+impl EventLoopWaker {
+  fn new() -> EventLoopWaker {
+    unsafe {
+      // the source only exists to wake up the eventloop, like the one of `EventLoopProxy`
+      extern "C" fn event_loop_waker_handler(_: *mut c_void) {}
+
+      let rl = CFRunLoopGetMain();
+      // we want all the members of context to be zero/null, except one
+      let mut context: CFRunLoopSourceContext = mem::zeroed();
+      context.perform = Some(event_loop_waker_handler);
+      let source = CFRunLoopSourceCreate(ptr::null_mut(), CFIndex::max_value() - 1, &mut context);
+      CFRunLoopAddSource(rl, source, kCFRunLoopCommonModes);
+
+      EventLoopWaker { source }
+    }
+  }
+
+  pub fn wake(&self) {
+    unsafe {
+      CFRunLoopSourceSignal(self.source);
+      let rl = CFRunLoopGetMain();
+      CFRunLoopWakeUp(rl);
+    }
+  }
+}
+
 pub struct EventLoopProxy<T> {
   sender: Sender<T>,
   source: CFRunLoopSourceRef,
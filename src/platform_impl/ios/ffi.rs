@@ -270,6 +270,11 @@ extern "C" {
     delegateClassName: id,
   ) -> c_int;
 
+  pub fn UIAccessibilityIsReduceMotionEnabled() -> bool;
+  pub fn UIAccessibilityIsReduceTransparencyEnabled() -> bool;
+  pub fn UIAccessibilityDarkerSystemColorsEnabled() -> bool;
+  pub fn UIAccessibilityIsInvertColorsEnabled() -> bool;
+
   pub fn CFRunLoopGetMain() -> CFRunLoopRef;
   pub fn CFRunLoopWakeUp(rl: CFRunLoopRef);
 
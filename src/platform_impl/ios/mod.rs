@@ -85,7 +85,7 @@ use std::fmt;
 
 pub(crate) use self::event_loop::PlatformSpecificEventLoopAttributes;
 pub use self::{
-  event_loop::{EventLoop, EventLoopProxy, EventLoopWindowTarget},
+  event_loop::{EventLoop, EventLoopProxy, EventLoopWaker, EventLoopWindowTarget},
   keycode::{keycode_from_scancode, keycode_to_scancode},
   monitor::{MonitorHandle, VideoMode},
   window::{PlatformSpecificWindowBuilderAttributes, Window, WindowId},
@@ -124,3 +124,172 @@ impl fmt::Display for OsError {
     }
   }
 }
+
+/// iOS has no notifications.
+pub enum Notification {}
+
+impl Notification {
+  pub fn new<T>(
+    _window_target: &EventLoopWindowTarget<T>,
+    _id: u32,
+    _attributes: crate::notification::NotificationAttributes,
+  ) -> Result<Self, crate::error::ExternalError> {
+    Err(crate::error::ExternalError::NotSupported(
+      crate::error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn close(&self) {
+    match *self {}
+  }
+}
+
+/// iOS has no window to deliver accessibility action requests to.
+#[derive(Clone)]
+pub enum AccessibilityActionSender {}
+
+impl AccessibilityActionSender {
+  pub fn send(
+    &self,
+    _request: crate::accessibility::AccessibilityActionRequest,
+  ) -> Result<
+    (),
+    crate::event_loop::EventLoopClosed<crate::accessibility::AccessibilityActionRequest>,
+  > {
+    match *self {}
+  }
+}
+
+/// iOS has no system tray.
+pub enum SystemTray {}
+
+impl SystemTray {
+  pub fn new<T>(
+    _window_target: &EventLoopWindowTarget<T>,
+    _id: u32,
+    _icon: crate::icon::Icon,
+    _tooltip: Option<String>,
+  ) -> Result<Self, crate::error::ExternalError> {
+    Err(crate::error::ExternalError::NotSupported(
+      crate::error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn set_icon(&self, _icon: crate::icon::Icon) {
+    match *self {}
+  }
+
+  pub fn set_tooltip(&self, _tooltip: Option<&str>) {
+    match *self {}
+  }
+
+  pub fn set_menu(&self, _menu: Option<&Submenu>) {
+    match *self {}
+  }
+}
+
+/// iOS has no menus, they are built but never shown.
+pub struct Menu;
+
+impl Menu {
+  pub fn new() -> Self {
+    Menu
+  }
+
+  pub fn append_submenu(&self, _submenu: &Submenu) {}
+}
+
+pub struct Submenu;
+
+impl Submenu {
+  pub fn new(_title: &str) -> Self {
+    Submenu
+  }
+
+  pub fn append_item(&self, _item: &MenuItem) {}
+
+  pub fn append_submenu(&self, _submenu: &Submenu) {}
+
+  pub fn append_separator(&self) {}
+
+  pub fn set_title(&self, _title: &str) {}
+
+  pub fn set_enabled(&self, _enabled: bool) {}
+}
+
+pub struct MenuItem;
+
+impl MenuItem {
+  pub fn new(_id: u32, _title: &str, _checked: Option<bool>) -> Self {
+    MenuItem
+  }
+
+  pub fn set_title(&self, _title: &str) {}
+
+  pub fn set_enabled(&self, _enabled: bool) {}
+
+  pub fn set_checked(&self, _checked: bool) {}
+
+  pub fn is_checked(&self) -> bool {
+    false
+  }
+}
+
+/// iOS has no clipboard support, nothing can be read and nothing is written.
+pub struct Clipboard;
+
+impl Clipboard {
+  pub fn new<T>(_window_target: &EventLoopWindowTarget<T>) -> Self {
+    Clipboard
+  }
+
+  pub fn read_text(&self) -> Option<String> {
+    None
+  }
+
+  pub fn write_text(&self, _text: &str) -> Result<(), crate::error::ExternalError> {
+    Err(crate::error::ExternalError::NotSupported(
+      crate::error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn read_image(&self) -> Option<crate::window::RgbaImage> {
+    None
+  }
+
+  pub fn write_image(
+    &self,
+    _image: &crate::window::RgbaImage,
+  ) -> Result<(), crate::error::ExternalError> {
+    Err(crate::error::ExternalError::NotSupported(
+      crate::error::NotSupportedError::new(),
+    ))
+  }
+
+  pub fn read_files(&self) -> Vec<std::path::PathBuf> {
+    Vec::new()
+  }
+
+  pub fn write_files(
+    &self,
+    _files: &[std::path::PathBuf],
+  ) -> Result<(), crate::error::ExternalError> {
+    Err(crate::error::ExternalError::NotSupported(
+      crate::error::NotSupportedError::new(),
+    ))
+  }
+}
+
+/// iOS has no power assertions.
+pub struct PowerAssertion;
+
+impl PowerAssertion {
+  pub fn keep_display_awake<T>(
+    _window_target: &EventLoopWindowTarget<T>,
+    _reason: &str,
+  ) -> Result<Self, crate::error::ExternalError> {
+    Err(crate::error::ExternalError::NotSupported(
+      crate::error::NotSupportedError::new(),
+    ))
+  }
+}
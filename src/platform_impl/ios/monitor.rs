@@ -10,11 +10,13 @@ use std::{
 
 use crate::{
   dpi::{PhysicalPosition, PhysicalSize},
+  error::{ExternalError, NotSupportedError},
   monitor::{MonitorHandle as RootMonitorHandle, VideoMode as RootVideoMode},
   platform_impl::platform::{
     app_state,
     ffi::{id, nil, CGFloat, CGRect, CGSize, NSInteger, NSUInteger},
   },
+  window::RgbaImage,
 };
 
 #[derive(Debug, PartialEq, Eq, Hash)]
@@ -217,6 +219,10 @@ impl Inner {
     }
   }
 
+  pub fn id(&self) -> u64 {
+    crate::monitor::stable_id(self.name().unwrap_or_default().as_bytes())
+  }
+
   pub fn size(&self) -> PhysicalSize<u32> {
     unsafe {
       let bounds: CGRect = msg_send![self.ui_screen(), nativeBounds];
@@ -238,6 +244,19 @@ impl Inner {
     }
   }
 
+  pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+    if !app_state::os_capabilities().maximum_frames_per_second {
+      return None;
+    }
+    let frames_per_second: NSInteger =
+      unsafe { msg_send![self.ui_screen(), maximumFramesPerSecond] };
+    Some(frames_per_second as u32 * 1000)
+  }
+
+  pub fn capture(&self) -> Result<RgbaImage, ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
   pub fn video_modes(&self) -> impl Iterator<Item = RootVideoMode> {
     let mut modes = BTreeSet::new();
     unsafe {
@@ -2,7 +2,7 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::HashMap, ffi::c_char};
+use std::{cell::RefCell, collections::HashMap, ffi::c_char, time::Instant};
 
 use objc::{
   declare::ClassDecl,
@@ -11,7 +11,9 @@ use objc::{
 
 use crate::{
   dpi::PhysicalPosition,
-  event::{DeviceId as RootDeviceId, Event, Force, Touch, TouchPhase, WindowEvent},
+  event::{
+    DeviceId as RootDeviceId, Event, Force, OpenedItem, Touch, TouchIds, TouchPhase, WindowEvent,
+  },
   platform::ios::MonitorHandleExtIOS,
   platform_impl::platform::{
     app_state::{self, OSCapabilities},
@@ -26,6 +28,11 @@ use crate::{
   window::{Fullscreen, WindowAttributes, WindowId as RootWindowId},
 };
 
+thread_local! {
+  /// Ids of the touch contacts, as the address of a `UITouch` can be reused once it's released.
+  static TOUCH_IDS: RefCell<TouchIds> = RefCell::new(TouchIds::default());
+}
+
 macro_rules! add_property {
     (
         $decl:ident,
@@ -250,7 +257,6 @@ unsafe fn get_view_class(root_view_class: &'static Class) -> &'static Class {
           } else {
             None
           };
-          let touch_id = touch as u64;
           let phase: UITouchPhase = msg_send![touch, phase];
           let phase = match phase {
             UITouchPhase::Began => TouchPhase::Started,
@@ -260,6 +266,7 @@ unsafe fn get_view_class(root_view_class: &'static Class) -> &'static Class {
             UITouchPhase::Cancelled => TouchPhase::Cancelled,
             _ => panic!("unexpected touch phase: {:?}", phase as i32),
           };
+          let touch_id = TOUCH_IDS.with(|ids| ids.borrow_mut().id(touch as u64, phase));
 
           let physical_location = {
             let scale_factor: CGFloat = msg_send![object, contentScaleFactor];
@@ -275,7 +282,9 @@ unsafe fn get_view_class(root_view_class: &'static Class) -> &'static Class {
               id: touch_id,
               location: physical_location,
               force,
+              rotation: None,
               phase,
+              timestamp: Instant::now(),
             }),
           }));
         }
@@ -433,7 +442,7 @@ unsafe fn get_window_class() -> &'static Class {
 
 // requires main thread
 pub unsafe fn create_view(
-  _window_attributes: &WindowAttributes,
+  window_attributes: &WindowAttributes,
   platform_attributes: &PlatformSpecificWindowBuilderAttributes,
   frame: CGRect,
 ) -> id {
@@ -444,7 +453,10 @@ pub unsafe fn create_view(
   let view: id = msg_send![view, initWithFrame: frame];
   assert!(!view.is_null(), "Failed to initialize `UIView` instance");
   let () = msg_send![view, setMultipleTouchEnabled: YES];
-  if let Some(scale_factor) = platform_attributes.scale_factor {
+  if let Some(scale_factor) = window_attributes
+    .scale_factor_override
+    .or(platform_attributes.scale_factor)
+  {
     let () = msg_send![view, setContentScaleFactor: scale_factor as CGFloat];
   }
 
@@ -580,7 +592,9 @@ pub fn create_delegate_class() {
 
       let url = url::Url::parse(std::str::from_utf8(bytes).unwrap()).unwrap();
 
-      app_state::handle_nonuser_event(EventWrapper::StaticEvent(Event::Opened { urls: vec![url] }));
+      app_state::handle_nonuser_event(EventWrapper::StaticEvent(Event::Opened {
+        items: vec![OpenedItem::from_url(url)],
+      }));
 
       YES
     }
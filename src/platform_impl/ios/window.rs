@@ -10,10 +10,10 @@ use std::{
 use objc::runtime::{Class, Object, BOOL, NO, YES};
 
 use crate::{
-  dpi::{self, LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Position, Size},
-  error::{ExternalError, NotSupportedError, OsError as RootOsError},
+  dpi::{self, LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Position, Rect, Size},
+  error::{ExternalError, NotSupportedError, OsError as RootOsError, WindowOperationError},
   event::{Event, WindowEvent},
-  icon::Icon,
+  icon::{Icon, RgbaIcon},
   monitor::MonitorHandle as RootMonitorHandle,
   platform::ios::{MonitorHandleExtIOS, ScreenEdge, ValidOrientations},
   platform_impl::platform::{
@@ -23,11 +23,13 @@ use crate::{
       id, CGFloat, CGPoint, CGRect, CGSize, UIEdgeInsets, UIInterfaceOrientationMask, UIRectEdge,
       UIScreenOverscanCompensation,
     },
-    monitor, view, EventLoopWindowTarget, MonitorHandle,
+    monitor, view, EventLoopWindowTarget, Menu, MonitorHandle, Submenu,
   },
   window::{
-    CursorIcon, Fullscreen, ResizeDirection, Theme, UserAttentionType, WindowAttributes,
-    WindowId as RootWindowId, WindowSizeConstraints,
+    ActivationToken, Color, CursorGrabMode, CursorIcon, CursorKind, DragData, DragImage,
+    FocusResult, Fullscreen, HitTestCallback, ImePurpose, ResizeDirection, RgbaImage, Theme,
+    UserAttentionType, WindowAttributes, WindowId as RootWindowId, WindowLevel, WindowShape,
+    WindowSizeConstraints,
   },
 };
 
@@ -67,11 +69,20 @@ impl Inner {
     }
   }
 
+  pub fn close(&self) {
+    warn!("`Window::close` is ignored on iOS");
+  }
+
   pub fn set_focus(&self) {
     //FIXME: implementation goes here
     warn!("set_focus not yet implemented on iOS");
   }
 
+  pub fn set_focus_with_token(&self, _token: Option<ActivationToken>) -> FocusResult {
+    warn!("`Window::set_focus_with_token` is ignored on iOS");
+    FocusResult::Failed
+  }
+
   pub fn is_focused(&self) -> bool {
     warn!("`Window::is_focused` is ignored on iOS");
     false
@@ -95,6 +106,14 @@ impl Inner {
     }
   }
 
+  pub fn request_redraw_synced(&self) {
+    self.request_redraw()
+  }
+
+  pub fn request_frame_callbacks(&self, _enabled: bool) {
+    warn!("`Window::request_frame_callbacks` is ignored on iOS")
+  }
+
   pub fn inner_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
     unsafe {
       let safe_area = self.safe_area_screen_space();
@@ -160,18 +179,22 @@ impl Inner {
     }
   }
 
-  pub fn set_inner_size(&self, _size: Size) {
+  pub fn set_inner_size(&self, _size: Size) -> Result<(), WindowOperationError> {
     warn!("not clear what `Window::set_inner_size` means on iOS");
+    Err(NotSupportedError::new().into())
   }
 
-  pub fn set_min_inner_size(&self, _: Option<Size>) {
-    warn!("`Window::set_min_inner_size` is ignored on iOS")
+  pub fn set_min_inner_size(&self, _: Option<Size>) -> Result<(), ExternalError> {
+    warn!("`Window::set_min_inner_size` is ignored on iOS");
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
-  pub fn set_max_inner_size(&self, _: Option<Size>) {
-    warn!("`Window::set_max_inner_size` is ignored on iOS")
+  pub fn set_max_inner_size(&self, _: Option<Size>) -> Result<(), ExternalError> {
+    warn!("`Window::set_max_inner_size` is ignored on iOS");
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
-  pub fn set_inner_size_constraints(&self, _: WindowSizeConstraints) {
-    warn!("`Window::set_inner_size_constraints` is ignored on iOS")
+  pub fn set_inner_size_constraints(&self, _: WindowSizeConstraints) -> Result<(), ExternalError> {
+    warn!("`Window::set_inner_size_constraints` is ignored on iOS");
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
 
   pub fn set_resizable(&self, _resizable: bool) {
@@ -201,11 +224,23 @@ impl Inner {
     debug!("`Window::set_cursor_icon` ignored on iOS")
   }
 
+  pub fn cursor_icon(&self) -> CursorKind {
+    CursorKind::default()
+  }
+
+  pub fn set_custom_cursor(
+    &self,
+    _icon: RgbaIcon,
+    _hotspot: PhysicalPosition<u32>,
+  ) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
   pub fn set_cursor_position(&self, _position: Position) -> Result<(), ExternalError> {
     Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
 
-  pub fn set_cursor_grab(&self, _grab: bool) -> Result<(), ExternalError> {
+  pub fn set_cursor_grab(&self, _mode: CursorGrabMode) -> Result<(), ExternalError> {
     Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
 
@@ -226,10 +261,64 @@ impl Inner {
     Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
 
+  pub fn start_drag(&self, _data: DragData, _image: DragImage) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
   pub fn set_ignore_cursor_events(&self, _ignore: bool) -> Result<(), ExternalError> {
     Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
 
+  pub fn set_minimize_target(&self, _target: Option<Rect>) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn capture(&self, _outer: bool) -> Result<RgbaImage, ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_skip_taskbar(&self, _skip: bool) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_cursor_hittest_region(&self, _region: Option<&[Rect]>) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_shape(&self, _shape: Option<WindowShape>) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_hit_test_callback(
+    &self,
+    _callback: Option<HitTestCallback>,
+  ) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn accessibility_action_sender(
+    &self,
+  ) -> Result<super::AccessibilityActionSender, ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_background_color(&self, _color: Option<Color>) {
+    warn!("`Window::set_background_color` is ignored on iOS")
+  }
+
+  pub fn set_opacity(&self, opacity: f32) {
+    unsafe {
+      let _: () = msg_send![self.window, setAlpha: opacity as CGFloat];
+    }
+  }
+
+  pub fn opacity(&self) -> f32 {
+    unsafe {
+      let opacity: CGFloat = msg_send![self.window, alpha];
+      opacity as f32
+    }
+  }
+
   pub fn set_minimized(&self, _minimized: bool) {
     warn!("`Window::set_minimized` is ignored on iOS")
   }
@@ -248,6 +337,11 @@ impl Inner {
     false
   }
 
+  pub fn is_visible_on_all_workspaces(&self) -> bool {
+    warn!("`Window::is_visible_on_all_workspaces` is ignored on iOS");
+    false
+  }
+
   pub fn is_visible(&self) -> bool {
     log::warn!("`Window::is_visible` is ignored on iOS");
     false
@@ -278,7 +372,7 @@ impl Inner {
     false
   }
 
-  pub fn set_fullscreen(&self, monitor: Option<Fullscreen>) {
+  pub fn set_fullscreen(&self, monitor: Option<Fullscreen>) -> Result<(), WindowOperationError> {
     unsafe {
       let uiscreen = match monitor {
         Some(Fullscreen::Exclusive(video_mode)) => {
@@ -291,7 +385,7 @@ impl Inner {
           .ui_screen() as id,
         None => {
           warn!("`Window::set_fullscreen(None)` ignored on iOS");
-          return;
+          return Err(NotSupportedError::new().into());
         }
       };
 
@@ -312,6 +406,7 @@ impl Inner {
         setOverscanCompensation: UIScreenOverscanCompensation::None
       ];
     }
+    Ok(())
   }
 
   pub fn fullscreen(&self) -> Option<Fullscreen> {
@@ -338,26 +433,75 @@ impl Inner {
     warn!("`Window::set_decorations` is ignored on iOS")
   }
 
+  pub fn raise(&self) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn lower(&self) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn restack_above(&self, _sibling: &Inner) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_parent(&self, _parent: Option<&Window>) {
+    warn!("`Window::set_parent` is ignored on iOS")
+  }
+
   pub fn set_always_on_bottom(&self, _always_on_bottom: bool) {
     warn!("`Window::set_always_on_bottom` is ignored on iOS")
   }
 
-  pub fn set_always_on_top(&self, _always_on_top: bool) {
-    warn!("`Window::set_always_on_top` is ignored on iOS")
+  pub fn set_always_on_top(&self, _always_on_top: bool) -> Result<(), WindowOperationError> {
+    warn!("`Window::set_always_on_top` is ignored on iOS");
+    Err(NotSupportedError::new().into())
+  }
+
+  pub fn set_window_level(&self, _level: WindowLevel) {
+    warn!("`Window::set_window_level` is ignored on iOS")
   }
 
   pub fn set_window_icon(&self, _icon: Option<Icon>) {
     warn!("`Window::set_window_icon` is ignored on iOS")
   }
 
-  pub fn set_ime_position(&self, _position: Position) {
-    warn!("`Window::set_ime_position` is ignored on iOS")
+  pub fn set_menu(&self, _menu: Option<&Menu>) {
+    warn!("`Window::set_menu` is ignored on iOS")
+  }
+
+  pub fn show_context_menu(&self, _menu: &Submenu, _position: Position) {
+    warn!("`Window::show_context_menu` is ignored on iOS")
+  }
+
+  pub fn set_ime_cursor_area(&self, _area: Rect) {
+    warn!("`Window::set_ime_cursor_area` is ignored on iOS")
+  }
+
+  pub fn set_ime_allowed(&self, _allowed: bool) {
+    warn!("`Window::set_ime_allowed` is ignored on iOS")
+  }
+
+  pub fn set_ime_visible(&self, _visible: bool) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_ime_purpose(&self, _purpose: ImePurpose) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
 
   pub fn request_user_attention(&self, _request_type: Option<UserAttentionType>) {
     warn!("`Window::request_user_attention` is ignored on iOS")
   }
 
+  pub fn is_attention_requested(&self) -> bool {
+    false
+  }
+
+  pub fn reset_dead_keys(&self) {
+    warn!("`Window::reset_dead_keys` is ignored on iOS")
+  }
+
   // Allow directly accessing the current monitor internally without unwrapping.
   fn current_monitor_inner(&self) -> RootMonitorHandle {
     unsafe {
@@ -433,6 +577,10 @@ impl Inner {
   pub fn theme(&self) -> Theme {
     Theme::Light
   }
+
+  pub fn set_theme(&self, _theme: Option<Theme>) {}
+
+  pub fn set_enable(&self, _enabled: bool) {}
 }
 
 pub struct Window {
@@ -476,8 +624,8 @@ impl Window {
     window_attributes: WindowAttributes,
     platform_attributes: PlatformSpecificWindowBuilderAttributes,
   ) -> Result<Window, RootOsError> {
-    if window_attributes.always_on_top {
-      warn!("`WindowAttributes::always_on_top` is unsupported on iOS");
+    if window_attributes.window_level != WindowLevel::Normal {
+      warn!("`WindowAttributes::window_level` is unsupported on iOS");
     }
     // TODO: transparency, visible
 
@@ -525,6 +673,9 @@ impl Window {
         frame,
         view_controller,
       );
+      if window_attributes.opacity < 1.0 {
+        let _: () = msg_send![window, setAlpha: window_attributes.opacity as CGFloat];
+      }
 
       let result = Window {
         inner: Inner {
@@ -723,6 +874,16 @@ impl WindowId {
       window: std::ptr::null_mut(),
     }
   }
+
+  pub fn into_raw(self) -> u64 {
+    self.window as usize as u64
+  }
+
+  pub fn from_raw(raw: u64) -> Self {
+    WindowId {
+      window: raw as usize as id,
+    }
+  }
 }
 
 unsafe impl Send for WindowId {}
@@ -0,0 +1,211 @@
+use std::{
+  cell::RefCell,
+  sync::{Arc, Mutex, PoisonError},
+};
+
+use gtk::{glib, prelude::*};
+use zbus::{
+  blocking::{Connection, MessageIterator},
+  zvariant::{OwnedValue, Value},
+  MatchRule, MessageType,
+};
+
+use super::window::GTK_THEME_SUFFIX_LIST;
+use crate::{
+  event_loop::{AccessibilityPrefs, SystemAppearance},
+  window::{Color, Theme},
+};
+
+const PORTAL: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const PORTAL_SETTINGS: &str = "org.freedesktop.portal.Settings";
+const APPEARANCE: &str = "org.freedesktop.appearance";
+
+/// The appearance settings of the portal, `None` for the ones it doesn't have.
+///
+/// They are read once when the event loop is created, then kept up to date by the thread of
+/// `spawn`, so that the GTK thread doesn't wait for D-Bus.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PortalSettings {
+  color_scheme: Option<u32>,
+  accent_color: Option<(f64, f64, f64)>,
+  contrast: Option<u32>,
+  reduced_motion: Option<u32>,
+}
+
+impl PortalSettings {
+  pub fn read(conn: &Connection) -> Self {
+    let mut settings = Self::default();
+    for key in ["color-scheme", "accent-color", "contrast", "reduced-motion"] {
+      if let Some(value) = setting(conn, key) {
+        settings.update(key, value);
+      }
+    }
+    settings
+  }
+
+  fn update(&mut self, key: &str, value: Value<'static>) {
+    match key {
+      "color-scheme" => self.color_scheme = u32::try_from(value).ok(),
+      "accent-color" => self.accent_color = <(f64, f64, f64)>::try_from(value).ok(),
+      "contrast" => self.contrast = u32::try_from(value).ok(),
+      "reduced-motion" => self.reduced_motion = u32::try_from(value).ok(),
+      _ => (),
+    }
+  }
+}
+
+/// The appearance from the settings of the portal, falling back to the GTK settings for the
+/// theme.
+pub fn system_appearance(portal: &PortalSettings) -> SystemAppearance {
+  // 1 is a dark preference, 2 a light one and 0 none.
+  let theme = match portal.color_scheme {
+    Some(1) => Theme::Dark,
+    Some(2) => Theme::Light,
+    _ => gtk_theme(),
+  };
+  // The channels are in the range of 0 to 1, values outside of it mean there's no accent color.
+  let accent_color = portal
+    .accent_color
+    .filter(|(r, g, b)| [r, g, b].iter().all(|c| (0.0..=1.0).contains(*c)))
+    .map(|(r, g, b)| {
+      let channel = |value: f64| (value * 255.0).round() as u8;
+      Color::new(channel(r), channel(g), channel(b), u8::MAX)
+    });
+  SystemAppearance {
+    theme,
+    accent_color,
+    accessibility: accessibility_prefs(portal),
+  }
+}
+
+/// The accessibility preferences from the settings of the portal, falling back to the GTK
+/// settings for the ones it doesn't have.
+pub fn accessibility_prefs(portal: &PortalSettings) -> AccessibilityPrefs {
+  let settings = gtk::Settings::default();
+  // 1 asks for a high contrast and 0 has no preference.
+  let high_contrast = match portal.contrast {
+    Some(contrast) => contrast == 1,
+    // Adwaita ships its high contrast variants as separate themes, e.g. `HighContrastInverse`.
+    None => {
+      system_gtk_theme_name(settings.as_ref()).map_or(false, |theme| theme.contains("HighContrast"))
+    }
+  };
+  // 1 asks for reduced motion and 0 has no preference.
+  let reduce_motion = match portal.reduced_motion {
+    Some(reduced_motion) => reduced_motion == 1,
+    None => settings.map_or(false, |settings| !settings.is_gtk_enable_animations()),
+  };
+  AccessibilityPrefs {
+    reduce_motion,
+    high_contrast,
+    ..Default::default()
+  }
+}
+
+thread_local! {
+  /// The GTK theme name of the user, saved while `set_gtk_theme` overrides it, `None` otherwise.
+  static SYSTEM_GTK_THEME_NAME: RefCell<Option<Option<String>>> = RefCell::new(None);
+}
+
+/// Saves the GTK theme name of the user before `set_gtk_theme` overrides it the first time, or
+/// forgets it once `set_gtk_theme` restores it.
+pub(super) fn save_system_gtk_theme_name(settings: &gtk::Settings, overridden: bool) {
+  SYSTEM_GTK_THEME_NAME.with(|name| {
+    let mut name = name.borrow_mut();
+    if !overridden {
+      *name = None;
+    } else if name.is_none() {
+      *name = Some(settings.gtk_theme_name().map(Into::into));
+    }
+  });
+}
+
+/// The GTK theme name of the user, rather than the one `Window::set_theme` sets for the whole
+/// application.
+fn system_gtk_theme_name(settings: Option<&gtk::Settings>) -> Option<String> {
+  SYSTEM_GTK_THEME_NAME
+    .with(|name| name.borrow().clone())
+    .unwrap_or_else(|| {
+      settings
+        .and_then(|settings| settings.gtk_theme_name())
+        .map(Into::into)
+    })
+}
+
+/// The theme of the GTK theme name of the user.
+fn gtk_theme() -> Theme {
+  let is_dark = system_gtk_theme_name(gtk::Settings::default().as_ref()).map_or(false, |theme| {
+    GTK_THEME_SUFFIX_LIST
+      .iter()
+      .any(|suffix| theme.ends_with(suffix))
+  });
+  if is_dark {
+    Theme::Dark
+  } else {
+    Theme::Light
+  }
+}
+
+fn setting(conn: &Connection, key: &str) -> Option<Value<'static>> {
+  let reply = conn
+    .call_method(
+      Some(PORTAL),
+      PORTAL_PATH,
+      Some(PORTAL_SETTINGS),
+      "Read",
+      &(APPEARANCE, key),
+    )
+    .ok()?;
+  let value: OwnedValue = reply.body().ok()?;
+  Some(unwrap_variant(value.into()))
+}
+
+/// `Read` wraps the value in another variant, unlike the newer `ReadOne` and `SettingChanged`.
+fn unwrap_variant(value: Value<'static>) -> Value<'static> {
+  match value {
+    Value::Value(value) => unwrap_variant(*value),
+    value => value,
+  }
+}
+
+/// Spawns the thread updating `portal` and notifying `appearance_tx` whenever an appearance
+/// setting of the portal changes. It stops at the next change once the receiver of
+/// `appearance_tx` is gone.
+pub fn spawn(
+  conn: Connection,
+  portal: Arc<Mutex<PortalSettings>>,
+  appearance_tx: glib::Sender<()>,
+) {
+  std::thread::spawn(move || {
+    let rule = MatchRule::builder()
+      .msg_type(MessageType::Signal)
+      .interface(PORTAL_SETTINGS)
+      .and_then(|rule| rule.member("SettingChanged"))
+      .map(|rule| rule.build());
+    let messages = match rule.and_then(|rule| MessageIterator::for_match_rule(rule, &conn, None)) {
+      Ok(messages) => messages,
+      Err(e) => {
+        log::warn!("Failed to listen to appearance changes: {}", e);
+        return;
+      }
+    };
+
+    for message in messages.flatten() {
+      let (namespace, key, value) = match message.body::<(String, String, OwnedValue)>() {
+        Ok(setting) => setting,
+        Err(_) => continue,
+      };
+      if namespace != APPEARANCE {
+        continue;
+      }
+      portal
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .update(&key, unwrap_variant(value.into()));
+      if appearance_tx.send(()).is_err() {
+        break;
+      }
+    }
+  });
+}
@@ -0,0 +1,150 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{cell::RefCell, path::PathBuf};
+
+use gtk::{
+  gdk,
+  gdk_pixbuf::{Colorspace, Pixbuf},
+  glib,
+  prelude::*,
+};
+
+use super::{util, EventLoopWindowTarget};
+use crate::{
+  error::{ExternalError, NotSupportedError},
+  event::Event,
+  window::RgbaImage,
+};
+
+thread_local! {
+  /// The handler of `owner-change` on the `CLIPBOARD` selection, shared by the clipboards of the
+  /// event loop of this thread so that each change is reported once, with their number.
+  static OWNER_CHANGE: RefCell<Option<(glib::SignalHandlerId, usize)>> = RefCell::new(None);
+}
+
+/// The `CLIPBOARD` selection, `None` for a headless event loop without a display server.
+pub struct Clipboard {
+  clipboard: Option<gtk::Clipboard>,
+}
+
+impl Clipboard {
+  pub fn new<T: 'static>(window_target: &EventLoopWindowTarget<T>) -> Self {
+    let clipboard = window_target.display.as_ref().map(|display| {
+      let clipboard = gtk::Clipboard::for_display(display, &gdk::SELECTION_CLIPBOARD);
+      OWNER_CHANGE.with(|owner_change| {
+        let mut owner_change = owner_change.borrow_mut();
+        match &mut *owner_change {
+          Some((_, count)) => *count += 1,
+          None => {
+            let event_tx = window_target.event_tx.clone();
+            let handler = clipboard.connect_local("owner-change", false, move |_| {
+              if let Err(e) = event_tx.send(Event::ClipboardUpdated) {
+                log::warn!("Failed to send clipboard event to event channel: {}", e);
+              }
+              None
+            });
+            *owner_change = Some((handler, 1));
+          }
+        }
+      });
+      clipboard
+    });
+    Self { clipboard }
+  }
+
+  fn clipboard(&self) -> Result<&gtk::Clipboard, ExternalError> {
+    self
+      .clipboard
+      .as_ref()
+      .ok_or(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn read_text(&self) -> Option<String> {
+    self.clipboard().ok()?.wait_for_text().map(Into::into)
+  }
+
+  pub fn write_text(&self, text: &str) -> Result<(), ExternalError> {
+    self.clipboard()?.set_text(text);
+    Ok(())
+  }
+
+  pub fn read_image(&self) -> Option<RgbaImage> {
+    let pixbuf = self.clipboard().ok()?.wait_for_image()?;
+    let pixbuf = if pixbuf.has_alpha() {
+      pixbuf
+    } else {
+      pixbuf.add_alpha(false, 0, 0, 0).ok()?
+    };
+    Some(util::pixbuf_to_rgba(&pixbuf))
+  }
+
+  pub fn write_image(&self, image: &RgbaImage) -> Result<(), ExternalError> {
+    let pixbuf = Pixbuf::from_mut_slice(
+      image.data.clone(),
+      Colorspace::Rgb,
+      true,
+      8,
+      image.width as i32,
+      image.height as i32,
+      image.stride as i32,
+    );
+    self.clipboard()?.set_image(&pixbuf);
+    Ok(())
+  }
+
+  pub fn read_files(&self) -> Vec<PathBuf> {
+    let clipboard = match self.clipboard() {
+      Ok(clipboard) => clipboard,
+      Err(_) => return Vec::new(),
+    };
+    clipboard
+      .wait_for_uris()
+      .iter()
+      .filter_map(|uri| glib::filename_from_uri(uri).ok())
+      .map(|(path, _)| path)
+      .collect()
+  }
+
+  pub fn write_files(&self, files: &[PathBuf]) -> Result<(), ExternalError> {
+    let uris: Vec<_> = files
+      .iter()
+      .filter_map(|path| glib::filename_to_uri(path, None).ok())
+      .collect();
+    let targets = [gtk::TargetEntry::new(
+      "text/uri-list",
+      gtk::TargetFlags::empty(),
+      0,
+    )];
+    let set = self
+      .clipboard()?
+      .set_with_data(&targets, move |_, selection, _| {
+        selection.set_uris(&uris.iter().map(|uri| uri.as_str()).collect::<Vec<_>>());
+      });
+    if set {
+      Ok(())
+    } else {
+      Err(ExternalError::Os(os_error!(super::OsError)))
+    }
+  }
+}
+
+impl Drop for Clipboard {
+  fn drop(&mut self) {
+    let clipboard = match self.clipboard.take() {
+      Some(clipboard) => clipboard,
+      None => return,
+    };
+    OWNER_CHANGE.with(|owner_change| {
+      let mut owner_change = owner_change.borrow_mut();
+      if let Some((_, count)) = &mut *owner_change {
+        *count -= 1;
+        if *count == 0 {
+          if let Some((handler, _)) = owner_change.take() {
+            clipboard.disconnect(handler);
+          }
+        }
+      }
+    });
+  }
+}
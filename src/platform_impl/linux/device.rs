@@ -1,14 +1,63 @@
 use std::{
+  cell::RefCell,
   os::raw::{c_int, c_uchar},
-  ptr,
+  ptr, slice,
+  sync::Mutex,
 };
 
-use gtk::glib;
+use gtk::{gdk, glib, prelude::*};
 use x11_dl::{xinput2, xlib};
 
-use crate::event::{DeviceEvent, ElementState, RawKeyEvent};
+use crate::event::{DeviceEvent, DeviceId as RootDeviceId, ElementState, RawKeyEvent};
 
-use super::keycode_from_scancode;
+use super::{keycode_from_scancode, DeviceId, DEVICE_ID};
+
+lazy_static! {
+  /// The names of the seats seen so far, the device id of a seat is its index plus one.
+  static ref SEAT_NAMES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+}
+
+thread_local! {
+  /// The seats seen so far on this thread with their device ids. Holding the seats keeps their
+  /// pointers from being reused by another seat.
+  static SEATS: RefCell<Vec<(gdk::Seat, usize)>> = RefCell::new(Vec::new());
+}
+
+/// Returns the device id of `seat`, the same for all the events of a seat, or the dummy id
+/// for the events without one.
+///
+/// GDK has a seat for every Wayland seat, and for every master pointer and keyboard pair with
+/// X11 MPX.
+pub fn seat_device_id(seat: Option<gdk::Seat>) -> RootDeviceId {
+  let seat = match seat {
+    Some(seat) => seat,
+    None => return DEVICE_ID,
+  };
+  SEATS.with(|seats| {
+    let mut seats = seats.borrow_mut();
+    if let Some((_, id)) = seats.iter().find(|(known, _)| *known == seat) {
+      return RootDeviceId(DeviceId(*id));
+    }
+    // GTK 3 ignores the names of Wayland seats, the name of the master device is the closest.
+    let name = seat
+      .pointer()
+      .or_else(|| seat.keyboard())
+      .and_then(|device| device.name())
+      .map(Into::into)
+      .unwrap_or_default();
+    let mut names = SEAT_NAMES.lock().unwrap();
+    names.push(name);
+    let id = names.len();
+    seats.push((seat, id));
+    RootDeviceId(DeviceId(id))
+  })
+}
+
+/// Returns the name of the seat with the device id `id`.
+pub fn seat_name(id: usize) -> Option<String> {
+  let index = id.checked_sub(1)?;
+  SEAT_NAMES.lock().unwrap().get(index).cloned()
+}
 
 /// Spawn Device event thread. Only works on x11 since wayland doesn't have such global events.
 pub fn spawn(device_tx: glib::Sender<DeviceEvent>) {
@@ -18,7 +67,8 @@ pub fn spawn(device_tx: glib::Sender<DeviceEvent>) {
     let display = (xlib.XOpenDisplay)(ptr::null());
     let root = (xlib.XDefaultRootWindow)(display);
     // TODO Add more device event mask
-    let mask = xinput2::XI_RawKeyPressMask | xinput2::XI_RawKeyReleaseMask;
+    let mask =
+      xinput2::XI_RawKeyPressMask | xinput2::XI_RawKeyReleaseMask | xinput2::XI_RawMotionMask;
     let mut event_mask = xinput2::XIEventMask {
       deviceid: xinput2::XIAllMasterDevices,
       mask: &mask as *const _ as *mut c_uchar,
@@ -69,6 +119,33 @@ pub fn spawn(device_tx: glib::Sender<DeviceEvent>) {
                   break;
                 }
               }
+              xinput2::XI_RawMotion => {
+                let xev: &xinput2::XIRawEvent = &*(xev.data as *const _);
+                // The raw values, before acceleration, of the valuators set in the mask, the
+                // first two being the relative motion along the axes.
+                let mask =
+                  slice::from_raw_parts(xev.valuators.mask, xev.valuators.mask_len as usize);
+                let mut values = xev.raw_values;
+                let mut delta = (0.0, 0.0);
+                for valuator in 0..xev.valuators.mask_len * 8 {
+                  if xinput2::XIMaskIsSet(mask, valuator) {
+                    match valuator {
+                      0 => delta.0 = *values,
+                      1 => delta.1 = *values,
+                      _ => {}
+                    }
+                    values = values.add(1);
+                  }
+                }
+                if delta == (0.0, 0.0) {
+                  continue;
+                }
+
+                if let Err(e) = device_tx.send(DeviceEvent::MouseMotion { delta }) {
+                  log::info!("Failed to send device event {} since receiver is closed. Closing x11 thread along with it", e);
+                  break;
+                }
+              }
               _ => {}
             }
           }
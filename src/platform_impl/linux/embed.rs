@@ -0,0 +1,305 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  ffi::c_void,
+  os::raw::{c_int, c_long, c_uchar, c_ulong},
+};
+
+use gtk::{
+  gdk,
+  glib::{
+    self,
+    translate::{FromGlibPtrFull, FromGlibPtrNone, ToGlibPtr},
+  },
+  prelude::*,
+};
+use x11_dl::xlib;
+
+use super::util;
+
+/// The messages of the embedder giving the focus to the embedded window and taking it back, in
+/// the data of an `_XEMBED` client message.
+const XEMBED_FOCUS_IN: c_long = 4;
+const XEMBED_FOCUS_OUT: c_long = 5;
+/// The version of the protocol, and the flag of a mapped window in `_XEMBED_INFO`.
+const XEMBED_VERSION: c_ulong = 0;
+const XEMBED_MAPPED: c_ulong = 1;
+
+/// Returns the X11 window `xid` of another application, or `None` if there's none, or the
+/// display isn't an X11 one.
+pub fn foreign_window(display: &gdk::Display, xid: u64) -> Option<gdk::Window> {
+  if !util::is_x11(display) {
+    return None;
+  }
+  unsafe {
+    let display: *mut gdk::ffi::GdkDisplay = display.to_glib_none().0;
+    let window = gdk_x11_sys::gdk_x11_window_foreign_new_for_display(display as *mut _, xid as _);
+    (!window.is_null()).then(|| gdk::Window::from_glib_full(window as *mut gdk::ffi::GdkWindow))
+  }
+}
+
+/// Key under which the GTK window stores its [`Embedding`] while it's embedded.
+const EMBEDDING_KEY: &str = "tao-embedding";
+/// Key under which the GTK window stores whether the handlers of [`set_embed_parent`] are
+/// connected.
+const HANDLERS_KEY: &str = "tao-embed-handlers";
+/// Key under which the GTK window stores whether its GDK window translates the XEmbed messages.
+const CLIENT_FILTER_KEY: &str = "tao-embed-client-filter";
+
+/// The parent of an embedded window, and the state to give back to it as a top-level window.
+struct Embedding {
+  parent: gdk::Window,
+  decorated: bool,
+  skip_taskbar: bool,
+  skip_pager: bool,
+}
+
+/// Embeds `window` into `parent`, filling it, or makes it a top-level window again with `None`.
+///
+/// The window follows the size of `parent`, and the focus its application gives with the
+/// XEmbed protocol.
+pub fn set_embed_parent(window: &gtk::Window, parent: Option<gdk::Window>) {
+  connect_handlers(window);
+  let previous = unsafe { window.steal_data::<Embedding>(EMBEDDING_KEY) };
+  if let Some(previous) = &previous {
+    remove_parent_filter(window, &previous.parent);
+  }
+
+  let parent = match parent {
+    Some(parent) => parent,
+    None => {
+      if let Some(previous) = previous {
+        window.set_decorated(previous.decorated);
+        window.set_skip_taskbar_hint(previous.skip_taskbar);
+        window.set_skip_pager_hint(previous.skip_pager);
+        if let Some(gdk_window) = window.window() {
+          delete_xembed_info(&gdk_window);
+          // The window manager only manages a window mapped after it's back on the root window.
+          if let Some(root) = gdk_window.screen().root_window() {
+            let visible = window.is_visible();
+            window.hide();
+            gdk_window.reparent(&root, 0, 0);
+            if visible {
+              window.show();
+            }
+          }
+        }
+      }
+      return;
+    }
+  };
+
+  let embedding = previous.unwrap_or_else(|| Embedding {
+    parent: parent.clone(),
+    decorated: window.is_decorated(),
+    skip_taskbar: window.skips_taskbar_hint(),
+    skip_pager: window.skips_pager_hint(),
+  });
+  window.set_decorated(false);
+  window.set_skip_taskbar_hint(true);
+  window.set_skip_pager_hint(true);
+  window.move_(0, 0);
+  let scale = parent.scale_factor();
+  window.resize(
+    (parent.width() / scale).max(1),
+    (parent.height() / scale).max(1),
+  );
+
+  // The parent reports its size changes to the filter.
+  parent.set_events(parent.events() | gdk::EventMask::STRUCTURE_MASK);
+  let gtk_window: *mut gtk::ffi::GtkWindow = window.to_glib_none().0;
+  unsafe {
+    gdk::ffi::gdk_window_add_filter(
+      parent.to_glib_none().0,
+      Some(parent_filter),
+      gtk_window as *mut c_void,
+    );
+  }
+  unsafe {
+    window.set_data(
+      EMBEDDING_KEY,
+      Embedding {
+        parent: parent.clone(),
+        ..embedding
+      },
+    )
+  };
+
+  // Otherwise, it's reparented once it's realized.
+  if window.is_realized() {
+    reparent(window, &parent);
+  }
+}
+
+/// Connects the handlers following the embedding of `window`, once.
+fn connect_handlers(window: &gtk::Window) {
+  if unsafe { window.data::<bool>(HANDLERS_KEY) }.is_some() {
+    return;
+  }
+  unsafe { window.set_data(HANDLERS_KEY, true) };
+
+  window.connect_realize(|window| {
+    if let Some(parent) = embedding_parent(window) {
+      reparent(window, &parent);
+    }
+  });
+  // The embedder shows and hides the window following `XEMBED_MAPPED`.
+  window.connect_map(|window| {
+    if let (Some(_), Some(gdk_window)) = (embedding_parent(window), window.window()) {
+      set_xembed_info(&gdk_window, true);
+    }
+  });
+  window.connect_unmap(|window| {
+    if let (Some(_), Some(gdk_window)) = (embedding_parent(window), window.window()) {
+      set_xembed_info(&gdk_window, false);
+    }
+  });
+  window.connect_destroy(|window| {
+    if let Some(embedding) = unsafe { window.steal_data::<Embedding>(EMBEDDING_KEY) } {
+      remove_parent_filter(window, &embedding.parent);
+    }
+  });
+}
+
+fn embedding_parent(window: &gtk::Window) -> Option<gdk::Window> {
+  unsafe { window.data::<Embedding>(EMBEDDING_KEY) }
+    .map(|embedding| unsafe { embedding.as_ref() }.parent.clone())
+}
+
+/// Moves the realized `window` into `parent`.
+fn reparent(window: &gtk::Window, parent: &gdk::Window) {
+  let gdk_window = match window.window() {
+    Some(gdk_window) => gdk_window,
+    None => return,
+  };
+  set_xembed_info(&gdk_window, window.is_mapped());
+  // A window reparented before it's mapped is never managed by the window manager.
+  gdk_window.reparent(parent, 0, 0);
+  if unsafe { window.data::<bool>(CLIENT_FILTER_KEY) }.is_none() {
+    unsafe { window.set_data(CLIENT_FILTER_KEY, true) };
+    let gdk_window: *mut gdk::ffi::GdkWindow = gdk_window.to_glib_none().0;
+    unsafe {
+      gdk::ffi::gdk_window_add_filter(gdk_window, Some(client_filter), gdk_window as *mut _)
+    };
+  }
+}
+
+fn remove_parent_filter(window: &gtk::Window, parent: &gdk::Window) {
+  let gtk_window: *mut gtk::ffi::GtkWindow = window.to_glib_none().0;
+  unsafe {
+    gdk::ffi::gdk_window_remove_filter(
+      parent.to_glib_none().0,
+      Some(parent_filter),
+      gtk_window as *mut c_void,
+    );
+  }
+}
+
+/// Runs `f` with the Xlib display and the id of `window`.
+fn with_xwindow(window: &gdk::Window, f: impl FnOnce(&xlib::Xlib, *mut xlib::Display, c_ulong)) {
+  let xlib = match xlib::Xlib::open() {
+    Ok(xlib) => xlib,
+    Err(_) => return,
+  };
+  unsafe {
+    let display: *mut gdk::ffi::GdkDisplay = window.display().to_glib_none().0;
+    let xdisplay = gdk_x11_sys::gdk_x11_display_get_xdisplay(display as *mut _) as *mut _;
+    let xid = gdk_x11_sys::gdk_x11_window_get_xid(window.as_ptr() as *mut _);
+    f(&xlib, xdisplay, xid);
+  }
+}
+
+fn xembed_info_atom(window: &gdk::Window) -> xlib::Atom {
+  let display: *mut gdk::ffi::GdkDisplay = window.display().to_glib_none().0;
+  unsafe {
+    gdk_x11_sys::gdk_x11_get_xatom_by_name_for_display(
+      display as *mut _,
+      b"_XEMBED_INFO\0".as_ptr() as *const _,
+    )
+  }
+}
+
+/// Tells the embedder that the window speaks XEmbed, and whether it's mapped.
+fn set_xembed_info(window: &gdk::Window, mapped: bool) {
+  let atom = xembed_info_atom(window);
+  let info = xembed_info(mapped);
+  with_xwindow(window, |xlib, xdisplay, xid| unsafe {
+    (xlib.XChangeProperty)(
+      xdisplay,
+      xid,
+      atom,
+      atom,
+      32,
+      xlib::PropModeReplace,
+      info.as_ptr() as *const c_uchar,
+      info.len() as c_int,
+    );
+  });
+}
+
+fn delete_xembed_info(window: &gdk::Window) {
+  let atom = xembed_info_atom(window);
+  with_xwindow(window, |xlib, xdisplay, xid| unsafe {
+    (xlib.XDeleteProperty)(xdisplay, xid, atom);
+  });
+}
+
+/// The value of `_XEMBED_INFO`: the version of the protocol and the flags.
+fn xembed_info(mapped: bool) -> [c_ulong; 2] {
+  [XEMBED_VERSION, if mapped { XEMBED_MAPPED } else { 0 }]
+}
+
+/// Resizes the embedded window with its parent.
+unsafe extern "C" fn parent_filter(
+  xevent: *mut gdk::ffi::GdkXEvent,
+  _: *mut gdk::ffi::GdkEvent,
+  window: *mut c_void,
+) -> gdk::ffi::GdkFilterReturn {
+  let xevent = &*(xevent as *const xlib::XEvent);
+  if xevent.get_type() == xlib::ConfigureNotify {
+    let configure = xevent.configure;
+    let window = gtk::Window::from_glib_none(window as *mut gtk::ffi::GtkWindow);
+    let scale = window.scale_factor();
+    window.resize(
+      (configure.width / scale).max(1),
+      (configure.height / scale).max(1),
+    );
+  }
+  gdk::ffi::GDK_FILTER_CONTINUE
+}
+
+/// Translates the focus messages of the embedder into the focus events of the window, which
+/// GTK handles as the ones of a top-level window.
+unsafe extern "C" fn client_filter(
+  xevent: *mut gdk::ffi::GdkXEvent,
+  event: *mut gdk::ffi::GdkEvent,
+  window: *mut c_void,
+) -> gdk::ffi::GdkFilterReturn {
+  let xevent = &*(xevent as *const xlib::XEvent);
+  if xevent.get_type() != xlib::ClientMessage {
+    return gdk::ffi::GDK_FILTER_CONTINUE;
+  }
+  let window = window as *mut gdk::ffi::GdkWindow;
+  let display = gdk::ffi::gdk_window_get_display(window);
+  let message = xevent.client_message;
+  let xembed = gdk_x11_sys::gdk_x11_get_xatom_by_name_for_display(
+    display as *mut _,
+    b"_XEMBED\0".as_ptr() as *const _,
+  );
+  if message.message_type != xembed {
+    return gdk::ffi::GDK_FILTER_CONTINUE;
+  }
+  let focused = match message.data.get_long(1) {
+    XEMBED_FOCUS_IN => true,
+    XEMBED_FOCUS_OUT => false,
+    _ => return gdk::ffi::GDK_FILTER_REMOVE,
+  };
+  let focus = &mut (*event).focus_change;
+  focus.type_ = gdk::ffi::GDK_FOCUS_CHANGE;
+  // Released with the event.
+  focus.window = glib::gobject_ffi::g_object_ref(window as *mut _) as *mut _;
+  focus.send_event = 1;
+  focus.in_ = focused as i16;
+  gdk::ffi::GDK_FILTER_TRANSLATE
+}
@@ -3,20 +3,27 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-  cell::RefCell,
-  collections::{HashSet, VecDeque},
+  cell::{Cell, RefCell},
+  collections::{HashMap, HashSet, VecDeque},
   error::Error,
+  ffi::c_void,
+  os::unix::io::RawFd,
   process,
   rc::Rc,
-  sync::atomic::{AtomicBool, Ordering},
-  time::Instant,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, PoisonError,
+  },
+  time::{Duration, Instant},
 };
 
 use cairo::{RectangleInt, Region};
 use crossbeam_channel::SendError;
-use gdk::{Cursor, CursorType, EventKey, EventMask, ScrollDirection, WindowEdge, WindowState};
+use gdk::{
+  Cursor, CursorType, EventKey, EventMask, ModifierType, ScrollDirection, WindowEdge, WindowState,
+};
 use gio::Cancellable;
-use glib::{source::Priority, MainContext};
+use glib::{source::Priority, thread_guard::ThreadGuard, MainContext};
 use gtk::{
   cairo, gdk, gio,
   glib::{self},
@@ -25,30 +32,62 @@ use gtk::{
 
 use crate::{
   dpi::{LogicalPosition, LogicalSize, PhysicalPosition},
-  error::ExternalError,
+  error::{ExternalError, NotSupportedError},
   event::{
-    ElementState, Event, MouseButton, MouseScrollDelta, StartCause, TouchPhase, WindowEvent,
+    ElementState, Event, Force, Ime, MouseButton, MouseScrollDelta, OpenedItem, Pen, PenPhase,
+    StartCause, Touch, TouchIds, TouchPhase, WindowEvent,
+  },
+  event_loop::{
+    AccessibilityPrefs, ControlFlow, DeviceEventFilter, EventLoopClosed,
+    EventLoopWindowTarget as RootELW, IdleDeadline, PowerInfo, SystemAppearance,
   },
-  event_loop::{ControlFlow, EventLoopClosed, EventLoopWindowTarget as RootELW},
-  keyboard::ModifiersState,
+  keyboard::{Key, KeyCode, KeyboardLayout, ModifiersState},
   monitor::MonitorHandle as RootMonitorHandle,
+  platform::inject::SyntheticInput,
   platform_impl::platform::{device, DEVICE_ID},
-  window::{CursorIcon, Fullscreen, ProgressBarState, ResizeDirection, WindowId as RootWindowId},
+  window::{
+    ActivationToken, CursorIcon, Fullscreen, HitTestResult, ImePurpose, ProgressBarState,
+    ResizeDirection, WindowId as RootWindowId,
+  },
 };
 
 use super::{
-  keyboard,
+  appearance::{self, PortalSettings},
+  embed, global_shortcut, inject, keyboard, menu,
   monitor::{self, MonitorHandle},
-  taskbar, util,
-  window::{WindowId, WindowRequest},
+  notification::NotificationServer,
+  power, session, taskbar,
+  timer::WaitUntilTimer,
+  util,
+  window::{
+    is_shown, set_gtk_theme, EventHook, WindowId, WindowRequest, EVENT_HOOK_KEY, UNMAPPED_KEY,
+  },
 };
 
 use taskbar::TaskbarIndicator;
 
+/// Key under which the GTK window stores the input method context created in `WireUpEvents`.
+const IM_CONTEXT_KEY: &str = "tao-im-context";
+/// Key under which the GTK window stores whether its composition is reported, see
+/// `Window::set_ime_allowed`.
+const IME_ALLOWED_KEY: &str = "tao-ime-allowed";
+
+/// How long a maximized state change waits for the configure event with its final size.
+const MAXIMIZED_CONFIGURE_TIMEOUT: Duration = Duration::from_millis(100);
+/// Keys under which the GTK window keeps the gesture controllers created in `WireUpEvents`
+/// alive, since GTK3 widgets don't own their gestures.
+const GESTURE_ZOOM_KEY: &str = "tao-gesture-zoom";
+const GESTURE_ROTATE_KEY: &str = "tao-gesture-rotate";
+/// Key under which the GTK window stores whether the interactive drag in progress resizes it.
+const DRAG_RESIZE_KEY: &str = "tao-drag-resize";
+/// Key under which the GTK window stores its tick callback while frame callbacks are requested,
+/// see `Window::request_frame_callbacks`.
+const FRAME_CALLBACKS_KEY: &str = "tao-frame-callbacks";
+
 #[derive(Clone)]
-pub struct EventLoopWindowTarget<T> {
-  /// Gdk display
-  pub(crate) display: gdk::Display,
+pub struct EventLoopWindowTarget<T: 'static> {
+  /// Gdk display, `None` for a headless event loop without a display server
+  pub(crate) display: Option<gdk::Display>,
   /// Gtk application
   pub(crate) app: gtk::Application,
   /// Window Ids of the application
@@ -57,23 +96,45 @@ pub struct EventLoopWindowTarget<T> {
   pub(crate) window_requests_tx: glib::Sender<(WindowId, WindowRequest)>,
   /// Draw event sender
   pub(crate) draw_tx: crossbeam_channel::Sender<WindowId>,
+  /// Device event filter
+  pub(crate) device_event_filter: Rc<Cell<DeviceEventFilter>>,
+  /// App id of the windows that don't set one
+  pub(crate) default_app_id: Option<String>,
+  /// Event sender, for the events of sources registered after the event loop was created
+  pub(crate) event_tx: crossbeam_channel::Sender<Event<'static, T>>,
+  /// Event queue of the event loop
+  event_rx: crossbeam_channel::Receiver<Event<'static, T>>,
+  /// Draw queue of the event loop
+  draw_rx: crossbeam_channel::Receiver<WindowId>,
+  /// Set by an `EventLoopWaker` until the event loop starts a new iteration
+  pub(crate) woken: Arc<AtomicBool>,
+  /// Whether the event loop was built with `EventLoopBuilder::with_headless`
+  pub(crate) headless: bool,
+  /// System bus connection to read the power information, `None` without a system bus
+  pub(crate) power_conn: Option<zbus::blocking::Connection>,
+  /// Session bus connection to listen to the settings portal, `None` without a session bus
+  pub(crate) appearance_conn: Option<zbus::blocking::Connection>,
+  /// The appearance settings of the portal, updated by the appearance thread
+  pub(crate) portal_settings: Arc<Mutex<PortalSettings>>,
+  /// Connection to the notification server, created with the first notification
+  pub(crate) notification_server: Rc<RefCell<Option<NotificationServer>>>,
+  /// See `set_idle_callback`
+  pub(crate) idle_callback: Rc<RefCell<Option<Box<dyn FnMut(IdleDeadline)>>>>,
   _marker: std::marker::PhantomData<T>,
 }
 
-impl<T> EventLoopWindowTarget<T> {
+impl<T: 'static> EventLoopWindowTarget<T> {
   #[inline]
   pub fn monitor_from_point(&self, x: f64, y: f64) -> Option<MonitorHandle> {
-    monitor::from_point(&self.display, x, y)
+    monitor::from_point(self.display.as_ref()?, x, y)
   }
   #[inline]
   pub fn available_monitors(&self) -> VecDeque<MonitorHandle> {
     let mut handles = VecDeque::new();
-    let display = &self.display;
-    let numbers = display.n_monitors();
-
-    for i in 0..numbers {
-      let monitor = MonitorHandle::new(display, i);
-      handles.push_back(monitor);
+    if let Some(display) = &self.display {
+      for i in 0..display.n_monitors() {
+        handles.push_back(MonitorHandle::new(display, i));
+      }
     }
 
     handles
@@ -81,7 +142,7 @@ impl<T> EventLoopWindowTarget<T> {
 
   #[inline]
   pub fn primary_monitor(&self) -> Option<RootMonitorHandle> {
-    let monitor = self.display.primary_monitor();
+    let monitor = self.display.as_ref()?.primary_monitor();
     monitor.and_then(|monitor| {
       let handle = MonitorHandle { monitor };
       Some(RootMonitorHandle { inner: handle })
@@ -90,16 +151,15 @@ impl<T> EventLoopWindowTarget<T> {
 
   #[cfg(feature = "rwh_05")]
   pub fn raw_display_handle_rwh_05(&self) -> rwh_05::RawDisplayHandle {
-    if self.is_wayland() {
+    if let Some(display) = self.wayland_display() {
       let mut display_handle = rwh_05::WaylandDisplayHandle::empty();
-      display_handle.display = unsafe {
-        gdk_wayland_sys::gdk_wayland_display_get_wl_display(self.display.as_ptr() as *mut _)
-      };
+      display_handle.display =
+        unsafe { gdk_wayland_sys::gdk_wayland_display_get_wl_display(display.as_ptr() as *mut _) };
       rwh_05::RawDisplayHandle::Wayland(display_handle)
     } else {
       let mut display_handle = rwh_05::XlibDisplayHandle::empty();
       unsafe {
-        if let Ok(xlib) = x11_dl::xlib::Xlib::open() {
+        if let (Some(_), Ok(xlib)) = (&self.display, x11_dl::xlib::Xlib::open()) {
           let display = (xlib.XOpenDisplay)(std::ptr::null());
           display_handle.display = display as _;
           display_handle.screen = (xlib.XDefaultScreen)(display) as _;
@@ -112,10 +172,11 @@ impl<T> EventLoopWindowTarget<T> {
 
   #[cfg(feature = "rwh_06")]
   pub fn raw_display_handle_rwh_06(&self) -> Result<rwh_06::RawDisplayHandle, rwh_06::HandleError> {
-    if self.is_wayland() {
-      let display = unsafe {
-        gdk_wayland_sys::gdk_wayland_display_get_wl_display(self.display.as_ptr() as *mut _)
-      };
+    if self.display.is_none() {
+      Err(rwh_06::HandleError::Unavailable)
+    } else if let Some(display) = self.wayland_display() {
+      let display =
+        unsafe { gdk_wayland_sys::gdk_wayland_display_get_wl_display(display.as_ptr() as *mut _) };
       let display = unsafe { std::ptr::NonNull::new_unchecked(display) };
       let display_handle = rwh_06::WaylandDisplayHandle::new(display);
       Ok(rwh_06::RawDisplayHandle::Wayland(display_handle))
@@ -135,11 +196,41 @@ impl<T> EventLoopWindowTarget<T> {
   }
 
   pub fn is_wayland(&self) -> bool {
-    self.display.backend().is_wayland()
+    self.wayland_display().is_some()
   }
 
   pub fn is_x11(&self) -> bool {
-    self.display.backend().is_x11()
+    self.display.as_ref().map_or(false, util::is_x11)
+  }
+
+  #[inline]
+  pub fn is_headless(&self) -> bool {
+    self.headless
+  }
+
+  #[inline]
+  pub fn has_window(&self, window_id: WindowId) -> bool {
+    self.windows.borrow().contains(&window_id)
+  }
+
+  /// The `wl_display` the event loop is connected to, `None` on X11.
+  pub fn wl_display(&self) -> Option<*mut c_void> {
+    self.wayland_display().map(|display| unsafe {
+      gdk_wayland_sys::gdk_wayland_display_get_wl_display(display.as_ptr() as *mut _)
+    })
+  }
+
+  fn wayland_display(&self) -> Option<&gdk::Display> {
+    self
+      .display
+      .as_ref()
+      .filter(|display| util::is_wayland(display))
+  }
+
+  #[inline]
+  pub fn set_device_event_filter(&self, filter: DeviceEventFilter) -> Result<(), ExternalError> {
+    self.device_event_filter.set(filter);
+    Ok(())
   }
 
   #[inline]
@@ -148,7 +239,118 @@ impl<T> EventLoopWindowTarget<T> {
   }
 
   #[inline]
-  pub fn set_progress_bar(&self, progress: ProgressBarState) {
+  pub fn accessibility_preferences(&self) -> AccessibilityPrefs {
+    appearance::accessibility_prefs(&self.portal_settings())
+  }
+
+  #[inline]
+  pub fn system_appearance(&self) -> SystemAppearance {
+    appearance::system_appearance(&self.portal_settings())
+  }
+
+  fn portal_settings(&self) -> PortalSettings {
+    *self
+      .portal_settings
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+  }
+
+  #[inline]
+  pub fn power_info(&self) -> PowerInfo {
+    power::power_info(self.power_conn.as_ref())
+  }
+
+  #[inline]
+  pub fn set_idle_callback(&self, callback: Option<Box<dyn FnMut(IdleDeadline)>>) {
+    *self.idle_callback.borrow_mut() = callback;
+  }
+
+  pub fn run_nested_until(
+    &self,
+    target: &RootELW<T>,
+    predicate: &mut dyn FnMut() -> bool,
+    handler: &mut dyn FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
+  ) -> Result<(), ExternalError> {
+    // The signal handlers only send the events, so the nested loop takes them from the same
+    // channels as `run_return`, which resumes its own iteration once the event handler returns.
+    let mut control_flow = ControlFlow::Wait;
+    let mut state = EventState::NewStart;
+    let wait_until_timer = WaitUntilTimer::new();
+    loop {
+      // Between iterations only, `step` only blocks when it didn't call `handler`.
+      if matches!(state, EventState::NewStart) && predicate() {
+        break;
+      }
+      let blocking = step(
+        target,
+        &mut state,
+        &wait_until_timer,
+        handler,
+        &mut control_flow,
+      );
+      if let ControlFlow::ExitWithCode(_) = control_flow {
+        break;
+      }
+      self.main_iteration(blocking);
+    }
+    Ok(())
+  }
+
+  /// Runs an iteration of the main context, which dispatches the signals of the windows.
+  fn main_iteration(&self, blocking: bool) {
+    if self.display.is_some() {
+      gtk::main_iteration_do(blocking);
+    } else {
+      MainContext::default().iteration(blocking);
+    }
+  }
+
+  #[inline]
+  pub fn key_for_keycode(&self, code: KeyCode) -> Option<Key<'static>> {
+    keyboard::key_for_keycode(code)
+  }
+
+  #[inline]
+  pub fn keyboard_layout(&self) -> Option<KeyboardLayout> {
+    keyboard::keyboard_layout()
+  }
+
+  pub fn inject_input(&self, input: SyntheticInput) -> Result<(), ExternalError> {
+    match &self.display {
+      Some(display) if !self.is_wayland() => inject::inject_input(display, input),
+      _ => Err(ExternalError::NotSupported(NotSupportedError::new())),
+    }
+  }
+
+  pub fn register_global_shortcut(
+    &self,
+    id: u32,
+    modifiers: ModifiersState,
+    key: KeyCode,
+  ) -> Result<(), ExternalError> {
+    match &self.display {
+      Some(display) if !self.is_wayland() => {
+        global_shortcut::register(display, &self.event_tx, id, modifiers, key)
+      }
+      _ => Err(ExternalError::NotSupported(NotSupportedError::new())),
+    }
+  }
+
+  pub fn unregister_global_shortcut(&self, id: u32) -> Result<(), ExternalError> {
+    match &self.display {
+      Some(display) if !self.is_wayland() => global_shortcut::unregister(display, id),
+      _ => Err(ExternalError::NotSupported(NotSupportedError::new())),
+    }
+  }
+
+  #[inline]
+  pub fn set_progress_bar(&self, mut progress: ProgressBarState) {
+    if self.display.is_none() {
+      return;
+    }
+    if progress.unity_uri.is_none() {
+      progress.unity_uri = self.default_app_id.as_deref().map(taskbar::unity_uri);
+    }
     if let Err(e) = self
       .window_requests_tx
       .send((WindowId::dummy(), WindowRequest::ProgressBarState(progress)))
@@ -156,6 +358,47 @@ impl<T> EventLoopWindowTarget<T> {
       log::warn!("Fail to send update progress bar request: {}", e);
     }
   }
+
+  #[inline]
+  pub fn create_waker(&self) -> EventLoopWaker {
+    EventLoopWaker {
+      woken: self.woken.clone(),
+    }
+  }
+
+  /// Forces the scale GDK renders the windows at, which is only possible on X11.
+  fn set_gdk_scale(&self, scale: i32) {
+    match &self.display {
+      Some(display) if self.is_x11() => unsafe {
+        gdk_x11_sys::gdk_x11_display_set_window_scale(display.as_ptr() as *mut _, scale)
+      },
+      _ => log::warn!("The GDK scale can only be set on X11"),
+    }
+  }
+
+  /// Emits `Event::FdReady(token)` whenever `fd` meets `interest`, until the source is removed.
+  pub fn add_fd_watch(&self, fd: RawFd, interest: glib::IOCondition, token: u64) -> glib::SourceId {
+    let event_tx = self.event_tx.clone();
+    glib::source::unix_fd_add_local(fd, interest, move |_, _| {
+      if let Err(e) = event_tx.send(Event::FdReady(token)) {
+        log::warn!("Failed to send fd ready event to event channel: {}", e);
+      }
+      glib::ControlFlow::Continue
+    })
+  }
+}
+
+/// Wakes the event loop up through the default main context.
+#[derive(Clone)]
+pub struct EventLoopWaker {
+  woken: Arc<AtomicBool>,
+}
+
+impl EventLoopWaker {
+  pub fn wake(&self) {
+    self.woken.store(true, Ordering::Release);
+    MainContext::default().wakeup();
+  }
 }
 
 pub struct EventLoop<T: 'static> {
@@ -163,37 +406,96 @@ pub struct EventLoop<T: 'static> {
   window_target: RootELW<T>,
   /// User event sender for EventLoopProxy
   pub(crate) user_event_tx: crossbeam_channel::Sender<Event<'static, T>>,
-  /// Event queue of EventLoop
-  events: crossbeam_channel::Receiver<Event<'static, T>>,
-  /// Draw queue of EventLoop
-  draws: crossbeam_channel::Receiver<WindowId>,
   /// Boolean to control device event thread
   run_device_thread: Option<Rc<AtomicBool>>,
+  /// Boolean to control power event thread
+  run_power_thread: Option<Rc<AtomicBool>>,
+  /// The thread of the power signals, stopped and joined once the event loop stops
+  power_thread: Option<power::PowerThread>,
+  /// Boolean to control appearance event thread
+  run_appearance_thread: Option<Rc<AtomicBool>>,
 }
 
-#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct PlatformSpecificEventLoopAttributes {
   pub(crate) any_thread: bool,
+  pub(crate) default_app_id: Option<String>,
+  pub(crate) headless: bool,
+  pub(crate) single_instance: Option<String>,
+  pub(crate) gdk_scale: Option<i32>,
 }
 
 impl<T: 'static> EventLoop<T> {
+  /// Same as `new`, but returns an error when GTK already runs on another thread.
+  pub(crate) fn try_new(
+    attrs: &PlatformSpecificEventLoopAttributes,
+  ) -> Result<Self, ExternalError> {
+    if gtk::is_initialized() && !gtk::is_initialized_main_thread() {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
+    Ok(Self::new(attrs))
+  }
+
   pub(crate) fn new(attrs: &PlatformSpecificEventLoopAttributes) -> EventLoop<T> {
     if !attrs.any_thread {
       assert_is_main_thread("new_any_thread");
     }
+    // GTK keeps the default main context acquired by the thread it was initialized on.
+    assert!(
+      !gtk::is_initialized() || gtk::is_initialized_main_thread(),
+      "GTK is already running on another thread. Every EventLoop has to be created on the thread \
+       of the first one, GTK doesn't support several threads."
+    );
 
     let context = MainContext::default();
-    context
-      .with_thread_default(|| EventLoop::new_gtk().expect("Failed to initialize gtk backend!"))
-      .expect("Failed to initialize gtk backend!")
+    let event_loop: EventLoop<T> = context
+      .with_thread_default(|| {
+        EventLoop::new_gtk(
+          attrs.default_app_id.clone(),
+          attrs.headless,
+          attrs.single_instance.as_deref(),
+        )
+        .expect("Failed to initialize gtk backend!")
+      })
+      .expect("Failed to initialize gtk backend!");
+    if let Some(scale) = attrs.gdk_scale {
+      event_loop.window_target.p.set_gdk_scale(scale);
+    }
+    event_loop
   }
 
-  fn new_gtk() -> Result<EventLoop<T>, Box<dyn Error>> {
+  fn new_gtk(
+    default_app_id: Option<String>,
+    headless: bool,
+    single_instance: Option<&str>,
+  ) -> Result<EventLoop<T>, Box<dyn Error>> {
     let context = MainContext::default();
-    let app = gtk::Application::new(None, gio::ApplicationFlags::empty());
+    // Registering the application initializes GTK, which exits the process when there's no
+    // display server. A headless loop checks first and runs on the bare glib context instead.
+    let has_display = !headless || gtk::init().is_ok();
+    // The application owns the D-Bus name of its id, which makes it single instance.
+    let single_instance_id = single_instance
+      .filter(|_| has_display)
+      .map(util::application_id);
+    let mut flags = gio::ApplicationFlags::HANDLES_OPEN;
+    if single_instance_id.is_some() {
+      flags |= gio::ApplicationFlags::HANDLES_COMMAND_LINE;
+    }
+    let app = gtk::Application::new(single_instance_id.as_deref(), flags);
     let app_ = app.clone();
     let cancellable: Option<&Cancellable> = None;
-    app.register(cancellable)?;
+    if has_display {
+      app.register(cancellable)?;
+      if app.is_remote() {
+        // Running the remote application sends the command line to the primary instance,
+        // along with the activation token GTK adds to its platform data.
+        let args: Vec<String> = std::env::args_os()
+          .map(|arg| arg.to_string_lossy().into_owned())
+          .collect();
+        app.run_with_args(&args);
+        process::exit(0);
+      }
+    }
 
     // Send StartCause::Init event
     let (event_tx, event_rx) = crossbeam_channel::unbounded();
@@ -204,19 +506,154 @@ impl<T: 'static> EventLoop<T> {
         log::warn!("Failed to send init event to event channel: {}", e);
       }
     });
+    let event_tx_ = event_tx.clone();
+    app.connect_open(move |_, files, _| {
+      let items = files
+        .iter()
+        .map(|file| match file.path() {
+          Some(path) => OpenedItem::Path(path),
+          None => OpenedItem::Url(file.uri().into()),
+        })
+        .collect();
+      if let Err(e) = event_tx_.send(Event::Opened { items }) {
+        log::warn!("Failed to send opened event to event channel: {}", e);
+      }
+    });
+    if single_instance_id.is_some() {
+      let event_tx_ = event_tx.clone();
+      app.connect_command_line(move |_, command_line| {
+        let activation_token = command_line
+          .platform_data()
+          .and_then(|data| {
+            let data = glib::VariantDict::new(Some(&data));
+            ["activation-token", "desktop-startup-id"]
+              .iter()
+              .find_map(|key| data.lookup::<String>(key).ok().flatten())
+          })
+          .filter(|token| !token.is_empty())
+          .map(ActivationToken::from_raw);
+        if let Err(e) = event_tx_.send(Event::SecondInstance {
+          args: command_line.arguments(),
+          cwd: command_line.cwd().unwrap_or_default(),
+          activation_token,
+        }) {
+          log::warn!(
+            "Failed to send second instance event to event channel: {}",
+            e
+          );
+        }
+        0
+      });
+    }
+    let appearance_conn = zbus::blocking::Connection::session().ok();
+    let portal_settings = Arc::new(Mutex::new(
+      appearance_conn
+        .as_ref()
+        .map(PortalSettings::read)
+        .unwrap_or_default(),
+    ));
+    let update_appearance: Rc<dyn Fn()> = {
+      let portal_settings = portal_settings.clone();
+      let read = move || {
+        appearance::system_appearance(
+          &portal_settings
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner),
+        )
+      };
+      let appearance = Cell::new(read());
+      let event_tx_ = event_tx.clone();
+      Rc::new(move || {
+        let new_appearance = read();
+        let old_appearance = appearance.replace(new_appearance);
+        if old_appearance.accessibility != new_appearance.accessibility {
+          if let Err(e) = event_tx_.send(Event::AccessibilityPreferencesChanged(
+            new_appearance.accessibility,
+          )) {
+            log::warn!(
+              "Failed to send accessibility preferences changed event to event channel: {}",
+              e
+            );
+          }
+        }
+        if old_appearance != new_appearance {
+          if let Err(e) = event_tx_.send(Event::SystemAppearanceChanged(new_appearance)) {
+            log::warn!(
+              "Failed to send system appearance changed event to event channel: {}",
+              e
+            );
+          }
+        }
+      })
+    };
+    // The accessibility preferences the portal doesn't have come from these settings.
+    if let Some(settings) = has_display.then(gtk::Settings::default).flatten() {
+      let update_appearance = update_appearance.clone();
+      settings.connect_notify_local(None, move |_, pspec| {
+        if matches!(pspec.name(), "gtk-enable-animations" | "gtk-theme-name") {
+          update_appearance();
+        }
+      });
+    }
+    if let Some(display) = has_display.then(gdk::Display::default).flatten() {
+      keyboard::watch_keyboard_layout(&display, &event_tx);
+      let event_tx_ = event_tx.clone();
+      display.connect_monitor_added(move |_, monitor| {
+        let monitor = RootMonitorHandle {
+          inner: MonitorHandle {
+            monitor: monitor.clone(),
+          },
+        };
+        if let Err(e) = event_tx_.send(Event::MonitorConnected(monitor)) {
+          log::warn!(
+            "Failed to send monitor connected event to event channel: {}",
+            e
+          );
+        }
+      });
+      let event_tx_ = event_tx.clone();
+      display.connect_monitor_removed(move |_, monitor| {
+        let monitor = RootMonitorHandle {
+          inner: MonitorHandle {
+            monitor: monitor.clone(),
+          },
+        };
+        if let Err(e) = event_tx_.send(Event::MonitorDisconnected(monitor)) {
+          log::warn!(
+            "Failed to send monitor disconnected event to event channel: {}",
+            e
+          );
+        }
+      });
+    }
+    menu::set_event_sender(event_tx.clone());
     let draw_tx_ = draw_tx.clone();
     let user_event_tx = event_tx.clone();
 
     // Create event loop window target.
     let (window_requests_tx, window_requests_rx) = glib::MainContext::channel(Priority::default());
-    let display = gdk::Display::default()
-      .expect("GdkDisplay not found. This usually means `gkt_init` hasn't called yet.");
+    let display = has_display.then(|| {
+      gdk::Display::default()
+        .expect("GdkDisplay not found. This usually means `gkt_init` hasn't called yet.")
+    });
     let window_target = EventLoopWindowTarget {
       display,
       app,
       windows: Rc::new(RefCell::new(HashSet::new())),
       window_requests_tx,
       draw_tx: draw_tx_,
+      device_event_filter: Rc::new(Cell::new(DeviceEventFilter::default())),
+      default_app_id,
+      event_tx: event_tx.clone(),
+      event_rx,
+      draw_rx,
+      woken: Default::default(),
+      headless,
+      power_conn: zbus::blocking::Connection::system().ok(),
+      appearance_conn,
+      portal_settings,
+      notification_server: Default::default(),
+      idle_callback: Default::default(),
       _marker: std::marker::PhantomData,
     };
 
@@ -226,13 +663,24 @@ impl<T: 'static> EventLoop<T> {
       let user_event_tx = user_event_tx.clone();
       let run_device_thread = Rc::new(AtomicBool::new(true));
       let run = run_device_thread.clone();
+      let device_event_filter = window_target.device_event_filter.clone();
       device::spawn(device_tx);
       device_rx.attach(Some(&context), move |event| {
-        if let Err(e) = user_event_tx.send(Event::DeviceEvent {
-          device_id: DEVICE_ID,
-          event,
-        }) {
-          log::warn!("Fail to send device event to event channel: {}", e);
+        let report = match device_event_filter.get() {
+          DeviceEventFilter::Always => false,
+          DeviceEventFilter::Unfocused => gtk::Window::list_toplevels()
+            .iter()
+            .filter_map(|w| w.downcast_ref::<gtk::Window>())
+            .any(|w| w.is_active()),
+          DeviceEventFilter::Never => true,
+        };
+        if report {
+          if let Err(e) = user_event_tx.send(Event::DeviceEvent {
+            device_id: DEVICE_ID,
+            event,
+          }) {
+            log::warn!("Fail to send device event to event channel: {}", e);
+          }
         }
         if run.load(Ordering::Relaxed) {
           glib::ControlFlow::Continue
@@ -245,13 +693,85 @@ impl<T: 'static> EventLoop<T> {
       None
     };
 
+    // Spawn threads to receive power and session changes from the system bus.
+    let mut power_thread = None;
+    let run_power_thread = window_target.power_conn.clone().map(|conn| {
+      let (power_tx, power_rx) = glib::MainContext::channel(glib::Priority::default());
+      let (session_tx, session_rx) = glib::MainContext::channel(glib::Priority::default());
+      let event_tx = event_tx.clone();
+      let run_power_thread = Rc::new(AtomicBool::new(true));
+      let run = run_power_thread.clone();
+      session::spawn(conn.clone(), session_tx);
+      power_thread = Some(power::spawn(conn, power_tx));
+      let session_event_tx = event_tx.clone();
+      let run_session = run_power_thread.clone();
+      session_rx.attach(Some(&context), move |change| {
+        if let Err(e) = session_event_tx.send(Event::SessionChanged(change)) {
+          log::warn!(
+            "Failed to send session changed event to event channel: {}",
+            e
+          );
+        }
+        if run_session.load(Ordering::Relaxed) {
+          glib::ControlFlow::Continue
+        } else {
+          glib::ControlFlow::Break
+        }
+      });
+      power_rx.attach(Some(&context), move |info| {
+        if let Err(e) = event_tx.send(Event::PowerInfoChanged(info)) {
+          log::warn!(
+            "Failed to send power info changed event to event channel: {}",
+            e
+          );
+        }
+        if run.load(Ordering::Relaxed) {
+          glib::ControlFlow::Continue
+        } else {
+          glib::ControlFlow::Break
+        }
+      });
+      run_power_thread
+    });
+
+    // Spawn thread to receive appearance changes from the settings portal.
+    let portal_settings = window_target.portal_settings.clone();
+    let run_appearance_thread = window_target.appearance_conn.clone().map(|conn| {
+      let (appearance_tx, appearance_rx) = glib::MainContext::channel(glib::Priority::default());
+      let run_appearance_thread = Rc::new(AtomicBool::new(true));
+      let run = run_appearance_thread.clone();
+      appearance::spawn(conn, portal_settings, appearance_tx);
+      appearance_rx.attach(Some(&context), move |()| {
+        update_appearance();
+        if run.load(Ordering::Relaxed) {
+          glib::ControlFlow::Continue
+        } else {
+          glib::ControlFlow::Break
+        }
+      });
+      run_appearance_thread
+    });
+
     let mut taskbar: Option<TaskbarIndicator> = None;
     let supports_unity = util::is_unity();
+    // The video modes to restore once the windows in exclusive fullscreen leave it.
+    let saved_modes: Rc<RefCell<HashMap<WindowId, monitor::SavedMode>>> = Default::default();
 
     // Window Request
     window_requests_rx.attach(Some(&context), move |(id, request)| {
       if let Some(window) = app_.window_by_id(id.0) {
         match request {
+          WindowRequest::Close => {
+            // An unrealized window can't get the delete event of `close`.
+            if window.is_realized() {
+              window.close();
+            } else if let Err(e) = event_tx.send(Event::WindowEvent {
+              window_id: RootWindowId(id),
+              event: WindowEvent::CloseRequested,
+            }) {
+              log::warn!("Failed to send window close event to event channel: {}", e);
+            }
+          }
           WindowRequest::Title(title) => window.set_title(&title),
           WindowRequest::Position((x, y)) => window.move_(x, y),
           WindowRequest::Size((w, h)) => window.resize(w, h),
@@ -260,12 +780,17 @@ impl<T: 'static> EventLoop<T> {
           }
           WindowRequest::Visible(visible) => {
             if visible {
-              window.show_all();
+              util::show_keeping_state(&window);
             } else {
-              window.hide();
+              util::hide_keeping_state(&window);
             }
           }
-          WindowRequest::Focus => {
+          WindowRequest::Focus(token) => {
+            // GTK uses the startup id as the xdg-activation token on Wayland and to pick
+            // the user time on X11.
+            if let Some(token) = token {
+              window.set_startup_id(&token);
+            }
             window.present_with_time(gdk::ffi::GDK_CURRENT_TIME as _);
           }
           WindowRequest::Resizable(resizable) => window.set_resizable(resizable),
@@ -278,6 +803,7 @@ impl<T: 'static> EventLoop<T> {
             }
           }
           WindowRequest::Maximized(maximized) => {
+            util::update_hidden_state(&window, WindowState::MAXIMIZED, maximized);
             if maximized {
               window.maximize();
             } else {
@@ -291,6 +817,7 @@ impl<T: 'static> EventLoop<T> {
               .and_then(|seat| seat.pointer())
             {
               let (_, x, y) = cursor.position();
+              begin_drag_resize(&window, id, false, &event_tx);
               window.begin_move_drag(1, x, y, 0);
             }
           }
@@ -301,6 +828,7 @@ impl<T: 'static> EventLoop<T> {
               .and_then(|seat| seat.pointer())
             {
               let (_, x, y) = cursor.position();
+              begin_drag_resize(&window, id, true, &event_tx);
               window.begin_resize_drag(
                 direction.to_gtk_edge(),
                 1,
@@ -310,37 +838,68 @@ impl<T: 'static> EventLoop<T> {
               );
             }
           }
-          WindowRequest::Fullscreen(fullscreen) => match fullscreen {
-            Some(f) => {
-              if let Fullscreen::Borderless(m) = f {
-                if let Some(monitor) = m {
-                  let display = window.display();
-                  let monitor = monitor.inner;
-                  let monitors = display.n_monitors();
-                  for i in 0..monitors {
-                    let m = display.monitor(i).unwrap();
-                    if m == monitor.monitor {
-                      let screen = display.default_screen();
-                      window.fullscreen_on_monitor(&screen, i);
-                    }
+          WindowRequest::StartDrag(data, image) => {
+            let tx_clone = event_tx.clone();
+            util::start_drag(&window, data, &image, move |result| {
+              if let Err(e) = tx_clone.send(Event::WindowEvent {
+                window_id: RootWindowId(id),
+                event: WindowEvent::DragFinished(result),
+              }) {
+                log::warn!("Failed to send drag finished event to event channel: {}", e);
+              }
+            });
+          }
+          // An empty slot was already applied by an earlier request.
+          WindowRequest::Fullscreen(request) => {
+            if let Some(fullscreen) = request.lock().unwrap().take() {
+              // Leaving exclusive fullscreen, or switching to another mode, starts from the mode
+              // the monitor had before.
+              if let Some(mode) = saved_modes.borrow_mut().remove(&id) {
+                monitor::restore_video_mode(&window.display(), mode);
+              }
+              match fullscreen {
+                Some(Fullscreen::Borderless(m)) => {
+                  util::update_hidden_state(&window, WindowState::FULLSCREEN, true);
+                  util::fullscreen_on(&window, m.as_ref().map(|m| &m.inner.monitor))
+                }
+                Some(Fullscreen::Exclusive(video_mode)) => {
+                  let video_mode = video_mode.video_mode;
+                  if let Some(mode) = monitor::set_video_mode(&video_mode) {
+                    // Restored when leaving fullscreen, or when the window is destroyed, see
+                    // `WireUpEvents`.
+                    saved_modes.borrow_mut().insert(id, mode);
                   }
-                } else {
-                  window.fullscreen();
+                  util::update_hidden_state(&window, WindowState::FULLSCREEN, true);
+                  util::fullscreen_on(&window, Some(&video_mode.monitor.monitor))
+                }
+                None => {
+                  util::update_hidden_state(&window, WindowState::FULLSCREEN, false);
+                  window.unfullscreen()
                 }
               }
             }
-            None => window.unfullscreen(),
-          },
+          }
           WindowRequest::Decorations(decorations) => window.set_decorated(decorations),
           WindowRequest::AlwaysOnBottom(always_on_bottom) => {
+            util::update_hidden_state(&window, WindowState::BELOW, always_on_bottom);
+            if always_on_bottom {
+              util::update_hidden_state(&window, WindowState::ABOVE, false);
+              window.set_keep_above(false);
+            }
             window.set_keep_below(always_on_bottom)
           }
-          WindowRequest::AlwaysOnTop(always_on_top) => window.set_keep_above(always_on_top),
-          WindowRequest::WindowIcon(window_icon) => {
-            if let Some(icon) = window_icon {
-              window.set_icon(Some(&icon.inner.into()));
+          WindowRequest::AlwaysOnTop(always_on_top) => {
+            util::update_hidden_state(&window, WindowState::ABOVE, always_on_top);
+            if always_on_top {
+              util::update_hidden_state(&window, WindowState::BELOW, false);
+              window.set_keep_below(false);
             }
+            window.set_keep_above(always_on_top)
           }
+          WindowRequest::WindowIcon(window_icon) => match window_icon {
+            Some(icon) => window.set_icon_list(&icon.inner.to_pixbufs()),
+            None => window.set_icon_list(&[]),
+          },
           WindowRequest::UserAttention(request_type) => {
             window.set_urgency_hint(request_type.is_some())
           }
@@ -348,7 +907,92 @@ impl<T: 'static> EventLoop<T> {
             window.set_skip_taskbar_hint(skip);
             window.set_skip_pager_hint(skip)
           }
+          WindowRequest::ResetDeadKeys => {
+            // Resetting drops any pending dead key or compose sequence.
+            if let Some(ime) = unsafe { window.data::<gtk::IMMulticontext>(IM_CONTEXT_KEY) } {
+              unsafe { ime.as_ref() }.reset();
+            }
+          }
+          WindowRequest::ImePurpose(purpose) => {
+            if let Some(ime) = unsafe { window.data::<gtk::IMMulticontext>(IM_CONTEXT_KEY) } {
+              let (purpose, hints) = match purpose {
+                ImePurpose::Normal => (gtk::InputPurpose::FreeForm, gtk::InputHints::NONE),
+                ImePurpose::Password => (
+                  gtk::InputPurpose::Password,
+                  gtk::InputHints::NO_SPELLCHECK | gtk::InputHints::NO_EMOJI,
+                ),
+                ImePurpose::Number => (gtk::InputPurpose::Number, gtk::InputHints::NO_SPELLCHECK),
+                ImePurpose::Terminal => {
+                  (gtk::InputPurpose::Terminal, gtk::InputHints::NO_SPELLCHECK)
+                }
+              };
+              let ime = unsafe { ime.as_ref() };
+              ime.set_input_purpose(purpose);
+              ime.set_input_hints(hints);
+            }
+          }
+          WindowRequest::ImeCursorArea((x, y, width, height)) => {
+            if let Some(ime) = unsafe { window.data::<gtk::IMMulticontext>(IM_CONTEXT_KEY) } {
+              unsafe { ime.as_ref() }
+                .set_cursor_location(&gdk::Rectangle::new(x, y, width, height));
+            }
+          }
+          WindowRequest::ImeAllowed(allowed) => {
+            if let Some(ime_allowed) = unsafe { window.data::<Rc<Cell<bool>>>(IME_ALLOWED_KEY) } {
+              let ime_allowed = unsafe { ime_allowed.as_ref() };
+              if ime_allowed.get() != allowed {
+                if !allowed {
+                  if let Some(ime) = unsafe { window.data::<gtk::IMMulticontext>(IM_CONTEXT_KEY) } {
+                    // Drops the text being composed while its clearing is still reported.
+                    unsafe { ime.as_ref() }.reset();
+                  }
+                }
+                ime_allowed.set(allowed);
+                let ime = if allowed { Ime::Enabled } else { Ime::Disabled };
+                if let Err(e) = event_tx.send(Event::WindowEvent {
+                  window_id: RootWindowId(id),
+                  event: WindowEvent::Ime(ime),
+                }) {
+                  log::warn!("Failed to send IME event to event channel: {}", e);
+                }
+              }
+            }
+          }
+          WindowRequest::Theme(theme) => set_gtk_theme(theme),
+          // GTK drops input events of insensitive widgets, but still delivers configure and
+          // draw events.
+          WindowRequest::Enable(enabled) => window.set_sensitive(enabled),
+          WindowRequest::Parent(parent) => {
+            let parent = parent.and_then(|parent| app_.window_by_id(parent.0));
+            window.set_transient_for(parent.as_ref());
+          }
+          WindowRequest::EmbedParent(parent) => match parent {
+            Some(xid) => match embed::foreign_window(&window.display(), xid) {
+              Some(parent) => embed::set_embed_parent(&window, Some(parent)),
+              None => log::warn!("Failed to embed the window: no X11 window {:#x}", xid),
+            },
+            None => embed::set_embed_parent(&window, None),
+          },
+          WindowRequest::Raise => {
+            if let Some(gdk_window) = window.window() {
+              gdk_window.raise();
+            }
+          }
+          WindowRequest::Lower => {
+            if let Some(gdk_window) = window.window() {
+              gdk_window.lower();
+            }
+          }
+          WindowRequest::RestackAbove(sibling) => {
+            let sibling = app_
+              .window_by_id(sibling.0)
+              .and_then(|sibling| sibling.window());
+            if let (Some(gdk_window), Some(sibling)) = (window.window(), sibling) {
+              gdk_window.restack(Some(&sibling), true);
+            }
+          }
           WindowRequest::SetVisibleOnAllWorkspaces(visible) => {
+            util::update_hidden_state(&window, WindowState::STICKY, visible);
             if visible {
               window.stick();
             } else {
@@ -356,17 +1000,19 @@ impl<T: 'static> EventLoop<T> {
             }
           }
           WindowRequest::CursorIcon(cursor) => {
+            util::set_custom_cursor(&window, None);
             if let Some(gdk_window) = window.window() {
               let display = window.display();
               match cursor {
-                Some(cr) => {
-                  gdk_window.set_cursor(Cursor::from_name(&display, cr.to_str()).as_ref())
-                }
+                Some(cr) => gdk_window.set_cursor(cr.to_gdk_cursor(&display).as_ref()),
                 None => gdk_window
                   .set_cursor(Cursor::for_display(&display, CursorType::BlankCursor).as_ref()),
               }
             };
           }
+          WindowRequest::CustomCursor(icon, hotspot) => {
+            util::set_custom_cursor(&window, Some((icon, hotspot)))
+          }
           WindowRequest::CursorPosition((x, y)) => {
             if let Some(cursor) = window
               .display()
@@ -389,12 +1035,103 @@ impl<T: 'static> EventLoop<T> {
               window.input_shape_combine_region(None)
             };
           }
+          WindowRequest::CursorHittestRegion(region) => {
+            util::set_cursor_hittest_region(&window, region)
+          }
+          WindowRequest::CursorGrab(mode) => util::set_cursor_grab(&window, mode),
+          WindowRequest::Shape(shape) => util::set_shape(&window, shape),
+          WindowRequest::EventHook(hook) => match hook {
+            Some(hook) => unsafe { window.set_data(EVENT_HOOK_KEY, hook.into_inner()) },
+            None => {
+              unsafe { window.steal_data::<EventHook>(EVENT_HOOK_KEY) };
+            }
+          },
+          WindowRequest::Opacity(opacity) => window.set_opacity(opacity as f64),
+          WindowRequest::Menu(menu_bar) => {
+            // Only sent for windows with the default vbox, which is their child.
+            if let Some(vbox) = window
+              .child()
+              .and_then(|child| child.downcast::<gtk::Box>().ok())
+            {
+              menu::set_for_vbox(&vbox, menu_bar.map(ThreadGuard::into_inner));
+            }
+          }
+          WindowRequest::BackgroundColor(color) => util::set_background_color(&window, color),
+          WindowRequest::HitTestCallback(callback) => {
+            util::set_hit_test_callback(&window, callback)
+          }
           WindowRequest::ProgressBarState(_) => unreachable!(),
+          WindowRequest::RedrawSynced(pending) => {
+            let draw_tx = draw_tx.clone();
+            let redraw = move || {
+              pending.store(false, Ordering::Release);
+              if let Err(e) = draw_tx.send(id) {
+                log::warn!("Failed to send redraw event to event channel: {}", e);
+              }
+            };
+            // The frame clock ticks with the refreshes of the monitor of the window, and stops
+            // while the window isn't shown.
+            let shown = window.is_visible()
+              && window.window().map_or(false, |window| {
+                !window.state().contains(WindowState::ICONIFIED)
+              });
+            if shown {
+              window.add_tick_callback(move |_, _| {
+                redraw();
+                glib::ControlFlow::Break
+              });
+            } else {
+              redraw();
+            }
+          }
+          WindowRequest::FrameCallbacks(enabled) => {
+            if !enabled {
+              if let Some(callback) =
+                unsafe { window.steal_data::<gtk::TickCallbackId>(FRAME_CALLBACKS_KEY) }
+              {
+                callback.remove();
+              }
+            } else if unsafe { window.data::<gtk::TickCallbackId>(FRAME_CALLBACKS_KEY) }.is_none() {
+              // The frame clock ticks with the refreshes of the monitor of the window, and stops
+              // while the window isn't shown.
+              let draw_tx = draw_tx.clone();
+              let callback = window.add_tick_callback(move |window, _| {
+                let minimized = window.window().map_or(false, |window| {
+                  window.state().contains(WindowState::ICONIFIED)
+                });
+                if !minimized {
+                  if let Err(e) = draw_tx.send(id) {
+                    log::warn!("Failed to send redraw event to event channel: {}", e);
+                  }
+                }
+                glib::ControlFlow::Continue
+              });
+              unsafe { window.set_data(FRAME_CALLBACKS_KEY, callback) };
+            }
+          }
+          WindowRequest::AccessibilityAction(request) => {
+            if let Err(e) = event_tx.send(Event::AccessibilityAction {
+              window_id: RootWindowId(id),
+              request,
+            }) {
+              log::warn!(
+                "Failed to send accessibility action event to event channel: {}",
+                e
+              );
+            }
+          }
           WindowRequest::WireUpEvents {
             transparent,
             fullscreen,
             cursor_moved,
           } => {
+            // The video mode of an exclusive fullscreen window is restored once it's gone.
+            let saved_modes = saved_modes.clone();
+            window.connect_destroy(move |window| {
+              if let Some(mode) = saved_modes.borrow_mut().remove(&id) {
+                monitor::restore_video_mode(&window.display(), mode);
+              }
+            });
             window.add_events(
               EventMask::POINTER_MOTION_MASK
                 | EventMask::BUTTON1_MOTION_MASK
@@ -402,26 +1139,209 @@ impl<T: 'static> EventLoop<T> {
                 | EventMask::TOUCH_MASK
                 | EventMask::STRUCTURE_MASK
                 | EventMask::FOCUS_CHANGE_MASK
-                | EventMask::SCROLL_MASK,
+                | EventMask::SCROLL_MASK
+                | EventMask::TOUCHPAD_GESTURE_MASK
+                | EventMask::PROXIMITY_IN_MASK
+                | EventMask::PROXIMITY_OUT_MASK
+                | EventMask::VISIBILITY_NOTIFY_MASK,
             );
 
             let fullscreen = Rc::new(AtomicBool::new(fullscreen));
             let fullscreen_ = fullscreen.clone();
-            window.connect_window_state_event(move |_window, event| {
+            let reported_fullscreen = Cell::new(false);
+            let tx_clone = event_tx.clone();
+            window.connect_window_state_event(move |window, event| {
+              // The window manager drops the state of a hidden window, see
+              // `util::hide_keeping_state`.
+              if !window.is_mapped() {
+                return glib::Propagation::Proceed;
+              }
               let state = event.changed_mask();
+              let is_fullscreen = event.new_window_state().contains(WindowState::FULLSCREEN);
               if state.contains(WindowState::FULLSCREEN) {
-                fullscreen_.store(
-                  event.new_window_state().contains(WindowState::FULLSCREEN),
-                  Ordering::Relaxed,
-                );
+                fullscreen_.store(is_fullscreen, Ordering::Relaxed);
+              }
+              // Showing a hidden window applies its state again, which isn't a change.
+              if state.contains(WindowState::FULLSCREEN)
+                && reported_fullscreen.replace(is_fullscreen) != is_fullscreen
+              {
+                let fullscreen = is_fullscreen.then(|| {
+                  let monitor = window
+                    .window()
+                    .and_then(|w| window.display().monitor_at_window(&w))
+                    .map(|monitor| RootMonitorHandle {
+                      inner: monitor::MonitorHandle { monitor },
+                    });
+                  Fullscreen::Borderless(monitor)
+                });
+                if let Err(e) = tx_clone.send(Event::WindowEvent {
+                  window_id: RootWindowId(id),
+                  event: WindowEvent::FullscreenChanged(fullscreen),
+                }) {
+                  log::warn!(
+                    "Failed to send fullscreen changed event to event channel: {}",
+                    e
+                  );
+                }
+              }
+              glib::Propagation::Proceed
+            });
+
+            // The window is occluded while hidden, minimized or, on X11, fully covered by other
+            // windows. Compositing window managers usually never report the latter.
+            let iconified = Rc::new(Cell::new(false));
+            let obscured = Rc::new(Cell::new(false));
+            let send_occluded: Rc<dyn Fn(&gtk::Window)> = {
+              let iconified = iconified.clone();
+              let obscured = obscured.clone();
+              let reported_occluded = Cell::new(false);
+              let tx_clone = event_tx.clone();
+              Rc::new(move |window: &gtk::Window| {
+                let occluded = !window.is_visible() || iconified.get() || obscured.get();
+                if reported_occluded.replace(occluded) != occluded {
+                  if let Err(e) = tx_clone.send(Event::WindowEvent {
+                    window_id: RootWindowId(id),
+                    event: WindowEvent::Occluded(occluded),
+                  }) {
+                    log::warn!(
+                      "Failed to send window occluded event to event channel: {}",
+                      e
+                    );
+                  }
+                }
+              })
+            };
+            let send_occluded_ = send_occluded.clone();
+            window.connect_window_state_event(move |window, event| {
+              if event.changed_mask().contains(WindowState::ICONIFIED) {
+                iconified.set(event.new_window_state().contains(WindowState::ICONIFIED));
+                send_occluded_(window);
               }
               glib::Propagation::Proceed
             });
+            let send_occluded_ = send_occluded.clone();
+            // The signal is deprecated and has no binding, but X11 still emits it with
+            // `VISIBILITY_NOTIFY_MASK`.
+            window.connect_local("visibility-notify-event", false, move |values| {
+              let window = values[0].get::<gtk::Window>();
+              let event = values[1]
+                .get::<gdk::Event>()
+                .ok()
+                .and_then(|event| event.downcast::<gdk::EventVisibility>().ok());
+              if let (Ok(window), Some(event)) = (window, event) {
+                obscured.set(event.state() == gdk::VisibilityState::FullyObscured);
+                send_occluded_(&window);
+              }
+              Some(false.to_value())
+            });
+            window.connect_visible_notify(move |window| send_occluded(window));
+
+            // Non-client parts of a custom title bar, see `Window::set_hit_test_callback`.
+            let pressed_hit: Rc<Cell<Option<HitTestResult>>> = Rc::new(Cell::new(None));
+            let pressed_hit_ = pressed_hit.clone();
+            let tx_clone = event_tx.clone();
+            window.connect_button_press_event(move |gtk_window, event| {
+              let hit = match util::hit_test_at(gtk_window, event.root()) {
+                Some(HitTestResult::Client) | None => return glib::Propagation::Proceed,
+                Some(hit) => hit,
+              };
+              let window = match gtk_window.window() {
+                Some(window) => window,
+                None => return glib::Propagation::Stop,
+              };
+              let (cx, cy) = event.root();
+              match hit {
+                HitTestResult::Caption if event.button() == 1 => {
+                  if event.event_type() == gdk::EventType::DoubleButtonPress {
+                    if gtk_window.is_maximized() {
+                      gtk_window.unmaximize();
+                    } else if gtk_window.is_resizable() {
+                      gtk_window.maximize();
+                    }
+                  } else if event.event_type() == gdk::EventType::ButtonPress {
+                    begin_drag_resize(gtk_window, id, false, &tx_clone);
+                    window.begin_move_drag(1, cx as i32, cy as i32, event.time());
+                  }
+                }
+                HitTestResult::Caption if event.button() == 3 => {
+                  let mut menu_event: gdk::Event = (**event).clone();
+                  window.show_window_menu(&mut menu_event);
+                }
+                HitTestResult::ResizeEdge(direction)
+                  if event.button() == 1 && gtk_window.is_resizable() =>
+                {
+                  begin_drag_resize(gtk_window, id, true, &tx_clone);
+                  window.begin_resize_drag(
+                    direction.to_gtk_edge(),
+                    1,
+                    cx as i32,
+                    cy as i32,
+                    event.time(),
+                  );
+                }
+                HitTestResult::Minimize | HitTestResult::Maximize | HitTestResult::Close
+                  if event.button() == 1 =>
+                {
+                  pressed_hit_.set(Some(hit));
+                }
+                _ => (),
+              }
+              glib::Propagation::Stop
+            });
+            window.connect_button_release_event(move |gtk_window, event| {
+              let pressed = match pressed_hit.take() {
+                Some(pressed) => pressed,
+                None => {
+                  return match util::hit_test_at(gtk_window, event.root()) {
+                    Some(HitTestResult::Client) | None => glib::Propagation::Proceed,
+                    Some(_) => glib::Propagation::Stop,
+                  }
+                }
+              };
+              // Like a regular button, releasing it elsewhere cancels the click.
+              if event.button() == 1 && util::hit_test_at(gtk_window, event.root()) == Some(pressed)
+              {
+                match pressed {
+                  HitTestResult::Minimize => gtk_window.iconify(),
+                  HitTestResult::Maximize if gtk_window.is_maximized() => gtk_window.unmaximize(),
+                  HitTestResult::Maximize if gtk_window.is_resizable() => gtk_window.maximize(),
+                  HitTestResult::Close => gtk_window.close(),
+                  _ => (),
+                }
+              }
+              glib::Propagation::Stop
+            });
+            let on_edge = Cell::new(false);
+            window.connect_motion_notify_event(move |gtk_window, event| {
+              let hit = util::hit_test_at(gtk_window, event.root());
+              if let Some(window) = gtk_window.window() {
+                match hit {
+                  Some(HitTestResult::ResizeEdge(direction)) if gtk_window.is_resizable() => {
+                    on_edge.set(true);
+                    window.set_cursor(
+                      Cursor::from_name(&window.display(), direction.to_cursor_str()).as_ref(),
+                    );
+                  }
+                  _ if on_edge.replace(false) => {
+                    window.set_cursor(Cursor::from_name(&window.display(), "default").as_ref());
+                  }
+                  _ => (),
+                }
+              }
+              match hit {
+                Some(HitTestResult::Client) | None => glib::Propagation::Proceed,
+                Some(_) => glib::Propagation::Stop,
+              }
+            });
 
             // Allow resizing unmaximized non-fullscreen undecorated window
             let fullscreen_ = fullscreen.clone();
             window.connect_motion_notify_event(move |window, event| {
-              if !window.is_decorated() && window.is_resizable() && !window.is_maximized() {
+              if !window.is_decorated()
+                && window.is_resizable()
+                && !window.is_maximized()
+                && !util::has_hit_test_callback(window)
+              {
                 if let Some(window) = window.window() {
                   let (cx, cy) = event.root();
                   let (left, top) = window.position();
@@ -445,13 +1365,15 @@ impl<T: 'static> EventLoop<T> {
               }
               glib::Propagation::Proceed
             });
-            window.connect_button_press_event(move |window, event| {
-              if !window.is_decorated()
-                && window.is_resizable()
-                && !window.is_maximized()
+            let tx_clone = event_tx.clone();
+            window.connect_button_press_event(move |gtk_window, event| {
+              if !gtk_window.is_decorated()
+                && gtk_window.is_resizable()
+                && !gtk_window.is_maximized()
+                && !util::has_hit_test_callback(gtk_window)
                 && event.button() == 1
               {
-                if let Some(window) = window.window() {
+                if let Some(window) = gtk_window.window() {
                   let (cx, cy) = event.root();
                   let (left, top) = window.position();
                   let (w, h) = (window.width(), window.height());
@@ -472,6 +1394,7 @@ impl<T: 'static> EventLoop<T> {
                   match edge {
                     WindowEdge::__Unknown(_) => (),
                     _ => {
+                      begin_drag_resize(gtk_window, id, true, &tx_clone);
                       // FIXME: calling `window.begin_resize_drag` uses the default cursor, it should show a resizing cursor instead
                       window.begin_resize_drag(edge, 1, cx as i32, cy as i32, event.time())
                     }
@@ -482,7 +1405,11 @@ impl<T: 'static> EventLoop<T> {
               glib::Propagation::Proceed
             });
             window.connect_touch_event(move |window, event| {
-              if !window.is_decorated() && window.is_resizable() && !window.is_maximized() {
+              if !window.is_decorated()
+                && window.is_resizable()
+                && !window.is_maximized()
+                && !util::has_hit_test_callback(window)
+              {
                 if let Some(window) = window.window() {
                   if let Some((cx, cy)) = event.root_coords() {
                     if let Some(device) = event.device() {
@@ -517,27 +1444,129 @@ impl<T: 'static> EventLoop<T> {
                     }
                   }
                 }
-              }
+              }
+
+              glib::Propagation::Proceed
+            });
+
+            // The window manager holds the pointer during the drag, cursor events coming back mean
+            // that it ended.
+            let tx_clone = event_tx.clone();
+            window.connect_event(move |window, event| {
+              if matches!(
+                event.event_type(),
+                gdk::EventType::MotionNotify
+                  | gdk::EventType::ButtonRelease
+                  | gdk::EventType::EnterNotify
+              ) {
+                end_drag_resize(window, id, &tx_clone);
+              }
+              glib::Propagation::Proceed
+            });
+
+            let tx_clone = event_tx.clone();
+            window.connect_delete_event(move |_, _| {
+              if let Err(e) = tx_clone.send(Event::WindowEvent {
+                window_id: RootWindowId(id),
+                event: WindowEvent::CloseRequested,
+              }) {
+                log::warn!("Failed to send window close event to event channel: {}", e);
+              }
+              glib::Propagation::Stop
+            });
+
+            // A maximized state change is reported once its final size is known, after the
+            // `Resized` of the next configure event.
+            let pending_maximized = Rc::new(Cell::new(None));
+            let send_maximized = {
+              let tx_clone = event_tx.clone();
+              let pending_maximized = pending_maximized.clone();
+              Rc::new(move || {
+                if let Some(maximized) = pending_maximized.take() {
+                  if let Err(e) = tx_clone.send(Event::WindowEvent {
+                    window_id: RootWindowId(id),
+                    event: WindowEvent::Maximized(maximized),
+                  }) {
+                    log::warn!(
+                      "Failed to send window maximized event to event channel: {}",
+                      e
+                    );
+                  }
+                }
+              })
+            };
 
+            // The size is reported once the window is mapped with its final geometry, the
+            // configure events of a withdrawn window may have a placeholder size.
+            let last_size = Rc::new(Cell::new(None));
+            let tx_clone = event_tx.clone();
+            let last_size_ = last_size.clone();
+            window.connect_map_event(move |window, _| {
+              let (w, h) = window.size();
+              let size = LogicalSize::new(w, h).to_physical(window.scale_factor() as f64);
+              if last_size_.replace(Some(size)) != Some(size) {
+                if let Err(e) = tx_clone.send(Event::WindowEvent {
+                  window_id: RootWindowId(id),
+                  event: WindowEvent::Resized(size),
+                }) {
+                  log::warn!(
+                    "Failed to send window resized event to event channel: {}",
+                    e
+                  );
+                }
+              }
               glib::Propagation::Proceed
             });
 
             let tx_clone = event_tx.clone();
-            window.connect_delete_event(move |_, _| {
-              if let Err(e) = tx_clone.send(Event::WindowEvent {
-                window_id: RootWindowId(id),
-                event: WindowEvent::CloseRequested,
-              }) {
-                log::warn!("Failed to send window close event to event channel: {}", e);
+            let last_visible = Cell::new(is_shown(&window));
+            let report_visibility = Rc::new(move |window: &gtk::Window| {
+              let visible = is_shown(window);
+              if last_visible.replace(visible) != visible {
+                if let Err(e) = tx_clone.send(Event::WindowEvent {
+                  window_id: RootWindowId(id),
+                  event: WindowEvent::VisibilityChanged(visible),
+                }) {
+                  log::warn!(
+                    "Failed to send window visibility changed event to event channel: {}",
+                    e
+                  );
+                }
               }
-              glib::Propagation::Stop
+            });
+            let report_visibility_ = report_visibility.clone();
+            window.connect_visible_notify(move |window| report_visibility_(window));
+            // The window manager may unmap a shown window by itself, like when it minimizes it or
+            // moves it to another workspace.
+            let report_visibility_ = report_visibility.clone();
+            window.connect_map_event(move |window, _| {
+              unsafe { window.set_data(UNMAPPED_KEY, false) };
+              report_visibility_(window);
+              glib::Propagation::Proceed
+            });
+            let report_visibility_ = report_visibility.clone();
+            window.connect_unmap_event(move |window, _| {
+              // Hiding the window clears its visible flag before unmapping it.
+              unsafe { window.set_data(UNMAPPED_KEY, window.is_visible()) };
+              report_visibility_(window);
+              glib::Propagation::Proceed
+            });
+            window.connect_window_state_event(move |window, event| {
+              if event.changed_mask().contains(WindowState::ICONIFIED) {
+                report_visibility(window);
+              }
+              glib::Propagation::Proceed
             });
 
             let tx_clone = event_tx.clone();
+            let send_maximized_ = send_maximized.clone();
             window.connect_configure_event(move |window, event| {
               let scale_factor = window.scale_factor();
 
-              let (x, y) = event.position();
+              // `event.position()` is the origin of the GDK window, which excludes the window
+              // manager frame but includes client-side shadows. Report the same point as
+              // `outer_position` instead.
+              let (x, y) = window.position();
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
                 event: WindowEvent::Moved(
@@ -548,19 +1577,250 @@ impl<T: 'static> EventLoop<T> {
               }
 
               let (w, h) = event.size();
-              if let Err(e) = tx_clone.send(Event::WindowEvent {
-                window_id: RootWindowId(id),
-                event: WindowEvent::Resized(
-                  LogicalSize::new(w, h).to_physical(scale_factor as f64),
-                ),
-              }) {
-                log::warn!(
-                  "Failed to send window resized event to event channel: {}",
-                  e
-                );
+              let size = LogicalSize::new(w, h).to_physical(scale_factor as f64);
+              let withdrawn = window
+                .window()
+                .map_or(true, |w| w.state().contains(WindowState::WITHDRAWN));
+              if !withdrawn && last_size.replace(Some(size)) != Some(size) {
+                if let Err(e) = tx_clone.send(Event::WindowEvent {
+                  window_id: RootWindowId(id),
+                  event: WindowEvent::Resized(size),
+                }) {
+                  log::warn!(
+                    "Failed to send window resized event to event channel: {}",
+                    e
+                  );
+                }
+              }
+              send_maximized_();
+              false
+            });
+
+            // Track the monitor of the window, which changes when the window is moved and when
+            // monitors are connected, disconnected or rearranged.
+            let current_monitor = Rc::new(RefCell::new(
+              window
+                .window()
+                .and_then(|w| window.display().monitor_at_window(&w)),
+            ));
+            let tx_clone = event_tx.clone();
+            let update_monitor = Rc::new(move |window: &gtk::Window| {
+              let monitor = match window
+                .window()
+                .and_then(|w| window.display().monitor_at_window(&w))
+              {
+                Some(monitor) => monitor,
+                None => return,
+              };
+              let previous = current_monitor.borrow_mut().replace(monitor.clone());
+              // The first known monitor isn't a transition.
+              if previous.map_or(false, |previous| previous != monitor) {
+                if let Err(e) = tx_clone.send(Event::WindowEvent {
+                  window_id: RootWindowId(id),
+                  event: WindowEvent::MonitorChanged(RootMonitorHandle {
+                    inner: monitor::MonitorHandle { monitor },
+                  }),
+                }) {
+                  log::warn!(
+                    "Failed to send window monitor changed event to event channel: {}",
+                    e
+                  );
+                }
               }
+            });
+            let update_monitor_ = update_monitor.clone();
+            window.connect_configure_event(move |window, _| {
+              update_monitor_(window);
               false
             });
+            let screen = WidgetExt::screen(&window);
+            if let Some(screen) = screen {
+              let window_ = window.downgrade();
+              let handler = screen.connect_monitors_changed(move |_| {
+                if let Some(window) = window_.upgrade() {
+                  update_monitor(&window);
+                }
+              });
+              let handler = RefCell::new(Some(handler));
+              window.connect_destroy(move |_| {
+                if let Some(handler) = handler.take() {
+                  screen.disconnect(handler);
+                }
+              });
+            }
+
+            if let Some(settings) = gtk::Settings::default() {
+              let display = window.display();
+              let text_scale_factor = Cell::new(util::text_scale_factor(&display));
+              let tx_clone = event_tx.clone();
+              let handler = settings.connect_notify_local(Some("gtk-xft-dpi"), move |_, _| {
+                let new_factor = util::text_scale_factor(&display);
+                if text_scale_factor.replace(new_factor) != new_factor {
+                  if let Err(e) = tx_clone.send(Event::WindowEvent {
+                    window_id: RootWindowId(id),
+                    event: WindowEvent::TextScaleFactorChanged(new_factor),
+                  }) {
+                    log::warn!(
+                      "Failed to send text scale factor changed event to event channel: {}",
+                      e
+                    );
+                  }
+                }
+              });
+              let handler = RefCell::new(Some(handler));
+              window.connect_destroy(move |_| {
+                if let Some(handler) = handler.take() {
+                  settings.disconnect(handler);
+                }
+              });
+            }
+
+            // Touchpad gestures. `begin` and `end` frame a gesture, `cancel` is followed by
+            // `end`, so `started` makes sure a gesture finishes with a single phase.
+            let zoom = gtk::GestureZoom::new(&window);
+            zoom.set_propagation_phase(gtk::PropagationPhase::Capture);
+            let zoom_scale = Rc::new(Cell::new(1.0));
+            let zoom_started = Rc::new(Cell::new(false));
+            // The device of a gesture is only known while it's active.
+            let zoom_device = Rc::new(Cell::new(DEVICE_ID));
+            let send_magnify = {
+              let tx_clone = event_tx.clone();
+              let window = window.downgrade();
+              let device_id = zoom_device.clone();
+              Rc::new(move |delta: f64, phase: TouchPhase| {
+                if !window.upgrade().map_or(false, |window| window.is_active()) {
+                  return;
+                }
+                if let Err(e) = tx_clone.send(Event::WindowEvent {
+                  window_id: RootWindowId(id),
+                  event: WindowEvent::TouchpadMagnify {
+                    device_id: device_id.get(),
+                    delta,
+                    phase,
+                  },
+                }) {
+                  log::warn!(
+                    "Failed to send touchpad magnify event to event channel: {}",
+                    e
+                  );
+                }
+              })
+            };
+            {
+              let (scale, started, send) = (
+                zoom_scale.clone(),
+                zoom_started.clone(),
+                send_magnify.clone(),
+              );
+              let device = zoom_device.clone();
+              zoom.connect_begin(move |zoom, _| {
+                device.set(device::seat_device_id(
+                  zoom.device().and_then(|device| device.seat()),
+                ));
+                scale.set(1.0);
+                started.set(true);
+                send(0.0, TouchPhase::Started);
+              });
+            }
+            {
+              let (scale, started, send) = (
+                zoom_scale.clone(),
+                zoom_started.clone(),
+                send_magnify.clone(),
+              );
+              zoom.connect_scale_changed(move |_, new_scale| {
+                if started.get() {
+                  send(new_scale - scale.replace(new_scale), TouchPhase::Moved);
+                }
+              });
+            }
+            {
+              let (started, send) = (zoom_started.clone(), send_magnify.clone());
+              zoom.connect_cancel(move |_, _| {
+                if started.replace(false) {
+                  send(0.0, TouchPhase::Cancelled);
+                }
+              });
+            }
+            zoom.connect_end(move |_, _| {
+              if zoom_started.replace(false) {
+                send_magnify(0.0, TouchPhase::Ended);
+              }
+            });
+            unsafe { window.set_data(GESTURE_ZOOM_KEY, zoom) };
+
+            let rotate = gtk::GestureRotate::new(&window);
+            rotate.set_propagation_phase(gtk::PropagationPhase::Capture);
+            let rotate_angle = Rc::new(Cell::new(0.0));
+            let rotate_started = Rc::new(Cell::new(false));
+            let rotate_device = Rc::new(Cell::new(DEVICE_ID));
+            let send_rotate = {
+              let tx_clone = event_tx.clone();
+              let window = window.downgrade();
+              let device_id = rotate_device.clone();
+              Rc::new(move |delta: f32, phase: TouchPhase| {
+                if !window.upgrade().map_or(false, |window| window.is_active()) {
+                  return;
+                }
+                if let Err(e) = tx_clone.send(Event::WindowEvent {
+                  window_id: RootWindowId(id),
+                  event: WindowEvent::TouchpadRotate {
+                    device_id: device_id.get(),
+                    delta,
+                    phase,
+                  },
+                }) {
+                  log::warn!(
+                    "Failed to send touchpad rotate event to event channel: {}",
+                    e
+                  );
+                }
+              })
+            };
+            {
+              let (angle, started, send) = (
+                rotate_angle.clone(),
+                rotate_started.clone(),
+                send_rotate.clone(),
+              );
+              let device = rotate_device.clone();
+              rotate.connect_begin(move |rotate, _| {
+                device.set(device::seat_device_id(
+                  rotate.device().and_then(|device| device.seat()),
+                ));
+                angle.set(0.0);
+                started.set(true);
+                send(0.0, TouchPhase::Started);
+              });
+            }
+            {
+              let (angle, started, send) = (
+                rotate_angle.clone(),
+                rotate_started.clone(),
+                send_rotate.clone(),
+              );
+              rotate.connect_angle_changed(move |_, _, angle_delta| {
+                if started.get() {
+                  // GTK angles grow clockwise, tao reports counterclockwise rotation as positive.
+                  let delta = angle.replace(angle_delta) - angle_delta;
+                  send(delta.to_degrees() as f32, TouchPhase::Moved);
+                }
+              });
+            }
+            {
+              let (started, send) = (rotate_started.clone(), send_rotate.clone());
+              rotate.connect_cancel(move |_, _| {
+                if started.replace(false) {
+                  send(0.0, TouchPhase::Cancelled);
+                }
+              });
+            }
+            rotate.connect_end(move |_, _| {
+              if rotate_started.replace(false) {
+                send_rotate(0.0, TouchPhase::Ended);
+              }
+            });
+            unsafe { window.set_data(GESTURE_ROTATE_KEY, rotate) };
 
             let tx_clone = event_tx.clone();
             window.connect_focus_in_event(move |_, _| {
@@ -604,11 +1864,120 @@ impl<T: 'static> EventLoop<T> {
             });
 
             let tx_clone = event_tx.clone();
-            window.connect_enter_notify_event(move |_, _| {
+            // GDK identifies contacts by sequence pointers, which may be reused.
+            let touch_ids = RefCell::new(TouchIds::default());
+            window.connect_touch_event(move |window, event| {
+              let phase = match event.event_type() {
+                gdk::EventType::TouchBegin => TouchPhase::Started,
+                gdk::EventType::TouchUpdate => TouchPhase::Moved,
+                gdk::EventType::TouchEnd => TouchPhase::Ended,
+                gdk::EventType::TouchCancel => TouchPhase::Cancelled,
+                _ => return glib::Propagation::Proceed,
+              };
+              if let (Some(sequence), Some((x, y)), Some(gdk_window)) =
+                (event.event_sequence(), event.root_coords(), window.window())
+              {
+                let (_, left, top) = gdk_window.origin();
+                let scale_factor = window.scale_factor() as f64;
+                let touch_id = touch_ids.borrow_mut().id(sequence.as_ptr() as u64, phase);
+                if let Err(e) = tx_clone.send(Event::WindowEvent {
+                  window_id: RootWindowId(id),
+                  event: WindowEvent::Touch(Touch {
+                    device_id: device::seat_device_id(event.seat()),
+                    phase,
+                    location: LogicalPosition::new(x - left as f64, y - top as f64)
+                      .to_physical(scale_factor),
+                    force: event.axis(gdk::AxisUse::Pressure).map(Force::Normalized),
+                    rotation: None,
+                    id: touch_id,
+                    timestamp: util::event_time(event.time()),
+                  }),
+                }) {
+                  log::warn!("Failed to send touch event to event channel: {}", e);
+                }
+              }
+              glib::Propagation::Proceed
+            });
+
+            let tx_clone = event_tx.clone();
+            window.connect_event(move |window, event| {
+              let mut in_contact = event
+                .state()
+                .map_or(false, |state| state.contains(ModifierType::BUTTON1_MASK));
+              let mut barrel_button = event.state().map_or(false, |state| {
+                state.intersects(ModifierType::BUTTON2_MASK | ModifierType::BUTTON3_MASK)
+              });
+              let phase = match (event.event_type(), event.button()) {
+                (gdk::EventType::ProximityIn, _) => PenPhase::Entered,
+                (gdk::EventType::ProximityOut, _) => PenPhase::Left,
+                (gdk::EventType::MotionNotify, _) => PenPhase::Moved,
+                (gdk::EventType::ButtonPress, Some(1)) => {
+                  in_contact = true;
+                  PenPhase::Pressed
+                }
+                (gdk::EventType::ButtonRelease, Some(1)) => {
+                  in_contact = false;
+                  PenPhase::Released
+                }
+                (gdk::EventType::ButtonPress, _) => {
+                  barrel_button = true;
+                  PenPhase::Moved
+                }
+                (gdk::EventType::ButtonRelease, _) => {
+                  barrel_button = false;
+                  PenPhase::Moved
+                }
+                _ => return glib::Propagation::Proceed,
+              };
+              let source_device = match event.source_device() {
+                Some(device) => device,
+                None => return glib::Propagation::Proceed,
+              };
+              let eraser = match source_device.source() {
+                gdk::InputSource::Pen => false,
+                gdk::InputSource::Eraser => true,
+                _ => return glib::Propagation::Proceed,
+              };
+              if let Some(gdk_window) = window.window() {
+                // Proximity events have no coordinates.
+                let (x, y) = match event.root_coords() {
+                  Some((x, y)) => {
+                    let (_, left, top) = gdk_window.origin();
+                    (x - left as f64, y - top as f64)
+                  }
+                  None => {
+                    let (_, x, y, _) = gdk_window.device_position_double(&source_device);
+                    (x, y)
+                  }
+                };
+                let (pressure, tilt, twist) = pen_axes(|axis| event.axis(axis));
+                if let Err(e) = tx_clone.send(Event::WindowEvent {
+                  window_id: RootWindowId(id),
+                  event: WindowEvent::Pen(Pen {
+                    device_id: device::seat_device_id(event.seat()),
+                    phase,
+                    location: LogicalPosition::new(x, y).to_physical(window.scale_factor() as f64),
+                    in_contact,
+                    pressure,
+                    tilt,
+                    twist,
+                    barrel_button,
+                    eraser,
+                    timestamp: util::event_time(event.time()),
+                  }),
+                }) {
+                  log::warn!("Failed to send pen event to event channel: {}", e);
+                }
+              }
+              glib::Propagation::Proceed
+            });
+
+            let tx_clone = event_tx.clone();
+            window.connect_enter_notify_event(move |_, event| {
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
                 event: WindowEvent::CursorEntered {
-                  device_id: DEVICE_ID,
+                  device_id: device::seat_device_id(event.seat()),
                 },
               }) {
                 log::warn!(
@@ -629,7 +1998,8 @@ impl<T: 'static> EventLoop<T> {
                     window_id: RootWindowId(id),
                     event: WindowEvent::CursorMoved {
                       position: LogicalPosition::new(x, y).to_physical(scale_factor as f64),
-                      device_id: DEVICE_ID,
+                      device_id: device::seat_device_id(motion.seat()),
+                      timestamp: util::event_time(motion.time()),
                       // this field is depracted so it is fine to pass empty state
                       modifiers: ModifiersState::empty(),
                     },
@@ -642,11 +2012,11 @@ impl<T: 'static> EventLoop<T> {
             });
 
             let tx_clone = event_tx.clone();
-            window.connect_leave_notify_event(move |_, _| {
+            window.connect_leave_notify_event(move |_, event| {
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
                 event: WindowEvent::CursorLeft {
-                  device_id: DEVICE_ID,
+                  device_id: device::seat_device_id(event.seat()),
                 },
               }) {
                 log::warn!("Failed to send cursor left event to event channel: {}", e);
@@ -654,9 +2024,20 @@ impl<T: 'static> EventLoop<T> {
               glib::Propagation::Proceed
             });
 
+            let click_state = Rc::new(Cell::new(util::ClickState::default()));
+
             let tx_clone = event_tx.clone();
+            let click_state_clone = click_state.clone();
             window.connect_button_press_event(move |_, event| {
+              // Double and triple clicks are preceded by a plain press which was already reported.
+              if event.event_type() != gdk::EventType::ButtonPress {
+                return glib::Propagation::Proceed;
+              }
+
               let button = event.button();
+              let mut state = click_state_clone.get();
+              let click_count = state.press(event);
+              click_state_clone.set(state);
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
                 event: WindowEvent::MouseInput {
@@ -667,7 +2048,9 @@ impl<T: 'static> EventLoop<T> {
                     _ => MouseButton::Other(button as u16),
                   },
                   state: ElementState::Pressed,
-                  device_id: DEVICE_ID,
+                  click_count,
+                  timestamp: util::event_time(event.time()),
+                  device_id: device::seat_device_id(event.seat()),
                   // this field is depracted so it is fine to pass empty state
                   modifiers: ModifiersState::empty(),
                 },
@@ -683,6 +2066,7 @@ impl<T: 'static> EventLoop<T> {
             let tx_clone = event_tx.clone();
             window.connect_button_release_event(move |_, event| {
               let button = event.button();
+              let click_count = click_state.get().release(button);
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
                 event: WindowEvent::MouseInput {
@@ -693,7 +2077,9 @@ impl<T: 'static> EventLoop<T> {
                     _ => MouseButton::Other(button as u16),
                   },
                   state: ElementState::Released,
-                  device_id: DEVICE_ID,
+                  click_count,
+                  timestamp: util::event_time(event.time()),
+                  device_id: device::seat_device_id(event.seat()),
                   // this field is depracted so it is fine to pass empty state
                   modifiers: ModifiersState::empty(),
                 },
@@ -712,12 +2098,13 @@ impl<T: 'static> EventLoop<T> {
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
                 event: WindowEvent::MouseWheel {
-                  device_id: DEVICE_ID,
+                  device_id: device::seat_device_id(event.seat()),
                   delta: MouseScrollDelta::LineDelta(-x as f32, -y as f32),
                   phase: match event.direction() {
                     ScrollDirection::Smooth => TouchPhase::Moved,
                     _ => TouchPhase::Ended,
                   },
+                  timestamp: util::event_time(event.time()),
                   modifiers: ModifiersState::empty(),
                 },
               }) {
@@ -726,16 +2113,22 @@ impl<T: 'static> EventLoop<T> {
               glib::Propagation::Proceed
             });
 
+            // GTK doesn't tell us whether a key press is an auto-repeat, so keep track of the
+            // physical keys that are currently held down. GDK enables XKB detectable autorepeat
+            // on X11, so a held key produces a stream of presses followed by a single release.
+            let pressed_keys = Rc::new(RefCell::new(HashSet::new()));
+            let pressed_keys_ = pressed_keys.clone();
+            window.connect_focus_out_event(move |_, _| {
+              pressed_keys_.borrow_mut().clear();
+              glib::Propagation::Proceed
+            });
+
             let tx_clone = event_tx.clone();
+            // The modifiers last reported through `ModifiersChanged`.
+            let modifiers = Cell::new(ModifiersState::empty());
             let keyboard_handler = Rc::new(move |event_key: EventKey, element_state| {
-              // if we have a modifier lets send it
-              let mut mods = keyboard::get_modifiers(event_key.clone());
-              if !mods.is_empty() {
-                // if we release the modifier tell the world
-                if ElementState::Released == element_state {
-                  mods = ModifiersState::empty();
-                }
-
+              let send_modifiers = |mods| {
+                modifiers.set(mods);
                 if let Err(e) = tx_clone.send(Event::WindowEvent {
                   window_id: RootWindowId(id),
                   event: WindowEvent::ModifiersChanged(mods),
@@ -744,40 +2137,81 @@ impl<T: 'static> EventLoop<T> {
                     "Failed to send modifiers changed event to event channel: {}",
                     e
                   );
-                } else {
-                  // stop here we don't want to send the key event
-                  // as we emit the `ModifiersChanged`
-                  return glib::ControlFlow::Continue;
                 }
+              };
+
+              // The state of a key event is the one before it, e.g. modifiers pressed while
+              // another window had the focus.
+              let held = keyboard::get_held_modifiers(&event_key);
+              if held != modifiers.get() {
+                send_modifiers(held);
               }
 
-              // todo: implement repeat?
-              let event = keyboard::make_key_event(&event_key, false, None, element_state);
+              // The new modifiers are sent before the press of a modifier, and after its release.
+              let key_modifiers = keyboard::get_modifiers(event_key.clone());
+              let (mods, key_event_mods) = match element_state {
+                ElementState::Pressed => (held | key_modifiers, held | key_modifiers),
+                ElementState::Released => (held - key_modifiers, held),
+              };
+              if element_state == ElementState::Pressed && mods != held {
+                send_modifiers(mods);
+              }
+
+              let scancode = event_key.hardware_keycode();
+              let is_repeat = match element_state {
+                ElementState::Pressed => !pressed_keys.borrow_mut().insert(scancode),
+                ElementState::Released => {
+                  pressed_keys.borrow_mut().remove(&scancode);
+                  false
+                }
+              };
+              let event = keyboard::make_key_event(
+                &event_key,
+                is_repeat,
+                None,
+                element_state,
+                key_event_mods,
+              );
 
               if let Some(event) = event {
                 if let Err(e) = tx_clone.send(Event::WindowEvent {
                   window_id: RootWindowId(id),
                   event: WindowEvent::KeyboardInput {
-                    device_id: DEVICE_ID,
+                    device_id: device::seat_device_id(event_key.seat()),
                     event,
                     is_synthetic: false,
+                    timestamp: util::event_time(event_key.time()),
                   },
                 }) {
                   log::warn!("Failed to send keyboard event to event channel: {}", e);
                 }
               }
+
+              if element_state == ElementState::Released && mods != held {
+                send_modifiers(mods);
+              }
               glib::ControlFlow::Continue
             });
 
             let tx_clone = event_tx.clone();
-            // TODO Add actual IME from system
-            let ime = gtk::IMContextSimple::default();
+            // The input method chosen by the user, falling back to the simple one of GTK.
+            let ime = gtk::IMMulticontext::new();
             ime.set_client_window(window.window().as_ref());
             ime.focus_in();
+            // Keep a reference on the window so `WindowRequest::ResetDeadKeys` can reach it.
+            unsafe { window.set_data(IM_CONTEXT_KEY, ime.clone()) };
+            let ime_allowed = Rc::new(Cell::new(false));
+            unsafe { window.set_data(IME_ALLOWED_KEY, ime_allowed.clone()) };
+            let allowed = ime_allowed.clone();
             ime.connect_commit(move |_, s| {
+              let event = if allowed.get() {
+                WindowEvent::Ime(Ime::Commit(s.to_string()))
+              } else {
+                WindowEvent::ReceivedImeText(s.to_string())
+              };
               if let Err(e) = tx_clone.send(Event::WindowEvent {
                 window_id: RootWindowId(id),
-                event: WindowEvent::ReceivedImeText(s.to_string()),
+                event,
               }) {
                 log::warn!(
                   "Failed to send received IME text event to event channel: {}",
@@ -785,6 +2219,37 @@ impl<T: 'static> EventLoop<T> {
                 );
               }
             });
+            let tx_clone = event_tx.clone();
+            ime.connect_preedit_changed(move |ime| {
+              if !ime_allowed.get() {
+                return;
+              }
+              // The cursor is counted in characters.
+              let (text, _, cursor) = ime.preedit_string();
+              let cursor = (!text.is_empty()).then(|| {
+                let cursor = text
+                  .char_indices()
+                  .nth(cursor.max(0) as usize)
+                  .map_or(text.len(), |(i, _)| i);
+                (cursor, cursor)
+              });
+              if let Err(e) = tx_clone.send(Event::WindowEvent {
+                window_id: RootWindowId(id),
+                event: WindowEvent::Ime(Ime::Preedit(text.to_string(), cursor)),
+              }) {
+                log::warn!("Failed to send IME preedit event to event channel: {}", e);
+              }
+            });
+            let ime_ = ime.clone();
+            window.connect_focus_in_event(move |_, _| {
+              ime_.focus_in();
+              glib::Propagation::Proceed
+            });
+            let ime_ = ime.clone();
+            window.connect_focus_out_event(move |_, _| {
+              ime_.focus_out();
+              glib::Propagation::Proceed
+            });
 
             let handler = keyboard_handler.clone();
             window.connect_key_press_event(move |_, event_key| {
@@ -801,9 +2266,13 @@ impl<T: 'static> EventLoop<T> {
             });
 
             let tx_clone = event_tx.clone();
+            let reported_maximized = Cell::new(None);
             window.connect_window_state_event(move |window, event| {
+              if !window.is_mapped() {
+                return glib::Propagation::Proceed;
+              }
               let state = event.changed_mask();
-              if state.contains(WindowState::ICONIFIED) || state.contains(WindowState::MAXIMIZED) {
+              if state.contains(WindowState::ICONIFIED) {
                 let scale_factor = window.scale_factor();
 
                 let (x, y) = window.position();
@@ -829,6 +2298,18 @@ impl<T: 'static> EventLoop<T> {
                   );
                 }
               }
+
+              let maximized = event.new_window_state().contains(WindowState::MAXIMIZED);
+              // Showing a hidden window applies its state again, which isn't a change.
+              if state.contains(WindowState::MAXIMIZED)
+                && reported_maximized.replace(Some(maximized)) != Some(maximized)
+              {
+                pending_maximized.set(Some(maximized));
+                // Some window managers send the configure event first, or none at all if the
+                // size doesn't change, so don't wait for it forever.
+                let send_maximized = send_maximized.clone();
+                glib::timeout_add_local_once(MAXIMIZED_CONFIGURE_TIMEOUT, move || send_maximized());
+              }
               glib::Propagation::Proceed
             });
 
@@ -877,12 +2358,19 @@ impl<T: 'static> EventLoop<T> {
     let event_loop = Self {
       window_target: RootELW {
         p: window_target,
+        exit: Default::default(),
+        executor: Default::default(),
+        captures: Default::default(),
+        window_motion_coalescing: Default::default(),
+        window_registry: Default::default(),
+        user_events: Default::default(),
         _marker: std::marker::PhantomData,
       },
       user_event_tx,
-      events: event_rx,
-      draws: draw_rx,
       run_device_thread,
+      run_power_thread,
+      power_thread,
+      run_appearance_thread,
     };
 
     Ok(event_loop)
@@ -930,122 +2418,56 @@ impl<T: 'static> EventLoop<T> {
   where
     F: FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
   {
-    enum EventState {
-      NewStart,
-      EventQueue,
-      DrawQueue,
-    }
-
     let context = MainContext::default();
     let run_device_thread = self.run_device_thread.clone();
+    let run_power_thread = self.run_power_thread.clone();
+    let power_thread = self.power_thread.take();
+    let run_appearance_thread = self.run_appearance_thread.clone();
 
     context
       .with_thread_default(|| {
         let mut control_flow = ControlFlow::default();
         let window_target = &self.window_target;
-        let events = &self.events;
-        let draws = &self.draws;
-
-        window_target.p.app.activate();
+        let wait_until_timer = WaitUntilTimer::new();
+
+        if window_target.p.display.is_some() {
+          window_target.p.app.activate();
+        } else if let Err(e) = window_target
+          .p
+          .event_tx
+          .send(Event::NewEvents(StartCause::Init))
+        {
+          // An unregistered application has no `activate` signal to send the init event.
+          log::warn!("Failed to send init event to event channel: {}", e);
+        }
 
         let mut state = EventState::NewStart;
         let exit_code = loop {
-          let mut blocking = false;
-          match state {
-            EventState::NewStart => match control_flow {
-              ControlFlow::ExitWithCode(code) => {
-                callback(Event::LoopDestroyed, window_target, &mut control_flow);
-                break code;
-              }
-              ControlFlow::Wait => {
-                if !events.is_empty() {
-                  callback(
-                    Event::NewEvents(StartCause::WaitCancelled {
-                      start: Instant::now(),
-                      requested_resume: None,
-                    }),
-                    window_target,
-                    &mut control_flow,
-                  );
-                  state = EventState::EventQueue;
-                } else {
-                  blocking = true;
-                }
-              }
-              ControlFlow::WaitUntil(requested_resume) => {
-                let start = Instant::now();
-                if start >= requested_resume {
-                  callback(
-                    Event::NewEvents(StartCause::ResumeTimeReached {
-                      start,
-                      requested_resume,
-                    }),
-                    window_target,
-                    &mut control_flow,
-                  );
-                  state = EventState::EventQueue;
-                } else if !events.is_empty() {
-                  callback(
-                    Event::NewEvents(StartCause::WaitCancelled {
-                      start,
-                      requested_resume: Some(requested_resume),
-                    }),
-                    window_target,
-                    &mut control_flow,
-                  );
-                  state = EventState::EventQueue;
-                } else {
-                  blocking = true;
-                }
-              }
-              _ => {
-                callback(
-                  Event::NewEvents(StartCause::Poll),
-                  window_target,
-                  &mut control_flow,
-                );
-                state = EventState::EventQueue;
-              }
-            },
-            EventState::EventQueue => match control_flow {
-              ControlFlow::ExitWithCode(code) => {
-                callback(Event::LoopDestroyed, window_target, &mut control_flow);
-                break (code);
-              }
-              _ => match events.try_recv() {
-                Ok(event) => match event {
-                  Event::LoopDestroyed => control_flow = ControlFlow::ExitWithCode(1),
-                  _ => callback(event, window_target, &mut control_flow),
-                },
-                Err(_) => {
-                  callback(Event::MainEventsCleared, window_target, &mut control_flow);
-                  state = EventState::DrawQueue;
-                }
-              },
-            },
-            EventState::DrawQueue => match control_flow {
-              ControlFlow::ExitWithCode(code) => {
-                callback(Event::LoopDestroyed, window_target, &mut control_flow);
-                break code;
-              }
-              _ => {
-                if let Ok(id) = draws.try_recv() {
-                  callback(
-                    Event::RedrawRequested(RootWindowId(id)),
-                    window_target,
-                    &mut control_flow,
-                  );
-                }
-                callback(Event::RedrawEventsCleared, window_target, &mut control_flow);
-                state = EventState::NewStart;
-              }
-            },
+          if let ControlFlow::ExitWithCode(code) = control_flow {
+            callback(Event::LoopDestroyed, window_target, &mut control_flow);
+            break code;
           }
-          gtk::main_iteration_do(blocking);
+          let blocking = step(
+            window_target,
+            &mut state,
+            &wait_until_timer,
+            &mut callback,
+            &mut control_flow,
+          );
+          window_target.p.main_iteration(blocking);
         };
         if let Some(run_device_thread) = run_device_thread {
           run_device_thread.store(false, Ordering::Relaxed);
         }
+        if let Some(run_power_thread) = run_power_thread {
+          run_power_thread.store(false, Ordering::Relaxed);
+        }
+        if let Some(power_thread) = power_thread {
+          power_thread.stop();
+        }
+        if let Some(run_appearance_thread) = run_appearance_thread {
+          run_appearance_thread.store(false, Ordering::Relaxed);
+        }
         exit_code
       })
       .unwrap_or(1)
@@ -1064,6 +2486,141 @@ impl<T: 'static> EventLoop<T> {
   }
 }
 
+/// Where the event loop is in its iteration, see `step`.
+enum EventState {
+  NewStart,
+  EventQueue,
+  DrawQueue,
+}
+
+/// Delivers the next event of the iteration of the event loop, and returns whether the event loop
+/// should then block until a source is ready. The event loop exits instead of calling it once
+/// `control_flow` is `ControlFlow::ExitWithCode`.
+fn step<T>(
+  window_target: &RootELW<T>,
+  state: &mut EventState,
+  wait_until_timer: &WaitUntilTimer,
+  callback: &mut dyn FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
+  control_flow: &mut ControlFlow,
+) -> bool {
+  let events = &window_target.p.event_rx;
+  let draws = &window_target.p.draw_rx;
+  let windows = &window_target.p.windows;
+  let woken = &window_target.p.woken;
+  let mut blocking = false;
+  match state {
+    EventState::NewStart => match *control_flow {
+      ControlFlow::Wait => {
+        if woken.swap(false, Ordering::AcqRel) || !events.is_empty() || !draws.is_empty() {
+          callback(
+            Event::NewEvents(StartCause::WaitCancelled {
+              start: Instant::now(),
+              requested_resume: None,
+            }),
+            window_target,
+            control_flow,
+          );
+          *state = EventState::EventQueue;
+        } else {
+          blocking = true;
+        }
+      }
+      ControlFlow::WaitUntil(requested_resume) => {
+        let start = Instant::now();
+        if start >= requested_resume {
+          callback(
+            Event::NewEvents(StartCause::ResumeTimeReached {
+              start,
+              requested_resume,
+              resumed: start,
+            }),
+            window_target,
+            control_flow,
+          );
+          *state = EventState::EventQueue;
+        } else if woken.swap(false, Ordering::AcqRel) || !events.is_empty() || !draws.is_empty() {
+          callback(
+            Event::NewEvents(StartCause::WaitCancelled {
+              start,
+              requested_resume: Some(requested_resume),
+            }),
+            window_target,
+            control_flow,
+          );
+          *state = EventState::EventQueue;
+        } else {
+          wait_until_timer.set(requested_resume);
+          blocking = true;
+        }
+      }
+      _ => {
+        woken.store(false, Ordering::Release);
+        callback(
+          Event::NewEvents(StartCause::Poll),
+          window_target,
+          control_flow,
+        );
+        *state = EventState::EventQueue;
+      }
+    },
+    EventState::EventQueue => match events.try_recv() {
+      Ok(event) => match event {
+        Event::LoopDestroyed => *control_flow = ControlFlow::ExitWithCode(1),
+        // `Destroyed` is the last event of a window, drop the ones emitted after it.
+        Event::WindowEvent { window_id, .. } if !windows.borrow().contains(&window_id.0) => {}
+        Event::WindowEvent {
+          window_id,
+          event: WindowEvent::Destroyed,
+        } => {
+          windows.borrow_mut().remove(&window_id.0);
+          callback(event, window_target, control_flow);
+        }
+        _ => callback(event, window_target, control_flow),
+      },
+      Err(_) => {
+        callback(Event::MainEventsCleared, window_target, control_flow);
+        *state = EventState::DrawQueue;
+      }
+    },
+    EventState::DrawQueue => {
+      // Take the requests made so far only, once per window, so requests made while
+      // redrawing are delivered in the next iteration.
+      let mut redraws = Vec::new();
+      for id in draws.try_iter() {
+        if !redraws.contains(&id) {
+          redraws.push(id);
+        }
+      }
+      for id in redraws {
+        if !windows.borrow().contains(&id) {
+          continue;
+        }
+        callback(
+          Event::RedrawRequested(RootWindowId(id)),
+          window_target,
+          control_flow,
+        );
+      }
+      callback(Event::RedrawEventsCleared, window_target, control_flow);
+      *state = EventState::NewStart;
+
+      // Only when the next iteration has nothing to handle yet.
+      if events.is_empty()
+        && draws.is_empty()
+        && !woken.load(Ordering::Acquire)
+        && !MainContext::default().pending()
+      {
+        if let Some(deadline) = IdleDeadline::new(*control_flow) {
+          if let Some(idle_callback) = &mut *window_target.p.idle_callback.borrow_mut() {
+            idle_callback(deadline);
+          }
+        }
+      }
+    }
+  }
+  blocking
+}
+
 /// Used to send custom events to `EventLoop`.
 #[derive(Debug)]
 pub struct EventLoopProxy<T: 'static> {
@@ -1103,6 +2660,59 @@ impl<T: 'static> EventLoopProxy<T> {
   }
 }
 
+/// Reports the start of an interactive move or resize of `window`, ended by `end_drag_resize`.
+fn begin_drag_resize<T>(
+  window: &gtk::Window,
+  id: WindowId,
+  resize: bool,
+  event_tx: &crossbeam_channel::Sender<Event<'static, T>>,
+) {
+  // A drag whose end went unnoticed is ended before the new one starts.
+  end_drag_resize(window, id, event_tx);
+  unsafe { window.set_data(DRAG_RESIZE_KEY, resize) };
+  if let Err(e) = event_tx.send(Event::WindowEvent {
+    window_id: RootWindowId(id),
+    event: WindowEvent::DragResizeStarted { resize },
+  }) {
+    log::warn!(
+      "Failed to send drag resize started event to event channel: {}",
+      e
+    );
+  }
+}
+
+/// Reports the end of the interactive move or resize of `window`, if one is in progress.
+fn end_drag_resize<T>(
+  window: &gtk::Window,
+  id: WindowId,
+  event_tx: &crossbeam_channel::Sender<Event<'static, T>>,
+) {
+  if let Some(resize) = unsafe { window.steal_data::<bool>(DRAG_RESIZE_KEY) } {
+    if let Err(e) = event_tx.send(Event::WindowEvent {
+      window_id: RootWindowId(id),
+      event: WindowEvent::DragResizeEnded { resize },
+    }) {
+      log::warn!(
+        "Failed to send drag resize ended event to event channel: {}",
+        e
+      );
+    }
+  }
+}
+
+/// The pressure, tilt and twist of a pen from the GDK axes `axis` returns. GDK normalizes the
+/// tilt from -1 to 1 and the rotation from 0 to 1.
+#[allow(clippy::type_complexity)]
+fn pen_axes(
+  axis: impl Fn(gdk::AxisUse) -> Option<f64>,
+) -> (Option<f64>, Option<(f64, f64)>, Option<f64>) {
+  let tilt = axis(gdk::AxisUse::Xtilt)
+    .zip(axis(gdk::AxisUse::Ytilt))
+    .map(|(x, y)| (x * 90.0, y * 90.0));
+  let twist = axis(gdk::AxisUse::Rotation).map(|rotation| rotation * 360.0);
+  (axis(gdk::AxisUse::Pressure), tilt, twist)
+}
+
 fn assert_is_main_thread(suggested_method: &str) {
   assert!(
     is_main_thread(),
@@ -1133,45 +2743,58 @@ fn is_main_thread() -> bool {
 }
 
 impl CursorIcon {
-  fn to_str(&self) -> &str {
+  /// CSS cursor name first, followed by the legacy X11 cursor-font and
+  /// freedesktop aliases that older or incomplete themes ship instead.
+  fn names(&self) -> &'static [&'static str] {
     match self {
-      CursorIcon::Crosshair => "crosshair",
-      CursorIcon::Hand => "pointer",
-      CursorIcon::Arrow => "arrow",
-      CursorIcon::Move => "move",
-      CursorIcon::Text => "text",
-      CursorIcon::Wait => "wait",
-      CursorIcon::Help => "help",
-      CursorIcon::Progress => "progress",
-      CursorIcon::NotAllowed => "not-allowed",
-      CursorIcon::ContextMenu => "context-menu",
-      CursorIcon::Cell => "cell",
-      CursorIcon::VerticalText => "vertical-text",
-      CursorIcon::Alias => "alias",
-      CursorIcon::Copy => "copy",
-      CursorIcon::NoDrop => "no-drop",
-      CursorIcon::Grab => "grab",
-      CursorIcon::Grabbing => "grabbing",
-      CursorIcon::AllScroll => "all-scroll",
-      CursorIcon::ZoomIn => "zoom-in",
-      CursorIcon::ZoomOut => "zoom-out",
-      CursorIcon::EResize => "e-resize",
-      CursorIcon::NResize => "n-resize",
-      CursorIcon::NeResize => "ne-resize",
-      CursorIcon::NwResize => "nw-resize",
-      CursorIcon::SResize => "s-resize",
-      CursorIcon::SeResize => "se-resize",
-      CursorIcon::SwResize => "sw-resize",
-      CursorIcon::WResize => "w-resize",
-      CursorIcon::EwResize => "ew-resize",
-      CursorIcon::NsResize => "ns-resize",
-      CursorIcon::NeswResize => "nesw-resize",
-      CursorIcon::NwseResize => "nwse-resize",
-      CursorIcon::ColResize => "col-resize",
-      CursorIcon::RowResize => "row-resize",
-      CursorIcon::Default => "default",
+      CursorIcon::Default => &["default", "left_ptr"],
+      CursorIcon::Crosshair => &["crosshair", "cross"],
+      CursorIcon::Hand => &["pointer", "hand2", "hand1"],
+      CursorIcon::Arrow => &["default", "arrow", "left_ptr"],
+      CursorIcon::Move => &["move", "fleur", "all-scroll"],
+      CursorIcon::Text => &["text", "xterm"],
+      CursorIcon::Wait => &["wait", "watch"],
+      CursorIcon::Help => &["help", "question_arrow", "left_ptr_help"],
+      CursorIcon::Progress => &["progress", "left_ptr_watch", "watch"],
+      CursorIcon::NotAllowed => &["not-allowed", "crossed_circle", "no-drop"],
+      CursorIcon::ContextMenu => &["context-menu", "left_ptr"],
+      CursorIcon::Cell => &["cell", "plus", "crosshair"],
+      CursorIcon::VerticalText => &["vertical-text", "text", "xterm"],
+      CursorIcon::Alias => &["alias", "link", "dnd-link"],
+      CursorIcon::Copy => &["copy", "dnd-copy"],
+      CursorIcon::NoDrop => &["no-drop", "dnd-no-drop", "not-allowed"],
+      CursorIcon::Grab => &["grab", "openhand", "hand1"],
+      CursorIcon::Grabbing => &["grabbing", "closedhand", "dnd-move", "fleur"],
+      CursorIcon::AllScroll => &["all-scroll", "fleur"],
+      CursorIcon::ZoomIn => &["zoom-in", "zoom_in", "plus"],
+      CursorIcon::ZoomOut => &["zoom-out", "zoom_out", "minus", "crosshair"],
+      CursorIcon::EResize => &["e-resize", "right_side"],
+      CursorIcon::NResize => &["n-resize", "top_side"],
+      CursorIcon::NeResize => &["ne-resize", "top_right_corner"],
+      CursorIcon::NwResize => &["nw-resize", "top_left_corner"],
+      CursorIcon::SResize => &["s-resize", "bottom_side"],
+      CursorIcon::SeResize => &["se-resize", "bottom_right_corner"],
+      CursorIcon::SwResize => &["sw-resize", "bottom_left_corner"],
+      CursorIcon::WResize => &["w-resize", "left_side"],
+      CursorIcon::EwResize => &["ew-resize", "sb_h_double_arrow", "e-resize"],
+      CursorIcon::NsResize => &["ns-resize", "sb_v_double_arrow", "n-resize"],
+      CursorIcon::NeswResize => &["nesw-resize", "fd_double_arrow", "ne-resize"],
+      CursorIcon::NwseResize => &["nwse-resize", "bd_double_arrow", "nw-resize"],
+      CursorIcon::ColResize => &["col-resize", "sb_h_double_arrow", "ew-resize"],
+      CursorIcon::RowResize => &["row-resize", "sb_v_double_arrow", "ns-resize"],
     }
   }
+
+  /// Loads the closest cursor the current theme provides, ending with the
+  /// built-in left pointer so a missing name never leaves the parent's cursor
+  /// in place.
+  fn to_gdk_cursor(&self, display: &gdk::Display) -> Option<Cursor> {
+    self
+      .names()
+      .iter()
+      .find_map(|name| Cursor::from_name(display, name))
+      .or_else(|| Cursor::for_display(display, CursorType::LeftPtr))
+  }
 }
 
 impl ResizeDirection {
@@ -1201,3 +2824,33 @@ impl ResizeDirection {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{gdk, pen_axes};
+
+  #[test]
+  fn pen_axes_scales_the_tilt_and_rotation() {
+    let (pressure, tilt, twist) = pen_axes(|axis| match axis {
+      gdk::AxisUse::Pressure => Some(0.25),
+      gdk::AxisUse::Xtilt => Some(-0.5),
+      gdk::AxisUse::Ytilt => Some(1.0),
+      gdk::AxisUse::Rotation => Some(0.5),
+      _ => None,
+    });
+    assert_eq!(pressure, Some(0.25));
+    assert_eq!(tilt, Some((-45.0, 90.0)));
+    assert_eq!(twist, Some(180.0));
+  }
+
+  #[test]
+  fn pen_axes_needs_both_tilt_axes() {
+    let (pressure, tilt, twist) = pen_axes(|axis| match axis {
+      gdk::AxisUse::Xtilt => Some(0.5),
+      _ => None,
+    });
+    assert_eq!(pressure, None);
+    assert_eq!(tilt, None);
+    assert_eq!(twist, None);
+  }
+}
@@ -0,0 +1,162 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  cell::{Cell, RefCell},
+  collections::HashMap,
+  os::raw::{c_int, c_uint, c_void},
+  ptr,
+};
+
+use gtk::{gdk, glib::translate::ToGlibPtr};
+use x11_dl::xlib;
+
+use crate::{
+  error::ExternalError,
+  event::Event,
+  event_loop::GlobalShortcutId,
+  keyboard::{KeyCode, ModifiersState},
+};
+
+/// The modifiers compared with those of the shortcuts, all others being ignored.
+const MODIFIERS_MASK: c_uint =
+  xlib::ShiftMask | xlib::ControlMask | xlib::Mod1Mask | xlib::Mod4Mask;
+
+/// The lock modifiers, NumLock being `Mod2Mask`, which are grabbed as well since a grab only
+/// matches the exact modifiers.
+const LOCK_MASKS: [c_uint; 4] = [
+  0,
+  xlib::LockMask,
+  xlib::Mod2Mask,
+  xlib::LockMask | xlib::Mod2Mask,
+];
+
+thread_local! {
+  /// The keycode and modifiers of the shortcuts by id.
+  static SHORTCUTS: RefCell<HashMap<u32, (c_int, c_uint)>> = RefCell::new(HashMap::new());
+  static FILTER_INSTALLED: Cell<bool> = Cell::new(false);
+}
+
+pub fn register<T: 'static>(
+  display: &gdk::Display,
+  event_tx: &crossbeam_channel::Sender<Event<'static, T>>,
+  id: u32,
+  modifiers: ModifiersState,
+  key: KeyCode,
+) -> Result<(), ExternalError> {
+  let keycode = key
+    .to_scancode()
+    .ok_or(ExternalError::InvalidInput("the key has no scancode"))? as c_int;
+  let mut mask = 0;
+  if modifiers.shift_key() {
+    mask |= xlib::ShiftMask;
+  }
+  if modifiers.control_key() {
+    mask |= xlib::ControlMask;
+  }
+  if modifiers.alt_key() {
+    mask |= xlib::Mod1Mask;
+  }
+  if modifiers.super_key() {
+    mask |= xlib::Mod4Mask;
+  }
+
+  if !FILTER_INSTALLED.with(Cell::get) {
+    // Lives as long as the filter, which is never removed.
+    let event_tx = Box::into_raw(Box::new(event_tx.clone()));
+    unsafe {
+      gdk::ffi::gdk_window_add_filter(ptr::null_mut(), Some(filter::<T>), event_tx as *mut c_void)
+    };
+    FILTER_INSTALLED.with(|installed| installed.set(true));
+  }
+
+  let grabbed = grab(display, |xlib, xdisplay, root| {
+    for lock_mask in LOCK_MASKS {
+      unsafe {
+        (xlib.XGrabKey)(
+          xdisplay,
+          keycode,
+          mask | lock_mask,
+          root,
+          xlib::False,
+          xlib::GrabModeAsync,
+          xlib::GrabModeAsync,
+        )
+      };
+    }
+  });
+  if let Err(error) = grabbed {
+    // The errors are only known after all the grabs, some of which may have succeeded.
+    let _ = ungrab(display, keycode, mask);
+    return Err(error);
+  }
+  SHORTCUTS.with(|shortcuts| shortcuts.borrow_mut().insert(id, (keycode, mask)));
+  Ok(())
+}
+
+pub fn unregister(display: &gdk::Display, id: u32) -> Result<(), ExternalError> {
+  let (keycode, mask) = SHORTCUTS
+    .with(|shortcuts| shortcuts.borrow_mut().remove(&id))
+    .ok_or(ExternalError::InvalidInput("the shortcut isn't registered"))?;
+  ungrab(display, keycode, mask)
+}
+
+/// Releases the grabs of the key for every lock modifier, those of other clients being left
+/// alone.
+fn ungrab(display: &gdk::Display, keycode: c_int, mask: c_uint) -> Result<(), ExternalError> {
+  grab(display, |xlib, xdisplay, root| {
+    for lock_mask in LOCK_MASKS {
+      unsafe { (xlib.XUngrabKey)(xdisplay, keycode, mask | lock_mask, root) };
+    }
+  })
+}
+
+/// Runs `f` with the root window of `display`, failing if it caused an X error, e.g. because
+/// another client grabbed the same key.
+fn grab(
+  display: &gdk::Display,
+  f: impl FnOnce(&xlib::Xlib, *mut xlib::Display, xlib::Window),
+) -> Result<(), ExternalError> {
+  let xlib = xlib::Xlib::open().map_err(|_| ExternalError::Os(os_error!(super::OsError)))?;
+  unsafe {
+    let display: *mut gdk::ffi::GdkDisplay = display.to_glib_none().0;
+    let xdisplay = gdk_x11_sys::gdk_x11_display_get_xdisplay(display as *mut _) as *mut _;
+    gdk_x11_sys::gdk_x11_display_error_trap_push(display as *mut _);
+    f(&xlib, xdisplay, (xlib.XDefaultRootWindow)(xdisplay));
+    (xlib.XSync)(xdisplay, xlib::False);
+    if gdk_x11_sys::gdk_x11_display_error_trap_pop(display as *mut _) != 0 {
+      return Err(ExternalError::Os(os_error!(super::OsError)));
+    }
+  }
+  Ok(())
+}
+
+unsafe extern "C" fn filter<T: 'static>(
+  xevent: *mut gdk::ffi::GdkXEvent,
+  _: *mut gdk::ffi::GdkEvent,
+  event_tx: *mut c_void,
+) -> gdk::ffi::GdkFilterReturn {
+  let xevent = &*(xevent as *const xlib::XEvent);
+  if xevent.get_type() != xlib::KeyPress {
+    return gdk::ffi::GDK_FILTER_CONTINUE;
+  }
+  let key = xevent.key;
+  let id = SHORTCUTS.with(|shortcuts| {
+    shortcuts.borrow().iter().find_map(|(id, (keycode, mask))| {
+      (*keycode as c_uint == key.keycode && *mask == key.state & MODIFIERS_MASK).then(|| *id)
+    })
+  });
+  match id {
+    Some(id) => {
+      let event_tx = &*(event_tx as *const crossbeam_channel::Sender<Event<'static, T>>);
+      if let Err(e) = event_tx.send(Event::GlobalShortcut(GlobalShortcutId(id))) {
+        log::warn!(
+          "Failed to send global shortcut event to event channel: {}",
+          e
+        );
+      }
+      gdk::ffi::GDK_FILTER_REMOVE
+    }
+    None => gdk::ffi::GDK_FILTER_CONTINUE,
+  }
+}
@@ -6,27 +6,31 @@ use std::{fs::File, io::BufWriter, path::Path};
 
 use gtk::gdk_pixbuf::{Colorspace, Pixbuf};
 
-use crate::window::BadIcon;
+use crate::{
+  icon::{validate_icon_set, RgbaIcon},
+  window::BadIcon,
+};
 
 /// An icon used for the window titlebar, taskbar, etc.
+///
+/// Holds every size of the icon, from the smallest to the largest.
 #[derive(Debug, Clone)]
 pub struct PlatformIcon {
-  raw: Vec<u8>,
-  width: i32,
-  height: i32,
-  row_stride: i32,
+  icons: Vec<RgbaIcon>,
 }
 
-impl From<PlatformIcon> for Pixbuf {
-  fn from(icon: PlatformIcon) -> Self {
+impl From<&RgbaIcon> for Pixbuf {
+  fn from(icon: &RgbaIcon) -> Self {
+    let (width, height) = (icon.width as i32, icon.height as i32);
+    let row_stride = Pixbuf::calculate_rowstride(Colorspace::Rgb, true, 8, width, height);
     Pixbuf::from_mut_slice(
-      icon.raw,
+      icon.rgba.clone(),
       Colorspace::Rgb,
       true,
       8,
-      icon.width,
-      icon.height,
-      icon.row_stride,
+      width,
+      height,
+      row_stride,
     )
   }
 }
@@ -37,25 +41,66 @@ impl PlatformIcon {
   /// The length of `rgba` must be divisible by 4, and `width * height` must equal
   /// `rgba.len() / 4`. Otherwise, this will return a `BadIcon` error.
   pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, BadIcon> {
-    let row_stride =
-      Pixbuf::calculate_rowstride(Colorspace::Rgb, true, 8, width as i32, height as i32);
     Ok(Self {
-      raw: rgba,
-      width: width as i32,
-      height: height as i32,
-      row_stride,
+      icons: vec![RgbaIcon::from_rgba(rgba, width, height)?],
+    })
+  }
+
+  pub fn from_rgba_set(icons: Vec<RgbaIcon>) -> Result<Self, BadIcon> {
+    Ok(Self {
+      icons: validate_icon_set(icons)?,
     })
   }
 
+  /// Every size of the icon, for `gtk_window_set_icon_list`.
+  pub fn to_pixbufs(&self) -> Vec<Pixbuf> {
+    self.icons.iter().map(Pixbuf::from).collect()
+  }
+
+  /// Every size of the icon as `(width, height, ARGB32 in network byte order)`, the
+  /// `IconPixmap` of a `StatusNotifierItem`.
+  pub fn to_argb_pixmaps(&self) -> Vec<(i32, i32, Vec<u8>)> {
+    self
+      .icons
+      .iter()
+      .map(|icon| {
+        let argb = icon
+          .rgba
+          .chunks_exact(4)
+          .flat_map(|rgba| [rgba[3], rgba[0], rgba[1], rgba[2]])
+          .collect();
+        (icon.width as i32, icon.height as i32, argb)
+      })
+      .collect()
+  }
+
+  /// The largest size of the icon as `(width, height, row stride, has alpha, bits per sample,
+  /// channels, RGBA)`, the `image-data` hint of a notification.
+  pub fn to_image_data(&self) -> (i32, i32, i32, bool, i32, i32, Vec<u8>) {
+    let icon = self.icons.last().unwrap();
+    (
+      icon.width as i32,
+      icon.height as i32,
+      icon.width as i32 * 4,
+      true,
+      8,
+      4,
+      icon.rgba.clone(),
+    )
+  }
+
+  /// Writes the largest size of the icon.
+  #[allow(dead_code)]
   pub fn write_to_png(&self, path: impl AsRef<Path>) {
+    let icon = self.icons.last().unwrap();
     let png = File::create(path).unwrap();
     let ref mut w = BufWriter::new(png);
 
-    let mut encoder = png::Encoder::new(w, self.width as _, self.height as _);
+    let mut encoder = png::Encoder::new(w, icon.width, icon.height);
     encoder.set_color(png::ColorType::Rgba);
     encoder.set_depth(png::BitDepth::Eight);
 
     let mut writer = encoder.write_header().unwrap();
-    writer.write_image_data(&self.raw).unwrap();
+    writer.write_image_data(&icon.rgba).unwrap();
   }
 }
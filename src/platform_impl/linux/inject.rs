@@ -0,0 +1,158 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::os::raw::{c_int, c_uint, c_ulong};
+
+use gtk::{gdk, glib::translate::ToGlibPtr};
+use x11_dl::{keysym, xlib, xtest};
+
+use super::keyboard::{xkb_state_group, XLIB};
+use crate::{
+  error::{ExternalError, NotSupportedError},
+  event::{ElementState, MouseButton, MouseScrollDelta},
+  platform::inject::SyntheticInput,
+};
+
+lazy_static! {
+  // `x11-dl` names the `XTest` library after another extension.
+  static ref XTEST: Option<xtest::Xf86vmode> = xtest::Xf86vmode::open().ok();
+}
+
+pub fn inject_input(display: &gdk::Display, input: SyntheticInput) -> Result<(), ExternalError> {
+  let xlib = XLIB
+    .as_ref()
+    .ok_or_else(|| ExternalError::Os(os_error!(super::OsError)))?;
+  let xtest = XTEST
+    .as_ref()
+    .ok_or_else(|| ExternalError::NotSupported(NotSupportedError::new()))?;
+  unsafe {
+    let display: *mut gdk::ffi::GdkDisplay = display.to_glib_none().0;
+    let xdisplay = gdk_x11_sys::gdk_x11_display_get_xdisplay(display as *mut _) as *mut _;
+    gdk_x11_sys::gdk_x11_display_error_trap_push(display as *mut _);
+    let result = fake_input(xlib, xtest, xdisplay, input);
+    (xlib.XSync)(xdisplay, xlib::False);
+    if gdk_x11_sys::gdk_x11_display_error_trap_pop(display as *mut _) != 0 {
+      return Err(ExternalError::Os(os_error!(super::OsError)));
+    }
+    result
+  }
+}
+
+unsafe fn fake_input(
+  xlib: &xlib::Xlib,
+  xtest: &xtest::Xf86vmode,
+  xdisplay: *mut xlib::Display,
+  input: SyntheticInput,
+) -> Result<(), ExternalError> {
+  let key = |keycode: c_uint, pressed: bool| {
+    (xtest.XTestFakeKeyEvent)(xdisplay, keycode, pressed as c_int, xlib::CurrentTime);
+  };
+  let button = |button: c_uint, pressed: bool| {
+    (xtest.XTestFakeButtonEvent)(xdisplay, button, pressed as c_int, xlib::CurrentTime);
+  };
+  match input {
+    // The scancodes are the X11 keycodes.
+    SyntheticInput::Key { code, state } => {
+      let keycode = code
+        .to_scancode()
+        .ok_or(ExternalError::InvalidInput("the key has no scancode"))?;
+      key(keycode, state == ElementState::Pressed);
+    }
+    SyntheticInput::Text(text) => {
+      let group = xkb_state_group(xlib, xdisplay).unwrap_or(0);
+      let modifier = |keysym: c_uint| {
+        let keycode = (xlib.XKeysymToKeycode)(xdisplay, keysym as c_ulong) as c_uint;
+        // The key must also be bound to a modifier to shift the level.
+        let bound = (xlib.XkbKeysymToModifiers)(xdisplay, keysym as c_ulong) != 0;
+        (keycode != 0 && bound).then(|| keycode)
+      };
+      let shift = modifier(keysym::XK_Shift_L);
+      // AltGr, which selects the third and fourth levels.
+      let level3 = modifier(keysym::XK_ISO_Level3_Shift);
+      // Checked beforehand so that no character is typed when one of them can't be.
+      let keys = text
+        .chars()
+        .map(|character| {
+          let keysym = char_keysym(character);
+          let keycode = (xlib.XKeysymToKeycode)(xdisplay, keysym);
+          let level = (keycode != 0)
+            .then(|| {
+              (0..4).find(|&level| {
+                (xlib.XkbKeycodeToKeysym)(xdisplay, keycode, c_int::from(group), level) == keysym
+              })
+            })
+            .flatten();
+          let modifiers = match level {
+            Some(0) => Some(Vec::new()),
+            Some(1) => shift.map(|shift| vec![shift]),
+            Some(2) => level3.map(|level3| vec![level3]),
+            Some(3) => shift.zip(level3).map(|(shift, level3)| vec![shift, level3]),
+            _ => None,
+          };
+          modifiers
+            .map(|modifiers| (keycode as c_uint, modifiers))
+            .ok_or(ExternalError::InvalidInput(
+              "the text has a character which isn't on the keyboard layout",
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+      for (keycode, modifiers) in keys {
+        for &modifier in &modifiers {
+          key(modifier, true);
+        }
+        key(keycode, true);
+        key(keycode, false);
+        for &modifier in modifiers.iter().rev() {
+          key(modifier, false);
+        }
+      }
+    }
+    SyntheticInput::CursorMoved(position) => {
+      (xtest.XTestFakeMotionEvent)(
+        xdisplay,
+        -1,
+        position.x.round() as c_int,
+        position.y.round() as c_int,
+        xlib::CurrentTime,
+      );
+    }
+    SyntheticInput::MouseInput {
+      button: mouse_button,
+      state,
+    } => {
+      let number = match mouse_button {
+        MouseButton::Left => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Right => 3,
+        MouseButton::Other(number) => number as c_uint,
+      };
+      button(number, state == ElementState::Pressed);
+    }
+    // Each line is a click of the buttons 4 to 7, like the wheels of a mouse.
+    SyntheticInput::MouseWheel(MouseScrollDelta::LineDelta(x, y)) => {
+      for (lines, positive, negative) in [(y, 4, 5), (x, 6, 7)] {
+        let number = if lines > 0.0 { positive } else { negative };
+        for _ in 0..lines.abs().round() as u32 {
+          button(number, true);
+          button(number, false);
+        }
+      }
+    }
+    SyntheticInput::MouseWheel(_) => {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()))
+    }
+  }
+  Ok(())
+}
+
+/// The keysym of a character, see appendix A of the X11 protocol.
+fn char_keysym(character: char) -> c_ulong {
+  match character {
+    '\n' | '\r' => keysym::XK_Return as c_ulong,
+    '\t' => keysym::XK_Tab as c_ulong,
+    '\u{8}' => keysym::XK_BackSpace as c_ulong,
+    // Latin-1 characters have the same keysyms, the others are offset Unicode code points.
+    ' '..='~' | '\u{a0}'..='\u{ff}' => character as c_ulong,
+    _ => 0x0100_0000 | character as c_ulong,
+  }
+}
@@ -2,27 +2,37 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use super::KeyEventExtra;
+use super::{util, KeyEventExtra};
 use crate::{
-  event::{ElementState, KeyEvent},
-  keyboard::{Key, KeyCode, KeyLocation, ModifiersState, NativeKeyCode},
+  event::{ElementState, Event, KeyEvent},
+  keyboard::{Key, KeyCode, KeyLocation, KeyboardLayout, ModifiersState, NativeKeyCode},
 };
 use gtk::{
   gdk::{self, keys::constants::*, EventKey},
-  glib,
+  glib::{self, translate::ToGlibPtr},
 };
 use std::{
+  cell::Cell,
   collections::HashSet,
-  ffi::c_void,
-  os::raw::{c_int, c_uint},
+  ffi::{c_void, CStr},
+  mem::MaybeUninit,
+  os::raw::{c_int, c_uint, c_ulong},
   ptr, slice,
   sync::Mutex,
 };
+use x11_dl::xlib;
 
 pub type RawKey = gdk::keys::Key;
 
+thread_local! {
+  /// The first event code of the XKB extension, set once the switches of group are watched.
+  static XKB_EVENT_BASE: Cell<Option<c_int>> = Cell::new(None);
+}
+
 lazy_static! {
   static ref KEY_STRINGS: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+  /// Loaded once, the XKB group is read for every key event.
+  pub(super) static ref XLIB: Option<xlib::Xlib> = xlib::Xlib::open().ok();
 }
 
 fn insert_or_get_key_str(string: String) -> &'static str {
@@ -165,18 +175,42 @@ pub(crate) fn get_modifiers(key: EventKey) -> ModifiersState {
   result
 }
 
+/// Returns the modifiers that were held down right before `key`.
+pub(crate) fn get_held_modifiers(key: &EventKey) -> ModifiersState {
+  let state = key.state();
+  let mut result = ModifiersState::empty();
+  result.set(
+    ModifiersState::SHIFT,
+    state.contains(gdk::ModifierType::SHIFT_MASK),
+  );
+  result.set(
+    ModifiersState::CONTROL,
+    state.contains(gdk::ModifierType::CONTROL_MASK),
+  );
+  result.set(
+    ModifiersState::ALT,
+    state.contains(gdk::ModifierType::MOD1_MASK),
+  );
+  result.set(
+    ModifiersState::SUPER,
+    state.intersects(gdk::ModifierType::SUPER_MASK | gdk::ModifierType::MOD4_MASK),
+  );
+  result
+}
+
 pub(crate) fn make_key_event(
   key: &EventKey,
   is_repeat: bool,
   key_override: Option<KeyCode>,
   state: ElementState,
+  modifiers: ModifiersState,
 ) -> Option<KeyEvent> {
   // a keycode (scancode in Windows) is a code that refers to a physical keyboard key.
   let scancode = key.hardware_keycode();
   // a keyval (keysym in X) is a "logical" key name, such as GDK_Enter, GDK_a, GDK_space, etc.
   let keyval_without_modifiers = key.keyval();
-  let keyval_with_modifiers =
-    hardware_keycode_to_keyval(scancode).unwrap_or_else(|| keyval_without_modifiers.clone());
+  let keyval_with_modifiers = hardware_keycode_to_keyval(scancode, c_int::from(key.group()))
+    .unwrap_or_else(|| keyval_without_modifiers.clone());
   // get unicode value, with and without modifiers
   let text_without_modifiers = keyval_with_modifiers.to_unicode();
   let text_with_modifiers = keyval_without_modifiers.to_unicode();
@@ -221,6 +255,7 @@ pub(crate) fn make_key_event(
       repeat: is_repeat,
       state,
       text: text_with_all_modifiers,
+      modifiers,
       platform_specific: KeyEventExtra {
         text_with_all_modifiers,
         key_without_modifiers,
@@ -233,9 +268,161 @@ pub(crate) fn make_key_event(
   None
 }
 
+/// `XkbUseCoreKbd`, the core keyboard device.
+const XKB_USE_CORE_KBD: c_uint = 0x0100;
+/// `XkbGroupNamesMask`.
+const XKB_GROUP_NAMES_MASK: c_uint = 1 << 12;
+
+/// The active XKB group of the core keyboard of `xdisplay`.
+pub(super) unsafe fn xkb_state_group(
+  xlib: &xlib::Xlib,
+  xdisplay: *mut xlib::Display,
+) -> Option<u8> {
+  let mut state = MaybeUninit::<xlib::XkbStateRec>::zeroed();
+  if (xlib.XkbGetState)(xdisplay, XKB_USE_CORE_KBD, state.as_mut_ptr()) != 0 {
+    return None;
+  }
+  Some(state.assume_init().group)
+}
+
+/// The active XKB group of the default display and its name, `None` unless it's an X11 one.
+fn xkb_group() -> Option<(c_int, Option<String>)> {
+  let display = gdk::Display::default().filter(util::is_x11)?;
+  let xlib = XLIB.as_ref()?;
+  unsafe {
+    let display: *mut gdk::ffi::GdkDisplay = display.to_glib_none().0;
+    let xdisplay = gdk_x11_sys::gdk_x11_display_get_xdisplay(display as *mut _) as *mut _;
+    let group = xkb_state_group(xlib, xdisplay)?;
+
+    let desc = (xlib.XkbAllocKeyboard)();
+    if desc.is_null() {
+      return Some((c_int::from(group), None));
+    }
+    (*desc).dpy = xdisplay;
+    let mut name = None;
+    if (xlib.XkbGetNames)(xdisplay, XKB_GROUP_NAMES_MASK, desc) == 0 && !(*desc).names.is_null() {
+      let atom = (*(*desc).names).groups[usize::from(group) % xlib::XkbNumKbdGroups];
+      if atom != 0 {
+        let atom_name = (xlib.XGetAtomName)(xdisplay, atom);
+        if !atom_name.is_null() {
+          name = Some(CStr::from_ptr(atom_name).to_string_lossy().into_owned());
+          (xlib.XFree)(atom_name as *mut c_void);
+        }
+      }
+      (xlib.XkbFreeNames)(desc, XKB_GROUP_NAMES_MASK, xlib::True);
+    }
+    (xlib.XkbFreeKeyboard)(desc, 0, xlib::True);
+    Some((c_int::from(group), name))
+  }
+}
+
+/// The active layout, identified by the name of its XKB group.
+pub(crate) fn keyboard_layout() -> Option<KeyboardLayout> {
+  let (_, id) = xkb_group()?;
+  Some(KeyboardLayout { id: id? })
+}
+
+/// Sends `Event::KeyboardLayoutChanged` through `event_tx` when the keymap of `display` changes,
+/// or, on X11, when another group of it is switched to.
+pub(crate) fn watch_keyboard_layout<T: 'static>(
+  display: &gdk::Display,
+  event_tx: &crossbeam_channel::Sender<Event<'static, T>>,
+) {
+  if let Some(keymap) = gdk::Keymap::for_display(display) {
+    let event_tx = event_tx.clone();
+    keymap.connect_keys_changed(move |_| send_keyboard_layout_changed(&event_tx));
+  }
+
+  if !util::is_x11(display) || XKB_EVENT_BASE.with(Cell::get).is_some() {
+    return;
+  }
+  let xlib = match XLIB.as_ref() {
+    Some(xlib) => xlib,
+    None => return,
+  };
+  unsafe {
+    let display: *mut gdk::ffi::GdkDisplay = display.to_glib_none().0;
+    let xdisplay = gdk_x11_sys::gdk_x11_display_get_xdisplay(display as *mut _) as *mut _;
+    let (mut opcode, mut event_base, mut error_base, mut major, mut minor) = (0, 0, 0, 1, 0);
+    if (xlib.XkbQueryExtension)(
+      xdisplay,
+      &mut opcode,
+      &mut event_base,
+      &mut error_base,
+      &mut major,
+      &mut minor,
+    ) == xlib::False
+    {
+      return;
+    }
+    // Adds the group changes to the state changes GDK selected.
+    (xlib.XkbSelectEventDetails)(
+      xdisplay,
+      XKB_USE_CORE_KBD,
+      xlib::XkbStateNotify as c_uint,
+      xlib::XkbGroupStateMask,
+      xlib::XkbGroupStateMask,
+    );
+    XKB_EVENT_BASE.with(|base| base.set(Some(event_base)));
+    // Lives as long as the filter, which is never removed.
+    let event_tx = Box::into_raw(Box::new(event_tx.clone()));
+    gdk::ffi::gdk_window_add_filter(
+      ptr::null_mut(),
+      Some(xkb_filter::<T>),
+      event_tx as *mut c_void,
+    );
+  }
+}
+
+fn send_keyboard_layout_changed<T: 'static>(
+  event_tx: &crossbeam_channel::Sender<Event<'static, T>>,
+) {
+  // Not `e`, which is the key constant imported with the others.
+  if let Err(error) = event_tx.send(Event::KeyboardLayoutChanged) {
+    log::warn!(
+      "Failed to send keyboard layout changed event to event channel: {}",
+      error
+    );
+  }
+}
+
+unsafe extern "C" fn xkb_filter<T: 'static>(
+  xevent: *mut gdk::ffi::GdkXEvent,
+  _: *mut gdk::ffi::GdkEvent,
+  event_tx: *mut c_void,
+) -> gdk::ffi::GdkFilterReturn {
+  let xevent = &*(xevent as *const xlib::XEvent);
+  if Some(xevent.get_type()) == XKB_EVENT_BASE.with(Cell::get) {
+    let xkb_event = &*(xevent as *const xlib::XEvent as *const xlib::XkbStateNotifyEvent);
+    if xkb_event.xkb_type == xlib::XkbStateNotify
+      && c_ulong::from(xkb_event.changed) & xlib::XkbGroupStateMask != 0
+    {
+      send_keyboard_layout_changed(
+        &*(event_tx as *const crossbeam_channel::Sender<Event<'static, T>>),
+      );
+    }
+  }
+  // GDK tracks the state as well.
+  gdk::ffi::GDK_FILTER_CONTINUE
+}
+
+/// Resolve the key produced by `code` under the active keymap, without any modifiers.
+pub(crate) fn key_for_keycode(code: KeyCode) -> Option<Key<'static>> {
+  let scancode = u16::try_from(code.to_scancode()?).ok()?;
+  // The group can't be read on Wayland, where the first one is used.
+  let group = xkb_group().map_or(0, |(group, _)| group);
+  let keyval = hardware_keycode_to_keyval(scancode, group)?;
+  raw_key_to_key(keyval).or_else(|| match keyval.to_unicode() {
+    Some(key) if key >= ' ' && key != '\x7f' => {
+      Some(Key::Character(insert_or_get_key_str(key.to_string())))
+    }
+    _ => None,
+  })
+}
+
 /// Map a hardware keycode to a keyval by performing a lookup in the keymap and finding the
-/// keyval with the lowest group and level
-fn hardware_keycode_to_keyval(keycode: u16) -> Option<RawKey> {
+/// keyval of `group` with the lowest level
+fn hardware_keycode_to_keyval(keycode: u16, group: c_int) -> Option<RawKey> {
   use glib::translate::FromGlib;
   unsafe {
     let keymap = gdk::ffi::gdk_keymap_get_default();
@@ -258,7 +445,7 @@ fn hardware_keycode_to_keyval(keycode: u16) -> Option<RawKey> {
       let keys_slice = slice::from_raw_parts(keys, nkeys as usize);
 
       let resolved_keyval = keys_slice.iter().enumerate().find_map(|(id, gdk_keymap)| {
-        if gdk_keymap.group == 0 && gdk_keymap.level == 0 {
+        if gdk_keymap.group == group && gdk_keymap.level == 0 {
           Some(RawKey::from_glib(keyvals_slice[id]))
         } else {
           None
@@ -39,7 +39,7 @@ pub fn keycode_to_scancode(code: KeyCode) -> Option<u32> {
     KeyCode::KeyJ => Some(0x002C),
     KeyCode::KeyK => Some(0x002D),
     KeyCode::KeyL => Some(0x002E),
-    KeyCode::KeyM => Some(0x002E),
+    KeyCode::KeyM => Some(0x003A),
     KeyCode::KeyN => Some(0x0039),
     KeyCode::KeyO => Some(0x0020),
     KeyCode::KeyP => Some(0x0021),
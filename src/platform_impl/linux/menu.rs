@@ -0,0 +1,239 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::cell::RefCell;
+
+use gtk::{gdk, glib, prelude::*};
+
+use crate::{dpi::LogicalPosition, event::Event, menu::MenuId};
+
+thread_local! {
+  /// Sends the `Event::MenuEvent` of an item to the event loop of this thread.
+  static EVENT_SENDER: RefCell<Option<Box<dyn Fn(Event<'static, ()>)>>> = RefCell::new(None);
+}
+
+/// Makes the items activated on this thread send their events to `event_tx`.
+pub fn set_event_sender<T: 'static>(event_tx: crossbeam_channel::Sender<Event<'static, T>>) {
+  EVENT_SENDER.with(|sender| {
+    *sender.borrow_mut() = Some(Box::new(move |event| {
+      // Only menu events are sent, which don't carry a user event.
+      if let Ok(event) = event.map_nonuser_event() {
+        if let Err(e) = event_tx.send(event) {
+          log::warn!("Failed to send menu event to event channel: {}", e);
+        }
+      }
+    }));
+  });
+}
+
+/// Whether the widgets can be created, menus are left empty without a display.
+fn is_gtk_available() -> bool {
+  gtk::is_initialized_main_thread()
+}
+
+pub struct Menu {
+  menu_bar: Option<gtk::MenuBar>,
+}
+
+impl Menu {
+  pub fn new() -> Self {
+    Self {
+      menu_bar: is_gtk_available().then(gtk::MenuBar::new),
+    }
+  }
+
+  pub fn append_submenu(&self, submenu: &Submenu) {
+    if let (Some(menu_bar), Some((menu_item, _))) = (&self.menu_bar, &submenu.widgets) {
+      if menu_item.parent().is_none() {
+        menu_bar.append(menu_item);
+      }
+    }
+  }
+
+  pub(crate) fn menu_bar(&self) -> Option<&gtk::MenuBar> {
+    self.menu_bar.as_ref()
+  }
+}
+
+const MENU_BAR_KEY: &str = "tao-menu-bar";
+
+/// Packs `menu_bar` at the top of `vbox`, replacing the one packed before.
+pub fn set_for_vbox(vbox: &gtk::Box, menu_bar: Option<gtk::MenuBar>) {
+  if let Some(current) = unsafe { vbox.steal_data::<gtk::MenuBar>(MENU_BAR_KEY) } {
+    vbox.remove(&current);
+  }
+  if let Some(menu_bar) = menu_bar {
+    if let Some(parent) = menu_bar.parent() {
+      // Moved from another window.
+      if let Ok(parent) = parent.downcast::<gtk::Container>() {
+        parent.remove(&menu_bar);
+      }
+    }
+    vbox.pack_start(&menu_bar, false, false, 0);
+    vbox.reorder_child(&menu_bar, 0);
+    menu_bar.show_all();
+    unsafe { vbox.set_data(MENU_BAR_KEY, menu_bar) };
+  }
+}
+
+pub struct Submenu {
+  /// The item shown in the parent and the menu it opens, `None` without a display.
+  widgets: Option<(gtk::MenuItem, gtk::Menu)>,
+}
+
+impl Submenu {
+  pub fn new(title: &str) -> Self {
+    let widgets = is_gtk_available().then(|| {
+      let menu_item = gtk::MenuItem::with_label(title);
+      let menu = gtk::Menu::new();
+      menu_item.set_submenu(Some(&menu));
+      menu_item.show();
+      (menu_item, menu)
+    });
+    Self { widgets }
+  }
+
+  pub fn append_item(&self, item: &MenuItem) {
+    if let (Some((_, menu)), Some(widgets)) = (&self.widgets, &item.widgets) {
+      if widgets.menu_item.parent().is_none() {
+        menu.append(&widgets.menu_item);
+      }
+    }
+  }
+
+  pub fn append_submenu(&self, submenu: &Submenu) {
+    if let (Some((_, menu)), Some((menu_item, _))) = (&self.widgets, &submenu.widgets) {
+      if menu_item.parent().is_none() {
+        menu.append(menu_item);
+      }
+    }
+  }
+
+  pub fn append_separator(&self) {
+    if let Some((_, menu)) = &self.widgets {
+      let separator = gtk::SeparatorMenuItem::new();
+      separator.show();
+      menu.append(&separator);
+    }
+  }
+
+  pub fn set_title(&self, title: &str) {
+    if let Some((menu_item, _)) = &self.widgets {
+      menu_item.set_label(title);
+    }
+  }
+
+  pub fn set_enabled(&self, enabled: bool) {
+    if let Some((menu_item, _)) = &self.widgets {
+      menu_item.set_sensitive(enabled);
+    }
+  }
+
+  /// The menu opened by the submenu, `None` without a display.
+  pub(crate) fn menu(&self) -> Option<&gtk::Menu> {
+    self.widgets.as_ref().map(|(_, menu)| menu)
+  }
+
+  /// Shows the menu at `position`, in logical pixels from the top left of `window`.
+  pub fn pop_up(&self, window: &gtk::ApplicationWindow, position: LogicalPosition<i32>) {
+    let menu = match &self.widgets {
+      Some((_, menu)) => menu,
+      None => return,
+    };
+    let gdk_window = match window.window() {
+      Some(gdk_window) => gdk_window,
+      None => return,
+    };
+    if menu.attach_widget().is_none() {
+      menu.set_attach_widget(Some(window));
+    }
+    menu.show_all();
+    menu.popup_at_rect(
+      &gdk_window,
+      &gdk::Rectangle::new(position.x, position.y, 1, 1),
+      gdk::Gravity::NorthWest,
+      gdk::Gravity::NorthWest,
+      None,
+    );
+  }
+}
+
+struct MenuItemWidgets {
+  menu_item: gtk::MenuItem,
+  /// `None` for the items without a check mark.
+  check_menu_item: Option<gtk::CheckMenuItem>,
+  activate_handler: glib::SignalHandlerId,
+}
+
+pub struct MenuItem {
+  /// `None` without a display.
+  widgets: Option<MenuItemWidgets>,
+}
+
+impl MenuItem {
+  pub fn new(id: u32, title: &str, checked: Option<bool>) -> Self {
+    if !is_gtk_available() {
+      return Self { widgets: None };
+    }
+    let check_menu_item = checked.map(|checked| {
+      let check_menu_item = gtk::CheckMenuItem::with_label(title);
+      check_menu_item.set_active(checked);
+      check_menu_item
+    });
+    let menu_item = match &check_menu_item {
+      Some(check_menu_item) => check_menu_item.clone().upcast(),
+      None => gtk::MenuItem::with_label(title),
+    };
+    let activate_handler = menu_item.connect_activate(move |_| {
+      EVENT_SENDER.with(|sender| {
+        if let Some(sender) = &*sender.borrow() {
+          sender(Event::MenuEvent {
+            menu_id: MenuId(id),
+          });
+        }
+      });
+    });
+    menu_item.show();
+    Self {
+      widgets: Some(MenuItemWidgets {
+        menu_item,
+        check_menu_item,
+        activate_handler,
+      }),
+    }
+  }
+
+  pub fn set_title(&self, title: &str) {
+    if let Some(widgets) = &self.widgets {
+      widgets.menu_item.set_label(title);
+    }
+  }
+
+  pub fn set_enabled(&self, enabled: bool) {
+    if let Some(widgets) = &self.widgets {
+      widgets.menu_item.set_sensitive(enabled);
+    }
+  }
+
+  pub fn set_checked(&self, checked: bool) {
+    if let Some(MenuItemWidgets {
+      check_menu_item: Some(check_menu_item),
+      activate_handler,
+      ..
+    }) = &self.widgets
+    {
+      // `set_active` activates the item.
+      check_menu_item.block_signal(activate_handler);
+      check_menu_item.set_active(checked);
+      check_menu_item.unblock_signal(activate_handler);
+    }
+  }
+
+  pub fn is_checked(&self) -> bool {
+    self
+      .widgets
+      .as_ref()
+      .and_then(|widgets| widgets.check_menu_item.as_ref())
+      .map_or(false, |check_menu_item| check_menu_item.is_active())
+  }
+}
@@ -10,12 +10,23 @@
   target_os = "openbsd"
 ))]
 
+mod appearance;
+mod clipboard;
 mod device;
+mod embed;
 mod event_loop;
+mod global_shortcut;
 mod icon;
+mod inject;
 mod keyboard;
 mod keycode;
+mod menu;
 mod monitor;
+mod notification;
+mod power;
+mod session;
+mod system_tray;
+mod timer;
 mod util;
 mod window;
 
@@ -23,13 +34,18 @@ pub mod taskbar;
 pub mod x11;
 
 pub use self::keycode::{keycode_from_scancode, keycode_to_scancode};
+pub use clipboard::Clipboard;
 pub(crate) use event_loop::PlatformSpecificEventLoopAttributes;
-pub use event_loop::{EventLoop, EventLoopProxy, EventLoopWindowTarget};
+pub use event_loop::{EventLoop, EventLoopProxy, EventLoopWaker, EventLoopWindowTarget};
 pub use icon::PlatformIcon;
+pub use menu::{Menu, MenuItem, Submenu};
 pub use monitor::{MonitorHandle, VideoMode};
-pub use window::{Window, WindowId};
+pub use notification::Notification;
+pub use power::PowerAssertion;
+pub use system_tray::SystemTray;
+pub use window::{AccessibilityActionSender, Window, WindowId};
 
-use crate::{event::DeviceId as RootDeviceId, keyboard::Key};
+use crate::{event::DeviceId as RootDeviceId, keyboard::Key, platform::unix::EmbedParent};
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct KeyEventExtra {
@@ -42,6 +58,8 @@ pub struct KeyEventExtra {
 pub enum Parent {
   None,
   ChildOf(gtk::Window),
+  /// A window of another application, see `WindowBuilderExtUnix::with_embed_parent`.
+  EmbedIn(EmbedParent),
 }
 
 impl Default for Parent {
@@ -53,26 +71,28 @@ impl Default for Parent {
 #[derive(Clone)]
 pub struct PlatformSpecificWindowBuilderAttributes {
   pub parent: Parent,
-  pub skip_taskbar: bool,
   pub auto_transparent: bool,
   pub double_buffered: bool,
   pub app_paintable: bool,
   pub rgba_visual: bool,
   pub cursor_moved: bool,
   pub default_vbox: bool,
+  pub app_id: Option<String>,
+  pub x11_instance: Option<String>,
 }
 
 impl Default for PlatformSpecificWindowBuilderAttributes {
   fn default() -> Self {
     Self {
       parent: Default::default(),
-      skip_taskbar: Default::default(),
       auto_transparent: true,
       double_buffered: true,
       app_paintable: false,
       rgba_visual: false,
       cursor_moved: true,
       default_vbox: true,
+      app_id: None,
+      x11_instance: None,
     }
   }
 }
@@ -96,7 +116,11 @@ impl DeviceId {
   pub unsafe fn dummy() -> Self {
     Self(0)
   }
+
+  pub fn seat_name(&self) -> Option<String> {
+    device::seat_name(self.0)
+  }
 }
 
-// FIXME: currently we use a dummy device id, find if we can get device id from gtk
+/// The device id of the input without a seat, like the raw device events.
 pub(crate) const DEVICE_ID: RootDeviceId = RootDeviceId(DeviceId(0));
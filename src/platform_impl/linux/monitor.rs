@@ -2,14 +2,27 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use gtk::gdk::{self, prelude::MonitorExt, Display};
+use std::{ffi::c_void, slice};
 
+use gtk::{
+  gdk::{
+    self,
+    prelude::{MonitorExt, WindowExtManual},
+    Display,
+  },
+  glib::translate::ToGlibPtr,
+};
+use x11_dl::{xlib, xrandr};
+
+use super::util;
 use crate::{
   dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize},
+  error::{ExternalError, NotSupportedError},
   monitor::{MonitorHandle as RootMonitorHandle, VideoMode as RootVideoMode},
+  window::RgbaImage,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct MonitorHandle {
   pub(crate) monitor: gdk::Monitor,
 }
@@ -20,9 +33,38 @@ impl MonitorHandle {
     Self { monitor }
   }
 
+  /// The `wl_output` of the monitor, `None` on X11.
+  pub fn wl_output(&self) -> Option<*mut c_void> {
+    if !util::is_wayland(&self.monitor.display()?) {
+      return None;
+    }
+    let monitor: *mut gdk::ffi::GdkMonitor = self.monitor.to_glib_none().0;
+    let output = unsafe { gdk_wayland_sys::gdk_wayland_monitor_get_wl_output(monitor as *mut _) };
+    (!output.is_null()).then(|| output)
+  }
+
   #[inline]
   pub fn name(&self) -> Option<String> {
-    self.monitor.model().map(|s| s.as_str().to_string())
+    let model = self.monitor.model()?;
+    match self.monitor.manufacturer() {
+      Some(manufacturer) if !model.starts_with(manufacturer.as_str()) => {
+        Some(format!("{} {}", manufacturer, model))
+      }
+      _ => Some(model.to_string()),
+    }
+  }
+
+  #[inline]
+  pub fn id(&self) -> u64 {
+    // GDK doesn't expose the EDID serial, so identical displays share an id.
+    let manufacturer = self.monitor.manufacturer();
+    let model = self.monitor.model();
+    let key = format!(
+      "{}\0{}",
+      manufacturer.as_deref().unwrap_or(""),
+      model.as_deref().unwrap_or("")
+    );
+    crate::monitor::stable_id(key.as_bytes())
   }
 
   #[inline]
@@ -50,9 +92,37 @@ impl MonitorHandle {
     self.monitor.scale_factor() as f64
   }
 
+  #[inline]
+  pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+    // 0 when GDK doesn't know it, it's updated with the mode of the output.
+    let refresh_rate = self.monitor.refresh_rate();
+    (refresh_rate > 0).then(|| refresh_rate as u32)
+  }
+
+  pub fn capture(&self) -> Result<RgbaImage, ExternalError> {
+    let display = self
+      .monitor
+      .display()
+      .ok_or(ExternalError::Os(os_error!(super::OsError)))?;
+    if util::is_wayland(&display) {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
+    let rect = self.monitor.geometry();
+    display
+      .default_screen()
+      .root_window()
+      .and_then(|root| root.pixbuf(rect.x(), rect.y(), rect.width(), rect.height()))
+      .and_then(|pixbuf| pixbuf.add_alpha(false, 0, 0, 0).ok())
+      .map(|pixbuf| util::pixbuf_to_rgba(&pixbuf))
+      .ok_or(ExternalError::Os(os_error!(super::OsError)))
+  }
+
   #[inline]
   pub fn video_modes(&self) -> Box<dyn Iterator<Item = RootVideoMode>> {
-    Box::new(Vec::new().into_iter())
+    let modes = unsafe { Crtc::of(self) }
+      .map(|crtc| unsafe { crtc.video_modes(self) })
+      .unwrap_or_default();
+    Box::new(modes.into_iter())
   }
 }
 
@@ -60,27 +130,228 @@ unsafe impl Send for MonitorHandle {}
 unsafe impl Sync for MonitorHandle {}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct VideoMode;
+pub struct VideoMode {
+  pub(crate) size: (u32, u32),
+  pub(crate) bit_depth: u16,
+  pub(crate) refresh_rate: u16,
+  pub(crate) monitor: MonitorHandle,
+  /// The RandR mode.
+  pub(crate) native_mode: xrandr::RRMode,
+}
 
 impl VideoMode {
   #[inline]
   pub fn size(&self) -> PhysicalSize<u32> {
-    panic!("VideoMode is unsupported on Linux.")
+    self.size.into()
   }
 
   #[inline]
   pub fn bit_depth(&self) -> u16 {
-    panic!("VideoMode is unsupported on Linux.")
+    self.bit_depth
   }
 
   #[inline]
   pub fn refresh_rate(&self) -> u16 {
-    panic!("VideoMode is unsupported on Linux.")
+    self.refresh_rate
   }
 
   #[inline]
   pub fn monitor(&self) -> RootMonitorHandle {
-    panic!("VideoMode is unsupported on Linux.")
+    RootMonitorHandle {
+      inner: self.monitor.clone(),
+    }
+  }
+}
+
+/// The mode of a CRTC before a window switched it, see `set_video_mode`.
+pub struct SavedMode {
+  crtc: xrandr::RRCrtc,
+  mode: xrandr::RRMode,
+}
+
+/// `RR_Interlace` and `RR_DoubleScan`.
+const RR_INTERLACE: u64 = 0x10;
+const RR_DOUBLE_SCAN: u64 = 0x20;
+
+/// The RandR CRTC scanning out a monitor of an X11 display, found by its geometry since GDK
+/// doesn't expose it.
+struct Crtc {
+  xlib: xlib::Xlib,
+  xrandr: xrandr::Xrandr_2_2_0,
+  xdisplay: *mut xlib::Display,
+  resources: *mut xrandr::XRRScreenResources,
+  crtc: xrandr::RRCrtc,
+  info: *mut xrandr::XRRCrtcInfo,
+}
+
+impl Crtc {
+  unsafe fn of(monitor: &MonitorHandle) -> Option<Self> {
+    let display = monitor.monitor.display()?;
+    if !util::is_x11(&display) {
+      return None;
+    }
+    let xlib = xlib::Xlib::open().ok()?;
+    let xrandr = xrandr::Xrandr_2_2_0::open().ok()?;
+    let display: *mut gdk::ffi::GdkDisplay = display.to_glib_none().0;
+    let xdisplay = gdk_x11_sys::gdk_x11_display_get_xdisplay(display as *mut _) as *mut _;
+    let root = (xlib.XDefaultRootWindow)(xdisplay);
+    let resources = (xrandr.XRRGetScreenResourcesCurrent)(xdisplay, root);
+    if resources.is_null() {
+      return None;
+    }
+    let position = monitor.position();
+    let size = monitor.size();
+    let crtcs = slice::from_raw_parts((*resources).crtcs, (*resources).ncrtc as usize);
+    for &crtc in crtcs {
+      let info = (xrandr.XRRGetCrtcInfo)(xdisplay, resources, crtc);
+      if info.is_null() {
+        continue;
+      }
+      if (*info).mode != 0
+        && ((*info).x, (*info).y) == (position.x, position.y)
+        && ((*info).width, (*info).height) == (size.width, size.height)
+      {
+        return Some(Self {
+          xlib,
+          xrandr,
+          xdisplay,
+          resources,
+          crtc,
+          info,
+        });
+      }
+      (xrandr.XRRFreeCrtcInfo)(info);
+    }
+    (xrandr.XRRFreeScreenResources)(resources);
+    None
+  }
+
+  /// The modes of the first output of the CRTC, which the others showing the same picture share.
+  unsafe fn video_modes(&self, monitor: &MonitorHandle) -> Vec<RootVideoMode> {
+    if (*self.info).noutput == 0 {
+      return Vec::new();
+    }
+    let output =
+      (self.xrandr.XRRGetOutputInfo)(self.xdisplay, self.resources, *(*self.info).outputs);
+    if output.is_null() {
+      return Vec::new();
+    }
+    let bit_depth =
+      (self.xlib.XDefaultDepth)(self.xdisplay, (self.xlib.XDefaultScreen)(self.xdisplay));
+    let mode_infos =
+      slice::from_raw_parts((*self.resources).modes, (*self.resources).nmode as usize);
+    let modes = slice::from_raw_parts((*output).modes, (*output).nmode as usize)
+      .iter()
+      .filter_map(|mode| mode_infos.iter().find(|info| info.id == *mode))
+      .map(|info| RootVideoMode {
+        video_mode: VideoMode {
+          size: (info.width, info.height),
+          bit_depth: bit_depth as u16,
+          refresh_rate: refresh_rate(info),
+          monitor: monitor.clone(),
+          native_mode: info.id,
+        },
+      })
+      .collect();
+    (self.xrandr.XRRFreeOutputInfo)(output);
+    modes
+  }
+
+  /// Switches the CRTC to `mode`, keeping its position, rotation and outputs.
+  unsafe fn set_mode(&self, mode: xrandr::RRMode) -> bool {
+    let info = &*self.info;
+    let status = (self.xrandr.XRRSetCrtcConfig)(
+      self.xdisplay,
+      self.resources,
+      self.crtc,
+      xlib::CurrentTime,
+      info.x,
+      info.y,
+      mode,
+      info.rotation,
+      info.outputs,
+      info.noutput,
+    );
+    (self.xlib.XFlush)(self.xdisplay);
+    status == 0
+  }
+}
+
+impl Drop for Crtc {
+  fn drop(&mut self) {
+    unsafe {
+      (self.xrandr.XRRFreeCrtcInfo)(self.info);
+      (self.xrandr.XRRFreeScreenResources)(self.resources);
+    }
+  }
+}
+
+/// The refresh rate of a mode, rounded to the nearest integer.
+fn refresh_rate(info: &xrandr::XRRModeInfo) -> u16 {
+  let mut v_total = f64::from(info.vTotal);
+  if info.modeFlags as u64 & RR_DOUBLE_SCAN != 0 {
+    v_total *= 2.0;
+  }
+  if info.modeFlags as u64 & RR_INTERLACE != 0 {
+    v_total /= 2.0;
+  }
+  if info.hTotal == 0 || v_total == 0.0 {
+    return 0;
+  }
+  (info.dotClock as f64 / (f64::from(info.hTotal) * v_total)).round() as u16
+}
+
+/// Switches the monitor of `video_mode` to it, returning the mode to restore with
+/// `restore_video_mode`. `None` if the monitor isn't one of an X11 display or RandR refused.
+pub fn set_video_mode(video_mode: &VideoMode) -> Option<SavedMode> {
+  unsafe {
+    let crtc = Crtc::of(&video_mode.monitor)?;
+    let saved = SavedMode {
+      crtc: crtc.crtc,
+      mode: (*crtc.info).mode,
+    };
+    if crtc.set_mode(video_mode.native_mode) {
+      Some(saved)
+    } else {
+      log::warn!("Failed to switch the video mode of the monitor");
+      None
+    }
+  }
+}
+
+/// Switches a CRTC back to the mode it had before `set_video_mode`.
+pub fn restore_video_mode(display: &Display, saved: SavedMode) {
+  if !util::is_x11(display) {
+    return;
+  }
+  let (xlib, xrandr) = match (xlib::Xlib::open(), xrandr::Xrandr_2_2_0::open()) {
+    (Ok(xlib), Ok(xrandr)) => (xlib, xrandr),
+    _ => return,
+  };
+  unsafe {
+    let display: *mut gdk::ffi::GdkDisplay = display.to_glib_none().0;
+    let xdisplay = gdk_x11_sys::gdk_x11_display_get_xdisplay(display as *mut _) as *mut _;
+    let resources =
+      (xrandr.XRRGetScreenResourcesCurrent)(xdisplay, (xlib.XDefaultRootWindow)(xdisplay));
+    if resources.is_null() {
+      return;
+    }
+    let info = (xrandr.XRRGetCrtcInfo)(xdisplay, resources, saved.crtc);
+    if !info.is_null() {
+      let crtc = Crtc {
+        xlib,
+        xrandr,
+        xdisplay,
+        resources,
+        crtc: saved.crtc,
+        info,
+      };
+      if !crtc.set_mode(saved.mode) {
+        log::warn!("Failed to restore the video mode of the monitor");
+      }
+    } else {
+      (xrandr.XRRFreeScreenResources)(resources);
+    }
   }
 }
 
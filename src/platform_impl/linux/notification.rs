@@ -0,0 +1,332 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  collections::HashMap,
+  sync::{
+    mpsc::{self, Receiver, Sender},
+    Arc, Mutex, PoisonError,
+  },
+  thread::{self, JoinHandle},
+};
+
+use gtk::glib;
+use zbus::{
+  blocking::{Connection, MessageIterator},
+  zvariant::{Structure, Value},
+  MatchRule, MessageType,
+};
+
+use super::EventLoopWindowTarget;
+use crate::{
+  error::ExternalError,
+  event::Event,
+  notification::{NotificationAttributes, NotificationId},
+};
+
+const NOTIFICATIONS: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+/// The key of the action invoked by a click on the notification itself.
+const DEFAULT_ACTION: &str = "default";
+/// The member of the signal that wakes the signal thread up to stop, see `SignalThread::stop`.
+const STOP_MEMBER: &str = "TaoStop";
+
+/// The ids of the notifications of an event loop by the id the server gave them.
+type Ids = Arc<Mutex<HashMap<u32, u32>>>;
+
+/// A call to the notification server, made on the notification thread.
+enum Request {
+  Notify {
+    id: u32,
+    app_name: String,
+    attributes: NotifyAttributes,
+  },
+  Close(u32),
+  /// The event loop is gone, so the signals aren't read anymore.
+  Stop,
+}
+
+/// The content of a notification, without its icon which isn't `Send`.
+struct NotifyAttributes {
+  title: String,
+  body: String,
+  actions: Vec<(String, String)>,
+  image_data: Option<(i32, i32, i32, bool, i32, i32, Vec<u8>)>,
+}
+
+/// The notification thread of an event loop, started with its first notification. It connects
+/// to the notification server and makes the calls, so that they don't block the GTK thread.
+pub(crate) struct NotificationServer {
+  request_tx: Sender<Request>,
+}
+
+impl NotificationServer {
+  fn spawn<T: 'static>(window_target: &EventLoopWindowTarget<T>) -> Self {
+    // The signals are read on another thread.
+    let (action_tx, action_rx) = glib::MainContext::channel(glib::Priority::default());
+    let event_tx = window_target.event_tx.clone();
+    action_rx.attach(
+      Some(&glib::MainContext::default()),
+      move |(id, action): (u32, Option<String>)| {
+        if let Err(e) = event_tx.send(Event::NotificationAction {
+          id: NotificationId(id),
+          action,
+        }) {
+          log::warn!("Failed to send notification event to event channel: {}", e);
+        }
+        glib::ControlFlow::Continue
+      },
+    );
+
+    let (request_tx, request_rx) = mpsc::channel();
+    thread::Builder::new()
+      .name("tao-notifications".into())
+      .spawn(move || run(request_rx, action_tx))
+      .expect("Failed to spawn the notification thread");
+    Self { request_tx }
+  }
+}
+
+impl Drop for NotificationServer {
+  fn drop(&mut self) {
+    let _ = self.request_tx.send(Request::Stop);
+  }
+}
+
+/// Runs the notification thread until the event loop and its notifications are gone.
+fn run(request_rx: Receiver<Request>, action_tx: glib::Sender<(u32, Option<String>)>) {
+  let conn = match Connection::session() {
+    Ok(conn) => conn,
+    Err(e) => {
+      log::warn!("Failed to connect to the notification server: {}", e);
+      return;
+    }
+  };
+  let ids = Ids::default();
+  // Started before the first call, so the signals of the first notification aren't missed.
+  let mut signal_thread = match SignalThread::spawn(&conn, ids.clone(), action_tx) {
+    Ok(signal_thread) => Some(signal_thread),
+    Err(e) => {
+      log::warn!("Failed to listen to the notification server: {}", e);
+      None
+    }
+  };
+
+  for request in request_rx {
+    match request {
+      Request::Notify {
+        id,
+        app_name,
+        attributes,
+      } => {
+        // Holds the lock until the id is known, for the signals read in the meantime.
+        let mut ids = ids.lock().unwrap_or_else(PoisonError::into_inner);
+        match notify(&conn, app_name, attributes) {
+          Ok(server_id) => {
+            ids.insert(server_id, id);
+          }
+          Err(e) => log::warn!("Failed to show the notification: {}", e),
+        }
+      }
+      Request::Close(id) => {
+        let server_id = ids
+          .lock()
+          .unwrap_or_else(PoisonError::into_inner)
+          .iter()
+          .find_map(|(server_id, notification_id)| (*notification_id == id).then(|| *server_id));
+        // Not shown, or closed already.
+        let server_id = match server_id {
+          Some(server_id) => server_id,
+          None => continue,
+        };
+        if let Err(e) = conn.call_method(
+          Some(NOTIFICATIONS),
+          NOTIFICATIONS_PATH,
+          Some(NOTIFICATIONS),
+          "CloseNotification",
+          &server_id,
+        ) {
+          log::warn!("Failed to close the notification: {}", e);
+        }
+      }
+      Request::Stop => {
+        if let Some(signal_thread) = signal_thread.take() {
+          signal_thread.stop(&conn);
+        }
+      }
+    }
+  }
+}
+
+fn notify(conn: &Connection, app_name: String, attributes: NotifyAttributes) -> zbus::Result<u32> {
+  // The actions are pairs of keys and labels, the default one makes the notification clickable.
+  let mut actions = vec![DEFAULT_ACTION, ""];
+  for (id, label) in &attributes.actions {
+    actions.extend([id.as_str(), label.as_str()]);
+  }
+  let mut hints = HashMap::new();
+  if let Some(image_data) = attributes.image_data {
+    hints.insert("image-data", Value::from(Structure::from(image_data)));
+  }
+  conn
+    .call_method(
+      Some(NOTIFICATIONS),
+      NOTIFICATIONS_PATH,
+      Some(NOTIFICATIONS),
+      "Notify",
+      &(
+        app_name,
+        0u32,
+        "",
+        attributes.title,
+        attributes.body,
+        actions,
+        hints,
+        -1i32,
+      ),
+    )
+    .and_then(|reply| reply.body())
+}
+
+/// A signal of the notification server, for a notification of any application.
+enum Signal {
+  Action(u32, String),
+  Closed(u32),
+}
+
+/// The thread reading the `ActionInvoked` and `NotificationClosed` signals of the notification
+/// server, until `SignalThread::stop` or until the receiver of its `action_tx` is gone.
+struct SignalThread {
+  stop_tx: Sender<()>,
+  handle: JoinHandle<()>,
+}
+
+impl SignalThread {
+  fn spawn(
+    conn: &Connection,
+    ids: Ids,
+    action_tx: glib::Sender<(u32, Option<String>)>,
+  ) -> zbus::Result<Self> {
+    let rule = MatchRule::builder()
+      .msg_type(MessageType::Signal)
+      .interface(NOTIFICATIONS)
+      .map(|rule| rule.build())?;
+    let messages = MessageIterator::for_match_rule(rule, conn, None)?;
+    let (stop_tx, stop_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+      for message in messages.flatten() {
+        if stop_rx.try_recv().is_ok() {
+          break;
+        }
+        let signal = match message.member().as_deref() {
+          Some("ActionInvoked") => message
+            .body::<(u32, String)>()
+            .ok()
+            .map(|(server_id, action)| Signal::Action(server_id, action)),
+          Some("NotificationClosed") => message
+            .body::<(u32, u32)>()
+            .ok()
+            .map(|(server_id, _)| Signal::Closed(server_id)),
+          _ => None,
+        };
+        match signal {
+          Some(Signal::Action(server_id, action)) => {
+            let id = match ids
+              .lock()
+              .unwrap_or_else(PoisonError::into_inner)
+              .get(&server_id)
+            {
+              Some(id) => *id,
+              None => continue,
+            };
+            let action = (action != DEFAULT_ACTION).then(|| action);
+            if action_tx.send((id, action)).is_err() {
+              break;
+            }
+          }
+          Some(Signal::Closed(server_id)) => {
+            ids
+              .lock()
+              .unwrap_or_else(PoisonError::into_inner)
+              .remove(&server_id);
+          }
+          None => {}
+        }
+      }
+    });
+    Ok(Self { stop_tx, handle })
+  }
+
+  /// Stops the thread and waits for it to finish.
+  fn stop(self, conn: &Connection) {
+    let _ = self.stop_tx.send(());
+    // The thread is blocked until the next signal, so one that matches its rule is sent to this
+    // connection itself.
+    let wake = conn.unique_name().map(|name| {
+      conn.emit_signal(
+        Some(name.to_owned()),
+        NOTIFICATIONS_PATH,
+        NOTIFICATIONS,
+        STOP_MEMBER,
+        &(),
+      )
+    });
+    match wake {
+      Some(Ok(())) => {
+        if self.handle.join().is_err() {
+          log::warn!("The notification signal thread panicked");
+        }
+      }
+      Some(Err(e)) => log::warn!("Failed to stop the notification signal thread: {}", e),
+      None => log::warn!("Failed to stop the notification signal thread: not connected to the bus"),
+    }
+  }
+}
+
+/// A notification shown with `org.freedesktop.Notifications`.
+pub struct Notification {
+  request_tx: Sender<Request>,
+  id: u32,
+}
+
+impl Notification {
+  pub fn new<T: 'static>(
+    window_target: &EventLoopWindowTarget<T>,
+    id: u32,
+    attributes: NotificationAttributes,
+  ) -> Result<Self, ExternalError> {
+    let request_tx = window_target
+      .notification_server
+      .borrow_mut()
+      .get_or_insert_with(|| NotificationServer::spawn(window_target))
+      .request_tx
+      .clone();
+
+    let app_name = window_target
+      .default_app_id
+      .clone()
+      .or_else(|| glib::application_name().map(Into::into))
+      .unwrap_or_default();
+    let attributes = NotifyAttributes {
+      title: attributes.title,
+      body: attributes.body.unwrap_or_default(),
+      actions: attributes
+        .actions
+        .into_iter()
+        .map(|action| (action.id, action.label))
+        .collect(),
+      image_data: attributes.icon.map(|icon| icon.inner.to_image_data()),
+    };
+    // The thread is only gone if it panicked.
+    let _ = request_tx.send(Request::Notify {
+      id,
+      app_name,
+      attributes,
+    });
+    Ok(Self { request_tx, id })
+  }
+
+  pub fn close(&self) {
+    let _ = self.request_tx.send(Request::Close(self.id));
+  }
+}
@@ -0,0 +1,273 @@
+use std::{
+  collections::HashMap,
+  sync::mpsc::{self, Sender},
+  thread::JoinHandle,
+};
+
+use gtk::{
+  glib::{self, thread_guard::ThreadGuard},
+  prelude::*,
+};
+use zbus::{
+  blocking::{Connection, MessageIterator},
+  zvariant::OwnedValue,
+  MatchRule, MessageType,
+};
+
+use super::{EventLoopWindowTarget, OsError};
+use crate::{error::ExternalError, event_loop::PowerInfo};
+
+const UPOWER: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+const UPOWER_DEVICE: &str = "org.freedesktop.UPower.Device";
+/// The composite device of UPower, which sums up all the batteries powering the system.
+const DISPLAY_DEVICE_PATH: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+const POWER_PROFILES: &str = "net.hadess.PowerProfiles";
+const POWER_PROFILES_PATH: &str = "/net/hadess/PowerProfiles";
+const SCREEN_SAVER: &str = "org.freedesktop.ScreenSaver";
+const SCREEN_SAVER_PATH: &str = "/org/freedesktop/ScreenSaver";
+
+/// Reads the power information from UPower and power-profiles-daemon, fields of services that
+/// aren't running are left unknown.
+pub fn power_info(conn: Option<&Connection>) -> PowerInfo {
+  let conn = match conn {
+    Some(conn) => conn,
+    None => return PowerInfo::default(),
+  };
+  let has_battery = property(
+    conn,
+    UPOWER,
+    DISPLAY_DEVICE_PATH,
+    UPOWER_DEVICE,
+    "IsPresent",
+  )
+  .unwrap_or(false);
+  PowerInfo {
+    on_battery: property(conn, UPOWER, UPOWER_PATH, UPOWER, "OnBattery"),
+    percent: if has_battery {
+      property::<f64>(
+        conn,
+        UPOWER,
+        DISPLAY_DEVICE_PATH,
+        UPOWER_DEVICE,
+        "Percentage",
+      )
+      .map(|percent| percent.round().clamp(0.0, 100.0) as u8)
+    } else {
+      None
+    },
+    power_saver: property::<String>(
+      conn,
+      POWER_PROFILES,
+      POWER_PROFILES_PATH,
+      POWER_PROFILES,
+      "ActiveProfile",
+    )
+    .map_or(false, |profile| profile == "power-saver"),
+  }
+}
+
+pub(super) fn property<T: TryFrom<OwnedValue>>(
+  conn: &Connection,
+  destination: &str,
+  path: &str,
+  interface: &str,
+  name: &str,
+) -> Option<T> {
+  let reply = conn
+    .call_method(
+      Some(destination),
+      path,
+      Some("org.freedesktop.DBus.Properties"),
+      "Get",
+      &(interface, name),
+    )
+    .ok()?;
+  let value: OwnedValue = reply.body().ok()?;
+  T::try_from(value).ok()
+}
+
+/// The interface of the signal that wakes the power thread up to stop, see `PowerThread::stop`.
+const STOP_INTERFACE: &str = "app.tao.PowerThread.Stop";
+
+/// The thread sending the power information, see `spawn`.
+pub struct PowerThread {
+  conn: Connection,
+  stop_tx: Sender<()>,
+  handle: JoinHandle<()>,
+}
+
+impl PowerThread {
+  /// Stops the thread and waits for it to finish.
+  pub fn stop(self) {
+    let _ = self.stop_tx.send(());
+    // The thread is blocked until the next signal, so one that matches its rule is sent to this
+    // connection itself.
+    let wake = self.conn.unique_name().map(|name| {
+      self.conn.emit_signal(
+        Some(name.to_owned()),
+        "/",
+        "org.freedesktop.DBus.Properties",
+        "PropertiesChanged",
+        &(
+          STOP_INTERFACE,
+          HashMap::<String, OwnedValue>::new(),
+          Vec::<String>::new(),
+        ),
+      )
+    });
+    match wake {
+      Some(Ok(())) => {
+        if self.handle.join().is_err() {
+          log::warn!("The power thread panicked");
+        }
+      }
+      Some(Err(e)) => log::warn!("Failed to stop the power thread: {}", e),
+      None => log::warn!("Failed to stop the power thread: not connected to the bus"),
+    }
+  }
+}
+
+/// Spawns the thread sending the power information whenever the power source or the power
+/// profile changes, until `PowerThread::stop` or until the receiver of `power_tx` is gone.
+pub fn spawn(conn: Connection, power_tx: glib::Sender<PowerInfo>) -> PowerThread {
+  let (stop_tx, stop_rx) = mpsc::channel();
+  let thread_conn = conn.clone();
+  let handle = std::thread::spawn(move || {
+    let conn = thread_conn;
+    // UPower and power-profiles-daemon are different senders, a single rule can't match both.
+    let rule = MatchRule::builder()
+      .msg_type(MessageType::Signal)
+      .interface("org.freedesktop.DBus.Properties")
+      .and_then(|rule| rule.member("PropertiesChanged"))
+      .map(|rule| rule.build());
+    let messages = match rule.and_then(|rule| MessageIterator::for_match_rule(rule, &conn, None)) {
+      Ok(messages) => messages,
+      Err(e) => {
+        log::warn!("Failed to listen to power changes: {}", e);
+        return;
+      }
+    };
+
+    let mut last = power_info(Some(&conn));
+    for message in messages.flatten() {
+      if stop_rx.try_recv().is_ok() {
+        break;
+      }
+      let changed = message
+        .body::<(String, HashMap<String, OwnedValue>, Vec<String>)>()
+        .map_or(false, |(interface, _, _)| {
+          matches!(interface.as_str(), UPOWER | UPOWER_DEVICE | POWER_PROFILES)
+        });
+      if !changed {
+        continue;
+      }
+      let info = power_info(Some(&conn));
+      // The battery charge alone isn't worth an event.
+      if (info.on_battery != last.on_battery || info.power_saver != last.power_saver)
+        && power_tx.send(info).is_err()
+      {
+        break;
+      }
+      last = info;
+    }
+  });
+  PowerThread {
+    conn,
+    stop_tx,
+    handle,
+  }
+}
+
+/// An inhibition of the idle session, through GTK or, without a session manager or portal GTK
+/// knows, `org.freedesktop.ScreenSaver`.
+///
+/// Both are released from the thread that took them, so that the assertion can be dropped from
+/// any thread.
+pub enum PowerAssertion {
+  /// Uninhibited on the GTK thread, `None` once dropped.
+  Gtk(Option<ThreadGuard<gtk::Application>>, u32),
+  /// The thread holding the D-Bus inhibition until it's told to release it.
+  ScreenSaver(Sender<()>),
+}
+
+impl PowerAssertion {
+  pub fn keep_display_awake<T: 'static>(
+    window_target: &EventLoopWindowTarget<T>,
+    reason: &str,
+  ) -> Result<Self, ExternalError> {
+    let app = &window_target.app;
+    let cookie = app.inhibit(
+      None::<&gtk::Window>,
+      gtk::ApplicationInhibitFlags::IDLE,
+      Some(reason),
+    );
+    if cookie != 0 {
+      return Ok(PowerAssertion::Gtk(
+        Some(ThreadGuard::new(app.clone())),
+        cookie,
+      ));
+    }
+
+    // The D-Bus calls would block the GTK thread, they are made on a thread that holds the
+    // inhibition, which lasts as long as its connection.
+    let app_name = glib::prgname().map_or_else(String::new, Into::into);
+    let reason = reason.to_owned();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    std::thread::Builder::new()
+      .name("tao-screen-saver-inhibit".into())
+      .spawn(move || {
+        let inhibited = Connection::session().and_then(|conn| {
+          let cookie: u32 = conn
+            .call_method(
+              Some(SCREEN_SAVER),
+              SCREEN_SAVER_PATH,
+              Some(SCREEN_SAVER),
+              "Inhibit",
+              &(app_name.as_str(), reason.as_str()),
+            )?
+            .body()?;
+          Ok((conn, cookie))
+        });
+        let (conn, cookie) = match inhibited {
+          Ok(inhibited) => inhibited,
+          Err(e) => {
+            log::warn!("Failed to inhibit the screen saver: {}", e);
+            return;
+          }
+        };
+        // Returns once the assertion is dropped, even before the inhibition was taken.
+        let _ = release_rx.recv();
+        let _ = conn.call_method(
+          Some(SCREEN_SAVER),
+          SCREEN_SAVER_PATH,
+          Some(SCREEN_SAVER),
+          "UnInhibit",
+          &(cookie,),
+        );
+      })
+      .map(|_| PowerAssertion::ScreenSaver(release_tx))
+      .map_err(|e| {
+        log::warn!("Failed to inhibit the screen saver: {}", e);
+        ExternalError::Os(os_error!(OsError))
+      })
+  }
+}
+
+impl Drop for PowerAssertion {
+  fn drop(&mut self) {
+    match self {
+      PowerAssertion::Gtk(app, cookie) => {
+        let (app, cookie) = (app.take(), *cookie);
+        glib::MainContext::default().invoke(move || {
+          if let Some(app) = app {
+            app.get_ref().uninhibit(cookie);
+          }
+        });
+      }
+      PowerAssertion::ScreenSaver(release_tx) => {
+        let _ = release_tx.send(());
+      }
+    }
+  }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use gtk::glib;
+use zbus::{
+  blocking::{Connection, MessageIterator},
+  zvariant::{OwnedObjectPath, OwnedValue},
+  MatchRule, MessageType,
+};
+
+use super::power::property;
+use crate::event::SessionChange;
+
+const LOGIND: &str = "org.freedesktop.login1";
+const LOGIND_PATH: &str = "/org/freedesktop/login1";
+const LOGIND_MANAGER: &str = "org.freedesktop.login1.Manager";
+const LOGIND_SESSION: &str = "org.freedesktop.login1.Session";
+
+/// Returns the path of the logind session of the process, or of the display session of the user
+/// when the process isn't part of one, e.g. when started by a user service.
+fn session_path(conn: &Connection) -> Option<OwnedObjectPath> {
+  conn
+    .call_method(
+      Some(LOGIND),
+      LOGIND_PATH,
+      Some(LOGIND_MANAGER),
+      "GetSessionByPID",
+      &std::process::id(),
+    )
+    .or_else(|_| {
+      conn.call_method(
+        Some(LOGIND),
+        LOGIND_PATH,
+        Some(LOGIND_MANAGER),
+        "GetSession",
+        &"auto",
+      )
+    })
+    .and_then(|reply| reply.body::<OwnedObjectPath>())
+    .ok()
+}
+
+/// Whether the session is locked and whether it's on the console, unknown properties being
+/// `false`.
+fn session_state(conn: &Connection, path: &str) -> (bool, bool) {
+  (
+    property(conn, LOGIND, path, LOGIND_SESSION, "LockedHint").unwrap_or(false),
+    property(conn, LOGIND, path, LOGIND_SESSION, "Active").unwrap_or(false),
+  )
+}
+
+/// Spawns the thread sending the changes of the `LockedHint` and `Active` properties of the logind
+/// session. It stops at the next change once the receiver of `session_tx` is gone.
+pub fn spawn(conn: Connection, session_tx: glib::Sender<SessionChange>) {
+  std::thread::spawn(move || {
+    let path = match session_path(&conn) {
+      Some(path) => path,
+      None => {
+        log::warn!("Failed to find the logind session of the process");
+        return;
+      }
+    };
+    let rule = MatchRule::builder()
+      .msg_type(MessageType::Signal)
+      .sender(LOGIND)
+      .and_then(|rule| rule.path(path.as_str()))
+      .and_then(|rule| rule.interface("org.freedesktop.DBus.Properties"))
+      .and_then(|rule| rule.member("PropertiesChanged"))
+      .map(|rule| rule.build());
+    let messages = match rule.and_then(|rule| MessageIterator::for_match_rule(rule, &conn, None)) {
+      Ok(messages) => messages,
+      Err(e) => {
+        log::warn!("Failed to listen to session changes: {}", e);
+        return;
+      }
+    };
+
+    let (mut locked, mut active) = session_state(&conn, path.as_str());
+    for message in messages.flatten() {
+      let changed = message
+        .body::<(String, HashMap<String, OwnedValue>, Vec<String>)>()
+        .map_or(false, |(interface, _, _)| interface == LOGIND_SESSION);
+      if !changed {
+        continue;
+      }
+      let (now_locked, now_active) = session_state(&conn, path.as_str());
+      let mut changes = Vec::new();
+      if now_active != active {
+        changes.push(if now_active {
+          SessionChange::SwitchedBack
+        } else {
+          SessionChange::SwitchedAway
+        });
+      }
+      if now_locked != locked {
+        changes.push(if now_locked {
+          SessionChange::Locked
+        } else {
+          SessionChange::Unlocked
+        });
+      }
+      if changes
+        .into_iter()
+        .any(|change| session_tx.send(change).is_err())
+      {
+        break;
+      }
+      locked = now_locked;
+      active = now_active;
+    }
+  });
+}
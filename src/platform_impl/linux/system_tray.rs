@@ -0,0 +1,220 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{cell::RefCell, process, rc::Rc, sync::Mutex};
+
+use gtk::{glib, prelude::*};
+use zbus::{
+  blocking::{Connection, ConnectionBuilder},
+  dbus_interface,
+};
+
+use super::{menu::Submenu, EventLoopWindowTarget, OsError};
+use crate::{
+  dpi::PhysicalPosition,
+  error::ExternalError,
+  event::{Event, MouseButton, SystemTrayEvent},
+  icon::Icon,
+  system_tray::SystemTrayId,
+};
+
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+const ITEM: &str = "org.kde.StatusNotifierItem";
+const ITEM_PATH: &str = "/StatusNotifierItem";
+
+/// The icon of the item, `(width, height, ARGB32 in network byte order)` for every size.
+type Pixmaps = Vec<(i32, i32, Vec<u8>)>;
+
+/// The `StatusNotifierItem` served for a system tray.
+struct Item {
+  id: String,
+  title: String,
+  icon: Pixmaps,
+  tooltip: Option<String>,
+  click_tx: Mutex<glib::Sender<SystemTrayEvent>>,
+}
+
+impl Item {
+  fn click(&self, button: MouseButton, x: i32, y: i32) {
+    let event = SystemTrayEvent::Click {
+      button,
+      position: PhysicalPosition::new(x as f64, y as f64),
+    };
+    if let Err(e) = self.click_tx.lock().unwrap().send(event) {
+      log::warn!("Failed to send system tray event: {}", e);
+    }
+  }
+}
+
+#[dbus_interface(name = "org.kde.StatusNotifierItem")]
+impl Item {
+  fn activate(&self, x: i32, y: i32) {
+    self.click(MouseButton::Left, x, y);
+  }
+
+  fn secondary_activate(&self, x: i32, y: i32) {
+    self.click(MouseButton::Middle, x, y);
+  }
+
+  fn context_menu(&self, x: i32, y: i32) {
+    self.click(MouseButton::Right, x, y);
+  }
+
+  fn scroll(&self, _delta: i32, _orientation: String) {}
+
+  #[dbus_interface(property)]
+  fn category(&self) -> String {
+    "ApplicationStatus".into()
+  }
+
+  #[dbus_interface(property)]
+  fn id(&self) -> String {
+    self.id.clone()
+  }
+
+  #[dbus_interface(property)]
+  fn title(&self) -> String {
+    self.title.clone()
+  }
+
+  #[dbus_interface(property)]
+  fn status(&self) -> String {
+    "Active".into()
+  }
+
+  #[dbus_interface(property)]
+  fn icon_name(&self) -> String {
+    String::new()
+  }
+
+  #[dbus_interface(property)]
+  fn icon_pixmap(&self) -> Pixmaps {
+    self.icon.clone()
+  }
+
+  #[dbus_interface(property)]
+  fn tool_tip(&self) -> (String, Pixmaps, String, String) {
+    (
+      String::new(),
+      Vec::new(),
+      self.tooltip.clone().unwrap_or_default(),
+      String::new(),
+    )
+  }
+
+  #[dbus_interface(property)]
+  fn item_is_menu(&self) -> bool {
+    false
+  }
+}
+
+fn os_error(e: zbus::Error) -> ExternalError {
+  log::warn!("Failed to serve the system tray: {}", e);
+  ExternalError::Os(os_error!(OsError))
+}
+
+/// A `StatusNotifierItem` on its own session bus connection, registered with the
+/// `StatusNotifierWatcher` of the tray host.
+pub struct SystemTray {
+  conn: Connection,
+  /// The menu shown when the tray host asks for the context menu.
+  menu: Rc<RefCell<Option<gtk::Menu>>>,
+}
+
+impl SystemTray {
+  pub fn new<T: 'static>(
+    window_target: &EventLoopWindowTarget<T>,
+    id: u32,
+    icon: Icon,
+    tooltip: Option<String>,
+  ) -> Result<Self, ExternalError> {
+    // The methods of the item are called on the thread of the connection.
+    let (click_tx, click_rx) = glib::MainContext::channel(glib::Priority::default());
+    let event_tx = window_target.event_tx.clone();
+    let menu = Rc::new(RefCell::new(None::<gtk::Menu>));
+    let context_menu = menu.clone();
+    // Removed once the connection, holding the sender, is dropped with the tray.
+    click_rx.attach(Some(&glib::MainContext::default()), move |event| {
+      let right_click = matches!(
+        event,
+        SystemTrayEvent::Click {
+          button: MouseButton::Right,
+          ..
+        }
+      );
+      if let Err(e) = event_tx.send(Event::SystemTrayEvent {
+        tray_id: SystemTrayId(id),
+        event,
+      }) {
+        log::warn!("Failed to send system tray event to event channel: {}", e);
+      }
+      if let (true, Some(menu)) = (right_click, &*context_menu.borrow()) {
+        // Without a window to attach it to, the menu is shown at the pointer.
+        menu.show_all();
+        menu.popup_easy(3, gtk::current_event_time());
+      }
+      glib::ControlFlow::Continue
+    });
+
+    let app_id = window_target
+      .default_app_id
+      .clone()
+      .or_else(|| glib::prgname().map(Into::into))
+      .unwrap_or_default();
+    let item = Item {
+      title: glib::application_name().map_or_else(|| app_id.clone(), Into::into),
+      id: app_id,
+      icon: icon.inner.to_argb_pixmaps(),
+      tooltip,
+      click_tx: Mutex::new(click_tx),
+    };
+    let name = format!("{}-{}-{}", ITEM, process::id(), id);
+    let conn = ConnectionBuilder::session()
+      .and_then(|builder| builder.name(name.as_str()))
+      .and_then(|builder| builder.serve_at(ITEM_PATH, item))
+      .and_then(|builder| builder.build())
+      .map_err(os_error)?;
+    conn
+      .call_method(
+        Some(WATCHER),
+        WATCHER_PATH,
+        Some(WATCHER),
+        "RegisterStatusNotifierItem",
+        &name,
+      )
+      .map_err(os_error)?;
+    Ok(Self { conn, menu })
+  }
+
+  pub fn set_icon(&self, icon: Icon) {
+    self.update("NewIcon", |item| item.icon = icon.inner.to_argb_pixmaps());
+  }
+
+  pub fn set_tooltip(&self, tooltip: Option<&str>) {
+    self.update("NewToolTip", |item| {
+      item.tooltip = tooltip.map(ToOwned::to_owned)
+    });
+  }
+
+  pub fn set_menu(&self, menu: Option<&Submenu>) {
+    *self.menu.borrow_mut() = menu.and_then(Submenu::menu).cloned();
+  }
+
+  /// Updates the item, then emits `signal` for the tray host to read the changed property.
+  fn update(&self, signal: &str, f: impl FnOnce(&mut Item)) {
+    let result = self
+      .conn
+      .object_server()
+      .interface::<_, Item>(ITEM_PATH)
+      .and_then(|item| {
+        f(&mut item.get_mut());
+        self
+          .conn
+          .emit_signal(None::<&str>, ITEM_PATH, ITEM, signal, &())
+      });
+    if let Err(e) = result {
+      log::warn!("Failed to update the system tray: {}", e);
+    }
+  }
+}
@@ -20,6 +20,11 @@ struct Progress {
   urgent: Option<bool>,
 }
 
+/// The Unity launcher entry of the `.desktop` file matching `app_id`.
+pub fn unity_uri(app_id: &str) -> String {
+  format!("application://{}.desktop", app_id)
+}
+
 impl TaskbarIndicator {
   pub fn new() -> Result<Self> {
     let conn = Connection::session()?;
@@ -37,7 +42,13 @@ impl TaskbarIndicator {
       self.app_uri = uri;
     }
 
-    if let Some(progress) = progress.progress {
+    // The launcher entry has no indeterminate state, so it is shown as a visible progress
+    // without a value.
+    let shows_value = !matches!(
+      progress.state,
+      Some(ProgressState::None) | Some(ProgressState::Indeterminate)
+    );
+    if let (Some(progress), true) = (progress.progress, shows_value) {
       let progress = if progress > 100 { 100 } else { progress };
 
       properties.progress = Some(progress as f64 / 100.0);
@@ -45,6 +56,9 @@ impl TaskbarIndicator {
 
     if let Some(state) = progress.state {
       properties.progress_visible = Some(!matches!(state, ProgressState::None));
+      if matches!(state, ProgressState::None) {
+        properties.progress = Some(0.0);
+      }
     }
 
     let signal = MessageBuilder::signal("/", "com.canonical.Unity.LauncherEntry", "Update")?
@@ -0,0 +1,115 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{cell::Cell, rc::Rc, time::Instant};
+
+use gtk::glib;
+
+/// Wakes the main loop up at the resume time of `ControlFlow::WaitUntil`.
+///
+/// GLib timeouts are rounded up to whole milliseconds, so on Linux a timerfd watched by the
+/// main context is used instead, which wakes up with the precision of the kernel timers.
+pub struct WaitUntilTimer {
+  #[cfg(target_os = "linux")]
+  timerfd: Option<(std::os::unix::io::RawFd, glib::SourceId)>,
+  timeout: Rc<Cell<Option<glib::SourceId>>>,
+}
+
+impl WaitUntilTimer {
+  /// Creates the timer on the default main context, which must be owned by the current thread.
+  pub fn new() -> Self {
+    Self {
+      #[cfg(target_os = "linux")]
+      timerfd: timerfd::create(),
+      timeout: Default::default(),
+    }
+  }
+
+  /// Wakes the main loop up at `instant`, replacing the previous resume time.
+  pub fn set(&self, instant: Instant) {
+    let timeout = instant.saturating_duration_since(Instant::now());
+
+    #[cfg(target_os = "linux")]
+    if let Some((fd, _)) = self.timerfd {
+      timerfd::arm(fd, timeout);
+      return;
+    }
+
+    if let Some(source) = self.timeout.take() {
+      source.remove();
+    }
+    let slot = self.timeout.clone();
+    let source = glib::timeout_add_local_once(timeout, move || slot.set(None));
+    self.timeout.set(Some(source));
+  }
+}
+
+impl Drop for WaitUntilTimer {
+  fn drop(&mut self) {
+    #[cfg(target_os = "linux")]
+    if let Some((fd, source)) = self.timerfd.take() {
+      source.remove();
+      unsafe { libc::close(fd) };
+    }
+
+    if let Some(source) = self.timeout.take() {
+      source.remove();
+    }
+  }
+}
+
+#[cfg(target_os = "linux")]
+mod timerfd {
+  use std::{os::unix::io::RawFd, time::Duration};
+
+  use gtk::glib;
+
+  pub fn create() -> Option<(RawFd, glib::SourceId)> {
+    let fd = unsafe {
+      libc::timerfd_create(
+        libc::CLOCK_MONOTONIC,
+        libc::TFD_NONBLOCK | libc::TFD_CLOEXEC,
+      )
+    };
+    if fd < 0 {
+      log::warn!(
+        "Fail to create timerfd, falling back to GLib timeouts: {}",
+        std::io::Error::last_os_error()
+      );
+      return None;
+    }
+
+    let source = glib::source::unix_fd_add_local(fd, glib::IOCondition::IN, |fd, _| {
+      // Reading the expiration count resets the readiness of the timer.
+      let mut expirations = 0u64;
+      unsafe {
+        libc::read(
+          fd,
+          &mut expirations as *mut u64 as *mut _,
+          std::mem::size_of::<u64>(),
+        )
+      };
+      glib::ControlFlow::Continue
+    });
+    Some((fd, source))
+  }
+
+  pub fn arm(fd: RawFd, timeout: Duration) {
+    // A zero value disarms the timer, so an elapsed resume time fires as soon as possible.
+    let timeout = timeout.max(Duration::from_nanos(1));
+    let spec = libc::itimerspec {
+      it_interval: libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+      },
+      it_value: libc::timespec {
+        tv_sec: timeout.as_secs() as _,
+        tv_nsec: timeout.subsec_nanos() as _,
+      },
+    };
+    if unsafe { libc::timerfd_settime(fd, 0, &spec, std::ptr::null_mut()) } != 0 {
+      log::warn!("Fail to arm timerfd: {}", std::io::Error::last_os_error());
+    }
+  }
+}
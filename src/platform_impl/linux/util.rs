@@ -1,36 +1,137 @@
+use std::{
+  cell::{Cell, RefCell},
+  rc::Rc,
+  time::{Duration, Instant},
+};
+
+use gtk::cairo::{RectangleInt, Region};
 use gtk::gdk::{
   self,
-  prelude::{DeviceExt, SeatExt},
+  prelude::{DeviceExt, GdkPixbufExt, MonitorExt, SeatExt},
   Display,
 };
-use gtk::traits::{GtkWindowExt, WidgetExt};
+use gtk::{
+  gdk_pixbuf::Pixbuf,
+  glib::{self, prelude::ObjectExt, translate::ToGlibPtr, IsA},
+  prelude::{CssProviderExt, DragContextExtManual, GtkSettingsExt, StyleContextExt},
+  traits::{GtkWindowExt, WidgetExt},
+};
 
 use crate::{
-  dpi::{LogicalPosition, LogicalSize, PhysicalPosition},
+  dpi::{LogicalPosition, LogicalSize, PhysicalPosition, Rect},
   error::ExternalError,
-  window::WindowSizeConstraints,
+  icon::RgbaIcon,
+  window::{
+    Color, CursorGrabMode, DragData, DragImage, DragOperation, DragResult, HitTestCallback,
+    HitTestResult, RgbaImage, WindowShape, WindowSizeConstraints,
+  },
 };
 
 #[inline]
 pub fn cursor_position(is_wayland: bool) -> Result<PhysicalPosition<f64>, ExternalError> {
+  let display = Display::default().ok_or(ExternalError::Os(os_error!(super::OsError)))?;
+  let pointer = pointer(&display)?;
+  // Wayland only tells where the pointer is while it's over one of our surfaces, and then
+  // positions are relative to that surface.
+  if is_wayland && pointer.window_at_position().0.is_none() {
+    return Err(ExternalError::Os(os_error!(super::OsError)));
+  }
+  let (_, x, y) = pointer.position_double();
+  Ok(LogicalPosition::new(x, y).to_physical(display.default_group().scale_factor() as _))
+}
+
+/// Returns the position of the cursor relative to `window`, the same as `CursorMoved`.
+pub fn cursor_position_in(
+  window: &gdk::Window,
+  is_wayland: bool,
+) -> Result<PhysicalPosition<f64>, ExternalError> {
+  let pointer = pointer(&window.display())?;
   if is_wayland {
-    Ok((0, 0).into())
-  } else {
-    Display::default()
-      .map(|d| {
-        (
-          d.default_seat().and_then(|s| s.pointer()),
-          d.default_group(),
-        )
-      })
-      .map(|(p, g)| {
-        p.map(|p| {
-          let (_, x, y) = p.position_double();
-          LogicalPosition::new(x, y).to_physical(g.scale_factor() as _)
-        })
-      })
-      .map(|p| p.ok_or(ExternalError::Os(os_error!(super::OsError))))
-      .ok_or(ExternalError::Os(os_error!(super::OsError)))?
+    let over_window = pointer
+      .window_at_position()
+      .0
+      .map_or(false, |surface| surface.toplevel() == *window);
+    if !over_window {
+      return Err(ExternalError::Os(os_error!(super::OsError)));
+    }
+  }
+  let (_, x, y, _) = window.device_position_double(&pointer);
+  Ok(LogicalPosition::new(x, y).to_physical(window.scale_factor() as _))
+}
+
+fn pointer(display: &Display) -> Result<gdk::Device, ExternalError> {
+  display
+    .default_seat()
+    .and_then(|seat| seat.pointer())
+    .ok_or(ExternalError::Os(os_error!(super::OsError)))
+}
+
+const SHAPE_KEY: &str = "tao-shape";
+
+/// Clips `window` to `shape` and restricts its input region to it, or removes both with `None`.
+pub fn set_shape(window: &impl IsA<gtk::Window>, shape: Option<WindowShape>) {
+  let window = window.as_ref();
+  match shape {
+    // Kept for `update_shape`.
+    Some(shape) => unsafe { window.set_data(SHAPE_KEY, shape) },
+    None => {
+      unsafe { window.steal_data::<WindowShape>(SHAPE_KEY) };
+      apply_shape(window, None);
+      return;
+    }
+  }
+  update_shape(window);
+}
+
+/// Builds the region of [`set_shape`] again, for a new size or scale factor. Does nothing without
+/// a shape.
+pub fn update_shape(window: &impl IsA<gtk::Window>) {
+  let window = window.as_ref();
+  if let Some(shape) = unsafe { window.data::<WindowShape>(SHAPE_KEY) } {
+    apply_shape(window, Some(unsafe { shape.as_ref() }));
+  }
+}
+
+fn apply_shape(window: &gtk::Window, shape: Option<&WindowShape>) {
+  let region = shape.map(|shape| {
+    // GTK works in logical pixels, physical rectangles are converted here.
+    let scale_factor = window.scale_factor() as f64;
+    let (width, height) = (window.allocated_width(), window.allocated_height());
+    let region = Region::create();
+    let union = |x, y, width, height| {
+      if let Err(e) = region.union_rectangle(&RectangleInt::new(x, y, width, height)) {
+        log::warn!("Fail to build window shape: {}", e);
+      }
+    };
+    match shape {
+      WindowShape::RoundedRect { radius } => {
+        // Regions are made of rectangles, so the corners are approximated by one per row.
+        let radius = radius.max(0.0).min(width.min(height) as f64 / 2.0);
+        let rows = radius.ceil() as i32;
+        for y in 0..rows {
+          let dy = radius - y as f64 - 0.5;
+          let inset = (radius - (radius * radius - dy * dy).max(0.0).sqrt()).round() as i32;
+          union(inset, y, width - 2 * inset, 1);
+          union(inset, height - 1 - y, width - 2 * inset, 1);
+        }
+        union(0, rows, width, height - 2 * rows);
+      }
+      WindowShape::Region(rects) => {
+        for rect in rects {
+          let position = rect.position.to_logical::<i32>(scale_factor);
+          let size = rect.size.to_logical::<i32>(scale_factor);
+          union(position.x, position.y, size.width, size.height);
+        }
+      }
+    }
+    region
+  });
+
+  window.shape_combine_region(region.as_ref());
+  window.input_shape_combine_region(region.as_ref());
+  // On Wayland, the compositor only blends what is outside of the opaque region.
+  if let Some(gdk_window) = window.window() {
+    gdk_window.set_opaque_region(region.as_ref());
   }
 }
 
@@ -71,6 +172,599 @@ pub fn set_size_constraints<W: GtkWindowExt + WidgetExt>(
   )
 }
 
+const HIDDEN_STATE_KEY: &str = "tao-hidden-state";
+
+/// The states that window managers drop when a window is unmapped.
+const KEPT_STATES: gdk::WindowState = gdk::WindowState::MAXIMIZED
+  .union(gdk::WindowState::FULLSCREEN)
+  .union(gdk::WindowState::ABOVE)
+  .union(gdk::WindowState::BELOW)
+  .union(gdk::WindowState::STICKY);
+
+/// Hides `window`, saving the states that the window manager drops when a window is unmapped so
+/// that [`show_keeping_state`] can request them again.
+pub fn hide_keeping_state(window: &impl IsA<gtk::Window>) {
+  let window = window.as_ref();
+  if window.is_mapped() {
+    if let Some(gdk_window) = window.window() {
+      unsafe { window.set_data(HIDDEN_STATE_KEY, gdk_window.state() & KEPT_STATES) };
+    }
+  }
+  window.hide();
+}
+
+/// Records a state requested while `window` is hidden, so that showing it again doesn't bring
+/// back the state saved by [`hide_keeping_state`].
+pub fn update_hidden_state(window: &impl IsA<gtk::Window>, state: gdk::WindowState, on: bool) {
+  let window = window.as_ref();
+  if let Some(mut saved) = unsafe { window.data::<gdk::WindowState>(HIDDEN_STATE_KEY) } {
+    unsafe { saved.as_mut() }.set(state, on);
+  }
+}
+
+/// Shows `window`, requesting the states saved by [`hide_keeping_state`] first. GTK hands them
+/// to the window manager along with the map request, so the window appears in its old state
+/// without going through the normal one.
+pub fn show_keeping_state(window: &impl IsA<gtk::Window>) {
+  let window = window.as_ref();
+  if let Some(state) = unsafe { window.steal_data::<gdk::WindowState>(HIDDEN_STATE_KEY) } {
+    if state.contains(gdk::WindowState::MAXIMIZED) {
+      window.maximize();
+    }
+    if state.contains(gdk::WindowState::FULLSCREEN) {
+      window.fullscreen();
+    }
+    if state.contains(gdk::WindowState::ABOVE) {
+      window.set_keep_above(true);
+    }
+    if state.contains(gdk::WindowState::BELOW) {
+      window.set_keep_below(true);
+    }
+    if state.contains(gdk::WindowState::STICKY) {
+      window.stick();
+    }
+  }
+  window.show_all();
+}
+
+const BACKGROUND_PROVIDER_KEY: &str = "tao-background-provider";
+
+/// Overrides the background of the GTK theme with `color`, or restores it with `None`.
+pub fn set_background_color(window: &impl IsA<gtk::Window>, color: Option<Color>) {
+  let window = window.as_ref();
+  let context = window.style_context();
+  if let Some(provider) = unsafe { window.steal_data::<gtk::CssProvider>(BACKGROUND_PROVIDER_KEY) }
+  {
+    context.remove_provider(&provider);
+  }
+  let color = match color {
+    Some(color) => color,
+    None => return,
+  };
+  let provider = gtk::CssProvider::new();
+  let css = format!(
+    "window {{ background-color: rgba({}, {}, {}, {}); }}",
+    color.r,
+    color.g,
+    color.b,
+    color.a as f64 / 255.0
+  );
+  if let Err(e) = provider.load_from_data(css.as_bytes()) {
+    log::warn!("Failed to load the background color of the window: {}", e);
+    return;
+  }
+  context.add_provider(&provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+  unsafe { window.set_data(BACKGROUND_PROVIDER_KEY, provider) };
+}
+
+const HITTEST_REGION_KEY: &str = "tao-hittest-region";
+
+/// Restricts the input region of `window` to `region`, or lets the whole window take the cursor
+/// events with `None`.
+pub fn set_cursor_hittest_region(window: &impl IsA<gtk::Window>, region: Option<Vec<Rect>>) {
+  let window = window.as_ref();
+  match region {
+    // Kept for `update_cursor_hittest_region`.
+    Some(region) => unsafe { window.set_data(HITTEST_REGION_KEY, region) },
+    None => {
+      unsafe { window.steal_data::<Vec<Rect>>(HITTEST_REGION_KEY) };
+      window.input_shape_combine_region(None);
+      return;
+    }
+  }
+  update_cursor_hittest_region(window);
+}
+
+/// Converts the region of [`set_cursor_hittest_region`] again, for a new scale factor. Does
+/// nothing without a region.
+pub fn update_cursor_hittest_region(window: &impl IsA<gtk::Window>) {
+  let window = window.as_ref();
+  let rects = match unsafe { window.data::<Vec<Rect>>(HITTEST_REGION_KEY) } {
+    Some(rects) => unsafe { rects.as_ref() },
+    None => return,
+  };
+  // GTK works in logical pixels, physical rectangles are converted here.
+  let scale_factor = window.scale_factor() as f64;
+  let region = Region::create();
+  for rect in rects {
+    let position = rect.position.to_logical::<i32>(scale_factor);
+    let size = rect.size.to_logical::<i32>(scale_factor);
+    let rect = RectangleInt::new(position.x, position.y, size.width, size.height);
+    if let Err(e) = region.union_rectangle(&rect) {
+      log::warn!("Fail to build cursor hittest region: {}", e);
+    }
+  }
+  window.input_shape_combine_region(Some(&region));
+}
+
+const CUSTOM_CURSOR_KEY: &str = "tao-custom-cursor";
+
+/// Shows `icon` as the cursor of `window`, with its hotspot in physical pixels. `None` only
+/// forgets the image, for another cursor to be set.
+pub fn set_custom_cursor(
+  window: &impl IsA<gtk::Window>,
+  cursor: Option<(RgbaIcon, PhysicalPosition<u32>)>,
+) {
+  let window = window.as_ref();
+  match cursor {
+    // Kept for `update_custom_cursor`.
+    Some(cursor) => unsafe { window.set_data(CUSTOM_CURSOR_KEY, cursor) },
+    None => {
+      unsafe { window.steal_data::<(RgbaIcon, PhysicalPosition<u32>)>(CUSTOM_CURSOR_KEY) };
+      return;
+    }
+  }
+  update_custom_cursor(window);
+}
+
+/// Builds the cursor of [`set_custom_cursor`] again, for a new scale factor. Does nothing without
+/// one.
+pub fn update_custom_cursor(window: &impl IsA<gtk::Window>) {
+  let window = window.as_ref();
+  let (icon, hotspot) =
+    match unsafe { window.data::<(RgbaIcon, PhysicalPosition<u32>)>(CUSTOM_CURSOR_KEY) } {
+      Some(cursor) => unsafe { cursor.as_ref() },
+      None => return,
+    };
+  if let Some(gdk_window) = window.window() {
+    // A surface at the scale of the window shows the image at its size in physical pixels, and
+    // takes the hotspot in logical pixels.
+    let scale = window.scale_factor();
+    let cursor = Pixbuf::from(icon)
+      .create_surface(scale, Some(&gdk_window))
+      .map(|surface| {
+        gdk::Cursor::from_surface(
+          &window.display(),
+          &surface,
+          hotspot.x as f64 / scale as f64,
+          hotspot.y as f64 / scale as f64,
+        )
+      });
+    gdk_window.set_cursor(cursor.as_ref());
+  }
+}
+
+const HIT_TEST_KEY: &str = "tao-hit-test";
+
+/// Stores the callback of `Window::set_hit_test_callback` in `window`, for [`hit_test_at`].
+pub fn set_hit_test_callback(window: &impl IsA<gtk::Window>, callback: Option<HitTestCallback>) {
+  let window = window.as_ref();
+  match callback {
+    Some(callback) => unsafe { window.set_data(HIT_TEST_KEY, callback) },
+    None => {
+      unsafe { window.steal_data::<HitTestCallback>(HIT_TEST_KEY) };
+    }
+  }
+}
+
+pub fn has_hit_test_callback(window: &impl IsA<gtk::Window>) -> bool {
+  unsafe { window.as_ref().data::<HitTestCallback>(HIT_TEST_KEY) }.is_some()
+}
+
+/// Runs the hit-test callback of `window` for the cursor at the `root` coordinates of an event,
+/// `None` without a callback.
+pub fn hit_test_at(window: &impl IsA<gtk::Window>, root: (f64, f64)) -> Option<HitTestResult> {
+  let window = window.as_ref();
+  // Cloned for the callback to be able to replace itself.
+  let callback = unsafe { window.data::<HitTestCallback>(HIT_TEST_KEY) }
+    .map(|callback| unsafe { callback.as_ref() }.clone())?;
+  let (_, x, y) = window.window()?.origin();
+  let position = LogicalPosition::new(root.0 - x as f64, root.1 - y as f64)
+    .to_physical(window.scale_factor() as f64);
+  Some(callback(position))
+}
+
+/// Makes `window` fullscreen on `monitor`, or on its current monitor if `None`.
+///
+/// GDK creates new monitor objects when the outputs are reconfigured, so a handle that isn't in
+/// the display anymore is matched by its geometry.
+pub fn fullscreen_on<W: GtkWindowExt + WidgetExt>(window: &W, monitor: Option<&gdk::Monitor>) {
+  let monitor = match monitor {
+    Some(monitor) => monitor,
+    None => {
+      window.fullscreen();
+      return;
+    }
+  };
+
+  let display = window.display();
+  let geometry = monitor.geometry();
+  let bounds = |g: gdk::Rectangle| (g.x(), g.y(), g.width(), g.height());
+  let index = (0..display.n_monitors())
+    .find(|&i| display.monitor(i).as_ref() == Some(monitor))
+    .or_else(|| {
+      (0..display.n_monitors())
+        .find(|&i| display.monitor(i).map(|m| bounds(m.geometry())) == Some(bounds(geometry)))
+    });
+  let index = match index {
+    Some(index) => index,
+    None => {
+      log::warn!("Fullscreen monitor is gone, using the current one");
+      window.fullscreen();
+      return;
+    }
+  };
+
+  // The window manager places a window before applying its state, so an unmapped window is moved
+  // to the monitor first or it may be mapped, and fullscreened, on another one.
+  if !window.is_mapped() {
+    window.move_(geometry.x(), geometry.y());
+  }
+  window.fullscreen_on_monitor(&display.default_screen(), index);
+}
+
+/// Starts a drag of `data` out of `window` at the pointer, then calls `finished` once it ends.
+pub fn start_drag(
+  window: &impl IsA<gtk::Window>,
+  data: DragData,
+  image: &DragImage,
+  finished: impl Fn(DragResult) + 'static,
+) {
+  let window = window.as_ref();
+  let targets = gtk::TargetList::new(&[]);
+  match data {
+    DragData::Files(_) => targets.add_uri_targets(0),
+    DragData::Text(_) => targets.add_text_targets(0),
+  }
+  // -1 starts the drag at the current pointer position.
+  let context = match window.drag_begin_with_coordinates(
+    &targets,
+    gdk::DragAction::COPY | gdk::DragAction::MOVE,
+    1,
+    None,
+    -1,
+    -1,
+  ) {
+    Some(context) => context,
+    None => {
+      finished(DragResult::Cancelled);
+      return;
+    }
+  };
+  context.drag_set_icon_pixbuf(
+    &Pixbuf::from(&image.image),
+    image.hotspot.x as i32,
+    image.hotspot.y as i32,
+  );
+
+  // The window also gets the signals of drags started by its children, e.g. a webview.
+  let failed = Rc::new(Cell::new(false));
+  let handlers = Rc::new(RefCell::new(Vec::new()));
+  let drag = context.clone();
+  handlers.borrow_mut().push(
+    window.connect_drag_data_get(move |_, context, selection, _, _| {
+      if context != &drag {
+        return;
+      }
+      match &data {
+        DragData::Files(paths) => {
+          let uris: Vec<_> = paths
+            .iter()
+            .filter_map(|path| glib::filename_to_uri(path, None).ok())
+            .collect();
+          selection.set_uris(&uris.iter().map(|uri| uri.as_str()).collect::<Vec<_>>());
+        }
+        DragData::Text(text) => {
+          selection.set_text(text);
+        }
+      }
+    }),
+  );
+  let drag = context.clone();
+  let failed_ = failed.clone();
+  handlers
+    .borrow_mut()
+    .push(window.connect_drag_failed(move |_, context, _| {
+      if context == &drag {
+        failed_.set(true);
+      }
+      glib::Propagation::Proceed
+    }));
+  let drag = context;
+  let handlers_ = handlers.clone();
+  handlers
+    .borrow_mut()
+    .push(window.connect_drag_end(move |window, context| {
+      if context != &drag {
+        return;
+      }
+      let result = if failed.get() {
+        DragResult::Cancelled
+      } else if context.selected_action().contains(gdk::DragAction::MOVE) {
+        DragResult::Dropped(DragOperation::Move)
+      } else {
+        DragResult::Dropped(DragOperation::Copy)
+      };
+      for handler in handlers_.borrow_mut().drain(..) {
+        window.disconnect(handler);
+      }
+      finished(result);
+    }));
+}
+
+/// Copies the pixels of a pixbuf that has an alpha channel.
+pub fn pixbuf_to_rgba(pixbuf: &Pixbuf) -> RgbaImage {
+  let width = pixbuf.width() as usize;
+  let height = pixbuf.height() as usize;
+  let rowstride = pixbuf.rowstride() as usize;
+  let bytes = pixbuf.read_pixel_bytes();
+  // Rows are padded to the row stride, except for the last one.
+  let mut data = Vec::with_capacity(width * height * 4);
+  for row in 0..height {
+    let start = row * rowstride;
+    data.extend_from_slice(&bytes[start..start + width * 4]);
+  }
+  RgbaImage {
+    width: width as u32,
+    height: height as u32,
+    stride: width as u32 * 4,
+    data,
+  }
+}
+
+/// Converts the time of a GDK event, in milliseconds of the monotonic clock on both X11 and
+/// Wayland, to an `Instant`.
+pub fn event_time(time: u32) -> Instant {
+  let now = Instant::now();
+  let mut ts = libc::timespec {
+    tv_sec: 0,
+    tv_nsec: 0,
+  };
+  if time == gdk::ffi::GDK_CURRENT_TIME as u32
+    || unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) } != 0
+  {
+    return now;
+  }
+
+  // The event time wraps around, like the server time it comes from.
+  let now_ms = (ts.tv_sec as u64 * 1000 + ts.tv_nsec as u64 / 1_000_000) as u32;
+  let elapsed = now_ms.wrapping_sub(time);
+  // Servers that don't use the monotonic clock give nonsense, fall back to the current time.
+  if elapsed > MAX_EVENT_AGE_MS {
+    return now;
+  }
+  now
+    .checked_sub(Duration::from_millis(u64::from(elapsed)))
+    .unwrap_or(now)
+}
+
+const MAX_EVENT_AGE_MS: u32 = 10_000;
+
+/// The factor GTK scales text by on top of the scale factor, see
+/// `WindowExtUnix::text_scale_factor`.
+pub fn text_scale_factor(display: &Display) -> f64 {
+  let screen = display.default_screen();
+  // `gtk-xft-dpi` is set by the settings daemon, its `Xft/DPI` follows the text scaling factor
+  // of the desktop.
+  let xft_dpi = gtk::Settings::for_screen(&screen).map_or(-1, |settings| settings.gtk_xft_dpi());
+  if xft_dpi <= 0 && is_x11(display) {
+    // GTK ignores the X resource, unlike the other toolkits.
+    if let Some(dpi) = xft_dpi_resource(display) {
+      return dpi / 96.0 * dpi_scale();
+    }
+  }
+  // The resolution GTK gives Pango, which includes `GDK_DPI_SCALE`.
+  let resolution = screen.resolution();
+  if resolution > 0.0 {
+    resolution / 96.0
+  } else {
+    dpi_scale()
+  }
+}
+
+fn dpi_scale() -> f64 {
+  std::env::var("GDK_DPI_SCALE")
+    .ok()
+    .and_then(|scale| scale.parse::<f64>().ok())
+    .filter(|scale| *scale > 0.0)
+    .unwrap_or(1.0)
+}
+
+const CURSOR_GRAB_KEY: &str = "tao-cursor-grab";
+const CURSOR_LOCK_KEY: &str = "tao-cursor-lock";
+
+/// Keeps the grab of `Window::set_cursor_grab` in `window`, and applies it if the window has
+/// focus. It's only held while the window has focus, see [`apply_cursor_grab`].
+pub fn set_cursor_grab(window: &impl IsA<gtk::Window>, mode: CursorGrabMode) {
+  let window = window.as_ref();
+  if cursor_grab(window) == mode {
+    return;
+  }
+  unsafe { window.set_data(CURSOR_GRAB_KEY, mode) };
+  if window.has_toplevel_focus() {
+    if let Err(e) = apply_cursor_grab(window, mode) {
+      log::warn!("Failed to grab the cursor: {}", e);
+    }
+  }
+}
+
+/// The grab kept by [`set_cursor_grab`].
+pub fn cursor_grab(window: &impl IsA<gtk::Window>) -> CursorGrabMode {
+  unsafe { window.as_ref().data::<CursorGrabMode>(CURSOR_GRAB_KEY) }
+    .map_or(CursorGrabMode::None, |mode| unsafe { *mode.as_ref() })
+}
+
+/// Grabs the pointer of the seat of `window` for `mode`, or releases it with
+/// `CursorGrabMode::None`.
+pub fn apply_cursor_grab(
+  window: &impl IsA<gtk::Window>,
+  mode: CursorGrabMode,
+) -> Result<(), ExternalError> {
+  let window = window.as_ref();
+  let seat = window
+    .display()
+    .default_seat()
+    .ok_or(ExternalError::Os(os_error!(super::OsError)))?;
+  unsafe { window.steal_data::<(i32, i32)>(CURSOR_LOCK_KEY) };
+  if mode == CursorGrabMode::None {
+    seat.ungrab();
+    return Ok(());
+  }
+  let gdk_window = window
+    .window()
+    .ok_or(ExternalError::Os(os_error!(super::OsError)))?;
+  let status = seat.grab(
+    &gdk_window,
+    gdk::SeatCapabilities::POINTER,
+    true,
+    None,
+    None,
+    None,
+  );
+  if status != gdk::GrabStatus::Success {
+    return Err(ExternalError::Os(os_error!(super::OsError)));
+  }
+  if mode == CursorGrabMode::Locked {
+    if let Some(pointer) = seat.pointer() {
+      let (_, x, y) = pointer.position();
+      unsafe { window.set_data(CURSOR_LOCK_KEY, (x, y)) };
+    }
+  }
+  Ok(())
+}
+
+/// Warps the cursor back inside `window` while it's confined, or to where it's locked, since a
+/// seat grab only routes the pointer events to the window. Returns whether `motion` must be
+/// dropped, which it is for a locked cursor.
+pub fn hold_grabbed_cursor(window: &impl IsA<gtk::Window>, motion: &gdk::EventMotion) -> bool {
+  let window = window.as_ref();
+  let (pointer, screen) = match (motion.device(), GtkWindowExt::screen(window)) {
+    (Some(pointer), Some(screen)) => (pointer, screen),
+    _ => return false,
+  };
+  let (x, y) = motion.root();
+  let (x, y) = (x as i32, y as i32);
+  match cursor_grab(window) {
+    CursorGrabMode::Locked => {
+      let (lock_x, lock_y) = match unsafe { window.data::<(i32, i32)>(CURSOR_LOCK_KEY) } {
+        Some(lock) => unsafe { *lock.as_ref() },
+        None => return false,
+      };
+      if (x, y) != (lock_x, lock_y) {
+        pointer.warp(&screen, lock_x, lock_y);
+      }
+      true
+    }
+    CursorGrabMode::Confined => {
+      if let Some(gdk_window) = window.window() {
+        let (_, left, top) = gdk_window.origin();
+        let right = left + gdk_window.width() - 1;
+        let bottom = top + gdk_window.height() - 1;
+        let (confined_x, confined_y) = (x.max(left).min(right), y.max(top).min(bottom));
+        if (confined_x, confined_y) != (x, y) {
+          pointer.warp(&screen, confined_x, confined_y);
+        }
+      }
+      false
+    }
+    CursorGrabMode::None => false,
+  }
+}
+
+/// The `Xft.dpi` X resource, usually set with `xrdb`.
+fn xft_dpi_resource(display: &Display) -> Option<f64> {
+  let xlib = x11_dl::xlib::Xlib::open().ok()?;
+  unsafe {
+    let display: *mut gdk::ffi::GdkDisplay = display.to_glib_none().0;
+    let xdisplay = gdk_x11_sys::gdk_x11_display_get_xdisplay(display as *mut _);
+    if xdisplay.is_null() {
+      return None;
+    }
+    let resources = (xlib.XResourceManagerString)(xdisplay as *mut _);
+    if resources.is_null() {
+      return None;
+    }
+    std::ffi::CStr::from_ptr(resources)
+      .to_str()
+      .ok()?
+      .lines()
+      .find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim() == "Xft.dpi" {
+          value.trim().parse::<f64>().ok()
+        } else {
+          None
+        }
+      })
+      .filter(|dpi| *dpi > 0.0)
+  }
+}
+
+/// The last mouse button press of a window, used to count consecutive clicks.
+///
+/// GDK only reports double and triple clicks after the plain press, so the count is tracked
+/// here with the same settings instead.
+#[derive(Clone, Copy, Default)]
+pub struct ClickState {
+  button: u32,
+  count: u32,
+  time: u32,
+  position: (f64, f64),
+}
+
+impl ClickState {
+  /// Registers a button press and returns its click count.
+  pub fn press(&mut self, event: &gdk::EventButton) -> u32 {
+    let (double_click_time, double_click_distance) = gtk::Settings::default()
+      .map(|s| (s.gtk_double_click_time(), s.gtk_double_click_distance()))
+      .unwrap_or((400, 5));
+    let (time, position) = (event.time(), event.position());
+
+    let is_repeat = self.count > 0
+      && self.button == event.button()
+      && time.wrapping_sub(self.time) <= double_click_time as u32
+      && (position.0 - self.position.0).abs() <= f64::from(double_click_distance)
+      && (position.1 - self.position.1).abs() <= f64::from(double_click_distance);
+
+    *self = ClickState {
+      button: event.button(),
+      count: if is_repeat { self.count + 1 } else { 1 },
+      time,
+      position,
+    };
+    self.count
+  }
+
+  /// Returns the click count of the press that a release of `button` ends.
+  pub fn release(&self, button: u32) -> u32 {
+    match self.button == button && self.count > 0 {
+      true => self.count,
+      false => 1,
+    }
+  }
+}
+
+/// Whether `display` is an X11 one, without panicking on the backends `gdk::Backend` doesn't
+/// know about.
+pub fn is_x11(display: &Display) -> bool {
+  display.type_().name() == "GdkX11Display"
+}
+
+/// Whether `display` is a Wayland one.
+pub fn is_wayland(display: &Display) -> bool {
+  display.type_().name() == "GdkWaylandDisplay"
+}
+
 pub fn is_unity() -> bool {
   std::env::var("XDG_CURRENT_DESKTOP")
     .map(|d| {
@@ -79,3 +773,34 @@ pub fn is_unity() -> bool {
     })
     .unwrap_or(false)
 }
+
+/// Turns the identifier of a single-instance application into a valid `GApplication` id: the
+/// characters that aren't allowed are replaced with `_` and an id with less than two elements
+/// gets `tao.` prefixes.
+pub fn application_id(identifier: &str) -> String {
+  let mut elements: Vec<String> = identifier
+    .split('.')
+    .filter(|element| !element.is_empty())
+    .map(|element| {
+      let element: String = element
+        .chars()
+        .map(|c| match c {
+          'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' => c,
+          _ => '_',
+        })
+        .collect();
+      // Elements can't start with a digit.
+      if element.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", element)
+      } else {
+        element
+      }
+    })
+    .collect();
+  while elements.len() < 2 {
+    elements.insert(0, "tao".into());
+  }
+  let mut id = elements.join(".");
+  id.truncate(255);
+  id
+}
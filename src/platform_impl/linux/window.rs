@@ -3,38 +3,54 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-  cell::RefCell,
-  collections::VecDeque,
+  cell::{Cell, RefCell},
+  collections::{HashSet, VecDeque},
   rc::Rc,
   sync::{
-    atomic::{AtomicBool, AtomicI32, Ordering},
-    Arc,
+    atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering},
+    mpsc::SendError,
+    Arc, Mutex,
   },
 };
 
 use gtk::{
-  gdk::WindowState,
-  glib::{self, translate::ToGlibPtr},
+  gdk::{self, WindowState},
+  glib::{self, thread_guard::ThreadGuard, translate::ToGlibPtr},
 };
 use gtk::{prelude::*, Settings};
 
 use crate::{
-  dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Position, Size},
-  error::{ExternalError, NotSupportedError, OsError as RootOsError},
-  icon::Icon,
+  accessibility::AccessibilityActionRequest,
+  dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Position, Rect, Size},
+  error::{ExternalError, NotSupportedError, OsError as RootOsError, WindowOperationError},
+  event_loop::EventLoopClosed,
+  icon::{Icon, RgbaIcon},
   monitor::MonitorHandle as RootMonitorHandle,
+  platform::unix::EmbedParent,
   window::{
-    CursorIcon, Fullscreen, ProgressBarState, ResizeDirection, Theme, UserAttentionType,
-    WindowAttributes, WindowSizeConstraints,
+    ActivationToken, Color, CursorGrabMode, CursorIcon, CursorKind, DragData, DragImage,
+    FocusResult, Fullscreen, HitTestCallback, ImePurpose, ProgressBarState, ResizeDirection,
+    RgbaImage, Theme, UserAttentionType, WindowAttributes, WindowLevel, WindowShape,
+    WindowSizeConstraints,
   },
 };
 
 use super::{
+  embed,
   event_loop::EventLoopWindowTarget,
+  menu::{Menu, Submenu},
   monitor::{self, MonitorHandle},
-  util, Parent, PlatformSpecificWindowBuilderAttributes,
+  taskbar, util, Parent, PlatformSpecificWindowBuilderAttributes,
 };
 
+// Missing from `gdk-wayland-sys`, available since GDK 3.24.
+extern "C" {
+  fn gdk_wayland_window_set_application_id(
+    window: *mut gdk::ffi::GdkWindow,
+    application_id: *const std::os::raw::c_char,
+  );
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct WindowId(pub(crate) u32);
 
@@ -42,11 +58,41 @@ impl WindowId {
   pub fn dummy() -> Self {
     WindowId(u32::MAX)
   }
+
+  pub fn into_raw(self) -> u64 {
+    self.0 as u64
+  }
+
+  pub fn from_raw(raw: u64) -> Self {
+    WindowId(raw as u32)
+  }
 }
 
 // Currently GTK doesn't provide feature for detect theme, so we need to check theme manually.
 // ref: https://github.com/WebKit/WebKit/blob/e44ffaa0d999a9807f76f1805943eea204cfdfbc/Source/WebKit/UIProcess/API/gtk/PageClientImpl.cpp#L587
-const GTK_THEME_SUFFIX_LIST: [&'static str; 3] = ["-dark", "-Dark", "-Darker"];
+pub(super) const GTK_THEME_SUFFIX_LIST: [&'static str; 3] = ["-dark", "-Dark", "-Darker"];
+
+/// Handles an event of the window before tao, which doesn't see it then, by returning `true`.
+pub(crate) type EventHook = Rc<dyn Fn(&gtk::ApplicationWindow, &gdk::Event) -> bool>;
+
+/// Key under which the GTK window stores the hook of `Window::set_event_hook`, called by the
+/// first handler of its `event` signal.
+pub(crate) const EVENT_HOOK_KEY: &str = "tao-event-hook";
+/// Key under which the GTK window stores whether the window manager unmapped it while it's
+/// shown, see `is_shown`.
+pub(crate) const UNMAPPED_KEY: &str = "tao-unmapped";
+
+/// Whether the window is shown. GTK keeps a window unmapped by the window manager visible, which
+/// isn't shown anymore unless it's only minimized.
+pub(crate) fn is_shown(window: &impl IsA<gtk::Window>) -> bool {
+  let window = window.as_ref();
+  let unmapped = unsafe { window.data::<bool>(UNMAPPED_KEY) }
+    .map_or(false, |unmapped| unsafe { *unmapped.as_ref() });
+  let minimized = window.window().map_or(false, |window| {
+    window.state().contains(WindowState::ICONIFIED)
+  });
+  window.is_visible() && (!unmapped || minimized)
+}
 
 pub struct Window {
   /// Window id.
@@ -60,33 +106,131 @@ pub struct Window {
   position: Rc<(AtomicI32, AtomicI32)>,
   size: Rc<(AtomicI32, AtomicI32)>,
   maximized: Rc<AtomicBool>,
+  /// Maximized state requested from the window manager that hasn't been applied yet.
+  pending_maximized: Arc<Mutex<Option<bool>>>,
   minimized: Rc<AtomicBool>,
-  fullscreen: RefCell<Option<Fullscreen>>,
-  inner_size_constraints: RefCell<WindowSizeConstraints>,
+  visible_on_all_workspaces: Rc<AtomicBool>,
+  attention_requested: Rc<AtomicBool>,
+  /// Fullscreen state last requested, or applied by the window manager by itself.
+  fullscreen: Arc<Mutex<Option<Fullscreen>>>,
+  /// Fullscreen state waiting for the event loop to apply it, shared with the queued request.
+  fullscreen_request: Arc<Mutex<Option<Option<Fullscreen>>>>,
+  inner_size_constraints: Arc<Mutex<WindowSizeConstraints>>,
   /// Draw event Sender
   draw_tx: crossbeam_channel::Sender<WindowId>,
-  preferred_theme: Option<Theme>,
+  preferred_theme: RefCell<Option<Theme>>,
+  /// App id of the window, also used for the Unity launcher entry.
+  app_id: Option<String>,
+  /// The window belongs to a headless event loop and is never shown.
+  headless: bool,
+  /// The id of the X11 window, `None` on Wayland.
+  xid: Option<u64>,
+  /// The icon is kept while the cursor is hidden, to show it again with `set_cursor_visible`.
+  cursor_icon: Mutex<CursorIcon>,
+  /// The image of `set_custom_cursor`, shown instead of the icon while it's set.
+  custom_cursor: Mutex<Option<(RgbaIcon, PhysicalPosition<u32>)>>,
+  cursor_visible: AtomicBool,
+  /// The bits of the opacity last requested, read back by `opacity`.
+  opacity: AtomicU32,
+  /// Set while a `request_redraw_synced` waits for the next frame, shared with the request.
+  synced_redraw: Arc<AtomicBool>,
 }
 
 impl Window {
   pub(crate) fn new<T>(
     event_loop_window_target: &EventLoopWindowTarget<T>,
-    attributes: WindowAttributes,
+    mut attributes: WindowAttributes,
     pl_attribs: PlatformSpecificWindowBuilderAttributes,
   ) -> Result<Self, RootOsError> {
+    // A headless event loop without a display server can't create windows.
+    if event_loop_window_target.display.is_none() {
+      return Err(os_error!(super::OsError));
+    }
+    let headless = event_loop_window_target.headless;
+    if headless {
+      attributes.visible = false;
+    }
+
     let app = &event_loop_window_target.app;
     let window_requests_tx = event_loop_window_target.window_requests_tx.clone();
     let draw_tx = event_loop_window_target.draw_tx.clone();
 
     let mut window_builder = gtk::ApplicationWindow::builder()
       .application(app)
-      .accept_focus(attributes.focused);
-    if let Parent::ChildOf(parent) = pl_attribs.parent {
-      window_builder = window_builder.transient_for(&parent);
+      .accept_focus(attributes.focused)
+      .focus_on_map(attributes.focused);
+    let mut embed_parent = None;
+    match &pl_attribs.parent {
+      Parent::None => (),
+      Parent::ChildOf(parent) => window_builder = window_builder.transient_for(parent),
+      // GTK 3 can't create a window inside a foreign Wayland surface, `foreign_window` fails
+      // there.
+      Parent::EmbedIn(EmbedParent::Xlib(xid)) => {
+        let display = event_loop_window_target.display.as_ref();
+        embed_parent = Some(
+          display
+            .and_then(|display| embed::foreign_window(display, *xid))
+            .ok_or_else(|| os_error!(super::OsError))?,
+        );
+      }
     }
 
     let window = window_builder.build();
 
+    // Connected before the handlers of tao, which are connected by the event loop once the
+    // window is created, so the hook sees the events first.
+    window.connect_event(|window, event| {
+      // Cloned for the hook to be able to replace itself.
+      let hook = unsafe { window.data::<EventHook>(EVENT_HOOK_KEY) }
+        .map(|hook| unsafe { hook.as_ref() }.clone());
+      match hook {
+        Some(hook) if hook(window, event) => glib::Propagation::Stop,
+        _ => glib::Propagation::Proceed,
+      }
+    });
+
+    let app_id = pl_attribs
+      .app_id
+      .or_else(|| event_loop_window_target.default_app_id.clone());
+    if let Some(app_id) = &app_id {
+      if event_loop_window_target.is_wayland() {
+        // The xdg toplevel only exists once the window is mapped.
+        let app_id = app_id.clone();
+        window.connect_map(move |window| {
+          if let Some(window) = window.window() {
+            let app_id = std::ffi::CString::new(app_id.as_str()).unwrap_or_default();
+            unsafe {
+              gdk_wayland_window_set_application_id(window.as_ptr() as *mut _, app_id.as_ptr());
+            }
+          }
+        });
+      } else {
+        // `WM_CLASS` is read when the window is realized.
+        let instance = pl_attribs
+          .x11_instance
+          .as_deref()
+          .unwrap_or(app_id.as_str());
+        let instance = std::ffi::CString::new(instance).unwrap_or_default();
+        let class = std::ffi::CString::new(app_id.as_str()).unwrap_or_default();
+        unsafe {
+          gtk::ffi::gtk_window_set_wmclass(
+            window.upcast_ref::<gtk::Window>().to_glib_none().0,
+            instance.as_ptr(),
+            class.as_ptr(),
+          );
+        }
+      }
+    }
+
+    if !attributes.focused && !event_loop_window_target.is_wayland() {
+      // A `_NET_WM_USER_TIME` of 0 asks the window manager not to focus the window on map.
+      window.connect_realize(|window| {
+        if let Some(window) = window.window() {
+          unsafe { gdk_x11_sys::gdk_x11_window_set_user_time(window.as_ptr() as *mut _, 0) };
+        }
+      });
+    }
+
     let window_id = WindowId(window.id());
     event_loop_window_target
       .windows
@@ -94,7 +238,7 @@ impl Window {
       .insert(window_id);
 
     // Set Width/Height & Resizable
-    let win_scale_factor = window.scale_factor();
+    let win_scale_factor = initial_scale_factor(&window);
     let (width, height) = attributes
       .inner_size
       .map(|size| size.to_logical::<f64>(win_scale_factor as f64).into())
@@ -116,6 +260,17 @@ impl Window {
     if let Some(position) = attributes.position {
       let (x, y): (i32, i32) = position.to_logical::<i32>(win_scale_factor as f64).into();
       window.move_(x, y);
+    } else if attributes.position_cascade
+      && attributes.fullscreen.is_none()
+      && !attributes.maximized
+      && !event_loop_window_target.is_wayland()
+    {
+      cascade(
+        &window,
+        window_id,
+        &event_loop_window_target.windows.borrow(),
+        (width, height),
+      );
     }
 
     // Set GDK Visual
@@ -132,6 +287,8 @@ impl Window {
       // See Gtk drawing model for more info:
       // https://docs.gtk.org/gtk3/drawing-model.html
       window.set_app_paintable(true);
+    } else if attributes.background_color.is_some() {
+      util::set_background_color(&window, attributes.background_color);
     }
 
     if !pl_attribs.double_buffered {
@@ -154,30 +311,15 @@ impl Window {
     // Rest attributes
     window.set_title(&attributes.title);
     if let Some(Fullscreen::Borderless(m)) = &attributes.fullscreen {
-      if let Some(monitor) = m {
-        let display = window.display();
-        let monitor = &monitor.inner;
-        let monitors = display.n_monitors();
-        for i in 0..monitors {
-          let m = display.monitor(i).unwrap();
-          if m == monitor.monitor {
-            let screen = display.default_screen();
-            window.fullscreen_on_monitor(&screen, i);
-          }
-        }
-      } else {
-        window.fullscreen();
-      }
+      util::fullscreen_on(&window, m.as_ref().map(|m| &m.inner.monitor));
     }
     window.set_visible(attributes.visible);
     window.set_decorated(attributes.decorations);
 
-    if attributes.always_on_bottom {
-      window.set_keep_below(attributes.always_on_bottom);
-    }
-
-    if attributes.always_on_top {
-      window.set_keep_above(attributes.always_on_top);
+    match attributes.window_level {
+      WindowLevel::AlwaysOnBottom => window.set_keep_below(true),
+      WindowLevel::AlwaysOnTop | WindowLevel::ScreenSaver => window.set_keep_above(true),
+      WindowLevel::Normal => (),
     }
 
     if attributes.visible_on_all_workspaces {
@@ -185,38 +327,40 @@ impl Window {
     }
 
     if let Some(icon) = attributes.window_icon {
-      window.set_icon(Some(&icon.inner.into()));
-    }
-
-    let preferred_theme = if let Some(settings) = Settings::default() {
-      if let Some(preferred_theme) = attributes.preferred_theme {
-        match preferred_theme {
-          Theme::Dark => settings.set_gtk_application_prefer_dark_theme(true),
-          Theme::Light => {
-            if let Some(theme) = settings.gtk_theme_name() {
-              let theme = theme.as_str();
-              // Remove dark variant.
-              if let Some(theme) = GTK_THEME_SUFFIX_LIST
-                .iter()
-                .find(|t| theme.ends_with(*t))
-                .map(|v| theme.strip_suffix(v))
-              {
-                settings.set_gtk_theme_name(theme);
-              }
-            }
-          }
-        }
+      window.set_icon_list(&icon.inner.to_pixbufs());
+    }
+
+    let preferred_theme = if Settings::default().is_some() {
+      if attributes.preferred_theme.is_some() {
+        set_gtk_theme(attributes.preferred_theme);
       }
       attributes.preferred_theme
     } else {
       None
     };
 
+    if let Some(parent) = embed_parent {
+      embed::set_embed_parent(window.upcast_ref(), Some(parent));
+    }
+
     if attributes.visible {
       window.show_all();
     } else {
       window.hide();
     }
+    // A hidden X11 window has an id for another application to embed it, see `xid`.
+    let xid = if event_loop_window_target
+      .display
+      .as_ref()
+      .map_or(false, util::is_x11)
+    {
+      window.realize();
+      window.window().map(|window| unsafe {
+        gdk_x11_sys::gdk_x11_window_get_xid(window.as_ptr() as *mut _)
+      } as u64)
+    } else {
+      None
+    };
 
     // restore accept-focus after the window has been drawn
     // if the window was initially created without focus
@@ -237,40 +381,151 @@ impl Window {
     let position: Rc<(AtomicI32, AtomicI32)> = Rc::new((w_pos.0.into(), w_pos.1.into()));
     let position_clone = position.clone();
 
-    let w_size = window.size();
-    let size: Rc<(AtomicI32, AtomicI32)> = Rc::new((w_size.0.into(), w_size.1.into()));
+    // Until the window is mapped with its final geometry, the requested size is the best guess.
+    let size: Rc<(AtomicI32, AtomicI32)> = Rc::new((width.into(), height.into()));
     let size_clone = size.clone();
+    let mapped = Rc::new(Cell::new(false));
+    let mapped_clone = mapped.clone();
+
+    let w_max = window.is_maximized();
+    let maximized: Rc<AtomicBool> = Rc::new(w_max.into());
+    let max_clone = maximized.clone();
+    let pending_maximized = Arc::new(Mutex::new(if attributes.maximized {
+      Some(true)
+    } else {
+      None
+    }));
+    let pending_max_clone = pending_maximized.clone();
 
-    window.connect_configure_event(move |_, event| {
-      let (x, y) = event.position();
+    window.connect_configure_event(move |window, event| {
+      let (x, y) = window.position();
       position_clone.0.store(x, Ordering::Release);
       position_clone.1.store(y, Ordering::Release);
 
-      let (w, h) = event.size();
-      size_clone.0.store(w as i32, Ordering::Release);
-      size_clone.1.store(h as i32, Ordering::Release);
+      // Windows configured while they're realized but not mapped yet may have a placeholder
+      // size, like the 1x1 default size.
+      if mapped_clone.get() {
+        let (w, h) = event.size();
+        size_clone.0.store(w as i32, Ordering::Release);
+        size_clone.1.store(h as i32, Ordering::Release);
+      }
+
+      // The size of the new state is only known once the configure event follows it.
+      let mut pending_maximized = pending_max_clone.lock().unwrap();
+      if *pending_maximized == Some(max_clone.load(Ordering::Acquire)) {
+        *pending_maximized = None;
+      }
 
       false
     });
 
-    let w_max = window.is_maximized();
-    let maximized: Rc<AtomicBool> = Rc::new(w_max.into());
     let max_clone = maximized.clone();
+    let pending_max_clone = pending_maximized.clone();
     let minimized = Rc::new(AtomicBool::new(false));
     let minimized_clone = minimized.clone();
-
-    window.connect_window_state_event(move |_, event| {
+    let visible_on_all_workspaces = Rc::new(AtomicBool::new(false));
+    let visible_on_all_workspaces_clone = visible_on_all_workspaces.clone();
+    let fullscreen = Arc::new(Mutex::new(attributes.fullscreen.clone()));
+    let fullscreen_clone = fullscreen.clone();
+    let fullscreen_request: Arc<Mutex<Option<Option<Fullscreen>>>> = Default::default();
+    let fullscreen_request_clone = fullscreen_request.clone();
+
+    window.connect_window_state_event(move |window, event| {
+      // Window managers drop the state of an unmapped window, keep the state it had when it was
+      // hidden until it's shown again.
+      if !window.is_mapped() {
+        return glib::Propagation::Proceed;
+      }
       let state = event.new_window_state();
-      max_clone.store(state.contains(WindowState::MAXIMIZED), Ordering::Release);
+      let is_maximized = state.contains(WindowState::MAXIMIZED);
+      max_clone.store(is_maximized, Ordering::Release);
+      // The window manager applied a different state than the requested one.
+      if event.changed_mask().contains(WindowState::MAXIMIZED) {
+        let mut pending_maximized = pending_max_clone.lock().unwrap();
+        if *pending_maximized != Some(is_maximized) {
+          *pending_maximized = None;
+        }
+      }
       minimized_clone.store(state.contains(WindowState::ICONIFIED), Ordering::Release);
+      visible_on_all_workspaces_clone.store(state.contains(WindowState::STICKY), Ordering::Release);
+      // Follow changes made by the window manager, unless a newer request is still queued.
+      if event.changed_mask().contains(WindowState::FULLSCREEN)
+        && fullscreen_request_clone.lock().unwrap().is_none()
+      {
+        let is_fullscreen = state.contains(WindowState::FULLSCREEN);
+        let mut fullscreen = fullscreen_clone.lock().unwrap();
+        if fullscreen.is_some() != is_fullscreen {
+          *fullscreen = is_fullscreen.then(|| Fullscreen::Borderless(None));
+        }
+      }
       glib::Propagation::Proceed
     });
 
+    let attention_requested = Rc::new(AtomicBool::new(false));
+    let attention_requested_clone = attention_requested.clone();
+    window.connect_focus_in_event(move |window, _| {
+      // Most window managers drop the urgency hint on focus, make sure it's gone regardless.
+      if attention_requested_clone.swap(false, Ordering::AcqRel) {
+        window.set_urgency_hint(false);
+      }
+      glib::Propagation::Proceed
+    });
+
+    window.connect_focus_in_event(|window, _| {
+      let mode = util::cursor_grab(window);
+      if mode != CursorGrabMode::None {
+        if let Err(e) = util::apply_cursor_grab(window, mode) {
+          log::warn!("Failed to grab the cursor: {}", e);
+        }
+      }
+      glib::Propagation::Proceed
+    });
+    window.connect_focus_out_event(|window, _| {
+      if util::cursor_grab(window) != CursorGrabMode::None {
+        if let Err(e) = util::apply_cursor_grab(window, CursorGrabMode::None) {
+          log::warn!("Failed to release the cursor: {}", e);
+        }
+      }
+      glib::Propagation::Proceed
+    });
+    window.connect_motion_notify_event(|window, motion| {
+      // Motions of a locked cursor aren't reported, it's warped back after every one of them.
+      if util::hold_grabbed_cursor(window, motion) {
+        glib::Propagation::Stop
+      } else {
+        glib::Propagation::Proceed
+      }
+    });
+
     let scale_factor: Rc<AtomicI32> = Rc::new(win_scale_factor.into());
     let scale_factor_clone = scale_factor.clone();
+    let inner_size_constraints = Arc::new(Mutex::new(attributes.inner_size_constraints));
+    let constraints_clone = inner_size_constraints.clone();
     window.connect_scale_factor_notify(move |window| {
       scale_factor_clone.store(window.scale_factor(), Ordering::Release);
+      // Physical constraints are applied as logical geometry hints, convert them again.
+      util::set_size_constraints(window, *constraints_clone.lock().unwrap());
+      util::update_shape(window);
+      util::update_cursor_hittest_region(window);
+      util::update_custom_cursor(window);
     });
+    let size_clone = size.clone();
+    let scale_factor_clone = scale_factor.clone();
+    window.connect_map_event(move |window, _| {
+      if !mapped.replace(true) {
+        let (w, h) = window.size();
+        size_clone.0.store(w, Ordering::Release);
+        size_clone.1.store(h, Ordering::Release);
+        // The guess made before the window was realized isn't notified if it was wrong.
+        scale_factor_clone.store(window.scale_factor(), Ordering::Release);
+      }
+      glib::Propagation::Proceed
+    });
+    window.connect_size_allocate(|window, _| util::update_shape(window));
+
+    if attributes.opacity < 1.0 {
+      window.set_opacity(attributes.opacity as f64);
+    }
 
     // Check if we should paint the transparent background ourselves.
     let mut transparent = false;
@@ -303,13 +558,33 @@ impl Window {
       position,
       size,
       maximized,
+      pending_maximized,
       minimized,
-      fullscreen: RefCell::new(attributes.fullscreen),
-      inner_size_constraints: RefCell::new(attributes.inner_size_constraints),
-      preferred_theme,
+      visible_on_all_workspaces,
+      attention_requested,
+      fullscreen,
+      fullscreen_request,
+      inner_size_constraints,
+      preferred_theme: RefCell::new(preferred_theme),
+      app_id,
+      headless,
+      xid,
+      cursor_icon: Mutex::new(CursorIcon::Default),
+      custom_cursor: Mutex::new(None),
+      cursor_visible: AtomicBool::new(true),
+      opacity: AtomicU32::new(attributes.opacity.to_bits()),
+      synced_redraw: Default::default(),
     };
 
-    win.set_skip_taskbar(pl_attribs.skip_taskbar);
+    if attributes.skip_taskbar {
+      if let Err(e) = win.set_skip_taskbar(true) {
+        log::warn!("Fail to skip the taskbar: {}", e);
+      }
+    }
+    // The video mode is switched by the event loop, which restores it when the window is gone.
+    if let Some(Fullscreen::Exclusive(_)) = &attributes.fullscreen {
+      let _ = win.set_fullscreen(attributes.fullscreen.clone());
+    }
 
     Ok(win)
   }
@@ -322,18 +597,94 @@ impl Window {
     self.scale_factor.load(Ordering::Acquire) as f64
   }
 
+  pub fn gdk_scale(&self) -> i32 {
+    self.window.scale_factor()
+  }
+
+  pub fn text_scale_factor(&self) -> f64 {
+    util::text_scale_factor(&self.window.display())
+  }
+
+  pub fn set_event_hook(&self, hook: Option<EventHook>) {
+    // The hook isn't `Send`, it can only be handed over on the thread it runs on.
+    if hook.is_some() && !gtk::is_initialized_main_thread() {
+      log::warn!("The event hook can only be set on the thread of the event loop");
+      return;
+    }
+    if let Err(e) = self.window_requests_tx.send((
+      self.window_id,
+      WindowRequest::EventHook(hook.map(ThreadGuard::new)),
+    )) {
+      log::warn!("Fail to send event hook request: {}", e);
+    }
+  }
+
+  pub fn xid(&self) -> Option<u64> {
+    self.xid
+  }
+
+  pub fn set_embed_parent(&self, parent: Option<EmbedParent>) -> Result<(), ExternalError> {
+    if self.xid.is_none() {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
+    let parent = parent.map(|parent| match parent {
+      EmbedParent::Xlib(xid) => xid,
+    });
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::EmbedParent(parent)))
+    {
+      log::warn!("Fail to send embed parent request: {}", e);
+    }
+
+    Ok(())
+  }
+
   pub fn request_redraw(&self) {
     if let Err(e) = self.draw_tx.send(self.window_id) {
       log::warn!("Failed to send redraw event to event channel: {}", e);
     }
+    // This can be called from any thread, so wake the event loop up in case it's waiting.
+    glib::MainContext::default().wakeup();
+  }
+
+  pub fn request_redraw_synced(&self) {
+    if self.synced_redraw.swap(true, Ordering::AcqRel) {
+      return;
+    }
+    if let Err(e) = self.window_requests_tx.send((
+      self.window_id,
+      WindowRequest::RedrawSynced(self.synced_redraw.clone()),
+    )) {
+      log::warn!("Fail to send synced redraw request: {}", e);
+    }
+  }
+
+  pub fn request_frame_callbacks(&self, enabled: bool) {
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::FrameCallbacks(enabled)))
+    {
+      log::warn!("Fail to send frame callbacks request: {}", e);
+    }
   }
 
   pub fn inner_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
-    let (x, y) = &*self.position;
-    Ok(
-      LogicalPosition::new(x.load(Ordering::Acquire), y.load(Ordering::Acquire))
-        .to_physical(self.scale_factor.load(Ordering::Acquire) as f64),
-    )
+    let scale_factor = self.scale_factor.load(Ordering::Acquire) as f64;
+    match self.window.window() {
+      Some(gdk_window) => {
+        // The origin of the GDK window includes client-side decorations and shadows, so offset
+        // it by the allocation of the content widget.
+        let (_, mut x, mut y) = gdk_window.origin();
+        if let Some(child) = self.window.child() {
+          let allocation = child.allocation();
+          x += allocation.x();
+          y += allocation.y();
+        }
+        Ok(LogicalPosition::new(x, y).to_physical(scale_factor))
+      }
+      None => self.outer_position(),
+    }
   }
 
   pub fn outer_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
@@ -368,8 +719,14 @@ impl Window {
     .to_physical(self.scale_factor.load(Ordering::Acquire) as f64)
   }
 
-  pub fn set_inner_size<S: Into<Size>>(&self, size: S) {
-    let (width, height) = size.into().to_logical::<i32>(self.scale_factor()).into();
+  pub fn set_inner_size<S: Into<Size>>(&self, size: S) -> Result<(), WindowOperationError> {
+    let scale_factor = self.scale_factor();
+    let size = self
+      .inner_size_constraints
+      .lock()
+      .unwrap()
+      .clamp(size.into(), scale_factor);
+    let (width, height) = size.to_logical::<i32>(scale_factor).into();
 
     if let Err(e) = self
       .window_requests_tx
@@ -377,6 +734,7 @@ impl Window {
     {
       log::warn!("Fail to send size request: {}", e);
     }
+    Ok(())
   }
 
   pub fn outer_size(&self) -> PhysicalSize<u32> {
@@ -398,23 +756,37 @@ impl Window {
     }
   }
 
-  pub fn set_min_inner_size(&self, size: Option<Size>) {
-    let mut size_constraints = self.inner_size_constraints.borrow_mut();
+  pub fn set_min_inner_size(&self, size: Option<Size>) -> Result<(), ExternalError> {
+    let mut size_constraints = *self.inner_size_constraints.lock().unwrap();
     size_constraints.min_width = size.map(|s| s.width());
     size_constraints.min_height = size.map(|s| s.height());
-    self.set_size_constraints(*size_constraints)
+    self.set_inner_size_constraints(size_constraints)
   }
 
-  pub fn set_max_inner_size(&self, size: Option<Size>) {
-    let mut size_constraints = self.inner_size_constraints.borrow_mut();
+  pub fn set_max_inner_size(&self, size: Option<Size>) -> Result<(), ExternalError> {
+    let mut size_constraints = *self.inner_size_constraints.lock().unwrap();
     size_constraints.max_width = size.map(|s| s.width());
     size_constraints.max_height = size.map(|s| s.height());
-    self.set_size_constraints(*size_constraints)
+    self.set_inner_size_constraints(size_constraints)
   }
 
-  pub fn set_inner_size_constraints(&self, constraints: WindowSizeConstraints) {
-    *self.inner_size_constraints.borrow_mut() = constraints;
-    self.set_size_constraints(constraints)
+  pub fn set_inner_size_constraints(
+    &self,
+    constraints: WindowSizeConstraints,
+  ) -> Result<(), ExternalError> {
+    constraints.validate(self.scale_factor())?;
+    *self.inner_size_constraints.lock().unwrap() = constraints;
+    self.set_size_constraints(constraints);
+    Ok(())
+  }
+
+  pub fn close(&self) {
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::Close))
+    {
+      log::warn!("Fail to send close request: {}", e);
+    }
   }
 
   pub fn set_title(&self, title: &str) {
@@ -435,6 +807,10 @@ impl Window {
   }
 
   pub fn set_visible(&self, visible: bool) {
+    if visible && self.headless {
+      log::debug!("`Window::set_visible(true)` is ignored in headless mode");
+      return;
+    }
     if let Err(e) = self
       .window_requests_tx
       .send((self.window_id, WindowRequest::Visible(visible)))
@@ -444,14 +820,22 @@ impl Window {
   }
 
   pub fn set_focus(&self) {
-    if !self.minimized.load(Ordering::Acquire) && self.window.get_visible() {
-      if let Err(e) = self
-        .window_requests_tx
-        .send((self.window_id, WindowRequest::Focus))
-      {
-        log::warn!("Fail to send visible request: {}", e);
-      }
+    let _ = self.set_focus_with_token(None);
+  }
+
+  pub fn set_focus_with_token(&self, token: Option<ActivationToken>) -> FocusResult {
+    if self.minimized.load(Ordering::Acquire) || !self.window.get_visible() {
+      return FocusResult::Failed;
     }
+
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::Focus(token.map(|t| t.0))))
+    {
+      log::warn!("Fail to send focus request: {}", e);
+      return FocusResult::Failed;
+    }
+    FocusResult::Focused
   }
 
   pub fn is_focused(&self) -> bool {
@@ -490,6 +874,7 @@ impl Window {
   }
 
   pub fn set_maximized(&self, maximized: bool) {
+    *self.pending_maximized.lock().unwrap() = Some(maximized);
     if let Err(e) = self
       .window_requests_tx
       .send((self.window_id, WindowRequest::Maximized(maximized)))
@@ -499,7 +884,11 @@ impl Window {
   }
 
   pub fn is_maximized(&self) -> bool {
-    self.maximized.load(Ordering::Acquire)
+    self
+      .pending_maximized
+      .lock()
+      .unwrap()
+      .unwrap_or_else(|| self.maximized.load(Ordering::Acquire))
   }
 
   pub fn is_minimized(&self) -> bool {
@@ -528,7 +917,7 @@ impl Window {
 
   #[inline]
   pub fn is_visible(&self) -> bool {
-    self.window.is_visible()
+    is_shown(&self.window)
   }
 
   pub fn drag_window(&self) -> Result<(), ExternalError> {
@@ -551,18 +940,39 @@ impl Window {
     Ok(())
   }
 
-  pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
-    self.fullscreen.replace(fullscreen.clone());
+  pub fn start_drag(&self, data: DragData, image: DragImage) -> Result<(), ExternalError> {
     if let Err(e) = self
       .window_requests_tx
-      .send((self.window_id, WindowRequest::Fullscreen(fullscreen)))
+      .send((self.window_id, WindowRequest::StartDrag(data, image)))
+    {
+      log::warn!("Fail to send start drag request: {}", e);
+    }
+    Ok(())
+  }
+
+  pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) -> Result<(), WindowOperationError> {
+    *self.fullscreen.lock().unwrap() = fullscreen.clone();
+    // A request that is still queued applies the new state instead.
+    if self
+      .fullscreen_request
+      .lock()
+      .unwrap()
+      .replace(fullscreen)
+      .is_some()
     {
+      return Ok(());
+    }
+    if let Err(e) = self.window_requests_tx.send((
+      self.window_id,
+      WindowRequest::Fullscreen(self.fullscreen_request.clone()),
+    )) {
       log::warn!("Fail to send fullscreen request: {}", e);
     }
+    Ok(())
   }
 
   pub fn fullscreen(&self) -> Option<Fullscreen> {
-    self.fullscreen.borrow().clone()
+    self.fullscreen.lock().unwrap().clone()
   }
 
   pub fn set_decorations(&self, decorations: bool) {
@@ -574,6 +984,44 @@ impl Window {
     }
   }
 
+  pub fn raise(&self) -> Result<(), ExternalError> {
+    self.restack(WindowRequest::Raise)
+  }
+
+  pub fn lower(&self) -> Result<(), ExternalError> {
+    self.restack(WindowRequest::Lower)
+  }
+
+  pub fn restack_above(&self, sibling: &Window) -> Result<(), ExternalError> {
+    self.restack(WindowRequest::RestackAbove(sibling.window_id))
+  }
+
+  /// Sends a restacking request, Wayland doesn't let clients order their windows.
+  fn restack(&self, request: WindowRequest) -> Result<(), ExternalError> {
+    if self.is_wayland() {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
+    if let Err(e) = self.window_requests_tx.send((self.window_id, request)) {
+      log::warn!("Fail to send restack request: {}", e);
+    }
+
+    Ok(())
+  }
+
+  /// Returns the parent to create a window transient for this one.
+  pub(crate) fn as_parent(&self) -> Parent {
+    Parent::ChildOf(self.window.clone().upcast())
+  }
+
+  pub fn set_parent(&self, parent: Option<&Window>) {
+    if let Err(e) = self.window_requests_tx.send((
+      self.window_id,
+      WindowRequest::Parent(parent.map(|parent| parent.window_id)),
+    )) {
+      log::warn!("Fail to send parent request: {}", e);
+    }
+  }
+
   pub fn set_always_on_bottom(&self, always_on_bottom: bool) {
     if let Err(e) = self.window_requests_tx.send((
       self.window_id,
@@ -583,13 +1031,32 @@ impl Window {
     }
   }
 
-  pub fn set_always_on_top(&self, always_on_top: bool) {
+  /// Wayland compositors don't let clients keep their windows above the others.
+  pub fn set_always_on_top(&self, always_on_top: bool) -> Result<(), WindowOperationError> {
+    if self.is_wayland() {
+      return Err(NotSupportedError::new().into());
+    }
     if let Err(e) = self
       .window_requests_tx
       .send((self.window_id, WindowRequest::AlwaysOnTop(always_on_top)))
     {
       log::warn!("Fail to send always on top request: {}", e);
     }
+    Ok(())
+  }
+
+  /// Window managers have no level above the windows kept above for a screen saver.
+  pub fn set_window_level(&self, level: WindowLevel) {
+    match level {
+      WindowLevel::AlwaysOnBottom => self.set_always_on_bottom(true),
+      WindowLevel::Normal => {
+        let _ = self.set_always_on_top(false);
+        self.set_always_on_bottom(false);
+      }
+      WindowLevel::AlwaysOnTop | WindowLevel::ScreenSaver => {
+        let _ = self.set_always_on_top(true);
+      }
+    }
   }
 
   pub fn set_window_icon(&self, window_icon: Option<Icon>) {
@@ -601,11 +1068,64 @@ impl Window {
     }
   }
 
-  pub fn set_ime_position<P: Into<Position>>(&self, _position: P) {
-    //TODO
+  pub fn set_menu(&self, menu: Option<&Menu>) {
+    if self.default_vbox.is_none() {
+      log::warn!("The menu bar can't be set without the default vbox");
+      return;
+    }
+    // A menu with widgets was created on the GTK thread, and is only used there again.
+    let menu_bar = menu.and_then(Menu::menu_bar).cloned().map(ThreadGuard::new);
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::Menu(menu_bar)))
+    {
+      log::warn!("Fail to send menu request: {}", e);
+    }
+  }
+
+  pub fn show_context_menu(&self, menu: &Submenu, position: Position) {
+    menu.pop_up(&self.window, position.to_logical(self.scale_factor()));
+  }
+
+  pub fn set_ime_cursor_area(&self, area: Rect) {
+    let scale_factor = self.scale_factor();
+    let (x, y): (i32, i32) = area.position.to_logical::<i32>(scale_factor).into();
+    let (width, height): (i32, i32) = area.size.to_logical::<i32>(scale_factor).into();
+    if let Err(e) = self.window_requests_tx.send((
+      self.window_id,
+      WindowRequest::ImeCursorArea((x, y, width, height)),
+    )) {
+      log::warn!("Fail to send IME cursor area request: {}", e);
+    }
+  }
+
+  pub fn set_ime_allowed(&self, allowed: bool) {
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::ImeAllowed(allowed)))
+    {
+      log::warn!("Fail to send IME allowed request: {}", e);
+    }
+  }
+
+  pub fn set_ime_visible(&self, _visible: bool) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_ime_purpose(&self, purpose: ImePurpose) -> Result<(), ExternalError> {
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::ImePurpose(purpose)))
+    {
+      log::warn!("Fail to send IME purpose request: {}", e);
+    }
+    Ok(())
   }
 
   pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+    self
+      .attention_requested
+      .store(request_type.is_some(), Ordering::Release);
     if let Err(e) = self
       .window_requests_tx
       .send((self.window_id, WindowRequest::UserAttention(request_type)))
@@ -614,6 +1134,10 @@ impl Window {
     }
   }
 
+  pub fn is_attention_requested(&self) -> bool {
+    self.attention_requested.load(Ordering::Acquire)
+  }
+
   pub fn set_visible_on_all_workspaces(&self, visible: bool) {
     if let Err(e) = self.window_requests_tx.send((
       self.window_id,
@@ -622,7 +1146,26 @@ impl Window {
       log::warn!("Fail to send visible on all workspaces request: {}", e);
     }
   }
+
+  pub fn reset_dead_keys(&self) {
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::ResetDeadKeys))
+    {
+      log::warn!("Fail to send reset dead keys request: {}", e);
+    }
+  }
+
+  pub fn is_visible_on_all_workspaces(&self) -> bool {
+    self.visible_on_all_workspaces.load(Ordering::Acquire)
+  }
+
   pub fn set_cursor_icon(&self, cursor: CursorIcon) {
+    let had_custom_cursor = self.custom_cursor.lock().unwrap().take().is_some();
+    let previous = std::mem::replace(&mut *self.cursor_icon.lock().unwrap(), cursor);
+    if (previous == cursor && !had_custom_cursor) || !self.cursor_visible.load(Ordering::Acquire) {
+      return;
+    }
     if let Err(e) = self
       .window_requests_tx
       .send((self.window_id, WindowRequest::CursorIcon(Some(cursor))))
@@ -632,11 +1175,18 @@ impl Window {
   }
 
   pub fn set_cursor_position<P: Into<Position>>(&self, position: P) -> Result<(), ExternalError> {
-    let inner_pos = self.inner_position().unwrap_or_default();
-    let (x, y): (i32, i32) = position
-      .into()
-      .to_logical::<i32>(self.scale_factor())
-      .into();
+    // Wayland doesn't let clients move the pointer, only a locked pointer could take a hint.
+    if self.is_wayland() {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
+
+    let scale_factor = self.scale_factor();
+    // GDK warps in logical screen coordinates.
+    let inner_pos: LogicalPosition<i32> = self
+      .inner_position()
+      .unwrap_or_default()
+      .to_logical(scale_factor);
+    let (x, y): (i32, i32) = position.into().to_logical::<i32>(scale_factor).into();
 
     if let Err(e) = self.window_requests_tx.send((
       self.window_id,
@@ -648,7 +1198,17 @@ impl Window {
     Ok(())
   }
 
-  pub fn set_cursor_grab(&self, _grab: bool) -> Result<(), ExternalError> {
+  pub fn set_cursor_grab(&self, mode: CursorGrabMode) -> Result<(), ExternalError> {
+    if self.is_wayland() && mode != CursorGrabMode::None {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::CursorGrab(mode)))
+    {
+      log::warn!("Fail to send cursor grab request: {}", e);
+    }
+
     Ok(())
   }
 
@@ -663,23 +1223,148 @@ impl Window {
     Ok(())
   }
 
-  pub fn set_cursor_visible(&self, visible: bool) {
-    let cursor = if visible {
-      Some(CursorIcon::Default)
-    } else {
-      None
-    };
+  pub fn set_shape(&self, shape: Option<WindowShape>) -> Result<(), ExternalError> {
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::Shape(shape)))
+    {
+      log::warn!("Fail to send shape request: {}", e);
+    }
+
+    Ok(())
+  }
+
+  pub fn set_opacity(&self, opacity: f32) {
+    self.opacity.store(opacity.to_bits(), Ordering::Release);
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::Opacity(opacity)))
+    {
+      log::warn!("Fail to send opacity request: {}", e);
+    }
+  }
+
+  pub fn opacity(&self) -> f32 {
+    f32::from_bits(self.opacity.load(Ordering::Acquire))
+  }
+
+  pub fn set_background_color(&self, color: Option<Color>) {
     if let Err(e) = self
       .window_requests_tx
-      .send((self.window_id, WindowRequest::CursorIcon(cursor)))
+      .send((self.window_id, WindowRequest::BackgroundColor(color)))
     {
+      log::warn!("Fail to send background color request: {}", e);
+    }
+  }
+
+  pub fn set_minimize_target(&self, _target: Option<Rect>) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  pub fn set_cursor_hittest_region(&self, region: Option<&[Rect]>) -> Result<(), ExternalError> {
+    if let Err(e) = self.window_requests_tx.send((
+      self.window_id,
+      WindowRequest::CursorHittestRegion(region.map(<[Rect]>::to_vec)),
+    )) {
+      log::warn!("Fail to send cursor hittest region request: {}", e);
+    }
+
+    Ok(())
+  }
+
+  pub fn set_hit_test_callback(
+    &self,
+    callback: Option<HitTestCallback>,
+  ) -> Result<(), ExternalError> {
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::HitTestCallback(callback)))
+    {
+      log::warn!("Fail to send hit test callback request: {}", e);
+    }
+
+    Ok(())
+  }
+
+  pub fn accessibility_action_sender(&self) -> Result<AccessibilityActionSender, ExternalError> {
+    Ok(AccessibilityActionSender {
+      window_id: self.window_id,
+      window_requests_tx: self.window_requests_tx.clone(),
+    })
+  }
+
+  pub fn cursor_icon(&self) -> CursorKind {
+    if !self.cursor_visible.load(Ordering::Acquire) {
+      CursorKind::Hidden
+    } else if self.custom_cursor.lock().unwrap().is_some() {
+      CursorKind::Custom
+    } else {
+      CursorKind::Icon(*self.cursor_icon.lock().unwrap())
+    }
+  }
+
+  pub fn set_custom_cursor(
+    &self,
+    icon: RgbaIcon,
+    hotspot: PhysicalPosition<u32>,
+  ) -> Result<(), ExternalError> {
+    *self.custom_cursor.lock().unwrap() = Some((icon.clone(), hotspot));
+    if self.cursor_visible.load(Ordering::Acquire) {
+      if let Err(e) = self
+        .window_requests_tx
+        .send((self.window_id, WindowRequest::CustomCursor(icon, hotspot)))
+      {
+        log::warn!("Fail to send custom cursor request: {}", e);
+      }
+    }
+    Ok(())
+  }
+
+  pub fn set_cursor_visible(&self, visible: bool) {
+    if self.cursor_visible.swap(visible, Ordering::AcqRel) == visible {
+      return;
+    }
+    let request = match &*self.custom_cursor.lock().unwrap() {
+      Some((icon, hotspot)) if visible => WindowRequest::CustomCursor(icon.clone(), *hotspot),
+      _ => WindowRequest::CursorIcon(visible.then(|| *self.cursor_icon.lock().unwrap())),
+    };
+    if let Err(e) = self.window_requests_tx.send((self.window_id, request)) {
       log::warn!("Fail to send cursor visibility request: {}", e);
     }
   }
 
   #[inline]
   pub fn cursor_position(&self) -> Result<PhysicalPosition<f64>, ExternalError> {
-    util::cursor_position(self.is_wayland())
+    let window = self
+      .window
+      .window()
+      .ok_or(ExternalError::Os(os_error!(super::OsError)))?;
+    util::cursor_position_in(&window, self.is_wayland())
+  }
+
+  pub fn capture(&self, outer: bool) -> Result<RgbaImage, ExternalError> {
+    if self.is_wayland() {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
+    let window = self
+      .window
+      .window()
+      .filter(|window| !window.state().contains(WindowState::ICONIFIED))
+      .ok_or(ExternalError::Os(os_error!(super::OsError)))?;
+    let pixbuf = if outer {
+      // The frame belongs to the window manager, read it from the root window instead.
+      let frame = window.frame_extents();
+      window
+        .screen()
+        .root_window()
+        .and_then(|root| root.pixbuf(frame.x(), frame.y(), frame.width(), frame.height()))
+    } else {
+      window.pixbuf(0, 0, window.width(), window.height())
+    };
+    pixbuf
+      .and_then(|pixbuf| pixbuf.add_alpha(false, 0, 0, 0).ok())
+      .map(|pixbuf| util::pixbuf_to_rgba(&pixbuf))
+      .ok_or(ExternalError::Os(os_error!(super::OsError)))
   }
 
   pub fn current_monitor(&self) -> Option<RootMonitorHandle> {
@@ -725,7 +1410,7 @@ impl Window {
     monitor::from_point(display, x, y).map(|inner| RootMonitorHandle { inner })
   }
 
-  fn is_wayland(&self) -> bool {
+  pub fn is_wayland(&self) -> bool {
     self.window.display().backend().is_wayland()
   }
 
@@ -841,16 +1526,24 @@ impl Window {
     }
   }
 
-  pub(crate) fn set_skip_taskbar(&self, skip: bool) {
+  pub fn set_skip_taskbar(&self, skip: bool) -> Result<(), ExternalError> {
+    // GDK only implements the hints on X11.
+    if self.is_wayland() {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
     if let Err(e) = self
       .window_requests_tx
       .send((self.window_id, WindowRequest::SetSkipTaskbar(skip)))
     {
       log::warn!("Fail to send skip taskbar request: {}", e);
     }
+    Ok(())
   }
 
-  pub fn set_progress_bar(&self, progress: ProgressBarState) {
+  pub fn set_progress_bar(&self, mut progress: ProgressBarState) {
+    if progress.unity_uri.is_none() {
+      progress.unity_uri = self.app_id.as_deref().map(taskbar::unity_uri);
+    }
     if let Err(e) = self
       .window_requests_tx
       .send((WindowId::dummy(), WindowRequest::ProgressBarState(progress)))
@@ -860,7 +1553,7 @@ impl Window {
   }
 
   pub fn theme(&self) -> Theme {
-    if let Some(theme) = self.preferred_theme {
+    if let Some(theme) = *self.preferred_theme.borrow() {
       return theme;
     }
 
@@ -873,6 +1566,57 @@ impl Window {
 
     Theme::Light
   }
+
+  pub fn set_enable(&self, enabled: bool) {
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::Enable(enabled)))
+    {
+      log::warn!("Fail to send enable request: {}", e);
+    }
+  }
+
+  pub fn set_theme(&self, theme: Option<Theme>) {
+    *self.preferred_theme.borrow_mut() = theme;
+    if let Err(e) = self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::Theme(theme)))
+    {
+      log::warn!("Fail to send theme request: {}", e);
+    }
+  }
+}
+
+/// Applies the theme to the GTK settings, or restores the user settings if `None` is given.
+///
+/// The settings are shared by every window of the application.
+pub(crate) fn set_gtk_theme(theme: Option<Theme>) {
+  let settings = match Settings::default() {
+    Some(settings) => settings,
+    None => return,
+  };
+  super::appearance::save_system_gtk_theme_name(&settings, theme.is_some());
+  match theme {
+    Some(Theme::Dark) => settings.set_gtk_application_prefer_dark_theme(true),
+    Some(Theme::Light) => {
+      settings.set_gtk_application_prefer_dark_theme(false);
+      if let Some(theme) = settings.gtk_theme_name() {
+        let theme = theme.as_str();
+        // Remove dark variant.
+        if let Some(theme) = GTK_THEME_SUFFIX_LIST
+          .iter()
+          .find(|t| theme.ends_with(*t))
+          .map(|v| theme.strip_suffix(v))
+        {
+          settings.set_gtk_theme_name(theme);
+        }
+      }
+    }
+    None => {
+      settings.reset_property("gtk-application-prefer-dark-theme");
+      settings.reset_property("gtk-theme-name");
+    }
+  }
 }
 
 // We need GtkWindow to initialize WebView, so we have to keep it in the field.
@@ -880,21 +1624,25 @@ impl Window {
 unsafe impl Send for Window {}
 unsafe impl Sync for Window {}
 
+/// Grabs the pointer of the focused X11 `window` with `mode`, locking the cursor where it is.
 #[non_exhaustive]
 pub enum WindowRequest {
+  Close,
   Title(String),
   Position((i32, i32)),
   Size((i32, i32)),
   SizeConstraints(WindowSizeConstraints),
   Visible(bool),
-  Focus,
+  Focus(Option<String>),
   Resizable(bool),
   Closable(bool),
   Minimized(bool),
   Maximized(bool),
   DragWindow,
   DragResizeWindow(ResizeDirection),
-  Fullscreen(Option<Fullscreen>),
+  StartDrag(DragData, DragImage),
+  /// Applies the fullscreen state left in the slot, if another request didn't already.
+  Fullscreen(Arc<Mutex<Option<Option<Fullscreen>>>>),
   Decorations(bool),
   AlwaysOnBottom(bool),
   AlwaysOnTop(bool),
@@ -902,8 +1650,18 @@ pub enum WindowRequest {
   UserAttention(Option<UserAttentionType>),
   SetSkipTaskbar(bool),
   CursorIcon(Option<CursorIcon>),
+  CustomCursor(RgbaIcon, PhysicalPosition<u32>),
   CursorPosition((i32, i32)),
   CursorIgnoreEvents(bool),
+  CursorGrab(CursorGrabMode),
+  CursorHittestRegion(Option<Vec<Rect>>),
+  Shape(Option<WindowShape>),
+  Opacity(f32),
+  /// Packs this menu bar in the default vbox, or removes the current one.
+  Menu(Option<ThreadGuard<gtk::MenuBar>>),
+  BackgroundColor(Option<Color>),
+  HitTestCallback(Option<HitTestCallback>),
+  EventHook(Option<ThreadGuard<EventHook>>),
   WireUpEvents {
     transparent: bool,
     fullscreen: bool,
@@ -911,6 +1669,104 @@ pub enum WindowRequest {
   },
   SetVisibleOnAllWorkspaces(bool),
   ProgressBarState(ProgressBarState),
+  ResetDeadKeys,
+  ImePurpose(ImePurpose),
+  ImeCursorArea((i32, i32, i32, i32)),
+  ImeAllowed(bool),
+  Theme(Option<Theme>),
+  Enable(bool),
+  /// Makes the window transient for the window with this id, or for none.
+  Parent(Option<WindowId>),
+  /// Embeds the window into the foreign X11 window with this id, or makes it a top-level window
+  /// again.
+  EmbedParent(Option<u64>),
+  Raise,
+  Lower,
+  /// Moves the window right above the window with this id.
+  RestackAbove(WindowId),
+  /// Redraws the window at the next tick of its frame clock, and clears the flag.
+  RedrawSynced(Arc<AtomicBool>),
+  /// Starts or stops the redraws at every tick of the frame clock.
+  FrameCallbacks(bool),
+  /// Sent by an `AccessibilityActionSender`, delivered as `Event::AccessibilityAction`.
+  AccessibilityAction(AccessibilityActionRequest),
+}
+
+/// Sends the accessibility action requests of a window through its window requests.
+#[derive(Clone)]
+pub struct AccessibilityActionSender {
+  window_id: WindowId,
+  window_requests_tx: glib::Sender<(WindowId, WindowRequest)>,
+}
+
+impl AccessibilityActionSender {
+  pub fn send(
+    &self,
+    request: AccessibilityActionRequest,
+  ) -> Result<(), EventLoopClosed<AccessibilityActionRequest>> {
+    self
+      .window_requests_tx
+      .send((self.window_id, WindowRequest::AccessibilityAction(request)))
+      .map_err(|SendError((_, request))| match request {
+        WindowRequest::AccessibilityAction(request) => EventLoopClosed(request),
+        _ => unreachable!(),
+      })
+  }
+}
+
+/// The scale factor of a window that isn't realized yet. GTK reports the one of the first
+/// monitor until then, while new windows usually open on the primary one.
+fn initial_scale_factor(window: &gtk::ApplicationWindow) -> i32 {
+  if window.is_realized() {
+    return window.scale_factor();
+  }
+  let display = window.display();
+  display
+    .primary_monitor()
+    .or_else(|| display.monitor(0))
+    .map_or_else(|| window.scale_factor(), |monitor| monitor.scale_factor())
+}
+
+thread_local! {
+  /// The windows placed by `cascade` that may still be open, most recent last.
+  static CASCADE: RefCell<Vec<(WindowId, glib::WeakRef<gtk::ApplicationWindow>)>> =
+    RefCell::new(Vec::new());
+}
+
+/// The offset between cascaded windows in logical pixels, about the height of a title bar.
+const CASCADE_STEP: i32 = 30;
+
+/// Places the window down and to the right of the last one placed by this function, see
+/// `WindowBuilder::with_position_cascade`. `windows` are the open windows of the event loop.
+fn cascade(
+  window: &gtk::ApplicationWindow,
+  window_id: WindowId,
+  windows: &HashSet<WindowId>,
+  (width, height): (i32, i32),
+) {
+  CASCADE.with(|cascaded| {
+    let mut cascaded = cascaded.borrow_mut();
+    cascaded.retain(|(id, window)| windows.contains(id) && window.upgrade().is_some());
+    if let Some(last) = cascaded.last().and_then(|(_, window)| window.upgrade()) {
+      let (last_x, last_y) = last.position();
+      let display = last.display();
+      let monitor = last
+        .window()
+        .and_then(|window| display.monitor_at_window(&window))
+        .or_else(|| display.monitor_at_point(last_x, last_y));
+      if let Some(work_area) = monitor.map(|monitor| monitor.workarea()) {
+        let (mut x, mut y) = (last_x + CASCADE_STEP, last_y + CASCADE_STEP);
+        if x + width > work_area.x() + work_area.width()
+          || y + height > work_area.y() + work_area.height()
+        {
+          x = work_area.x();
+          y = work_area.y();
+        }
+        window.move_(x, y);
+      }
+    }
+    cascaded.push((window_id, window.downgrade()));
+  });
 }
 
 impl Drop for Window {
@@ -13,8 +13,11 @@ use objc::{
   runtime::{Class, Object, Sel},
 };
 
-use super::{app_state::AppState, event::EventWrapper, util, DEVICE_ID};
-use crate::event::{DeviceEvent, ElementState, Event};
+use super::{app_state::AppState, device, event::EventWrapper, util, DEVICE_ID};
+use crate::{
+  event::{DeviceEvent, ElementState, Event},
+  event_loop::DeviceEventFilter,
+};
 
 pub struct AppClass(pub *const Class);
 unsafe impl Send for AppClass {}
@@ -42,6 +45,8 @@ extern "C" fn send_event(this: &Object, _sel: Sel, event: id) {
     // For posterity, there are some undocumented event types
     // (https://github.com/servo/cocoa-rs/issues/155)
     // but that doesn't really matter here.
+    maybe_dispatch_device_event(event);
+
     let event_type = event.eventType();
     let modifier_flags = event.modifierFlags();
     if event_type == appkit::NSKeyUp
@@ -53,7 +58,6 @@ extern "C" fn send_event(this: &Object, _sel: Sel, event: id) {
       let key_window: id = msg_send![this, keyWindow];
       let _: () = msg_send![key_window, sendEvent: event];
     } else {
-      maybe_dispatch_device_event(event);
       let superclass = util::superclass(this);
       let _: () = msg_send![super(this, superclass), sendEvent: event];
     }
@@ -61,6 +65,10 @@ extern "C" fn send_event(this: &Object, _sel: Sel, event: id) {
 }
 
 unsafe fn maybe_dispatch_device_event(event: id) {
+  if device::filter() == DeviceEventFilter::Always {
+    return;
+  }
+
   let event_type = event.eventType();
   match event_type {
     appkit::NSMouseMoved
@@ -129,6 +137,9 @@ unsafe fn maybe_dispatch_device_event(event: id) {
 
       AppState::queue_events(events);
     }
+    appkit::NSKeyDown | appkit::NSKeyUp | appkit::NSFlagsChanged => {
+      device::maybe_dispatch_key_event(event);
+    }
     _ => (),
   }
 }
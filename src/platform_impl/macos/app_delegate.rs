@@ -2,17 +2,34 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{platform::macos::ActivationPolicy, platform_impl::platform::app_state::AppState};
+use crate::{
+  event::{Event, OpenedItem, OpenedUrl, SessionChange},
+  event_loop::{AccessibilityPrefs, SystemAppearance},
+  keyboard::KeyboardLayout,
+  platform::macos::{ActivationPolicy, AppleEvent, DefaultMenuConfig, ReopenBehavior},
+  platform_impl::platform::{
+    app_state::AppState,
+    event::{self, EventWrapper},
+    monitor, power,
+    util::{self, IdRef},
+    window::get_window_id,
+  },
+  window::WindowId,
+};
 
-use cocoa::base::id;
+use cocoa::appkit::{NSApp, NSApplication, NSWindow};
+use cocoa::base::{id, nil};
 use cocoa::foundation::NSString;
+use dispatch::Queue;
 use objc::{
   declare::ClassDecl,
-  runtime::{Class, Object, Sel, BOOL, NO},
+  runtime::{Class, Object, Sel, BOOL, NO, YES},
 };
 use std::{
   cell::{RefCell, RefMut},
   os::raw::c_void,
+  path::PathBuf,
+  rc::Rc,
 };
 
 use cocoa::foundation::NSArray;
@@ -26,17 +43,18 @@ pub const kInternetEventClass: u32 = 0x4755524c;
 /// Apple kAEGetURL constant
 #[allow(non_upper_case_globals)]
 pub const kAEGetURL: u32 = 0x4755524c;
-
-// Global callback for rustdesk
-extern "C" {
-  fn handle_open_urls(_self: &Object, _cmd: Sel, _: id, urls: id) -> ();
-  fn service_should_handle_reopen(
-    obj: &Object,
-    sel: Sel,
-    sender: id,
-    hasVisibleWindows: BOOL,
-  ) -> BOOL;
-}
+/// Apple keyDirectObject constant
+const KEY_DIRECT_OBJECT: u32 = 0x2d2d2d2d;
+/// Apple keySenderPIDAttr constant
+const KEY_SENDER_PID_ATTR: u32 = 0x73706964;
+/// Apple keyAddressAttr constant
+const KEY_ADDRESS_ATTR: u32 = 0x61646472;
+/// Apple typeKernelProcessID constant
+const TYPE_KERNEL_PROCESS_ID: u32 = 0x6b706964;
+/// Apple typeApplicationBundleID constant
+const TYPE_APPLICATION_BUNDLE_ID: u32 = 0x62756e64;
+/// `NSApplicationDelegateReplySuccess`
+const NS_APPLICATION_DELEGATE_REPLY_SUCCESS: u64 = 0;
 
 pub struct AuxDelegateState {
   /// We store this value in order to be able to defer setting the activation policy until
@@ -44,9 +62,47 @@ pub struct AuxDelegateState {
   /// menubar is initially unresponsive on macOS 10.15 for example.
   pub activation_policy: ActivationPolicy,
 
+  /// Whether to set the default menu described by `default_menu_config` once the app has
+  /// finished launching.
   pub create_default_menu: bool,
 
+  pub default_menu_config: DefaultMenuConfig,
+
   pub activate_ignoring_other_apps: bool,
+
+  /// Application requests made before `applicationDidFinishLaunching`, applied in order once it
+  /// has been called, like the activation policy.
+  pub pending_app_requests: Vec<AppRequest>,
+
+  /// The windows that were visible when the application was hidden, from front to back.
+  pub hidden_windows: Vec<IdRef>,
+
+  /// The last reported accessibility preferences, the notification doesn't say which changed.
+  pub accessibility_prefs: AccessibilityPrefs,
+
+  /// The last reported appearance of the system.
+  pub system_appearance: SystemAppearance,
+
+  pub reopen_behavior: ReopenBehavior,
+
+  /// The last window that became key, which may have been destroyed since.
+  pub last_key_window: Option<WindowId>,
+
+  /// The last reported keyboard layout, the notification is also posted when an input method is
+  /// selected on top of the same layout.
+  pub keyboard_layout: Option<KeyboardLayout>,
+
+  /// Set with `EventLoopBuilderExtMacOS::with_apple_event_handler`.
+  pub apple_event_handler: Option<Rc<dyn Fn(AppleEvent) -> bool>>,
+}
+
+/// A request to the whole application, made through `EventLoopWindowTargetExtMacOS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppRequest {
+  Hide,
+  Show,
+  HideOthers,
+  Activate { ignoring_other_apps: bool },
 }
 
 pub struct AppDelegateClass(pub *const Class);
@@ -73,6 +129,14 @@ lazy_static! {
       sel!(application:openURLs:),
       application_open_urls as extern "C" fn(&Object, Sel, id, id),
     );
+    decl.add_method(
+      sel!(handleEvent:withReplyEvent:),
+      handle_get_url_event as extern "C" fn(&Object, Sel, id, id),
+    );
+    decl.add_method(
+      sel!(application:openFiles:),
+      application_open_files as extern "C" fn(&Object, Sel, id, id),
+    );
     decl.add_method(
       sel!(applicationSupportsSecureRestorableState:),
       application_supports_secure_restorable_state as extern "C" fn(&Object, Sel, id) -> BOOL,
@@ -81,6 +145,42 @@ lazy_static! {
       sel!(applicationWillBecomeActive:),
       application_will_become_active as extern "C" fn(&Object, Sel, id),
     );
+    decl.add_method(
+      sel!(accessibilityDisplayOptionsDidChange:),
+      accessibility_display_options_did_change as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(powerStateDidChange:),
+      power_state_did_change as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(selectedKeyboardInputSourceDidChange:),
+      selected_keyboard_input_source_did_change as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(systemAppearanceDidChange:),
+      system_appearance_did_change as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(systemAppearanceDidChangeOnMainThread:),
+      system_appearance_did_change_on_main_thread as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(sessionDidResignActive:),
+      session_did_resign_active as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(sessionDidBecomeActive:),
+      session_did_become_active as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(screenIsLocked:),
+      screen_is_locked as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(screenIsUnlocked:),
+      screen_is_unlocked as extern "C" fn(&Object, Sel, id),
+    );
     // decl.add_method(sel!(applicationShouldHandleReopen:hasVisibleWindows:), func)
     decl.add_method(sel!(applicationShouldHandleReopen:hasVisibleWindows:),
     application_should_handle_reopen as extern "C" fn (&Object, Sel, id, BOOL) -> BOOL);
@@ -105,10 +205,103 @@ extern "C" fn new(class: &Class, _: Sel) -> id {
       AUX_DELEGATE_STATE_NAME,
       Box::into_raw(Box::new(RefCell::new(AuxDelegateState {
         activation_policy: ActivationPolicy::Regular,
-        create_default_menu: true,
+        create_default_menu: false,
+        default_menu_config: Default::default(),
         activate_ignoring_other_apps: true,
+        pending_app_requests: Vec::new(),
+        hidden_windows: Vec::new(),
+        accessibility_prefs: util::accessibility_prefs(),
+        system_appearance: util::system_appearance(),
+        reopen_behavior: Default::default(),
+        last_key_window: None,
+        keyboard_layout: event::keyboard_layout(),
+        apple_event_handler: None,
       }))) as *mut c_void,
     );
+    let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+    let notification_center: id = msg_send![workspace, notificationCenter];
+    let notification_name =
+      NSString::alloc(nil).init_str("NSWorkspaceAccessibilityDisplayOptionsDidChangeNotification");
+    let _: () = msg_send![
+      notification_center,
+      addObserver: this
+      selector: sel!(accessibilityDisplayOptionsDidChange:)
+      name: notification_name
+      object: nil
+    ];
+    // Posted when fast user switching moves the session off and back to the console.
+    let notification_name =
+      NSString::alloc(nil).init_str("NSWorkspaceSessionDidResignActiveNotification");
+    let _: () = msg_send![
+      notification_center,
+      addObserver: this
+      selector: sel!(sessionDidResignActive:)
+      name: notification_name
+      object: nil
+    ];
+    let notification_name =
+      NSString::alloc(nil).init_str("NSWorkspaceSessionDidBecomeActiveNotification");
+    let _: () = msg_send![
+      notification_center,
+      addObserver: this
+      selector: sel!(sessionDidBecomeActive:)
+      name: notification_name
+      object: nil
+    ];
+    let notification_center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+    let notification_name =
+      NSString::alloc(nil).init_str("NSProcessInfoPowerStateDidChangeNotification");
+    let _: () = msg_send![
+      notification_center,
+      addObserver: this
+      selector: sel!(powerStateDidChange:)
+      name: notification_name
+      object: nil
+    ];
+    // Delivered once the application is active again if it changed meanwhile.
+    let notification_center: id = msg_send![class!(NSDistributedNotificationCenter), defaultCenter];
+    let notification_name =
+      NSString::alloc(nil).init_str("com.apple.Carbon.TISNotifySelectedKeyboardInputSourceChanged");
+    let _: () = msg_send![
+      notification_center,
+      addObserver: this
+      selector: sel!(selectedKeyboardInputSourceDidChange:)
+      name: notification_name
+      object: nil
+    ];
+    // Neither is documented, they are posted when the theme and the accent color change.
+    for name in &[
+      "AppleInterfaceThemeChangedNotification",
+      "AppleColorPreferencesChangedNotification",
+    ] {
+      let notification_name = NSString::alloc(nil).init_str(name);
+      let _: () = msg_send![
+        notification_center,
+        addObserver: this
+        selector: sel!(systemAppearanceDidChange:)
+        name: notification_name
+        object: nil
+      ];
+    }
+    // Not documented, but posted by the login window since the screen lock exists.
+    let notification_name = NSString::alloc(nil).init_str("com.apple.screenIsLocked");
+    let _: () = msg_send![
+      notification_center,
+      addObserver: this
+      selector: sel!(screenIsLocked:)
+      name: notification_name
+      object: nil
+    ];
+    let notification_name = NSString::alloc(nil).init_str("com.apple.screenIsUnlocked");
+    let _: () = msg_send![
+      notification_center,
+      addObserver: this
+      selector: sel!(screenIsUnlocked:)
+      name: notification_name
+      object: nil
+    ];
+    power::start_monitoring();
+    monitor::start_monitoring();
     let cls = Class::get("NSAppleEventManager").unwrap();
     let manager: *mut Object = msg_send![cls, sharedAppleEventManager];
     let _: () = msg_send![manager,
@@ -122,6 +315,15 @@ extern "C" fn new(class: &Class, _: Sel) -> id {
 
 extern "C" fn dealloc(this: &Object, _: Sel) {
   unsafe {
+    let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+    let notification_center: id = msg_send![workspace, notificationCenter];
+    let _: () = msg_send![notification_center, removeObserver: this];
+    let notification_center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+    let _: () = msg_send![notification_center, removeObserver: this];
+    let notification_center: id = msg_send![class!(NSDistributedNotificationCenter), defaultCenter];
+    let _: () = msg_send![notification_center, removeObserver: this];
+    power::stop_monitoring();
+    monitor::stop_monitoring();
     let state_ptr: *mut c_void = *(this.get_ivar(AUX_DELEGATE_STATE_NAME));
     // As soon as the box is constructed it is immediately dropped, releasing the underlying
     // memory
@@ -137,15 +339,15 @@ extern "C" fn did_finish_launching(this: &Object, _: Sel, _: id) {
 
 extern "C" fn application_will_terminate(_: &Object, _: Sel, _: id) {
   trace!("Triggered `applicationWillTerminate`");
-  AppState::exit();
+  let exit_code = AppState::exit();
   trace!("Completed `applicationWillTerminate`");
+  // AppKit exits with status 0 once this returns, exit first to keep the requested code.
+  std::process::exit(exit_code);
 }
 
-extern "C" fn application_open_urls(obj: &Object, sel: Sel, id: id, urls: id) -> () {
+extern "C" fn application_open_urls(this: &Object, _: Sel, _: id, urls: id) -> () {
   trace!("Trigger `application:openURLs:`");
 
-  unsafe { handle_open_urls(obj, sel, id, urls); }
-
   let urls = unsafe {
     (0..urls.count())
       .map(|i| {
@@ -156,27 +358,299 @@ extern "C" fn application_open_urls(obj: &Object, sel: Sel, id: id, urls: id) ->
       .flatten()
       .collect::<Vec<_>>()
   };
-  trace!("Get `application:openURLs:` URLs: {:?}", urls);
-  AppState::open_urls(urls);
+  if handle_apple_event(this, AppleEvent::OpenUrls(urls.clone())) {
+    let items = urls
+      .into_iter()
+      .map(OpenedItem::from_url)
+      .collect::<Vec<_>>();
+    trace!("Get `application:openURLs:` items: {:?}", items);
+    AppState::open_items(items);
+  }
   trace!("Completed `application:openURLs:`");
 }
 
+/// Calls the handler set with `with_apple_event_handler`, and returns whether Tao should handle
+/// `event` as well.
+fn handle_apple_event(this: &Object, event: AppleEvent) -> bool {
+  // Cloned, so that the handler can call into Tao.
+  let handler = unsafe { get_aux_state_mut(this) }
+    .apple_event_handler
+    .clone();
+  handler.map_or(true, |handler| handler(event))
+}
+
+extern "C" fn handle_get_url_event(this: &Object, _: Sel, event: id, _reply: id) {
+  trace!("Triggered `handleEvent:withReplyEvent:`");
+  let opened_url = unsafe { opened_url(event) };
+  trace!("Get `handleEvent:withReplyEvent:` url: {:?}", opened_url);
+  // A malformed URL has nothing to give to the handler, but is still delivered.
+  let url = opened_url
+    .url
+    .as_deref()
+    .and_then(|url| url::Url::parse(url).ok());
+  if url.map_or(true, |url| {
+    handle_apple_event(this, AppleEvent::OpenUrls(vec![url]))
+  }) {
+    AppState::open_url(opened_url);
+  }
+  trace!("Completed `handleEvent:withReplyEvent:`");
+}
+
+/// Reads what it can of the `kAEGetURL` Apple event `event`.
+unsafe fn opened_url(event: id) -> OpenedUrl {
+  let mut opened_url = OpenedUrl::default();
+  if event == nil {
+    return opened_url;
+  }
+  let event_class: u32 = msg_send![event, eventClass];
+  let event_id: u32 = msg_send![event, eventID];
+  opened_url.event_class = Some(event_class);
+  opened_url.event_id = Some(event_id);
+
+  let direct_object: id = msg_send![event, paramDescriptorForKeyword: KEY_DIRECT_OBJECT];
+  opened_url.url = descriptor_string(direct_object);
+
+  let sender_pid: id = msg_send![event, attributeDescriptorForKeyword: KEY_SENDER_PID_ATTR];
+  opened_url.source_pid = descriptor_pid(sender_pid);
+  let address: id = msg_send![event, attributeDescriptorForKeyword: KEY_ADDRESS_ATTR];
+  if address != nil {
+    let descriptor_type: u32 = msg_send![address, descriptorType];
+    match descriptor_type {
+      TYPE_APPLICATION_BUNDLE_ID => opened_url.source_bundle_id = descriptor_string(address),
+      TYPE_KERNEL_PROCESS_ID if opened_url.source_pid.is_none() => {
+        opened_url.source_pid = descriptor_pid(address)
+      }
+      _ => (),
+    }
+  }
+
+  if opened_url.source_bundle_id.is_none() {
+    if let Some(pid) = opened_url.source_pid {
+      let application: id = msg_send![
+        class!(NSRunningApplication),
+        runningApplicationWithProcessIdentifier: pid
+      ];
+      if application != nil {
+        opened_url.source_bundle_id = ns_string_to_string(msg_send![application, bundleIdentifier]);
+      }
+    }
+  }
+  opened_url
+}
+
+unsafe fn descriptor_string(descriptor: id) -> Option<String> {
+  if descriptor == nil {
+    return None;
+  }
+  ns_string_to_string(msg_send![descriptor, stringValue])
+}
+
+unsafe fn descriptor_pid(descriptor: id) -> Option<i32> {
+  if descriptor == nil {
+    return None;
+  }
+  let pid: i32 = msg_send![descriptor, int32Value];
+  // `int32Value` is 0 when the descriptor can't be coerced.
+  (pid > 0).then(|| pid)
+}
+
+unsafe fn ns_string_to_string(string: id) -> Option<String> {
+  (string != nil).then(|| util::ns_string_to_rust(string))
+}
+
+extern "C" fn application_open_files(_: &Object, _: Sel, _: id, files: id) {
+  trace!("Trigger `application:openFiles:`");
+  let items = unsafe {
+    (0..files.count())
+      .map(|i| {
+        let path = CStr::from_ptr(files.objectAtIndex(i).UTF8String()).to_string_lossy();
+        OpenedItem::Path(PathBuf::from(path.into_owned()))
+      })
+      .collect::<Vec<_>>()
+  };
+  trace!("Get `application:openFiles:` items: {:?}", items);
+  AppState::open_items(items);
+  unsafe {
+    let _: () = msg_send![NSApp(), replyToOpenOrPrint: NS_APPLICATION_DELEGATE_REPLY_SUCCESS];
+  }
+  trace!("Completed `application:openFiles:`");
+}
+
 extern "C" fn application_supports_secure_restorable_state(_: &Object, _: Sel, _: id) -> BOOL {
   trace!("Triggered `applicationSupportsSecureRestorableState`");
   trace!("Triggered `applicationWillBecomeActive`");
   trace!("Completed `applicationSupportsSecureRestorableState`");
   objc::runtime::YES
- }
+}
 
 extern "C" fn application_will_become_active(obj: &Object, sel: Sel, id: id) {
   trace!("Triggered `applicationWillBecomeActive`");
 }
 
+extern "C" fn accessibility_display_options_did_change(this: &Object, _: Sel, _: id) {
+  trace!("Triggered `accessibilityDisplayOptionsDidChange:`");
+  let prefs = util::accessibility_prefs();
+  let previous = {
+    let mut aux_state = unsafe { get_aux_state_mut(this) };
+    std::mem::replace(&mut aux_state.accessibility_prefs, prefs)
+  };
+  if previous != prefs {
+    AppState::queue_event(EventWrapper::StaticEvent(
+      Event::AccessibilityPreferencesChanged(prefs),
+    ));
+  }
+  update_system_appearance(this);
+  trace!("Completed `accessibilityDisplayOptionsDidChange:`");
+}
+
+extern "C" fn power_state_did_change(_: &Object, _: Sel, _: id) {
+  trace!("Triggered `powerStateDidChange:`");
+  // The notification is posted on a background queue.
+  Queue::main().exec_async(power::power_info_changed);
+  trace!("Completed `powerStateDidChange:`");
+}
+
+extern "C" fn selected_keyboard_input_source_did_change(this: &Object, _: Sel, _: id) {
+  trace!("Triggered `selectedKeyboardInputSourceDidChange:`");
+  let layout = event::keyboard_layout();
+  let previous = {
+    let mut aux_state = unsafe { get_aux_state_mut(this) };
+    std::mem::replace(&mut aux_state.keyboard_layout, layout.clone())
+  };
+  if previous != layout {
+    AppState::queue_event_and_wake(EventWrapper::StaticEvent(Event::KeyboardLayoutChanged));
+  }
+  trace!("Completed `selectedKeyboardInputSourceDidChange:`");
+}
+
+extern "C" fn system_appearance_did_change(this: &Object, _: Sel, _: id) {
+  trace!("Triggered `systemAppearanceDidChange:`");
+  // The application only picks up the new appearance after the notification.
+  unsafe {
+    let _: () = msg_send![
+      this,
+      performSelectorOnMainThread: sel!(systemAppearanceDidChangeOnMainThread:)
+      withObject: nil
+      waitUntilDone: NO
+    ];
+  }
+  trace!("Completed `systemAppearanceDidChange:`");
+}
+
+extern "C" fn system_appearance_did_change_on_main_thread(this: &Object, _: Sel, _: id) {
+  update_system_appearance(this);
+}
+
+/// Reports the appearance of the system if it changed since the last time.
+fn update_system_appearance(this: &Object) {
+  let appearance = util::system_appearance();
+  let previous = {
+    let mut aux_state = unsafe { get_aux_state_mut(this) };
+    std::mem::replace(&mut aux_state.system_appearance, appearance)
+  };
+  if previous != appearance {
+    AppState::queue_event_and_wake(EventWrapper::StaticEvent(Event::SystemAppearanceChanged(
+      appearance,
+    )));
+  }
+}
+
+extern "C" fn session_did_resign_active(_: &Object, _: Sel, _: id) {
+  trace!("Triggered `sessionDidResignActive:`");
+  session_changed(SessionChange::SwitchedAway);
+  trace!("Completed `sessionDidResignActive:`");
+}
+
+extern "C" fn session_did_become_active(_: &Object, _: Sel, _: id) {
+  trace!("Triggered `sessionDidBecomeActive:`");
+  session_changed(SessionChange::SwitchedBack);
+  trace!("Completed `sessionDidBecomeActive:`");
+}
+
+extern "C" fn screen_is_locked(_: &Object, _: Sel, _: id) {
+  trace!("Triggered `screenIsLocked:`");
+  session_changed(SessionChange::Locked);
+  trace!("Completed `screenIsLocked:`");
+}
+
+extern "C" fn screen_is_unlocked(_: &Object, _: Sel, _: id) {
+  trace!("Triggered `screenIsUnlocked:`");
+  session_changed(SessionChange::Unlocked);
+  trace!("Completed `screenIsUnlocked:`");
+}
+
+fn session_changed(change: SessionChange) {
+  AppState::queue_event_and_wake(EventWrapper::StaticEvent(Event::SessionChanged(change)));
+}
+
 extern "C" fn application_should_handle_reopen(
-  obj: &Object,
-  sel: Sel,
-  id: id,
+  this: &Object,
+  _: Sel,
+  _: id,
   has_visible_windows: BOOL,
 ) -> BOOL {
-  unsafe { service_should_handle_reopen(obj, sel, id, has_visible_windows) }
+  trace!("Triggered `applicationShouldHandleReopen:hasVisibleWindows:`");
+  // The handler of the application runs first, and can skip the default behavior.
+  let reopen = AppleEvent::Reopen {
+    has_visible_windows: has_visible_windows == YES,
+  };
+  if !handle_apple_event(this, reopen) {
+    trace!("Completed `applicationShouldHandleReopen:hasVisibleWindows:`");
+    return NO;
+  }
+  AppState::reopen(has_visible_windows == YES);
+  let (behavior, last_key_window) = {
+    let aux_state = unsafe { get_aux_state_mut(this) };
+    (aux_state.reopen_behavior, aux_state.last_key_window)
+  };
+  let reopened = unsafe { reopen_windows(behavior, last_key_window) };
+  trace!("Completed `applicationShouldHandleReopen:hasVisibleWindows:`");
+  // AppKit would otherwise deminiaturize a window of its own choosing.
+  if reopened {
+    NO
+  } else {
+    YES
+  }
+}
+
+/// Shows the windows as `behavior` says, and returns whether it showed any.
+unsafe fn reopen_windows(behavior: ReopenBehavior, last_key_window: Option<WindowId>) -> bool {
+  if behavior == ReopenBehavior::None {
+    return false;
+  }
+  let ns_app = NSApp();
+  let windows: id = msg_send![ns_app, windows];
+  let tao_windows: Vec<id> = (0..windows.count())
+    .map(|i| windows.objectAtIndex(i))
+    .filter(|&window| {
+      let is_tao_window: BOOL = msg_send![window, isKindOfClass: class!(TaoWindow)];
+      is_tao_window == YES && AppState::has_window(WindowId(get_window_id(window)))
+    })
+    .collect();
+  // The last key window may have been destroyed since, any other window takes its place.
+  let key_window = match tao_windows
+    .iter()
+    .copied()
+    .find(|&window| Some(WindowId(get_window_id(window))) == last_key_window)
+    .or_else(|| tao_windows.first().copied())
+  {
+    Some(window) => window,
+    None => return false,
+  };
+
+  let () = msg_send![ns_app, unhide: nil];
+  if behavior == ReopenBehavior::ShowAllWindows {
+    for &window in tao_windows.iter().filter(|&&window| window != key_window) {
+      if window.isMiniaturized() == YES {
+        window.deminiaturize_(nil);
+      }
+      window.orderFront_(nil);
+    }
+  }
+  if key_window.isMiniaturized() == YES {
+    key_window.deminiaturize_(nil);
+  }
+  key_window.makeKeyAndOrderFront_(nil);
+  ns_app.activateIgnoringOtherApps_(YES);
+  true
 }
@@ -4,7 +4,7 @@
 
 use std::{
   cell::{RefCell, RefMut},
-  collections::VecDeque,
+  collections::{HashSet, VecDeque},
   fmt::{self, Debug},
   hint::unreachable_unchecked,
   mem,
@@ -19,20 +19,22 @@ use std::{
 use cocoa::{
   appkit::{NSApp, NSApplication, NSWindow},
   base::{id, nil},
-  foundation::{NSAutoreleasePool, NSSize},
+  foundation::{NSArray, NSAutoreleasePool, NSSize},
 };
 use objc::runtime::{Object, NO, YES};
 
 use crate::{
   dpi::LogicalSize,
-  event::{Event, StartCause, WindowEvent},
-  event_loop::{ControlFlow, EventLoopWindowTarget as RootWindowTarget},
-  platform::macos::ActivationPolicy,
+  event::{Event, OpenedItem, OpenedUrl, StartCause, WindowEvent},
+  event_loop::{ControlFlow, EventLoopWindowTarget as RootWindowTarget, IdleDeadline},
+  platform::macos::{ActivationPolicy, ReopenBehavior},
   platform_impl::{
     get_aux_state_mut,
     platform::{
+      app_delegate::AppRequest,
       event::{EventProxy, EventWrapper},
       event_loop::{post_dummy_event, PanicInfo},
+      menu,
       observer::{CFRunLoopGetMain, CFRunLoopWakeUp, EventLoopWaker},
       util::{self, IdRef, Never},
       window::get_window_id,
@@ -59,6 +61,7 @@ pub trait EventHandler: Debug {
   // Not sure probably it should accept Event<'static, Never>
   fn handle_nonuser_event(&mut self, event: Event<'_, Never>, control_flow: &mut ControlFlow);
   fn handle_user_events(&mut self, control_flow: &mut ControlFlow);
+  fn handle_idle(&mut self, deadline: IdleDeadline);
 }
 
 struct EventLoopHandler<T: 'static> {
@@ -119,6 +122,16 @@ impl<T> EventHandler for EventLoopHandler<T> {
       }
     });
   }
+
+  fn handle_idle(&mut self, deadline: IdleDeadline) {
+    // User events sent since they were handled come first.
+    if !self.window_target.p.receiver.is_empty() {
+      return;
+    }
+    if let Some(idle_callback) = &mut *self.window_target.p.idle_callback.borrow_mut() {
+      idle_callback(deadline);
+    }
+  }
 }
 
 #[derive(Default)]
@@ -131,6 +144,10 @@ struct Handler {
   callback: Mutex<Option<Box<dyn EventHandler>>>,
   pending_events: Mutex<VecDeque<EventWrapper>>,
   pending_redraw: Mutex<Vec<WindowId>>,
+  /// Windows that got their `Destroyed` event, which must be their last one.
+  destroyed_windows: Mutex<HashSet<WindowId>>,
+  /// Windows that were created and didn't get their `Destroyed` event yet.
+  live_windows: Mutex<HashSet<WindowId>>,
   waker: Mutex<EventLoopWaker>,
 }
 
@@ -201,11 +218,35 @@ impl Handler {
     self.in_callback.store(in_callback, Ordering::Release);
   }
 
+  /// Returns `false` for the events that a window gets after its `Destroyed` event, like a
+  /// redraw that was requested before the window was dropped.
+  fn is_window_alive(&self, event: &Event<'_, Never>) -> bool {
+    let (window_id, destroyed) = match event {
+      Event::WindowEvent { window_id, event } => {
+        (*window_id, matches!(event, WindowEvent::Destroyed))
+      }
+      Event::RedrawRequested(window_id) => (*window_id, false),
+      _ => return true,
+    };
+    let mut destroyed_windows = self.destroyed_windows.lock().unwrap();
+    if destroyed_windows.contains(&window_id) {
+      return false;
+    }
+    if destroyed {
+      destroyed_windows.insert(window_id);
+      // Forgotten before the event is handled, see `has_window`.
+      self.live_windows.lock().unwrap().remove(&window_id);
+    }
+    true
+  }
+
   fn handle_nonuser_event(&self, wrapper: EventWrapper) {
     if let Some(ref mut callback) = *self.callback.lock().unwrap() {
       match wrapper {
         EventWrapper::StaticEvent(event) => {
-          callback.handle_nonuser_event(event, &mut *self.control_flow.lock().unwrap())
+          if self.is_window_alive(&event) {
+            callback.handle_nonuser_event(event, &mut *self.control_flow.lock().unwrap())
+          }
         }
         EventWrapper::EventProxy(proxy) => self.handle_proxy(proxy, callback),
       }
@@ -218,6 +259,20 @@ impl Handler {
     }
   }
 
+  /// Runs the idle callback if no event is pending, and the event loop isn't exiting.
+  fn handle_idle(&self) {
+    if !self.events().is_empty() || !self.redraw().is_empty() {
+      return;
+    }
+    let deadline = match IdleDeadline::new(*self.control_flow.lock().unwrap()) {
+      Some(deadline) => deadline,
+      None => return,
+    };
+    if let Some(ref mut callback) = *self.callback.lock().unwrap() {
+      callback.handle_idle(deadline);
+    }
+  }
+
   fn handle_scale_factor_changed_event(
     &self,
     callback: &mut Box<dyn EventHandler + 'static>,
@@ -225,22 +280,10 @@ impl Handler {
     suggested_size: LogicalSize<f64>,
     scale_factor: f64,
   ) {
-    let mut size = suggested_size.to_physical(scale_factor);
-    let new_inner_size = &mut size;
-    let event = Event::WindowEvent {
-      window_id: WindowId(get_window_id(*ns_window)),
-      event: WindowEvent::ScaleFactorChanged {
-        scale_factor,
-        new_inner_size,
-      },
-    };
-
-    callback.handle_nonuser_event(event, &mut *self.control_flow.lock().unwrap());
-
-    let physical_size = *new_inner_size;
-    let logical_size = physical_size.to_logical(scale_factor);
-    let size = NSSize::new(logical_size.width, logical_size.height);
-    unsafe { NSWindow::setContentSize_(*ns_window, size) };
+    let control_flow = &mut *self.control_flow.lock().unwrap();
+    change_scale_factor(ns_window, suggested_size, scale_factor, |event| {
+      callback.handle_nonuser_event(event, control_flow)
+    });
   }
 
   fn handle_proxy(&self, proxy: EventProxy, callback: &mut Box<dyn EventHandler + 'static>) {
@@ -256,6 +299,28 @@ impl Handler {
   }
 }
 
+/// Delivers `ScaleFactorChanged` with `deliver`, then gives the window the inner size the event
+/// handler picked.
+fn change_scale_factor(
+  ns_window: IdRef,
+  suggested_size: LogicalSize<f64>,
+  scale_factor: f64,
+  deliver: impl FnOnce(Event<'_, Never>),
+) {
+  let mut size = suggested_size.to_physical(scale_factor);
+  deliver(Event::WindowEvent {
+    window_id: WindowId(get_window_id(*ns_window)),
+    event: WindowEvent::ScaleFactorChanged {
+      scale_factor,
+      new_inner_size: &mut size,
+    },
+  });
+
+  let logical_size = size.to_logical::<f64>(scale_factor);
+  let size = NSSize::new(logical_size.width, logical_size.height);
+  unsafe { NSWindow::setContentSize_(*ns_window, size) };
+}
+
 pub enum AppState {}
 
 impl AppState {
@@ -292,6 +357,19 @@ impl AppState {
         NO
       };
       ns_app.activateIgnoringOtherApps_(ignore);
+      let requests = mem::take(&mut get_aux_state_mut(app_delegate).pending_app_requests);
+      for request in requests {
+        apply_app_request(app_delegate, request);
+      }
+      let (create_default_menu, default_menu_config) = {
+        let aux_state = get_aux_state_mut(app_delegate);
+        (aux_state.create_default_menu, aux_state.default_menu_config)
+      };
+      // A menu bar set with `Window::set_menu` before the launch replaces the default one.
+      let main_menu: id = msg_send![ns_app, mainMenu];
+      if create_default_menu && main_menu == nil {
+        menu::initialize(default_menu_config);
+      }
     };
     HANDLER.set_ready();
     HANDLER.waker().start();
@@ -299,11 +377,60 @@ impl AppState {
     HANDLER.handle_nonuser_event(EventWrapper::StaticEvent(Event::NewEvents(
       StartCause::Init,
     )));
+    for event in HANDLER.take_events() {
+      HANDLER.handle_nonuser_event(event);
+    }
     HANDLER.set_in_callback(false);
   }
 
-  pub fn open_urls(urls: Vec<url::Url>) {
-    HANDLER.handle_nonuser_event(EventWrapper::StaticEvent(Event::Opened { urls }));
+  /// Applies `request` now, or once the application has finished launching.
+  pub fn app_request(request: AppRequest) {
+    unsafe {
+      let delegate: id = msg_send![NSApp(), delegate];
+      if delegate == nil {
+        return;
+      }
+      if HANDLER.is_ready() {
+        apply_app_request(&*delegate, request);
+      } else {
+        get_aux_state_mut(&*delegate)
+          .pending_app_requests
+          .push(request);
+      }
+    }
+  }
+
+  pub fn open_items(items: Vec<OpenedItem>) {
+    Self::open(EventWrapper::StaticEvent(Event::Opened { items }));
+  }
+
+  pub fn open_url(url: OpenedUrl) {
+    Self::open(EventWrapper::StaticEvent(Event::OpenedUrl(url)));
+  }
+
+  /// Delivers `Event::Reopen` right away when possible, so that the handler can still change the
+  /// default reopen behavior.
+  pub fn reopen(has_visible_windows: bool) {
+    let wrapper = EventWrapper::StaticEvent(Event::Reopen {
+      has_visible_windows,
+    });
+    if HANDLER.is_ready() && !HANDLER.get_in_callback() {
+      HANDLER.set_in_callback(true);
+      HANDLER.handle_nonuser_event(wrapper);
+      HANDLER.set_in_callback(false);
+    } else {
+      Self::queue_event(wrapper);
+    }
+  }
+
+  fn open(wrapper: EventWrapper) {
+    if HANDLER.is_ready() {
+      HANDLER.handle_nonuser_event(wrapper);
+    } else {
+      // Launch-time items arrive before `applicationDidFinishLaunching`, hold them back until
+      // `StartCause::Init` has been delivered.
+      HANDLER.events().push_back(wrapper);
+    }
   }
 
   pub fn wakeup(panic_info: Weak<PanicInfo>) {
@@ -322,10 +449,12 @@ impl AppState {
         requested_resume: None,
       },
       ControlFlow::WaitUntil(requested_resume) => {
-        if Instant::now() >= requested_resume {
+        let now = Instant::now();
+        if now >= requested_resume {
           StartCause::ResumeTimeReached {
             start,
             requested_resume,
+            resumed: now,
           }
         } else {
           StartCause::WaitCancelled {
@@ -354,7 +483,44 @@ impl AppState {
   }
 
   pub fn handle_redraw(window_id: WindowId) {
+    // The event handler is running, the redraw waits for the end of the iteration or for the
+    // next iteration of a nested event loop.
+    if HANDLER.get_in_callback() {
+      Self::queue_redraw(window_id);
+      return;
+    }
+    // The system is redrawing the window, which also answers a pending request.
+    HANDLER.redraw().retain(|id| *id != window_id);
+    HANDLER.set_in_callback(true);
     HANDLER.handle_nonuser_event(EventWrapper::StaticEvent(Event::RedrawRequested(window_id)));
+    HANDLER.set_in_callback(false);
+  }
+
+  pub fn register_window(window_id: WindowId) {
+    HANDLER.live_windows.lock().unwrap().insert(window_id);
+  }
+
+  pub fn set_default_reopen_behavior(behavior: ReopenBehavior) {
+    unsafe {
+      let delegate: id = msg_send![NSApp(), delegate];
+      if delegate != nil {
+        get_aux_state_mut(&*delegate).reopen_behavior = behavior;
+      }
+    }
+  }
+
+  /// Remembers the window that became key, for the default reopen behavior.
+  pub fn window_became_key(window_id: WindowId) {
+    unsafe {
+      let delegate: id = msg_send![NSApp(), delegate];
+      if delegate != nil {
+        get_aux_state_mut(&*delegate).last_key_window = Some(window_id);
+      }
+    }
+  }
+
+  pub fn has_window(window_id: WindowId) -> bool {
+    HANDLER.live_windows.lock().unwrap().contains(&window_id)
   }
 
   pub fn queue_event(wrapper: EventWrapper) {
@@ -364,6 +530,13 @@ impl AppState {
     HANDLER.events().push_back(wrapper);
   }
 
+  /// Queues an event from outside of the event handler, and wakes the run loop up so that it's
+  /// delivered with the other pending events at the end of its next iteration.
+  pub fn queue_event_and_wake(wrapper: EventWrapper) {
+    Self::queue_event(wrapper);
+    unsafe { CFRunLoopWakeUp(CFRunLoopGetMain()) };
+  }
+
   pub fn queue_events(mut wrappers: VecDeque<EventWrapper>) {
     if !util::is_main_thread() {
       panic!("Events queued from different thread: {:#?}", wrappers);
@@ -371,6 +544,51 @@ impl AppState {
     HANDLER.events().append(&mut wrappers);
   }
 
+  /// Whether an event or a redraw is waiting for the end of the iteration.
+  pub fn has_pending_events() -> bool {
+    !HANDLER.events().is_empty() || !HANDLER.redraw().is_empty()
+  }
+
+  /// Runs an iteration of a nested event loop, see `EventLoopWindowTarget::run_nested_until`.
+  ///
+  /// The event handler is running, so the observers and the views queue the events, which are
+  /// delivered to `handler` in the order `cleared` would deliver them.
+  pub fn nested_iteration<T: 'static>(
+    cause: StartCause,
+    target: &RootWindowTarget<T>,
+    handler: &mut dyn FnMut(Event<'_, T>, &RootWindowTarget<T>, &mut ControlFlow),
+    control_flow: &mut ControlFlow,
+  ) {
+    handler(Event::NewEvents(cause), target, control_flow);
+    for event in target.p.receiver.try_iter() {
+      handler(Event::UserEvent(event), target, control_flow);
+    }
+    for wrapper in HANDLER.take_events() {
+      match wrapper {
+        EventWrapper::StaticEvent(event) => {
+          if HANDLER.is_window_alive(&event) {
+            handler(event.userify(), target, control_flow);
+          }
+        }
+        EventWrapper::EventProxy(EventProxy::DpiChangedProxy {
+          ns_window,
+          suggested_size,
+          scale_factor,
+        }) => change_scale_factor(ns_window, suggested_size, scale_factor, |event| {
+          handler(event.userify(), target, control_flow)
+        }),
+      }
+    }
+    handler(Event::MainEventsCleared, target, control_flow);
+    for window_id in HANDLER.should_redraw() {
+      let event = Event::RedrawRequested(window_id);
+      if HANDLER.is_window_alive(&event) {
+        handler(event.userify(), target, control_flow);
+      }
+    }
+    handler(Event::RedrawEventsCleared, target, control_flow);
+  }
+
   pub fn cleared(panic_info: Weak<PanicInfo>) {
     let panic_info = panic_info
       .upgrade()
@@ -389,6 +607,8 @@ impl AppState {
       HANDLER.handle_nonuser_event(EventWrapper::StaticEvent(Event::RedrawRequested(window_id)));
     }
     HANDLER.handle_nonuser_event(EventWrapper::StaticEvent(Event::RedrawEventsCleared));
+    // The run loop is about to sleep, so no system event is pending.
+    HANDLER.handle_idle();
     HANDLER.set_in_callback(false);
     if HANDLER.should_exit() {
       unsafe {
@@ -441,6 +661,52 @@ unsafe fn window_activation_hack(ns_app: id) {
     }
   }
 }
+
+/// Applies a request made through `EventLoopWindowTargetExtMacOS` to the application. Hiding
+/// remembers the visible windows from front to back, so that showing can order them front again
+/// in the same order.
+unsafe fn apply_app_request(app_delegate: &Object, request: AppRequest) {
+  let ns_app = NSApp();
+  match request {
+    AppRequest::Hide => {
+      let key_window: id = msg_send![ns_app, keyWindow];
+      let windows: id = msg_send![ns_app, orderedWindows];
+      let visible = (0..windows.count())
+        .map(|i| windows.objectAtIndex(i))
+        .filter(|&window| window.isVisible() == YES)
+        .map(IdRef::retain)
+        .collect();
+      get_aux_state_mut(app_delegate).hidden_windows = visible;
+      let () = msg_send![ns_app, hide: nil];
+      // Hiding deactivates the application, which normally resigns the key window already.
+      if key_window != nil && key_window.isKeyWindow() == YES {
+        AppState::queue_event(EventWrapper::StaticEvent(Event::WindowEvent {
+          window_id: WindowId(get_window_id(key_window)),
+          event: WindowEvent::Focused(false),
+        }));
+      }
+    }
+    AppRequest::Show => {
+      let () = msg_send![ns_app, unhide: nil];
+      // Bring the windows back to front from the back one, in the order they had when hidden.
+      let windows = mem::take(&mut get_aux_state_mut(app_delegate).hidden_windows);
+      for window in windows.iter().rev() {
+        if window.isVisible() == YES {
+          let () = msg_send![**window, orderFront: nil];
+        }
+      }
+    }
+    AppRequest::HideOthers => {
+      let () = msg_send![ns_app, hideOtherApplications: nil];
+    }
+    AppRequest::Activate {
+      ignoring_other_apps,
+    } => {
+      ns_app.activateIgnoringOtherApps_(if ignoring_other_apps { YES } else { NO });
+    }
+  }
+}
+
 fn apply_activation_policy(app_delegate: &Object) {
   unsafe {
     use cocoa::appkit::NSApplicationActivationPolicy::*;
@@ -0,0 +1,219 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{cell::Cell, ffi::c_void, path::PathBuf, ptr};
+
+use cocoa::{
+  base::{id, nil, BOOL, NO, YES},
+  foundation::{NSArray, NSInteger, NSRect, NSString},
+};
+use core_graphics::{image::CGImageRef, sys};
+use foreign_types::ForeignTypeRef;
+use objc::{class, msg_send, sel, sel_impl};
+
+use super::{
+  app_state::AppState,
+  event::EventWrapper,
+  event_loop::EventLoopWindowTarget,
+  icon::NS_BITMAP_FORMAT_ALPHA_NONPREMULTIPLIED,
+  observer::{
+    kCFRunLoopCommonModes, CFAbsoluteTimeGetCurrent, CFRelease, CFRunLoopAddTimer,
+    CFRunLoopGetMain, CFRunLoopTimerCreate, CFRunLoopTimerInvalidate, CFRunLoopTimerRef,
+  },
+  services::{pasteboard_files, pasteboard_text},
+  util, OsError,
+};
+use crate::{error::ExternalError, event::Event, window::RgbaImage};
+
+/// `NSBitmapImageFileTypePNG`.
+const NS_BITMAP_IMAGE_FILE_TYPE_PNG: NSInteger = 4;
+
+/// The seconds between two checks of the pasteboard, AppKit doesn't notify its changes.
+const POLL_INTERVAL: f64 = 0.5;
+
+thread_local! {
+  /// The timer polling the pasteboard, with the number of clipboards needing it.
+  static POLL_TIMER: Cell<Option<(CFRunLoopTimerRef, usize)>> = Cell::new(None);
+  /// The `changeCount` of the pasteboard when it was last polled.
+  static CHANGE_COUNT: Cell<NSInteger> = Cell::new(0);
+}
+
+extern "C" fn poll_pasteboard(_timer: CFRunLoopTimerRef, _info: *mut c_void) {
+  let change_count: NSInteger = unsafe { msg_send![general_pasteboard(), changeCount] };
+  if CHANGE_COUNT.with(|count| count.replace(change_count)) != change_count {
+    AppState::queue_event_and_wake(EventWrapper::StaticEvent(Event::ClipboardUpdated));
+  }
+}
+
+fn general_pasteboard() -> id {
+  unsafe { msg_send![class!(NSPasteboard), generalPasteboard] }
+}
+
+fn write_result(written: BOOL) -> Result<(), ExternalError> {
+  if written == YES {
+    Ok(())
+  } else {
+    Err(ExternalError::Os(os_error!(OsError::CreationError(
+      "Couldn't write to the pasteboard"
+    ))))
+  }
+}
+
+/// The general pasteboard.
+pub struct Clipboard {
+  pasteboard: id,
+}
+
+impl Clipboard {
+  pub fn new<T: 'static>(_window_target: &EventLoopWindowTarget<T>) -> Self {
+    let pasteboard = general_pasteboard();
+    POLL_TIMER.with(|poll_timer| {
+      let (timer, count) = poll_timer.get().unwrap_or_else(|| unsafe {
+        let change_count: NSInteger = msg_send![pasteboard, changeCount];
+        CHANGE_COUNT.with(|count| count.set(change_count));
+        let timer = CFRunLoopTimerCreate(
+          ptr::null_mut(),
+          CFAbsoluteTimeGetCurrent() + POLL_INTERVAL,
+          POLL_INTERVAL,
+          0,
+          0,
+          poll_pasteboard,
+          ptr::null_mut(),
+        );
+        CFRunLoopAddTimer(CFRunLoopGetMain(), timer, kCFRunLoopCommonModes);
+        (timer, 0)
+      });
+      poll_timer.set(Some((timer, count + 1)));
+    });
+    Self { pasteboard }
+  }
+
+  pub fn read_text(&self) -> Option<String> {
+    unsafe { pasteboard_text(self.pasteboard) }
+  }
+
+  pub fn write_text(&self, text: &str) -> Result<(), ExternalError> {
+    unsafe {
+      let string = NSString::alloc(nil).init_str(text);
+      let string_type = NSString::alloc(nil).init_str("public.utf8-plain-text");
+      let _: NSInteger = msg_send![self.pasteboard, clearContents];
+      let written: BOOL = msg_send![self.pasteboard, setString: string forType: string_type];
+      let () = msg_send![string_type, release];
+      let () = msg_send![string, release];
+      write_result(written)
+    }
+  }
+
+  pub fn read_image(&self) -> Option<RgbaImage> {
+    unsafe {
+      let image: id = msg_send![class!(NSImage), alloc];
+      let image: id = msg_send![image, initWithPasteboard: self.pasteboard];
+      if image == nil {
+        return None;
+      }
+      // Owned by the image.
+      let cg_image: *mut sys::CGImage = msg_send![
+        image,
+        CGImageForProposedRect: ptr::null_mut::<NSRect>()
+        context: nil
+        hints: nil
+      ];
+      let rgba = (!cg_image.is_null())
+        .then(|| util::cg_image_to_rgba(&CGImageRef::from_ptr(cg_image).to_owned()));
+      let () = msg_send![image, release];
+      rgba
+    }
+  }
+
+  pub fn write_image(&self, image: &RgbaImage) -> Result<(), ExternalError> {
+    unsafe {
+      let color_space = NSString::alloc(nil).init_str("NSDeviceRGBColorSpace");
+      let rep: id = msg_send![class!(NSBitmapImageRep), alloc];
+      let rep: id = msg_send![rep,
+        initWithBitmapDataPlanes: ptr::null_mut::<*mut u8>()
+        pixelsWide: image.width as NSInteger
+        pixelsHigh: image.height as NSInteger
+        bitsPerSample: 8 as NSInteger
+        samplesPerPixel: 4 as NSInteger
+        hasAlpha: YES
+        isPlanar: NO
+        colorSpaceName: color_space
+        bitmapFormat: NS_BITMAP_FORMAT_ALPHA_NONPREMULTIPLIED
+        bytesPerRow: (image.width * 4) as NSInteger
+        bitsPerPixel: 32 as NSInteger
+      ];
+      let () = msg_send![color_space, release];
+      if rep == nil {
+        return write_result(NO);
+      }
+      let data: *mut u8 = msg_send![rep, bitmapData];
+      let row_len = image.width as usize * 4;
+      for (y, row) in image
+        .data
+        .chunks(image.stride as usize)
+        .take(image.height as usize)
+        .enumerate()
+      {
+        ptr::copy_nonoverlapping(row.as_ptr(), data.add(y * row_len), row_len);
+      }
+
+      // PNG for the applications reading it, TIFF for the older ones.
+      let properties: id = msg_send![class!(NSDictionary), dictionary];
+      let png: id = msg_send![
+        rep,
+        representationUsingType: NS_BITMAP_IMAGE_FILE_TYPE_PNG
+        properties: properties
+      ];
+      let tiff: id = msg_send![rep, TIFFRepresentation];
+      let png_type = NSString::alloc(nil).init_str("public.png");
+      let tiff_type = NSString::alloc(nil).init_str("public.tiff");
+      let _: NSInteger = msg_send![self.pasteboard, clearContents];
+      let written: BOOL = msg_send![self.pasteboard, setData: png forType: png_type];
+      let written_tiff: BOOL = msg_send![self.pasteboard, setData: tiff forType: tiff_type];
+      let () = msg_send![tiff_type, release];
+      let () = msg_send![png_type, release];
+      let () = msg_send![rep, release];
+      write_result(if written == YES || written_tiff == YES {
+        YES
+      } else {
+        NO
+      })
+    }
+  }
+
+  pub fn read_files(&self) -> Vec<PathBuf> {
+    unsafe { pasteboard_files(self.pasteboard) }
+  }
+
+  pub fn write_files(&self, files: &[PathBuf]) -> Result<(), ExternalError> {
+    unsafe {
+      let urls: Vec<id> = files
+        .iter()
+        .map(|file| {
+          let path = NSString::alloc(nil).init_str(&file.to_string_lossy());
+          let url: id = msg_send![class!(NSURL), fileURLWithPath: path];
+          let () = msg_send![path, release];
+          url
+        })
+        .collect();
+      let urls = NSArray::arrayWithObjects(nil, &urls);
+      let _: NSInteger = msg_send![self.pasteboard, clearContents];
+      let written: BOOL = msg_send![self.pasteboard, writeObjects: urls];
+      write_result(written)
+    }
+  }
+}
+
+impl Drop for Clipboard {
+  fn drop(&mut self) {
+    POLL_TIMER.with(|poll_timer| match poll_timer.get() {
+      Some((timer, 1)) => unsafe {
+        CFRunLoopTimerInvalidate(timer);
+        CFRelease(timer as _);
+        poll_timer.set(None);
+      },
+      Some((timer, count)) => poll_timer.set(Some((timer, count - 1))),
+      None => {}
+    });
+  }
+}
@@ -0,0 +1,164 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{cell::Cell, ffi::c_void, ptr};
+
+use cocoa::{
+  appkit::{self, NSEvent},
+  base::id,
+};
+use core_foundation::base::CFRelease;
+
+use super::{
+  app_state::AppState,
+  event::EventWrapper,
+  ffi,
+  observer::{kCFRunLoopCommonModes, CFRunLoopAddSource, CFRunLoopGetMain, CFRunLoopSourceRef},
+  OsError, DEVICE_ID,
+};
+use crate::{
+  error::ExternalError,
+  event::{DeviceEvent, ElementState, Event, RawKeyEvent},
+  event_loop::DeviceEventFilter,
+  keyboard::KeyCode,
+};
+
+thread_local! {
+  static FILTER: Cell<DeviceEventFilter> = Cell::new(DeviceEventFilter::default());
+  static KEY_EVENT_TAP: Cell<Option<(ffi::CFMachPortRef, CFRunLoopSourceRef)>> = Cell::new(None);
+}
+
+/// Applies `filter` to the device events. Reporting key events while the application is not
+/// focused requires an event tap, which needs the Input Monitoring permission.
+pub fn set_filter(filter: DeviceEventFilter) -> Result<(), ExternalError> {
+  if filter == DeviceEventFilter::Never {
+    unsafe { install_key_event_tap()? };
+  } else {
+    unsafe { remove_key_event_tap() };
+  }
+  FILTER.with(|f| f.set(filter));
+  Ok(())
+}
+
+pub fn filter() -> DeviceEventFilter {
+  FILTER.with(|f| f.get())
+}
+
+/// Reports key events received by the application, unless the event tap already does.
+pub unsafe fn maybe_dispatch_key_event(event: id) {
+  if filter() != DeviceEventFilter::Unfocused {
+    return;
+  }
+
+  let scancode = event.keyCode();
+  let state = match event.eventType() {
+    appkit::NSKeyDown => Some(ElementState::Pressed),
+    appkit::NSKeyUp => Some(ElementState::Released),
+    appkit::NSFlagsChanged => modifier_state(scancode, event.modifierFlags().bits() as u64),
+    _ => return,
+  };
+  if let Some(state) = state {
+    queue_key_event(scancode, state);
+  }
+}
+
+unsafe fn install_key_event_tap() -> Result<(), ExternalError> {
+  if KEY_EVENT_TAP.with(|tap| tap.get()).is_some() {
+    return Ok(());
+  }
+
+  let events_of_interest =
+    (1 << ffi::kCGEventKeyDown) | (1 << ffi::kCGEventKeyUp) | (1 << ffi::kCGEventFlagsChanged);
+  let port = ffi::CGEventTapCreate(
+    ffi::kCGSessionEventTap,
+    ffi::kCGHeadInsertEventTap,
+    ffi::kCGEventTapOptionListenOnly,
+    events_of_interest,
+    key_event_tap_callback,
+    ptr::null_mut(),
+  );
+  if port.is_null() {
+    return Err(ExternalError::Os(os_error!(OsError::CreationError(
+      "Couldn't create the key event tap, the Input Monitoring permission is missing"
+    ))));
+  }
+
+  let source = ffi::CFMachPortCreateRunLoopSource(ptr::null(), port, 0);
+  CFRunLoopAddSource(CFRunLoopGetMain(), source, kCFRunLoopCommonModes);
+  ffi::CGEventTapEnable(port, true);
+  KEY_EVENT_TAP.with(|tap| tap.set(Some((port, source))));
+  Ok(())
+}
+
+unsafe fn remove_key_event_tap() {
+  if let Some((port, source)) = KEY_EVENT_TAP.with(|tap| tap.take()) {
+    // Invalidating the port also removes its source from the run loop.
+    ffi::CFMachPortInvalidate(port);
+    CFRelease(source as _);
+    CFRelease(port as _);
+  }
+}
+
+extern "C" fn key_event_tap_callback(
+  _proxy: ffi::CGEventTapProxy,
+  event_type: ffi::CGEventType,
+  event: ffi::CGEventRef,
+  _user_info: *mut c_void,
+) -> ffi::CGEventRef {
+  unsafe {
+    let state = match event_type {
+      // The system disables taps that are too slow, or on user request, so turn it back on.
+      ffi::kCGEventTapDisabledByTimeout | ffi::kCGEventTapDisabledByUserInput => {
+        if let Some((port, _)) = KEY_EVENT_TAP.with(|tap| tap.get()) {
+          ffi::CGEventTapEnable(port, true);
+        }
+        None
+      }
+      ffi::kCGEventKeyDown => Some(ElementState::Pressed),
+      ffi::kCGEventKeyUp => Some(ElementState::Released),
+      ffi::kCGEventFlagsChanged => modifier_state(keycode(event), ffi::CGEventGetFlags(event)),
+      _ => None,
+    };
+    if let Some(state) = state {
+      queue_key_event(keycode(event), state);
+    }
+  }
+  event
+}
+
+unsafe fn keycode(event: ffi::CGEventRef) -> u16 {
+  ffi::CGEventGetIntegerValueField(event, ffi::kCGKeyboardEventKeycode) as u16
+}
+
+/// Returns whether the modifier key of a flags changed event was pressed or released, using the
+/// device dependent flags to tell the left and right keys apart.
+fn modifier_state(scancode: u16, flags: u64) -> Option<ElementState> {
+  let mask = match scancode {
+    0x38 => ffi::NX_DEVICELSHIFTKEYMASK,
+    0x3c => ffi::NX_DEVICERSHIFTKEYMASK,
+    0x3b => ffi::NX_DEVICELCTLKEYMASK,
+    0x3e => ffi::NX_DEVICERCTLKEYMASK,
+    0x3a => ffi::NX_DEVICELALTKEYMASK,
+    0x3d => ffi::NX_DEVICERALTKEYMASK,
+    0x37 => ffi::NX_DEVICELCMDKEYMASK,
+    0x36 => ffi::NX_DEVICERCMDKEYMASK,
+    0x39 => ffi::NX_ALPHASHIFTMASK,
+    0x3f => ffi::NX_SECONDARYFNMASK,
+    _ => return None,
+  };
+  Some(match flags & mask != 0 {
+    true => ElementState::Pressed,
+    false => ElementState::Released,
+  })
+}
+
+fn queue_key_event(scancode: u16, state: ElementState) {
+  AppState::queue_event(EventWrapper::StaticEvent(Event::DeviceEvent {
+    device_id: DEVICE_ID,
+    event: DeviceEvent::Key(RawKeyEvent {
+      physical_key: KeyCode::from_scancode(scancode as u32),
+      state,
+    }),
+  }));
+}
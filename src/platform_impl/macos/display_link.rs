@@ -0,0 +1,165 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  ffi::c_void,
+  ptr,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+};
+
+use core_graphics::display::CGDirectDisplayID;
+use dispatch::Queue;
+
+use super::{app_state::AppState, ffi};
+use crate::window::WindowId;
+
+/// The requests of a window to its display link, read by the callback of the link.
+struct Requests {
+  window_id: WindowId,
+  /// Redraws on each refresh, see `Window::request_frame_callbacks`.
+  continuous: AtomicBool,
+  /// Redraws on the next refresh, see `Window::request_redraw_synced`.
+  once: AtomicBool,
+}
+
+/// Requests a redraw of a window on the refreshes of a display, see
+/// `Window::request_frame_callbacks` and `Window::request_redraw_synced`.
+pub struct DisplayLink {
+  link: ffi::CVDisplayLinkRef,
+  /// Owned by the link, which passes it to its callback.
+  requests: *const Requests,
+  display_id: Option<CGDirectDisplayID>,
+}
+
+// The link is thread safe, and its callback only reads the requests.
+unsafe impl Send for DisplayLink {}
+
+impl DisplayLink {
+  pub fn new(window_id: WindowId) -> Option<Self> {
+    unsafe {
+      let mut link = ptr::null_mut();
+      if ffi::CVDisplayLinkCreateWithActiveCGDisplays(&mut link) != ffi::kCVReturnSuccess {
+        return None;
+      }
+      let requests = Arc::into_raw(Arc::new(Requests {
+        window_id,
+        continuous: AtomicBool::new(false),
+        once: AtomicBool::new(false),
+      }));
+      ffi::CVDisplayLinkSetOutputCallback(link, display_link_callback, requests as *mut c_void);
+      Some(Self {
+        link,
+        requests,
+        display_id: None,
+      })
+    }
+  }
+
+  fn requests(&self) -> &Requests {
+    unsafe { &*self.requests }
+  }
+
+  pub fn set_continuous(&mut self, enabled: bool) {
+    self.requests().continuous.store(enabled, Ordering::Release);
+    self.update(self.display_id);
+  }
+
+  /// Redraws on the next refresh, or right away while the window isn't on a display.
+  pub fn request_once(&mut self) {
+    if self.display_id.is_none() {
+      AppState::queue_redraw(self.requests().window_id);
+      return;
+    }
+    self.requests().once.store(true, Ordering::Release);
+    self.update(self.display_id);
+  }
+
+  /// Follows the refreshes of `display_id` if it's `Some` and there's a request, stops
+  /// otherwise.
+  pub fn update(&mut self, display_id: Option<CGDirectDisplayID>) {
+    let requested = self.requests().continuous.load(Ordering::Acquire)
+      || self.requests().once.load(Ordering::Acquire);
+    unsafe {
+      if let Some(display_id) = display_id {
+        if self.display_id != Some(display_id) {
+          ffi::CVDisplayLinkSetCurrentCGDisplay(self.link, display_id);
+        }
+      }
+      if display_id.is_some() && requested {
+        if ffi::CVDisplayLinkIsRunning(self.link) == ffi::FALSE {
+          ffi::CVDisplayLinkStart(self.link);
+        }
+      } else {
+        ffi::CVDisplayLinkStop(self.link);
+      }
+    }
+    // There's no refresh to wait for.
+    if display_id.is_none() && self.requests().once.swap(false, Ordering::AcqRel) {
+      AppState::queue_redraw(self.requests().window_id);
+    }
+    self.display_id = display_id;
+  }
+}
+
+impl Drop for DisplayLink {
+  fn drop(&mut self) {
+    unsafe {
+      // Waits for the callback to return, so the requests can go.
+      ffi::CVDisplayLinkStop(self.link);
+      ffi::CVDisplayLinkRelease(self.link);
+      drop(Arc::from_raw(self.requests));
+    }
+  }
+}
+
+/// A display link retained for the main thread.
+struct IdleLink {
+  link: ffi::CVDisplayLinkRef,
+  requests: Arc<Requests>,
+}
+
+unsafe impl Send for IdleLink {}
+
+impl Drop for IdleLink {
+  fn drop(&mut self) {
+    unsafe { ffi::CVDisplayLinkRelease(self.link) };
+  }
+}
+
+// Runs on the thread of the display link.
+extern "C" fn display_link_callback(
+  display_link: ffi::CVDisplayLinkRef,
+  _in_now: *const c_void,
+  _in_output_time: *const c_void,
+  _flags_in: u64,
+  _flags_out: *mut u64,
+  context: *mut c_void,
+) -> ffi::CVReturn {
+  let requests = unsafe { &*(context as *const Requests) };
+  let once = requests.once.swap(false, Ordering::AcqRel);
+  let continuous = requests.continuous.load(Ordering::Acquire);
+  if continuous || once {
+    AppState::queue_redraw(requests.window_id);
+  }
+  if !continuous {
+    // The link is stopped once there's no request left, from the main thread since stopping it
+    // waits for this callback to return.
+    let idle_link = unsafe {
+      Arc::increment_strong_count(requests);
+      IdleLink {
+        link: ffi::CVDisplayLinkRetain(display_link),
+        requests: Arc::from_raw(requests),
+      }
+    };
+    Queue::main().exec_async(move || {
+      let requests = &idle_link.requests;
+      if !requests.continuous.load(Ordering::Acquire) && !requests.once.load(Ordering::Acquire) {
+        unsafe { ffi::CVDisplayLinkStop(idle_link.link) };
+      }
+    });
+  }
+  ffi::kCVReturnSuccess
+}
@@ -2,19 +2,30 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::HashSet, ffi::c_void, os::raw::c_ushort, sync::Mutex};
+use std::{
+  collections::HashSet,
+  ffi::c_void,
+  os::raw::c_ushort,
+  sync::Mutex,
+  time::{Duration, Instant},
+};
 
 use cocoa::{
   appkit::{NSEvent, NSEventModifierFlags},
   base::id,
+  foundation::NSTimeInterval,
 };
 
-use core_foundation::{base::CFRelease, data::CFDataGetBytePtr};
+use core_foundation::{
+  base::{CFRelease, TCFType},
+  data::CFDataGetBytePtr,
+  string::{CFString, CFStringRef},
+};
 
 use crate::{
   dpi::LogicalSize,
   event::{ElementState, Event, KeyEvent},
-  keyboard::{Key, KeyCode, KeyLocation, ModifiersState, NativeKeyCode},
+  keyboard::{Key, KeyCode, KeyLocation, KeyboardLayout, ModifiersState, NativeKeyCode},
   platform_impl::platform::{
     ffi,
     util::{ns_string_to_rust, IdRef, Never},
@@ -59,6 +70,24 @@ pub struct KeyEventExtra {
   pub key_without_modifiers: Key<'static>,
 }
 
+/// The keyboard layout input source, which stays the same while an input method is selected.
+pub fn keyboard_layout() -> Option<KeyboardLayout> {
+  unsafe {
+    let input_source = ffi::TISCopyCurrentKeyboardLayoutInputSource();
+    if input_source.is_null() {
+      return None;
+    }
+    // Owned by the input source.
+    let id =
+      ffi::TISGetInputSourceProperty(input_source, ffi::kTISPropertyInputSourceID) as CFStringRef;
+    let layout = (!id.is_null()).then(|| KeyboardLayout {
+      id: CFString::wrap_under_get_rule(id).to_string(),
+    });
+    CFRelease(input_source as *mut c_void);
+    layout
+  }
+}
+
 pub fn get_modifierless_char(scancode: u16) -> Key<'static> {
   let mut string = [0; 16];
   let input_source;
@@ -192,6 +221,7 @@ pub fn create_key_event(
     repeat: is_repeat,
     state,
     text,
+    modifiers: event_mods(ns_event),
     platform_specific: KeyEventExtra {
       text_with_all_modifiers,
       key_without_modifiers,
@@ -308,6 +338,24 @@ pub fn extra_function_key_to_code(scancode: u16, string: &str) -> KeyCode {
   }
 }
 
+/// Returns when `ns_event` was generated as an `Instant`. The event timestamp counts the seconds
+/// since system startup, like `systemUptime`.
+pub fn event_timestamp(ns_event: id) -> Instant {
+  let now = Instant::now();
+  let elapsed = unsafe {
+    let timestamp: NSTimeInterval = msg_send![ns_event, timestamp];
+    let process_info: id = msg_send![class!(NSProcessInfo), processInfo];
+    let uptime: NSTimeInterval = msg_send![process_info, systemUptime];
+    uptime - timestamp
+  };
+  if !elapsed.is_finite() || elapsed < 0.0 {
+    return now;
+  }
+  now
+    .checked_sub(Duration::from_secs_f64(elapsed))
+    .unwrap_or(now)
+}
+
 pub fn event_mods(event: id) -> ModifiersState {
   let flags = unsafe { NSEvent::modifierFlags(event) };
   let mut m = ModifiersState::empty();
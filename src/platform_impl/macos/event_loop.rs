@@ -12,10 +12,14 @@ use std::{
   panic::{catch_unwind, resume_unwind, RefUnwindSafe, UnwindSafe},
   process, ptr,
   rc::{Rc, Weak},
+  sync::Arc,
+  time::Instant,
 };
 
 use cocoa::{
-  appkit::{NSApp, NSEventModifierFlags, NSEventSubtype, NSEventType::NSApplicationDefined},
+  appkit::{
+    NSApp, NSEventMask, NSEventModifierFlags, NSEventSubtype, NSEventType::NSApplicationDefined,
+  },
   base::{id, nil, YES},
   foundation::{NSAutoreleasePool, NSInteger, NSPoint, NSTimeInterval},
 };
@@ -25,19 +29,32 @@ use scopeguard::defer;
 use crate::{
   dpi::PhysicalPosition,
   error::ExternalError,
-  event::Event,
-  event_loop::{ControlFlow, EventLoopClosed, EventLoopWindowTarget as RootWindowTarget},
+  event::{Event, StartCause},
+  event_loop::{
+    AccessibilityPrefs, ControlFlow, DeviceEventFilter, EventLoopClosed,
+    EventLoopWindowTarget as RootWindowTarget, IdleDeadline, PowerInfo, SystemAppearance,
+  },
+  keyboard::{Key, KeyCode, KeyboardLayout, ModifiersState},
   monitor::MonitorHandle as RootMonitorHandle,
+  platform::{
+    inject::SyntheticInput,
+    macos::{ActivationPolicy, AppleEvent, DefaultMenuConfig, ReopenBehavior},
+  },
   platform_impl::platform::{
     app::APP_CLASS,
-    app_delegate::APP_DELEGATE_CLASS,
+    app_delegate::{get_aux_state_mut, AppRequest, APP_DELEGATE_CLASS},
     app_state::AppState,
+    device,
+    event::{code_to_key, get_modifierless_char, keyboard_layout},
+    ffi, global_shortcut, inject,
     monitor::{self, MonitorHandle},
     observer::*,
+    power, services, single_instance,
     util::{self, IdRef},
+    WindowId,
   },
   platform_impl::set_progress_indicator,
-  window::ProgressBarState,
+  window::{ProgressBarState, WindowId as RootWindowId},
 };
 
 #[derive(Default)]
@@ -72,16 +89,91 @@ impl PanicInfo {
 pub struct EventLoopWindowTarget<T: 'static> {
   pub sender: Sender<T>, // this is only here to be cloned elsewhere
   pub receiver: Receiver<T>,
+  pub(crate) headless: bool,
+  /// See `set_idle_callback`.
+  pub(crate) idle_callback: RefCell<Option<Box<dyn FnMut(IdleDeadline)>>>,
 }
 
 impl<T> Default for EventLoopWindowTarget<T> {
   fn default() -> Self {
     let (sender, receiver) = channel::unbounded();
-    EventLoopWindowTarget { sender, receiver }
+    EventLoopWindowTarget {
+      sender,
+      receiver,
+      headless: false,
+      idle_callback: RefCell::new(None),
+    }
   }
 }
 
 impl<T: 'static> EventLoopWindowTarget<T> {
+  #[inline]
+  pub fn is_headless(&self) -> bool {
+    self.headless
+  }
+
+  #[inline]
+  pub fn has_window(&self, window_id: WindowId) -> bool {
+    AppState::has_window(RootWindowId(window_id))
+  }
+
+  #[inline]
+  pub fn create_waker(&self) -> EventLoopWaker {
+    EventLoopWaker::new()
+  }
+
+  #[inline]
+  pub fn hide_application(&self) {
+    AppState::app_request(AppRequest::Hide)
+  }
+
+  #[inline]
+  pub fn show_application(&self) {
+    AppState::app_request(AppRequest::Show)
+  }
+
+  #[inline]
+  pub fn hide_other_applications(&self) {
+    AppState::app_request(AppRequest::HideOthers)
+  }
+
+  #[inline]
+  pub fn activate(&self, ignoring_other_apps: bool) {
+    AppState::app_request(AppRequest::Activate {
+      ignoring_other_apps,
+    })
+  }
+
+  #[inline]
+  pub fn set_default_reopen_behavior(&self, behavior: ReopenBehavior) {
+    AppState::set_default_reopen_behavior(behavior)
+  }
+
+  #[inline]
+  pub fn register_service(&self, message: &str) {
+    unsafe { services::register(message) }
+  }
+
+  #[inline]
+  pub fn inject_input(&self, input: SyntheticInput) -> Result<(), ExternalError> {
+    inject::inject_input(input)
+  }
+
+  #[inline]
+  pub fn register_global_shortcut(
+    &self,
+    id: u32,
+    modifiers: ModifiersState,
+    key: KeyCode,
+  ) -> Result<(), ExternalError> {
+    unsafe { global_shortcut::register(id, modifiers, key) }
+  }
+
+  #[inline]
+  pub fn unregister_global_shortcut(&self, id: u32) -> Result<(), ExternalError> {
+    unsafe { global_shortcut::unregister(id) }
+  }
+
   #[inline]
   pub fn available_monitors(&self) -> VecDeque<MonitorHandle> {
     monitor::available_monitors()
@@ -113,7 +205,7 @@ impl<T: 'static> EventLoopWindowTarget<T> {
   }
   #[inline]
   pub fn cursor_position(&self) -> Result<PhysicalPosition<f64>, ExternalError> {
-    let point = util::cursor_position()?;
+    let point = util::cursor_position();
     if let Some(m) = self.monitor_from_point(point.x, point.y) {
       Ok(point.to_physical(m.scale_factor()))
     } else {
@@ -121,6 +213,128 @@ impl<T: 'static> EventLoopWindowTarget<T> {
     }
   }
 
+  #[inline]
+  pub fn set_device_event_filter(&self, filter: DeviceEventFilter) -> Result<(), ExternalError> {
+    device::set_filter(filter)
+  }
+
+  #[inline]
+  pub fn accessibility_preferences(&self) -> AccessibilityPrefs {
+    util::accessibility_prefs()
+  }
+
+  #[inline]
+  pub fn system_appearance(&self) -> SystemAppearance {
+    util::system_appearance()
+  }
+
+  #[inline]
+  pub fn power_info(&self) -> PowerInfo {
+    power::power_info()
+  }
+
+  #[inline]
+  pub fn set_idle_callback(&self, callback: Option<Box<dyn FnMut(IdleDeadline)>>) {
+    *self.idle_callback.borrow_mut() = callback;
+  }
+
+  pub fn run_nested_until(
+    &self,
+    target: &RootWindowTarget<T>,
+    predicate: &mut dyn FnMut() -> bool,
+    handler: &mut dyn FnMut(Event<'_, T>, &RootWindowTarget<T>, &mut ControlFlow),
+  ) -> Result<(), ExternalError> {
+    let mut control_flow = ControlFlow::Wait;
+    let mut cause = StartCause::WaitCancelled {
+      start: Instant::now(),
+      requested_resume: None,
+    };
+    unsafe {
+      let app = NSApp();
+      let mode = ffi::NSModalPanelRunLoopMode;
+      while !predicate() {
+        let pool = NSAutoreleasePool::new(nil);
+        AppState::nested_iteration(cause, target, handler, &mut control_flow);
+        pool.drain();
+        if let ControlFlow::ExitWithCode(_) = control_flow {
+          break;
+        }
+        if predicate() {
+          break;
+        }
+
+        let start = Instant::now();
+        let timeout = match control_flow {
+          _ if AppState::has_pending_events() || !self.receiver.is_empty() => 0.0,
+          ControlFlow::WaitUntil(requested_resume) => requested_resume
+            .saturating_duration_since(start)
+            .as_secs_f64(),
+          ControlFlow::Poll => 0.0,
+          _ => 1.0e10,
+        };
+        let pool = NSAutoreleasePool::new(nil);
+        // Returns once a source was handled, the window server and the wakers included.
+        CFRunLoopRunInMode(mode as CFRunLoopMode, timeout, 1);
+        loop {
+          let distant_past: id = msg_send![class!(NSDate), distantPast];
+          let event: id = msg_send![app,
+              nextEventMatchingMask: NSEventMask::NSAnyEventMask.bits()
+              untilDate: distant_past
+              inMode: mode
+              dequeue: YES
+          ];
+          if event == nil {
+            break;
+          }
+          // The views queue the events for the next iteration.
+          let () = msg_send![app, sendEvent: event];
+        }
+        pool.drain();
+
+        cause = match control_flow {
+          ControlFlow::Poll => StartCause::Poll,
+          ControlFlow::WaitUntil(requested_resume) if Instant::now() >= requested_resume => {
+            StartCause::ResumeTimeReached {
+              start,
+              requested_resume,
+              resumed: Instant::now(),
+            }
+          }
+          ControlFlow::WaitUntil(requested_resume) => StartCause::WaitCancelled {
+            start,
+            requested_resume: Some(requested_resume),
+          },
+          _ => StartCause::WaitCancelled {
+            start,
+            requested_resume: None,
+          },
+        };
+      }
+      // The events queued since the last iteration wait for the next iteration of the outer run
+      // loop.
+      CFRunLoopWakeUp(CFRunLoopGetMain());
+    }
+    Ok(())
+  }
+
+  #[inline]
+  pub fn key_for_keycode(&self, code: KeyCode) -> Option<Key<'static>> {
+    let scancode = u16::try_from(code.to_scancode()?).ok()?;
+    let key = match code_to_key(code, scancode) {
+      Key::Unidentified(_) => get_modifierless_char(scancode),
+      key => key,
+    };
+    match key {
+      Key::Unidentified(_) => None,
+      key => Some(key),
+    }
+  }
+
+  #[inline]
+  pub fn keyboard_layout(&self) -> Option<KeyboardLayout> {
+    keyboard_layout()
+  }
+
   #[inline]
   pub fn set_progress_bar(&self, progress: ProgressBarState) {
     set_progress_indicator(progress);
@@ -142,11 +356,16 @@ pub struct EventLoop<T: 'static> {
   _callback: Option<Rc<RefCell<dyn FnMut(Event<'_, T>, &RootWindowTarget<T>, &mut ControlFlow)>>>,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
-pub(crate) struct PlatformSpecificEventLoopAttributes {}
+#[derive(Default)]
+pub(crate) struct PlatformSpecificEventLoopAttributes {
+  pub(crate) headless: bool,
+  pub(crate) single_instance: Option<String>,
+  pub(crate) default_menu: Option<DefaultMenuConfig>,
+  pub(crate) apple_event_handler: Option<Box<dyn Fn(AppleEvent) -> bool + 'static>>,
+}
 
 impl<T> EventLoop<T> {
-  pub(crate) fn new(_: &PlatformSpecificEventLoopAttributes) -> Self {
+  pub(crate) fn new(attributes: &mut PlatformSpecificEventLoopAttributes) -> Self {
     let panic_info: Rc<PanicInfo> = Default::default();
     setup_control_flow_observers(Rc::downgrade(&panic_info));
 
@@ -155,6 +374,10 @@ impl<T> EventLoop<T> {
         panic!("On macOS, `EventLoop` must be created on the main thread!");
       }
 
+      if let Some(identifier) = &attributes.single_instance {
+        single_instance::acquire(identifier);
+      }
+
       // This must be done before `NSApp()` (equivalent to sending
       // `sharedApplication`) is called anywhere else, or we'll end up
       // with the wrong `NSApplication` class and the wrong thread could
@@ -165,13 +388,34 @@ impl<T> EventLoop<T> {
       let pool = NSAutoreleasePool::new(nil);
       let _: () = msg_send![app, setDelegate:*delegate];
       let _: () = msg_send![pool, drain];
+      if attributes.headless {
+        // No Dock icon, menu bar or activation for an app that never shows a window.
+        let mut aux_state = get_aux_state_mut(&**delegate);
+        aux_state.activation_policy = ActivationPolicy::Prohibited;
+        aux_state.activate_ignoring_other_apps = false;
+      } else if let Some(config) = attributes.default_menu {
+        let mut aux_state = get_aux_state_mut(&**delegate);
+        aux_state.create_default_menu = true;
+        aux_state.default_menu_config = config;
+      }
+      get_aux_state_mut(&**delegate).apple_event_handler =
+        attributes.apple_event_handler.take().map(Rc::from);
       delegate
     };
 
     EventLoop {
       delegate,
       window_target: Rc::new(RootWindowTarget {
-        p: Default::default(),
+        p: EventLoopWindowTarget {
+          headless: attributes.headless,
+          ..Default::default()
+        },
+        exit: Default::default(),
+        executor: Default::default(),
+        captures: Default::default(),
+        window_motion_coalescing: Default::default(),
+        window_registry: Default::default(),
+        user_events: Default::default(),
         _marker: PhantomData,
       }),
       panic_info,
@@ -179,6 +423,12 @@ impl<T> EventLoop<T> {
     }
   }
 
+  pub(crate) fn try_new(
+    attributes: &mut PlatformSpecificEventLoopAttributes,
+  ) -> Result<Self, ExternalError> {
+    Ok(Self::new(attributes))
+  }
+
   pub fn window_target(&self) -> &RootWindowTarget<T> {
     &self.window_target
   }
@@ -343,3 +593,52 @@ impl<T> Proxy<T> {
     Ok(())
   }
 }
+
+/// The run loop source of an `EventLoopWaker`, shared by its clones and removed from the run loop
+/// once the last one is dropped.
+struct WakerSource(CFRunLoopSourceRef);
+
+unsafe impl Send for WakerSource {}
+unsafe impl Sync for WakerSource {}
+
+impl Drop for WakerSource {
+  fn drop(&mut self) {
+    unsafe {
+      // The run loop keeps the source added to it alive, invalidating removes it.
+      CFRunLoopSourceInvalidate(self.0);
+      CFRelease(self.0 as _);
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct EventLoopWaker {
+  source: Arc<WakerSource>,
+}
+
+impl EventLoopWaker {
+  fn new() -> Self {
+    unsafe {
+      // the source only exists to wake up the eventloop, like the one of `Proxy`
+      extern "C" fn event_loop_waker_handler(_: *mut c_void) {}
+
+      let rl = CFRunLoopGetMain();
+      let mut context: CFRunLoopSourceContext = mem::zeroed();
+      context.perform = Some(event_loop_waker_handler);
+      let source = CFRunLoopSourceCreate(ptr::null_mut(), CFIndex::max_value() - 1, &mut context);
+      CFRunLoopAddSource(rl, source, kCFRunLoopCommonModes);
+
+      EventLoopWaker {
+        source: Arc::new(WakerSource(source)),
+      }
+    }
+  }
+
+  pub fn wake(&self) {
+    unsafe {
+      CFRunLoopSourceSignal(self.source.0);
+      let rl = CFRunLoopGetMain();
+      CFRunLoopWakeUp(rl);
+    }
+  }
+}
@@ -13,13 +13,20 @@
 
 use std::ffi::c_void;
 
+use super::observer::CFRunLoopSourceRef;
+
 use cocoa::{
   appkit::CGPoint,
   base::id,
   foundation::{NSInteger, NSUInteger},
 };
 use core_foundation::{
-  array::CFArrayRef, data::CFDataRef, dictionary::CFDictionaryRef, string::CFStringRef,
+  array::CFArrayRef,
+  base::{CFAllocatorRef, CFIndex, CFTypeRef},
+  data::CFDataRef,
+  date::CFTimeInterval,
+  dictionary::CFDictionaryRef,
+  string::CFStringRef,
   uuid::CFUUIDRef,
 };
 use core_graphics::{
@@ -29,6 +36,12 @@ use core_graphics::{
 };
 pub const NSNotFound: NSInteger = NSInteger::max_value();
 
+pub const NSWindowOcclusionStateVisible: NSUInteger = 1 << 1;
+
+pub const NSTabletPointEventSubtype: i16 = 1;
+pub const NSTabletProximityEventSubtype: i16 = 2;
+pub const NSPointingDeviceTypeEraser: NSUInteger = 3;
+
 #[repr(C)]
 pub struct NSRange {
   pub location: NSUInteger,
@@ -116,9 +129,15 @@ pub const kCGDesktopIconWindowLevelKey: NSInteger = 18;
 pub const kCGCursorWindowLevelKey: NSInteger = 19;
 pub const kCGNumberOfWindowLevelKeys: NSInteger = 20;
 
+// `kCGMinimumWindowLevel + 20`, see CGWindowLevel.h
+pub const kCGDesktopWindowLevel: CGWindowLevel = i32::MIN + 25;
+// The level of `kCGScreenSaverWindowLevelKey`, see CGWindowLevel.h
+pub const kCGScreenSaverWindowLevel: CGWindowLevel = 1000;
+
 #[derive(Debug, Clone, Copy)]
 #[repr(isize)]
 pub enum NSWindowLevel {
+  NSDesktopWindowLevel = kCGDesktopWindowLevel as _,
   BelowNormalWindowLevel = (kCGBaseWindowLevelKey - 1) as _,
   NSNormalWindowLevel = kCGBaseWindowLevelKey as _,
   NSFloatingWindowLevel = kCGFloatingWindowLevelKey as _,
@@ -127,7 +146,7 @@ pub enum NSWindowLevel {
   NSMainMenuWindowLevel = kCGMainMenuWindowLevelKey as _,
   NSStatusWindowLevel = kCGStatusWindowLevelKey as _,
   NSPopUpMenuWindowLevel = kCGPopUpMenuWindowLevelKey as _,
-  NSScreenSaverWindowLevel = kCGScreenSaverWindowLevelKey as _,
+  NSScreenSaverWindowLevel = kCGScreenSaverWindowLevel as _,
 }
 
 pub type CGDisplayFadeInterval = f32;
@@ -175,6 +194,17 @@ pub const IO8BitOverlayPixels: &str = "O8";
 pub type CGWindowLevel = i32;
 pub type CGDisplayModeRef = *mut libc::c_void;
 
+pub type CGDisplayChangeSummaryFlags = u32;
+pub const kCGDisplayAddFlag: CGDisplayChangeSummaryFlags = 1 << 4;
+pub const kCGDisplayRemoveFlag: CGDisplayChangeSummaryFlags = 1 << 5;
+pub const kCGDisplayEnabledFlag: CGDisplayChangeSummaryFlags = 1 << 8;
+pub const kCGDisplayDisabledFlag: CGDisplayChangeSummaryFlags = 1 << 9;
+pub type CGDisplayReconfigurationCallBack = extern "C" fn(
+  display: CGDirectDisplayID,
+  flags: CGDisplayChangeSummaryFlags,
+  userInfo: *mut c_void,
+);
+
 // `CGDisplayCreateUUIDFromDisplayID` comes from the `ColorSync` framework.
 // However, that framework was only introduced "publicly" in macOS 10.13.
 //
@@ -226,14 +256,144 @@ extern "C" {
     display: CGDirectDisplayID,
     options: CFDictionaryRef,
   ) -> CFArrayRef;
+  pub fn CGDisplayCopyDisplayMode(display: CGDirectDisplayID) -> CGDisplayModeRef;
   pub fn CGDisplayModeGetPixelWidth(mode: CGDisplayModeRef) -> usize;
   pub fn CGDisplayModeGetPixelHeight(mode: CGDisplayModeRef) -> usize;
   pub fn CGDisplayModeGetRefreshRate(mode: CGDisplayModeRef) -> f64;
   pub fn CGDisplayModeCopyPixelEncoding(mode: CGDisplayModeRef) -> CFStringRef;
   pub fn CGDisplayModeRetain(mode: CGDisplayModeRef);
   pub fn CGDisplayModeRelease(mode: CGDisplayModeRef);
+  pub fn CGDisplayRegisterReconfigurationCallback(
+    callback: CGDisplayReconfigurationCallBack,
+    userInfo: *mut c_void,
+  ) -> CGError;
+  pub fn CGDisplayRemoveReconfigurationCallback(
+    callback: CGDisplayReconfigurationCallBack,
+    userInfo: *mut c_void,
+  ) -> CGError;
+  pub fn CGEventTapCreate(
+    tap: CGEventTapLocation,
+    place: CGEventTapPlacement,
+    options: CGEventTapOptions,
+    eventsOfInterest: CGEventMask,
+    callback: CGEventTapCallBack,
+    userInfo: *mut c_void,
+  ) -> CFMachPortRef;
+  pub fn CGEventTapEnable(tap: CFMachPortRef, enable: bool);
+  pub fn CGEventGetIntegerValueField(event: CGEventRef, field: CGEventField) -> i64;
+  pub fn CGEventGetFlags(event: CGEventRef) -> CGEventFlags;
+  pub fn CGEventSourceCreate(state_id: CGEventSourceStateID) -> CGEventSourceRef;
+  pub fn CGEventSourceSetLocalEventsSuppressionInterval(source: CGEventSourceRef, seconds: f64);
+  pub fn CGEventCreate(source: CGEventSourceRef) -> CGEventRef;
+  pub fn CGEventGetLocation(event: CGEventRef) -> CGPoint;
+  pub fn CGEventCreateKeyboardEvent(
+    source: CGEventSourceRef,
+    virtualKey: u16,
+    keyDown: bool,
+  ) -> CGEventRef;
+  pub fn CGEventKeyboardSetUnicodeString(
+    event: CGEventRef,
+    stringLength: UniCharCount,
+    unicodeString: *const UniChar,
+  );
+  pub fn CGEventCreateMouseEvent(
+    source: CGEventSourceRef,
+    mouseType: CGEventType,
+    mouseCursorPosition: CGPoint,
+    mouseButton: CGMouseButton,
+  ) -> CGEventRef;
+  pub fn CGEventCreateScrollWheelEvent2(
+    source: CGEventSourceRef,
+    units: CGScrollEventUnit,
+    wheelCount: u32,
+    wheel1: i32,
+    wheel2: i32,
+    wheel3: i32,
+  ) -> CGEventRef;
+  pub fn CGEventSetIntegerValueField(event: CGEventRef, field: CGEventField, value: i64);
+  pub fn CGEventPost(tap: CGEventTapLocation, event: CGEventRef);
+}
+
+pub type CGEventSourceRef = *mut c_void;
+pub type CGEventSourceStateID = i32;
+pub const kCGEventSourceStateCombinedSessionState: CGEventSourceStateID = 0;
+pub const kCGEventSourceStateHIDSystemState: CGEventSourceStateID = 1;
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+  pub fn CFMachPortCreateRunLoopSource(
+    allocator: *const c_void,
+    port: CFMachPortRef,
+    order: isize,
+  ) -> CFRunLoopSourceRef;
+  pub fn CFMachPortInvalidate(port: CFMachPortRef);
 }
 
+pub type CFMachPortRef = *mut c_void;
+pub type CGEventRef = *mut c_void;
+pub type CGEventTapProxy = *mut c_void;
+pub type CGEventTapCallBack = extern "C" fn(
+  proxy: CGEventTapProxy,
+  event_type: CGEventType,
+  event: CGEventRef,
+  user_info: *mut c_void,
+) -> CGEventRef;
+
+pub type CGEventTapLocation = u32;
+pub const kCGHIDEventTap: CGEventTapLocation = 0;
+pub const kCGSessionEventTap: CGEventTapLocation = 1;
+
+pub type CGEventTapPlacement = u32;
+pub const kCGHeadInsertEventTap: CGEventTapPlacement = 0;
+
+pub type CGEventTapOptions = u32;
+pub const kCGEventTapOptionDefault: CGEventTapOptions = 0;
+pub const kCGEventTapOptionListenOnly: CGEventTapOptions = 1;
+
+pub type CGEventType = u32;
+pub const kCGEventLeftMouseDown: CGEventType = 1;
+pub const kCGEventLeftMouseUp: CGEventType = 2;
+pub const kCGEventRightMouseDown: CGEventType = 3;
+pub const kCGEventRightMouseUp: CGEventType = 4;
+pub const kCGEventMouseMoved: CGEventType = 5;
+pub const kCGEventLeftMouseDragged: CGEventType = 6;
+pub const kCGEventRightMouseDragged: CGEventType = 7;
+pub const kCGEventKeyDown: CGEventType = 10;
+pub const kCGEventKeyUp: CGEventType = 11;
+pub const kCGEventFlagsChanged: CGEventType = 12;
+pub const kCGEventOtherMouseDown: CGEventType = 25;
+pub const kCGEventOtherMouseUp: CGEventType = 26;
+pub const kCGEventOtherMouseDragged: CGEventType = 27;
+pub const kCGEventTapDisabledByTimeout: CGEventType = 0xFFFFFFFE;
+pub const kCGEventTapDisabledByUserInput: CGEventType = 0xFFFFFFFF;
+
+pub type CGEventMask = u64;
+pub type CGEventField = u32;
+pub const kCGMouseEventButtonNumber: CGEventField = 3;
+pub const kCGKeyboardEventKeycode: CGEventField = 9;
+
+pub type CGMouseButton = u32;
+pub const kCGMouseButtonLeft: CGMouseButton = 0;
+pub const kCGMouseButtonRight: CGMouseButton = 1;
+pub const kCGMouseButtonCenter: CGMouseButton = 2;
+
+pub type CGScrollEventUnit = u32;
+pub const kCGScrollEventUnitPixel: CGScrollEventUnit = 0;
+pub const kCGScrollEventUnitLine: CGScrollEventUnit = 1;
+
+// Device dependent modifier flags, see IOKit/hidsystem/IOLLEvent.h
+pub type CGEventFlags = u64;
+pub const NX_DEVICELCTLKEYMASK: CGEventFlags = 0x00000001;
+pub const NX_DEVICELSHIFTKEYMASK: CGEventFlags = 0x00000002;
+pub const NX_DEVICERSHIFTKEYMASK: CGEventFlags = 0x00000004;
+pub const NX_DEVICELCMDKEYMASK: CGEventFlags = 0x00000008;
+pub const NX_DEVICERCMDKEYMASK: CGEventFlags = 0x00000010;
+pub const NX_DEVICELALTKEYMASK: CGEventFlags = 0x00000020;
+pub const NX_DEVICERALTKEYMASK: CGEventFlags = 0x00000040;
+pub const NX_DEVICERCTLKEYMASK: CGEventFlags = 0x00002000;
+pub const NX_ALPHASHIFTMASK: CGEventFlags = 0x00010000;
+pub const NX_SECONDARYFNMASK: CGEventFlags = 0x00800000;
+
 #[repr(transparent)]
 pub struct TISInputSource(std::ffi::c_void);
 pub type TISInputSourceRef = *mut TISInputSource;
@@ -251,9 +411,159 @@ pub const kUCKeyActionDisplay: u16 = 3;
 #[allow(non_upper_case_globals)]
 pub const kUCKeyTranslateNoDeadKeysMask: OptionBits = 1;
 
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {
+  pub static NSAllRomanInputSourcesLocaleIdentifier: id;
+  pub static NSModalPanelRunLoopMode: id;
+  pub static NSPasteboardURLReadingFileURLsOnlyKey: id;
+}
+
+pub type IOPowerSourceCallbackType = extern "C" fn(context: *mut c_void);
+
+pub type CVDisplayLinkRef = *mut c_void;
+pub type CVReturn = i32;
+#[allow(non_upper_case_globals)]
+pub const kCVReturnSuccess: CVReturn = 0;
+/// The timestamps are `*const CVTimeStamp`, which tao doesn't read.
+pub type CVDisplayLinkOutputCallback = extern "C" fn(
+  display_link: CVDisplayLinkRef,
+  in_now: *const c_void,
+  in_output_time: *const c_void,
+  flags_in: u64,
+  flags_out: *mut u64,
+  context: *mut c_void,
+) -> CVReturn;
+
+#[link(name = "CoreVideo", kind = "framework")]
+extern "C" {
+  pub fn CVDisplayLinkCreateWithActiveCGDisplays(
+    display_link_out: *mut CVDisplayLinkRef,
+  ) -> CVReturn;
+  pub fn CVDisplayLinkSetOutputCallback(
+    display_link: CVDisplayLinkRef,
+    callback: CVDisplayLinkOutputCallback,
+    context: *mut c_void,
+  ) -> CVReturn;
+  pub fn CVDisplayLinkSetCurrentCGDisplay(
+    display_link: CVDisplayLinkRef,
+    display_id: CGDirectDisplayID,
+  ) -> CVReturn;
+  pub fn CVDisplayLinkStart(display_link: CVDisplayLinkRef) -> CVReturn;
+  pub fn CVDisplayLinkStop(display_link: CVDisplayLinkRef) -> CVReturn;
+  pub fn CVDisplayLinkIsRunning(display_link: CVDisplayLinkRef) -> Boolean;
+  pub fn CVDisplayLinkRetain(display_link: CVDisplayLinkRef) -> CVDisplayLinkRef;
+  pub fn CVDisplayLinkRelease(display_link: CVDisplayLinkRef);
+}
+
+#[link(name = "IOKit", kind = "framework")]
+extern "C" {
+  pub fn IOPSCopyPowerSourcesInfo() -> CFTypeRef;
+  pub fn IOPSCopyPowerSourcesList(blob: CFTypeRef) -> CFArrayRef;
+  pub fn IOPSGetPowerSourceDescription(blob: CFTypeRef, ps: CFTypeRef) -> CFDictionaryRef;
+  pub fn IOPSGetProvidingPowerSourceType(snapshot: CFTypeRef) -> CFStringRef;
+  pub fn IOPSNotificationCreateRunLoopSource(
+    callback: IOPowerSourceCallbackType,
+    context: *mut c_void,
+  ) -> CFRunLoopSourceRef;
+  pub fn IOPMAssertionCreateWithName(
+    assertion_type: CFStringRef,
+    assertion_level: IOPMAssertionLevel,
+    assertion_name: CFStringRef,
+    assertion_id: *mut IOPMAssertionID,
+  ) -> IOReturn;
+  pub fn IOPMAssertionRelease(assertion_id: IOPMAssertionID) -> IOReturn;
+}
+
+#[link(name = "UserNotifications", kind = "framework")]
+extern "C" {
+  pub static UNNotificationDefaultActionIdentifier: id;
+}
+
+pub type IOPMAssertionID = u32;
+pub type IOPMAssertionLevel = u32;
+pub type IOReturn = i32;
+
+pub const kIOPMAssertionLevelOn: IOPMAssertionLevel = 255;
+pub const kIOReturnSuccess: IOReturn = 0;
+
+pub enum __CFMessagePort {}
+pub type CFMessagePortRef = *mut __CFMessagePort;
+
+pub type CFMessagePortCallBack = extern "C" fn(
+  local: CFMessagePortRef,
+  msgid: i32,
+  data: CFDataRef,
+  info: *mut c_void,
+) -> CFDataRef;
+
+pub const kCFMessagePortSuccess: i32 = 0;
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+  pub fn CFMessagePortCreateLocal(
+    allocator: CFAllocatorRef,
+    name: CFStringRef,
+    callout: CFMessagePortCallBack,
+    context: *mut c_void,
+    shouldFreeInfo: *mut Boolean,
+  ) -> CFMessagePortRef;
+  pub fn CFMessagePortCreateRemote(
+    allocator: CFAllocatorRef,
+    name: CFStringRef,
+  ) -> CFMessagePortRef;
+  pub fn CFMessagePortSendRequest(
+    remote: CFMessagePortRef,
+    msgid: i32,
+    data: CFDataRef,
+    sendTimeout: CFTimeInterval,
+    rcvTimeout: CFTimeInterval,
+    replyMode: CFStringRef,
+    returnData: *mut CFDataRef,
+  ) -> i32;
+  pub fn CFMessagePortCreateRunLoopSource(
+    allocator: CFAllocatorRef,
+    local: CFMessagePortRef,
+    order: CFIndex,
+  ) -> CFRunLoopSourceRef;
+}
+
+pub type EventTargetRef = *mut c_void;
+pub type EventHandlerRef = *mut c_void;
+pub type EventHandlerCallRef = *mut c_void;
+pub type EventRef = *mut c_void;
+pub type EventHotKeyRef = *mut c_void;
+pub type EventHandlerProcPtr =
+  extern "C" fn(next: EventHandlerCallRef, event: EventRef, user_data: *mut c_void) -> OSStatus;
+
+#[repr(C)]
+pub struct EventTypeSpec {
+  pub event_class: u32,
+  pub event_kind: u32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+pub struct EventHotKeyID {
+  pub signature: u32,
+  pub id: u32,
+}
+
+/// `'keyb'`
+pub const kEventClassKeyboard: u32 = 0x6b657962;
+pub const kEventHotKeyPressed: u32 = 5;
+/// `'----'`
+pub const kEventParamDirectObject: u32 = 0x2d2d2d2d;
+/// `'hkid'`
+pub const typeEventHotKeyID: u32 = 0x686b6964;
+pub const cmdKey: u32 = 1 << 8;
+pub const shiftKey: u32 = 1 << 9;
+pub const optionKey: u32 = 1 << 11;
+pub const controlKey: u32 = 1 << 12;
+
 #[link(name = "Carbon", kind = "framework")]
 extern "C" {
   pub static kTISPropertyUnicodeKeyLayoutData: CFStringRef;
+  pub static kTISPropertyInputSourceID: CFStringRef;
 
   #[allow(non_snake_case)]
   pub fn TISGetInputSourceProperty(
@@ -265,6 +575,37 @@ extern "C" {
 
   pub fn LMGetKbdType() -> u8;
 
+  pub fn EnableSecureEventInput() -> OSStatus;
+  pub fn DisableSecureEventInput() -> OSStatus;
+
+  pub fn GetApplicationEventTarget() -> EventTargetRef;
+  pub fn InstallEventHandler(
+    target: EventTargetRef,
+    handler: EventHandlerProcPtr,
+    num_types: u32,
+    list: *const EventTypeSpec,
+    user_data: *mut c_void,
+    out_ref: *mut EventHandlerRef,
+  ) -> OSStatus;
+  pub fn GetEventParameter(
+    event: EventRef,
+    name: u32,
+    desired_type: u32,
+    actual_type: *mut u32,
+    buffer_size: usize,
+    actual_size: *mut usize,
+    data: *mut c_void,
+  ) -> OSStatus;
+  pub fn RegisterEventHotKey(
+    key_code: u32,
+    modifiers: u32,
+    hot_key_id: EventHotKeyID,
+    target: EventTargetRef,
+    options: u32,
+    out_ref: *mut EventHotKeyRef,
+  ) -> OSStatus;
+  pub fn UnregisterEventHotKey(hot_key: EventHotKeyRef) -> OSStatus;
+
   #[allow(non_snake_case)]
   pub fn UCKeyTranslate(
     keyLayoutPtr: *const UCKeyboardLayout,
@@ -0,0 +1,129 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::HashMap, mem, ptr, sync::Mutex};
+
+use super::{app_state::AppState, event::EventWrapper, ffi, OsError};
+use crate::{
+  error::ExternalError,
+  event::Event,
+  event_loop::GlobalShortcutId,
+  keyboard::{KeyCode, ModifiersState},
+};
+
+/// `'tao '`, the signature of the hot keys of Tao.
+const SIGNATURE: u32 = 0x74616f20;
+
+/// The hot keys by id, stored as addresses. Only used on the main thread.
+#[derive(Default)]
+struct HotKeys {
+  handler_installed: bool,
+  hot_keys: HashMap<u32, usize>,
+}
+
+lazy_static! {
+  static ref HOT_KEYS: Mutex<HotKeys> = Default::default();
+}
+
+fn os_error(status: ffi::OSStatus) -> ExternalError {
+  ExternalError::Os(os_error!(OsError::OSStatus(status)))
+}
+
+pub unsafe fn register(
+  id: u32,
+  modifiers: ModifiersState,
+  key: KeyCode,
+) -> Result<(), ExternalError> {
+  let key_code = key
+    .to_scancode()
+    .ok_or(ExternalError::InvalidInput("the key has no scancode"))?;
+  let mut carbon_modifiers = 0;
+  if modifiers.shift_key() {
+    carbon_modifiers |= ffi::shiftKey;
+  }
+  if modifiers.control_key() {
+    carbon_modifiers |= ffi::controlKey;
+  }
+  if modifiers.alt_key() {
+    carbon_modifiers |= ffi::optionKey;
+  }
+  if modifiers.super_key() {
+    carbon_modifiers |= ffi::cmdKey;
+  }
+
+  let mut hot_keys = HOT_KEYS.lock().unwrap();
+  if !hot_keys.handler_installed {
+    let event_type = ffi::EventTypeSpec {
+      event_class: ffi::kEventClassKeyboard,
+      event_kind: ffi::kEventHotKeyPressed,
+    };
+    let status = ffi::InstallEventHandler(
+      ffi::GetApplicationEventTarget(),
+      hot_key_handler,
+      1,
+      &event_type,
+      ptr::null_mut(),
+      ptr::null_mut(),
+    );
+    if status != 0 {
+      return Err(os_error(status));
+    }
+    hot_keys.handler_installed = true;
+  }
+
+  let mut hot_key = ptr::null_mut();
+  let status = ffi::RegisterEventHotKey(
+    key_code,
+    carbon_modifiers,
+    ffi::EventHotKeyID {
+      signature: SIGNATURE,
+      id,
+    },
+    ffi::GetApplicationEventTarget(),
+    0,
+    &mut hot_key,
+  );
+  if status != 0 {
+    return Err(os_error(status));
+  }
+  hot_keys.hot_keys.insert(id, hot_key as usize);
+  Ok(())
+}
+
+pub unsafe fn unregister(id: u32) -> Result<(), ExternalError> {
+  let hot_key = HOT_KEYS
+    .lock()
+    .unwrap()
+    .hot_keys
+    .remove(&id)
+    .ok_or(ExternalError::InvalidInput("the shortcut isn't registered"))?;
+  match ffi::UnregisterEventHotKey(hot_key as ffi::EventHotKeyRef) {
+    0 => Ok(()),
+    status => Err(os_error(status)),
+  }
+}
+
+extern "C" fn hot_key_handler(
+  _: ffi::EventHandlerCallRef,
+  event: ffi::EventRef,
+  _: *mut std::ffi::c_void,
+) -> ffi::OSStatus {
+  let mut hot_key_id = ffi::EventHotKeyID::default();
+  let status = unsafe {
+    ffi::GetEventParameter(
+      event,
+      ffi::kEventParamDirectObject,
+      ffi::typeEventHotKeyID,
+      ptr::null_mut(),
+      mem::size_of::<ffi::EventHotKeyID>(),
+      ptr::null_mut(),
+      &mut hot_key_id as *mut _ as *mut _,
+    )
+  };
+  if status == 0 && hot_key_id.signature == SIGNATURE {
+    AppState::queue_event_and_wake(EventWrapper::StaticEvent(Event::GlobalShortcut(
+      GlobalShortcutId(hot_key_id.id),
+    )));
+  }
+  status
+}
@@ -2,13 +2,88 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::icon::{BadIcon, RgbaIcon};
+use std::{
+  fs::File,
+  io::{self, BufWriter},
+  path::Path,
+  ptr,
+};
 
+use cocoa::{
+  base::{id, nil, NO, YES},
+  foundation::{NSInteger, NSSize, NSString},
+};
+use objc::{class, msg_send, sel, sel_impl};
+
+use crate::icon::{validate_icon_set, BadIcon, RgbaIcon};
+
+/// `NSBitmapFormatAlphaNonpremultiplied`, the RGBA data of icons has straight alpha.
+pub(super) const NS_BITMAP_FORMAT_ALPHA_NONPREMULTIPLIED: NSInteger = 1 << 1;
+
+/// Holds every size of the icon, from the smallest to the largest.
 #[derive(Debug, Clone)]
-pub struct PlatformIcon(RgbaIcon);
+pub struct PlatformIcon(Vec<RgbaIcon>);
 
 impl PlatformIcon {
   pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, BadIcon> {
-    Ok(PlatformIcon(RgbaIcon::from_rgba(rgba, width, height)?))
+    Ok(PlatformIcon(vec![RgbaIcon::from_rgba(
+      rgba, width, height,
+    )?]))
+  }
+
+  pub fn from_rgba_set(icons: Vec<RgbaIcon>) -> Result<Self, BadIcon> {
+    Ok(PlatformIcon(validate_icon_set(icons)?))
+  }
+
+  /// Creates an autoreleased `NSImage` with a representation for every size, so AppKit can pick
+  /// the best one for the display.
+  pub unsafe fn to_ns_image(&self) -> id {
+    let largest = self.0.last().unwrap();
+    let size = NSSize::new(largest.width as f64, largest.height as f64);
+    let image: id = msg_send![class!(NSImage), alloc];
+    let image: id = msg_send![image, initWithSize: size];
+
+    let color_space = NSString::alloc(nil).init_str("NSDeviceRGBColorSpace");
+    for icon in &self.0 {
+      let rep: id = msg_send![class!(NSBitmapImageRep), alloc];
+      let rep: id = msg_send![rep,
+        initWithBitmapDataPlanes: ptr::null_mut::<*mut u8>()
+        pixelsWide: icon.width as NSInteger
+        pixelsHigh: icon.height as NSInteger
+        bitsPerSample: 8 as NSInteger
+        samplesPerPixel: 4 as NSInteger
+        hasAlpha: YES
+        isPlanar: NO
+        colorSpaceName: color_space
+        bitmapFormat: NS_BITMAP_FORMAT_ALPHA_NONPREMULTIPLIED
+        bytesPerRow: (icon.width * 4) as NSInteger
+        bitsPerPixel: 32 as NSInteger
+      ];
+      if rep == nil {
+        continue;
+      }
+      let data: *mut u8 = msg_send![rep, bitmapData];
+      ptr::copy_nonoverlapping(icon.rgba.as_ptr(), data, icon.rgba.len());
+      // Every representation covers the whole image, the pixel density tells them apart.
+      let () = msg_send![rep, setSize: size];
+      let () = msg_send![image, addRepresentation: rep];
+      let () = msg_send![rep, release];
+    }
+    let () = msg_send![color_space, release];
+
+    msg_send![image, autorelease]
+  }
+
+  /// Writes the largest size of the icon to a PNG file, for the APIs taking an image file.
+  pub fn write_to_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+    let icon = self.0.last().unwrap();
+    let png = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(png), icon.width, icon.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+      .write_header()
+      .and_then(|mut writer| writer.write_image_data(&icon.rgba))
+      .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
   }
 }
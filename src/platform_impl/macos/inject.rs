@@ -0,0 +1,166 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use cocoa::{appkit::CGPoint, foundation::NSUInteger};
+use core_foundation::base::CFRelease;
+use core_graphics::display::CGDisplayBounds;
+
+use crate::{
+  dpi::PhysicalPosition,
+  error::ExternalError,
+  event::{ElementState, MouseButton, MouseScrollDelta},
+  platform::inject::SyntheticInput,
+  platform_impl::platform::{ffi, monitor},
+};
+
+/// The longest string of a keyboard event, longer ones are truncated by the system.
+const MAX_STRING_LENGTH: usize = 20;
+
+pub fn inject_input(input: SyntheticInput) -> Result<(), ExternalError> {
+  unsafe {
+    let source = ffi::CGEventSourceCreate(ffi::kCGEventSourceStateHIDSystemState);
+    let result = post_input(source, input);
+    if !source.is_null() {
+      CFRelease(source as _);
+    }
+    result
+  }
+}
+
+unsafe fn post_input(
+  source: ffi::CGEventSourceRef,
+  input: SyntheticInput,
+) -> Result<(), ExternalError> {
+  match input {
+    SyntheticInput::Key { code, state } => {
+      let keycode = code
+        .to_scancode()
+        .and_then(|scancode| u16::try_from(scancode).ok())
+        .ok_or(ExternalError::InvalidInput("the key has no scancode"))?;
+      post(ffi::CGEventCreateKeyboardEvent(
+        source,
+        keycode,
+        state == ElementState::Pressed,
+      ))
+    }
+    // The text replaces what the key types, so it doesn't depend on the keyboard layout.
+    SyntheticInput::Text(text) => {
+      let text: Vec<u16> = text.encode_utf16().collect();
+      for chunk in text.chunks(MAX_STRING_LENGTH) {
+        for key_down in [true, false] {
+          let event = ffi::CGEventCreateKeyboardEvent(source, 0, key_down);
+          if !event.is_null() {
+            ffi::CGEventKeyboardSetUnicodeString(event, chunk.len() as _, chunk.as_ptr());
+          }
+          post(event)?;
+        }
+      }
+      Ok(())
+    }
+    SyntheticInput::CursorMoved(position) => {
+      let point = global_point(position).ok_or(ExternalError::InvalidInput(
+        "the position isn't on a monitor",
+      ))?;
+      // The applications expect drags while a button is held.
+      let buttons: NSUInteger = msg_send![class!(NSEvent), pressedMouseButtons];
+      let (event_type, button) = if buttons & 1 != 0 {
+        (ffi::kCGEventLeftMouseDragged, ffi::kCGMouseButtonLeft)
+      } else if buttons & 2 != 0 {
+        (ffi::kCGEventRightMouseDragged, ffi::kCGMouseButtonRight)
+      } else if buttons != 0 {
+        (ffi::kCGEventOtherMouseDragged, ffi::kCGMouseButtonCenter)
+      } else {
+        (ffi::kCGEventMouseMoved, ffi::kCGMouseButtonLeft)
+      };
+      post(ffi::CGEventCreateMouseEvent(
+        source, event_type, point, button,
+      ))
+    }
+    SyntheticInput::MouseInput { button, state } => {
+      let pressed = state == ElementState::Pressed;
+      let (event_type, cg_button, number) = match (button, pressed) {
+        (MouseButton::Left, true) => (ffi::kCGEventLeftMouseDown, ffi::kCGMouseButtonLeft, 0),
+        (MouseButton::Left, false) => (ffi::kCGEventLeftMouseUp, ffi::kCGMouseButtonLeft, 0),
+        (MouseButton::Right, true) => (ffi::kCGEventRightMouseDown, ffi::kCGMouseButtonRight, 1),
+        (MouseButton::Right, false) => (ffi::kCGEventRightMouseUp, ffi::kCGMouseButtonRight, 1),
+        (MouseButton::Middle, true) => (ffi::kCGEventOtherMouseDown, ffi::kCGMouseButtonCenter, 2),
+        (MouseButton::Middle, false) => (ffi::kCGEventOtherMouseUp, ffi::kCGMouseButtonCenter, 2),
+        (MouseButton::Other(number), true) => (
+          ffi::kCGEventOtherMouseDown,
+          ffi::kCGMouseButtonCenter,
+          number,
+        ),
+        (MouseButton::Other(number), false) => {
+          (ffi::kCGEventOtherMouseUp, ffi::kCGMouseButtonCenter, number)
+        }
+      };
+      let event = ffi::CGEventCreateMouseEvent(source, event_type, cursor_location(), cg_button);
+      if !event.is_null() {
+        ffi::CGEventSetIntegerValueField(event, ffi::kCGMouseEventButtonNumber, number as i64);
+      }
+      post(event)
+    }
+    SyntheticInput::MouseWheel(delta) => {
+      let (units, x, y) = match delta {
+        MouseScrollDelta::LineDelta(x, y) => (
+          ffi::kCGScrollEventUnitLine,
+          x.round() as i32,
+          y.round() as i32,
+        ),
+        MouseScrollDelta::PixelDelta(position) => (
+          ffi::kCGScrollEventUnitPixel,
+          position.x.round() as i32,
+          position.y.round() as i32,
+        ),
+      };
+      post(ffi::CGEventCreateScrollWheelEvent2(
+        source, units, 2, y, x, 0,
+      ))
+    }
+  }
+}
+
+/// Posts a created event to the HID event tap, and releases it.
+unsafe fn post(event: ffi::CGEventRef) -> Result<(), ExternalError> {
+  if event.is_null() {
+    return Err(ExternalError::Os(os_error!(super::OsError::CreationError(
+      "Failed to create the event"
+    ))));
+  }
+  ffi::CGEventPost(ffi::kCGHIDEventTap, event);
+  CFRelease(event as _);
+  Ok(())
+}
+
+/// The current location of the cursor, in global display coordinates.
+unsafe fn cursor_location() -> CGPoint {
+  let event = ffi::CGEventCreate(std::ptr::null_mut());
+  if event.is_null() {
+    return CGPoint::new(0.0, 0.0);
+  }
+  let location = ffi::CGEventGetLocation(event);
+  CFRelease(event as _);
+  location
+}
+
+/// Converts a position in the physical coordinates of the monitors to the global display
+/// coordinates, which are in points.
+fn global_point(position: PhysicalPosition<f64>) -> Option<CGPoint> {
+  monitor::available_monitors()
+    .into_iter()
+    .find_map(|monitor| {
+      let origin = monitor.position();
+      let size = monitor.size();
+      let x = position.x - origin.x as f64;
+      let y = position.y - origin.y as f64;
+      if x < 0.0 || y < 0.0 || x >= size.width as f64 || y >= size.height as f64 {
+        return None;
+      }
+      let bounds = unsafe { CGDisplayBounds(monitor.native_identifier()) };
+      let scale_factor = monitor.scale_factor();
+      Some(CGPoint::new(
+        bounds.origin.x + x / scale_factor,
+        bounds.origin.y + y / scale_factor,
+      ))
+    })
+}
@@ -0,0 +1,408 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use cocoa::{
+  appkit::{NSApp, NSApplication, NSEventModifierFlags, NSMenu, NSMenuItem},
+  base::{id, nil, selector, BOOL, NO, YES},
+  foundation::{NSInteger, NSPoint, NSProcessInfo, NSRect, NSString},
+};
+use objc::{
+  declare::ClassDecl,
+  rc::autoreleasepool,
+  runtime::{Class, Object, Sel},
+};
+
+use super::{app_state::AppState, event::EventWrapper, util::IdRef};
+use crate::{dpi::LogicalPosition, event::Event, menu::MenuId, platform::macos::DefaultMenuConfig};
+
+struct KeyEquivalent<'a> {
+  key: &'a str,
+  masks: Option<NSEventModifierFlags>,
+}
+
+/// Sets the main menu of the application to the default one described by `config`.
+pub fn initialize(config: DefaultMenuConfig) {
+  autoreleasepool(|| unsafe {
+    let menubar = IdRef::new(NSMenu::new(nil));
+    let app_menu_item = IdRef::new(NSMenuItem::new(nil));
+    menubar.addItem_(*app_menu_item);
+
+    let app_menu = NSMenu::new(nil);
+    let process_name = NSProcessInfo::processInfo(nil).processName();
+
+    if config.app_menu {
+      let about_item_prefix = NSString::alloc(nil).init_str("About ");
+      let about_item_title = about_item_prefix.stringByAppendingString_(process_name);
+      app_menu.addItem_(menu_item(
+        about_item_title,
+        selector("orderFrontStandardAboutPanel:"),
+        None,
+      ));
+      app_menu.addItem_(NSMenuItem::separatorItem(nil));
+    }
+
+    if config.services {
+      let services_menu = NSMenu::new(nil);
+      let services_item = NSMenuItem::new(nil);
+      let _: () = msg_send![services_item, setTitle: NSString::alloc(nil).init_str("Services")];
+      services_item.setSubmenu_(services_menu);
+      app_menu.addItem_(services_item);
+      app_menu.addItem_(NSMenuItem::separatorItem(nil));
+      let _: () = msg_send![NSApp(), setServicesMenu: services_menu];
+    }
+
+    if config.app_menu {
+      let hide_item_prefix = NSString::alloc(nil).init_str("Hide ");
+      let hide_item_title = hide_item_prefix.stringByAppendingString_(process_name);
+      app_menu.addItem_(menu_item(
+        hide_item_title,
+        selector("hide:"),
+        Some(KeyEquivalent {
+          key: "h",
+          masks: None,
+        }),
+      ));
+      app_menu.addItem_(menu_item(
+        NSString::alloc(nil).init_str("Hide Others"),
+        selector("hideOtherApplications:"),
+        Some(KeyEquivalent {
+          key: "h",
+          masks: Some(
+            NSEventModifierFlags::NSAlternateKeyMask | NSEventModifierFlags::NSCommandKeyMask,
+          ),
+        }),
+      ));
+      app_menu.addItem_(menu_item(
+        NSString::alloc(nil).init_str("Show All"),
+        selector("unhideAllApplications:"),
+        None,
+      ));
+      app_menu.addItem_(NSMenuItem::separatorItem(nil));
+    }
+
+    // Always there, so that Cmd+Q quits.
+    let quit_item_prefix = NSString::alloc(nil).init_str("Quit ");
+    let quit_item_title = quit_item_prefix.stringByAppendingString_(process_name);
+    app_menu.addItem_(menu_item(
+      quit_item_title,
+      selector("terminate:"),
+      Some(KeyEquivalent {
+        key: "q",
+        masks: None,
+      }),
+    ));
+    app_menu_item.setSubmenu_(app_menu);
+
+    if config.edit_menu {
+      let edit_menu = submenu(*menubar, "Edit");
+      edit_menu.addItem_(menu_item(
+        NSString::alloc(nil).init_str("Undo"),
+        selector("undo:"),
+        Some(KeyEquivalent {
+          key: "z",
+          masks: None,
+        }),
+      ));
+      edit_menu.addItem_(menu_item(
+        NSString::alloc(nil).init_str("Redo"),
+        selector("redo:"),
+        Some(KeyEquivalent {
+          key: "z",
+          masks: Some(
+            NSEventModifierFlags::NSShiftKeyMask | NSEventModifierFlags::NSCommandKeyMask,
+          ),
+        }),
+      ));
+      edit_menu.addItem_(NSMenuItem::separatorItem(nil));
+      for (title, action, key) in [
+        ("Cut", "cut:", "x"),
+        ("Copy", "copy:", "c"),
+        ("Paste", "paste:", "v"),
+        ("Select All", "selectAll:", "a"),
+      ] {
+        edit_menu.addItem_(menu_item(
+          NSString::alloc(nil).init_str(title),
+          selector(action),
+          Some(KeyEquivalent { key, masks: None }),
+        ));
+      }
+    }
+
+    if config.window_menu {
+      let window_menu = submenu(*menubar, "Window");
+      window_menu.addItem_(menu_item(
+        NSString::alloc(nil).init_str("Minimize"),
+        selector("performMiniaturize:"),
+        Some(KeyEquivalent {
+          key: "m",
+          masks: None,
+        }),
+      ));
+      window_menu.addItem_(menu_item(
+        NSString::alloc(nil).init_str("Zoom"),
+        selector("performZoom:"),
+        None,
+      ));
+      window_menu.addItem_(NSMenuItem::separatorItem(nil));
+      window_menu.addItem_(menu_item(
+        NSString::alloc(nil).init_str("Bring All to Front"),
+        selector("arrangeInFront:"),
+        None,
+      ));
+      // AppKit lists the windows of the application at the end of it.
+      let _: () = msg_send![NSApp(), setWindowsMenu: window_menu];
+    }
+
+    NSApp().setMainMenu_(*menubar);
+  });
+}
+
+/// Adds a menu titled `title` to `menubar`, and returns it.
+unsafe fn submenu(menubar: id, title: &str) -> id {
+  let title = NSString::alloc(nil).init_str(title);
+  let menu: id = msg_send![class!(NSMenu), alloc];
+  let menu: id = msg_send![menu, initWithTitle: title];
+  let item = NSMenuItem::new(nil);
+  item.setSubmenu_(menu);
+  menubar.addItem_(item);
+  menu
+}
+
+fn menu_item(title: id, selector: Sel, key_equivalent: Option<KeyEquivalent<'_>>) -> id {
+  unsafe {
+    let (key, masks) = match key_equivalent {
+      Some(ke) => (NSString::alloc(nil).init_str(ke.key), ke.masks),
+      None => (NSString::alloc(nil).init_str(""), None),
+    };
+    let item = NSMenuItem::alloc(nil).initWithTitle_action_keyEquivalent_(title, selector, key);
+    if let Some(masks) = masks {
+      item.setKeyEquivalentModifierMask_(masks)
+    }
+
+    item
+  }
+}
+
+/// `NSControlStateValueOn` and `NSControlStateValueOff`.
+const STATE_ON: NSInteger = 1;
+const STATE_OFF: NSInteger = 0;
+
+struct MenuTargetClass(*const Class);
+unsafe impl Send for MenuTargetClass {}
+unsafe impl Sync for MenuTargetClass {}
+
+lazy_static! {
+  static ref MENU_TARGET_CLASS: MenuTargetClass = unsafe {
+    let mut decl = ClassDecl::new("TaoMenuTarget", class!(NSObject)).unwrap();
+    decl.add_method(
+      sel!(fireItem:),
+      fire_item as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(fireCheckItem:),
+      fire_check_item as extern "C" fn(&Object, Sel, id),
+    );
+    MenuTargetClass(decl.register())
+  };
+}
+
+/// The target of every item, which lives as long as the process.
+static MENU_TARGET: AtomicPtr<Object> = AtomicPtr::new(std::ptr::null_mut());
+
+fn menu_target() -> id {
+  let target = MENU_TARGET.load(Ordering::Acquire);
+  if !target.is_null() {
+    return target;
+  }
+  let target: id = unsafe { msg_send![MENU_TARGET_CLASS.0, new] };
+  MENU_TARGET.store(target, Ordering::Release);
+  target
+}
+
+/// The items carry their id in their tag.
+extern "C" fn fire_item(_: &Object, _: Sel, item: id) {
+  trace!("Triggered `fireItem:`");
+  let tag: NSInteger = unsafe { msg_send![item, tag] };
+  AppState::queue_event_and_wake(EventWrapper::StaticEvent(Event::MenuEvent {
+    menu_id: MenuId(tag as u32),
+  }));
+  trace!("Completed `fireItem:`");
+}
+
+extern "C" fn fire_check_item(this: &Object, sel: Sel, item: id) {
+  unsafe {
+    let state: NSInteger = msg_send![item, state];
+    let state = if state == STATE_ON {
+      STATE_OFF
+    } else {
+      STATE_ON
+    };
+    let () = msg_send![item, setState: state];
+  }
+  fire_item(this, sel, item);
+}
+
+/// Creates an empty menu that leaves its items enabled or disabled as they were set.
+unsafe fn new_menu(title: &str) -> id {
+  let title = NSString::alloc(nil).init_str(title);
+  let menu: id = msg_send![class!(NSMenu), alloc];
+  let menu: id = msg_send![menu, initWithTitle: title];
+  let () = msg_send![title, release];
+  let () = msg_send![menu, setAutoenablesItems: NO];
+  menu
+}
+
+unsafe fn set_title(item: id, title: &str) {
+  let title = NSString::alloc(nil).init_str(title);
+  let () = msg_send![item, setTitle: title];
+  let () = msg_send![title, release];
+}
+
+pub struct Menu {
+  ns_menu: IdRef,
+}
+
+impl Menu {
+  pub fn new() -> Self {
+    Self {
+      ns_menu: IdRef::new(unsafe { new_menu("") }),
+    }
+  }
+
+  pub fn append_submenu(&self, submenu: &Submenu) {
+    unsafe { self.ns_menu.addItem_(*submenu.ns_menu_item) };
+  }
+
+  /// Sets the menu as the menu bar of the application.
+  pub fn set_as_main_menu(&self) {
+    unsafe { NSApp().setMainMenu_(*self.ns_menu) };
+  }
+}
+
+pub struct Submenu {
+  ns_menu_item: IdRef,
+  ns_menu: IdRef,
+}
+
+impl Submenu {
+  pub fn new(title: &str) -> Self {
+    unsafe {
+      let ns_menu = new_menu(title);
+      let ns_menu_item = NSMenuItem::new(nil);
+      set_title(ns_menu_item, title);
+      ns_menu_item.setSubmenu_(ns_menu);
+      Self {
+        ns_menu_item: IdRef::new(ns_menu_item),
+        ns_menu: IdRef::new(ns_menu),
+      }
+    }
+  }
+
+  pub fn append_item(&self, item: &MenuItem) {
+    unsafe { self.ns_menu.addItem_(*item.ns_menu_item) };
+  }
+
+  pub fn append_submenu(&self, submenu: &Submenu) {
+    unsafe { self.ns_menu.addItem_(*submenu.ns_menu_item) };
+  }
+
+  pub fn append_separator(&self) {
+    unsafe { self.ns_menu.addItem_(NSMenuItem::separatorItem(nil)) };
+  }
+
+  pub fn set_title(&self, title: &str) {
+    unsafe {
+      set_title(*self.ns_menu_item, title);
+      let ns_title = NSString::alloc(nil).init_str(title);
+      let () = msg_send![*self.ns_menu, setTitle: ns_title];
+      let () = msg_send![ns_title, release];
+    }
+  }
+
+  pub fn set_enabled(&self, enabled: bool) {
+    let enabled: BOOL = if enabled { YES } else { NO };
+    unsafe {
+      let () = msg_send![*self.ns_menu_item, setEnabled: enabled];
+    }
+  }
+
+  pub(crate) fn ns_menu(&self) -> id {
+    *self.ns_menu
+  }
+
+  /// Shows the menu in `ns_view` at `position`, in points from its top left, until it's closed.
+  pub fn pop_up(&self, ns_view: id, position: LogicalPosition<f64>) {
+    unsafe {
+      let frame: NSRect = msg_send![ns_view, frame];
+      let is_flipped: BOOL = msg_send![ns_view, isFlipped];
+      let y = if is_flipped == YES {
+        position.y
+      } else {
+        frame.size.height - position.y
+      };
+      let _: BOOL = msg_send![
+        *self.ns_menu,
+        popUpMenuPositioningItem: nil
+        atLocation: NSPoint::new(position.x, y)
+        inView: ns_view
+      ];
+    }
+  }
+}
+
+pub struct MenuItem {
+  ns_menu_item: IdRef,
+  checkable: bool,
+}
+
+impl MenuItem {
+  pub fn new(id: u32, title: &str, checked: Option<bool>) -> Self {
+    unsafe {
+      let ns_menu_item = NSMenuItem::new(nil);
+      set_title(ns_menu_item, title);
+      let () = msg_send![ns_menu_item, setTag: id as NSInteger];
+      let () = msg_send![ns_menu_item, setTarget: menu_target()];
+      let action = if checked.is_some() {
+        sel!(fireCheckItem:)
+      } else {
+        sel!(fireItem:)
+      };
+      let () = msg_send![ns_menu_item, setAction: action];
+      let item = Self {
+        ns_menu_item: IdRef::new(ns_menu_item),
+        checkable: checked.is_some(),
+      };
+      item.set_checked(checked.unwrap_or(false));
+      item
+    }
+  }
+
+  pub fn set_title(&self, title: &str) {
+    unsafe { set_title(*self.ns_menu_item, title) };
+  }
+
+  pub fn set_enabled(&self, enabled: bool) {
+    let enabled: BOOL = if enabled { YES } else { NO };
+    unsafe {
+      let () = msg_send![*self.ns_menu_item, setEnabled: enabled];
+    }
+  }
+
+  pub fn set_checked(&self, checked: bool) {
+    if !self.checkable {
+      return;
+    }
+    let state = if checked { STATE_ON } else { STATE_OFF };
+    unsafe {
+      let () = msg_send![*self.ns_menu_item, setState: state];
+    }
+  }
+
+  pub fn is_checked(&self) -> bool {
+    let state: NSInteger = unsafe { msg_send![*self.ns_menu_item, state] };
+    self.checkable && state == STATE_ON
+  }
+}
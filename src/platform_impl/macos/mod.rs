@@ -7,14 +7,25 @@
 mod app;
 mod app_delegate;
 mod app_state;
+mod clipboard;
+mod device;
+mod display_link;
 mod event;
 mod event_loop;
 mod ffi;
+mod global_shortcut;
 mod icon;
+mod inject;
 mod keycode;
+mod menu;
 mod monitor;
+mod notification;
 mod observer;
+mod power;
 mod progress_bar;
+mod services;
+mod single_instance;
+mod system_tray;
 mod util;
 mod view;
 mod window;
@@ -25,19 +36,38 @@ use std::{fmt, ops::Deref, sync::Arc};
 pub(crate) use self::event_loop::PlatformSpecificEventLoopAttributes;
 pub use self::{
   app_delegate::{get_aux_state_mut, AuxDelegateState},
+  clipboard::Clipboard,
   event::KeyEventExtra,
-  event_loop::{EventLoop, EventLoopWindowTarget, Proxy as EventLoopProxy},
+  event_loop::{EventLoop, EventLoopWaker, EventLoopWindowTarget, Proxy as EventLoopProxy},
   keycode::{keycode_from_scancode, keycode_to_scancode},
+  menu::{Menu, MenuItem, Submenu},
   monitor::{MonitorHandle, VideoMode},
+  notification::Notification,
+  power::PowerAssertion,
   progress_bar::set_progress_indicator,
-  window::{Id as WindowId, Parent, PlatformSpecificWindowBuilderAttributes, UnownedWindow},
+  system_tray::SystemTray,
+  window::{
+    AccessibilityActionSender, Id as WindowId, Parent, PlatformSpecificWindowBuilderAttributes,
+    UnownedWindow,
+  },
 };
+use self::{app_state::AppState, event::EventWrapper};
 use crate::{
-  error::OsError as RootOsError, event::DeviceId as RootDeviceId, window::WindowAttributes,
+  error::OsError as RootOsError,
+  event::{DeviceId as RootDeviceId, Event},
+  window::WindowAttributes,
 };
 
 pub(crate) use icon::PlatformIcon;
 
+/// See `platform::macos::queue_event_from_delegate`.
+#[allow(clippy::result_large_err)]
+pub fn queue_event_from_delegate<T>(event: Event<'static, T>) -> Result<(), Event<'static, T>> {
+  let event = event.map_nonuser_event::<util::Never>()?;
+  AppState::queue_event_and_wake(EventWrapper::StaticEvent(event));
+  Ok(())
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DeviceId;
 
@@ -62,6 +92,7 @@ pub struct Window {
 pub enum OsError {
   CGError(core_graphics::base::CGError),
   CreationError(&'static str),
+  OSStatus(i32),
 }
 
 unsafe impl Send for Window {}
@@ -77,11 +108,15 @@ impl Deref for Window {
 
 impl Window {
   pub fn new<T: 'static>(
-    _window_target: &EventLoopWindowTarget<T>,
-    attributes: WindowAttributes,
+    window_target: &EventLoopWindowTarget<T>,
+    mut attributes: WindowAttributes,
     pl_attribs: PlatformSpecificWindowBuilderAttributes,
   ) -> Result<Self, RootOsError> {
+    if window_target.headless {
+      attributes.visible = false;
+    }
     let (window, delegate) = UnownedWindow::new(attributes, pl_attribs)?;
+    window.shared_state.lock().unwrap().headless = window_target.headless;
     Ok(Window { window, delegate })
   }
 }
@@ -91,6 +126,7 @@ impl fmt::Display for OsError {
     match self {
       OsError::CGError(e) => f.pad(&format!("CGError {}", e)),
       OsError::CreationError(e) => f.pad(e),
+      OsError::OSStatus(status) => f.pad(&format!("OSStatus {}", status)),
     }
   }
 }
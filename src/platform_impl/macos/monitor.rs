@@ -2,27 +2,34 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::VecDeque, fmt};
+use std::{collections::VecDeque, ffi::c_void, fmt, ptr};
 
 use super::{
+  app_state::AppState,
+  event::EventWrapper,
   ffi::{self, CGRectContainsPoint},
-  util,
+  util, OsError,
 };
 use crate::{
   dpi::{PhysicalPosition, PhysicalSize},
+  error::ExternalError,
+  event::Event,
   monitor::{MonitorHandle as RootMonitorHandle, VideoMode as RootVideoMode},
+  window::RgbaImage,
 };
 use cocoa::{
   appkit::{CGPoint, NSScreen},
-  base::{id, nil},
+  base::{id, nil, BOOL, YES},
   foundation::NSUInteger,
 };
 use core_foundation::{
   array::{CFArrayGetCount, CFArrayGetValueAtIndex},
   base::{CFRelease, TCFType},
   string::CFString,
+  uuid::CFUUIDGetUUIDBytes,
 };
 use core_graphics::display::{CGDirectDisplayID, CGDisplay, CGDisplayBounds};
+use dispatch::Queue;
 
 #[derive(Clone)]
 pub struct VideoMode {
@@ -145,6 +152,41 @@ impl std::hash::Hash for MonitorHandle {
   }
 }
 
+/// Starts reporting the displays that are connected and disconnected.
+pub unsafe fn start_monitoring() {
+  ffi::CGDisplayRegisterReconfigurationCallback(display_reconfiguration_callback, ptr::null_mut());
+}
+
+pub unsafe fn stop_monitoring() {
+  ffi::CGDisplayRemoveReconfigurationCallback(display_reconfiguration_callback, ptr::null_mut());
+}
+
+extern "C" fn display_reconfiguration_callback(
+  display: CGDirectDisplayID,
+  flags: ffi::CGDisplayChangeSummaryFlags,
+  _: *mut c_void,
+) {
+  let connected = if flags & (ffi::kCGDisplayAddFlag | ffi::kCGDisplayEnabledFlag) != 0 {
+    true
+  } else if flags & (ffi::kCGDisplayRemoveFlag | ffi::kCGDisplayDisabledFlag) != 0 {
+    false
+  } else {
+    return;
+  };
+  // Queued after the display configuration is updated, so the new monitor can be queried.
+  Queue::main().exec_async(move || {
+    let monitor = RootMonitorHandle {
+      inner: MonitorHandle(display),
+    };
+    let event = if connected {
+      Event::MonitorConnected(monitor)
+    } else {
+      Event::MonitorDisconnected(monitor)
+    };
+    AppState::queue_event_and_wake(EventWrapper::StaticEvent(event));
+  });
+}
+
 pub fn available_monitors() -> VecDeque<MonitorHandle> {
   if let Ok(displays) = CGDisplay::active_displays() {
     let mut monitors = VecDeque::with_capacity(displays.len());
@@ -206,10 +248,37 @@ impl MonitorHandle {
 
   pub fn name(&self) -> Option<String> {
     let MonitorHandle(display_id) = *self;
+    // `localizedName` is the EDID product name, available since macOS 10.15.
+    if let Some(screen) = self.ns_screen() {
+      unsafe {
+        let has_name: BOOL = msg_send![screen, respondsToSelector: sel!(localizedName)];
+        if has_name == YES {
+          let name: id = msg_send![screen, localizedName];
+          if name != nil {
+            return Some(util::ns_string_to_rust(name));
+          }
+        }
+      }
+    }
     let screen_num = CGDisplay::new(display_id).model_number();
     Some(format!("Monitor #{}", screen_num))
   }
 
+  pub fn id(&self) -> u64 {
+    unsafe {
+      let uuid = ffi::CGDisplayCreateUUIDFromDisplayID(self.0);
+      if uuid.is_null() {
+        return crate::monitor::stable_id(&self.0.to_ne_bytes());
+      }
+      let b = CFUUIDGetUUIDBytes(uuid);
+      CFRelease(uuid as _);
+      crate::monitor::stable_id(&[
+        b.byte0, b.byte1, b.byte2, b.byte3, b.byte4, b.byte5, b.byte6, b.byte7, b.byte8, b.byte9,
+        b.byte10, b.byte11, b.byte12, b.byte13, b.byte14, b.byte15,
+      ])
+    }
+  }
+
   #[inline]
   pub fn native_identifier(&self) -> u32 {
     self.0
@@ -240,6 +309,40 @@ impl MonitorHandle {
     unsafe { NSScreen::backingScaleFactor(screen) as f64 }
   }
 
+  pub fn capture(&self) -> Result<RgbaImage, ExternalError> {
+    let image = CGDisplay::new(self.0)
+      .image()
+      .ok_or(ExternalError::Os(os_error!(OsError::CreationError(
+        "Couldn't capture the display"
+      ))))?;
+    Ok(util::cg_image_to_rgba(&image))
+  }
+
+  pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+    unsafe {
+      let mode = ffi::CGDisplayCopyDisplayMode(self.0);
+      if !mode.is_null() {
+        let refresh_rate = ffi::CGDisplayModeGetRefreshRate(mode);
+        ffi::CGDisplayModeRelease(mode);
+        if refresh_rate > 0.0 {
+          return Some((refresh_rate * 1000.0).round() as u32);
+        }
+      }
+
+      // CGDisplayModeGetRefreshRate returns 0.0 for the built-in displays.
+      let mut display_link = ptr::null_mut();
+      if ffi::CVDisplayLinkCreateWithCGDisplay(self.0, &mut display_link) != ffi::kCVReturnSuccess {
+        return None;
+      }
+      let time = ffi::CVDisplayLinkGetNominalOutputVideoRefreshPeriod(display_link);
+      ffi::CVDisplayLinkRelease(display_link);
+      if time.flags & ffi::kCVTimeIsIndefinite != 0 || time.time_value <= 0 {
+        return None;
+      }
+      Some((time.time_scale as i64 * 1000 / time.time_value) as u32)
+    }
+  }
+
   pub fn video_modes(&self) -> impl Iterator<Item = RootVideoMode> {
     let cv_refresh_rate = unsafe {
       let mut display_link = std::ptr::null_mut();
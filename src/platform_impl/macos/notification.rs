@@ -0,0 +1,326 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  collections::HashMap,
+  env, process,
+  sync::{Mutex, MutexGuard, Once, PoisonError},
+};
+
+use block::{Block, ConcreteBlock};
+use cocoa::{
+  base::{id, nil, BOOL, YES},
+  foundation::{NSArray, NSString, NSUInteger},
+};
+use dispatch::Queue;
+use objc::{
+  declare::ClassDecl,
+  runtime::{Class, Object, Sel},
+};
+
+use super::{app_state::AppState, event::EventWrapper, ffi, util, EventLoopWindowTarget};
+use crate::{
+  error::{ExternalError, NotSupportedError},
+  event::Event,
+  notification::{NotificationAttributes, NotificationId},
+};
+
+/// `UNAuthorizationOptionSound | UNAuthorizationOptionAlert`.
+const AUTHORIZATION_OPTIONS: NSUInteger = (1 << 1) | (1 << 2);
+/// `UNNotificationPresentationOptionSound | UNNotificationPresentationOptionAlert`, to show the
+/// notifications while the application is active too.
+const PRESENTATION_OPTIONS: NSUInteger = (1 << 1) | (1 << 2);
+/// `UNNotificationActionOptionForeground`, the application is activated by the actions.
+const ACTION_OPTIONS: NSUInteger = 1 << 2;
+
+struct NotificationDelegateClass(*const Class);
+unsafe impl Send for NotificationDelegateClass {}
+unsafe impl Sync for NotificationDelegateClass {}
+
+lazy_static! {
+  static ref NOTIFICATION_DELEGATE_CLASS: NotificationDelegateClass = unsafe {
+    let mut decl = ClassDecl::new("TaoNotificationDelegate", class!(NSObject)).unwrap();
+    decl.add_method(
+      sel!(userNotificationCenter:didReceiveNotificationResponse:withCompletionHandler:),
+      did_receive_response as extern "C" fn(&Object, Sel, id, id, id),
+    );
+    decl.add_method(
+      sel!(userNotificationCenter:willPresentNotification:withCompletionHandler:),
+      will_present_notification as extern "C" fn(&Object, Sel, id, id, id),
+    );
+    NotificationDelegateClass(decl.register())
+  };
+}
+
+/// A retained `UNNotificationCategory`, which is immutable.
+struct Category(id);
+unsafe impl Send for Category {}
+
+lazy_static! {
+  /// The categories holding the actions of the notifications, by id. The notification center
+  /// only takes them all at once.
+  static ref CATEGORIES: Mutex<HashMap<u32, Category>> = Default::default();
+}
+
+fn categories() -> MutexGuard<'static, HashMap<u32, Category>> {
+  CATEGORIES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The identifier of the request of a notification. The delivered notifications outlive the
+/// process, so it's told apart from the ones of the previous launches.
+fn request_identifier(id: u32) -> String {
+  format!("tao-notification-{}-{}", process::id(), id)
+}
+
+/// The id of a notification of this process, from the identifier of its request.
+fn notification_id(identifier: &str) -> Option<u32> {
+  let (pid, id) = identifier
+    .strip_prefix("tao-notification-")?
+    .split_once('-')?;
+  if pid.parse::<u32>().ok() != Some(process::id()) {
+    return None;
+  }
+  id.parse().ok()
+}
+
+/// The notification center of the application, with the delegate delivering the events.
+///
+/// `currentNotificationCenter` raises an exception outside of an application bundle, and the
+/// class is missing before macOS 10.14.
+unsafe fn notification_center() -> Option<id> {
+  let bundle: id = msg_send![class!(NSBundle), mainBundle];
+  let bundle_identifier: id = msg_send![bundle, bundleIdentifier];
+  if bundle_identifier == nil {
+    return None;
+  }
+  let class = Class::get("UNUserNotificationCenter")?;
+  let center: id = msg_send![class, currentNotificationCenter];
+  static SET_DELEGATE: Once = Once::new();
+  SET_DELEGATE.call_once(|| {
+    // The center doesn't retain its delegate, which lives as long as the process.
+    let delegate: id = msg_send![NOTIFICATION_DELEGATE_CLASS.0, new];
+    let () = msg_send![center, setDelegate: delegate];
+  });
+  Some(center)
+}
+
+/// Creates an autoreleased `NSString`.
+unsafe fn ns_string(string: &str) -> id {
+  let string = NSString::alloc(nil).init_str(string);
+  msg_send![string, autorelease]
+}
+
+/// A notification shown by `UNUserNotificationCenter`.
+pub struct Notification {
+  id: u32,
+}
+
+impl Notification {
+  pub fn new<T>(
+    _window_target: &EventLoopWindowTarget<T>,
+    id: u32,
+    attributes: NotificationAttributes,
+  ) -> Result<Self, ExternalError> {
+    unsafe {
+      let center = notification_center()
+        .ok_or_else(|| ExternalError::NotSupported(NotSupportedError::new()))?;
+
+      let content: id = msg_send![class!(UNMutableNotificationContent), new];
+      let () = msg_send![content, setTitle: ns_string(&attributes.title)];
+      if let Some(body) = &attributes.body {
+        let () = msg_send![content, setBody: ns_string(body)];
+      }
+      let sound: id = msg_send![class!(UNNotificationSound), defaultSound];
+      let () = msg_send![content, setSound: sound];
+
+      if !attributes.actions.is_empty() {
+        let category_identifier = ns_string(&request_identifier(id));
+        let actions: Vec<id> = attributes
+          .actions
+          .iter()
+          .map(|action| {
+            msg_send![class!(UNNotificationAction),
+              actionWithIdentifier: ns_string(&action.id)
+              title: ns_string(&action.label)
+              options: ACTION_OPTIONS
+            ]
+          })
+          .collect();
+        let category: id = msg_send![class!(UNNotificationCategory),
+          categoryWithIdentifier: category_identifier
+          actions: NSArray::arrayWithObjects(nil, &actions)
+          intentIdentifiers: NSArray::array(nil)
+          options: 0 as NSUInteger
+        ];
+        let category: id = msg_send![category, retain];
+        categories().insert(id, Category(category));
+        set_categories(center);
+        let () = msg_send![content, setCategoryIdentifier: category_identifier];
+      }
+
+      // The system moves the file of the attachment to its own storage.
+      if let Some(icon) = &attributes.icon {
+        let path = env::temp_dir().join(format!("{}.png", request_identifier(id)));
+        match icon.inner.write_to_png(&path) {
+          Ok(()) => {
+            let url: id = msg_send![class!(NSURL),
+              fileURLWithPath: ns_string(&path.to_string_lossy())
+            ];
+            let mut error: id = nil;
+            let attachment: id = msg_send![class!(UNNotificationAttachment),
+              attachmentWithIdentifier: ns_string("icon")
+              URL: url
+              options: nil
+              error: &mut error
+            ];
+            if attachment != nil {
+              let () =
+                msg_send![content, setAttachments: NSArray::arrayWithObject(nil, attachment)];
+            } else {
+              warn!("Failed to attach the image of the notification");
+            }
+          }
+          Err(e) => warn!("Failed to write the image of the notification: {}", e),
+        }
+      }
+
+      let request: id = msg_send![class!(UNNotificationRequest),
+        requestWithIdentifier: ns_string(&request_identifier(id))
+        content: content
+        trigger: nil
+      ];
+      let () = msg_send![content, release];
+      // Released by the handler, which is called on another thread once the user answered the
+      // first time.
+      let request: id = msg_send![request, retain];
+      let handler = ConcreteBlock::new(move |granted: BOOL, _error: id| {
+        if granted == YES {
+          let () = msg_send![center, addNotificationRequest: request withCompletionHandler: nil];
+        } else {
+          warn!("The notifications of the application aren't allowed");
+        }
+        let () = msg_send![request, release];
+      })
+      .copy();
+      let () = msg_send![center,
+        requestAuthorizationWithOptions: AUTHORIZATION_OPTIONS
+        completionHandler: &*handler
+      ];
+
+      Ok(Self { id })
+    }
+  }
+
+  /// `UNUserNotificationCenter` is thread-safe, so this works from any thread.
+  pub fn close(&self) {
+    unsafe {
+      let center = match notification_center() {
+        Some(center) => center,
+        None => return,
+      };
+      let identifiers = NSArray::arrayWithObject(nil, ns_string(&request_identifier(self.id)));
+      let () = msg_send![center, removePendingNotificationRequestsWithIdentifiers: identifiers];
+      let () = msg_send![center, removeDeliveredNotificationsWithIdentifiers: identifiers];
+      remove_category(center, self.id);
+    }
+  }
+}
+
+/// Releases the category of the actions of the notification `id`, once it can't be clicked
+/// anymore.
+unsafe fn remove_category(center: id, id: u32) {
+  let category = categories().remove(&id);
+  if let Some(Category(category)) = category {
+    let () = msg_send![category, release];
+    set_categories(center);
+  }
+}
+
+unsafe fn set_categories(center: id) {
+  let categories = categories()
+    .values()
+    .map(|category| category.0)
+    .collect::<Vec<_>>();
+  let categories: id = msg_send![class!(NSSet),
+    setWithArray: NSArray::arrayWithObjects(nil, &categories)
+  ];
+  let () = msg_send![center, setNotificationCategories: categories];
+}
+
+extern "C" fn did_receive_response(
+  _: &Object,
+  _: Sel,
+  center: id,
+  response: id,
+  completion_handler: id,
+) {
+  trace!(
+    "Triggered `userNotificationCenter:didReceiveNotificationResponse:withCompletionHandler:`"
+  );
+  unsafe {
+    let notification: id = msg_send![response, notification];
+    let request: id = msg_send![notification, request];
+    let identifier = util::ns_string_to_rust(msg_send![request, identifier]);
+    let action = util::ns_string_to_rust(msg_send![response, actionIdentifier]);
+    if let Some(id) = notification_id(&identifier) {
+      // A notification is removed from the notification center once answered.
+      remove_category(center, id);
+      let action = (action != util::ns_string_to_rust(ffi::UNNotificationDefaultActionIdentifier))
+        .then(|| action);
+      // The delegate may be called on another thread.
+      Queue::main().exec_async(move || {
+        AppState::queue_event_and_wake(EventWrapper::StaticEvent(Event::NotificationAction {
+          id: NotificationId(id),
+          action,
+        }));
+      });
+    }
+    let completion_handler = &*(completion_handler as *const Block<(), ()>);
+    completion_handler.call(());
+  }
+  trace!(
+    "Completed `userNotificationCenter:didReceiveNotificationResponse:withCompletionHandler:`"
+  );
+}
+
+extern "C" fn will_present_notification(
+  _: &Object,
+  _: Sel,
+  _center: id,
+  _notification: id,
+  completion_handler: id,
+) {
+  unsafe {
+    let completion_handler = &*(completion_handler as *const Block<(NSUInteger,), ()>);
+    completion_handler.call((PRESENTATION_OPTIONS,));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn notification_id_reads_the_request_identifier() {
+    assert_eq!(notification_id(&request_identifier(7)), Some(7));
+    assert_eq!(
+      notification_id(&request_identifier(u32::MAX)),
+      Some(u32::MAX)
+    );
+  }
+
+  #[test]
+  fn notification_id_skips_other_processes_and_identifiers() {
+    let other_pid = process::id().wrapping_add(1);
+    assert_eq!(
+      notification_id(&format!("tao-notification-{}-7", other_pid)),
+      None
+    );
+    assert_eq!(notification_id("tao-notification-7"), None);
+    assert_eq!(
+      notification_id(&format!("tao-notification-{}-x", process::id())),
+      None
+    );
+    assert_eq!(notification_id("com.apple.other"), None);
+  }
+}
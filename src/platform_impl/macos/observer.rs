@@ -23,6 +23,11 @@ extern "C" {
 
   pub fn CFRunLoopGetMain() -> CFRunLoopRef;
   pub fn CFRunLoopWakeUp(rl: CFRunLoopRef);
+  pub fn CFRunLoopRunInMode(
+    mode: CFRunLoopMode,
+    seconds: CFTimeInterval,
+    returnAfterSourceHandled: ffi::Boolean,
+  ) -> i32;
 
   pub fn CFRunLoopObserverCreate(
     allocator: CFAllocatorRef,
@@ -50,6 +55,7 @@ extern "C" {
   pub fn CFRunLoopAddTimer(rl: CFRunLoopRef, timer: CFRunLoopTimerRef, mode: CFRunLoopMode);
   pub fn CFRunLoopTimerSetNextFireDate(timer: CFRunLoopTimerRef, fireDate: CFAbsoluteTime);
   pub fn CFRunLoopTimerInvalidate(time: CFRunLoopTimerRef);
+  pub fn CFRunLoopTimerSetTolerance(timer: CFRunLoopTimerRef, tolerance: CFTimeInterval);
 
   pub fn CFRunLoopSourceCreate(
     allocator: CFAllocatorRef,
@@ -57,7 +63,6 @@ extern "C" {
     context: *mut CFRunLoopSourceContext,
   ) -> CFRunLoopSourceRef;
   pub fn CFRunLoopAddSource(rl: CFRunLoopRef, source: CFRunLoopSourceRef, mode: CFRunLoopMode);
-  #[allow(dead_code)]
   pub fn CFRunLoopSourceInvalidate(source: CFRunLoopSourceRef);
   pub fn CFRunLoopSourceSignal(source: CFRunLoopSourceRef);
 
@@ -274,6 +279,9 @@ impl Default for EventLoopWaker {
         wakeup_main_loop,
         ptr::null_mut(),
       );
+      // Don't let the system coalesce the timer with others, `ControlFlow::WaitUntil` should
+      // resume as close to the requested time as possible.
+      CFRunLoopTimerSetTolerance(timer, 0.0);
       CFRunLoopAddTimer(CFRunLoopGetMain(), timer, kCFRunLoopCommonModes);
       EventLoopWaker { timer }
     }
@@ -0,0 +1,177 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{cell::Cell, ffi::c_void, ptr};
+
+use cocoa::base::{id, BOOL, YES};
+use core_foundation::{
+  array::CFArray,
+  base::{CFRelease, CFType, TCFType},
+  dictionary::CFDictionary,
+  number::CFNumber,
+  string::CFString,
+};
+
+use super::{
+  app_state::AppState,
+  event::EventWrapper,
+  event_loop::EventLoopWindowTarget,
+  ffi,
+  observer::{
+    kCFRunLoopCommonModes, CFRunLoopAddSource, CFRunLoopGetMain, CFRunLoopSourceInvalidate,
+    CFRunLoopSourceRef,
+  },
+  OsError,
+};
+use crate::{error::ExternalError, event::Event, event_loop::PowerInfo};
+
+thread_local! {
+  static POWER_SOURCE: Cell<Option<CFRunLoopSourceRef>> = Cell::new(None);
+  static LAST_POWER_INFO: Cell<PowerInfo> = Cell::new(PowerInfo::default());
+}
+
+pub fn power_info() -> PowerInfo {
+  let (on_battery, percent) = unsafe { power_sources() };
+  PowerInfo {
+    on_battery,
+    percent,
+    power_saver: low_power_mode(),
+  }
+}
+
+unsafe fn power_sources() -> (Option<bool>, Option<u8>) {
+  let blob = ffi::IOPSCopyPowerSourcesInfo();
+  if blob.is_null() {
+    return (None, None);
+  }
+  let blob = CFType::wrap_under_create_rule(blob);
+
+  let providing = ffi::IOPSGetProvidingPowerSourceType(blob.as_CFTypeRef());
+  let on_battery = if providing.is_null() {
+    None
+  } else {
+    Some(CFString::wrap_under_get_rule(providing) == CFString::from_static_string("Battery Power"))
+  };
+
+  let list = ffi::IOPSCopyPowerSourcesList(blob.as_CFTypeRef());
+  if list.is_null() {
+    return (on_battery, None);
+  }
+  let list = CFArray::<CFType>::wrap_under_create_rule(list);
+  let percent = list.iter().find_map(|source| {
+    let description =
+      ffi::IOPSGetPowerSourceDescription(blob.as_CFTypeRef(), source.as_CFTypeRef());
+    if description.is_null() {
+      return None;
+    }
+    let description = CFDictionary::<CFString, CFType>::wrap_under_get_rule(description);
+    let number = |key: &'static str| {
+      description
+        .find(CFString::from_static_string(key))
+        .and_then(|value| value.downcast::<CFNumber>())
+        .and_then(|value| value.to_i64())
+    };
+    let is_battery = description
+      .find(CFString::from_static_string("Type"))
+      .and_then(|value| value.downcast::<CFString>())
+      .map_or(false, |kind| {
+        kind == CFString::from_static_string("InternalBattery")
+      });
+    match (
+      is_battery,
+      number("Current Capacity"),
+      number("Max Capacity"),
+    ) {
+      (true, Some(current), Some(max)) if max > 0 => {
+        Some((current * 100 / max).clamp(0, 100) as u8)
+      }
+      _ => None,
+    }
+  });
+  (on_battery, percent)
+}
+
+fn low_power_mode() -> bool {
+  unsafe {
+    let process_info: id = msg_send![class!(NSProcessInfo), processInfo];
+    // The low power mode came to macOS 12.
+    let responds: BOOL = msg_send![process_info, respondsToSelector: sel!(isLowPowerModeEnabled)];
+    if responds != YES {
+      return false;
+    }
+    let enabled: BOOL = msg_send![process_info, isLowPowerModeEnabled];
+    enabled == YES
+  }
+}
+
+/// Adds the power source notification to the main run loop, until [`stop_monitoring`].
+pub unsafe fn start_monitoring() {
+  if POWER_SOURCE.with(|source| source.get()).is_some() {
+    return;
+  }
+  LAST_POWER_INFO.with(|last| last.set(power_info()));
+  let source = ffi::IOPSNotificationCreateRunLoopSource(power_source_callback, ptr::null_mut());
+  if source.is_null() {
+    return;
+  }
+  CFRunLoopAddSource(CFRunLoopGetMain(), source, kCFRunLoopCommonModes);
+  POWER_SOURCE.with(|power_source| power_source.set(Some(source)));
+}
+
+pub unsafe fn stop_monitoring() {
+  if let Some(source) = POWER_SOURCE.with(|source| source.take()) {
+    // Invalidating the source also removes it from the run loop.
+    CFRunLoopSourceInvalidate(source);
+    CFRelease(source as _);
+  }
+}
+
+/// Queues `Event::PowerInfoChanged` if the power source or the low power mode changed, must be
+/// called on the main thread.
+pub fn power_info_changed() {
+  let info = power_info();
+  let previous = LAST_POWER_INFO.with(|last| last.replace(info));
+  // The battery charge alone isn't worth an event.
+  if previous.on_battery != info.on_battery || previous.power_saver != info.power_saver {
+    AppState::queue_event(EventWrapper::StaticEvent(Event::PowerInfoChanged(info)));
+  }
+}
+
+extern "C" fn power_source_callback(_context: *mut c_void) {
+  power_info_changed();
+}
+
+/// An IOKit assertion of the `PreventUserIdleDisplaySleep` type.
+pub struct PowerAssertion {
+  id: ffi::IOPMAssertionID,
+}
+
+impl PowerAssertion {
+  pub fn keep_display_awake<T: 'static>(
+    _window_target: &EventLoopWindowTarget<T>,
+    reason: &str,
+  ) -> Result<Self, ExternalError> {
+    let assertion_type = CFString::from_static_string("PreventUserIdleDisplaySleep");
+    let reason = CFString::new(reason);
+    let mut id = 0;
+    let result = unsafe {
+      ffi::IOPMAssertionCreateWithName(
+        assertion_type.as_concrete_TypeRef(),
+        ffi::kIOPMAssertionLevelOn,
+        reason.as_concrete_TypeRef(),
+        &mut id,
+      )
+    };
+    if result == ffi::kIOReturnSuccess {
+      Ok(Self { id })
+    } else {
+      Err(ExternalError::Os(os_error!(OsError::OSStatus(result))))
+    }
+  }
+}
+
+impl Drop for PowerAssertion {
+  fn drop(&mut self) {
+    unsafe { ffi::IOPMAssertionRelease(self.id) };
+  }
+}
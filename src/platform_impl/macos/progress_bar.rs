@@ -1,7 +1,7 @@
 use std::sync::Once;
 
 use cocoa::{
-  base::{id, nil},
+  base::{id, nil, BOOL, YES},
   foundation::{NSArray, NSPoint, NSRect, NSSize},
 };
 use objc::{
@@ -11,6 +11,13 @@ use objc::{
 
 use crate::window::{ProgressBarState, ProgressState};
 
+/// Interval between two frames of the indeterminate animation, in seconds.
+const INDETERMINATE_FRAME_INTERVAL: f64 = 1.0 / 20.0;
+/// Time for the indeterminate segment to cross the bar, in seconds.
+const INDETERMINATE_PERIOD: f64 = 1.5;
+/// Width of the indeterminate segment relative to the bar.
+const INDETERMINATE_SEGMENT: f64 = 0.3;
+
 /// Set progress indicator in the Dock.
 pub fn set_progress_indicator(progress_state: ProgressBarState) {
   unsafe {
@@ -20,6 +27,12 @@ pub fn set_progress_indicator(progress_state: ProgressBarState) {
       return;
     }
 
+    if matches!(progress_state.state, Some(ProgressState::None)) {
+      remove_progress_indicator(dock_tile);
+      let _: () = msg_send![dock_tile, display];
+      return;
+    }
+
     // check progress indicator is already set or create new one
     let progress_indicator: id = get_exist_progress_indicator(dock_tile)
       .unwrap_or_else(|| create_progress_indicator(ns_app, dock_tile));
@@ -32,20 +45,54 @@ pub fn set_progress_indicator(progress_state: ProgressBarState) {
     }
     if let Some(state) = progress_state.state {
       (*progress_indicator).set_ivar("state", state as u8);
-      let _: () = msg_send![
+      let _: () = msg_send![progress_indicator, setHidden: NO];
+      set_animating(
         progress_indicator,
-        setHidden: matches!(state, ProgressState::None)
-      ];
+        matches!(state, ProgressState::Indeterminate),
+      );
     }
 
     let _: () = msg_send![dock_tile, display];
   }
 }
 
+/// Removes the progress indicator and the content view created for it from the Dock tile.
+unsafe fn remove_progress_indicator(dock_tile: id) {
+  if let Some(progress_indicator) = get_exist_progress_indicator(dock_tile) {
+    // the animation timer retains the indicator, so it has to be stopped first
+    set_animating(progress_indicator, false);
+    let owns_content_view: BOOL = *(*progress_indicator).get_ivar("ownsContentView");
+    let _: () = msg_send![progress_indicator, removeFromSuperview];
+    if owns_content_view == YES {
+      let _: () = msg_send![dock_tile, setContentView: nil];
+    }
+  }
+}
+
+/// Starts or stops the timer that redraws the Dock tile for the indeterminate animation.
+unsafe fn set_animating(progress_indicator: id, animating: bool) {
+  let timer: id = *(*progress_indicator).get_ivar("animationTimer");
+  if animating && timer == nil {
+    let timer: id = msg_send![
+      class!(NSTimer),
+      scheduledTimerWithTimeInterval: INDETERMINATE_FRAME_INTERVAL
+      target: progress_indicator
+      selector: sel!(animationTick:)
+      userInfo: nil
+      repeats: YES
+    ];
+    (*progress_indicator).set_ivar("animationTimer", timer);
+  } else if !animating && timer != nil {
+    let _: () = msg_send![timer, invalidate];
+    (*progress_indicator).set_ivar("animationTimer", nil);
+  }
+}
+
 fn create_progress_indicator(ns_app: id, dock_tile: id) -> id {
   unsafe {
     let mut image_view: id = msg_send![dock_tile, contentView];
-    if image_view == nil {
+    let owns_content_view = image_view == nil;
+    if owns_content_view {
       // create new dock tile view with current app icon
       let app_icon_image: id = msg_send![ns_app, applicationIconImage];
       image_view = msg_send![class!(NSImageView), imageViewWithImage: app_icon_image];
@@ -62,6 +109,8 @@ fn create_progress_indicator(ns_app: id, dock_tile: id) -> id {
     let progress_indicator: id = msg_send![progress_class, alloc];
     let progress_indicator: id = msg_send![progress_indicator, initWithFrame: frame];
     let _: () = msg_send![progress_indicator, autorelease];
+    (*progress_indicator)
+      .set_ivar::<BOOL>("ownsContentView", if owns_content_view { YES } else { NO });
 
     // set progress indicator to the dock tile
     let _: () = msg_send![image_view, addSubview: progress_indicator];
@@ -107,8 +156,17 @@ fn create_progress_indicator_class() -> *const Class {
       draw_progress_bar as extern "C" fn(&Object, _, NSRect),
     );
 
+    decl.add_method(
+      sel!(animationTick:),
+      animation_tick as extern "C" fn(&mut Object, _, id),
+    );
+
     // progress bar states, follows ProgressState
     decl.add_ivar::<u8>("state");
+    // offset of the indeterminate segment, from 0.0 to 1.0
+    decl.add_ivar::<f64>("animationPhase");
+    decl.add_ivar::<id>("animationTimer");
+    decl.add_ivar::<BOOL>("ownsContentView");
 
     APP_CLASS = decl.register();
   });
@@ -116,6 +174,20 @@ fn create_progress_indicator_class() -> *const Class {
   unsafe { APP_CLASS }
 }
 
+extern "C" fn animation_tick(this: &mut Object, _: Sel, _timer: id) {
+  unsafe {
+    let phase: f64 = *this.get_ivar("animationPhase");
+    this.set_ivar(
+      "animationPhase",
+      (phase + INDETERMINATE_FRAME_INTERVAL / INDETERMINATE_PERIOD) % 1.0,
+    );
+
+    let ns_app: id = msg_send![class!(NSApplication), sharedApplication];
+    let dock_tile: id = msg_send![ns_app, dockTile];
+    let _: () = msg_send![dock_tile, display];
+  }
+}
+
 extern "C" fn draw_progress_bar(this: &Object, _: Sel, rect: NSRect) {
   unsafe {
     let bar = NSRect::new(
@@ -127,11 +199,25 @@ extern "C" fn draw_progress_bar(this: &Object, _: Sel, rect: NSRect) {
     );
     let bar_inner = bar.inset(0.5, 0.5);
     let mut bar_progress = bar.inset(1.0, 1.0);
-
-    // set progress width
-    let current_progress: f64 = msg_send![this, doubleValue];
-    let normalized_progress: f64 = (current_progress / 100.0).clamp(0.0, 1.0);
-    bar_progress.size.width *= normalized_progress;
+    let state: u8 = *(this.get_ivar("state"));
+    let indeterminate = state == ProgressState::Indeterminate as u8;
+
+    if indeterminate {
+      // a segment that slides from the left edge to the right edge
+      let phase: f64 = *this.get_ivar("animationPhase");
+      let track_width = bar_progress.size.width;
+      let segment_width = track_width * INDETERMINATE_SEGMENT;
+      let x = bar_progress.origin.x - segment_width + (track_width + segment_width) * phase;
+      let start = x.max(bar_progress.origin.x);
+      let end = (x + segment_width).min(bar_progress.origin.x + track_width);
+      bar_progress.origin.x = start;
+      bar_progress.size.width = (end - start).max(0.0);
+    } else {
+      // set progress width
+      let current_progress: f64 = msg_send![this, doubleValue];
+      let normalized_progress: f64 = (current_progress / 100.0).clamp(0.0, 1.0);
+      bar_progress.size.width *= normalized_progress;
+    }
 
     // draw outer bar
     let bg_color: id = msg_send![class!(NSColor), colorWithWhite:1.0 alpha:0.05];
@@ -141,7 +227,6 @@ extern "C" fn draw_progress_bar(this: &Object, _: Sel, rect: NSRect) {
     draw_rounded_rect(bar_inner);
 
     // draw progress
-    let state: u8 = *(this.get_ivar("state"));
     let progress_color: id = match state {
       x if x == ProgressState::Paused as u8 => msg_send![class!(NSColor), systemYellowColor],
       x if x == ProgressState::Error as u8 => msg_send![class!(NSColor), systemRedColor],
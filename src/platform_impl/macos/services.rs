@@ -0,0 +1,109 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  ffi::CString,
+  mem,
+  path::PathBuf,
+  sync::atomic::{AtomicPtr, Ordering},
+};
+
+use cocoa::{
+  appkit::NSApp,
+  base::{id, nil},
+  foundation::{NSArray, NSString},
+};
+use objc::{
+  declare::ClassDecl,
+  runtime::{self, Class, Imp, Object, Sel, YES},
+};
+
+use super::{app_state::AppState, event::EventWrapper, ffi, util};
+use crate::event::{Event, ServiceRequest};
+
+/// The suffix of the selector of a service, after its `NSMessage`.
+const SELECTOR_SUFFIX: &str = ":userData:error:";
+
+struct ServicesProviderClass(*const Class);
+unsafe impl Send for ServicesProviderClass {}
+unsafe impl Sync for ServicesProviderClass {}
+
+lazy_static! {
+  static ref SERVICES_PROVIDER_CLASS: ServicesProviderClass = unsafe {
+    let decl = ClassDecl::new("TaoServicesProvider", class!(NSObject)).unwrap();
+    ServicesProviderClass(decl.register())
+  };
+}
+
+/// The services provider of the application, which lives as long as the process.
+static PROVIDER: AtomicPtr<Object> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Makes the application provide the service whose `NSMessage` is `message`, delivering its
+/// requests as `Event::ServiceRequested`.
+pub unsafe fn register(message: &str) {
+  let class = SERVICES_PROVIDER_CLASS.0 as *mut Class;
+  let selector = Sel::register(&format!("{}{}", message, SELECTOR_SUFFIX));
+  // The arguments are the pasteboard, the user data and a pointer to the error.
+  let types = CString::new("v@:@@^@").unwrap();
+  let imp: Imp = mem::transmute(perform_service as extern "C" fn(&Object, Sel, id, id, *mut id));
+  // Fails when the service was already registered, which is fine.
+  runtime::class_addMethod(class, selector, imp, types.as_ptr());
+
+  if PROVIDER.load(Ordering::Acquire).is_null() {
+    let provider: id = msg_send![class, new];
+    PROVIDER.store(provider, Ordering::Release);
+    let () = msg_send![NSApp(), setServicesProvider: provider];
+  }
+}
+
+extern "C" fn perform_service(
+  _: &Object,
+  selector: Sel,
+  pasteboard: id,
+  user_data: id,
+  _: *mut id,
+) {
+  trace!("Triggered service `{}`", selector.name());
+  let message = selector
+    .name()
+    .strip_suffix(SELECTOR_SUFFIX)
+    .unwrap_or_else(|| selector.name())
+    .to_owned();
+  let request = unsafe {
+    ServiceRequest {
+      message,
+      user_data: (user_data != nil).then(|| util::ns_string_to_rust(user_data)),
+      text: pasteboard_text(pasteboard),
+      files: pasteboard_files(pasteboard),
+    }
+  };
+  AppState::queue_event_and_wake(EventWrapper::StaticEvent(Event::ServiceRequested(request)));
+  trace!("Completed service");
+}
+
+pub(super) unsafe fn pasteboard_text(pasteboard: id) -> Option<String> {
+  let string_type = NSString::alloc(nil).init_str("public.utf8-plain-text");
+  let string: id = msg_send![pasteboard, stringForType: string_type];
+  let () = msg_send![string_type, release];
+  (string != nil).then(|| util::ns_string_to_rust(string))
+}
+
+pub(super) unsafe fn pasteboard_files(pasteboard: id) -> Vec<PathBuf> {
+  let classes = NSArray::arrayWithObject(nil, class!(NSURL) as *const Class as id);
+  let yes: id = msg_send![class!(NSNumber), numberWithBool: YES];
+  let options: id = msg_send![
+    class!(NSDictionary),
+    dictionaryWithObject: yes
+    forKey: ffi::NSPasteboardURLReadingFileURLsOnlyKey
+  ];
+  let urls: id = msg_send![pasteboard, readObjectsForClasses: classes options: options];
+  if urls == nil {
+    return Vec::new();
+  }
+  (0..urls.count())
+    .map(|i| {
+      let path: id = msg_send![urls.objectAtIndex(i), path];
+      PathBuf::from(util::ns_string_to_rust(path))
+    })
+    .collect()
+}
@@ -0,0 +1,117 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  env,
+  ffi::{c_void, OsString},
+  os::unix::ffi::{OsStrExt, OsStringExt},
+  path::PathBuf,
+  process, ptr, thread,
+  time::{Duration, Instant},
+};
+
+use core_foundation::{
+  base::{kCFAllocatorDefault, CFRelease, TCFType},
+  data::{CFData, CFDataRef},
+  string::CFString,
+};
+
+use super::{
+  app_state::AppState,
+  event::EventWrapper,
+  ffi::{self, CFMessagePortRef},
+  observer::{kCFRunLoopCommonModes, CFRunLoopAddSource, CFRunLoopGetMain},
+};
+use crate::event::Event;
+
+/// How long a second instance keeps trying to reach the first one.
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Opens the message port of the first instance of `identifier` on the main run loop. If another
+/// instance already holds it, forwards the command line of this process to it and exits.
+pub unsafe fn acquire(identifier: &str) {
+  let name = CFString::new(identifier);
+  let data = CFData::from_buffer(&command_line());
+  let deadline = Instant::now() + FORWARD_TIMEOUT;
+  loop {
+    // Registering the port name is atomic, a single one of simultaneous launches gets it.
+    let local = ffi::CFMessagePortCreateLocal(
+      kCFAllocatorDefault,
+      name.as_concrete_TypeRef(),
+      message_callback,
+      ptr::null_mut(),
+      ptr::null_mut(),
+    );
+    if !local.is_null() {
+      // The port and its source live as long as the process.
+      let source = ffi::CFMessagePortCreateRunLoopSource(kCFAllocatorDefault, local, 0);
+      CFRunLoopAddSource(CFRunLoopGetMain(), source, kCFRunLoopCommonModes);
+      return;
+    }
+
+    // The first instance may be exiting and release the name for this process to take over.
+    let remote = ffi::CFMessagePortCreateRemote(kCFAllocatorDefault, name.as_concrete_TypeRef());
+    if !remote.is_null() {
+      let status = ffi::CFMessagePortSendRequest(
+        remote,
+        0,
+        data.as_concrete_TypeRef(),
+        FORWARD_TIMEOUT.as_secs_f64(),
+        0.0,
+        ptr::null(),
+        ptr::null_mut(),
+      );
+      CFRelease(remote as _);
+      if status == ffi::kCFMessagePortSuccess {
+        process::exit(0);
+      }
+    }
+    if Instant::now() >= deadline {
+      warn!("The first instance of `{}` didn't answer", identifier);
+      process::exit(0);
+    }
+    thread::sleep(Duration::from_millis(50));
+  }
+}
+
+/// The working directory followed by the arguments of this process, each terminated by a nul.
+fn command_line() -> Vec<u8> {
+  let cwd = env::current_dir().unwrap_or_default();
+  let mut data: Vec<u8> = cwd.as_os_str().as_bytes().to_vec();
+  data.push(0);
+  for arg in env::args_os() {
+    data.extend_from_slice(arg.as_bytes());
+    data.push(0);
+  }
+  data
+}
+
+fn decode(data: &[u8]) -> Option<(Vec<OsString>, PathBuf)> {
+  let mut parts = data
+    .split(|c| *c == 0)
+    .map(|part| OsString::from_vec(part.to_vec()));
+  let cwd = PathBuf::from(parts.next()?);
+  let mut args: Vec<OsString> = parts.collect();
+  // The nul terminating the last argument leaves an empty part.
+  args.pop();
+  Some((args, cwd))
+}
+
+extern "C" fn message_callback(
+  _local: CFMessagePortRef,
+  _msgid: i32,
+  data: CFDataRef,
+  _info: *mut c_void,
+) -> CFDataRef {
+  if !data.is_null() {
+    let data = unsafe { CFData::wrap_under_get_rule(data) };
+    if let Some((args, cwd)) = decode(data.bytes()) {
+      AppState::queue_event(EventWrapper::StaticEvent(Event::SecondInstance {
+        args,
+        cwd,
+        activation_token: None,
+      }));
+    }
+  }
+  ptr::null()
+}
@@ -0,0 +1,193 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use cocoa::{
+  appkit::{NSApp, NSEvent, NSEventMask, NSEventType},
+  base::{id, nil, BOOL},
+  foundation::{NSInteger, NSPoint, NSSize, NSString},
+};
+use core_graphics::base::CGFloat;
+use objc::{
+  declare::ClassDecl,
+  runtime::{Class, Object, Sel},
+};
+
+use super::{app_state::AppState, event::EventWrapper, menu::Submenu, util, EventLoopWindowTarget};
+use crate::{
+  error::ExternalError,
+  event::{Event, MouseButton, SystemTrayEvent},
+  icon::Icon,
+  system_tray::SystemTrayId,
+};
+
+/// `NSVariableStatusItemLength`, the status item fits its image.
+const VARIABLE_STATUS_ITEM_LENGTH: CGFloat = -1.0;
+/// The size of the image in the menu bar, in points.
+const ICON_SIZE: CGFloat = 18.0;
+/// The ivar of the target holding the id of its system tray.
+const TRAY_ID_IVAR: &str = "taoTrayId";
+/// The ivar of the target holding the retained `NSMenu` shown on a right click, or `nil`.
+const TRAY_MENU_IVAR: &str = "taoTrayMenu";
+
+struct SystemTrayTargetClass(*const Class);
+unsafe impl Send for SystemTrayTargetClass {}
+unsafe impl Sync for SystemTrayTargetClass {}
+
+lazy_static! {
+  static ref SYSTEM_TRAY_TARGET_CLASS: SystemTrayTargetClass = unsafe {
+    let mut decl = ClassDecl::new("TaoSystemTrayTarget", class!(NSObject)).unwrap();
+    decl.add_ivar::<u32>(TRAY_ID_IVAR);
+    decl.add_ivar::<id>(TRAY_MENU_IVAR);
+    decl.add_method(sel!(click:), click as extern "C" fn(&Object, Sel, id));
+    SystemTrayTargetClass(decl.register())
+  };
+}
+
+pub struct SystemTray {
+  status_item: id,
+  /// The target of the button of the status item, which doesn't retain it.
+  target: id,
+}
+
+impl SystemTray {
+  pub fn new<T>(
+    _window_target: &EventLoopWindowTarget<T>,
+    id: u32,
+    icon: Icon,
+    tooltip: Option<String>,
+  ) -> Result<Self, ExternalError> {
+    unsafe {
+      let status_bar: id = msg_send![class!(NSStatusBar), systemStatusBar];
+      let status_item: id =
+        msg_send![status_bar, statusItemWithLength: VARIABLE_STATUS_ITEM_LENGTH];
+      let status_item: id = msg_send![status_item, retain];
+
+      let target: id = msg_send![SYSTEM_TRAY_TARGET_CLASS.0, new];
+      (*target).set_ivar(TRAY_ID_IVAR, id);
+      (*target).set_ivar(TRAY_MENU_IVAR, nil);
+      let button: id = msg_send![status_item, button];
+      let () = msg_send![button, setTarget: target];
+      let () = msg_send![button, setAction: sel!(click:)];
+      let mask = NSEventMask::NSLeftMouseUpMask
+        | NSEventMask::NSRightMouseUpMask
+        | NSEventMask::NSOtherMouseUpMask;
+      let _: NSInteger = msg_send![button, sendActionOn: mask.bits()];
+
+      let tray = Self {
+        status_item,
+        target,
+      };
+      tray.set_icon(icon);
+      tray.set_tooltip(tooltip.as_deref());
+      Ok(tray)
+    }
+  }
+
+  pub fn set_icon(&self, icon: Icon) {
+    unsafe {
+      let image = icon.inner.to_ns_image();
+      let () = msg_send![image, setSize: NSSize::new(ICON_SIZE, ICON_SIZE)];
+      let button: id = msg_send![self.status_item, button];
+      let () = msg_send![button, setImage: image];
+    }
+  }
+
+  pub fn set_tooltip(&self, tooltip: Option<&str>) {
+    unsafe {
+      let button: id = msg_send![self.status_item, button];
+      match tooltip {
+        Some(tooltip) => {
+          let tooltip = NSString::alloc(nil).init_str(tooltip);
+          let () = msg_send![button, setToolTip: tooltip];
+          let () = msg_send![tooltip, release];
+        }
+        None => {
+          let () = msg_send![button, setToolTip: nil];
+        }
+      }
+    }
+  }
+
+  pub fn set_menu(&self, menu: Option<&Submenu>) {
+    unsafe {
+      let ns_menu = menu.map_or(nil, |menu| {
+        let ns_menu: id = msg_send![menu.ns_menu(), retain];
+        ns_menu
+      });
+      let previous = replace_menu(self.target, ns_menu);
+      if previous != nil {
+        let () = msg_send![previous, release];
+      }
+    }
+  }
+}
+
+/// Sets the menu of `target`, and returns the one it replaces.
+unsafe fn replace_menu(target: id, ns_menu: id) -> id {
+  let previous: id = *(*target).get_ivar(TRAY_MENU_IVAR);
+  (*target).set_ivar(TRAY_MENU_IVAR, ns_menu);
+  previous
+}
+
+impl Drop for SystemTray {
+  fn drop(&mut self) {
+    unsafe {
+      let ns_menu = replace_menu(self.target, nil);
+      if ns_menu != nil {
+        let () = msg_send![ns_menu, release];
+      }
+      let status_bar: id = msg_send![class!(NSStatusBar), systemStatusBar];
+      let () = msg_send![status_bar, removeStatusItem: self.status_item];
+      let () = msg_send![self.status_item, release];
+      let () = msg_send![self.target, release];
+    }
+  }
+}
+
+extern "C" fn click(this: &Object, _: Sel, button: id) {
+  trace!("Triggered `click:`");
+  unsafe {
+    let tray_id = SystemTrayId(*this.get_ivar::<u32>(TRAY_ID_IVAR));
+    let event: id = msg_send![NSApp(), currentEvent];
+    if event == nil {
+      return;
+    }
+    let window: id = msg_send![button, window];
+    let scale_factor: CGFloat = msg_send![window, backingScaleFactor];
+    let position = util::cursor_position().to_physical(scale_factor);
+
+    let mouse_button = match event.eventType() {
+      NSEventType::NSLeftMouseUp => MouseButton::Left,
+      NSEventType::NSRightMouseUp => MouseButton::Right,
+      _ => match event.buttonNumber() {
+        2 => MouseButton::Middle,
+        n => MouseButton::Other(n as u16),
+      },
+    };
+    let mut events = vec![SystemTrayEvent::Click {
+      button: mouse_button,
+      position,
+    }];
+    if mouse_button == MouseButton::Left && event.clickCount() == 2 {
+      events.push(SystemTrayEvent::DoubleClick { position });
+    }
+    for event in events {
+      AppState::queue_event_and_wake(EventWrapper::StaticEvent(Event::SystemTrayEvent {
+        tray_id,
+        event,
+      }));
+    }
+
+    let ns_menu: id = *this.get_ivar(TRAY_MENU_IVAR);
+    if mouse_button == MouseButton::Right && ns_menu != nil {
+      // Below the button, whose origin is at its bottom left.
+      let _: BOOL = msg_send![
+        ns_menu,
+        popUpMenuPositioningItem: nil
+        atLocation: NSPoint::new(0.0, 0.0)
+        inView: button
+      ];
+    }
+  }
+  trace!("Completed `click:`");
+}
@@ -8,9 +8,12 @@ use std::{
 };
 
 use cocoa::{
-  appkit::{CGFloat, NSScreen, NSWindow, NSWindowStyleMask},
+  appkit::{
+    CGFloat, NSScreen, NSWindow, NSWindowCollectionBehavior, NSWindowOrderingMode,
+    NSWindowStyleMask,
+  },
   base::{id, nil},
-  foundation::{NSPoint, NSSize, NSString},
+  foundation::{NSArray, NSInteger, NSPoint, NSRect, NSSize, NSString},
 };
 use dispatch::Queue;
 use objc::{
@@ -19,8 +22,9 @@ use objc::{
 };
 
 use crate::{
-  dpi::LogicalSize,
-  platform_impl::platform::{ffi, util::IdRef, window::SharedState},
+  dpi::{LogicalSize, Rect},
+  platform::macos::MetalLayerOptions,
+  platform_impl::platform::{ffi, util::IdRef, view, window::SharedState},
 };
 
 pub fn is_main_thread() -> bool {
@@ -58,6 +62,22 @@ unsafe fn set_style_mask(ns_window: id, ns_view: id, mask: NSWindowStyleMask) {
   ns_window.makeFirstResponder_(ns_view);
 }
 
+// `set_ime_allowed` queues events, which is only possible on the main thread.
+pub unsafe fn set_ime_allowed_async(ns_view: id, allowed: bool) {
+  let ns_view = MainThreadSafe(ns_view);
+  Queue::main().exec_async(move || {
+    view::set_ime_allowed(*ns_view, allowed);
+  });
+}
+
+// The mouse monitors of the hit-test region must be added on the main thread.
+pub unsafe fn set_cursor_hittest_region_async(ns_view: id, region: Option<Vec<Rect>>) {
+  let ns_view = MainThreadSafe(ns_view);
+  Queue::main().exec_async(move || {
+    view::set_cursor_hittest_region(*ns_view, region);
+  });
+}
+
 // Always use this function instead of trying to modify `styleMask` directly!
 // `setStyleMask:` isn't thread-safe, so we have to use Grand Central Dispatch.
 // Otherwise, this would vomit out errors about not being on the main thread
@@ -99,20 +119,74 @@ pub unsafe fn set_frame_top_left_point_async(ns_window: id, point: NSPoint) {
   });
 }
 
+// `setFrame:display:` isn't thread-safe either.
+pub unsafe fn set_frame_async(ns_window: id, frame: NSRect) {
+  let ns_window = MainThreadSafe(ns_window);
+  Queue::main().exec_async(move || {
+    ns_window.setFrame_display_(frame, YES);
+  });
+}
+
+// `NSTextInputContext` isn't thread-safe.
+pub unsafe fn set_roman_input_only_async(input_context: IdRef, roman_only: bool) {
+  let input_context = MainThreadSafe(input_context);
+  Queue::main().exec_async(move || {
+    let locales: id = if roman_only {
+      NSArray::arrayWithObject(nil, ffi::NSAllRomanInputSourcesLocaleIdentifier)
+    } else {
+      nil
+    };
+    let _: () = msg_send![**input_context, setAllowedInputSourceLocales: locales];
+  });
+}
+
 // `setFrameTopLeftPoint:` isn't thread-safe, and fails silently.
+// A screen saver level window also shows over fullscreen windows, which takes
+// `FullScreenAuxiliary`. Only the bit added here is cleared again, not one the user set.
 pub unsafe fn set_level_async(ns_window: id, level: ffi::NSWindowLevel) {
   let ns_window = MainThreadSafe(ns_window);
   Queue::main().exec_async(move || {
+    let auxiliary = NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenAuxiliary;
+    let behavior = ns_window.collectionBehavior();
+    let added: BOOL = *(**ns_window).get_ivar("taoAddedFullScreenAuxiliary");
+    if matches!(level, ffi::NSWindowLevel::NSScreenSaverWindowLevel) {
+      if !behavior.contains(auxiliary) {
+        ns_window.setCollectionBehavior_(behavior | auxiliary);
+        (**ns_window).set_ivar::<BOOL>("taoAddedFullScreenAuxiliary", YES);
+      }
+    } else if added == YES {
+      ns_window.setCollectionBehavior_(behavior & !auxiliary);
+      (**ns_window).set_ivar::<BOOL>("taoAddedFullScreenAuxiliary", NO);
+    }
     ns_window.setLevel_(level as _);
   });
 }
 
+// A window is kept above its parent, and moves along with it, once added as a child.
+pub unsafe fn set_parent_async(ns_window: id, parent: Option<id>) {
+  let ns_window = MainThreadSafe(ns_window);
+  let parent = MainThreadSafe(parent);
+  Queue::main().exec_async(move || {
+    let current: id = msg_send![*ns_window, parentWindow];
+    if current != nil {
+      let () = msg_send![current, removeChildWindow: *ns_window];
+    }
+    if let Some(parent) = *parent {
+      let () =
+        msg_send![parent, addChildWindow: *ns_window ordered: NSWindowOrderingMode::NSWindowAbove];
+    }
+  });
+}
+
 // `toggleFullScreen` is thread-safe, but our additional logic to account for
 // window styles isn't.
+// `frame` is applied right before the toggle, in the same block, so a window going fullscreen on
+// another screen isn't shown there at its old size first.
 pub unsafe fn toggle_full_screen_async(
   ns_window: id,
   ns_view: id,
   not_fullscreen: bool,
+  frame: Option<NSRect>,
   shared_state: Weak<Mutex<SharedState>>,
 ) {
   let ns_window = MainThreadSafe(ns_window);
@@ -140,6 +214,9 @@ pub unsafe fn toggle_full_screen_async(
     // + 1` back to normal in order for `toggleFullScreen` to do
     // anything
     ns_window.setLevel_(0);
+    if let Some(frame) = frame {
+      ns_window.setFrame_display_(frame, NO);
+    }
     ns_window.toggleFullScreen_(nil);
   });
 }
@@ -197,6 +274,45 @@ pub unsafe fn set_maximized_async(
   });
 }
 
+unsafe fn set_metal_layer(ns_window: id, ns_view: id, options: MetalLayerOptions) -> id {
+  let mut layer: id = msg_send![ns_view, layer];
+  let is_metal_layer: BOOL = if layer == nil {
+    NO
+  } else {
+    msg_send![layer, isKindOfClass: class!(CAMetalLayer)]
+  };
+  if is_metal_layer == NO {
+    layer = msg_send![class!(CAMetalLayer), layer];
+    // Setting the layer before `wantsLayer` makes the view host it instead of drawing into it.
+    let _: () = msg_send![ns_view, setLayer: layer];
+    let _: () = msg_send![ns_view, setWantsLayer: YES];
+    // The mask of the shape belonged to the previous layer.
+    view::update_shape(ns_view);
+  }
+
+  if options.contents_scale_follows_window {
+    let scale_factor = NSWindow::backingScaleFactor(ns_window);
+    let _: () = msg_send![layer, setContentsScale: scale_factor];
+  }
+  let display_sync: BOOL = msg_send![layer, respondsToSelector: sel!(setDisplaySyncEnabled:)];
+  if display_sync == YES {
+    let _: () = msg_send![layer, setDisplaySyncEnabled: options.display_sync as BOOL];
+  }
+  let _: () = msg_send![
+    layer,
+    setWantsExtendedDynamicRangeContent: options.extended_dynamic_range as BOOL
+  ];
+  layer
+}
+
+// Layers must be set up on the main thread, where the view draws.
+pub unsafe fn set_metal_layer_sync(ns_window: id, ns_view: id, options: MetalLayerOptions) -> id {
+  let ns_window = MainThreadSafe(ns_window);
+  let ns_view = MainThreadSafe(ns_view);
+  let layer = run_on_main(move || MainThreadSafe(set_metal_layer(*ns_window, *ns_view, options)));
+  *layer
+}
+
 // `orderOut:` isn't thread-safe. Calling it from another thread actually works,
 // but with an odd delay.
 pub unsafe fn order_out_sync(ns_window: id) {
@@ -206,8 +322,36 @@ pub unsafe fn order_out_sync(ns_window: id) {
   });
 }
 
+// Zooms the window toward `target` before ordering it out, then restores its frame for the next
+// time it's shown. The animation blocks the main thread like `orderOut:`.
+pub unsafe fn zoom_out_sync(ns_window: id, target: NSRect) {
+  let ns_window = MainThreadSafe(ns_window);
+  run_on_main(move || {
+    let frame = NSWindow::frame(*ns_window);
+    let () = msg_send![*ns_window, setFrame: target display: YES animate: YES];
+    ns_window.orderOut_(nil);
+    ns_window.setFrame_display_(frame, NO);
+  });
+}
+
 // `makeKeyAndOrderFront:` isn't thread-safe. Calling it from another thread
 // actually works, but with an odd delay.
+pub unsafe fn order_front_sync(ns_window: id) {
+  let ns_window = MainThreadSafe(ns_window);
+  run_on_main(move || {
+    ns_window.orderFront_(nil);
+  });
+}
+
+// Orders the window above or below the one numbered `relative_to`, or all the windows of its level
+// with `0`, without making it key.
+pub unsafe fn order_window_sync(ns_window: id, mode: NSWindowOrderingMode, relative_to: NSInteger) {
+  let ns_window = MainThreadSafe(ns_window);
+  run_on_main(move || {
+    let () = msg_send![*ns_window, orderWindow: mode relativeTo: relative_to];
+  });
+}
+
 pub unsafe fn make_key_and_order_front_sync(ns_window: id) {
   let ns_window = MainThreadSafe(ns_window);
   run_on_main(move || {
@@ -227,13 +371,28 @@ pub unsafe fn set_title_async(ns_window: id, title: String) {
 }
 
 // `setFocus:` isn't thread-safe.
-pub unsafe fn set_focus(ns_window: id) {
+//
+// Returns whether the window could be brought to front, minimized windows are left alone.
+pub unsafe fn set_focus(ns_window: id) -> bool {
   let ns_window = MainThreadSafe(ns_window);
   run_on_main(move || {
-    ns_window.makeKeyAndOrderFront_(nil);
     let app: id = msg_send![class!(NSApplication), sharedApplication];
+    // Windows of a hidden application are not visible either.
+    let is_hidden: BOOL = msg_send![app, isHidden];
+    if is_hidden == YES {
+      let () = msg_send![app, unhide: nil];
+    }
+
+    let is_minimized: BOOL = msg_send![*ns_window, isMiniaturized];
+    let is_visible: BOOL = msg_send![*ns_window, isVisible];
+    if is_minimized == YES || is_visible == NO {
+      return false;
+    }
+
+    ns_window.makeKeyAndOrderFront_(nil);
     let () = msg_send![app, activateIgnoringOtherApps: YES];
-  });
+    true
+  })
 }
 
 // `close:` is thread-safe, but we want the event to be triggered from the main
@@ -250,6 +409,18 @@ pub unsafe fn close_async(ns_window: IdRef) {
   });
 }
 
+// Goes through the delegate like the close button, without the alert sound `performClose:` plays
+// when the close is vetoed.
+pub unsafe fn request_close_async(ns_window: IdRef) {
+  let ns_window = MainThreadSafe(ns_window);
+  Queue::main().exec_async(move || {
+    let delegate: id = msg_send![**ns_window, delegate];
+    if delegate != nil {
+      let _: BOOL = msg_send![delegate, windowShouldClose: **ns_window];
+    }
+  });
+}
+
 // `setIgnoresMouseEvents_:` isn't thread-safe, and fails silently.
 pub unsafe fn set_ignore_mouse_events(ns_window: id, ignore: bool) {
   let ns_window = MainThreadSafe(ns_window);
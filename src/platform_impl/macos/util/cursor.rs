@@ -7,16 +7,23 @@ use cocoa::{
   base::{id, nil},
   foundation::{NSDictionary, NSPoint, NSString},
 };
-use objc::runtime::{Sel, NO};
+use objc::runtime::{Sel, BOOL, NO};
 use std::{cell::RefCell, ptr::null_mut};
 
+use super::IdRef;
 use crate::window::CursorIcon;
 
 pub enum Cursor {
   Default,
   Native(&'static str),
-  Undocumented(&'static str),
-  WebKit(&'static str),
+  /// Private `NSCursor` selector, with the public selector used when the
+  /// running macOS doesn't provide it.
+  Undocumented(&'static str, &'static str),
+  /// Cursor resources shipped with HIServices, with the public selector used
+  /// when the resource is missing.
+  WebKit(&'static str, &'static str),
+  /// An `NSCursor` made from an image, see `Window::set_custom_cursor`.
+  Custom(IdRef),
 }
 
 impl From<CursorIcon> for Cursor {
@@ -43,15 +50,27 @@ impl From<CursorIcon> for Cursor {
       CursorIcon::NsResize | CursorIcon::RowResize => Cursor::Native("resizeUpDownCursor"),
 
       // Undocumented cursors: https://stackoverflow.com/a/46635398/5435443
-      CursorIcon::Help => Cursor::Undocumented("_helpCursor"),
-      CursorIcon::ZoomIn => Cursor::Undocumented("_zoomInCursor"),
-      CursorIcon::ZoomOut => Cursor::Undocumented("_zoomOutCursor"),
-      CursorIcon::NeResize => Cursor::Undocumented("_windowResizeNorthEastCursor"),
-      CursorIcon::NwResize => Cursor::Undocumented("_windowResizeNorthWestCursor"),
-      CursorIcon::SeResize => Cursor::Undocumented("_windowResizeSouthEastCursor"),
-      CursorIcon::SwResize => Cursor::Undocumented("_windowResizeSouthWestCursor"),
-      CursorIcon::NeswResize => Cursor::Undocumented("_windowResizeNorthEastSouthWestCursor"),
-      CursorIcon::NwseResize => Cursor::Undocumented("_windowResizeNorthWestSouthEastCursor"),
+      CursorIcon::Help => Cursor::Undocumented("_helpCursor", "arrowCursor"),
+      CursorIcon::ZoomIn => Cursor::Undocumented("_zoomInCursor", "crosshairCursor"),
+      CursorIcon::ZoomOut => Cursor::Undocumented("_zoomOutCursor", "crosshairCursor"),
+      CursorIcon::NeResize => {
+        Cursor::Undocumented("_windowResizeNorthEastCursor", "crosshairCursor")
+      }
+      CursorIcon::NwResize => {
+        Cursor::Undocumented("_windowResizeNorthWestCursor", "crosshairCursor")
+      }
+      CursorIcon::SeResize => {
+        Cursor::Undocumented("_windowResizeSouthEastCursor", "crosshairCursor")
+      }
+      CursorIcon::SwResize => {
+        Cursor::Undocumented("_windowResizeSouthWestCursor", "crosshairCursor")
+      }
+      CursorIcon::NeswResize => {
+        Cursor::Undocumented("_windowResizeNorthEastSouthWestCursor", "crosshairCursor")
+      }
+      CursorIcon::NwseResize => {
+        Cursor::Undocumented("_windowResizeNorthWestSouthEastCursor", "crosshairCursor")
+      }
 
       // While these are available, the former just loads a white arrow,
       // and the latter loads an ugly deflated beachball!
@@ -62,14 +81,16 @@ impl From<CursorIcon> for Cursor {
       // https://bugs.eclipse.org/bugs/show_bug.cgi?id=522349
       // This is the wrong semantics for `Wait`, but it's the same as
       // what's used in Safari and Chrome.
-      CursorIcon::Wait | CursorIcon::Progress => Cursor::Undocumented("busyButClickableCursor"),
+      CursorIcon::Wait | CursorIcon::Progress => {
+        Cursor::Undocumented("busyButClickableCursor", "arrowCursor")
+      }
 
       // For the rest, we can just snatch the cursors from WebKit...
       // They fit the style of the native cursors, and will seem
       // completely standard to macOS users.
       // https://stackoverflow.com/a/21786835/5435443
-      CursorIcon::Move | CursorIcon::AllScroll => Cursor::WebKit("move"),
-      CursorIcon::Cell => Cursor::WebKit("cell"),
+      CursorIcon::Move | CursorIcon::AllScroll => Cursor::WebKit("move", "openHandCursor"),
+      CursorIcon::Cell => Cursor::WebKit("cell", "crosshairCursor"),
     }
   }
 }
@@ -88,24 +109,35 @@ impl Cursor {
         let sel = Sel::register(cursor_name);
         msg_send![class!(NSCursor), performSelector: sel]
       }
-      Cursor::Undocumented(cursor_name) => {
+      Cursor::Undocumented(cursor_name, fallback) => {
         let class = class!(NSCursor);
         let sel = Sel::register(cursor_name);
         let sel = if msg_send![class, respondsToSelector: sel] {
           sel
         } else {
           warn!("Cursor `{}` appears to be invalid", cursor_name);
-          sel!(arrowCursor)
+          Sel::register(fallback)
         };
         msg_send![class, performSelector: sel]
       }
-      Cursor::WebKit(cursor_name) => load_webkit_cursor(cursor_name),
+      Cursor::WebKit(cursor_name, fallback) => {
+        let cursor = load_webkit_cursor(cursor_name);
+        if cursor != nil {
+          cursor
+        } else {
+          warn!("Cursor `{}` could not be loaded", cursor_name);
+          msg_send![class!(NSCursor), performSelector: Sel::register(fallback)]
+        }
+      }
+      Cursor::Custom(cursor) => **cursor,
     }
   }
 }
 
 // Note that loading `busybutclickable` with this code won't animate the frames;
 // instead you'll just get them all in a column.
+//
+// Returns `nil` when the cursor resources are missing.
 pub unsafe fn load_webkit_cursor(cursor_name: &str) -> id {
   static CURSOR_ROOT: &str = "/System/Library/Frameworks/ApplicationServices.framework/Versions/A/Frameworks/HIServices.framework/Versions/A/Resources/cursors";
   let cursor_root = NSString::alloc(nil).init_str(CURSOR_ROOT);
@@ -121,6 +153,10 @@ pub unsafe fn load_webkit_cursor(cursor_name: &str) -> id {
 
   let image = NSImage::alloc(nil).initByReferencingFile_(pdf_path);
   let info = NSDictionary::dictionaryWithContentsOfFile_(nil, info_path);
+  let valid: BOOL = msg_send![image, isValid];
+  if info == nil || valid == NO {
+    return nil;
+  }
   let x = info.valueForKey_(key_x);
   let y = info.valueForKey_(key_y);
   let point = NSPoint::new(msg_send![x, doubleValue], msg_send![y, doubleValue]);
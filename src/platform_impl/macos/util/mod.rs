@@ -18,13 +18,21 @@ use cocoa::{
   foundation::{NSAutoreleasePool, NSPoint, NSRect, NSString, NSUInteger},
 };
 use core_graphics::{
+  base::{kCGImageAlphaPremultipliedLast, CGFloat},
+  color_space::CGColorSpace,
+  context::CGContext,
   display::CGDisplay,
-  event::CGEvent,
-  event_source::{CGEventSource, CGEventSourceStateID},
+  geometry::{CGPoint, CGRect, CGSize},
+  image::CGImage,
 };
-use objc::runtime::{Class, Object, Sel, BOOL, YES};
+use objc::runtime::{Class, Object, Sel, BOOL, NO, YES};
 
-use crate::{dpi::LogicalPosition, error::ExternalError, platform_impl::platform::ffi};
+use crate::{
+  dpi::LogicalPosition,
+  event_loop::{AccessibilityPrefs, SystemAppearance},
+  platform_impl::platform::{ffi, window::get_ns_theme},
+  window::{Color, RgbaImage},
+};
 
 // Replace with `!` once stable
 #[derive(Debug)]
@@ -111,16 +119,66 @@ pub fn window_position(position: LogicalPosition<f64>) -> NSPoint {
 }
 
 // FIXME: This is actually logical position.
-pub fn cursor_position() -> Result<LogicalPosition<f64>, ExternalError> {
-  if let Ok(s) = CGEventSource::new(CGEventSourceStateID::CombinedSessionState) {
-    if let Ok(e) = CGEvent::new(s) {
-      let pt = e.location();
-      let pos = LogicalPosition::new(pt.x, pt.y);
-      return Ok(pos);
+/// Returns the cursor position in tao screen-coordinates.
+pub fn cursor_position() -> LogicalPosition<f64> {
+  let point: NSPoint = unsafe { msg_send![class!(NSEvent), mouseLocation] };
+  LogicalPosition::new(
+    point.x as f64,
+    CGDisplay::main().pixels_high() as f64 - point.y as f64,
+  )
+}
+
+pub fn accessibility_prefs() -> AccessibilityPrefs {
+  unsafe {
+    let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+    // Reduce motion was added in macOS 10.12, the others in 10.10.
+    let reduce_motion_sel = sel!(accessibilityDisplayShouldReduceMotion);
+    let responds: BOOL = msg_send![workspace, respondsToSelector: reduce_motion_sel];
+    let reduce_motion: BOOL = if responds == YES {
+      msg_send![workspace, accessibilityDisplayShouldReduceMotion]
+    } else {
+      NO
+    };
+    let reduce_transparency: BOOL =
+      msg_send![workspace, accessibilityDisplayShouldReduceTransparency];
+    let high_contrast: BOOL = msg_send![workspace, accessibilityDisplayShouldIncreaseContrast];
+    let inverted_colors: BOOL = msg_send![workspace, accessibilityDisplayShouldInvertColors];
+    AccessibilityPrefs {
+      reduce_motion: reduce_motion == YES,
+      reduce_transparency: reduce_transparency == YES,
+      high_contrast: high_contrast == YES,
+      inverted_colors: inverted_colors == YES,
+    }
+  }
+}
+
+/// Reads the appearance of the system from the application, which doesn't force one.
+pub fn system_appearance() -> SystemAppearance {
+  unsafe {
+    SystemAppearance {
+      theme: get_ns_theme(NSApp()),
+      accent_color: accent_color(),
+      accessibility: accessibility_prefs(),
     }
   }
+}
 
-  return Err(ExternalError::Os(os_error!(super::OsError::CGError(0))));
+unsafe fn accent_color() -> Option<Color> {
+  // Added in macOS 10.14.
+  let responds: BOOL = msg_send![class!(NSColor), respondsToSelector: sel!(controlAccentColor)];
+  if responds == NO {
+    return None;
+  }
+  let color: id = msg_send![class!(NSColor), controlAccentColor];
+  let color_space: id = msg_send![class!(NSColorSpace), sRGBColorSpace];
+  let color: id = msg_send![color, colorUsingColorSpace: color_space];
+  if color == nil {
+    return None;
+  }
+  let (mut r, mut g, mut b, mut a): (CGFloat, CGFloat, CGFloat, CGFloat) = (0., 0., 0., 0.);
+  let _: () = msg_send![color, getRed: &mut r green: &mut g blue: &mut b alpha: &mut a];
+  let channel = |value: CGFloat| (value.clamp(0., 1.) * 255.).round() as u8;
+  Some(Color::new(channel(r), channel(g), channel(b), channel(a)))
 }
 
 pub unsafe fn ns_string_id_ref(s: &str) -> IdRef {
@@ -167,3 +225,40 @@ pub unsafe fn toggle_style_mask(window: id, view: id, mask: NSWindowStyleMask, o
   // If we don't do this, key handling will break. Therefore, never call `setStyleMask` directly!
   window.makeFirstResponder_(view);
 }
+
+/// Draws `image` into a bitmap of a known format, whatever its own format, with straight alpha.
+pub fn cg_image_to_rgba(image: &CGImage) -> RgbaImage {
+  let (width, height) = (image.width(), image.height());
+  let mut context = CGContext::create_bitmap_context(
+    None,
+    width,
+    height,
+    8,
+    width * 4,
+    &CGColorSpace::create_device_rgb(),
+    kCGImageAlphaPremultipliedLast,
+  );
+  context.draw_image(
+    CGRect::new(
+      &CGPoint::new(0., 0.),
+      &CGSize::new(width as f64, height as f64),
+    ),
+    image,
+  );
+  let mut data = context.data().to_vec();
+  for pixel in data.chunks_exact_mut(4) {
+    let alpha = pixel[3] as u32;
+    if alpha != 0 && alpha != 255 {
+      for channel in &mut pixel[..3] {
+        *channel = (*channel as u32 * 255 / alpha).min(255) as u8;
+      }
+    }
+  }
+
+  RgbaImage {
+    width: width as u32,
+    height: height as u32,
+    stride: width as u32 * 4,
+    data,
+  }
+}
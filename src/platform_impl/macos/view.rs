@@ -6,12 +6,18 @@ use std::{
   boxed::Box,
   collections::{HashSet, VecDeque},
   os::raw::*,
-  ptr, slice, str,
+  ptr,
+  rc::Rc,
+  slice, str,
   sync::{Arc, Mutex, Weak},
 };
 
+use block::ConcreteBlock;
 use cocoa::{
-  appkit::{NSApp, NSEvent, NSEventModifierFlags, NSEventPhase, NSView, NSWindow, NSWindowButton},
+  appkit::{
+    CGFloat, NSApp, NSEvent, NSEventMask, NSEventModifierFlags, NSEventPhase, NSEventType, NSView,
+    NSWindow, NSWindowButton, NSWindowStyleMask,
+  },
   base::{id, nil},
   foundation::{NSInteger, NSPoint, NSRect, NSSize, NSString, NSUInteger},
 };
@@ -21,25 +27,34 @@ use objc::{
 };
 
 use crate::{
-  dpi::LogicalPosition,
+  dpi::{LogicalPosition, LogicalSize, Rect},
   event::{
-    DeviceEvent, ElementState, Event, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
+    DeviceEvent, ElementState, Event, Ime, MouseButton, MouseScrollDelta, Pen, PenPhase,
+    TouchPhase, WindowEvent,
   },
   keyboard::{KeyCode, ModifiersState},
+  platform::macos::AccessibilityProviderMacOS,
   platform_impl::platform::{
     app_state::AppState,
-    event::{code_to_key, create_key_event, event_mods, get_scancode, EventWrapper},
+    event::{
+      code_to_key, create_key_event, event_mods, event_timestamp, get_scancode, EventWrapper,
+    },
     ffi::*,
     util::{self, IdRef},
     window::get_window_id,
     DEVICE_ID,
   },
-  window::WindowId,
+  window::{
+    CursorIcon, DragOperation, DragResult, HitTestCallback, HitTestResult, ResizeDirection,
+    WindowId, WindowShape,
+  },
 };
 
 pub struct CursorState {
   pub visible: bool,
   pub cursor: util::Cursor,
+  /// The icon `cursor` was made from.
+  pub icon: CursorIcon,
 }
 
 impl Default for CursorState {
@@ -47,6 +62,7 @@ impl Default for CursorState {
     Self {
       visible: true,
       cursor: Default::default(),
+      icon: Default::default(),
     }
   }
 }
@@ -54,7 +70,10 @@ impl Default for CursorState {
 pub(super) struct ViewState {
   ns_window: id,
   pub cursor_state: Arc<Mutex<CursorState>>,
-  ime_spot: Option<(f64, f64)>,
+  /// The area set with `Window::set_ime_cursor_area`, in screen coordinates.
+  ime_area: Option<NSRect>,
+  /// See `Window::set_ime_allowed`.
+  ime_allowed: bool,
 
   /// This is true when we are currently modifying a marked text
   /// using ime. When the text gets commited, this is set to false.
@@ -71,6 +90,28 @@ pub(super) struct ViewState {
   phys_modifiers: HashSet<KeyCode>,
   tracking_rect: Option<NSInteger>,
   pub(super) traffic_light_inset: Option<LogicalPosition<f64>>,
+  /// Rectangles of the view that accept cursor events, see `hitTest:`.
+  cursor_hittest_region: Option<Vec<Rect>>,
+  /// The local and global mouse monitors of `update_ignores_mouse_events`, while there is a
+  /// hit-test region.
+  cursor_monitors: Option<(id, id)>,
+  /// The shape masking the layer of the view, see `set_shape`.
+  shape: Option<WindowShape>,
+  /// See `Window::set_hit_test_callback`.
+  hit_test_callback: Option<HitTestCallback>,
+  /// The button part of the custom title bar pressed by the left button, acting once released.
+  hit_test_pressed: Option<HitTestResult>,
+  /// The edge of the custom frame under the cursor, whose cursor replaces the one of the view.
+  hit_test_edge: Option<ResizeDirection>,
+  /// The resize started from an edge of the custom frame: its direction, and the position of the
+  /// cursor and the frame of the window when it started, in screen coordinates.
+  hit_test_resize: Option<(ResizeDirection, NSPoint, NSRect)>,
+  /// Whether the pen in proximity of the tablet uses its eraser end.
+  pen_eraser: bool,
+  /// See `WindowExtMacOS::set_accessibility_provider`.
+  accessibility_provider: Option<Box<dyn AccessibilityProviderMacOS>>,
+  /// See `WindowExtMacOS::add_view_responder`.
+  responders: Vec<Rc<dyn Fn(*mut c_void) -> bool>>,
 }
 
 impl ViewState {
@@ -85,7 +126,8 @@ pub fn new_view(ns_window: id) -> (IdRef, Weak<Mutex<CursorState>>) {
   let state = ViewState {
     ns_window,
     cursor_state,
-    ime_spot: None,
+    ime_area: None,
+    ime_allowed: false,
     in_ime_preedit: false,
     key_triggered_ime: false,
     is_key_down: false,
@@ -93,6 +135,16 @@ pub fn new_view(ns_window: id) -> (IdRef, Weak<Mutex<CursorState>>) {
     phys_modifiers: Default::default(),
     tracking_rect: None,
     traffic_light_inset: None,
+    cursor_hittest_region: None,
+    cursor_monitors: None,
+    shape: None,
+    hit_test_callback: None,
+    hit_test_pressed: None,
+    hit_test_edge: None,
+    hit_test_resize: None,
+    pen_eraser: false,
+    accessibility_provider: None,
+    responders: Vec::new(),
   };
   unsafe {
     // This is free'd in `dealloc`
@@ -105,17 +157,69 @@ pub fn new_view(ns_window: id) -> (IdRef, Weak<Mutex<CursorState>>) {
   }
 }
 
-pub unsafe fn set_ime_position(ns_view: id, input_context: id, x: f64, y: f64) {
+/// Sets the area of the text cursor from its logical position and size in the content view.
+pub unsafe fn set_ime_cursor_area(
+  ns_view: id,
+  input_context: id,
+  position: LogicalPosition<f64>,
+  size: LogicalSize<f64>,
+) {
   let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
   let state = &mut *(state_ptr as *mut ViewState);
   let content_rect =
     NSWindow::contentRectForFrameRect_(state.ns_window, NSWindow::frame(state.ns_window));
   let base_x = content_rect.origin.x as f64;
   let base_y = (content_rect.origin.y + content_rect.size.height) as f64;
-  state.ime_spot = Some((base_x + x, base_y - y));
+  state.ime_area = Some(NSRect::new(
+    NSPoint::new(base_x + position.x, base_y - position.y - size.height),
+    NSSize::new(size.width, size.height),
+  ));
   let _: () = msg_send![input_context, invalidateCharacterCoordinates];
 }
 
+pub unsafe fn set_ime_allowed(ns_view: id, allowed: bool) {
+  let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+  if state.ime_allowed == allowed {
+    return;
+  }
+  state.ime_allowed = allowed;
+  // Called outside of the handlers of the view, so the run loop is woken up.
+  let queue = |ime| {
+    AppState::queue_event_and_wake(EventWrapper::StaticEvent(Event::WindowEvent {
+      window_id: WindowId(get_window_id(state.ns_window)),
+      event: WindowEvent::Ime(ime),
+    }))
+  };
+  if allowed {
+    queue(Ime::Enabled);
+  } else {
+    if state.in_ime_preedit {
+      queue(Ime::Preedit(String::new(), None));
+    }
+    queue(Ime::Disabled);
+  }
+}
+
+fn queue_ime_event(state: &ViewState, ime: Ime) {
+  AppState::queue_event(EventWrapper::StaticEvent(Event::WindowEvent {
+    window_id: WindowId(get_window_id(state.ns_window)),
+    event: WindowEvent::Ime(ime),
+  }));
+}
+
+/// Converts an offset in UTF-16 code units of `text`, as counted by `NSString`, to bytes.
+fn utf16_to_byte_offset(text: &str, offset: usize) -> usize {
+  let mut utf16_offset = 0;
+  for (i, c) in text.char_indices() {
+    if utf16_offset >= offset {
+      return i;
+    }
+    utf16_offset += c.len_utf16();
+  }
+  text.len()
+}
+
 fn is_arrow_key(keycode: KeyCode) -> bool {
   matches!(
     keycode,
@@ -123,6 +227,208 @@ fn is_arrow_key(keycode: KeyCode) -> bool {
   )
 }
 
+/// Sets the region of the view that takes the cursor events, the whole view with `None`.
+///
+/// Returning `nil` from `hitTest:` only keeps the events from the view, the window still takes
+/// them. So the window ignores the mouse events while the cursor is outside of the region, for
+/// them to reach the windows below, which mouse monitors check as the cursor moves.
+pub unsafe fn set_cursor_hittest_region(ns_view: id, region: Option<Vec<Rect>>) {
+  let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+  state.cursor_hittest_region = region;
+  match (
+    state.cursor_hittest_region.is_some(),
+    state.cursor_monitors.is_some(),
+  ) {
+    (true, false) => {
+      let mask = NSEventMask::NSMouseMovedMask.bits();
+      let local = ConcreteBlock::new(move |event: id| -> id {
+        unsafe { update_ignores_mouse_events(ns_view) };
+        event
+      })
+      .copy();
+      // The events of the other applications, once the window ignores them.
+      let global = ConcreteBlock::new(move |_event: id| {
+        unsafe { update_ignores_mouse_events(ns_view) };
+      })
+      .copy();
+      let local: id = msg_send![class!(NSEvent),
+        addLocalMonitorForEventsMatchingMask: mask
+        handler: &*local
+      ];
+      let global: id = msg_send![class!(NSEvent),
+        addGlobalMonitorForEventsMatchingMask: mask
+        handler: &*global
+      ];
+      state.cursor_monitors = Some((local, global));
+    }
+    (false, true) => {
+      remove_cursor_monitors(state);
+      state.ns_window.setIgnoresMouseEvents_(NO);
+      return;
+    }
+    _ => (),
+  }
+  update_ignores_mouse_events(ns_view);
+}
+
+unsafe fn remove_cursor_monitors(state: &mut ViewState) {
+  if let Some((local, global)) = state.cursor_monitors.take() {
+    let () = msg_send![class!(NSEvent), removeMonitor: local];
+    let () = msg_send![class!(NSEvent), removeMonitor: global];
+  }
+}
+
+/// Lets the window take the mouse events only while the cursor is over the hit-test region.
+unsafe fn update_ignores_mouse_events(ns_view: id) {
+  let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+  if state.cursor_hittest_region.is_none() {
+    return;
+  }
+  let location: NSPoint = msg_send![class!(NSEvent), mouseLocation];
+  let rect: NSRect = msg_send![state.ns_window,
+    convertRectFromScreen: NSRect::new(location, NSSize::new(0.0, 0.0))
+  ];
+  let view_point = ns_view.convertPoint_fromView_(rect.origin, nil);
+  let ignore = !accepts_cursor(ns_view, state, view_point);
+  state
+    .ns_window
+    .setIgnoresMouseEvents_(if ignore { YES } else { NO });
+}
+
+/// Whether the point of the view, in its coordinate system, is in its hit-test region and its
+/// shape.
+unsafe fn accepts_cursor(ns_view: id, state: &ViewState, view_point: NSPoint) -> bool {
+  let view_rect = NSView::frame(ns_view);
+  let position = LogicalPosition::new(
+    view_point.x as f64,
+    view_rect.size.height as f64 - view_point.y as f64,
+  );
+  let scale_factor = state.get_scale_factor();
+  if let Some(region) = &state.cursor_hittest_region {
+    if !region
+      .iter()
+      .any(|rect| rect.contains(position, scale_factor))
+    {
+      return false;
+    }
+  }
+  if let Some(shape) = &state.shape {
+    let size = LogicalSize::new(view_rect.size.width as f64, view_rect.size.height as f64)
+      .to_physical(scale_factor);
+    if !shape.contains(position, size, scale_factor) {
+      return false;
+    }
+  }
+  true
+}
+
+pub unsafe fn set_hit_test_callback(ns_view: id, callback: Option<HitTestCallback>) {
+  let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+  state.hit_test_callback = callback;
+  state.hit_test_pressed = None;
+  state.hit_test_resize = None;
+  set_hit_test_edge(ns_view, state, None);
+}
+
+pub unsafe fn set_accessibility_provider(
+  ns_view: id,
+  provider: Option<Box<dyn AccessibilityProviderMacOS>>,
+) {
+  let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+  state.accessibility_provider = provider;
+}
+
+pub unsafe fn add_responder(ns_view: id, responder: Box<dyn Fn(*mut c_void) -> bool>) {
+  let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+  state.responders.push(responder.into());
+}
+
+pub unsafe fn clear_responders(ns_view: id) {
+  let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+  state.responders.clear();
+}
+
+/// Passes `event` to the responders of `WindowExtMacOS::add_view_responder` in order, and returns
+/// `true` once one of them handles it, in which case the view doesn't.
+fn responders_handle(this: &Object, event: id) -> bool {
+  let responders = unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &*(state_ptr as *const ViewState);
+    // Cloned for the responders to be able to add others.
+    state.responders.clone()
+  };
+  responders
+    .iter()
+    .any(|responder| responder(event as *mut c_void))
+}
+
+/// Clips the view to `shape`, or stops clipping it with `None`.
+pub unsafe fn set_shape(ns_view: id, shape: Option<WindowShape>) {
+  let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+  state.shape = shape;
+  update_shape(ns_view);
+}
+
+/// Masks the layer of the view with its shape, for the current size and scale factor.
+pub unsafe fn update_shape(ns_view: id) {
+  let state_ptr: *mut c_void = *(*ns_view).get_mut_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+  let shape = match &state.shape {
+    Some(shape) => shape,
+    None => {
+      let layer: id = msg_send![ns_view, layer];
+      if layer != nil {
+        let _: () = msg_send![layer, setMask: nil];
+      }
+      return;
+    }
+  };
+
+  let _: () = msg_send![ns_view, setWantsLayer: YES];
+  let layer: id = msg_send![ns_view, layer];
+  let bounds: NSRect = msg_send![ns_view, bounds];
+  let black: id = msg_send![class!(NSColor), blackColor];
+  let black: id = msg_send![black, CGColor];
+  // Only the alpha of the mask matters, the layer is transparent where the mask is.
+  let mask: id = msg_send![class!(CALayer), layer];
+  let _: () = msg_send![mask, setFrame: bounds];
+  match shape {
+    WindowShape::RoundedRect { radius } => {
+      let max = bounds.size.width.min(bounds.size.height) / 2.0;
+      let radius = (*radius as CGFloat).max(0.0).min(max);
+      let _: () = msg_send![mask, setCornerRadius: radius];
+      let _: () = msg_send![mask, setBackgroundColor: black];
+    }
+    WindowShape::Region(rects) => {
+      let scale_factor = state.get_scale_factor();
+      for rect in rects {
+        let position = rect.position.to_logical::<f64>(scale_factor);
+        let size = rect.size.to_logical::<f64>(scale_factor);
+        // The view isn't flipped, its origin is at the bottom-left corner.
+        let frame = NSRect::new(
+          NSPoint::new(
+            position.x as CGFloat,
+            bounds.size.height - (position.y + size.height) as CGFloat,
+          ),
+          NSSize::new(size.width as CGFloat, size.height as CGFloat),
+        );
+        let sublayer: id = msg_send![class!(CALayer), layer];
+        let _: () = msg_send![sublayer, setFrame: frame];
+        let _: () = msg_send![sublayer, setBackgroundColor: black];
+        let _: () = msg_send![mask, addSublayer: sublayer];
+      }
+    }
+  }
+  let _: () = msg_send![layer, setMask: mask];
+}
+
 /// `view` must be the reference to the `TaoView` class
 ///
 /// Returns the mutable reference to the `markedText` field.
@@ -162,6 +468,10 @@ lazy_static! {
       sel!(touchBar),
       touch_bar as extern "C" fn(&Object, Sel) -> BOOL,
     );
+    decl.add_method(
+      sel!(hitTest:),
+      hit_test as extern "C" fn(&Object, Sel, NSPoint) -> id,
+    );
     decl.add_method(
       sel!(resetCursorRects),
       reset_cursor_rects as extern "C" fn(&Object, Sel),
@@ -273,6 +583,14 @@ lazy_static! {
       sel!(mouseExited:),
       mouse_exited as extern "C" fn(&Object, Sel, id),
     );
+    decl.add_method(
+      sel!(tabletPoint:),
+      tablet_point as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(tabletProximity:),
+      tablet_proximity as extern "C" fn(&Object, Sel, id),
+    );
     decl.add_method(
       sel!(scrollWheel:),
       scroll_wheel as extern "C" fn(&Object, Sel, id),
@@ -281,6 +599,18 @@ lazy_static! {
       sel!(pressureChangeWithEvent:),
       pressure_change_with_event as extern "C" fn(&Object, Sel, id),
     );
+    decl.add_method(
+      sel!(magnifyWithEvent:),
+      magnify_with_event as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(rotateWithEvent:),
+      rotate_with_event as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(smartMagnifyWithEvent:),
+      smart_magnify_with_event as extern "C" fn(&Object, Sel, id),
+    );
     decl.add_method(
       sel!(_wantsKeyDownForEvent:),
       wants_key_down_for_event as extern "C" fn(&Object, Sel, id) -> BOOL,
@@ -297,10 +627,33 @@ lazy_static! {
       sel!(acceptsFirstMouse:),
       accepts_first_mouse as extern "C" fn(&Object, Sel, id) -> BOOL,
     );
+    decl.add_method(
+      sel!(draggingSession:sourceOperationMaskForDraggingContext:),
+      dragging_source_operation_mask as extern "C" fn(&Object, Sel, id, NSInteger) -> NSUInteger,
+    );
+    decl.add_method(
+      sel!(draggingSession:endedAtPoint:operation:),
+      dragging_session_ended as extern "C" fn(&Object, Sel, id, NSPoint, NSUInteger),
+    );
+    decl.add_method(
+      sel!(accessibilityChildren),
+      accessibility_children as extern "C" fn(&Object, Sel) -> id,
+    );
+    decl.add_method(
+      sel!(accessibilityFocusedUIElement),
+      accessibility_focused_ui_element as extern "C" fn(&Object, Sel) -> id,
+    );
+    decl.add_method(
+      sel!(accessibilityHitTest:),
+      accessibility_hit_test as extern "C" fn(&Object, Sel, NSPoint) -> id,
+    );
     decl.add_ivar::<*mut c_void>("taoState");
     decl.add_ivar::<id>("markedText");
     let protocol = Protocol::get("NSTextInputClient").unwrap();
     decl.add_protocol(protocol);
+    if let Some(protocol) = Protocol::get("NSDraggingSource") {
+      decl.add_protocol(protocol);
+    }
     ViewClass(decl.register())
   };
 }
@@ -310,6 +663,8 @@ extern "C" fn dealloc(this: &Object, _sel: Sel) {
     let state: *mut c_void = *this.get_ivar("taoState");
     let marked_text: id = *this.get_ivar("markedText");
     let _: () = msg_send![marked_text, release];
+    // The monitors point to the view.
+    remove_cursor_monitors(&mut *(state as *mut ViewState));
     drop(Box::from_raw(state as *mut ViewState));
   }
 }
@@ -378,6 +733,11 @@ extern "C" fn frame_did_change(this: &Object, _sel: Sel, _event: id) {
     ];
 
     state.tracking_rect = Some(tracking_rect);
+
+    // The mask is sized to the view.
+    if state.shape.is_some() {
+      update_shape(this as *const _ as *mut _);
+    }
   }
 }
 
@@ -409,6 +769,72 @@ extern "C" fn touch_bar(_this: &Object, _sel: Sel) -> BOOL {
   NO
 }
 
+extern "C" fn hit_test(this: &Object, _sel: Sel, point: NSPoint) -> id {
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &mut *(state_ptr as *mut ViewState);
+
+    if state.cursor_hittest_region.is_some() || state.shape.is_some() {
+      let view: id = this as *const _ as *mut _;
+      // `point` is in the coordinate system of the superview.
+      let superview: id = msg_send![this, superview];
+      let view_point = view.convertPoint_fromView_(point, superview);
+      if !accepts_cursor(view, state, view_point) {
+        return nil;
+      }
+    }
+
+    let superclass = util::superclass(this);
+    msg_send![super(this, superclass), hitTest: point]
+  }
+}
+
+// The accessibility methods ask the provider first, the view answers itself without one.
+extern "C" fn accessibility_children(this: &Object, _sel: Sel) -> id {
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &*(state_ptr as *const ViewState);
+    if let Some(provider) = &state.accessibility_provider {
+      let children = provider.children() as id;
+      if children != nil {
+        return children;
+      }
+    }
+    let superclass = util::superclass(this);
+    msg_send![super(this, superclass), accessibilityChildren]
+  }
+}
+
+extern "C" fn accessibility_focused_ui_element(this: &Object, _sel: Sel) -> id {
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &*(state_ptr as *const ViewState);
+    if let Some(provider) = &state.accessibility_provider {
+      let element = provider.focused_element() as id;
+      if element != nil {
+        return element;
+      }
+    }
+    let superclass = util::superclass(this);
+    msg_send![super(this, superclass), accessibilityFocusedUIElement]
+  }
+}
+
+extern "C" fn accessibility_hit_test(this: &Object, _sel: Sel, point: NSPoint) -> id {
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &*(state_ptr as *const ViewState);
+    if let Some(provider) = &state.accessibility_provider {
+      let element = provider.hit_test(point.x as f64, point.y as f64) as id;
+      if element != nil {
+        return element;
+      }
+    }
+    let superclass = util::superclass(this);
+    msg_send![super(this, superclass), accessibilityHitTest: point]
+  }
+}
+
 extern "C" fn reset_cursor_rects(this: &Object, _sel: Sel) {
   unsafe {
     let state_ptr: *mut c_void = *this.get_ivar("taoState");
@@ -416,10 +842,10 @@ extern "C" fn reset_cursor_rects(this: &Object, _sel: Sel) {
 
     let bounds: NSRect = msg_send![this, bounds];
     let cursor_state = state.cursor_state.lock().unwrap();
-    let cursor = if cursor_state.visible {
-      cursor_state.cursor.load()
-    } else {
-      util::invisible_cursor()
+    let cursor = match state.hit_test_edge {
+      Some(direction) => edge_cursor(direction),
+      None if cursor_state.visible => cursor_state.cursor.load(),
+      None => util::invisible_cursor(),
     };
 
     if !cursor.is_null() {
@@ -467,7 +893,7 @@ extern "C" fn set_marked_text(
   this: &mut Object,
   _sel: Sel,
   string: id,
-  _selected_range: NSRange,
+  selected_range: NSRange,
   _replacement_range: NSRange,
 ) {
   trace!("Triggered `setMarkedText`");
@@ -484,6 +910,21 @@ extern "C" fn set_marked_text(
     let state = &mut *(state_ptr as *mut ViewState);
     state.in_ime_preedit = true;
     state.key_triggered_ime = true;
+
+    if state.ime_allowed {
+      let text = util::ns_string_to_rust(msg_send![*marked_text_ref, string]);
+      let cursor = if text.is_empty() {
+        None
+      } else {
+        let start = utf16_to_byte_offset(&text, selected_range.location as usize);
+        let end = utf16_to_byte_offset(
+          &text,
+          (selected_range.location + selected_range.length) as usize,
+        );
+        Some((start, end))
+      };
+      queue_ime_event(state, Ime::Preedit(text, cursor));
+    }
   }
   trace!("Completed `setMarkedText`");
 }
@@ -531,15 +972,15 @@ extern "C" fn first_rect_for_character_range(
     trace!("Triggered `firstRectForCharacterRange`");
     let state_ptr: *mut c_void = *this.get_ivar("taoState");
     let state = &mut *(state_ptr as *mut ViewState);
-    let (x, y) = state.ime_spot.unwrap_or_else(|| {
+    let rect = state.ime_area.unwrap_or_else(|| {
       let content_rect =
         NSWindow::contentRectForFrameRect_(state.ns_window, NSWindow::frame(state.ns_window));
       let x = content_rect.origin.x;
       let y = util::bottom_left_to_top_left(content_rect);
-      (x, y)
+      NSRect::new(NSPoint::new(x as _, y as _), NSSize::new(0.0, 0.0))
     });
     trace!("Completed `firstRectForCharacterRange`");
-    NSRect::new(NSPoint::new(x as _, y as _), NSSize::new(0.0, 0.0))
+    rect
   }
 }
 
@@ -568,10 +1009,15 @@ extern "C" fn insert_text(this: &Object, _sel: Sel, string: id, _replacement_ran
     // We don't need this now, but it's here if that changes.
     //let event: id = msg_send![NSApp(), currentEvent];
 
-    AppState::queue_event(EventWrapper::StaticEvent(Event::WindowEvent {
-      window_id: WindowId(get_window_id(state.ns_window)),
-      event: WindowEvent::ReceivedImeText(string),
-    }));
+    if state.ime_allowed && state.in_ime_preedit {
+      queue_ime_event(state, Ime::Preedit(String::new(), None));
+      queue_ime_event(state, Ime::Commit(string));
+    } else {
+      AppState::queue_event(EventWrapper::StaticEvent(Event::WindowEvent {
+        window_id: WindowId(get_window_id(state.ns_window)),
+        event: WindowEvent::ReceivedImeText(string),
+      }));
+    }
     if state.in_ime_preedit {
       state.in_ime_preedit = false;
       state.key_triggered_ime = true;
@@ -693,6 +1139,9 @@ fn update_potentially_stale_modifiers(state: &mut ViewState, event: id) {
 
 extern "C" fn key_down(this: &mut Object, _sel: Sel, event: id) {
   trace!("Triggered `keyDown`");
+  if responders_handle(this, event) {
+    return;
+  }
   unsafe {
     let state_ptr: *mut c_void = *this.get_ivar("taoState");
     let state = &mut *(state_ptr as *mut ViewState);
@@ -736,6 +1185,9 @@ extern "C" fn key_down(this: &mut Object, _sel: Sel, event: id) {
         // In this case we should cancel the IME session.
         let () = msg_send![this, unmarkText];
         state.in_ime_preedit = false;
+        if state.ime_allowed {
+          queue_ime_event(state, Ime::Preedit(String::new(), None));
+        }
       }
     }
     let window_event = Event::WindowEvent {
@@ -744,6 +1196,7 @@ extern "C" fn key_down(this: &mut Object, _sel: Sel, event: id) {
         device_id: DEVICE_ID,
         event: key_event,
         is_synthetic: false,
+        timestamp: event_timestamp(event),
       },
     };
     AppState::queue_event(EventWrapper::StaticEvent(window_event));
@@ -753,6 +1206,9 @@ extern "C" fn key_down(this: &mut Object, _sel: Sel, event: id) {
 
 extern "C" fn key_up(this: &Object, _sel: Sel, event: id) {
   trace!("Triggered `keyUp`");
+  if responders_handle(this, event) {
+    return;
+  }
   unsafe {
     let state_ptr: *mut c_void = *this.get_ivar("taoState");
     let state = &mut *(state_ptr as *mut ViewState);
@@ -767,6 +1223,7 @@ extern "C" fn key_up(this: &Object, _sel: Sel, event: id) {
         device_id: DEVICE_ID,
         event: create_key_event(event, false, false, false, None),
         is_synthetic: false,
+        timestamp: event_timestamp(event),
       },
     };
     AppState::queue_event(EventWrapper::StaticEvent(window_event));
@@ -780,6 +1237,9 @@ extern "C" fn flags_changed(this: &Object, _sel: Sel, ns_event: id) {
   };
 
   trace!("Triggered `flagsChanged`");
+  if responders_handle(this, ns_event) {
+    return;
+  }
   unsafe {
     let state_ptr: *mut c_void = *this.get_ivar("taoState");
     let state = &mut *(state_ptr as *mut ViewState);
@@ -823,21 +1283,37 @@ extern "C" fn flags_changed(this: &Object, _sel: Sel, ns_event: id) {
             };
             event.physical_key = actual_key;
             event.logical_key = code_to_key(event.physical_key, scancode);
-            events.push_back(WindowEvent::KeyboardInput {
-              device_id: DEVICE_ID,
-              event,
-              is_synthetic: false,
-            });
             if is_pressed {
               state.phys_modifiers.insert($target_key);
             } else {
               state.phys_modifiers.remove(&$target_key);
             }
+
+            // The new modifiers are sent before the press of a modifier, and after its release.
+            let old_modifiers = state.modifiers;
             if ns_event_contains_keymask {
               state.modifiers.insert($tao_flag);
             } else {
               state.modifiers.remove($tao_flag);
             }
+            let modifiers_changed = state.modifiers != old_modifiers;
+            if is_pressed && modifiers_changed {
+              events.push_back(WindowEvent::ModifiersChanged(state.modifiers));
+            }
+            event.modifiers = if is_pressed {
+              state.modifiers
+            } else {
+              old_modifiers
+            };
+            events.push_back(WindowEvent::KeyboardInput {
+              device_id: DEVICE_ID,
+              event,
+              is_synthetic: false,
+              timestamp: event_timestamp(ns_event),
+            });
+            if !is_pressed && modifiers_changed {
+              events.push_back(WindowEvent::ModifiersChanged(state.modifiers));
+            }
           }
         }
       };
@@ -891,11 +1367,6 @@ extern "C" fn flags_changed(this: &Object, _sel: Sel, ns_event: id) {
         event,
       }));
     }
-
-    AppState::queue_event(EventWrapper::StaticEvent(Event::WindowEvent {
-      window_id,
-      event: WindowEvent::ModifiersChanged(state.modifiers),
-    }));
   }
   trace!("Completed `flagsChanged`");
 }
@@ -943,6 +1414,7 @@ extern "C" fn cancel_operation(this: &Object, _sel: Sel, _sender: id) {
         device_id: DEVICE_ID,
         event: create_key_event(event, true, false, false, Some(key)),
         is_synthetic: false,
+        timestamp: event_timestamp(event),
       },
     };
     AppState::queue_event(EventWrapper::StaticEvent(window_event));
@@ -957,12 +1429,17 @@ fn mouse_click(this: &Object, event: id, button: MouseButton, button_state: Elem
 
     update_potentially_stale_modifiers(state, event);
 
+    // AppKit already applies the system double-click time and resets the count for other buttons.
+    let click_count: NSInteger = msg_send![event, clickCount];
+
     let window_event = Event::WindowEvent {
       window_id: WindowId(get_window_id(state.ns_window)),
       event: WindowEvent::MouseInput {
         device_id: DEVICE_ID,
         state: button_state,
         button,
+        click_count: click_count.max(1) as u32,
+        timestamp: event_timestamp(event),
         modifiers: event_mods(event),
       },
     };
@@ -971,32 +1448,262 @@ fn mouse_click(this: &Object, event: id, button: MouseButton, button_state: Elem
   }
 }
 
+/// Acts on a left click in the non-client parts of `Window::set_hit_test_callback`, returns
+/// whether the event is consumed.
+fn hit_test_click(this: &Object, event: id, button_state: ElementState) -> bool {
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &mut *(state_ptr as *mut ViewState);
+
+    if button_state == ElementState::Released && state.hit_test_resize.take().is_some() {
+      queue_window_event(state, WindowEvent::DragResizeEnded { resize: true });
+      return true;
+    }
+    let hit = match hit_test_at(this, state, event) {
+      Some(hit) => hit,
+      None => return false,
+    };
+
+    match button_state {
+      ElementState::Pressed => {
+        state.hit_test_pressed = None;
+        match hit {
+          HitTestResult::Client => return false,
+          HitTestResult::Caption => {
+            let click_count: NSInteger = msg_send![event, clickCount];
+            if click_count == 2 {
+              double_click_title_bar(state.ns_window);
+            } else {
+              let _: () = msg_send![state.ns_window, performWindowDragWithEvent: event];
+            }
+          }
+          HitTestResult::ResizeEdge(direction) => {
+            let style_mask: NSWindowStyleMask = state.ns_window.styleMask();
+            if style_mask.contains(NSWindowStyleMask::NSResizableWindowMask) {
+              let origin: NSPoint = msg_send![class!(NSEvent), mouseLocation];
+              state.hit_test_resize = Some((direction, origin, NSWindow::frame(state.ns_window)));
+              queue_window_event(state, WindowEvent::DragResizeStarted { resize: true });
+            }
+          }
+          button => state.hit_test_pressed = Some(button),
+        }
+        true
+      }
+      ElementState::Released => match state.hit_test_pressed.take() {
+        // Like a regular button, releasing it elsewhere cancels the click.
+        Some(pressed) => {
+          match pressed {
+            _ if pressed != hit => (),
+            HitTestResult::Minimize => NSWindow::miniaturize_(state.ns_window, state.ns_window),
+            HitTestResult::Maximize => {
+              let _: () = msg_send![state.ns_window, zoom: state.ns_window];
+            }
+            HitTestResult::Close => queue_window_event(state, WindowEvent::CloseRequested),
+            _ => (),
+          }
+          true
+        }
+        None => hit != HitTestResult::Client,
+      },
+    }
+  }
+}
+
+/// The part of the custom frame of `Window::set_hit_test_callback` under the cursor of `event`,
+/// `None` without a callback.
+unsafe fn hit_test_at(this: &Object, state: &ViewState, event: id) -> Option<HitTestResult> {
+  // Cloned for the callback to be able to replace itself.
+  let callback = state.hit_test_callback.clone()?;
+  let view: id = this as *const _ as *mut _;
+  let view_point = view.convertPoint_fromView_(event.locationInWindow(), nil);
+  let view_rect = NSView::frame(view);
+  let position = LogicalPosition::new(
+    view_point.x as f64,
+    view_rect.size.height as f64 - view_point.y as f64,
+  );
+  Some(callback(position.to_physical(state.get_scale_factor())))
+}
+
+fn queue_window_event(state: &ViewState, event: WindowEvent<'static>) {
+  AppState::queue_event(EventWrapper::StaticEvent(Event::WindowEvent {
+    window_id: WindowId(get_window_id(state.ns_window)),
+    event,
+  }));
+}
+
+/// Does what the user chose for a double-click on a title bar, in the Dock settings.
+unsafe fn double_click_title_bar(ns_window: id) {
+  let defaults: id = msg_send![class!(NSUserDefaults), standardUserDefaults];
+  let key = NSString::alloc(nil).init_str("AppleActionOnDoubleClick");
+  let action: id = msg_send![defaults, stringForKey: key];
+  let () = msg_send![key, release];
+  let action = if action == nil {
+    // Set by macOS before 10.11.
+    let key = NSString::alloc(nil).init_str("AppleMiniaturizeOnDoubleClick");
+    let minimize: BOOL = msg_send![defaults, boolForKey: key];
+    let () = msg_send![key, release];
+    if minimize == YES {
+      "Minimize"
+    } else {
+      "Maximize"
+    }
+  } else {
+    let action = slice::from_raw_parts(action.UTF8String() as *const u8, action.len());
+    str::from_utf8(action).unwrap_or("Maximize")
+  };
+  match action {
+    "Minimize" => NSWindow::miniaturize_(ns_window, ns_window),
+    "None" => (),
+    _ => {
+      let _: () = msg_send![ns_window, zoom: ns_window];
+    }
+  }
+}
+
+fn edge_cursor(direction: ResizeDirection) -> id {
+  let icon = match direction {
+    ResizeDirection::East | ResizeDirection::West => CursorIcon::EwResize,
+    ResizeDirection::North | ResizeDirection::South => CursorIcon::NsResize,
+    ResizeDirection::NorthEast | ResizeDirection::SouthWest => CursorIcon::NeswResize,
+    ResizeDirection::NorthWest | ResizeDirection::SouthEast => CursorIcon::NwseResize,
+  };
+  unsafe { util::Cursor::from(icon).load() }
+}
+
+/// Shows the resize cursor of `edge` over the whole view, which would otherwise be reset to the
+/// cursor of the window on its next cursor update, or restores it with `None`.
+unsafe fn set_hit_test_edge(ns_view: id, state: &mut ViewState, edge: Option<ResizeDirection>) {
+  if state.hit_test_edge == edge {
+    return;
+  }
+  state.hit_test_edge = edge;
+  if let Some(direction) = edge {
+    let cursor = edge_cursor(direction);
+    if cursor != nil {
+      let () = msg_send![cursor, set];
+    }
+  }
+  let () = msg_send![state.ns_window, invalidateCursorRectsForView: ns_view];
+}
+
+/// Resizes the window from the edge of the custom frame of `Window::set_hit_test_callback` the
+/// left button was pressed on, returns whether it is being resized.
+fn hit_test_resizing(this: &Object) -> bool {
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &mut *(state_ptr as *mut ViewState);
+
+    if let Some((direction, origin, frame)) = state.hit_test_resize {
+      let location: NSPoint = msg_send![class!(NSEvent), mouseLocation];
+      let (dx, dy) = (location.x - origin.x, location.y - origin.y);
+      let min_size: NSSize = msg_send![state.ns_window, minSize];
+      let max_size: NSSize = msg_send![state.ns_window, maxSize];
+      let clamp = |size: CGFloat, min: CGFloat, max: CGFloat| size.max(min).min(max.max(min));
+      let mut new_frame = frame;
+      // The screen coordinates grow upwards, so the top edge is at `y + height`.
+      match direction {
+        ResizeDirection::West | ResizeDirection::NorthWest | ResizeDirection::SouthWest => {
+          new_frame.size.width = clamp(frame.size.width - dx, min_size.width, max_size.width);
+          new_frame.origin.x = frame.origin.x + frame.size.width - new_frame.size.width;
+        }
+        ResizeDirection::East | ResizeDirection::NorthEast | ResizeDirection::SouthEast => {
+          new_frame.size.width = clamp(frame.size.width + dx, min_size.width, max_size.width);
+        }
+        _ => (),
+      }
+      match direction {
+        ResizeDirection::South | ResizeDirection::SouthEast | ResizeDirection::SouthWest => {
+          new_frame.size.height = clamp(frame.size.height - dy, min_size.height, max_size.height);
+          new_frame.origin.y = frame.origin.y + frame.size.height - new_frame.size.height;
+        }
+        ResizeDirection::North | ResizeDirection::NorthEast | ResizeDirection::NorthWest => {
+          new_frame.size.height = clamp(frame.size.height + dy, min_size.height, max_size.height);
+        }
+        _ => (),
+      }
+      let () = msg_send![state.ns_window, setFrame: new_frame display: YES];
+      true
+    } else {
+      false
+    }
+  }
+}
+
+/// Follows the cursor over the custom frame of `Window::set_hit_test_callback`, showing the
+/// resize cursor of its edges. Returns whether the event is consumed.
+fn hit_test_motion(this: &Object, event: id) -> bool {
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &mut *(state_ptr as *mut ViewState);
+    let view: id = this as *const _ as *mut _;
+
+    let hit = hit_test_at(this, state, event);
+    let style_mask: NSWindowStyleMask = state.ns_window.styleMask();
+    let edge = match hit {
+      Some(HitTestResult::ResizeEdge(direction))
+        if style_mask.contains(NSWindowStyleMask::NSResizableWindowMask) =>
+      {
+        Some(direction)
+      }
+      _ => None,
+    };
+    set_hit_test_edge(view, state, edge);
+    !matches!(hit, Some(HitTestResult::Client) | None)
+  }
+}
+
 extern "C" fn mouse_down(this: &Object, _sel: Sel, event: id) {
+  if responders_handle(this, event) {
+    return;
+  }
+  if hit_test_click(this, event, ElementState::Pressed) {
+    return;
+  }
   mouse_motion(this, event);
   mouse_click(this, event, MouseButton::Left, ElementState::Pressed);
+  pen_event(this, event, PenPhase::Pressed);
 }
 
 extern "C" fn mouse_up(this: &Object, _sel: Sel, event: id) {
+  if responders_handle(this, event) {
+    return;
+  }
+  if hit_test_click(this, event, ElementState::Released) {
+    return;
+  }
   mouse_motion(this, event);
   mouse_click(this, event, MouseButton::Left, ElementState::Released);
+  pen_event(this, event, PenPhase::Released);
 }
 
 extern "C" fn right_mouse_down(this: &Object, _sel: Sel, event: id) {
+  if responders_handle(this, event) {
+    return;
+  }
   mouse_motion(this, event);
   mouse_click(this, event, MouseButton::Right, ElementState::Pressed);
 }
 
 extern "C" fn right_mouse_up(this: &Object, _sel: Sel, event: id) {
+  if responders_handle(this, event) {
+    return;
+  }
   mouse_motion(this, event);
   mouse_click(this, event, MouseButton::Right, ElementState::Released);
 }
 
 extern "C" fn other_mouse_down(this: &Object, _sel: Sel, event: id) {
+  if responders_handle(this, event) {
+    return;
+  }
   mouse_motion(this, event);
   mouse_click(this, event, MouseButton::Middle, ElementState::Pressed);
 }
 
 extern "C" fn other_mouse_up(this: &Object, _sel: Sel, event: id) {
+  if responders_handle(this, event) {
+    return;
+  }
   mouse_motion(this, event);
   mouse_click(this, event, MouseButton::Middle, ElementState::Released);
 }
@@ -1036,6 +1743,7 @@ fn mouse_motion(this: &Object, event: id) {
       event: WindowEvent::CursorMoved {
         device_id: DEVICE_ID,
         position: logical_position.to_physical(state.get_scale_factor()),
+        timestamp: event_timestamp(event),
         modifiers: event_mods(event),
       },
     };
@@ -1045,23 +1753,161 @@ fn mouse_motion(this: &Object, event: id) {
 }
 
 extern "C" fn mouse_moved(this: &Object, _sel: Sel, event: id) {
+  if responders_handle(this, event) {
+    return;
+  }
+  if hit_test_motion(this, event) {
+    return;
+  }
   mouse_motion(this, event);
+  pen_event(this, event, PenPhase::Moved);
 }
 
 extern "C" fn mouse_dragged(this: &Object, _sel: Sel, event: id) {
+  if responders_handle(this, event) {
+    return;
+  }
+  if hit_test_resizing(this) {
+    return;
+  }
   mouse_motion(this, event);
+  pen_event(this, event, PenPhase::Moved);
 }
 
 extern "C" fn right_mouse_dragged(this: &Object, _sel: Sel, event: id) {
+  if responders_handle(this, event) {
+    return;
+  }
   mouse_motion(this, event);
+  pen_event(this, event, PenPhase::Moved);
 }
 
 extern "C" fn other_mouse_dragged(this: &Object, _sel: Sel, event: id) {
+  if responders_handle(this, event) {
+    return;
+  }
   mouse_motion(this, event);
+  pen_event(this, event, PenPhase::Moved);
+}
+
+extern "C" fn tablet_point(this: &Object, _sel: Sel, event: id) {
+  if responders_handle(this, event) {
+    return;
+  }
+  pen_event(this, event, PenPhase::Moved);
+}
+
+extern "C" fn tablet_proximity(this: &Object, _sel: Sel, event: id) {
+  trace!("Triggered `tabletProximity`");
+  if responders_handle(this, event) {
+    return;
+  }
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &mut *(state_ptr as *mut ViewState);
+
+    let entering: BOOL = msg_send![event, isEnteringProximity];
+    let device_type: NSUInteger = msg_send![event, pointingDeviceType];
+    state.pen_eraser = device_type == NSPointingDeviceTypeEraser;
+    let phase = if entering == YES {
+      PenPhase::Entered
+    } else {
+      PenPhase::Left
+    };
+    pen_event(this, event, phase);
+  }
+  trace!("Completed `tabletProximity`");
+}
+
+/// Whether an event comes from a tablet, `Some(true)` for a proximity event and `Some(false)`
+/// for a point event. Mouse events carry the tablet data in their subtype, the tablet events
+/// always do.
+fn tablet_event(event_type: NSEventType, subtype: i16) -> Option<bool> {
+  if event_type == NSEventType::NSTabletProximity
+    || (event_type != NSEventType::NSTabletPoint && subtype == NSTabletProximityEventSubtype)
+  {
+    Some(true)
+  } else if event_type == NSEventType::NSTabletPoint || subtype == NSTabletPointEventSubtype {
+    Some(false)
+  } else {
+    None
+  }
+}
+
+/// The tilt of `Pen` in degrees from the one of AppKit, which goes from -1 to 1 with a positive
+/// `y` away from the user.
+fn pen_tilt(tilt: NSPoint) -> (f64, f64) {
+  (tilt.x as f64 * 90.0, -tilt.y as f64 * 90.0)
 }
 
-extern "C" fn mouse_entered(this: &Object, _sel: Sel, _event: id) {
+/// Queues a `WindowEvent::Pen` for a tablet event, or a mouse event coming from a tablet.
+fn pen_event(this: &Object, event: id, phase: PenPhase) {
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &mut *(state_ptr as *mut ViewState);
+
+    let subtype: i16 = msg_send![event, subtype];
+    let proximity = match tablet_event(event.eventType(), subtype) {
+      Some(proximity) => proximity,
+      None => return,
+    };
+
+    let view: id = this as *const _ as *mut _;
+    // Proximity events have no location, the pen is where the cursor is.
+    let window_point = if proximity {
+      let screen_point: NSPoint = msg_send![class!(NSEvent), mouseLocation];
+      msg_send![state.ns_window, convertPointFromScreen: screen_point]
+    } else {
+      event.locationInWindow()
+    };
+    let view_point = view.convertPoint_fromView_(window_point, nil);
+    let view_rect = NSView::frame(view);
+    let location = LogicalPosition::new(
+      view_point.x as f64,
+      view_rect.size.height as f64 - view_point.y as f64,
+    );
+
+    let (in_contact, pressure, tilt, twist, barrel_button) = if proximity {
+      (false, None, None, None, false)
+    } else {
+      let buttons: NSUInteger = msg_send![event, buttonMask];
+      let pressure: f32 = msg_send![event, pressure];
+      let tilt: NSPoint = msg_send![event, tilt];
+      let rotation: f32 = msg_send![event, rotation];
+      (
+        buttons & 1 != 0,
+        Some(pressure as f64),
+        Some(pen_tilt(tilt)),
+        Some(rotation as f64),
+        buttons & 0b110 != 0,
+      )
+    };
+
+    let window_event = Event::WindowEvent {
+      window_id: WindowId(get_window_id(state.ns_window)),
+      event: WindowEvent::Pen(Pen {
+        device_id: DEVICE_ID,
+        phase,
+        location: location.to_physical(state.get_scale_factor()),
+        in_contact: in_contact && phase != PenPhase::Released,
+        pressure,
+        tilt,
+        twist,
+        barrel_button,
+        eraser: state.pen_eraser,
+        timestamp: event_timestamp(event),
+      }),
+    };
+
+    AppState::queue_event(EventWrapper::StaticEvent(window_event));
+  }
+}
+
+extern "C" fn mouse_entered(this: &Object, _sel: Sel, event: id) {
   trace!("Triggered `mouseEntered`");
+  if responders_handle(this, event) {
+    return;
+  }
   unsafe {
     let state_ptr: *mut c_void = *this.get_ivar("taoState");
     let state = &mut *(state_ptr as *mut ViewState);
@@ -1078,8 +1924,11 @@ extern "C" fn mouse_entered(this: &Object, _sel: Sel, _event: id) {
   trace!("Completed `mouseEntered`");
 }
 
-extern "C" fn mouse_exited(this: &Object, _sel: Sel, _event: id) {
+extern "C" fn mouse_exited(this: &Object, _sel: Sel, event: id) {
   trace!("Triggered `mouseExited`");
+  if responders_handle(this, event) {
+    return;
+  }
   unsafe {
     let state_ptr: *mut c_void = *this.get_ivar("taoState");
     let state = &mut *(state_ptr as *mut ViewState);
@@ -1099,6 +1948,9 @@ extern "C" fn mouse_exited(this: &Object, _sel: Sel, _event: id) {
 extern "C" fn scroll_wheel(this: &Object, _sel: Sel, event: id) {
   trace!("Triggered `scrollWheel`");
 
+  if responders_handle(this, event) {
+    return;
+  }
   mouse_motion(this, event);
 
   unsafe {
@@ -1137,6 +1989,7 @@ extern "C" fn scroll_wheel(this: &Object, _sel: Sel, event: id) {
         device_id: DEVICE_ID,
         delta,
         phase,
+        timestamp: event_timestamp(event),
         modifiers: event_mods(event),
       },
     };
@@ -1150,6 +2003,9 @@ extern "C" fn scroll_wheel(this: &Object, _sel: Sel, event: id) {
 extern "C" fn pressure_change_with_event(this: &Object, _sel: Sel, event: id) {
   trace!("Triggered `pressureChangeWithEvent`");
 
+  if responders_handle(this, event) {
+    return;
+  }
   mouse_motion(this, event);
 
   unsafe {
@@ -1173,6 +2029,92 @@ extern "C" fn pressure_change_with_event(this: &Object, _sel: Sel, event: id) {
   trace!("Completed `pressureChangeWithEvent`");
 }
 
+/// Maps the phase of a gesture event, `None` for phases that don't belong to a gesture.
+unsafe fn gesture_phase(event: id) -> Option<TouchPhase> {
+  match event.phase() {
+    NSEventPhase::NSEventPhaseBegan => Some(TouchPhase::Started),
+    NSEventPhase::NSEventPhaseChanged => Some(TouchPhase::Moved),
+    NSEventPhase::NSEventPhaseEnded => Some(TouchPhase::Ended),
+    NSEventPhase::NSEventPhaseCancelled => Some(TouchPhase::Cancelled),
+    _ => None,
+  }
+}
+
+/// Queues a gesture event, unless the window doesn't have focus.
+unsafe fn queue_gesture_event(this: &Object, event: WindowEvent<'static>) {
+  let state_ptr: *mut c_void = *this.get_ivar("taoState");
+  let state = &mut *(state_ptr as *mut ViewState);
+
+  let is_key_window: BOOL = msg_send![state.ns_window, isKeyWindow];
+  if is_key_window == NO {
+    return;
+  }
+
+  let window_event = Event::WindowEvent {
+    window_id: WindowId(get_window_id(state.ns_window)),
+    event,
+  };
+  AppState::queue_event(EventWrapper::StaticEvent(window_event));
+}
+
+extern "C" fn magnify_with_event(this: &Object, _sel: Sel, event: id) {
+  trace!("Triggered `magnifyWithEvent`");
+  if responders_handle(this, event) {
+    return;
+  }
+  unsafe {
+    if let Some(phase) = gesture_phase(event) {
+      let delta: f64 = msg_send![event, magnification];
+      queue_gesture_event(
+        this,
+        WindowEvent::TouchpadMagnify {
+          device_id: DEVICE_ID,
+          delta,
+          phase,
+        },
+      );
+    }
+  }
+  trace!("Completed `magnifyWithEvent`");
+}
+
+extern "C" fn rotate_with_event(this: &Object, _sel: Sel, event: id) {
+  trace!("Triggered `rotateWithEvent`");
+  if responders_handle(this, event) {
+    return;
+  }
+  unsafe {
+    if let Some(phase) = gesture_phase(event) {
+      let delta: f32 = msg_send![event, rotation];
+      queue_gesture_event(
+        this,
+        WindowEvent::TouchpadRotate {
+          device_id: DEVICE_ID,
+          delta,
+          phase,
+        },
+      );
+    }
+  }
+  trace!("Completed `rotateWithEvent`");
+}
+
+extern "C" fn smart_magnify_with_event(this: &Object, _sel: Sel, event: id) {
+  trace!("Triggered `smartMagnifyWithEvent`");
+  if responders_handle(this, event) {
+    return;
+  }
+  unsafe {
+    queue_gesture_event(
+      this,
+      WindowEvent::SmartMagnify {
+        device_id: DEVICE_ID,
+      },
+    );
+  }
+  trace!("Completed `smartMagnifyWithEvent`");
+}
+
 // Allows us to receive Ctrl-Tab and Ctrl-Esc.
 // Note that this *doesn't* help with any missing Cmd inputs.
 // https://github.com/chromium/chromium/blob/a86a8a6bcfa438fa3ac2eba6f02b3ad1f8e0756f/ui/views/cocoa/bridged_content_view.mm#L816
@@ -1184,6 +2126,47 @@ extern "C" fn accepts_first_mouse(_this: &Object, _sel: Sel, _event: id) -> BOOL
   YES
 }
 
+/// `NSDragOperationCopy`
+const NS_DRAG_OPERATION_COPY: NSUInteger = 1;
+/// `NSDragOperationMove`
+const NS_DRAG_OPERATION_MOVE: NSUInteger = 16;
+
+extern "C" fn dragging_source_operation_mask(
+  _this: &Object,
+  _sel: Sel,
+  _session: id,
+  _context: NSInteger,
+) -> NSUInteger {
+  NS_DRAG_OPERATION_COPY | NS_DRAG_OPERATION_MOVE
+}
+
+extern "C" fn dragging_session_ended(
+  this: &Object,
+  _sel: Sel,
+  _session: id,
+  _point: NSPoint,
+  operation: NSUInteger,
+) {
+  trace!("Triggered `draggingSession:endedAtPoint:operation:`");
+  unsafe {
+    let state_ptr: *mut c_void = *this.get_ivar("taoState");
+    let state = &mut *(state_ptr as *mut ViewState);
+
+    let result = if operation & NS_DRAG_OPERATION_MOVE != 0 {
+      DragResult::Dropped(DragOperation::Move)
+    } else if operation != 0 {
+      DragResult::Dropped(DragOperation::Copy)
+    } else {
+      DragResult::Cancelled
+    };
+    AppState::queue_event(EventWrapper::StaticEvent(Event::WindowEvent {
+      window_id: WindowId(get_window_id(state.ns_window)),
+      event: WindowEvent::DragFinished(result),
+    }));
+  }
+  trace!("Completed `draggingSession:endedAtPoint:operation:`");
+}
+
 pub unsafe fn inset_traffic_lights<W: NSWindow + Copy>(window: W, position: LogicalPosition<f64>) {
   let (x, y) = (position.x, position.y);
 
@@ -1209,3 +2192,36 @@ pub unsafe fn inset_traffic_lights<W: NSWindow + Copy>(window: W, position: Logi
     button.setFrameOrigin(rect.origin);
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use cocoa::{appkit::NSEventType, foundation::NSPoint};
+
+  use super::{pen_tilt, tablet_event};
+  use crate::platform_impl::platform::ffi::{
+    NSTabletPointEventSubtype, NSTabletProximityEventSubtype,
+  };
+
+  #[test]
+  fn tablet_events_are_told_apart_by_type_and_subtype() {
+    assert_eq!(tablet_event(NSEventType::NSTabletProximity, 0), Some(true));
+    assert_eq!(
+      tablet_event(NSEventType::NSTabletPoint, NSTabletProximityEventSubtype),
+      Some(false)
+    );
+    assert_eq!(
+      tablet_event(NSEventType::NSLeftMouseDragged, NSTabletPointEventSubtype),
+      Some(false)
+    );
+    assert_eq!(
+      tablet_event(NSEventType::NSMouseMoved, NSTabletProximityEventSubtype),
+      Some(true)
+    );
+    assert_eq!(tablet_event(NSEventType::NSLeftMouseDown, 0), None);
+  }
+
+  #[test]
+  fn pen_tilt_is_in_degrees_toward_the_user() {
+    assert_eq!(pen_tilt(NSPoint::new(0.5, -1.0)), (45.0, 90.0));
+  }
+}
@@ -3,27 +3,35 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
+  cell::RefCell,
   collections::VecDeque,
   convert::TryInto,
-  f64,
+  f64, mem,
   os::raw::c_void,
   sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicUsize, Ordering},
     Arc, Mutex, Weak,
   },
 };
 
 use crate::{
-  dpi::{
-    LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Position, Size, Size::Logical,
-  },
-  error::{ExternalError, NotSupportedError, OsError as RootOsError},
-  icon::Icon,
+  accessibility::AccessibilityActionRequest,
+  dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Position, Rect, Size},
+  error::{ExternalError, NotSupportedError, OsError as RootOsError, WindowOperationError},
+  event::{Event, WindowEvent},
+  event_loop::EventLoopClosed,
+  icon::{Icon, RgbaIcon},
   monitor::{MonitorHandle as RootMonitorHandle, VideoMode as RootVideoMode},
-  platform::macos::WindowExtMacOS,
+  platform::macos::{
+    AccessibilityProviderMacOS, MetalLayerOptions, WindowExtMacOS, SHEET_RESPONSE_STOP,
+  },
   platform_impl::platform::{
     app_state::AppState,
+    display_link::DisplayLink,
+    event::EventWrapper,
     ffi,
+    icon::PlatformIcon,
+    menu::{Menu, Submenu},
     monitor::{self, MonitorHandle, VideoMode},
     util::{self, IdRef},
     view::{self, new_view, CursorState},
@@ -32,10 +40,13 @@ use crate::{
   },
   platform_impl::set_progress_indicator,
   window::{
-    CursorIcon, Fullscreen, ProgressBarState, ResizeDirection, Theme, UserAttentionType,
-    WindowAttributes, WindowId as RootWindowId, WindowSizeConstraints,
+    ActivationToken, Color, CursorGrabMode, CursorIcon, CursorKind, DragData, DragImage,
+    FocusResult, Fullscreen, HitTestCallback, ImePurpose, ProgressBarState, ResizeDirection,
+    RgbaImage, Theme, UserAttentionType, Window as RootWindow, WindowAttributes,
+    WindowId as RootWindowId, WindowLevel, WindowShape, WindowSizeConstraints,
   },
 };
+use block::ConcreteBlock;
 use cocoa::{
   appkit::{
     self, CGFloat, NSApp, NSApplication, NSApplicationPresentationOptions, NSColor, NSEvent,
@@ -49,7 +60,17 @@ use cocoa::{
     NSTimeInterval, NSUInteger,
   },
 };
-use core_graphics::display::{CGDisplay, CGDisplayMode};
+use core_foundation::base::CFRelease;
+use core_graphics::{
+  base::CGError,
+  display::{CGDisplay, CGDisplayMode},
+  geometry::{CGPoint, CGRect, CGSize},
+  window::{
+    self as cg_window, kCGWindowImageBestResolution, kCGWindowImageBoundsIgnoreFraming,
+    kCGWindowListOptionIncludingWindow, CGWindowID,
+  },
+};
+use dispatch::Queue;
 use objc::{
   declare::ClassDecl,
   runtime::{Class, Object, Sel, BOOL, NO, YES},
@@ -64,12 +85,20 @@ impl Id {
   pub unsafe fn dummy() -> Self {
     Id(0)
   }
+
+  pub fn into_raw(self) -> u64 {
+    self.0 as u64
+  }
+
+  pub fn from_raw(raw: u64) -> Self {
+    Id(raw as usize)
+  }
 }
 
-// Convert the `cocoa::base::id` associated with a window to a usize to use as a unique identifier
-// for the window.
+// Returns the identifier assigned to a `TaoWindow` when it was created. Unlike its address, it's
+// never reused by another window.
 pub fn get_window_id(window_cocoa_id: id) -> Id {
-  Id(window_cocoa_id as *const Object as _)
+  Id(unsafe { *(*window_cocoa_id).get_ivar::<usize>("taoWindowId") })
 }
 
 #[non_exhaustive]
@@ -230,6 +259,13 @@ fn create_window(
       NO,
     ));
     let res = ns_window.non_nil().map(|ns_window| {
+      // 0 is the dummy id.
+      static NEXT_WINDOW_ID: AtomicUsize = AtomicUsize::new(1);
+      (**ns_window).set_ivar::<usize>(
+        "taoWindowId",
+        NEXT_WINDOW_ID.fetch_add(1, Ordering::Relaxed),
+      );
+
       let title = util::ns_string_id_ref(&attrs.title);
       ns_window.setReleasedWhenClosed_(NO);
       ns_window.setTitle_(*title);
@@ -256,24 +292,18 @@ fn create_window(
         ns_window.setMovableByWindowBackground_(YES);
       }
 
-      if attrs.always_on_top {
-        let _: () = msg_send![
-          *ns_window,
-          setLevel: ffi::NSWindowLevel::NSFloatingWindowLevel
-        ];
-      }
-
-      if attrs.always_on_bottom {
-        let _: () = msg_send![
-          *ns_window,
-          setLevel: ffi::NSWindowLevel::BelowNormalWindowLevel
-        ];
+      if attrs.window_level != WindowLevel::Normal {
+        set_ns_window_level(*ns_window, attrs.window_level);
       }
 
       if attrs.content_protection {
         let _: () = msg_send![*ns_window, setSharingType: 0];
       }
 
+      if attrs.opacity < 1.0 {
+        let _: () = msg_send![*ns_window, setAlphaValue: attrs.opacity as CGFloat];
+      }
+
       if !attrs.maximizable {
         let button = ns_window.standardWindowButton_(NSWindowButton::NSWindowZoomButton);
         let _: () = msg_send![button, setEnabled: NO];
@@ -313,18 +343,18 @@ fn create_window(
   }
 }
 
-pub(super) fn get_ns_theme() -> Theme {
+/// Returns the effective theme of the window, which follows the app and system appearance
+/// unless the window appearance was set with [`set_ns_theme`].
+pub(super) fn get_ns_theme(ns_window: id) -> Theme {
   unsafe {
     let mut appearances: Vec<id> = Vec::new();
     appearances.push(NSString::alloc(nil).init_str("NSAppearanceNameAqua"));
     appearances.push(NSString::alloc(nil).init_str("NSAppearanceNameDarkAqua"));
-    let app_class = class!(NSApplication);
-    let app: id = msg_send![app_class, sharedApplication];
-    let has_theme: BOOL = msg_send![app, respondsToSelector: sel!(effectiveAppearance)];
+    let has_theme: BOOL = msg_send![ns_window, respondsToSelector: sel!(effectiveAppearance)];
     if has_theme == NO {
       return Theme::Light;
     }
-    let appearance: id = msg_send![app, effectiveAppearance];
+    let appearance: id = msg_send![ns_window, effectiveAppearance];
     let name: id = msg_send![
       appearance,
       bestMatchFromAppearancesWithNames: NSArray::arrayWithObjects(nil, &appearances)
@@ -337,23 +367,51 @@ pub(super) fn get_ns_theme() -> Theme {
   }
 }
 
-pub(super) fn set_ns_theme(theme: Theme) {
-  let name = match theme {
-    Theme::Dark => "NSAppearanceNameDarkAqua",
-    Theme::Light => "NSAppearanceNameAqua",
-  };
+/// Forces the appearance of the window, or makes it follow the app and system appearance again
+/// if `None` is given.
+pub(super) fn set_ns_theme(ns_window: id, theme: Option<Theme>) {
   unsafe {
-    let app_class = class!(NSApplication);
-    let app: id = msg_send![app_class, sharedApplication];
-    let has_theme: BOOL = msg_send![app, respondsToSelector: sel!(effectiveAppearance)];
+    let has_theme: BOOL = msg_send![ns_window, respondsToSelector: sel!(setAppearance:)];
     if has_theme == YES {
-      let name = NSString::alloc(nil).init_str(name);
-      let appearance: id = msg_send![class!(NSAppearance), appearanceNamed: name];
-      let _: () = msg_send![app, setAppearance: appearance];
+      let appearance: id = match theme {
+        Some(theme) => {
+          let name = match theme {
+            Theme::Dark => "NSAppearanceNameDarkAqua",
+            Theme::Light => "NSAppearanceNameAqua",
+          };
+          let name = NSString::alloc(nil).init_str(name);
+          msg_send![class!(NSAppearance), appearanceNamed: name]
+        }
+        None => nil,
+      };
+      let _: () = msg_send![ns_window, setAppearance: appearance];
     }
   }
 }
 
+thread_local! {
+  /// The windows placed by `cascade` that may still be open, most recent last, and the top left
+  /// corner of the next one.
+  static CASCADE: RefCell<(Vec<RootWindowId>, NSPoint)> =
+    RefCell::new((Vec::new(), NSPoint::new(0., 0.)));
+}
+
+/// Places the window down and to the right of the last one placed by this function, see
+/// `WindowBuilder::with_position_cascade`.
+fn cascade(window: &UnownedWindow) {
+  CASCADE.with(|cascade| {
+    let (windows, next_top_left) = &mut *cascade.borrow_mut();
+    windows.retain(|id| AppState::has_window(*id));
+    if windows.is_empty() {
+      // The window stays where it is for a zero point, which only computes the next one.
+      *next_top_left = NSPoint::new(0., 0.);
+    }
+    *next_top_left =
+      unsafe { msg_send![*window.ns_window, cascadeTopLeftFromPoint: *next_top_left] };
+    windows.push(RootWindowId(window.id()));
+  });
+}
+
 struct WindowClass(*const Class);
 unsafe impl Send for WindowClass {}
 unsafe impl Sync for WindowClass {}
@@ -364,23 +422,65 @@ lazy_static! {
     let mut decl = ClassDecl::new("TaoWindow", window_superclass).unwrap();
     decl.add_method(
       sel!(canBecomeMainWindow),
-      util::yes as extern "C" fn(&Object, Sel) -> BOOL,
+      can_become_main_or_key_window as extern "C" fn(&Object, Sel) -> BOOL,
     );
     decl.add_method(
       sel!(canBecomeKeyWindow),
-      util::yes as extern "C" fn(&Object, Sel) -> BOOL,
+      can_become_main_or_key_window as extern "C" fn(&Object, Sel) -> BOOL,
     );
     decl.add_method(
       sel!(sendEvent:),
       send_event as extern "C" fn(&Object, Sel, id),
     );
+    // Set by `set_enable(false)`, see `send_event`.
+    decl.add_ivar::<BOOL>("taoDisabled");
+    // Set at creation, see `get_window_id`.
+    decl.add_ivar::<usize>("taoWindowId");
+    // Set while `set_level_async` owns the `FullScreenAuxiliary` collection behavior.
+    decl.add_ivar::<BOOL>("taoAddedFullScreenAuxiliary");
     WindowClass(decl.register())
   };
 }
 
+extern "C" fn can_become_main_or_key_window(this: &Object, _sel: Sel) -> BOOL {
+  let disabled: BOOL = unsafe { *this.get_ivar("taoDisabled") };
+  if disabled == YES {
+    NO
+  } else {
+    YES
+  }
+}
+
 extern "C" fn send_event(this: &Object, _sel: Sel, event: id) {
   unsafe {
     let event_type = event.eventType();
+
+    // A disabled window drops input, but still handles window management events.
+    let disabled: BOOL = *this.get_ivar("taoDisabled");
+    if disabled == YES {
+      match event_type {
+        appkit::NSLeftMouseDown
+        | appkit::NSLeftMouseUp
+        | appkit::NSLeftMouseDragged
+        | appkit::NSRightMouseDown
+        | appkit::NSRightMouseUp
+        | appkit::NSRightMouseDragged
+        | appkit::NSOtherMouseDown
+        | appkit::NSOtherMouseUp
+        | appkit::NSOtherMouseDragged
+        | appkit::NSScrollWheel
+        | appkit::NSKeyDown
+        | appkit::NSKeyUp
+        | appkit::NSFlagsChanged
+        | appkit::NSEventTypeMagnify
+        | appkit::NSEventTypeRotate
+        | appkit::NSEventTypeSmartMagnify
+        | appkit::NSEventTypeSwipe
+        | appkit::NSEventTypePressure => return,
+        _ => (),
+      }
+    }
+
     match event_type {
       appkit::NSLeftMouseDown => {
         // When wkwebview is set on NSWindow, `WindowBuilder::with_movable_by_window_background` is not working.
@@ -420,6 +520,34 @@ pub struct SharedState {
   save_presentation_opts: Option<NSApplicationPresentationOptions>,
   pub saved_desktop_display_mode: Option<(CGDisplay, CGDisplayMode)>,
   pub current_theme: Theme,
+  /// The theme forced with `set_theme`, `None` when following the system theme.
+  pub preferred_theme: Option<Theme>,
+  /// The id returned by `requestUserAttention:`, needed to cancel the request.
+  pub user_attention_request: Option<NSInteger>,
+  /// Kept in their original unit to convert them again when the scale factor changes.
+  pub inner_size_constraints: WindowSizeConstraints,
+  /// The screen rectangle that hiding the window zooms toward, see `set_minimize_target`.
+  pub minimize_target: Option<Rect>,
+  /// Set while hiding the window zooms it toward the minimize target, the frame changes of the
+  /// animation aren't reported as `Moved` and `Resized` events.
+  pub in_minimize_animation: bool,
+  /// Set when the window was created inactive and hidden, so that showing it for the first time
+  /// doesn't make it key.
+  pub show_inactive: bool,
+  pub ime_purpose: ImePurpose,
+  /// Whether this window enabled secure event input, which must be balanced, see
+  /// `update_secure_input`.
+  pub secure_input: bool,
+  /// Whether `set_cursor_grab` locked the cursor, only applied while the window is key.
+  pub cursor_grab: bool,
+  /// Whether this window dissociated the cursor from the mouse, see `update_cursor_grab`.
+  pub cursor_grabbed: bool,
+  /// The window belongs to a headless event loop and is never shown.
+  pub headless: bool,
+  /// Created with the first frame callbacks or synced redraw, see `request_frame_callbacks`.
+  pub display_link: Option<DisplayLink>,
+  /// The options of the `CAMetalLayer` backing the view, see `set_metal_layer_options`.
+  pub metal_layer: Option<MetalLayerOptions>,
 }
 
 impl SharedState {
@@ -442,11 +570,44 @@ impl From<WindowAttributes> for SharedState {
       // identical, resulting in a no-op.
       fullscreen: None,
       maximized: attribs.maximized,
+      inner_size_constraints: attribs.inner_size_constraints,
+      show_inactive: !attribs.focused && !attribs.visible,
       ..Default::default()
     }
   }
 }
 
+/// Queues the accessibility action requests of a window on the main thread.
+#[derive(Clone)]
+pub struct AccessibilityActionSender {
+  window_id: Id,
+  /// Gone with the window.
+  shared_state: Weak<Mutex<SharedState>>,
+}
+
+impl AccessibilityActionSender {
+  pub fn send(
+    &self,
+    request: AccessibilityActionRequest,
+  ) -> Result<(), EventLoopClosed<AccessibilityActionRequest>> {
+    if self.shared_state.strong_count() == 0 {
+      return Err(EventLoopClosed(request));
+    }
+    let window_id = RootWindowId(self.window_id);
+    let shared_state = self.shared_state.clone();
+    Queue::main().exec_async(move || {
+      // The window may be closed meanwhile.
+      if shared_state.strong_count() > 0 {
+        AppState::queue_event_and_wake(EventWrapper::StaticEvent(Event::AccessibilityAction {
+          window_id,
+          request,
+        }));
+      }
+    });
+    Ok(())
+  }
+}
+
 pub struct UnownedWindow {
   pub ns_window: IdRef, // never changes
   pub ns_view: IdRef,   // never changes
@@ -457,6 +618,18 @@ pub struct UnownedWindow {
   pub inner_rect: Option<PhysicalSize<u32>>,
 }
 
+/// Applies `level`, a screen saver window is also shown in the spaces of the applications in
+/// fullscreen.
+unsafe fn set_ns_window_level(ns_window: id, level: WindowLevel) {
+  let ns_level = match level {
+    WindowLevel::AlwaysOnBottom => ffi::NSWindowLevel::NSDesktopWindowLevel,
+    WindowLevel::Normal => ffi::NSWindowLevel::NSNormalWindowLevel,
+    WindowLevel::AlwaysOnTop => ffi::NSWindowLevel::NSFloatingWindowLevel,
+    WindowLevel::ScreenSaver => ffi::NSWindowLevel::NSScreenSaverWindowLevel,
+  };
+  util::set_level_async(ns_window, ns_level);
+}
+
 unsafe impl Send for UnownedWindow {}
 unsafe impl Sync for UnownedWindow {}
 
@@ -496,6 +669,8 @@ impl UnownedWindow {
       if win_attribs.transparent {
         ns_window.setOpaque_(NO);
         ns_window.setBackgroundColor_(NSColor::clearColor(nil));
+      } else if win_attribs.background_color.is_some() {
+        set_background_color(*ns_window, win_attribs.background_color);
       }
 
       if win_attribs.inner_size_constraints.has_min() {
@@ -525,10 +700,15 @@ impl UnownedWindow {
     // `WindowDelegate` to update the state.
     let fullscreen = win_attribs.fullscreen.take();
     let maximized = win_attribs.maximized;
+    let position_cascade = win_attribs.position_cascade
+      && win_attribs.position.is_none()
+      && fullscreen.is_none()
+      && !maximized;
     let visible = win_attribs.visible;
     let focused = win_attribs.focused;
     let decorations = win_attribs.decorations;
     let visible_on_all_workspaces = win_attribs.visible_on_all_workspaces;
+    let skip_taskbar = win_attribs.skip_taskbar;
     let inner_rect = win_attribs
       .inner_size
       .map(|size| size.to_physical(scale_factor));
@@ -545,23 +725,29 @@ impl UnownedWindow {
       inner_rect,
     });
 
-    match cloned_preferred_theme {
-      Some(theme) => {
-        set_ns_theme(theme);
-        let mut state = window.shared_state.lock().unwrap();
-        state.current_theme = theme.clone();
-      }
-      None => {
-        let mut state = window.shared_state.lock().unwrap();
-        state.current_theme = get_ns_theme();
-      }
+    if cloned_preferred_theme.is_some() {
+      set_ns_theme(*window.ns_window, cloned_preferred_theme);
+    }
+    {
+      let mut state = window.shared_state.lock().unwrap();
+      state.preferred_theme = cloned_preferred_theme;
+      state.current_theme = get_ns_theme(*window.ns_window);
     }
 
     let delegate = new_delegate(&window, fullscreen.is_some());
+    if position_cascade {
+      cascade(&window);
+    }
+    AppState::register_window(RootWindowId(window.id()));
 
     // Set fullscreen mode after we setup everything
-    window.set_fullscreen(fullscreen);
+    if let Err(e) = window.set_fullscreen(fullscreen) {
+      warn!("Failed to make the window fullscreen: {}", e);
+    }
     window.set_visible_on_all_workspaces(visible_on_all_workspaces);
+    if skip_taskbar {
+      let _ = window.set_skip_taskbar(true);
+    }
 
     // Setting the window as key has to happen *after* we set the fullscreen
     // state, since otherwise we'll briefly see the window at normal size
@@ -609,22 +795,63 @@ impl UnownedWindow {
     }
   }
 
+  pub fn close(&self) {
+    unsafe { util::request_close_async(self.ns_window.clone()) };
+  }
+
   pub fn set_visible(&self, visible: bool) {
+    if visible && self.shared_state.lock().unwrap().headless {
+      debug!("`Window::set_visible(true)` is ignored in headless mode");
+      return;
+    }
     match visible {
+      true if mem::take(&mut self.shared_state.lock().unwrap().show_inactive) => unsafe {
+        util::order_front_sync(*self.ns_window)
+      },
       true => unsafe { util::make_key_and_order_front_sync(*self.ns_window) },
-      false => unsafe { util::order_out_sync(*self.ns_window) },
+      false => match self.minimize_target_frame() {
+        Some(target) => {
+          self.shared_state.lock().unwrap().in_minimize_animation = true;
+          unsafe { util::zoom_out_sync(*self.ns_window, target) };
+          self.shared_state.lock().unwrap().in_minimize_animation = false;
+        }
+        None => unsafe { util::order_out_sync(*self.ns_window) },
+      },
     }
   }
 
+  /// Returns the minimize target in Cocoa screen coordinates, if it's still on one of the
+  /// monitors.
+  fn minimize_target_frame(&self) -> Option<NSRect> {
+    let target = self.shared_state.lock().unwrap().minimize_target?;
+    let scale_factor = self.scale_factor();
+    let position = target.position.to_logical::<f64>(scale_factor);
+    let size = target.size.to_logical::<f64>(scale_factor);
+    // Monitors may have changed since the target was set.
+    self.monitor_from_point(position.x + size.width / 2., position.y + size.height / 2.)?;
+    let position = util::window_position(position);
+    Some(NSRect::new(
+      NSPoint::new(position.x, position.y - size.height),
+      NSSize::new(size.width, size.height),
+    ))
+  }
+
+  #[inline]
+  pub fn set_minimize_target(&self, target: Option<Rect>) -> Result<(), ExternalError> {
+    self.shared_state.lock().unwrap().minimize_target = target;
+    Ok(())
+  }
+
   #[inline]
   // Shortener for set_visible(true)
   pub fn set_focus(&self) {
-    unsafe {
-      let is_minimized: BOOL = msg_send![*self.ns_window, isMiniaturized];
-      let is_visible: BOOL = msg_send![*self.ns_window, isVisible];
-      if is_minimized == NO && is_visible == YES {
-        util::set_focus(*self.ns_window);
-      }
+    let _ = self.set_focus_with_token(None);
+  }
+
+  pub fn set_focus_with_token(&self, _token: Option<ActivationToken>) -> FocusResult {
+    match unsafe { util::set_focus(*self.ns_window) } {
+      true => FocusResult::Focused,
+      false => FocusResult::Failed,
     }
   }
 
@@ -640,6 +867,77 @@ impl UnownedWindow {
     AppState::queue_redraw(RootWindowId(self.id()));
   }
 
+  pub fn request_redraw_synced(&self) {
+    if self.create_display_link() {
+      self.update_frame_callbacks();
+    }
+    let mut shared_state = self.shared_state.lock().unwrap();
+    match &mut shared_state.display_link {
+      Some(display_link) => display_link.request_once(),
+      None => AppState::queue_redraw(RootWindowId(self.id())),
+    }
+  }
+
+  pub fn request_frame_callbacks(&self, enabled: bool) {
+    if enabled && self.create_display_link() {
+      self.update_frame_callbacks();
+    }
+    if let Some(display_link) = &mut self.shared_state.lock().unwrap().display_link {
+      display_link.set_continuous(enabled);
+    }
+  }
+
+  /// Creates the display link of the frame callbacks and synced redraws if the window has none,
+  /// and returns whether it did.
+  fn create_display_link(&self) -> bool {
+    let mut shared_state = self.shared_state.lock().unwrap();
+    if shared_state.display_link.is_some() {
+      return false;
+    }
+    shared_state.display_link = DisplayLink::new(RootWindowId(self.id()));
+    shared_state.display_link.is_some()
+  }
+
+  /// Keeps the `contentsScale` of the `CAMetalLayer` backing the view at the scale factor of the
+  /// window, if it was asked to.
+  pub(crate) fn update_metal_layer_scale(&self) {
+    let follows = self
+      .shared_state
+      .lock()
+      .unwrap()
+      .metal_layer
+      .map_or(false, |options| options.contents_scale_follows_window);
+    if follows {
+      unsafe {
+        let layer: id = msg_send![*self.ns_view, layer];
+        if layer != nil {
+          let scale_factor = NSWindow::backingScaleFactor(*self.ns_window);
+          let _: () = msg_send![layer, setContentsScale: scale_factor];
+        }
+      }
+    }
+  }
+
+  /// Returns whether no part of the window is visible on screen.
+  pub(crate) fn is_occluded(&self) -> bool {
+    let occlusion_state: NSUInteger = unsafe { msg_send![*self.ns_window, occlusionState] };
+    occlusion_state & ffi::NSWindowOcclusionStateVisible == 0
+  }
+
+  /// Makes the display link of the frame callbacks and synced redraws follow the screen of the
+  /// window, and stop while the window is occluded or offscreen.
+  pub(crate) fn update_frame_callbacks(&self) {
+    let screen: id = unsafe { msg_send![*self.ns_window, screen] };
+    let display_id = if self.is_occluded() || screen == nil {
+      None
+    } else {
+      Some(self.current_monitor_inner().inner.native_identifier())
+    };
+    if let Some(display_link) = &mut self.shared_state.lock().unwrap().display_link {
+      display_link.update(display_id);
+    }
+  }
+
   pub fn outer_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
     let frame_rect = unsafe { NSWindow::frame(*self.ns_window) };
     let position = LogicalPosition::new(
@@ -689,42 +987,52 @@ impl UnownedWindow {
   }
 
   #[inline]
-  pub fn set_inner_size(&self, size: Size) {
+  pub fn set_inner_size(&self, size: Size) -> Result<(), WindowOperationError> {
+    let scale_factor = self.scale_factor();
+    // `setContentSize:` ignores the minimum and maximum sizes of the window.
+    let size = self
+      .shared_state
+      .lock()
+      .unwrap()
+      .inner_size_constraints
+      .clamp(size, scale_factor);
     unsafe {
-      let scale_factor = self.scale_factor();
       util::set_content_size_async(*self.ns_window, size.to_logical(scale_factor));
     }
+    Ok(())
   }
 
-  pub fn set_min_inner_size(&self, dimensions: Option<Size>) {
-    let dimensions = dimensions.unwrap_or(Logical(LogicalSize {
-      width: 0.0,
-      height: 0.0,
-    }));
-    let scale_factor = self.scale_factor();
-    unsafe {
-      set_min_inner_size(*self.ns_window, dimensions.to_logical(scale_factor));
-    }
+  pub fn set_min_inner_size(&self, dimensions: Option<Size>) -> Result<(), ExternalError> {
+    let mut constraints = self.shared_state.lock().unwrap().inner_size_constraints;
+    constraints.min_width = dimensions.map(|s| s.width());
+    constraints.min_height = dimensions.map(|s| s.height());
+    self.set_inner_size_constraints(constraints)
   }
 
-  pub fn set_max_inner_size(&self, dimensions: Option<Size>) {
-    let dimensions = dimensions.unwrap_or(Logical(LogicalSize {
-      width: std::f32::MAX as f64,
-      height: std::f32::MAX as f64,
-    }));
-    let scale_factor = self.scale_factor();
-    unsafe {
-      set_max_inner_size(*self.ns_window, dimensions.to_logical(scale_factor));
-    }
+  pub fn set_max_inner_size(&self, dimensions: Option<Size>) -> Result<(), ExternalError> {
+    let mut constraints = self.shared_state.lock().unwrap().inner_size_constraints;
+    constraints.max_width = dimensions.map(|s| s.width());
+    constraints.max_height = dimensions.map(|s| s.height());
+    self.set_inner_size_constraints(constraints)
+  }
+
+  pub fn set_inner_size_constraints(
+    &self,
+    constraints: WindowSizeConstraints,
+  ) -> Result<(), ExternalError> {
+    constraints.validate(self.scale_factor())?;
+    self.shared_state.lock().unwrap().inner_size_constraints = constraints;
+    self.apply_inner_size_constraints();
+    Ok(())
   }
 
-  pub fn set_inner_size_constraints(&self, constraints: WindowSizeConstraints) {
+  /// Applies the size constraints in points at the current scale factor.
+  pub(crate) fn apply_inner_size_constraints(&self) {
+    let constraints = self.shared_state.lock().unwrap().inner_size_constraints;
     let scale_factor = self.scale_factor();
     unsafe {
-      let min_size = constraints.min_size_logical(scale_factor);
-      set_min_inner_size(*self.ns_window, min_size);
-      let max_size = constraints.max_size_logical(scale_factor);
-      set_max_inner_size(*self.ns_window, max_size);
+      set_min_inner_size(*self.ns_window, constraints.min_size_logical(scale_factor));
+      set_max_inner_size(*self.ns_window, constraints.max_size_logical(scale_factor));
     }
   }
 
@@ -781,9 +1089,13 @@ impl UnownedWindow {
   }
 
   pub fn set_cursor_icon(&self, cursor: CursorIcon) {
-    let cursor = util::Cursor::from(cursor);
     if let Some(cursor_access) = self.cursor_state.upgrade() {
-      cursor_access.lock().unwrap().cursor = cursor;
+      let mut cursor_state = cursor_access.lock().unwrap();
+      if cursor_state.icon == cursor && !matches!(cursor_state.cursor, util::Cursor::Custom(_)) {
+        return;
+      }
+      cursor_state.icon = cursor;
+      cursor_state.cursor = util::Cursor::from(cursor);
     }
     unsafe {
       let _: () = msg_send![*self.ns_window,
@@ -793,10 +1105,37 @@ impl UnownedWindow {
   }
 
   #[inline]
-  pub fn set_cursor_grab(&self, grab: bool) -> Result<(), ExternalError> {
+  pub fn set_cursor_grab(&self, mode: CursorGrabMode) -> Result<(), ExternalError> {
+    let grab = match mode {
+      CursorGrabMode::None => false,
+      CursorGrabMode::Locked => true,
+      // There's no API to confine the cursor.
+      CursorGrabMode::Confined => {
+        return Err(ExternalError::NotSupported(NotSupportedError::new()))
+      }
+    };
+    let previous = {
+      let mut shared_state = self.shared_state.lock().unwrap();
+      mem::replace(&mut shared_state.cursor_grab, grab)
+    };
+    self.update_cursor_grab().map_err(|status| {
+      self.shared_state.lock().unwrap().cursor_grab = previous;
+      ExternalError::Os(os_error!(OsError::CGError(status)))
+    })
+  }
+
+  /// Dissociates the cursor from the mouse while the window is key and grabs it, and associates
+  /// them again otherwise, so that the cursor never stays frozen after switching applications.
+  pub(crate) fn update_cursor_grab(&self) -> Result<(), CGError> {
     // TODO: Do this for real https://stackoverflow.com/a/40922095/5435443
-    CGDisplay::associate_mouse_and_mouse_cursor_position(!grab)
-      .map_err(|status| ExternalError::Os(os_error!(OsError::CGError(status))))
+    let is_key: BOOL = unsafe { msg_send![*self.ns_window, isKeyWindow] };
+    let mut shared_state = self.shared_state.lock().unwrap();
+    let grabbed = is_key == YES && shared_state.cursor_grab;
+    if grabbed != shared_state.cursor_grabbed {
+      CGDisplay::associate_mouse_and_mouse_cursor_position(!grabbed)?;
+      shared_state.cursor_grabbed = grabbed;
+    }
+    Ok(())
   }
 
   #[inline]
@@ -815,14 +1154,107 @@ impl UnownedWindow {
     }
   }
 
+  #[inline]
+  pub fn cursor_icon(&self) -> CursorKind {
+    match self.cursor_state.upgrade() {
+      Some(cursor_access) => {
+        let cursor_state = cursor_access.lock().unwrap();
+        if !cursor_state.visible {
+          CursorKind::Hidden
+        } else if let util::Cursor::Custom(_) = cursor_state.cursor {
+          CursorKind::Custom
+        } else {
+          CursorKind::Icon(cursor_state.icon)
+        }
+      }
+      None => CursorKind::default(),
+    }
+  }
+
+  pub fn set_custom_cursor(
+    &self,
+    icon: RgbaIcon,
+    hotspot: PhysicalPosition<u32>,
+  ) -> Result<(), ExternalError> {
+    // The image is sized in points, so that it's shown at its size in physical pixels.
+    let scale_factor = self.scale_factor();
+    let size = NSSize::new(
+      icon.width as f64 / scale_factor,
+      icon.height as f64 / scale_factor,
+    );
+    let hotspot = NSPoint::new(
+      hotspot.x as f64 / scale_factor,
+      hotspot.y as f64 / scale_factor,
+    );
+    let image = PlatformIcon::from_rgba(icon.rgba, icon.width, icon.height)
+      .map_err(|_| ExternalError::InvalidInput("the data of the image is shorter than its size"))?;
+    let cursor = unsafe {
+      let image = image.to_ns_image();
+      let () = msg_send![image, setSize: size];
+      let cursor: id = msg_send![class!(NSCursor), alloc];
+      IdRef::new(msg_send![cursor, initWithImage: image hotSpot: hotspot])
+    };
+    if let Some(cursor_access) = self.cursor_state.upgrade() {
+      cursor_access.lock().unwrap().cursor = util::Cursor::Custom(cursor);
+    }
+    unsafe {
+      let _: () = msg_send![*self.ns_window,
+          invalidateCursorRectsForView:*self.ns_view
+      ];
+    }
+    Ok(())
+  }
+
   #[inline]
   pub fn cursor_position(&self) -> Result<PhysicalPosition<f64>, ExternalError> {
-    let point = util::cursor_position()?;
-    if let Some(m) = self.monitor_from_point(point.x, point.y) {
-      Ok(point.to_physical(m.scale_factor()))
-    } else {
-      Err(ExternalError::Os(os_error!(OsError::CGError(0))))
+    // Same conversion as `CursorMoved`, from the screen to the flipped coordinates of the view.
+    let view_point = unsafe {
+      let screen_point: NSPoint = msg_send![class!(NSEvent), mouseLocation];
+      let screen_rect = NSRect::new(screen_point, NSSize::new(0., 0.));
+      let window_rect: NSRect = msg_send![*self.ns_window, convertRectFromScreen: screen_rect];
+      NSView::convertPoint_fromView_(*self.ns_view, window_rect.origin, nil)
+    };
+    let view_rect = unsafe { NSView::frame(*self.ns_view) };
+    let position = LogicalPosition::new(
+      view_point.x as f64,
+      view_rect.size.height as f64 - view_point.y as f64,
+    );
+    Ok(position.to_physical(self.scale_factor()))
+  }
+
+  pub fn capture(&self, outer: bool) -> Result<RgbaImage, ExternalError> {
+    let is_minimized: BOOL = unsafe { msg_send![*self.ns_window, isMiniaturized] };
+    if is_minimized == YES {
+      return Err(ExternalError::Os(os_error!(OsError::CreationError(
+        "a minimized window can't be captured"
+      ))));
     }
+
+    let rect = unsafe {
+      let frame = NSWindow::frame(*self.ns_window);
+      if outer {
+        frame
+      } else {
+        NSWindow::contentRectForFrameRect_(*self.ns_window, frame)
+      }
+    };
+    // Quartz window bounds have their origin at the top left of the main screen.
+    let bounds = CGRect::new(
+      &CGPoint::new(rect.origin.x, util::bottom_left_to_top_left(rect)),
+      &CGSize::new(rect.size.width, rect.size.height),
+    );
+    let window_number: NSInteger = unsafe { msg_send![*self.ns_window, windowNumber] };
+    let image = cg_window::create_image(
+      bounds,
+      kCGWindowListOptionIncludingWindow,
+      window_number as CGWindowID,
+      kCGWindowImageBoundsIgnoreFraming | kCGWindowImageBestResolution,
+    )
+    .ok_or(ExternalError::Os(os_error!(OsError::CreationError(
+      "Couldn't capture the window"
+    ))))?;
+
+    Ok(util::cg_image_to_rgba(&image))
   }
 
   #[inline]
@@ -840,10 +1272,23 @@ impl UnownedWindow {
       x: logical_cursor_position.x + window_position.x,
       y: logical_cursor_position.y + window_position.y,
     };
+    unsafe {
+      // Local mouse events are suppressed for a quarter of a second after a warp by default,
+      // which delays the next motion.
+      let source = ffi::CGEventSourceCreate(ffi::kCGEventSourceStateCombinedSessionState);
+      if !source.is_null() {
+        ffi::CGEventSourceSetLocalEventsSuppressionInterval(source, 0.0);
+        CFRelease(source as _);
+      }
+    }
     CGDisplay::warp_mouse_cursor_position(point)
       .map_err(|e| ExternalError::Os(os_error!(OsError::CGError(e))))?;
-    CGDisplay::associate_mouse_and_mouse_cursor_position(true)
-      .map_err(|e| ExternalError::Os(os_error!(OsError::CGError(e))))?;
+    // Associating the mouse and the cursor again also ends the suppression, but it would release
+    // the cursor of a grabbed window.
+    if !self.shared_state.lock().unwrap().cursor_grabbed {
+      CGDisplay::associate_mouse_and_mouse_cursor_position(true)
+        .map_err(|e| ExternalError::Os(os_error!(OsError::CGError(e))))?;
+    }
 
     Ok(())
   }
@@ -884,6 +1329,77 @@ impl UnownedWindow {
     Err(ExternalError::NotSupported(NotSupportedError::new()))
   }
 
+  pub fn start_drag(&self, data: DragData, image: DragImage) -> Result<(), ExternalError> {
+    unsafe {
+      // AppKit starts the session from the mouse down, or dragged, event being handled.
+      let event: id = msg_send![NSApp(), currentEvent];
+      let event_type: NSUInteger = if event == nil {
+        0
+      } else {
+        msg_send![event, type]
+      };
+      if !matches!(event_type, 1 | 3 | 6 | 7 | 25 | 27) {
+        return Err(ExternalError::Os(os_error!(OsError::CreationError(
+          "Dragging needs the current event to be a mouse event"
+        ))));
+      }
+
+      let scale_factor = self.scale_factor();
+      let icon = &image.image;
+      let size = NSSize::new(
+        icon.width as f64 / scale_factor,
+        icon.height as f64 / scale_factor,
+      );
+      let ns_image = PlatformIcon::from_rgba(icon.rgba.clone(), icon.width, icon.height)
+        .map_err(|_| ExternalError::Os(os_error!(OsError::CreationError("Invalid drag image"))))?
+        .to_ns_image();
+      let () = msg_send![ns_image, setSize: size];
+
+      // The view isn't flipped, so the hotspot is measured from the top of the frame.
+      let view = *self.ns_view;
+      let location: NSPoint = msg_send![event, locationInWindow];
+      let location: NSPoint = msg_send![view, convertPoint: location fromView: nil];
+      let frame = NSRect::new(
+        NSPoint::new(
+          location.x - image.hotspot.x as f64 / scale_factor,
+          location.y - (icon.height - image.hotspot.y) as f64 / scale_factor,
+        ),
+        size,
+      );
+
+      let writers: Vec<id> = match &data {
+        DragData::Files(paths) => paths
+          .iter()
+          .map(|path| {
+            let path = NSString::alloc(nil).init_str(&path.to_string_lossy());
+            let url: id = msg_send![class!(NSURL), fileURLWithPath: path];
+            let () = msg_send![path, release];
+            url
+          })
+          .collect(),
+        DragData::Text(text) => vec![NSString::alloc(nil).init_str(text).autorelease()],
+      };
+      if writers.is_empty() {
+        return Err(ExternalError::Os(os_error!(OsError::CreationError(
+          "Nothing to drag"
+        ))));
+      }
+
+      let items: id = msg_send![class!(NSMutableArray), array];
+      for (i, writer) in writers.into_iter().enumerate() {
+        let item: id = msg_send![class!(NSDraggingItem), alloc];
+        let item: id = msg_send![item, initWithPasteboardWriter: writer];
+        // Only the first item shows the image, the others follow it.
+        let contents = if i == 0 { ns_image } else { nil };
+        let () = msg_send![item, setDraggingFrame: frame contents: contents];
+        let () = msg_send![items, addObject: item];
+        let () = msg_send![item, release];
+      }
+      let _: id = msg_send![view, beginDraggingSessionWithItems: items event: event source: view];
+    }
+    Ok(())
+  }
+
   #[inline]
   pub fn set_ignore_cursor_events(&self, ignore: bool) -> Result<(), ExternalError> {
     unsafe {
@@ -893,6 +1409,68 @@ impl UnownedWindow {
     Ok(())
   }
 
+  #[inline]
+  pub fn set_cursor_hittest_region(&self, region: Option<&[Rect]>) -> Result<(), ExternalError> {
+    unsafe {
+      util::set_cursor_hittest_region_async(*self.ns_view, region.map(<[Rect]>::to_vec));
+    }
+
+    Ok(())
+  }
+
+  #[inline]
+  pub fn set_hit_test_callback(
+    &self,
+    callback: Option<HitTestCallback>,
+  ) -> Result<(), ExternalError> {
+    unsafe {
+      view::set_hit_test_callback(*self.ns_view, callback);
+    }
+
+    Ok(())
+  }
+
+  #[inline]
+  pub fn accessibility_action_sender(&self) -> Result<AccessibilityActionSender, ExternalError> {
+    Ok(AccessibilityActionSender {
+      window_id: self.id(),
+      shared_state: Arc::downgrade(&self.shared_state),
+    })
+  }
+
+  #[inline]
+  pub fn set_shape(&self, shape: Option<WindowShape>) -> Result<(), ExternalError> {
+    unsafe {
+      // Outside of the shape, the window itself must be transparent.
+      if shape.is_some() {
+        self.ns_window.setOpaque_(NO);
+        self.ns_window.setBackgroundColor_(NSColor::clearColor(nil));
+      }
+      view::set_shape(*self.ns_view, shape);
+      // The shadow follows the shape of the content.
+      let _: () = msg_send![*self.ns_window, invalidateShadow];
+    }
+
+    Ok(())
+  }
+
+  pub fn set_opacity(&self, opacity: f32) {
+    unsafe {
+      let _: () = msg_send![*self.ns_window, setAlphaValue: opacity as CGFloat];
+    }
+  }
+
+  pub fn opacity(&self) -> f32 {
+    unsafe {
+      let opacity: CGFloat = msg_send![*self.ns_window, alphaValue];
+      opacity as f32
+    }
+  }
+
+  pub fn set_background_color(&self, color: Option<Color>) {
+    unsafe { set_background_color(*self.ns_window, color) }
+  }
+
   pub(crate) fn is_zoomed(&self) -> bool {
     // because `isZoomed` doesn't work if the window's borderless,
     // we make it resizable temporalily.
@@ -983,7 +1561,11 @@ impl UnownedWindow {
   #[inline]
   pub fn fullscreen(&self) -> Option<Fullscreen> {
     let shared_state_lock = self.shared_state.lock().unwrap();
-    shared_state_lock.fullscreen.clone()
+    // A state requested during a transition is applied once it ends.
+    shared_state_lock
+      .target_fullscreen
+      .clone()
+      .unwrap_or_else(|| shared_state_lock.fullscreen.clone())
   }
 
   #[inline]
@@ -1039,24 +1621,26 @@ impl UnownedWindow {
   }
 
   #[inline]
-  pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+  pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) -> Result<(), WindowOperationError> {
     trace!("Locked shared state in `set_fullscreen`");
     let mut shared_state_lock = self.shared_state.lock().unwrap();
     if shared_state_lock.is_simple_fullscreen {
       trace!("Unlocked shared state in `set_fullscreen`");
-      return;
+      return Err(WindowOperationError::InvalidState(
+        "the window is in simple fullscreen",
+      ));
     }
     if shared_state_lock.in_fullscreen_transition {
       // We can't set fullscreen here.
       // Set fullscreen after transition.
       shared_state_lock.target_fullscreen = Some(fullscreen);
       trace!("Unlocked shared state in `set_fullscreen`");
-      return;
+      return Ok(());
     }
     let old_fullscreen = shared_state_lock.fullscreen.clone();
     if fullscreen == old_fullscreen {
       trace!("Unlocked shared state in `set_fullscreen`");
-      return;
+      return Ok(());
     }
     trace!("Unlocked shared state in `set_fullscreen`");
     drop(shared_state_lock);
@@ -1064,6 +1648,7 @@ impl UnownedWindow {
     // If the fullscreen is on a different monitor, we must move the window
     // to that monitor before we toggle fullscreen (as `toggleFullScreen`
     // does not take a screen parameter, but uses the current screen)
+    let mut screen_frame = None;
     if let Some(ref fullscreen) = fullscreen {
       let new_screen = match fullscreen {
         Fullscreen::Borderless(borderless) => {
@@ -1082,11 +1667,27 @@ impl UnownedWindow {
       unsafe {
         let old_screen = NSWindow::screen(*self.ns_window);
         if old_screen != new_screen {
-          let mut screen_frame: NSRect = msg_send![new_screen, frame];
-          // The coordinate system here has its origin at bottom-left
-          // and Y goes up
-          screen_frame.origin.y += screen_frame.size.height;
-          util::set_frame_top_left_point_async(*self.ns_window, screen_frame.origin);
+          if let (Some(Fullscreen::Borderless(_)), Fullscreen::Borderless(_)) =
+            (&old_fullscreen, fullscreen)
+          {
+            // A fullscreen space can't be moved to another screen, so leave it and enter
+            // fullscreen again from `window_did_exit_fullscreen`.
+            trace!("Locked shared state in `set_fullscreen`");
+            let mut shared_state_lock = self.shared_state.lock().unwrap();
+            shared_state_lock.target_fullscreen = Some(Some(fullscreen.clone()));
+            shared_state_lock.in_fullscreen_transition = true;
+            trace!("Unlocked shared state in `set_fullscreen`");
+            drop(shared_state_lock);
+            util::toggle_full_screen_async(
+              *self.ns_window,
+              *self.ns_view,
+              false,
+              None,
+              Arc::downgrade(&self.shared_state),
+            );
+            return Ok(());
+          }
+          screen_frame = Some(NSScreen::frame(new_screen));
         }
       }
     }
@@ -1133,16 +1734,17 @@ impl UnownedWindow {
           );
         }
 
-        assert_eq!(ffi::CGDisplayCapture(display_id), ffi::kCGErrorSuccess);
-      }
-
-      unsafe {
-        let result = ffi::CGDisplaySetDisplayMode(
-          display_id,
-          video_mode.video_mode.native_mode.0,
-          std::ptr::null(),
-        );
-        assert!(result == ffi::kCGErrorSuccess, "failed to set video mode");
+        let mut result = ffi::CGDisplayCapture(display_id);
+        if result == ffi::kCGErrorSuccess {
+          result = ffi::CGDisplaySetDisplayMode(
+            display_id,
+            video_mode.video_mode.native_mode.0,
+            std::ptr::null(),
+          );
+          if result != ffi::kCGErrorSuccess {
+            ffi::CGDisplayRelease(display_id);
+          }
+        }
 
         // After the display has been configured, fade back in
         // asynchronously
@@ -1159,20 +1761,32 @@ impl UnownedWindow {
           );
           ffi::CGReleaseDisplayFadeReservation(fade_token);
         }
+        if result != ffi::kCGErrorSuccess {
+          return Err(WindowOperationError::Os(os_error!(OsError::CGError(
+            result
+          ))));
+        }
       }
     }
 
     trace!("Locked shared state in `set_fullscreen`");
     let mut shared_state_lock = self.shared_state.lock().unwrap();
     shared_state_lock.fullscreen = fullscreen.clone();
+    // `toggleFullScreen` runs asynchronously and ignores calls made while a transition is in
+    // progress, so later requests must wait for this one to end instead of queueing more toggles.
+    if old_fullscreen.is_some() != fullscreen.is_some() {
+      shared_state_lock.in_fullscreen_transition = true;
+    }
     trace!("Unlocked shared state in `set_fullscreen`");
 
     match (&old_fullscreen, &fullscreen) {
       (&None, &Some(_)) => unsafe {
+        // Moved and resized to the screen in one step, right before the toggle.
         util::toggle_full_screen_async(
           *self.ns_window,
           *self.ns_view,
           old_fullscreen.is_none(),
+          screen_frame.take(),
           Arc::downgrade(&self.shared_state),
         );
       },
@@ -1182,6 +1796,7 @@ impl UnownedWindow {
           *self.ns_window,
           *self.ns_view,
           old_fullscreen.is_none(),
+          None,
           Arc::downgrade(&self.shared_state),
         );
       },
@@ -1192,6 +1807,7 @@ impl UnownedWindow {
           *self.ns_window,
           *self.ns_view,
           old_fullscreen.is_none(),
+          None,
           Arc::downgrade(&self.shared_state),
         );
       },
@@ -1239,6 +1855,13 @@ impl UnownedWindow {
       _ => {}
     }
     trace!("Unlocked shared state in `set_fullscreen`");
+    drop(shared_state_lock);
+
+    // Switching between exclusive and borderless fullscreen doesn't toggle.
+    if let Some(screen_frame) = screen_frame {
+      unsafe { util::set_frame_async(*self.ns_window, screen_frame) };
+    }
+    Ok(())
   }
 
   #[inline]
@@ -1280,10 +1903,45 @@ impl UnownedWindow {
     }
   }
 
+  #[inline]
+  pub fn raise(&self) -> Result<(), ExternalError> {
+    unsafe { util::order_window_sync(*self.ns_window, NSWindowOrderingMode::NSWindowAbove, 0) };
+    Ok(())
+  }
+
+  #[inline]
+  pub fn lower(&self) -> Result<(), ExternalError> {
+    unsafe { util::order_window_sync(*self.ns_window, NSWindowOrderingMode::NSWindowBelow, 0) };
+    Ok(())
+  }
+
+  #[inline]
+  pub fn restack_above(&self, sibling: &UnownedWindow) -> Result<(), ExternalError> {
+    unsafe {
+      let window_number: NSInteger = msg_send![*sibling.ns_window, windowNumber];
+      util::order_window_sync(
+        *self.ns_window,
+        NSWindowOrderingMode::NSWindowAbove,
+        window_number,
+      );
+    }
+    Ok(())
+  }
+
+  /// Returns the parent to create a child window of this one.
+  pub(crate) fn as_parent(&self) -> Parent {
+    Parent::ChildOf(*self.ns_window as *mut c_void)
+  }
+
+  #[inline]
+  pub fn set_parent(&self, parent: Option<&super::Window>) {
+    unsafe { util::set_parent_async(*self.ns_window, parent.map(|parent| *parent.ns_window)) };
+  }
+
   #[inline]
   pub fn set_always_on_bottom(&self, always_on_bottom: bool) {
     let level = if always_on_bottom {
-      ffi::NSWindowLevel::BelowNormalWindowLevel
+      ffi::NSWindowLevel::NSDesktopWindowLevel
     } else {
       ffi::NSWindowLevel::NSNormalWindowLevel
     };
@@ -1291,13 +1949,19 @@ impl UnownedWindow {
   }
 
   #[inline]
-  pub fn set_always_on_top(&self, always_on_top: bool) {
+  pub fn set_always_on_top(&self, always_on_top: bool) -> Result<(), WindowOperationError> {
     let level = if always_on_top {
       ffi::NSWindowLevel::NSFloatingWindowLevel
     } else {
       ffi::NSWindowLevel::NSNormalWindowLevel
     };
     unsafe { util::set_level_async(*self.ns_window, level) };
+    Ok(())
+  }
+
+  #[inline]
+  pub fn set_window_level(&self, level: WindowLevel) {
+    unsafe { set_ns_window_level(*self.ns_window, level) };
   }
 
   #[inline]
@@ -1313,32 +1977,97 @@ impl UnownedWindow {
   }
 
   #[inline]
-  pub fn set_ime_position(&self, spot: Position) {
+  pub fn set_ime_cursor_area(&self, area: Rect) {
     let scale_factor = self.scale_factor();
-    let logical_spot = spot.to_logical(scale_factor);
     unsafe {
-      view::set_ime_position(
+      view::set_ime_cursor_area(
         *self.ns_view,
         *self.input_context,
-        logical_spot.x,
-        logical_spot.y,
+        area.position.to_logical(scale_factor),
+        area.size.to_logical(scale_factor),
       );
     }
   }
 
+  #[inline]
+  pub fn set_ime_allowed(&self, allowed: bool) {
+    unsafe { util::set_ime_allowed_async(*self.ns_view, allowed) }
+  }
+
+  #[inline]
+  pub fn set_ime_visible(&self, _visible: bool) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  #[inline]
+  pub fn set_ime_purpose(&self, purpose: ImePurpose) -> Result<(), ExternalError> {
+    self.shared_state.lock().unwrap().ime_purpose = purpose;
+    // `NSSecureTextField` also restricts its input to roman input sources.
+    let roman_only = matches!(purpose, ImePurpose::Password | ImePurpose::Number);
+    unsafe { util::set_roman_input_only_async(self.input_context.clone(), roman_only) };
+    self.update_secure_input();
+    Ok(())
+  }
+
+  /// Enables secure event input while the window is key and takes a password, and disables it
+  /// otherwise. Calls to `EnableSecureEventInput` are counted, so each window only enables it
+  /// once.
+  pub(crate) fn update_secure_input(&self) {
+    let is_key: BOOL = unsafe { msg_send![*self.ns_window, isKeyWindow] };
+    let mut shared_state = self.shared_state.lock().unwrap();
+    let secure = is_key == YES && shared_state.ime_purpose == ImePurpose::Password;
+    if secure != shared_state.secure_input {
+      shared_state.secure_input = secure;
+      unsafe {
+        if secure {
+          ffi::EnableSecureEventInput();
+        } else {
+          ffi::DisableSecureEventInput();
+        }
+      }
+    }
+  }
+
   #[inline]
   pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
     let ns_request_type = request_type.map(|ty| match ty {
       UserAttentionType::Critical => NSRequestUserAttentionType::NSCriticalRequest,
       UserAttentionType::Informational => NSRequestUserAttentionType::NSInformationalRequest,
     });
+    let mut shared_state = self.shared_state.lock().unwrap();
     unsafe {
+      if let Some(request) = shared_state.user_attention_request.take() {
+        let _: () = msg_send![NSApp(), cancelUserAttentionRequest: request];
+      }
       if let Some(ty) = ns_request_type {
-        NSApp().requestUserAttention_(ty);
+        let request: NSInteger = msg_send![NSApp(), requestUserAttention: ty];
+        // `requestUserAttention:` returns 0 and does nothing when the app is already active.
+        if request != 0 {
+          shared_state.user_attention_request = Some(request);
+        }
       }
     }
   }
 
+  #[inline]
+  pub fn reset_dead_keys(&self) {
+    // `unmarkText` drops the marked text of a pending dead key and discards it from the input
+    // context, so the next key press starts from a clean state.
+    unsafe {
+      let _: () = msg_send![*self.ns_view, unmarkText];
+    }
+  }
+
+  #[inline]
+  pub fn is_attention_requested(&self) -> bool {
+    self
+      .shared_state
+      .lock()
+      .unwrap()
+      .user_attention_request
+      .is_some()
+  }
+
   #[inline]
   // Allow directly accessing the current monitor internally without unwrapping.
   pub(crate) fn current_monitor_inner(&self) -> RootMonitorHandle {
@@ -1416,12 +2145,26 @@ impl UnownedWindow {
     ))
   }
 
+  pub fn set_enable(&self, enabled: bool) {
+    unsafe {
+      let ns_window = *self.ns_window as *mut Object;
+      (*ns_window).set_ivar::<BOOL>("taoDisabled", if enabled { NO } else { YES });
+    }
+  }
+
   #[inline]
   pub fn theme(&self) -> Theme {
     let state = self.shared_state.lock().unwrap();
     state.current_theme
   }
 
+  pub fn set_theme(&self, theme: Option<Theme>) {
+    set_ns_theme(*self.ns_window, theme);
+    let mut state = self.shared_state.lock().unwrap();
+    state.preferred_theme = theme;
+    state.current_theme = get_ns_theme(*self.ns_window);
+  }
+
   pub fn set_content_protection(&self, enabled: bool) {
     unsafe {
       let _: () = msg_send![*self.ns_window, setSharingType: !enabled as i32];
@@ -1442,9 +2185,44 @@ impl UnownedWindow {
     }
   }
 
+  pub fn is_visible_on_all_workspaces(&self) -> bool {
+    unsafe {
+      self
+        .ns_window
+        .collectionBehavior()
+        .contains(NSWindowCollectionBehavior::NSWindowCollectionBehaviorCanJoinAllSpaces)
+    }
+  }
+
+  pub fn set_skip_taskbar(&self, skip: bool) -> Result<(), ExternalError> {
+    unsafe {
+      let mut collection_behavior = self.ns_window.collectionBehavior();
+      if skip {
+        collection_behavior |= NSWindowCollectionBehavior::NSWindowCollectionBehaviorIgnoresCycle;
+      } else {
+        collection_behavior &= !NSWindowCollectionBehavior::NSWindowCollectionBehaviorIgnoresCycle;
+      };
+      self.ns_window.setCollectionBehavior_(collection_behavior);
+      let _: () = msg_send![*self.ns_window, setExcludedFromWindowsMenu: skip as BOOL];
+    }
+    Ok(())
+  }
+
   pub fn set_progress_bar(&self, progress: ProgressBarState) {
     set_progress_indicator(progress);
   }
+
+  #[inline]
+  pub fn set_menu(&self, menu: Option<&Menu>) {
+    if let Some(menu) = menu {
+      menu.set_as_main_menu();
+    }
+  }
+
+  #[inline]
+  pub fn show_context_menu(&self, menu: &Submenu, position: Position) {
+    menu.pop_up(*self.ns_view, position.to_logical(self.scale_factor()));
+  }
 }
 
 impl WindowExtMacOS for UnownedWindow {
@@ -1618,11 +2396,79 @@ impl WindowExtMacOS for UnownedWindow {
       ns_string_to_rust(tabbing_identifier)
     }
   }
+
+  #[inline]
+  fn set_miniaturize_proxy_rect(&self, rect: Option<Rect>) -> Result<(), ExternalError> {
+    self.set_minimize_target(rect)
+  }
+
+  fn begin_sheet(&self, sheet: &RootWindow) {
+    let window_id = RootWindowId(get_window_id(*self.ns_window));
+    let sheet_id = RootWindowId(get_window_id(*sheet.window.ns_window));
+    let handler = ConcreteBlock::new(move |response: NSInteger| {
+      AppState::queue_event(EventWrapper::StaticEvent(Event::WindowEvent {
+        window_id,
+        event: WindowEvent::SheetEnded {
+          sheet: sheet_id,
+          response: response as isize,
+        },
+      }));
+    })
+    .copy();
+    unsafe {
+      let sheet = *sheet.window.ns_window;
+      let _: () = msg_send![*self.ns_window, beginSheet: sheet completionHandler: &*handler];
+    }
+  }
+
+  #[inline]
+  fn end_sheet(&self, sheet: &RootWindow) {
+    self.end_sheet_with_response(sheet, SHEET_RESPONSE_STOP)
+  }
+
+  fn end_sheet_with_response(&self, sheet: &RootWindow, response: isize) {
+    unsafe {
+      let sheet = *sheet.window.ns_window;
+      let _: () = msg_send![*self.ns_window, endSheet: sheet returnCode: response as NSInteger];
+    }
+  }
+
+  #[inline]
+  fn set_metal_layer_options(&self, options: MetalLayerOptions) -> *mut c_void {
+    self.shared_state.lock().unwrap().metal_layer = Some(options);
+    unsafe { util::set_metal_layer_sync(*self.ns_window, *self.ns_view, options) as *mut _ }
+  }
+
+  #[inline]
+  fn set_accessibility_provider(&self, provider: Option<Box<dyn AccessibilityProviderMacOS>>) {
+    if !util::is_main_thread() {
+      panic!("The accessibility provider can only be set on the main thread on macOS");
+    }
+    unsafe { view::set_accessibility_provider(*self.ns_view, provider) }
+  }
+
+  fn add_view_responder(&self, responder: Box<dyn Fn(*mut c_void) -> bool>) {
+    if !util::is_main_thread() {
+      panic!("The view responders can only be added on the main thread on macOS");
+    }
+    unsafe { view::add_responder(*self.ns_view, responder) }
+  }
+
+  fn clear_view_responders(&self) {
+    if !util::is_main_thread() {
+      panic!("The view responders can only be cleared on the main thread on macOS");
+    }
+    unsafe { view::clear_responders(*self.ns_view) }
+  }
 }
 
 impl Drop for UnownedWindow {
   fn drop(&mut self) {
     trace!("Dropping `UnownedWindow` ({:?})", self as *mut _);
+    // Never leave the cursor frozen behind a closed window.
+    if self.shared_state.lock().unwrap().cursor_grabbed {
+      let _ = CGDisplay::associate_mouse_and_mouse_cursor_position(true);
+    }
     // Close the window if it has not yet been closed.
     if *self.ns_window != nil {
       unsafe { util::close_async(self.ns_window.clone()) };
@@ -1630,6 +2476,26 @@ impl Drop for UnownedWindow {
   }
 }
 
+unsafe fn set_background_color(ns_window: id, color: Option<Color>) {
+  // Transparent and shaped windows keep their clear background.
+  let opaque: BOOL = msg_send![ns_window, isOpaque];
+  if opaque == NO {
+    return;
+  }
+  let color: id = match color {
+    Some(color) => msg_send![
+      class!(NSColor),
+      colorWithSRGBRed: color.r as CGFloat / 255.0
+      green: color.g as CGFloat / 255.0
+      blue: color.b as CGFloat / 255.0
+      alpha: color.a as CGFloat / 255.0
+    ],
+    // The system color follows the appearance of the window.
+    None => msg_send![class!(NSColor), windowBackgroundColor],
+  };
+  ns_window.setBackgroundColor_(color);
+}
+
 unsafe fn set_min_inner_size<V: NSWindow + Copy>(window: V, mut min_size: LogicalSize<f64>) {
   let mut current_rect = NSWindow::frame(window);
   let content_rect = NSWindow::contentRectForFrameRect_(window, NSWindow::frame(window));
@@ -11,7 +11,7 @@ use std::{
 use cocoa::{
   appkit::{self, NSApplicationPresentationOptions, NSView, NSWindow},
   base::{id, nil},
-  foundation::{NSAutoreleasePool, NSString, NSUInteger},
+  foundation::{NSAutoreleasePool, NSInteger, NSString, NSUInteger},
 };
 use objc::{
   declare::ClassDecl,
@@ -22,11 +22,13 @@ use crate::{
   dpi::{LogicalPosition, LogicalSize},
   event::{Event, WindowEvent},
   keyboard::ModifiersState,
+  monitor::MonitorHandle as RootMonitorHandle,
+  platform::macos::SHEET_RESPONSE_ABORT,
   platform_impl::platform::{
     app_state::AppState,
     event::{EventProxy, EventWrapper},
     util::{self, IdRef},
-    view::ViewState,
+    view::{self, ViewState},
     window::{get_ns_theme, get_window_id, UnownedWindow},
   },
   window::{Fullscreen, WindowId},
@@ -53,6 +55,18 @@ pub struct WindowDelegateState {
   // Used to prevent redundant events.
   previous_scale_factor: f64,
 
+  // During `windowDidResize`, we use this to only send Maximized if the zoom state changed.
+  previous_maximized: bool,
+
+  // The monitor last reported through `MonitorChanged`.
+  previous_monitor: Option<RootMonitorHandle>,
+
+  // The visibility last reported through `VisibilityChanged`.
+  previous_visible: bool,
+
+  // The occlusion last reported through `Occluded`.
+  previous_occluded: bool,
+
   // Used to prevent resized events from being fired
   // when we are using our workaround in the `is_zoomed` function.
   is_checking_zoomed_in: bool,
@@ -68,6 +82,10 @@ impl WindowDelegateState {
       initial_fullscreen,
       previous_position: None,
       previous_scale_factor: scale_factor,
+      previous_maximized: false,
+      previous_monitor: screen_monitor(window),
+      previous_visible: window.is_visible(),
+      previous_occluded: false,
       is_checking_zoomed_in: false,
     };
     if (scale_factor - 1.0).abs() > f64::EPSILON {
@@ -97,12 +115,18 @@ impl WindowDelegateState {
   }
 
   pub fn emit_static_scale_factor_changed_event(&mut self) {
+    // Moving to another monitor is what usually changes the scale factor, so make sure
+    // `MonitorChanged` is delivered first.
+    self.emit_monitor_changed_event();
+
     let scale_factor = self.get_scale_factor();
     if (scale_factor - self.previous_scale_factor).abs() < f64::EPSILON {
       return;
     };
 
     self.previous_scale_factor = scale_factor;
+    // Physical size constraints cover a different number of points now.
+    self.with_window(|window| window.apply_inner_size_constraints());
     let wrapper = EventWrapper::EventProxy(EventProxy::DpiChangedProxy {
       ns_window: IdRef::retain(*self.ns_window),
       suggested_size: self.view_size(),
@@ -132,6 +156,75 @@ impl WindowDelegateState {
     }
   }
 
+  fn emit_visibility_changed_event(&mut self) {
+    let visible: BOOL = unsafe { msg_send![*self.ns_window, isVisible] };
+    let visible = visible == YES;
+    if self.previous_visible != visible {
+      self.previous_visible = visible;
+      self.emit_event(WindowEvent::VisibilityChanged(visible));
+    }
+  }
+
+  fn emit_occluded_event(&mut self) {
+    let occluded = match self.window.upgrade() {
+      Some(window) => window.is_occluded(),
+      None => return,
+    };
+    if self.previous_occluded != occluded {
+      self.previous_occluded = occluded;
+      self.emit_event(WindowEvent::Occluded(occluded));
+    }
+  }
+
+  fn emit_monitor_changed_event(&mut self) {
+    let monitor = match self
+      .window
+      .upgrade()
+      .and_then(|window| screen_monitor(&window))
+    {
+      Some(monitor) => monitor,
+      None => return,
+    };
+    if self.previous_monitor.as_ref() != Some(&monitor) {
+      self.previous_monitor = Some(monitor.clone());
+      self.emit_event(WindowEvent::MonitorChanged(monitor));
+    }
+  }
+
+  /// Whether the window is zooming toward its minimize target, see `UnownedWindow::set_visible`.
+  fn in_minimize_animation(&mut self) -> bool {
+    self
+      .with_window(|window| window.shared_state.lock().unwrap().in_minimize_animation)
+      .unwrap_or(false)
+  }
+
+  fn emit_maximized_event(&mut self) {
+    // Live resizes never zoom the window, and skipping them avoids toggling the style mask of
+    // borderless windows in `is_zoomed` on every frame.
+    let in_live_resize: BOOL = unsafe { msg_send![*self.ns_window, inLiveResize] };
+    if in_live_resize == YES {
+      return;
+    }
+    let maximized = self.with_window(|window| {
+      // Entering fullscreen resizes the window too, but doesn't change its zoom state.
+      let in_fullscreen = {
+        let shared_state = window.shared_state.lock().unwrap();
+        shared_state.fullscreen.is_some() || shared_state.in_fullscreen_transition
+      };
+      if in_fullscreen {
+        None
+      } else {
+        Some(window.is_zoomed())
+      }
+    });
+    if let Some(Some(maximized)) = maximized {
+      if maximized != self.previous_maximized {
+        self.previous_maximized = maximized;
+        self.emit_event(WindowEvent::Maximized(maximized));
+      }
+    }
+  }
+
   fn get_scale_factor(&self) -> f64 {
     (unsafe { NSWindow::backingScaleFactor(*self.ns_window) }) as f64
   }
@@ -142,6 +235,16 @@ impl WindowDelegateState {
   }
 }
 
+/// Returns the monitor of the window, or `None` if the window is offscreen.
+fn screen_monitor(window: &UnownedWindow) -> Option<RootMonitorHandle> {
+  let screen: id = unsafe { msg_send![*window.ns_window, screen] };
+  if screen == nil {
+    None
+  } else {
+    Some(window.current_monitor_inner())
+  }
+}
+
 pub fn new_delegate(window: &Arc<UnownedWindow>, initial_fullscreen: bool) -> IdRef {
   let state = WindowDelegateState::new(window, initial_fullscreen);
   unsafe {
@@ -187,6 +290,14 @@ lazy_static! {
       sel!(windowDidResize:),
       window_did_resize as extern "C" fn(&Object, Sel, id),
     );
+    decl.add_method(
+      sel!(windowWillStartLiveResize:),
+      window_will_start_live_resize as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(windowDidEndLiveResize:),
+      window_did_end_live_resize as extern "C" fn(&Object, Sel, id),
+    );
     decl.add_method(
       sel!(windowDidMove:),
       window_did_move as extern "C" fn(&Object, Sel, id),
@@ -195,6 +306,18 @@ lazy_static! {
       sel!(windowDidChangeBackingProperties:),
       window_did_change_backing_properties as extern "C" fn(&Object, Sel, id),
     );
+    decl.add_method(
+      sel!(windowDidChangeScreen:),
+      window_did_change_screen as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(windowDidChangeOcclusionState:),
+      window_did_change_occlusion_state as extern "C" fn(&Object, Sel, id),
+    );
+    decl.add_method(
+      sel!(applicationDidChangeScreenParameters:),
+      application_did_change_screen_parameters as extern "C" fn(&Object, Sel, id),
+    );
     decl.add_method(
       sel!(windowDidBecomeKey:),
       window_did_become_key as extern "C" fn(&Object, Sel, id),
@@ -301,6 +424,17 @@ extern "C" fn init_with_tao(this: &Object, _sel: Sel, state: *mut c_void) -> id
         object: nil
     ];
 
+    let notification_center: &Object = msg_send![class!(NSNotificationCenter), defaultCenter];
+    let notification_name =
+      NSString::alloc(nil).init_str("NSApplicationDidChangeScreenParametersNotification");
+    let _: () = msg_send![
+        notification_center,
+        addObserver: this
+        selector: sel!(applicationDidChangeScreenParameters:)
+        name: notification_name
+        object: nil
+    ];
+
     this
   }
 }
@@ -327,6 +461,19 @@ extern "C" fn window_should_close(this: &Object, _: Sel, _: id) -> BOOL {
 extern "C" fn window_will_close(this: &Object, _: Sel, _: id) {
   trace!("Triggered `windowWillClose:`");
   with_state(this, |state| unsafe {
+    // End the sheet presented by the window, or the window itself if it's a sheet, so that the
+    // sheet isn't left attached to a closed window.
+    let sheet: id = msg_send![*state.ns_window, attachedSheet];
+    if sheet != nil {
+      let () =
+        msg_send![*state.ns_window, endSheet: sheet returnCode: SHEET_RESPONSE_ABORT as NSInteger];
+    }
+    let parent: id = msg_send![*state.ns_window, sheetParent];
+    if parent != nil {
+      let () =
+        msg_send![parent, endSheet: *state.ns_window returnCode: SHEET_RESPONSE_ABORT as NSInteger];
+    }
+
     // `setDelegate:` retains the previous value and then autoreleases it
     let pool = NSAutoreleasePool::new(nil);
     // Since El Capitan, we need to be careful that delegate methods can't
@@ -338,30 +485,91 @@ extern "C" fn window_will_close(this: &Object, _: Sel, _: id) {
   trace!("Completed `windowWillClose:`");
 }
 
+extern "C" fn window_will_start_live_resize(this: &Object, _: Sel, _: id) {
+  trace!("Triggered `windowWillStartLiveResize:`");
+  with_state(this, |state| {
+    // Start from the current origin, so the first frame of a left or top edge drag reports it.
+    state.emit_move_event();
+    state.emit_event(WindowEvent::DragResizeStarted { resize: true });
+  });
+  trace!("Completed `windowWillStartLiveResize:`");
+}
+
+extern "C" fn window_did_end_live_resize(this: &Object, _: Sel, _: id) {
+  trace!("Triggered `windowDidEndLiveResize:`");
+  with_state(this, |state| {
+    // AppKit coalesces `windowDidResize:` per frame, so report where the drag ended.
+    state.emit_move_event();
+    state.emit_event(WindowEvent::DragResizeEnded { resize: true });
+  });
+  trace!("Completed `windowDidEndLiveResize:`");
+}
+
 extern "C" fn window_did_resize(this: &Object, _: Sel, _: id) {
   trace!("Triggered `windowDidResize:`");
   with_state(this, |state| {
-    if !state.is_checking_zoomed_in {
-      state.emit_resize_event();
+    if !state.is_checking_zoomed_in && !state.in_minimize_animation() {
+      // Dragging the left or top edge moves the window without `windowDidMove:`, so the new
+      // origin is reported here, before the size, in the same order as the other backends.
       state.emit_move_event();
+      state.emit_resize_event();
+      state.emit_maximized_event();
     }
   });
   trace!("Completed `windowDidResize:`");
 }
 
-// This won't be triggered if the move was part of a resize.
+// This won't be triggered if the move was part of a resize, see `window_did_resize`.
 extern "C" fn window_did_move(this: &Object, _: Sel, _: id) {
   trace!("Triggered `windowDidMove:`");
   with_state(this, |state| {
-    state.emit_move_event();
+    if !state.in_minimize_animation() {
+      state.emit_move_event();
+    }
   });
   trace!("Completed `windowDidMove:`");
 }
 
+extern "C" fn window_did_change_screen(this: &Object, _: Sel, _: id) {
+  trace!("Triggered `windowDidChangeScreen:`");
+  with_state(this, |state| {
+    state.emit_monitor_changed_event();
+    state.with_window(|window| window.update_frame_callbacks());
+  });
+  trace!("Completed `windowDidChangeScreen:`");
+}
+
+extern "C" fn window_did_change_occlusion_state(this: &Object, _: Sel, _: id) {
+  trace!("Triggered `windowDidChangeOcclusionState:`");
+  with_state(this, |state| {
+    state.with_window(|window| window.update_frame_callbacks());
+    // Ordering the window in or out, hiding the application and minimizing all change whether
+    // the window is occluded.
+    state.emit_visibility_changed_event();
+    state.emit_occluded_event();
+  });
+  trace!("Completed `windowDidChangeOcclusionState:`");
+}
+
+// Monitors were connected, disconnected or changed resolution, which can move the window to
+// another monitor without `windowDidChangeScreen:`.
+extern "C" fn application_did_change_screen_parameters(this: &Object, _: Sel, _: id) {
+  trace!("Triggered `applicationDidChangeScreenParameters:`");
+  with_state(this, |state| {
+    state.emit_monitor_changed_event();
+    state.with_window(|window| window.update_frame_callbacks());
+  });
+  trace!("Completed `applicationDidChangeScreenParameters:`");
+}
+
 extern "C" fn window_did_change_backing_properties(this: &Object, _: Sel, _: id) {
   trace!("Triggered `windowDidChangeBackingProperties:`");
   with_state(this, |state| {
+    // Before the event, the application may render at the new scale factor right away.
+    state.with_window(|window| window.update_metal_layer_scale());
     state.emit_static_scale_factor_changed_event();
+    // Physical rectangles of the shape cover a different part of the view.
+    unsafe { view::update_shape(*state.ns_view) };
   });
   trace!("Completed `windowDidChangeBackingProperties:`");
 }
@@ -371,6 +579,13 @@ extern "C" fn window_did_become_key(this: &Object, _: Sel, _: id) {
   with_state(this, |state| {
     // TODO: center the cursor if the window had mouse grab when it
     // lost focus
+    state.with_window(|window| {
+      // The system stops the request once the app is active, forget about it.
+      window.shared_state.lock().unwrap().user_attention_request = None;
+      window.update_secure_input();
+      let _ = window.update_cursor_grab();
+    });
+    AppState::window_became_key(WindowId(get_window_id(*state.ns_window)));
     state.emit_event(WindowEvent::Focused(true));
   });
   trace!("Completed `windowDidBecomeKey:`");
@@ -402,6 +617,10 @@ extern "C" fn window_did_resign_key(this: &Object, _: Sel, _: id) {
       state.emit_event(WindowEvent::ModifiersChanged(view_state.modifiers));
     }
 
+    state.with_window(|window| {
+      window.update_secure_input();
+      let _ = window.update_cursor_grab();
+    });
     state.emit_event(WindowEvent::Focused(false));
   });
   trace!("Completed `windowDidResignKey:`");
@@ -567,7 +786,7 @@ extern "C" fn window_did_enter_fullscreen(this: &Object, _: Sel, _: id) {
   trace!("Triggered `windowDidEnterFullscreen:`");
   with_state(this, |state| {
     state.initial_fullscreen = false;
-    state.with_window(|window| {
+    let fullscreen = state.with_window(|window| {
       trace!("Locked shared state in `window_did_enter_fullscreen`");
       let mut shared_state = window.shared_state.lock().unwrap();
       shared_state.in_fullscreen_transition = false;
@@ -575,11 +794,15 @@ extern "C" fn window_did_enter_fullscreen(this: &Object, _: Sel, _: id) {
       trace!("Unlocked shared state in `window_did_enter_fullscreen`");
       drop(shared_state);
       if let Some(target_fullscreen) = target_fullscreen {
-        window.set_fullscreen(target_fullscreen);
+        let _ = window.set_fullscreen(target_fullscreen);
       }
+      settled_fullscreen(window)
     });
     state.emit_resize_event();
     state.emit_move_event();
+    if let Some(Some(fullscreen)) = fullscreen {
+      state.emit_event(WindowEvent::FullscreenChanged(fullscreen));
+    }
   });
   trace!("Completed `windowDidEnterFullscreen:`");
 }
@@ -588,7 +811,7 @@ extern "C" fn window_did_enter_fullscreen(this: &Object, _: Sel, _: id) {
 extern "C" fn window_did_exit_fullscreen(this: &Object, _: Sel, _: id) {
   trace!("Triggered `windowDidExitFullscreen:`");
   with_state(this, |state| {
-    state.with_window(|window| {
+    let fullscreen = state.with_window(|window| {
       window.restore_state_from_fullscreen();
       trace!("Locked shared state in `window_did_exit_fullscreen`");
       let mut shared_state = window.shared_state.lock().unwrap();
@@ -597,15 +820,30 @@ extern "C" fn window_did_exit_fullscreen(this: &Object, _: Sel, _: id) {
       trace!("Unlocked shared state in `window_did_exit_fullscreen`");
       drop(shared_state);
       if let Some(target_fullscreen) = target_fullscreen {
-        window.set_fullscreen(target_fullscreen);
+        let _ = window.set_fullscreen(target_fullscreen);
       }
+      settled_fullscreen(window)
     });
     state.emit_resize_event();
     state.emit_move_event();
+    if let Some(Some(fullscreen)) = fullscreen {
+      state.emit_event(WindowEvent::FullscreenChanged(fullscreen));
+    }
   });
   trace!("Completed `windowDidExitFullscreen:`");
 }
 
+/// The fullscreen state to report through `FullscreenChanged`, or `None` if the window is still
+/// in a transition, to report once the last requested state is reached.
+fn settled_fullscreen(window: &UnownedWindow) -> Option<Option<Fullscreen>> {
+  let shared_state = window.shared_state.lock().unwrap();
+  if shared_state.in_fullscreen_transition {
+    None
+  } else {
+    Some(shared_state.fullscreen.clone())
+  }
+}
+
 /// Invoked when fail to enter fullscreen
 ///
 /// When this window launch from a fullscreen app (e.g. launch from VS Code
@@ -642,6 +880,7 @@ extern "C" fn window_did_fail_to_enter_fullscreen(this: &Object, _: Sel, _: id)
       };
     } else {
       state.with_window(|window| window.restore_state_from_fullscreen());
+      state.emit_event(WindowEvent::FullscreenChanged(None));
     }
   });
   trace!("Completed `windowDidFailToEnterFullscreen:`");
@@ -656,14 +895,18 @@ extern "C" fn effective_appearance_did_change(this: &Object, _: Sel, _: id) {
 }
 extern "C" fn effective_appearance_did_changed_on_main_thread(this: &Object, _: Sel, _: id) {
   with_state(this, |state| {
-    let theme = get_ns_theme();
-    let current_theme = state.window.upgrade().map(|w| {
+    let theme = get_ns_theme(*state.ns_window);
+    let current_theme = state.window.upgrade().and_then(|w| {
       let mut state = w.shared_state.lock().unwrap();
+      // windows with a forced theme don't follow the system theme
+      if state.preferred_theme.is_some() {
+        return None;
+      }
       let current_theme = state.current_theme;
       state.current_theme = theme;
-      current_theme
+      Some(current_theme)
     });
-    if current_theme != Some(theme) {
+    if matches!(current_theme, Some(current_theme) if current_theme != theme) {
       state.emit_event(WindowEvent::ThemeChanged(theme));
     }
   });
@@ -2,25 +2,31 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-#[cfg(target_os = "windows")]
+#[cfg(tao_headless)]
+#[path = "headless/mod.rs"]
+mod platform;
+#[cfg(all(not(tao_headless), target_os = "windows"))]
 #[path = "windows/mod.rs"]
 mod platform;
-#[cfg(any(
-  target_os = "linux",
-  target_os = "dragonfly",
-  target_os = "freebsd",
-  target_os = "netbsd",
-  target_os = "openbsd"
+#[cfg(all(
+  not(tao_headless),
+  any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  )
 ))]
 #[path = "linux/mod.rs"]
 mod platform;
-#[cfg(target_os = "macos")]
+#[cfg(all(not(tao_headless), target_os = "macos"))]
 #[path = "macos/mod.rs"]
 mod platform;
-#[cfg(target_os = "android")]
+#[cfg(all(not(tao_headless), target_os = "android"))]
 #[path = "android/mod.rs"]
 mod platform;
-#[cfg(target_os = "ios")]
+#[cfg(all(not(tao_headless), target_os = "ios"))]
 #[path = "ios/mod.rs"]
 mod platform;
 
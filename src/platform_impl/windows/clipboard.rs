@@ -0,0 +1,268 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  collections::HashMap, ffi::OsString, mem, os::windows::ffi::OsStringExt, path::PathBuf, ptr,
+  slice, sync::Mutex, thread, time::Duration,
+};
+
+use windows::Win32::{
+  Foundation::{HANDLE, HGLOBAL, HWND},
+  Graphics::Gdi::{BITMAPINFOHEADER, BITMAPV5HEADER, BI_BITFIELDS, BI_RGB},
+  System::{
+    DataExchange::{
+      AddClipboardFormatListener, CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard,
+      RemoveClipboardFormatListener, SetClipboardData,
+    },
+    Memory::{GlobalAlloc, GlobalFree, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE},
+    Ole::{CF_DIB, CF_DIBV5, CF_HDROP, CF_UNICODETEXT},
+  },
+  UI::Shell::{DragQueryFileW, HDROP},
+};
+
+use super::{
+  drag_source::{files_to_hglobal, text_to_hglobal},
+  event_loop::EventLoopWindowTarget,
+};
+use crate::{error::ExternalError, window::RgbaImage};
+
+/// `LCS_sRGB`, the color space of the images written to the clipboard.
+const LCS_SRGB: u32 = 0x7352_4742;
+
+lazy_static! {
+  /// The number of clipboards listening to the updates of every thread event target, which is
+  /// only registered as a listener once.
+  static ref LISTENERS: Mutex<HashMap<isize, usize>> = Mutex::new(HashMap::new());
+}
+
+/// The clipboard, owned by the thread event target while writing, which also receives
+/// `WM_CLIPBOARDUPDATE`.
+pub struct Clipboard {
+  hwnd: HWND,
+}
+
+impl Clipboard {
+  pub fn new<T>(window_target: &EventLoopWindowTarget<T>) -> Self {
+    let hwnd = window_target.thread_msg_target;
+    let mut listeners = LISTENERS.lock().unwrap();
+    let count = listeners.entry(hwnd.0).or_insert(0);
+    if *count == 0 {
+      if let Err(e) = unsafe { AddClipboardFormatListener(hwnd) } {
+        warn!("Failed to listen to the clipboard updates: {}", e);
+      }
+    }
+    *count += 1;
+    Self { hwnd }
+  }
+
+  /// Opens the clipboard for `f`, retrying for a while since other applications hold it for short
+  /// periods.
+  fn open<R>(&self, f: impl FnOnce() -> R) -> windows::core::Result<R> {
+    let mut attempts = 0;
+    while let Err(e) = unsafe { OpenClipboard(self.hwnd) } {
+      attempts += 1;
+      if attempts == 10 {
+        return Err(e);
+      }
+      thread::sleep(Duration::from_millis(10));
+    }
+    let result = f();
+    let _ = unsafe { CloseClipboard() };
+    Ok(result)
+  }
+
+  /// Reads the data of `format` in the clipboard with `f`.
+  fn read<R>(&self, format: u16, f: impl FnOnce(&[u8]) -> Option<R>) -> Option<R> {
+    self
+      .open(|| unsafe {
+        let handle = GetClipboardData(format as u32).ok()?;
+        let global = HGLOBAL(handle.0 as _);
+        let data = GlobalLock(global) as *const u8;
+        if data.is_null() {
+          return None;
+        }
+        let result = f(slice::from_raw_parts(data, GlobalSize(global)));
+        let _ = GlobalUnlock(global);
+        result
+      })
+      .ok()
+      .flatten()
+  }
+
+  /// Replaces the content of the clipboard with `global` of `format`, which the clipboard owns
+  /// from then on.
+  fn write(&self, format: u16, global: HGLOBAL) -> Result<(), ExternalError> {
+    let result = self.open(|| unsafe {
+      EmptyClipboard()?;
+      SetClipboardData(format as u32, HANDLE(global.0 as isize))
+    });
+    match result {
+      Ok(Ok(_)) => Ok(()),
+      Ok(Err(e)) | Err(e) => {
+        let _ = unsafe { GlobalFree(global) };
+        Err(e.into())
+      }
+    }
+  }
+
+  pub fn read_text(&self) -> Option<String> {
+    self.read(CF_UNICODETEXT.0, |data| {
+      let wide = unsafe {
+        slice::from_raw_parts(
+          data.as_ptr() as *const u16,
+          data.len() / mem::size_of::<u16>(),
+        )
+      };
+      let len = wide.iter().position(|c| *c == 0).unwrap_or(wide.len());
+      Some(String::from_utf16_lossy(&wide[..len]))
+    })
+  }
+
+  pub fn write_text(&self, text: &str) -> Result<(), ExternalError> {
+    let global = unsafe { text_to_hglobal(text)? };
+    self.write(CF_UNICODETEXT.0, global)
+  }
+
+  pub fn read_image(&self) -> Option<RgbaImage> {
+    self.read(CF_DIB.0, dib_to_rgba)
+  }
+
+  pub fn write_image(&self, image: &RgbaImage) -> Result<(), ExternalError> {
+    let global = unsafe { rgba_to_dibv5(image)? };
+    self.write(CF_DIBV5.0, global)
+  }
+
+  pub fn read_files(&self) -> Vec<PathBuf> {
+    self
+      .open(|| unsafe {
+        let handle = match GetClipboardData(CF_HDROP.0 as u32) {
+          Ok(handle) => handle,
+          Err(_) => return Vec::new(),
+        };
+        let hdrop = HDROP(handle.0);
+        let count = DragQueryFileW(hdrop, u32::MAX, None);
+        (0..count)
+          .map(|i| {
+            let len = DragQueryFileW(hdrop, i, None) as usize;
+            let mut path = vec![0; len + 1];
+            DragQueryFileW(hdrop, i, Some(&mut path));
+            OsString::from_wide(&path[..len]).into()
+          })
+          .collect()
+      })
+      .unwrap_or_default()
+  }
+
+  pub fn write_files(&self, files: &[PathBuf]) -> Result<(), ExternalError> {
+    let global = unsafe { files_to_hglobal(files)? };
+    self.write(CF_HDROP.0, global)
+  }
+}
+
+impl Drop for Clipboard {
+  fn drop(&mut self) {
+    let mut listeners = LISTENERS.lock().unwrap();
+    if let Some(count) = listeners.get_mut(&self.hwnd.0) {
+      *count -= 1;
+      if *count == 0 {
+        listeners.remove(&self.hwnd.0);
+        let _ = unsafe { RemoveClipboardFormatListener(self.hwnd) };
+      }
+    }
+  }
+}
+
+/// Converts a 24 or 32 bits per pixel `CF_DIB`, a `BITMAPINFOHEADER` followed by the pixels.
+fn dib_to_rgba(dib: &[u8]) -> Option<RgbaImage> {
+  if dib.len() < mem::size_of::<BITMAPINFOHEADER>() {
+    return None;
+  }
+  let header = unsafe { ptr::read_unaligned(dib.as_ptr() as *const BITMAPINFOHEADER) };
+  let bytes_per_pixel = match header.biBitCount {
+    24 => 3,
+    32 => 4,
+    _ => return None,
+  };
+  // The color masks follow a `BITMAPINFOHEADER`, newer headers include them. Only the usual
+  // masks, matching `BI_RGB`, are supported.
+  let mut offset = header.biSize as usize;
+  if header.biCompression == BI_BITFIELDS.0 {
+    if header.biSize as usize == mem::size_of::<BITMAPINFOHEADER>() {
+      offset += 3 * mem::size_of::<u32>();
+    }
+    let masks = dib.get(40..52)?;
+    // 0x00ff0000, 0x0000ff00 and 0x000000ff in little endian.
+    if masks != [0, 0, 0xff, 0, 0, 0xff, 0, 0, 0xff, 0, 0, 0][..] {
+      return None;
+    }
+  } else if header.biCompression != BI_RGB.0 {
+    return None;
+  }
+
+  let width = header.biWidth.unsigned_abs() as usize;
+  let height = header.biHeight.unsigned_abs() as usize;
+  let stride = (width * bytes_per_pixel + 3) & !3;
+  let pixels = dib.get(offset..offset + stride * height)?;
+  // Many applications leave the alpha channel of 32 bits images empty.
+  let has_alpha = bytes_per_pixel == 4 && pixels.chunks_exact(4).any(|pixel| pixel[3] != 0);
+
+  let mut data = Vec::with_capacity(width * height * 4);
+  for y in 0..height {
+    // Positive heights are for rows ordered from the bottom.
+    let row = if header.biHeight > 0 {
+      height - 1 - y
+    } else {
+      y
+    };
+    let row = &pixels[row * stride..row * stride + width * bytes_per_pixel];
+    for pixel in row.chunks_exact(bytes_per_pixel) {
+      let alpha = if has_alpha { pixel[3] } else { u8::MAX };
+      data.extend_from_slice(&[pixel[2], pixel[1], pixel[0], alpha]);
+    }
+  }
+  Some(RgbaImage {
+    width: width as u32,
+    height: height as u32,
+    stride: width as u32 * 4,
+    data,
+  })
+}
+
+/// Lays out a `CF_DIBV5` with an alpha channel, from which Windows synthesizes the other bitmap
+/// formats.
+unsafe fn rgba_to_dibv5(image: &RgbaImage) -> windows::core::Result<HGLOBAL> {
+  let header = BITMAPV5HEADER {
+    bV5Size: mem::size_of::<BITMAPV5HEADER>() as u32,
+    bV5Width: image.width as i32,
+    // Positive for rows ordered from the bottom, which most applications expect.
+    bV5Height: image.height as i32,
+    bV5Planes: 1,
+    bV5BitCount: 32,
+    bV5Compression: BI_BITFIELDS,
+    bV5SizeImage: image.width * image.height * 4,
+    bV5RedMask: 0x00ff_0000,
+    bV5GreenMask: 0x0000_ff00,
+    bV5BlueMask: 0x0000_00ff,
+    bV5AlphaMask: 0xff00_0000,
+    bV5CSType: LCS_SRGB,
+    ..Default::default()
+  };
+  let header_size = mem::size_of::<BITMAPV5HEADER>();
+  let global = GlobalAlloc(GMEM_MOVEABLE, header_size + header.bV5SizeImage as usize)?;
+  let ptr = GlobalLock(global) as *mut u8;
+  ptr::write_unaligned(ptr as *mut BITMAPV5HEADER, header);
+  let mut pixel = ptr.add(header_size);
+  for row in image
+    .data
+    .chunks(image.stride as usize)
+    .take(image.height as usize)
+    .rev()
+  {
+    for rgba in row[..image.width as usize * 4].chunks_exact(4) {
+      ptr::copy_nonoverlapping([rgba[2], rgba[1], rgba[0], rgba[3]].as_ptr(), pixel, 4);
+      pixel = pixel.add(4);
+    }
+  }
+  let _ = GlobalUnlock(global);
+  Ok(global)
+}
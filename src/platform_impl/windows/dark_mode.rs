@@ -150,6 +150,15 @@ pub fn try_window_theme(hwnd: HWND, preferred_theme: Option<Theme>) -> Theme {
   }
 }
 
+/// Returns the theme of the system, the one windows without a forced theme pick.
+pub fn system_theme() -> Theme {
+  if *DARK_MODE_SUPPORTED && should_use_dark_mode() {
+    Theme::Dark
+  } else {
+    Theme::Light
+  }
+}
+
 fn allow_dark_mode_for_window(hwnd: HWND, is_dark_mode: bool) {
   const UXTHEME_ALLOWDARKMODEFORWINDOW_ORDINAL: u16 = 133;
   type AllowDarkModeForWindow = unsafe extern "system" fn(HWND, bool) -> bool;
@@ -234,7 +243,7 @@ fn should_apps_use_dark_mode() -> bool {
     .unwrap_or(false)
 }
 
-fn is_high_contrast() -> bool {
+pub(crate) fn is_high_contrast() -> bool {
   const HCF_HIGHCONTRASTON: u32 = 1;
 
   let mut hc = HIGHCONTRASTA {
@@ -12,27 +12,47 @@ use windows::Win32::{
   UI::{HiDpi::*, WindowsAndMessaging::*},
 };
 
-use crate::platform_impl::platform::util::{
-  ENABLE_NON_CLIENT_DPI_SCALING, GET_DPI_FOR_MONITOR, GET_DPI_FOR_WINDOW, SET_PROCESS_DPI_AWARE,
-  SET_PROCESS_DPI_AWARENESS, SET_PROCESS_DPI_AWARENESS_CONTEXT,
+use crate::{
+  platform::windows::DpiAwareness,
+  platform_impl::platform::util::{
+    ENABLE_NON_CLIENT_DPI_SCALING, GET_DPI_FOR_MONITOR, GET_DPI_FOR_WINDOW, SET_PROCESS_DPI_AWARE,
+    SET_PROCESS_DPI_AWARENESS, SET_PROCESS_DPI_AWARENESS_CONTEXT,
+  },
 };
 
-pub fn become_dpi_aware() {
+pub fn become_dpi_aware(awareness: DpiAwareness) {
   static ENABLE_DPI_AWARENESS: Once = Once::new();
+  if awareness == DpiAwareness::Unaware {
+    return;
+  }
   ENABLE_DPI_AWARENESS.call_once(|| {
     unsafe {
       if let Some(SetProcessDpiAwarenessContext) = *SET_PROCESS_DPI_AWARENESS_CONTEXT {
         // We are on Windows 10 Anniversary Update (1607) or later.
-        if !SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2).as_bool() {
-          // V2 only works with Windows 10 Creators Update (1703). Try using the older
-          // V1 if we can't set V2.
-          SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE);
+        match awareness {
+          DpiAwareness::PerMonitorV2 => {
+            if !SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2).as_bool()
+            {
+              // V2 only works with Windows 10 Creators Update (1703). Try using the older
+              // V1 if we can't set V2.
+              SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE);
+            }
+          }
+          DpiAwareness::PerMonitor => {
+            SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE);
+          }
+          _ => {
+            SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_SYSTEM_AWARE);
+          }
         }
       } else if let Some(SetProcessDpiAwareness) = *SET_PROCESS_DPI_AWARENESS {
         // We are on Windows 8.1 or later.
-        let _ = SetProcessDpiAwareness(PROCESS_PER_MONITOR_DPI_AWARE);
+        let _ = SetProcessDpiAwareness(match awareness {
+          DpiAwareness::System => PROCESS_SYSTEM_DPI_AWARE,
+          _ => PROCESS_PER_MONITOR_DPI_AWARE,
+        });
       } else if let Some(SetProcessDPIAware) = *SET_PROCESS_DPI_AWARE {
-        // We are on Vista or later.
+        // We are on Vista or later, which only knows the system awareness.
         SetProcessDPIAware();
       }
     }
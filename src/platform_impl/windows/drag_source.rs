@@ -0,0 +1,211 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{mem, os::windows::ffi::OsStrExt, path::PathBuf, ptr};
+
+use windows::{
+  core::HRESULT,
+  Win32::{
+    Foundation::{
+      BOOL, COLORREF, DRAGDROP_S_CANCEL, DRAGDROP_S_DROP, DRAGDROP_S_USEDEFAULTCURSORS, HANDLE,
+      HGLOBAL, POINT, SIZE, S_OK,
+    },
+    Graphics::Gdi::{
+      CreateDIBSection, DeleteObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HDC,
+    },
+    System::{
+      Com::{
+        CoCreateInstance, IDataObject, CLSCTX_INPROC_SERVER, DVASPECT_CONTENT, FORMATETC,
+        STGMEDIUM, STGMEDIUM_0, TYMED_HGLOBAL,
+      },
+      Memory::{GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE, GMEM_ZEROINIT},
+      Ole::{
+        DoDragDrop, IDropSource, IDropSource_Impl, OleInitialize, CF_HDROP, CF_UNICODETEXT,
+        DROPEFFECT, DROPEFFECT_COPY, DROPEFFECT_MOVE,
+      },
+      SystemServices::{MK_LBUTTON, MK_MBUTTON, MK_RBUTTON, MODIFIERKEYS_FLAGS},
+    },
+    UI::Shell::{
+      CLSID_DragDropHelper, IDragSourceHelper, SHCreateDataObject, DROPFILES, SHDRAGIMAGE,
+    },
+  },
+};
+
+use windows_implement::implement;
+
+use crate::window::{DragData, DragImage, DragOperation, DragResult};
+
+#[implement(IDropSource)]
+struct DropSource;
+
+#[allow(non_snake_case)]
+impl IDropSource_Impl for DropSource {
+  fn QueryContinueDrag(&self, fEscapePressed: BOOL, grfKeyState: MODIFIERKEYS_FLAGS) -> HRESULT {
+    if fEscapePressed.as_bool() {
+      DRAGDROP_S_CANCEL
+    } else if (grfKeyState & (MK_LBUTTON | MK_MBUTTON | MK_RBUTTON)).0 == 0 {
+      DRAGDROP_S_DROP
+    } else {
+      S_OK
+    }
+  }
+
+  fn GiveFeedback(&self, _dwEffect: DROPEFFECT) -> HRESULT {
+    DRAGDROP_S_USEDEFAULTCURSORS
+  }
+}
+
+/// Runs the modal drag loop of `data`, returning once it's dropped or cancelled.
+pub(crate) unsafe fn do_drag_drop(
+  data: &DragData,
+  image: &DragImage,
+) -> windows::core::Result<DragResult> {
+  // It is ok if OLE was already initialized, e.g. for the drop handler of the window.
+  let _ = OleInitialize(None);
+
+  // The shell data object takes any format, including the ones of the drag image helper.
+  let data_object: IDataObject = SHCreateDataObject(None, None, None::<&IDataObject>)?;
+  let (format, global) = match data {
+    DragData::Files(paths) => (CF_HDROP, files_to_hglobal(paths)?),
+    DragData::Text(text) => (CF_UNICODETEXT, text_to_hglobal(text)?),
+  };
+  let format = FORMATETC {
+    cfFormat: format.0 as u16,
+    ptd: ptr::null_mut(),
+    dwAspect: DVASPECT_CONTENT.0 as u32,
+    lindex: -1,
+    tymed: TYMED_HGLOBAL.0 as u32,
+  };
+  let medium = STGMEDIUM {
+    tymed: TYMED_HGLOBAL.0 as u32,
+    u: STGMEDIUM_0 { hGlobal: global },
+    pUnkForRelease: mem::ManuallyDrop::new(None),
+  };
+  // The data object owns the memory once it's set.
+  if let Err(e) = data_object.SetData(&format, &medium, BOOL::from(true)) {
+    let _ = GlobalFree(global);
+    return Err(e);
+  }
+
+  // Without the helper, the drag still works with the default cursors only.
+  if let Err(e) = set_drag_image(&data_object, image) {
+    warn!("Failed to set the drag image: {}", e);
+  }
+
+  let source: IDropSource = DropSource.into();
+  let mut effect = DROPEFFECT::default();
+  let result = DoDragDrop(
+    &data_object,
+    &source,
+    DROPEFFECT_COPY | DROPEFFECT_MOVE,
+    &mut effect,
+  );
+  Ok(
+    if result == DRAGDROP_S_DROP && effect.0 & DROPEFFECT_MOVE.0 != 0 {
+      DragResult::Dropped(DragOperation::Move)
+    } else if result == DRAGDROP_S_DROP && effect.0 & DROPEFFECT_COPY.0 != 0 {
+      DragResult::Dropped(DragOperation::Copy)
+    } else {
+      DragResult::Cancelled
+    },
+  )
+}
+
+unsafe fn set_drag_image(
+  data_object: &IDataObject,
+  image: &DragImage,
+) -> windows::core::Result<()> {
+  let helper: IDragSourceHelper =
+    CoCreateInstance(&CLSID_DragDropHelper, None, CLSCTX_INPROC_SERVER)?;
+  let (width, height) = (image.image.width as i32, image.image.height as i32);
+  let info = BITMAPINFO {
+    bmiHeader: BITMAPINFOHEADER {
+      biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+      biWidth: width,
+      // Negative for rows ordered from the top.
+      biHeight: -height,
+      biPlanes: 1,
+      biBitCount: 32,
+      biCompression: BI_RGB.0,
+      ..Default::default()
+    },
+    ..Default::default()
+  };
+  let mut bits = ptr::null_mut();
+  let bitmap = CreateDIBSection(
+    HDC::default(),
+    &info,
+    DIB_RGB_COLORS,
+    &mut bits,
+    HANDLE::default(),
+    0,
+  )?;
+  // The helper expects premultiplied BGRA.
+  let bgra = std::slice::from_raw_parts_mut(bits as *mut u8, image.image.rgba.len());
+  for (dst, src) in bgra
+    .chunks_exact_mut(4)
+    .zip(image.image.rgba.chunks_exact(4))
+  {
+    let alpha = src[3] as u32;
+    dst[0] = (src[2] as u32 * alpha / 255) as u8;
+    dst[1] = (src[1] as u32 * alpha / 255) as u8;
+    dst[2] = (src[0] as u32 * alpha / 255) as u8;
+    dst[3] = src[3];
+  }
+
+  let drag_image = SHDRAGIMAGE {
+    sizeDragImage: SIZE {
+      cx: width,
+      cy: height,
+    },
+    ptOffset: POINT {
+      x: image.hotspot.x as i32,
+      y: image.hotspot.y as i32,
+    },
+    hbmpDragImage: bitmap,
+    // CLR_NONE, the image is transparent through its alpha channel.
+    crColorKey: COLORREF(0xFFFF_FFFF),
+  };
+  // The helper owns the bitmap only if it succeeds.
+  helper
+    .InitializeFromBitmap(&drag_image, data_object)
+    .map_err(|e| {
+      DeleteObject(bitmap);
+      e
+    })
+}
+
+/// Lays out a `DROPFILES` followed by the double-null-terminated list of paths.
+pub(super) unsafe fn files_to_hglobal(paths: &[PathBuf]) -> windows::core::Result<HGLOBAL> {
+  let mut wide: Vec<u16> = Vec::new();
+  for path in paths {
+    wide.extend(path.as_os_str().encode_wide());
+    wide.push(0);
+  }
+  wide.push(0);
+
+  let header = mem::size_of::<DROPFILES>();
+  let global = GlobalAlloc(
+    GMEM_MOVEABLE | GMEM_ZEROINIT,
+    header + wide.len() * mem::size_of::<u16>(),
+  )?;
+  let ptr = GlobalLock(global) as *mut u8;
+  *(ptr as *mut DROPFILES) = DROPFILES {
+    pFiles: header as u32,
+    pt: POINT::default(),
+    fNC: false.into(),
+    fWide: true.into(),
+  };
+  ptr::copy_nonoverlapping(wide.as_ptr(), ptr.add(header) as *mut u16, wide.len());
+  let _ = GlobalUnlock(global);
+  Ok(global)
+}
+
+pub(super) unsafe fn text_to_hglobal(text: &str) -> windows::core::Result<HGLOBAL> {
+  let wide: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+  let global = GlobalAlloc(GMEM_MOVEABLE, wide.len() * mem::size_of::<u16>())?;
+  let ptr = GlobalLock(global) as *mut u16;
+  ptr::copy_nonoverlapping(wide.as_ptr(), ptr, wide.len());
+  let _ = GlobalUnlock(global);
+  Ok(global)
+}
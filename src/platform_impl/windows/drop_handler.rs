@@ -2,53 +2,133 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{cell::UnsafeCell, ffi::OsString, os::windows::ffi::OsStringExt, path::PathBuf, ptr};
-
-use windows::Win32::{
-  Foundation::{self as win32f, HWND, POINTL},
-  System::{
-    Com::{IDataObject, DVASPECT_CONTENT, FORMATETC, TYMED_HGLOBAL},
-    Ole::{IDropTarget, IDropTarget_Impl, CF_HDROP, DROPEFFECT, DROPEFFECT_COPY, DROPEFFECT_NONE},
-    SystemServices::MODIFIERKEYS_FLAGS,
+use std::{
+  cell::{Cell, RefCell},
+  ffi::OsString,
+  io::{self, Cursor, Read},
+  mem,
+  os::windows::ffi::OsStringExt,
+  path::PathBuf,
+  ptr, slice,
+  sync::Arc,
+};
+
+use parking_lot::Mutex;
+use windows::{
+  core::{w, AgileReference, ComInterface},
+  Win32::{
+    Foundation::{self as win32f, BOOL, HGLOBAL, HWND, POINT, POINTL, S_OK},
+    Graphics::Gdi::ScreenToClient,
+    System::{
+      Com::{
+        CoCreateInstance, IBindCtx, IDataObject, IStream,
+        StructuredStorage::{
+          CreateILockBytesOnHGlobal, GetHGlobalFromILockBytes, IStorage,
+          StgCreateDocfileOnILockBytes,
+        },
+        CLSCTX_INPROC_SERVER, DVASPECT_CONTENT, FORMATETC, STATFLAG_NONAME, STATSTG, STGC_DEFAULT,
+        STGMEDIUM, STGM_CREATE, STGM_READWRITE, STGM_SHARE_EXCLUSIVE, TYMED_HGLOBAL,
+        TYMED_ISTORAGE, TYMED_ISTREAM,
+      },
+      DataExchange::RegisterClipboardFormatW,
+      Memory::{GlobalLock, GlobalSize, GlobalUnlock},
+      Ole::{
+        IDropTarget, IDropTarget_Impl, ReleaseStgMedium, CF_HDROP, CF_UNICODETEXT, DROPEFFECT,
+        DROPEFFECT_COPY, DROPEFFECT_LINK, DROPEFFECT_MOVE, DROPEFFECT_NONE,
+      },
+      SystemServices::MODIFIERKEYS_FLAGS,
+    },
+    UI::Shell::{
+      CLSID_DragDropHelper, DragFinish, DragQueryFileW, IDataObjectAsyncCapability,
+      IDropTargetHelper, CFSTR_FILECONTENTS, CFSTR_FILEDESCRIPTORW, FILEDESCRIPTORW, HDROP,
+    },
   },
-  UI::Shell::{DragFinish, DragQueryFileW, HDROP},
 };
 
 use windows_implement::implement;
 
-use crate::platform_impl::platform::WindowId;
+use crate::{
+  dpi::PhysicalPosition,
+  event::{Event, WindowEvent},
+  platform::windows::DropEffect,
+  platform_impl::platform::{window_state::WindowState, WindowId},
+  window::{DropData, DropFormat, VirtualFileContents, WindowId as SuperWindowId},
+};
+
+/// `FD_ATTRIBUTES`, `dwFileAttributes` is set.
+const FD_ATTRIBUTES: u32 = 0x4;
+/// `FD_FILESIZE`, `nFileSizeHigh` and `nFileSizeLow` are set.
+const FD_FILESIZE: u32 = 0x40;
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
 
-use crate::{event::Event, window::WindowId as SuperWindowId};
+/// The registered clipboard formats read from the dragged data.
+struct ClipboardFormats {
+  html: u16,
+  file_descriptor: u16,
+  file_contents: u16,
+}
+
+impl ClipboardFormats {
+  fn register() -> Self {
+    unsafe {
+      Self {
+        html: RegisterClipboardFormatW(w!("HTML Format")) as u16,
+        file_descriptor: RegisterClipboardFormatW(CFSTR_FILEDESCRIPTORW) as u16,
+        file_contents: RegisterClipboardFormatW(CFSTR_FILECONTENTS) as u16,
+      }
+    }
+  }
+}
 
 #[implement(IDropTarget)]
 pub struct FileDropHandler {
   window: HWND,
+  window_state: Arc<Mutex<WindowState>>,
   send_event: Box<dyn Fn(Event<'static, ()>)>,
-  cursor_effect: UnsafeCell<DROPEFFECT>,
-  hovered_is_valid: UnsafeCell<bool>, /* If the currently hovered item is not valid there must not be any `HoveredFileCancelled` emitted */
+  /// Shows the drag image of the source over the window.
+  helper: Option<IDropTargetHelper>,
+  clipboard_formats: ClipboardFormats,
+  /// The formats of the data dragged over the window, empty if it can't be dropped.
+  formats: RefCell<Vec<DropFormat>>,
+  /// The position of the last `DragOver` event, in screen coordinates.
+  position: Cell<Option<(i32, i32)>>,
+  hovered_is_valid: Cell<bool>, /* If the currently hovered item is not valid there must not be any `HoveredFileCancelled` emitted */
 }
 
 impl FileDropHandler {
-  pub fn new(window: HWND, send_event: Box<dyn Fn(Event<'static, ()>)>) -> FileDropHandler {
+  pub fn new(
+    window: HWND,
+    window_state: Arc<Mutex<WindowState>>,
+    send_event: Box<dyn Fn(Event<'static, ()>)>,
+  ) -> FileDropHandler {
+    // Without the helper, the drag still works without the image of the source.
+    let helper = unsafe { CoCreateInstance(&CLSID_DragDropHelper, None, CLSCTX_INPROC_SERVER) }
+      .map_err(|e| warn!("Failed to create the drop target helper: {}", e))
+      .ok();
     Self {
       window,
+      window_state,
       send_event,
-      cursor_effect: DROPEFFECT_NONE.into(),
+      helper,
+      clipboard_formats: ClipboardFormats::register(),
+      formats: Default::default(),
+      position: Cell::new(None),
       hovered_is_valid: false.into(),
     }
   }
 
+  fn send_window_event(&self, event: WindowEvent<'static>) {
+    (self.send_event)(Event::WindowEvent {
+      window_id: SuperWindowId(WindowId(self.window.0)),
+      event,
+    });
+  }
+
   unsafe fn iterate_filenames<F>(data_obj: Option<&IDataObject>, callback: F) -> Option<HDROP>
   where
     F: Fn(PathBuf),
   {
-    let drop_format = FORMATETC {
-      cfFormat: CF_HDROP.0 as u16,
-      ptd: ptr::null_mut(),
-      dwAspect: DVASPECT_CONTENT.0 as u32,
-      lindex: -1,
-      tymed: TYMED_HGLOBAL.0 as u32,
-    };
+    let drop_format = format_etc(CF_HDROP.0 as u16, -1, TYMED_HGLOBAL.0);
 
     match data_obj
       .as_ref()
@@ -96,6 +176,383 @@ impl FileDropHandler {
       }
     }
   }
+
+  /// The formats `data_obj` holds, with the files with a path preferred to the virtual ones,
+  /// since the shell offers both for some files.
+  unsafe fn held_formats(&self, data_obj: &IDataObject) -> Vec<DropFormat> {
+    let has =
+      |format: u16, tymed: i32| data_obj.QueryGetData(&format_etc(format, -1, tymed)) == S_OK;
+    let files = has(CF_HDROP.0 as u16, TYMED_HGLOBAL.0);
+    let virtual_files = !files && has(self.clipboard_formats.file_descriptor, TYMED_HGLOBAL.0);
+    [
+      (DropFormat::Files, files),
+      (
+        DropFormat::Text,
+        has(CF_UNICODETEXT.0 as u16, TYMED_HGLOBAL.0 | TYMED_ISTREAM.0),
+      ),
+      (
+        DropFormat::Html,
+        has(
+          self.clipboard_formats.html,
+          TYMED_HGLOBAL.0 | TYMED_ISTREAM.0,
+        ),
+      ),
+      (DropFormat::VirtualFiles, virtual_files),
+    ]
+    .into_iter()
+    .filter_map(|(format, held)| held.then(|| format))
+    .collect()
+  }
+
+  /// Sends a `DragOver` event if the drag moved, `point` being in screen coordinates.
+  unsafe fn drag_over(&self, point: &POINTL) {
+    if self.position.replace(Some((point.x, point.y))) == Some((point.x, point.y)) {
+      return;
+    }
+    let mut position = POINT {
+      x: point.x,
+      y: point.y,
+    };
+    ScreenToClient(self.window, &mut position);
+    self.send_window_event(WindowEvent::DragOver {
+      position: PhysicalPosition::new(position.x as f64, position.y as f64),
+      formats: self.formats.borrow().clone(),
+    });
+  }
+
+  /// The effect of a drop, chosen with `set_drop_effect` among the `allowed` ones.
+  fn effect(&self, allowed: DROPEFFECT) -> DROPEFFECT {
+    if self.formats.borrow().is_empty() {
+      return DROPEFFECT_NONE;
+    }
+    let effect = match self.window_state.lock().drop_effect {
+      DropEffect::None => DROPEFFECT_NONE,
+      DropEffect::Copy => DROPEFFECT_COPY,
+      DropEffect::Move => DROPEFFECT_MOVE,
+      DropEffect::Link => DROPEFFECT_LINK,
+    };
+    if allowed.0 & effect.0 == effect.0 {
+      effect
+    } else {
+      DROPEFFECT_NONE
+    }
+  }
+
+  /// Ends the drag over the window, without a drop.
+  fn leave(&self) {
+    use crate::event::WindowEvent::HoveredFileCancelled;
+    if self.hovered_is_valid.replace(false) {
+      self.send_window_event(HoveredFileCancelled);
+    }
+    self.position.set(None);
+    self.formats.borrow_mut().clear();
+    self.send_window_event(WindowEvent::DragLeft);
+  }
+
+  /// Reads the data of `data_obj` in the formats other than the files with a path, and gives
+  /// the virtual files to be read later.
+  unsafe fn dropped_data(&self, data_obj: &IDataObject, effect: DROPEFFECT) -> Vec<DropData> {
+    let formats = self.formats.borrow();
+    let mut data = Vec::new();
+    if formats.contains(&DropFormat::Text) {
+      if let Some(bytes) = get_data(data_obj, CF_UNICODETEXT.0 as u16, -1) {
+        let wide = bytes
+          .chunks_exact(2)
+          .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]));
+        data.push(DropData::Text(utf16_to_string(wide)));
+      }
+    }
+    if formats.contains(&DropFormat::Html) {
+      if let Some(bytes) = get_data(data_obj, self.clipboard_formats.html, -1) {
+        let html = String::from_utf8_lossy(&bytes);
+        data.push(DropData::Html(html_fragment(&html).to_owned()));
+      }
+    }
+    if formats.contains(&DropFormat::VirtualFiles) {
+      data.extend(self.virtual_files(data_obj, effect));
+    }
+    data
+  }
+
+  /// The files described by `CFSTR_FILEDESCRIPTORW`, whose contents are read later from
+  /// `CFSTR_FILECONTENTS` at their index.
+  unsafe fn virtual_files(&self, data_obj: &IDataObject, effect: DROPEFFECT) -> Vec<DropData> {
+    let descriptors = match get_data(data_obj, self.clipboard_formats.file_descriptor, -1) {
+      Some(descriptors) => file_descriptors(&descriptors),
+      None => return Vec::new(),
+    };
+    let files: Vec<_> = descriptors
+      .into_iter()
+      .enumerate()
+      .filter(|(_, descriptor)| !is_directory(descriptor))
+      .collect();
+    if files.is_empty() {
+      return Vec::new();
+    }
+    let dropped = match DroppedObject::new(data_obj, self.clipboard_formats.file_contents, effect) {
+      Ok(dropped) => Arc::new(dropped),
+      Err(e) => {
+        warn!("Failed to keep the dropped virtual files: {}", e);
+        return Vec::new();
+      }
+    };
+    files
+      .into_iter()
+      .map(|(index, descriptor)| {
+        let dropped = dropped.clone();
+        let size = file_size(&descriptor);
+        DropData::VirtualFile {
+          name: utf16_to_string(descriptor.cFileName),
+          contents: VirtualFileContents::new(move || unsafe { dropped.open(index, size) }),
+        }
+      })
+      .collect()
+  }
+}
+
+/// The data object of a drop with virtual files, kept until the contents of all of them are
+/// dropped.
+struct DroppedObject {
+  data_obj: AgileReference<IDataObject>,
+  /// Set when the source lets the drop end before the files are read, see `Drop`.
+  async_capability: Option<AgileReference<IDataObjectAsyncCapability>>,
+  file_contents: u16,
+  effect: DROPEFFECT,
+}
+
+impl DroppedObject {
+  unsafe fn new(
+    data_obj: &IDataObject,
+    file_contents: u16,
+    effect: DROPEFFECT,
+  ) -> windows::core::Result<Self> {
+    let agile_data_obj = AgileReference::new(data_obj)?;
+    // A source supporting asynchronous extraction, like Outlook, lets `DoDragDrop` return while
+    // the files are read, until `EndOperation`.
+    let async_capability = data_obj
+      .cast::<IDataObjectAsyncCapability>()
+      .ok()
+      .filter(|capability| capability.GetAsyncMode().map_or(false, BOOL::as_bool))
+      .and_then(|capability| {
+        capability.StartOperation(None::<&IBindCtx>).ok()?;
+        AgileReference::new(&capability).ok()
+      });
+    Ok(Self {
+      data_obj: agile_data_obj,
+      async_capability,
+      file_contents,
+      effect,
+    })
+  }
+
+  /// Opens the contents of the file at `index`, of `size` bytes if the source gives it.
+  unsafe fn open(&self, index: usize, size: Option<u64>) -> io::Result<Box<dyn Read>> {
+    let data_obj = self.data_obj.resolve().map_err(io_error)?;
+    let mut medium = data_obj
+      .GetData(&format_etc(
+        self.file_contents,
+        index as i32,
+        TYMED_ISTREAM.0 | TYMED_HGLOBAL.0 | TYMED_ISTORAGE.0,
+      ))
+      .map_err(io_error)?;
+    let reader: io::Result<Box<dyn Read>> = if medium.tymed == TYMED_ISTREAM.0 as u32 {
+      // Kept past the release of the medium.
+      match (*medium.u.pstm).clone() {
+        Some(stream) => Ok(Box::new(StreamReader(stream))),
+        None => Err(io::Error::new(io::ErrorKind::Other, "the stream is null")),
+      }
+    } else if medium.tymed == TYMED_HGLOBAL.0 as u32 {
+      global_bytes(medium.u.hGlobal)
+        .map(|bytes| Box::new(Cursor::new(bytes)) as Box<dyn Read>)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "the memory can't be locked"))
+    } else if medium.tymed == TYMED_ISTORAGE.0 as u32 {
+      match &*medium.u.pstg {
+        Some(storage) => {
+          compound_file(storage).map(|bytes| Box::new(Cursor::new(bytes)) as Box<dyn Read>)
+        }
+        None => Err(io::Error::new(io::ErrorKind::Other, "the storage is null")),
+      }
+    } else {
+      Err(io::Error::new(
+        io::ErrorKind::Other,
+        "the contents aren't in a known medium",
+      ))
+    };
+    ReleaseStgMedium(&mut medium);
+    // A stream or memory can be larger than the file.
+    reader.map(|reader| match size {
+      Some(size) => Box::new(reader.take(size)) as Box<dyn Read>,
+      None => reader,
+    })
+  }
+}
+
+impl Drop for DroppedObject {
+  fn drop(&mut self) {
+    if let Some(capability) = &self.async_capability {
+      match capability.resolve() {
+        Ok(capability) => unsafe {
+          let _ = capability.EndOperation(S_OK, None::<&IBindCtx>, self.effect.0);
+        },
+        Err(e) => warn!("Failed to end the asynchronous drop: {}", e),
+      }
+    }
+  }
+}
+
+/// Reads a file given as a stream.
+struct StreamReader(IStream);
+
+impl Read for StreamReader {
+  fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    unsafe {
+      self
+        .0
+        .Read(
+          buffer.as_mut_ptr() as _,
+          buffer.len().min(u32::MAX as usize) as u32,
+          Some(&mut read as *mut u32),
+        )
+        .ok()
+        .map_err(io_error)?;
+    }
+    Ok(read as usize)
+  }
+}
+
+fn io_error(error: windows::core::Error) -> io::Error {
+  io::Error::new(io::ErrorKind::Other, error)
+}
+
+/// Saves `storage`, like an Outlook message, as the bytes of a compound file.
+unsafe fn compound_file(storage: &IStorage) -> io::Result<Vec<u8>> {
+  let lock_bytes = CreateILockBytesOnHGlobal(HGLOBAL::default(), true).map_err(io_error)?;
+  let copy = StgCreateDocfileOnILockBytes(
+    &lock_bytes,
+    STGM_CREATE | STGM_READWRITE | STGM_SHARE_EXCLUSIVE,
+    0,
+  )
+  .map_err(io_error)?;
+  storage.CopyTo(None, ptr::null(), &copy).map_err(io_error)?;
+  copy.Commit(STGC_DEFAULT.0 as u32).map_err(io_error)?;
+  let mut stat = STATSTG::default();
+  lock_bytes
+    .Stat(&mut stat, STATFLAG_NONAME)
+    .map_err(io_error)?;
+  let global = GetHGlobalFromILockBytes(&lock_bytes).map_err(io_error)?;
+  let mut bytes = global_bytes(global)
+    .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "the memory can't be locked"))?;
+  // The memory can be larger than the file.
+  bytes.truncate(stat.cbSize as usize);
+  Ok(bytes)
+}
+
+/// The descriptors of a `FILEGROUPDESCRIPTORW`: the count of the files, followed by their
+/// descriptors. The ones past the end of `bytes` are left out.
+fn file_descriptors(bytes: &[u8]) -> Vec<FILEDESCRIPTORW> {
+  let count = match bytes.get(..4) {
+    Some(count) => u32::from_ne_bytes([count[0], count[1], count[2], count[3]]),
+    None => return Vec::new(),
+  };
+  let size = mem::size_of::<FILEDESCRIPTORW>();
+  bytes[4..]
+    .chunks_exact(size)
+    .take(count as usize)
+    .map(|descriptor| unsafe { ptr::read_unaligned(descriptor.as_ptr() as *const FILEDESCRIPTORW) })
+    .collect()
+}
+
+/// Whether the descriptor is the one of a folder, whose files have their own descriptors.
+fn is_directory(descriptor: &FILEDESCRIPTORW) -> bool {
+  descriptor.dwFlags & FD_ATTRIBUTES != 0
+    && descriptor.dwFileAttributes & FILE_ATTRIBUTE_DIRECTORY != 0
+}
+
+/// The size of the file, if the descriptor gives it.
+fn file_size(descriptor: &FILEDESCRIPTORW) -> Option<u64> {
+  (descriptor.dwFlags & FD_FILESIZE != 0)
+    .then(|| (u64::from(descriptor.nFileSizeHigh) << 32) | u64::from(descriptor.nFileSizeLow))
+}
+
+fn format_etc(format: u16, index: i32, tymed: i32) -> FORMATETC {
+  FORMATETC {
+    cfFormat: format,
+    ptd: ptr::null_mut(),
+    dwAspect: DVASPECT_CONTENT.0 as u32,
+    lindex: index,
+    tymed: tymed as u32,
+  }
+}
+
+/// Reads the data of `data_obj` in `format`, at `index` for the formats holding several items,
+/// from an HGLOBAL or a stream.
+unsafe fn get_data(data_obj: &IDataObject, format: u16, index: i32) -> Option<Vec<u8>> {
+  let mut medium: STGMEDIUM = data_obj
+    .GetData(&format_etc(
+      format,
+      index,
+      TYMED_HGLOBAL.0 | TYMED_ISTREAM.0,
+    ))
+    .ok()?;
+  let data = if medium.tymed == TYMED_HGLOBAL.0 as u32 {
+    global_bytes(medium.u.hGlobal)
+  } else if medium.tymed == TYMED_ISTREAM.0 as u32 {
+    (*medium.u.pstm).as_ref().map(|stream| {
+      let mut bytes = Vec::new();
+      let mut buffer = [0u8; 64 * 1024];
+      loop {
+        let mut read = 0;
+        let result = stream.Read(
+          buffer.as_mut_ptr() as _,
+          buffer.len() as u32,
+          Some(&mut read as *mut u32),
+        );
+        bytes.extend_from_slice(&buffer[..read as usize]);
+        if result.is_err() || read == 0 {
+          break bytes;
+        }
+      }
+    })
+  } else {
+    None
+  };
+  ReleaseStgMedium(&mut medium);
+  data
+}
+
+/// Copies the memory of `global`, or `None` if it can't be locked.
+unsafe fn global_bytes(global: HGLOBAL) -> Option<Vec<u8>> {
+  let data = GlobalLock(global) as *const u8;
+  let bytes = (!data.is_null()).then(|| slice::from_raw_parts(data, GlobalSize(global)).to_vec());
+  let _ = GlobalUnlock(global);
+  bytes
+}
+
+/// Decodes UTF-16 up to the first null character.
+fn utf16_to_string(wide: impl IntoIterator<Item = u16>) -> String {
+  let wide: Vec<u16> = wide
+    .into_iter()
+    .take_while(|character| *character != 0)
+    .collect();
+  String::from_utf16_lossy(&wide)
+}
+
+/// The fragment of the `HTML Format` clipboard format, whose header gives its byte offsets, or
+/// the whole data without them.
+fn html_fragment(html: &str) -> &str {
+  let html = html.trim_end_matches('\0');
+  let offset = |key: &str| -> Option<usize> {
+    let start = html.find(key)? + key.len();
+    let digits = html[start..]
+      .split(|character: char| !character.is_ascii_digit())
+      .next()?;
+    digits.parse().ok()
+  };
+  match (offset("StartFragment:"), offset("EndFragment:")) {
+    (Some(start), Some(end)) => html.get(start..end).unwrap_or(html),
+    _ => html,
+  }
 }
 
 #[allow(non_snake_case)]
@@ -104,26 +561,27 @@ impl IDropTarget_Impl for FileDropHandler {
     &self,
     pDataObj: Option<&IDataObject>,
     _grfKeyState: MODIFIERKEYS_FLAGS,
-    _pt: &POINTL,
+    pt: &POINTL,
     pdwEffect: *mut DROPEFFECT,
   ) -> windows::core::Result<()> {
     use crate::event::WindowEvent::HoveredFile;
     unsafe {
       let hdrop = Self::iterate_filenames(pDataObj, |filename| {
-        (self.send_event)(Event::WindowEvent {
-          window_id: SuperWindowId(WindowId(self.window.0)),
-          event: HoveredFile(filename),
-        });
+        self.send_window_event(HoveredFile(filename));
       });
-      let hovered_is_valid = hdrop.is_some();
-      let cursor_effect = if hovered_is_valid {
-        DROPEFFECT_COPY
-      } else {
-        DROPEFFECT_NONE
-      };
-      *self.hovered_is_valid.get() = hovered_is_valid;
-      *self.cursor_effect.get() = cursor_effect;
-      *pdwEffect = cursor_effect;
+      self.hovered_is_valid.set(hdrop.is_some());
+      *self.formats.borrow_mut() = pDataObj
+        .map(|data_obj| self.held_formats(data_obj))
+        .unwrap_or_default();
+      self.position.set(None);
+      self.drag_over(pt);
+
+      let effect = self.effect(*pdwEffect);
+      if let (Some(helper), Some(data_obj)) = (&self.helper, pDataObj) {
+        let mut point = POINT { x: pt.x, y: pt.y };
+        let _ = helper.DragEnter(self.window, data_obj, &mut point, effect);
+      }
+      *pdwEffect = effect;
     }
     Ok(())
   }
@@ -131,23 +589,26 @@ impl IDropTarget_Impl for FileDropHandler {
   fn DragOver(
     &self,
     _grfKeyState: MODIFIERKEYS_FLAGS,
-    _pt: &POINTL,
+    pt: &POINTL,
     pdwEffect: *mut DROPEFFECT,
   ) -> windows::core::Result<()> {
     unsafe {
-      *pdwEffect = *self.cursor_effect.get();
+      self.drag_over(pt);
+      let effect = self.effect(*pdwEffect);
+      if let Some(helper) = &self.helper {
+        let mut point = POINT { x: pt.x, y: pt.y };
+        let _ = helper.DragOver(&mut point, effect);
+      }
+      *pdwEffect = effect;
     }
     Ok(())
   }
 
   fn DragLeave(&self) -> windows::core::Result<()> {
-    use crate::event::WindowEvent::HoveredFileCancelled;
-    if unsafe { *self.hovered_is_valid.get() } {
-      (self.send_event)(Event::WindowEvent {
-        window_id: SuperWindowId(WindowId(self.window.0)),
-        event: HoveredFileCancelled,
-      });
+    if let Some(helper) = &self.helper {
+      let _ = unsafe { helper.DragLeave() };
     }
+    self.leave();
     Ok(())
   }
 
@@ -155,21 +616,136 @@ impl IDropTarget_Impl for FileDropHandler {
     &self,
     pDataObj: Option<&IDataObject>,
     _grfKeyState: MODIFIERKEYS_FLAGS,
-    _pt: &POINTL,
-    _pdwEffect: *mut DROPEFFECT,
+    pt: &POINTL,
+    pdwEffect: *mut DROPEFFECT,
   ) -> windows::core::Result<()> {
     use crate::event::WindowEvent::DroppedFile;
     unsafe {
+      let effect = self.effect(*pdwEffect);
+      if let (Some(helper), Some(data_obj)) = (&self.helper, pDataObj) {
+        let mut point = POINT { x: pt.x, y: pt.y };
+        let _ = helper.Drop(data_obj, &mut point, effect);
+      }
+      *pdwEffect = effect;
+      if effect == DROPEFFECT_NONE {
+        self.leave();
+        return Ok(());
+      }
+
       let hdrop = Self::iterate_filenames(pDataObj, |filename| {
-        (self.send_event)(Event::WindowEvent {
-          window_id: SuperWindowId(WindowId(self.window.0)),
-          event: DroppedFile(filename),
-        });
+        self.send_window_event(DroppedFile(filename));
       });
       if let Some(hdrop) = hdrop {
         DragFinish(hdrop);
       }
+      if let Some(data_obj) = pDataObj {
+        for data in self.dropped_data(data_obj, effect) {
+          self.send_window_event(WindowEvent::DroppedData(data));
+        }
+      }
+      self.hovered_is_valid.set(false);
+      self.position.set(None);
+      self.formats.borrow_mut().clear();
     }
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn descriptor(name: &str, flags: u32, attributes: u32, size: u64) -> FILEDESCRIPTORW {
+    let mut descriptor: FILEDESCRIPTORW = unsafe { mem::zeroed() };
+    descriptor.dwFlags = flags;
+    descriptor.dwFileAttributes = attributes;
+    descriptor.nFileSizeHigh = (size >> 32) as u32;
+    descriptor.nFileSizeLow = size as u32;
+    for (character, wide) in descriptor.cFileName.iter_mut().zip(name.encode_utf16()) {
+      *character = wide;
+    }
+    descriptor
+  }
+
+  fn group(descriptors: &[FILEDESCRIPTORW]) -> Vec<u8> {
+    let mut bytes = (descriptors.len() as u32).to_ne_bytes().to_vec();
+    for descriptor in descriptors {
+      bytes.extend_from_slice(unsafe {
+        slice::from_raw_parts(
+          descriptor as *const FILEDESCRIPTORW as *const u8,
+          mem::size_of::<FILEDESCRIPTORW>(),
+        )
+      });
+    }
+    bytes
+  }
+
+  #[test]
+  fn file_descriptors_are_read_from_the_group() {
+    let bytes = group(&[
+      descriptor("Mail", FD_ATTRIBUTES, FILE_ATTRIBUTE_DIRECTORY, 0),
+      descriptor("Mail\\report.pdf", FD_FILESIZE, 0, (5 << 32) | 7),
+    ]);
+    let descriptors = file_descriptors(&bytes);
+    assert_eq!(descriptors.len(), 2);
+    assert!(is_directory(&descriptors[0]));
+    assert!(!is_directory(&descriptors[1]));
+    assert_eq!(
+      utf16_to_string(descriptors[1].cFileName),
+      "Mail\\report.pdf"
+    );
+    assert_eq!(file_size(&descriptors[1]), Some((5 << 32) | 7));
+    assert_eq!(file_size(&descriptors[0]), None);
+
+    // The attributes only count with their flag.
+    assert!(!is_directory(&descriptor(
+      "a",
+      0,
+      FILE_ATTRIBUTE_DIRECTORY,
+      0
+    )));
+  }
+
+  #[test]
+  fn truncated_file_descriptors_are_left_out() {
+    let mut bytes = group(&[descriptor("a", 0, 0, 0), descriptor("b", 0, 0, 0)]);
+    bytes.pop();
+    let descriptors = file_descriptors(&bytes);
+    assert_eq!(descriptors.len(), 1);
+    assert_eq!(utf16_to_string(descriptors[0].cFileName), "a");
+
+    assert!(file_descriptors(&[1, 0]).is_empty());
+    // More descriptors counted than given.
+    bytes[0] = 9;
+    assert_eq!(file_descriptors(&bytes).len(), 1);
+  }
+
+  #[test]
+  fn utf16_stops_at_the_first_null() {
+    let wide: Vec<u16> = "héllo\0world".encode_utf16().collect();
+    assert_eq!(utf16_to_string(wide), "héllo");
+    assert_eq!(utf16_to_string([0xD800, 0x61]), "\u{FFFD}a");
+    assert_eq!(utf16_to_string([]), "");
+  }
+
+  #[test]
+  fn html_fragment_follows_the_header_offsets() {
+    let fragment = "<b>bold</b>";
+    let prefix = "<html><body><!--StartFragment-->";
+    let header_len = "Version:0.9\r\nStartFragment:0000000000\r\nEndFragment:0000000000\r\n".len();
+    let start = header_len + prefix.len();
+    let html = format!(
+      "Version:0.9\r\nStartFragment:{:010}\r\nEndFragment:{:010}\r\n{}{}<!--EndFragment--></body></html>\0",
+      start,
+      start + fragment.len(),
+      prefix,
+      fragment
+    );
+    assert_eq!(html_fragment(&html), fragment);
+
+    // Without the header, or with offsets out of the data, all of it.
+    assert_eq!(html_fragment("<p>text</p>\0\0"), "<p>text</p>");
+    let out_of_range = "StartFragment:10\r\nEndFragment:999\r\n";
+    assert_eq!(html_fragment(out_of_range), out_of_range);
+  }
+}
@@ -9,30 +9,44 @@ mod runner;
 use crossbeam_channel::{self as channel, Receiver, Sender};
 use parking_lot::Mutex;
 use std::{
-  cell::Cell,
+  cell::{Cell, RefCell},
   collections::VecDeque,
   ffi::c_void,
   marker::PhantomData,
   mem, panic,
   rc::Rc,
-  sync::Arc,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
   thread,
   time::{Duration, Instant},
 };
 use windows::{
-  core::{s, PCWSTR},
+  core::{s, GUID, PCWSTR},
   Win32::{
     Devices::HumanInterfaceDevice::*,
     Foundation::{
-      BOOL, HANDLE, HINSTANCE, HMODULE, HWND, LPARAM, LRESULT, POINT, RECT, WAIT_TIMEOUT, WPARAM,
+      CloseHandle, BOOL, COLORREF, HANDLE, HINSTANCE, HMODULE, HWND, LPARAM, LRESULT, POINT, RECT,
+      WAIT_OBJECT_0, WAIT_TIMEOUT, WPARAM,
     },
     Graphics::Gdi::*,
     System::{
+      DataExchange::COPYDATASTRUCT,
       LibraryLoader::GetModuleHandleW,
       Ole::{IDropTarget, RevokeDragDrop},
-      Threading::{GetCurrentThreadId, INFINITE},
+      Power::{RegisterPowerSettingNotification, UnregisterPowerSettingNotification, HPOWERNOTIFY},
+      RemoteDesktop::{
+        WTSRegisterSessionNotification, WTSUnRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION,
+      },
+      SystemServices::GUID_POWER_SAVING_STATUS,
+      Threading::{
+        CreateWaitableTimerExW, GetCurrentProcessId, GetCurrentThreadId, SetWaitableTimer,
+        CREATE_WAITABLE_TIMER_HIGH_RESOLUTION, INFINITE, TIMER_ALL_ACCESS,
+      },
     },
     UI::{
+      Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK},
       Controls::{self as win32c, HOVER_DEFAULT},
       Input::{KeyboardAndMouse::*, Pointer::*, Touch::*, *},
       Shell::{
@@ -46,24 +60,38 @@ use windows::{
 
 use crate::{
   dpi::{PhysicalPosition, PhysicalSize, PixelUnit},
-  error::ExternalError,
-  event::{DeviceEvent, Event, Force, RawKeyEvent, Touch, TouchPhase, WindowEvent},
-  event_loop::{ControlFlow, DeviceEventFilter, EventLoopClosed, EventLoopWindowTarget as RootELW},
-  keyboard::{KeyCode, ModifiersState},
+  error::{ExternalError, NotSupportedError},
+  event::{
+    DeviceEvent, Event, Force, Pen, PenPhase, RawKeyEvent, SessionChange, Touch, TouchPhase,
+    WindowEvent,
+  },
+  event_loop::{
+    AccessibilityPrefs, ControlFlow, DeviceEventFilter, EventLoopClosed,
+    EventLoopWindowTarget as RootELW, GlobalShortcutId, IdleDeadline, PowerInfo, SystemAppearance,
+  },
+  keyboard::{Key, KeyCode, KeyboardLayout, ModifiersState},
   monitor::MonitorHandle as RootMonitorHandle,
+  notification::NotificationId,
+  platform::{inject::SyntheticInput, windows::DpiAwareness},
   platform_impl::platform::{
     dark_mode::{try_app_theme, try_window_theme},
     dpi::{become_dpi_aware, dpi_to_scale_factor, enable_non_client_dpi_scaling},
+    inject,
     keyboard::is_msg_keyboard_related,
-    keyboard_layout::LAYOUT_CACHE,
+    keyboard_layout::{self, WindowsModifiers, LAYOUT_CACHE},
+    menu,
     minimal_ime::is_msg_ime_related,
     monitor::{self, MonitorHandle},
-    raw_input, util,
-    window::set_skip_taskbar,
-    window_state::{CursorFlags, WindowFlags, WindowState},
+    notification::{self, ToastMessage},
+    raw_input,
+    single_instance::{self, SingleInstanceLock},
+    system_tray, util,
+    window::{set_skip_taskbar, AccessibilityActions},
+    window_state::{CursorFlags, SubclassHandler, WindowFlags, WindowState},
     wrap_device_id, WindowId, DEVICE_ID,
   },
-  window::{Fullscreen, Theme, WindowId as RootWindowId},
+  system_tray::SystemTrayId,
+  window::{DragOperation, DragResult, Fullscreen, Theme, WindowId as RootWindowId},
 };
 use runner::{EventLoopRunner, EventLoopRunnerShared};
 
@@ -103,6 +131,8 @@ lazy_static! {
 
 pub(crate) struct SubclassInput<T: 'static> {
   pub window_state: Arc<Mutex<WindowState>>,
+  pub subclass_handler: Arc<Mutex<Option<SubclassHandler>>>,
+  pub accessibility_actions: AccessibilityActions,
   pub event_loop_runner: EventLoopRunnerShared<T>,
   pub _file_drop_handler: Option<IDropTarget>,
   pub subclass_removed: Cell<bool>,
@@ -114,11 +144,60 @@ impl<T> SubclassInput<T> {
   unsafe fn send_event(&self, event: Event<'_, T>) {
     self.event_loop_runner.send_event(event);
   }
+
+  /// Reports the appearance of the system if it changed since the last time.
+  unsafe fn update_system_appearance(&self, window: HWND) {
+    let appearance = util::system_appearance();
+    if self.system_appearance.replace(appearance) != appearance {
+      self.send_event(Event::SystemAppearanceChanged(appearance));
+      RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+    }
+  }
+
+  /// Reports the monitors connected and disconnected since the last time.
+  unsafe fn update_monitors(&self, window: HWND) {
+    let monitors: Vec<_> = monitor::available_monitors()
+      .into_iter()
+      .map(|monitor| (monitor.id(), monitor))
+      .collect();
+    let previous = self.monitors.replace(monitors.clone());
+    let mut changed = false;
+    for (id, monitor) in &previous {
+      if !monitors.iter().any(|(other, _)| other == id) {
+        self.send_event(Event::MonitorDisconnected(RootMonitorHandle {
+          inner: monitor.clone(),
+        }));
+        changed = true;
+      }
+    }
+    for (id, monitor) in monitors {
+      if !previous.iter().any(|(other, _)| *other == id) {
+        self.send_event(Event::MonitorConnected(RootMonitorHandle {
+          inner: monitor,
+        }));
+        changed = true;
+      }
+    }
+    if changed {
+      RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+    }
+  }
 }
 
 struct ThreadMsgTargetSubclassInput<T: 'static> {
   event_loop_runner: EventLoopRunnerShared<T>,
   user_event_receiver: Receiver<T>,
+  /// Set while a `USER_EVENT_MSG_ID` message is posted, so a burst of user events posts one.
+  user_event_posted: Arc<AtomicBool>,
+  /// The last reported preferences, `WM_SETTINGCHANGE` is sent for any setting.
+  accessibility_prefs: Cell<AccessibilityPrefs>,
+  system_appearance: Cell<SystemAppearance>,
+  /// The last reported power information.
+  power_info: Cell<PowerInfo>,
+  /// Unregistered when the window is destroyed with the event loop.
+  power_notifications: Vec<HPOWERNOTIFY>,
+  /// The connected monitors with their ids, which can't be read anymore once they're gone.
+  monitors: RefCell<Vec<(u64, MonitorHandle)>>,
 }
 
 impl<T> ThreadMsgTargetSubclassInput<T> {
@@ -136,24 +215,31 @@ pub(crate) enum ProcResult {
 
 pub struct EventLoop<T: 'static> {
   thread_msg_sender: Sender<T>,
+  user_event_posted: Arc<AtomicBool>,
   window_target: RootELW<T>,
   msg_hook: Option<Box<dyn FnMut(*const c_void) -> bool + 'static>>,
+  _single_instance: Option<SingleInstanceLock>,
+  cloak_hook: HWINEVENTHOOK,
 }
 
 pub(crate) struct PlatformSpecificEventLoopAttributes {
   pub(crate) any_thread: bool,
-  pub(crate) dpi_aware: bool,
+  pub(crate) dpi_awareness: DpiAwareness,
   pub(crate) msg_hook: Option<Box<dyn FnMut(*const c_void) -> bool + 'static>>,
   pub(crate) preferred_theme: Option<Theme>,
+  pub(crate) headless: bool,
+  pub(crate) single_instance: Option<String>,
 }
 
 impl Default for PlatformSpecificEventLoopAttributes {
   fn default() -> Self {
     Self {
       any_thread: false,
-      dpi_aware: true,
+      dpi_awareness: DpiAwareness::default(),
       msg_hook: None,
       preferred_theme: None,
+      headless: false,
+      single_instance: None,
     }
   }
 }
@@ -161,9 +247,10 @@ impl Default for PlatformSpecificEventLoopAttributes {
 #[derive(Clone)]
 pub struct EventLoopWindowTarget<T: 'static> {
   thread_id: u32,
-  thread_msg_target: HWND,
+  pub(crate) thread_msg_target: HWND,
   pub(crate) preferred_theme: Option<Theme>,
   pub(crate) runner_shared: EventLoopRunnerShared<T>,
+  pub(crate) headless: bool,
 }
 
 impl<T: 'static> EventLoop<T> {
@@ -179,11 +266,22 @@ impl<T: 'static> EventLoop<T> {
       );
     }
 
-    if attributes.dpi_aware {
-      become_dpi_aware();
-    }
+    become_dpi_aware(attributes.dpi_awareness);
+
+    let single_instance = attributes
+      .single_instance
+      .as_deref()
+      .and_then(|identifier| unsafe {
+        SingleInstanceLock::acquire(
+          identifier,
+          PCWSTR::from_raw(THREAD_EVENT_TARGET_WINDOW_CLASS.as_ptr()),
+        )
+      });
 
     let thread_msg_target = create_event_target_window();
+    if let Some(single_instance) = &single_instance {
+      unsafe { single_instance.expose(thread_msg_target) };
+    }
 
     try_app_theme(attributes.preferred_theme);
 
@@ -193,24 +291,54 @@ impl<T: 'static> EventLoop<T> {
 
     let runner_shared = Rc::new(EventLoopRunner::new(thread_msg_target, wait_thread_id));
 
-    let thread_msg_sender = subclass_event_target_window(thread_msg_target, runner_shared.clone());
-    raw_input::register_all_mice_and_keyboards_for_raw_input(thread_msg_target, Default::default());
+    let (thread_msg_sender, user_event_posted) =
+      subclass_event_target_window(thread_msg_target, runner_shared.clone());
+    raw_input::claim_raw_input(thread_msg_target);
+
+    // DWM doesn't send a message to the windows it cloaks or uncloaks.
+    let cloak_hook = unsafe {
+      SetWinEventHook(
+        EVENT_OBJECT_CLOAKED,
+        EVENT_OBJECT_UNCLOAKED,
+        HMODULE::default(),
+        Some(cloak_event_hook),
+        GetCurrentProcessId(),
+        thread_id,
+        WINEVENT_OUTOFCONTEXT,
+      )
+    };
 
     EventLoop {
       thread_msg_sender,
+      user_event_posted,
       window_target: RootELW {
         p: EventLoopWindowTarget {
           thread_id,
           thread_msg_target,
           runner_shared,
           preferred_theme: attributes.preferred_theme,
+          headless: attributes.headless,
         },
+        exit: Default::default(),
+        executor: Default::default(),
+        captures: Default::default(),
+        window_motion_coalescing: Default::default(),
+        window_registry: Default::default(),
+        user_events: Default::default(),
         _marker: PhantomData,
       },
       msg_hook: attributes.msg_hook.take(),
+      _single_instance: single_instance,
+      cloak_hook,
     }
   }
 
+  pub(crate) fn try_new(
+    attributes: &mut PlatformSpecificEventLoopAttributes,
+  ) -> Result<Self, ExternalError> {
+    Ok(Self::new(attributes))
+  }
+
   pub fn window_target(&self) -> &RootELW<T> {
     &self.window_target
   }
@@ -284,11 +412,29 @@ impl<T: 'static> EventLoop<T> {
     EventLoopProxy {
       target_window: self.window_target.p.thread_msg_target,
       event_send: self.thread_msg_sender.clone(),
+      user_event_posted: self.user_event_posted.clone(),
     }
   }
 }
 
 impl<T> EventLoopWindowTarget<T> {
+  #[inline]
+  pub fn is_headless(&self) -> bool {
+    self.headless
+  }
+
+  #[inline]
+  pub fn has_window(&self, window_id: WindowId) -> bool {
+    self.runner_shared.has_window(HWND(window_id.0))
+  }
+
+  #[inline]
+  pub fn create_waker(&self) -> EventLoopWaker {
+    EventLoopWaker {
+      target_window: self.thread_msg_target,
+    }
+  }
+
   #[inline(always)]
   pub(crate) fn create_thread_executor(&self) -> EventLoopThreadExecutor {
     EventLoopThreadExecutor {
@@ -323,14 +469,144 @@ impl<T> EventLoopWindowTarget<T> {
     ))
   }
 
-  pub fn set_device_event_filter(&self, filter: DeviceEventFilter) {
+  pub fn set_device_event_filter(&self, filter: DeviceEventFilter) -> Result<(), ExternalError> {
+    if !raw_input::owns_raw_input(self.thread_msg_target) {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()));
+    }
     raw_input::register_all_mice_and_keyboards_for_raw_input(self.thread_msg_target, filter);
+    Ok(())
   }
 
   #[inline]
   pub fn cursor_position(&self) -> Result<PhysicalPosition<f64>, ExternalError> {
     util::cursor_position().map_err(Into::into)
   }
+
+  #[inline]
+  pub fn accessibility_preferences(&self) -> AccessibilityPrefs {
+    util::accessibility_prefs()
+  }
+
+  #[inline]
+  pub fn system_appearance(&self) -> SystemAppearance {
+    util::system_appearance()
+  }
+
+  #[inline]
+  pub fn power_info(&self) -> PowerInfo {
+    util::power_info()
+  }
+
+  #[inline]
+  pub fn set_idle_callback(&self, callback: Option<Box<dyn FnMut(IdleDeadline)>>) {
+    self.runner_shared.set_idle_callback(callback);
+  }
+
+  pub fn run_nested_until(
+    &self,
+    target: &RootELW<T>,
+    predicate: &mut dyn FnMut() -> bool,
+    handler: &mut dyn FnMut(Event<'_, T>, &RootELW<T>, &mut ControlFlow),
+  ) -> Result<(), ExternalError> {
+    if predicate() {
+      return Ok(());
+    }
+    let runner = &self.runner_shared;
+    unsafe {
+      runner.run_nested(
+        &mut |event, control_flow| handler(event, target, control_flow),
+        || {
+          // A first iteration delivers the events buffered so far.
+          runner.poll();
+          // The runner delivers the events as the messages are dispatched, and ends each iteration
+          // on the `WM_PAINT` of the thread target, so `GetMessageW` blocks in between.
+          let mut msg = MSG::default();
+          loop {
+            if let Err(payload) = runner.take_panic_error() {
+              panic::resume_unwind(payload);
+            }
+            if let ControlFlow::ExitWithCode(_) = runner.control_flow() {
+              break;
+            }
+            if !runner.handling_events() && predicate() {
+              break;
+            }
+            match GetMessageW(&mut msg, HWND::default(), 0, 0).0 {
+              // `GetMessageW` removed `WM_QUIT`, post it again for the outer loop.
+              0 => {
+                PostQuitMessage(msg.wParam.0 as i32);
+                break;
+              }
+              -1 => break,
+              _ => {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+              }
+            }
+          }
+        },
+      );
+    }
+    Ok(())
+  }
+
+  pub fn key_for_keycode(&self, code: KeyCode) -> Option<Key<'static>> {
+    let mut layouts = LAYOUT_CACHE.lock();
+    let (_, layout) = layouts.get_current_layout();
+    layout
+      .keys
+      .get(&WindowsModifiers::empty())
+      .and_then(|keys| keys.get(&code))
+      .filter(|key| !matches!(key, Key::Unidentified(_)))
+      .cloned()
+  }
+
+  #[inline]
+  pub fn keyboard_layout(&self) -> Option<KeyboardLayout> {
+    keyboard_layout::keyboard_layout()
+  }
+
+  pub fn inject_input(&self, input: SyntheticInput) -> Result<(), ExternalError> {
+    inject::inject_input(input)
+  }
+
+  pub fn register_global_shortcut(
+    &self,
+    id: u32,
+    modifiers: ModifiersState,
+    key: KeyCode,
+  ) -> Result<(), ExternalError> {
+    let scancode = key
+      .to_scancode()
+      .ok_or(ExternalError::InvalidInput("the key has no scancode"))?;
+    let vk = unsafe { MapVirtualKeyW(scancode, MAPVK_VSC_TO_VK_EX) };
+    if vk == 0 {
+      return Err(ExternalError::InvalidInput(
+        "the key has no virtual key on the current layout",
+      ));
+    }
+    let mut hot_key_modifiers = MOD_NOREPEAT;
+    if modifiers.shift_key() {
+      hot_key_modifiers |= MOD_SHIFT;
+    }
+    if modifiers.control_key() {
+      hot_key_modifiers |= MOD_CONTROL;
+    }
+    if modifiers.alt_key() {
+      hot_key_modifiers |= MOD_ALT;
+    }
+    if modifiers.super_key() {
+      hot_key_modifiers |= MOD_WIN;
+    }
+    // `WM_HOTKEY` is posted to the thread event target.
+    unsafe { RegisterHotKey(self.thread_msg_target, id as i32, hot_key_modifiers, vk)? };
+    Ok(())
+  }
+
+  pub fn unregister_global_shortcut(&self, id: u32) -> Result<(), ExternalError> {
+    unsafe { UnregisterHotKey(self.thread_msg_target, id as i32)? };
+    Ok(())
+  }
 }
 
 fn main_thread_id() -> u32 {
@@ -378,6 +654,17 @@ fn wait_thread(parent_thread_id: u32, msg_window_id: HWND) {
       LPARAM(cur_thread_id as _),
     );
 
+    // The timeout of `MsgWaitForMultipleObjectsEx` is limited by the resolution of the system
+    // timer, a high resolution waitable timer wakes up much closer to the requested time. They
+    // are only available on Windows 10 1803 and later.
+    let timer = CreateWaitableTimerExW(
+      None,
+      PCWSTR::null(),
+      CREATE_WAITABLE_TIMER_HIGH_RESOLUTION,
+      TIMER_ALL_ACCESS.0,
+    )
+    .ok();
+
     let mut wait_until_opt = None;
     'main: loop {
       // Zeroing out the message ensures that the `WaitUntilInstantBox` doesn't get
@@ -406,16 +693,28 @@ fn wait_thread(parent_thread_id: u32, msg_window_id: HWND) {
       if let Some(wait_until) = wait_until_opt {
         let now = Instant::now();
         if now < wait_until {
-          // MsgWaitForMultipleObjects tends to overshoot just a little bit. We subtract
-          // 1 millisecond from the requested time and spinlock for the remainder to
-          // compensate for that.
-          let resume_reason = MsgWaitForMultipleObjectsEx(
-            None,
-            dur2timeout(wait_until - now).saturating_sub(1),
-            QS_ALLEVENTS,
-            MWMO_INPUTAVAILABLE,
-          );
-          if resume_reason == WAIT_TIMEOUT {
+          let timed_out = match timer.filter(|&timer| set_timer(timer, wait_until - now)) {
+            Some(timer) => {
+              MsgWaitForMultipleObjectsEx(
+                Some(&[timer]),
+                INFINITE,
+                QS_ALLEVENTS,
+                MWMO_INPUTAVAILABLE,
+              ) == WAIT_OBJECT_0
+            }
+            // MsgWaitForMultipleObjects tends to overshoot just a little bit. We subtract
+            // 1 millisecond from the requested time and spinlock for the remainder to
+            // compensate for that.
+            None => {
+              MsgWaitForMultipleObjectsEx(
+                None,
+                dur2timeout(wait_until - now).saturating_sub(1),
+                QS_ALLEVENTS,
+                MWMO_INPUTAVAILABLE,
+              ) == WAIT_TIMEOUT
+            }
+          };
+          if timed_out {
             let _ = PostMessageW(
               msg_window_id,
               *PROCESS_NEW_EVENTS_MSG_ID,
@@ -435,9 +734,20 @@ fn wait_thread(parent_thread_id: u32, msg_window_id: HWND) {
         }
       }
     }
+
+    if let Some(timer) = timer {
+      let _ = CloseHandle(timer);
+    }
   }
 }
 
+/// Arms the waitable `timer` to be signaled after `dur`, replacing its previous due time.
+unsafe fn set_timer(timer: HANDLE, dur: Duration) -> bool {
+  // Negative due times are relative, in 100 nanosecond intervals.
+  let due_time = -((dur.as_nanos() / 100).clamp(1, i64::MAX as u128) as i64);
+  SetWaitableTimer(timer, &due_time, 0, None, None, false).is_ok()
+}
+
 // Implementation taken from https://github.com/rust-lang/rust/blob/db5476571d9b27c862b95c1e64764b0ac8980e23/src/libstd/sys/windows/mod.rs
 fn dur2timeout(dur: Duration) -> u32 {
   // Note that a duration is a (u64, u32) (seconds, nanoseconds) pair, and the
@@ -471,11 +781,29 @@ fn dur2timeout(dur: Duration) -> u32 {
 impl<T> Drop for EventLoop<T> {
   fn drop(&mut self) {
     unsafe {
+      if !self.cloak_hook.is_invalid() {
+        let _ = UnhookWinEvent(self.cloak_hook);
+      }
+      raw_input::release_raw_input(self.window_target.p.thread_msg_target);
       let _ = DestroyWindow(self.window_target.p.thread_msg_target);
     }
   }
 }
 
+unsafe extern "system" fn cloak_event_hook(
+  _hook: HWINEVENTHOOK,
+  _event: u32,
+  window: HWND,
+  object: i32,
+  _child: i32,
+  _thread_id: u32,
+  _time: u32,
+) {
+  if object == OBJID_WINDOW.0 {
+    let _ = PostMessageW(window, *UPDATE_OCCLUSION_MSG_ID, WPARAM(0), LPARAM(0));
+  }
+}
+
 pub(crate) struct EventLoopThreadExecutor {
   thread_id: u32,
   target_window: HWND,
@@ -538,6 +866,7 @@ type ThreadExecFn = Box<Box<dyn FnMut()>>;
 pub struct EventLoopProxy<T: 'static> {
   target_window: HWND,
   event_send: Sender<T>,
+  user_event_posted: Arc<AtomicBool>,
 }
 unsafe impl<T: Send + 'static> Send for EventLoopProxy<T> {}
 unsafe impl<T: Send + 'static> Sync for EventLoopProxy<T> {}
@@ -547,20 +876,42 @@ impl<T: 'static> Clone for EventLoopProxy<T> {
     Self {
       target_window: self.target_window,
       event_send: self.event_send.clone(),
+      user_event_posted: self.user_event_posted.clone(),
     }
   }
 }
 
 impl<T: 'static> EventLoopProxy<T> {
   pub fn send_event(&self, event: T) -> Result<(), EventLoopClosed<T>> {
-    unsafe {
-      if PostMessageW(self.target_window, *USER_EVENT_MSG_ID, WPARAM(0), LPARAM(0)).is_ok() {
-        self.event_send.send(event).ok();
-        Ok(())
-      } else {
-        Err(EventLoopClosed(event))
+    // The event is queued before the message that wakes the event loop up, so that the event
+    // loop never waits for this thread to queue it. The receiver is dropped with the event loop.
+    self
+      .event_send
+      .send(event)
+      .map_err(|channel::SendError(event)| EventLoopClosed(event))?;
+    // The message delivers all the queued events, so a single one is posted at a time.
+    if !self.user_event_posted.swap(true, Ordering::AcqRel) {
+      unsafe {
+        let _ = PostMessageW(self.target_window, *USER_EVENT_MSG_ID, WPARAM(0), LPARAM(0));
       }
     }
+    Ok(())
+  }
+}
+
+#[derive(Clone)]
+pub struct EventLoopWaker {
+  target_window: HWND,
+}
+unsafe impl Send for EventLoopWaker {}
+unsafe impl Sync for EventLoopWaker {}
+
+impl EventLoopWaker {
+  pub fn wake(&self) {
+    // Fails once the thread message window is destroyed, which is fine.
+    unsafe {
+      let _ = PostMessageW(self.target_window, *WAKER_MSG_ID, WPARAM(0), LPARAM(0));
+    }
   }
 }
 
@@ -574,6 +925,13 @@ lazy_static! {
             RegisterWindowMessageA(s!("Tao::WakeupMsg"))
         }
     };
+    /// Message sent by the `EventLoopWaker` when we want to wake up the thread without an event.
+    /// WPARAM and LPARAM are unused.
+    static ref WAKER_MSG_ID: u32 = {
+        unsafe {
+            RegisterWindowMessageA(s!("Tao::WakerMsg"))
+        }
+    };
     /// Message sent when we want to execute a closure in the thread.
     /// WPARAM contains a Box<Box<dyn FnMut()>> that must be retrieved with `Box::from_raw`,
     /// and LPARAM is unused.
@@ -617,6 +975,36 @@ lazy_static! {
     pub static ref SET_RETAIN_STATE_ON_SIZE_MSG_ID: u32 = unsafe {
         RegisterWindowMessageA(s!("Tao::SetRetainMaximized"))
     };
+    /// Message sent by a `Window` once a drag it started ends, as `DoDragDrop` blocks the event
+    /// handler that started it. WPARAM is 0 if cancelled, 1 if copied and 2 if moved.
+    pub static ref DRAG_FINISHED_MSG_ID: u32 = unsafe {
+        RegisterWindowMessageA(s!("Tao::DragFinished"))
+    };
+    /// Message sent by `Window::set_ime_allowed`. WPARAM is a bool, whether the composition is
+    /// reported.
+    pub static ref SET_IME_ALLOWED_MSG_ID: u32 = unsafe {
+        RegisterWindowMessageA(s!("Tao::SetImeAllowed"))
+    };
+    /// Message posted when DWM cloaks or uncloaks a window, which then reports whether it's
+    /// occluded.
+    static ref UPDATE_OCCLUSION_MSG_ID: u32 = unsafe {
+        RegisterWindowMessageA(s!("Tao::UpdateOcclusion"))
+    };
+    /// Message sent by the shell for the clicks on a system tray. WPARAM is the id of the tray
+    /// and LPARAM the mouse message.
+    pub static ref SYSTEM_TRAY_MSG_ID: u32 = unsafe {
+        RegisterWindowMessageA(s!("Tao::SystemTray"))
+    };
+    /// Message posted by an `AccessibilityActionSender` after queuing its request in the
+    /// `accessibility_actions` of the window.
+    pub static ref ACCESSIBILITY_ACTION_MSG_ID: u32 = unsafe {
+        RegisterWindowMessageA(s!("Tao::AccessibilityAction"))
+    };
+    /// Message posted by the handlers of a toast. WPARAM is the id of the notification and
+    /// LPARAM a `Box<ToastMessage>`.
+    pub static ref NOTIFICATION_MSG_ID: u32 = unsafe {
+        RegisterWindowMessageA(s!("Tao::Notification"))
+    };
     /// When the taskbar is created, it registers a message with the "TaskbarCreated" string and then broadcasts this message to all top-level windows
     /// When the application receives this message, it should assume that any taskbar icons it added have been removed and add them again.
     pub static ref S_U_TASKBAR_RESTART: u32 = unsafe {
@@ -685,14 +1073,36 @@ fn create_event_target_window() -> HWND {
 fn subclass_event_target_window<T>(
   window: HWND,
   event_loop_runner: EventLoopRunnerShared<T>,
-) -> Sender<T> {
+) -> (Sender<T>, Arc<AtomicBool>) {
   unsafe {
     let (tx, rx) = channel::unbounded();
+    let user_event_posted = Arc::new(AtomicBool::new(false));
 
     let subclass_input = ThreadMsgTargetSubclassInput {
       event_loop_runner,
       user_event_receiver: rx,
+      user_event_posted: user_event_posted.clone(),
+      accessibility_prefs: Cell::new(util::accessibility_prefs()),
+      system_appearance: Cell::new(util::system_appearance()),
+      power_info: Cell::new(util::power_info()),
+      // Changes of the power source are broadcast to all top-level windows already.
+      power_notifications: [&GUID_POWER_SAVING_STATUS, &GUID_ENERGY_SAVER_STATUS]
+        .iter()
+        .filter_map(|guid| {
+          RegisterPowerSettingNotification(HANDLE(window.0), *guid, DEVICE_NOTIFY_WINDOW_HANDLE)
+            .ok()
+        })
+        .collect(),
+      monitors: RefCell::new(
+        monitor::available_monitors()
+          .into_iter()
+          .map(|monitor| (monitor.id(), monitor))
+          .collect(),
+      ),
     };
+    if let Err(e) = WTSRegisterSessionNotification(window, NOTIFY_FOR_THIS_SESSION) {
+      warn!("Failed to register for the session notifications: {}", e);
+    }
     let input_ptr = Box::into_raw(Box::new(subclass_input));
     let subclass_result = SetWindowSubclass(
       window,
@@ -702,7 +1112,7 @@ fn subclass_event_target_window<T>(
     );
     assert!(subclass_result.as_bool());
 
-    tx
+    (tx, user_event_posted)
   }
 }
 
@@ -735,6 +1145,9 @@ unsafe fn release_mouse(mut window_state: parking_lot::MutexGuard<'_, WindowStat
   }
 }
 
+/// `GUID_ENERGY_SAVER_STATUS`, the energy saver that replaces the battery saver on Windows 11.
+const GUID_ENERGY_SAVER_STATUS: GUID = GUID::from_u128(0x550e8400_e29b_41d4_a716_446655440000);
+
 const WINDOW_SUBCLASS_ID: usize = 0;
 const THREAD_EVENT_TARGET_SUBCLASS_ID: usize = 1;
 pub(crate) fn subclass_window<T>(window: HWND, subclass_input: SubclassInput<T>) {
@@ -769,6 +1182,36 @@ fn normalize_pointer_pressure(pressure: u32) -> Option<Force> {
   }
 }
 
+/// The pen data of a pointer, `None` when it isn't a pen.
+unsafe fn pointer_pen_info(pointer_id: u32) -> Option<POINTER_PEN_INFO> {
+  GET_POINTER_PEN_INFO.and_then(|GetPointerPenInfo| {
+    let mut pen_info = mem::MaybeUninit::uninit();
+    if GetPointerPenInfo(pointer_id, pen_info.as_mut_ptr()).as_bool() {
+      Some(pen_info.assume_init())
+    } else {
+      None
+    }
+  })
+}
+
+fn pen_from_info(phase: PenPhase, location: PhysicalPosition<f64>, info: &POINTER_PEN_INFO) -> Pen {
+  let has_mask = |mask| (info.penMask & mask) != Default::default();
+  let has_flag = |flag| (info.penFlags & flag) != Default::default();
+  Pen {
+    device_id: DEVICE_ID,
+    phase,
+    location,
+    in_contact: (info.pointerInfo.pointerFlags & POINTER_FLAG_INCONTACT) != Default::default(),
+    pressure: has_mask(PEN_MASK_PRESSURE).then(|| info.pressure as f64 / 1024.0),
+    tilt: (has_mask(PEN_MASK_TILT_X) || has_mask(PEN_MASK_TILT_Y))
+      .then(|| (info.tiltX as f64, info.tiltY as f64)),
+    twist: has_mask(PEN_MASK_ROTATION).then(|| info.rotation as f64),
+    barrel_button: has_flag(PEN_FLAG_BARREL),
+    eraser: has_flag(PEN_FLAG_ERASER) || has_flag(PEN_FLAG_INVERTED),
+    timestamp: util::message_time(),
+  }
+}
+
 /// Flush redraw events for Tao's windows.
 ///
 /// Tao's API guarantees that all redraw events will be clustered together and dispatched all at
@@ -815,6 +1258,37 @@ unsafe fn flush_paint_messages<T: 'static>(
   }
 }
 
+/// Delivers `RedrawRequested` right away while the user resizes or moves the window, so a frame
+/// of the new size is presented before the modal loop shows it. See `with_resize_redraw_sync`.
+///
+/// Never re-entrant: a resize from inside the event handler, or from that redraw, is left to the
+/// next `WM_PAINT`.
+unsafe fn redraw_in_size_move<T: 'static>(window: HWND, subclass_input: &SubclassInput<T>) {
+  let runner = &subclass_input.event_loop_runner;
+  {
+    let mut w = subclass_input.window_state.lock();
+    if !w.resize_redraw_sync
+      || w.in_sync_redraw
+      || !w.window_flags().contains(WindowFlags::MARKER_IN_SIZE_MOVE)
+      || runner.should_buffer()
+    {
+      return;
+    }
+    w.in_sync_redraw = true;
+  }
+
+  let managing_redraw = flush_paint_messages(Some(window), runner);
+  subclass_input.send_event(Event::RedrawRequested(RootWindowId(WindowId(window.0))));
+  if managing_redraw {
+    runner.redraw_events_cleared();
+    process_control_flow(runner);
+  }
+  // The frame is already drawn, so the `WM_PAINT` of this resize is dropped.
+  ValidateRect(window, None);
+
+  subclass_input.window_state.lock().in_sync_redraw = false;
+}
+
 unsafe fn process_control_flow<T: 'static>(runner: &EventLoopRunner<T>) {
   match runner.control_flow() {
     ControlFlow::Poll => {
@@ -864,6 +1338,8 @@ fn update_modifiers<T>(window: HWND, subclass_input: &SubclassInput<T>) -> Modif
 unsafe fn gain_active_focus<T>(window: HWND, subclass_input: &SubclassInput<T>) {
   use crate::event::WindowEvent::Focused;
   update_modifiers(window, subclass_input);
+  // `FLASHW_TIMERNOFG` stops flashing once the window comes to the foreground.
+  subclass_input.window_state.lock().attention_requested = false;
 
   subclass_input.send_event(Event::WindowEvent {
     window_id: RootWindowId(WindowId(window.0)),
@@ -871,6 +1347,47 @@ unsafe fn gain_active_focus<T>(window: HWND, subclass_input: &SubclassInput<T>)
   });
 }
 
+/// Emits `WindowEvent::MonitorChanged` if `hmonitor` isn't the monitor that was last reported.
+unsafe fn update_current_monitor<T>(
+  window: HWND,
+  hmonitor: HMONITOR,
+  subclass_input: &SubclassInput<T>,
+) {
+  use crate::event::WindowEvent::MonitorChanged;
+
+  {
+    let mut window_state = subclass_input.window_state.lock();
+    if hmonitor.is_invalid() || window_state.current_monitor == hmonitor {
+      return;
+    }
+    window_state.current_monitor = hmonitor;
+  }
+
+  subclass_input.send_event(Event::WindowEvent {
+    window_id: RootWindowId(WindowId(window.0)),
+    event: MonitorChanged(RootMonitorHandle {
+      inner: MonitorHandle::new(hmonitor),
+    }),
+  });
+}
+
+/// Reports whether the window is occluded, if that changed. Windows doesn't tell whether other
+/// windows cover it, only whether it's hidden, minimized or cloaked.
+unsafe fn update_occluded<T>(window: HWND, subclass_input: &SubclassInput<T>) {
+  let occluded =
+    !util::is_visible(window) || IsIconic(window).as_bool() || util::is_cloaked(window);
+  let changed = {
+    let mut window_state = subclass_input.window_state.lock();
+    mem::replace(&mut window_state.reported_occluded, occluded) != occluded
+  };
+  if changed {
+    subclass_input.send_event(Event::WindowEvent {
+      window_id: RootWindowId(WindowId(window.0)),
+      event: WindowEvent::Occluded(occluded),
+    });
+  }
+}
+
 unsafe fn lose_active_focus<T>(window: HWND, subclass_input: &SubclassInput<T>) {
   use crate::event::WindowEvent::{Focused, ModifiersChanged};
 
@@ -943,12 +1460,30 @@ unsafe fn public_window_callback_inner<T: 'static>(
     RDW_INTERNALPAINT,
   );
 
+  // `WM_NCDESTROY` is left to tao, which releases the window with it.
+  if msg != win32wm::WM_NCDESTROY {
+    // Unlocked for the handler to be able to use the window.
+    let handler = subclass_input.subclass_handler.lock().clone();
+    if let Some(handler) = handler {
+      let value = subclass_input
+        .event_loop_runner
+        .catch_unwind(|| handler(window.0, msg, wparam.0, lparam.0));
+      match value {
+        Some(Some(value)) => return LRESULT(value),
+        Some(None) => {}
+        None => return LRESULT(-1),
+      }
+    }
+  }
+
   let mut result = ProcResult::DefSubclassProc;
 
-  // Send new modifiers before sending key events.
+  // Send new modifiers before the key events of a press, and after the ones of a release.
   let mods_changed_callback = || match msg {
     win32wm::WM_KEYDOWN | win32wm::WM_SYSKEYDOWN | win32wm::WM_KEYUP | win32wm::WM_SYSKEYUP => {
-      update_modifiers(window, subclass_input);
+      if matches!(msg, win32wm::WM_KEYDOWN | win32wm::WM_SYSKEYDOWN) {
+        update_modifiers(window, subclass_input);
+      }
       result = ProcResult::Value(LRESULT(0));
     }
     _ => (),
@@ -975,13 +1510,16 @@ unsafe fn public_window_callback_inner<T: 'static>(
         Vec::new()
       }
     };
-    for event in events {
+    let modifiers = subclass_input.window_state.lock().modifiers_state;
+    for mut event in events {
+      event.event.modifiers = modifiers;
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: KeyboardInput {
           device_id: DEVICE_ID,
           event: event.event,
           is_synthetic: event.is_synthetic,
+          timestamp: util::message_time(),
         },
       });
     }
@@ -991,22 +1529,32 @@ unsafe fn public_window_callback_inner<T: 'static>(
     .catch_unwind(keyboard_callback)
     .unwrap_or_else(|| result = ProcResult::Value(LRESULT(-1)));
 
+  let mods_released_callback = || match msg {
+    win32wm::WM_KEYUP | win32wm::WM_SYSKEYUP => {
+      update_modifiers(window, subclass_input);
+    }
+    _ => (),
+  };
+  subclass_input
+    .event_loop_runner
+    .catch_unwind(mods_released_callback)
+    .unwrap_or_else(|| result = ProcResult::Value(LRESULT(-1)));
+
   let ime_callback = || {
-    use crate::event::WindowEvent::ReceivedImeText;
     let is_ime_related = is_msg_ime_related(msg);
     if !is_ime_related {
       return;
     }
-    let text = {
+    let events = {
       let mut window_state = subclass_input.window_state.lock();
       window_state
         .ime_handler
         .process_message(window, msg, wparam, lparam, &mut result)
     };
-    if let Some(str) = text {
+    for event in events {
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
-        event: ReceivedImeText(str),
+        event,
       });
     }
   };
@@ -1020,20 +1568,35 @@ unsafe fn public_window_callback_inner<T: 'static>(
   // the git blame and history would be preserved.
   let callback = || match msg {
     win32wm::WM_ENTERSIZEMOVE => {
-      subclass_input
-        .window_state
-        .lock()
-        .set_window_flags_in_place(|f| f.insert(WindowFlags::MARKER_IN_SIZE_MOVE));
+      use crate::event::WindowEvent::DragResizeStarted;
+      let resize = {
+        let mut state = subclass_input.window_state.lock();
+        state.set_window_flags_in_place(|f| f.insert(WindowFlags::MARKER_IN_SIZE_MOVE));
+        state.size_move_report = None;
+        state.size_move_resize
+      };
+      subclass_input.send_event(Event::WindowEvent {
+        window_id: RootWindowId(WindowId(window.0)),
+        event: DragResizeStarted { resize },
+      });
       result = ProcResult::Value(LRESULT(0));
     }
 
     win32wm::WM_EXITSIZEMOVE => {
-      let mut state = subclass_input.window_state.lock();
-      if state.dragging {
-        state.dragging = false;
-        let _ = unsafe { PostMessageW(window, WM_LBUTTONUP, WPARAM::default(), lparam) };
-      }
-      state.set_window_flags_in_place(|f| f.remove(WindowFlags::MARKER_IN_SIZE_MOVE));
+      use crate::event::WindowEvent::DragResizeEnded;
+      let resize = {
+        let mut state = subclass_input.window_state.lock();
+        if state.dragging {
+          state.dragging = false;
+          let _ = unsafe { PostMessageW(window, WM_LBUTTONUP, WPARAM::default(), lparam) };
+        }
+        state.set_window_flags_in_place(|f| f.remove(WindowFlags::MARKER_IN_SIZE_MOVE));
+        state.size_move_resize
+      };
+      subclass_input.send_event(Event::WindowEvent {
+        window_id: RootWindowId(WindowId(window.0)),
+        event: DragResizeEnded { resize },
+      });
       result = ProcResult::Value(LRESULT(0));
     }
 
@@ -1045,6 +1608,17 @@ unsafe fn public_window_callback_inner<T: 'static>(
         let _ = PostMessageW(window, WM_MOUSEMOVE, WPARAM(0), lparam);
       }
 
+      // The buttons of the hit-test callback act on release, like the system ones, which
+      // `DefWindowProc` would draw while tracking the press.
+      let mut window_state = subclass_input.window_state.lock();
+      if window_state.hit_test_callback.is_some()
+        && matches!(wparam.0 as u32, HTMINBUTTON | HTMAXBUTTON | HTCLOSE)
+      {
+        window_state.hit_test_pressed = Some(wparam.0 as u32);
+        result = ProcResult::Value(LRESULT(0));
+      }
+      drop(window_state);
+
       use crate::event::WindowEvent::DecorationsClick;
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
@@ -1052,6 +1626,28 @@ unsafe fn public_window_callback_inner<T: 'static>(
       });
     }
 
+    win32wm::WM_NCLBUTTONUP => {
+      let pressed = subclass_input.window_state.lock().hit_test_pressed.take();
+      if pressed == Some(wparam.0 as u32) {
+        // The same commands as the system buttons, which keep the window flags up to date.
+        let command = match wparam.0 as u32 {
+          HTMINBUTTON => SC_MINIMIZE,
+          HTMAXBUTTON if util::is_maximized(window).unwrap_or(false) => SC_RESTORE,
+          HTMAXBUTTON => SC_MAXIMIZE,
+          _ => SC_CLOSE,
+        };
+        let _ = PostMessageW(window, WM_SYSCOMMAND, WPARAM(command as _), LPARAM(0));
+        result = ProcResult::Value(LRESULT(0));
+      }
+    }
+
+    win32wm::WM_MENUCOMMAND => {
+      if let Some(event) = menu::menu_command(HMENU(lparam.0), wparam.0 as u32) {
+        subclass_input.send_event(event);
+      }
+      result = ProcResult::Value(LRESULT(0));
+    }
+
     win32wm::WM_CLOSE => {
       use crate::event::WindowEvent::CloseRequested;
       subclass_input.send_event(Event::WindowEvent {
@@ -1062,17 +1658,22 @@ unsafe fn public_window_callback_inner<T: 'static>(
     }
 
     win32wm::WM_DESTROY => {
-      use crate::event::WindowEvent::Destroyed;
       let _ = RevokeDragDrop(window);
-      subclass_input.send_event(Event::WindowEvent {
-        window_id: RootWindowId(WindowId(window.0)),
-        event: Destroyed,
-      });
-      subclass_input.event_loop_runner.remove_window(window);
       result = ProcResult::Value(LRESULT(0));
     }
 
+    // The last message the window gets, so `Destroyed` is its last event.
     win32wm::WM_NCDESTROY => {
+      use crate::event::WindowEvent::Destroyed;
+      // Forgotten first, for `has_window` to return false in the handler of `Destroyed`.
+      subclass_input.event_loop_runner.remove_window(window);
+      if let Some(frame_clock) = subclass_input.window_state.lock().frame_clock.take() {
+        frame_clock.stop();
+      }
+      subclass_input.send_event(Event::WindowEvent {
+        window_id: RootWindowId(WindowId(window.0)),
+        event: Destroyed,
+      });
       remove_window_subclass::<T>(window);
       subclass_input.subclass_removed.set(true);
       result = ProcResult::Value(LRESULT(0));
@@ -1173,8 +1774,11 @@ unsafe fn public_window_callback_inner<T: 'static>(
 
       let window_flags = window_state.window_flags;
       if window_flags.contains(WindowFlags::ALWAYS_ON_BOTTOM) {
+        // Activation and restoring from minimized bring the window to the front,
+        // push it back down whenever it is moved, resized or shown.
         let window_pos = &mut *(lparam.0 as *mut WINDOWPOS);
         window_pos.hwndInsertAfter = HWND_BOTTOM;
+        window_pos.flags &= !SWP_NOZORDER;
       }
 
       result = ProcResult::Value(LRESULT(0));
@@ -1185,45 +1789,203 @@ unsafe fn public_window_callback_inner<T: 'static>(
       use crate::event::WindowEvent::Moved;
 
       let windowpos = lparam.0 as *const WINDOWPOS;
-      if (*windowpos).flags & SWP_NOMOVE != SWP_NOMOVE {
-        let physical_position = PhysicalPosition::new((*windowpos).x as i32, (*windowpos).y as i32);
+      let in_minimize_animation = subclass_input.window_state.lock().in_minimize_animation;
+      if (*windowpos).flags & SWP_NOMOVE != SWP_NOMOVE && !in_minimize_animation {
+        let (dx, dy) = util::get_frame_offset(window);
+        let physical_position =
+          PhysicalPosition::new((*windowpos).x as i32 + dx, (*windowpos).y as i32 + dy);
         subclass_input.send_event(Event::WindowEvent {
           window_id: RootWindowId(WindowId(window.0)),
           event: Moved(physical_position),
         });
       }
+      update_current_monitor(
+        window,
+        MonitorFromWindow(window, MONITOR_DEFAULTTONEAREST),
+        subclass_input,
+      );
+
+      if (*windowpos).flags & (SWP_SHOWWINDOW | SWP_HIDEWINDOW) != SET_WINDOW_POS_FLAGS::default() {
+        let visible = util::is_visible(window);
+        let visibility_changed = {
+          let mut w = subclass_input.window_state.lock();
+          mem::replace(&mut w.reported_visible, visible) != visible
+        };
+        if visibility_changed {
+          subclass_input.send_event(Event::WindowEvent {
+            window_id: RootWindowId(WindowId(window.0)),
+            event: WindowEvent::VisibilityChanged(visible),
+          });
+        }
+      }
+
+      // Showing, hiding, minimizing and restoring the window all go through here.
+      update_occluded(window, subclass_input);
 
       // This is necessary for us to still get sent WM_SIZE.
       result = ProcResult::DefSubclassProc;
     }
 
+    // Monitors were added, removed or changed resolution.
+    win32wm::WM_DISPLAYCHANGE => {
+      update_current_monitor(
+        window,
+        MonitorFromWindow(window, MONITOR_DEFAULTTONEAREST),
+        subclass_input,
+      );
+      result = ProcResult::DefSubclassProc;
+    }
+
     win32wm::WM_SIZE => {
-      use crate::event::WindowEvent::Resized;
+      use crate::event::WindowEvent::{FullscreenChanged, Maximized, Resized};
       let w = u32::from(util::LOWORD(lparam.0 as u32));
       let h = u32::from(util::HIWORD(lparam.0 as u32));
 
+      // The window shrinks into its minimize target before it's hidden, see
+      // `animate_to_minimize_target`.
+      if subclass_input.window_state.lock().in_minimize_animation {
+        return;
+      }
+
       let physical_size = PhysicalSize::new(w, h);
       let event = Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
         event: Resized(physical_size),
       };
 
-      {
+      let maximized_changed = {
         let mut w = subclass_input.window_state.lock();
         // See WindowFlags::MARKER_RETAIN_STATE_ON_SIZE docs for info on why this `if` check exists.
+        // Minimizing keeps the maximized state, so that restoring doesn't report a change either.
         if !w
           .window_flags()
           .contains(WindowFlags::MARKER_RETAIN_STATE_ON_SIZE)
+          && wparam.0 != win32wm::SIZE_MINIMIZED as _
         {
           let maximized = wparam.0 == win32wm::SIZE_MAXIMIZED as _;
           w.set_window_flags_in_place(|f| f.set(WindowFlags::MAXIMIZED, maximized));
+          // `set_maximized` updates the flags before the window is resized, so compare against
+          // the last reported state instead of the flags.
+          if w.reported_maximized != maximized {
+            w.reported_maximized = maximized;
+            Some(maximized)
+          } else {
+            None
+          }
+        } else {
+          None
         }
-      }
+      };
+
+      // `set_fullscreen` updates the state before the window is resized, so the transition is
+      // complete once the resize arrives.
+      let fullscreen_changed = {
+        let mut w = subclass_input.window_state.lock();
+        if w.reported_fullscreen != w.fullscreen {
+          w.reported_fullscreen = w.fullscreen.clone();
+          Some(w.fullscreen.clone())
+        } else {
+          None
+        }
+      };
 
       subclass_input.send_event(event);
+      if let Some(maximized) = maximized_changed {
+        subclass_input.send_event(Event::WindowEvent {
+          window_id: RootWindowId(WindowId(window.0)),
+          event: Maximized(maximized),
+        });
+      }
+      if let Some(fullscreen) = fullscreen_changed {
+        subclass_input.send_event(Event::WindowEvent {
+          window_id: RootWindowId(WindowId(window.0)),
+          event: FullscreenChanged(fullscreen),
+        });
+      }
+      // The shape follows the size of the client area, and the scale factor that changed with it.
+      if wparam.0 != win32wm::SIZE_MINIMIZED as _ {
+        let shape = {
+          let w = subclass_input.window_state.lock();
+          w.shape.clone().map(|shape| (shape, w.scale_factor))
+        };
+        if let Some((shape, scale_factor)) = shape {
+          util::set_window_shape(window, Some(&shape), scale_factor);
+        }
+      }
+      redraw_in_size_move(window, subclass_input);
       result = ProcResult::Value(LRESULT(0));
     }
 
+    win32wm::WM_MOVING => {
+      use crate::event::WindowEvent::Moved;
+      // Without the contents shown while dragging, the window only moves once the drag ends, so
+      // the position is reported from the outline being dragged.
+      if let Some(rect) = outline_drag_rect(lparam, subclass_input) {
+        let (dx, dy) = util::get_frame_offset(window);
+        subclass_input.send_event(Event::WindowEvent {
+          window_id: RootWindowId(WindowId(window.0)),
+          event: Moved(PhysicalPosition::new(rect.left + dx, rect.top + dy)),
+        });
+      }
+      redraw_in_size_move(window, subclass_input);
+    }
+
+    win32wm::WM_SIZING => {
+      use crate::event::WindowEvent::{Moved, Resized};
+      if let Some(rect) = outline_drag_rect(lparam, subclass_input) {
+        // Dragging the left or top edge moves the window too.
+        if matches!(
+          wparam.0 as u32,
+          WMSZ_LEFT | WMSZ_TOP | WMSZ_TOPLEFT | WMSZ_TOPRIGHT | WMSZ_BOTTOMLEFT
+        ) {
+          let (dx, dy) = util::get_frame_offset(window);
+          subclass_input.send_event(Event::WindowEvent {
+            window_id: RootWindowId(WindowId(window.0)),
+            event: Moved(PhysicalPosition::new(rect.left + dx, rect.top + dy)),
+          });
+        }
+        // The outline is the window rect, remove the borders around the client area.
+        if let (Some(window_rect), Ok(client_rect)) =
+          (util::get_window_rect(window), util::get_client_rect(window))
+        {
+          let border_width =
+            (window_rect.right - window_rect.left) - (client_rect.right - client_rect.left);
+          let border_height =
+            (window_rect.bottom - window_rect.top) - (client_rect.bottom - client_rect.top);
+          let size = PhysicalSize::new(
+            (rect.right - rect.left - border_width).max(0) as u32,
+            (rect.bottom - rect.top - border_height).max(0) as u32,
+          );
+          subclass_input.send_event(Event::WindowEvent {
+            window_id: RootWindowId(WindowId(window.0)),
+            event: Resized(size),
+          });
+        }
+      }
+    }
+
+    win32wm::WM_ERASEBKGND => {
+      let window_state = subclass_input.window_state.lock();
+      if window_state.no_background_erase {
+        result = ProcResult::Value(LRESULT(1));
+      } else if !window_state
+        .window_flags()
+        .contains(WindowFlags::TRANSPARENT)
+      {
+        let color = window_state.background_color();
+        drop(window_state);
+        let mut rect = RECT::default();
+        if GetClientRect(window, &mut rect).is_ok() {
+          let brush = CreateSolidBrush(COLORREF(
+            color.r as u32 | (color.g as u32) << 8 | (color.b as u32) << 16,
+          ));
+          FillRect(HDC(wparam.0 as _), &rect, brush);
+          DeleteObject(brush);
+        }
+        result = ProcResult::Value(LRESULT(1));
+      }
+    }
+
     // this is necessary for us to maintain minimize/restore state
     win32wm::WM_SYSCOMMAND => {
       if wparam.0 == SC_RESTORE as _ {
@@ -1236,6 +1998,13 @@ unsafe fn public_window_callback_inner<T: 'static>(
       }
       // Send `WindowEvent::Minimized` here if we decide to implement one
 
+      // The low four bits are used by the system, e.g. for the border of a resize.
+      match wparam.0 as u32 & 0xFFF0 {
+        SC_MOVE => subclass_input.window_state.lock().size_move_resize = false,
+        SC_SIZE => subclass_input.window_state.lock().size_move_resize = true,
+        _ => (),
+      }
+
       if wparam.0 == SC_SCREENSAVE as _ {
         let window_state = subclass_input.window_state.lock();
         if window_state.fullscreen.is_some() {
@@ -1295,6 +2064,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
           event: CursorMoved {
             device_id: DEVICE_ID,
             position,
+            timestamp: util::message_time(),
             modifiers,
           },
         });
@@ -1336,6 +2106,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
           device_id: DEVICE_ID,
           delta: LineDelta(0.0, value),
           phase: TouchPhase::Moved,
+          timestamp: util::message_time(),
           modifiers,
         },
       });
@@ -1357,6 +2128,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
           device_id: DEVICE_ID,
           delta: LineDelta(value, 0.0),
           phase: TouchPhase::Moved,
+          timestamp: util::message_time(),
           modifiers,
         },
       });
@@ -1376,6 +2148,11 @@ unsafe fn public_window_callback_inner<T: 'static>(
       capture_mouse(window, &mut subclass_input.window_state.lock());
 
       let modifiers = update_modifiers(window, subclass_input);
+      let click_count = subclass_input
+        .window_state
+        .lock()
+        .mouse
+        .press_click(Left, lparam);
 
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
@@ -1383,6 +2160,8 @@ unsafe fn public_window_callback_inner<T: 'static>(
           device_id: DEVICE_ID,
           state: Pressed,
           button: Left,
+          click_count,
+          timestamp: util::message_time(),
           modifiers,
         },
       });
@@ -1395,6 +2174,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
       release_mouse(subclass_input.window_state.lock());
 
       let modifiers = update_modifiers(window, subclass_input);
+      let click_count = subclass_input.window_state.lock().mouse.release_click(Left);
 
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
@@ -1402,6 +2182,8 @@ unsafe fn public_window_callback_inner<T: 'static>(
           device_id: DEVICE_ID,
           state: Released,
           button: Left,
+          click_count,
+          timestamp: util::message_time(),
           modifiers,
         },
       });
@@ -1414,6 +2196,11 @@ unsafe fn public_window_callback_inner<T: 'static>(
       capture_mouse(window, &mut *subclass_input.window_state.lock());
 
       let modifiers = update_modifiers(window, subclass_input);
+      let click_count = subclass_input
+        .window_state
+        .lock()
+        .mouse
+        .press_click(Right, lparam);
 
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
@@ -1421,6 +2208,8 @@ unsafe fn public_window_callback_inner<T: 'static>(
           device_id: DEVICE_ID,
           state: Pressed,
           button: Right,
+          click_count,
+          timestamp: util::message_time(),
           modifiers,
         },
       });
@@ -1433,6 +2222,11 @@ unsafe fn public_window_callback_inner<T: 'static>(
       release_mouse(subclass_input.window_state.lock());
 
       let modifiers = update_modifiers(window, subclass_input);
+      let click_count = subclass_input
+        .window_state
+        .lock()
+        .mouse
+        .release_click(Right);
 
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
@@ -1440,6 +2234,8 @@ unsafe fn public_window_callback_inner<T: 'static>(
           device_id: DEVICE_ID,
           state: Released,
           button: Right,
+          click_count,
+          timestamp: util::message_time(),
           modifiers,
         },
       });
@@ -1452,6 +2248,11 @@ unsafe fn public_window_callback_inner<T: 'static>(
       capture_mouse(window, &mut subclass_input.window_state.lock());
 
       let modifiers = update_modifiers(window, subclass_input);
+      let click_count = subclass_input
+        .window_state
+        .lock()
+        .mouse
+        .press_click(Middle, lparam);
 
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
@@ -1459,6 +2260,8 @@ unsafe fn public_window_callback_inner<T: 'static>(
           device_id: DEVICE_ID,
           state: Pressed,
           button: Middle,
+          click_count,
+          timestamp: util::message_time(),
           modifiers,
         },
       });
@@ -1471,6 +2274,11 @@ unsafe fn public_window_callback_inner<T: 'static>(
       release_mouse(subclass_input.window_state.lock());
 
       let modifiers = update_modifiers(window, subclass_input);
+      let click_count = subclass_input
+        .window_state
+        .lock()
+        .mouse
+        .release_click(Middle);
 
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
@@ -1478,6 +2286,8 @@ unsafe fn public_window_callback_inner<T: 'static>(
           device_id: DEVICE_ID,
           state: Released,
           button: Middle,
+          click_count,
+          timestamp: util::message_time(),
           modifiers,
         },
       });
@@ -1491,6 +2301,11 @@ unsafe fn public_window_callback_inner<T: 'static>(
       capture_mouse(window, &mut subclass_input.window_state.lock());
 
       let modifiers = update_modifiers(window, subclass_input);
+      let click_count = subclass_input
+        .window_state
+        .lock()
+        .mouse
+        .press_click(Other(xbutton), lparam);
 
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
@@ -1498,6 +2313,8 @@ unsafe fn public_window_callback_inner<T: 'static>(
           device_id: DEVICE_ID,
           state: Pressed,
           button: Other(xbutton),
+          click_count,
+          timestamp: util::message_time(),
           modifiers,
         },
       });
@@ -1511,6 +2328,11 @@ unsafe fn public_window_callback_inner<T: 'static>(
       release_mouse(subclass_input.window_state.lock());
 
       let modifiers = update_modifiers(window, subclass_input);
+      let click_count = subclass_input
+        .window_state
+        .lock()
+        .mouse
+        .release_click(Other(xbutton));
 
       subclass_input.send_event(Event::WindowEvent {
         window_id: RootWindowId(WindowId(window.0)),
@@ -1518,6 +2340,8 @@ unsafe fn public_window_callback_inner<T: 'static>(
           device_id: DEVICE_ID,
           state: Released,
           button: Other(xbutton),
+          click_count,
+          timestamp: util::message_time(),
           modifiers,
         },
       });
@@ -1561,22 +2385,30 @@ unsafe fn public_window_callback_inner<T: 'static>(
           let x = location.x as f64 + (input.x % 100) as f64 / 100f64;
           let y = location.y as f64 + (input.y % 100) as f64 / 100f64;
           let location = PhysicalPosition::new(x, y);
+          let phase = if (input.dwFlags & TOUCHEVENTF_DOWN) != Default::default() {
+            TouchPhase::Started
+          } else if (input.dwFlags & TOUCHEVENTF_UP) != Default::default() {
+            TouchPhase::Ended
+          } else if (input.dwFlags & TOUCHEVENTF_MOVE) != Default::default() {
+            TouchPhase::Moved
+          } else {
+            continue;
+          };
+          let id = subclass_input
+            .window_state
+            .lock()
+            .touch_ids
+            .id(input.dwID as u64, phase);
           subclass_input.send_event(Event::WindowEvent {
             window_id: RootWindowId(WindowId(window.0)),
             event: WindowEvent::Touch(Touch {
-              phase: if (input.dwFlags & TOUCHEVENTF_DOWN) != Default::default() {
-                TouchPhase::Started
-              } else if (input.dwFlags & TOUCHEVENTF_UP) != Default::default() {
-                TouchPhase::Ended
-              } else if (input.dwFlags & TOUCHEVENTF_MOVE) != Default::default() {
-                TouchPhase::Moved
-              } else {
-                continue;
-              },
+              phase,
               location,
               force: None, // WM_TOUCH doesn't support pressure information
-              id: input.dwID as u64,
+              rotation: None,
+              id,
               device_id: DEVICE_ID,
+              timestamp: util::message_time(),
             }),
           });
         }
@@ -1670,49 +2502,85 @@ unsafe fn public_window_callback_inner<T: 'static>(
             continue;
           }
 
+          let pen_info = match pointer_info.pointerType {
+            win32wm::PT_PEN => pointer_pen_info(pointer_info.pointerId),
+            _ => None,
+          };
+          let touch_info = match pointer_info.pointerType {
+            win32wm::PT_TOUCH => GET_POINTER_TOUCH_INFO.and_then(|GetPointerTouchInfo| {
+              let mut touch_info = mem::MaybeUninit::uninit();
+              if GetPointerTouchInfo(pointer_info.pointerId, touch_info.as_mut_ptr()).as_bool() {
+                Some(touch_info.assume_init())
+              } else {
+                None
+              }
+            }),
+            _ => None,
+          };
           let force = match pointer_info.pointerType {
             win32wm::PT_TOUCH => {
-              let mut touch_info = mem::MaybeUninit::uninit();
-              GET_POINTER_TOUCH_INFO.and_then(|GetPointerTouchInfo| {
-                if GetPointerTouchInfo(pointer_info.pointerId, touch_info.as_mut_ptr()).as_bool() {
-                  normalize_pointer_pressure(touch_info.assume_init().pressure)
-                } else {
-                  None
-                }
-              })
+              touch_info.and_then(|touch_info| normalize_pointer_pressure(touch_info.pressure))
             }
             win32wm::PT_PEN => {
-              let mut pen_info = mem::MaybeUninit::uninit();
-              GET_POINTER_PEN_INFO.and_then(|GetPointerPenInfo| {
-                if GetPointerPenInfo(pointer_info.pointerId, pen_info.as_mut_ptr()).as_bool() {
-                  normalize_pointer_pressure(pen_info.assume_init().pressure)
-                } else {
-                  None
-                }
-              })
+              pen_info.and_then(|pen_info| normalize_pointer_pressure(pen_info.pressure))
             }
             _ => None,
           };
+          let rotation = touch_info
+            .filter(|touch_info| {
+              (touch_info.touchMask & TOUCH_MASK_ORIENTATION) != Default::default()
+            })
+            .map(|touch_info| touch_info.orientation as f64);
 
           let x = location.x as f64 + x.fract();
           let y = location.y as f64 + y.fract();
           let location = PhysicalPosition::new(x, y);
+          let has_flag = |flag| (pointer_info.pointerFlags & flag) != Default::default();
+          if let Some(pen_info) = &pen_info {
+            let phase = if has_flag(POINTER_FLAG_DOWN) {
+              Some(PenPhase::Pressed)
+            } else if has_flag(POINTER_FLAG_CANCELED) {
+              Some(PenPhase::Left)
+            } else if has_flag(POINTER_FLAG_UP) {
+              Some(PenPhase::Released)
+            } else if has_flag(POINTER_FLAG_UPDATE) {
+              Some(PenPhase::Moved)
+            } else {
+              None
+            };
+            if let Some(phase) = phase {
+              subclass_input.send_event(Event::WindowEvent {
+                window_id: RootWindowId(WindowId(window.0)),
+                event: WindowEvent::Pen(pen_from_info(phase, location, pen_info)),
+              });
+            }
+          }
+          let phase = if has_flag(POINTER_FLAG_DOWN) {
+            TouchPhase::Started
+          } else if has_flag(POINTER_FLAG_CANCELED) {
+            TouchPhase::Cancelled
+          } else if has_flag(POINTER_FLAG_UP) {
+            TouchPhase::Ended
+          } else if has_flag(POINTER_FLAG_UPDATE) {
+            TouchPhase::Moved
+          } else {
+            continue;
+          };
+          let id = subclass_input
+            .window_state
+            .lock()
+            .touch_ids
+            .id(pointer_info.pointerId as u64, phase);
           subclass_input.send_event(Event::WindowEvent {
             window_id: RootWindowId(WindowId(window.0)),
             event: WindowEvent::Touch(Touch {
-              phase: if (pointer_info.pointerFlags & POINTER_FLAG_DOWN) != Default::default() {
-                TouchPhase::Started
-              } else if (pointer_info.pointerFlags & POINTER_FLAG_UP) != Default::default() {
-                TouchPhase::Ended
-              } else if (pointer_info.pointerFlags & POINTER_FLAG_UPDATE) != Default::default() {
-                TouchPhase::Moved
-              } else {
-                continue;
-              },
+              phase,
               location,
               force,
-              id: pointer_info.pointerId as u64,
+              rotation,
+              id,
               device_id: DEVICE_ID,
+              timestamp: util::message_time(),
             }),
           });
         }
@@ -1723,6 +2591,26 @@ unsafe fn public_window_callback_inner<T: 'static>(
       result = ProcResult::Value(LRESULT(0));
     }
 
+    win32wm::WM_POINTERENTER | win32wm::WM_POINTERLEAVE => {
+      // Only pens report entering and leaving, touches start and end with their contact.
+      let pointer_id = u32::from(util::LOWORD(wparam.0 as u32));
+      if let Some(pen_info) = pointer_pen_info(pointer_id) {
+        let mut location = pen_info.pointerInfo.ptPixelLocation;
+        if ScreenToClient(window, &mut location as *mut _).as_bool() {
+          let phase = match msg {
+            win32wm::WM_POINTERENTER => PenPhase::Entered,
+            _ => PenPhase::Left,
+          };
+          let location = PhysicalPosition::new(location.x as f64, location.y as f64);
+          subclass_input.send_event(Event::WindowEvent {
+            window_id: RootWindowId(WindowId(window.0)),
+            event: WindowEvent::Pen(pen_from_info(phase, location, &pen_info)),
+          });
+        }
+      }
+      result = ProcResult::DefWindowProc;
+    }
+
     win32wm::WM_NCACTIVATE => {
       let is_active = wparam != WPARAM(0);
       let active_focus_changed = subclass_input.window_state.lock().set_active(is_active);
@@ -1737,7 +2625,14 @@ unsafe fn public_window_callback_inner<T: 'static>(
     }
 
     win32wm::WM_SETFOCUS => {
-      let active_focus_changed = subclass_input.window_state.lock().set_focused(true);
+      let active_focus_changed = {
+        let mut w = subclass_input.window_state.lock();
+        // Grabs the cursor again, and hides it, if that was released on focus loss.
+        w.mouse
+          .set_cursor_flags(window, |f| f.insert(CursorFlags::FOCUSED))
+          .ok();
+        w.set_focused(true)
+      };
       if active_focus_changed {
         gain_active_focus(window, subclass_input);
       }
@@ -1745,13 +2640,27 @@ unsafe fn public_window_callback_inner<T: 'static>(
     }
 
     win32wm::WM_KILLFOCUS => {
-      let active_focus_changed = subclass_input.window_state.lock().set_focused(false);
+      let active_focus_changed = {
+        let mut w = subclass_input.window_state.lock();
+        w.mouse
+          .set_cursor_flags(window, |f| f.remove(CursorFlags::FOCUSED))
+          .ok();
+        w.set_focused(false)
+      };
       if active_focus_changed {
         lose_active_focus(window, subclass_input);
       }
       result = ProcResult::Value(LRESULT(0));
     }
 
+    // Sent to the focused top-level window, which passes it on to its children.
+    win32wm::WM_INPUTLANGCHANGE => {
+      if GetAncestor(window, GA_ROOT) == window {
+        subclass_input.send_event(Event::KeyboardLayoutChanged);
+      }
+      result = ProcResult::DefWindowProc;
+    }
+
     win32wm::WM_SETCURSOR => {
       let set_cursor_to = {
         let window_state = subclass_input.window_state.lock();
@@ -1760,7 +2669,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
         // `WM_MOUSEMOVE` seems to come after `WM_SETCURSOR` for a given cursor movement.
         let in_client_area = u32::from(util::LOWORD(lparam.0 as u32)) == HTCLIENT;
         if in_client_area {
-          Some(window_state.mouse.cursor)
+          Some(window_state.mouse.client_cursor())
         } else {
           None
         }
@@ -1768,9 +2677,7 @@ unsafe fn public_window_callback_inner<T: 'static>(
 
       match set_cursor_to {
         Some(cursor) => {
-          if let Ok(cursor) = LoadCursorW(HMODULE::default(), cursor.to_windows_cursor()) {
-            SetCursor(cursor);
-          }
+          SetCursor(cursor);
           result = ProcResult::Value(LRESULT(0));
         }
         None => result = ProcResult::DefWindowProc,
@@ -1785,6 +2692,13 @@ unsafe fn public_window_callback_inner<T: 'static>(
         .window_flags()
         .contains(WindowFlags::MARKER_DECORATIONS);
 
+      // The minimize target is usually smaller than the smallest window.
+      if window_state.in_minimize_animation {
+        (*mmi).ptMinTrackSize = POINT { x: 0, y: 0 };
+        result = ProcResult::Value(LRESULT(0));
+        return;
+      }
+
       let size_constraints = window_state.size_constraints;
 
       if size_constraints.has_min() {
@@ -1842,8 +2756,21 @@ unsafe fn public_window_callback_inner<T: 'static>(
       let new_scale_factor = dpi_to_scale_factor(new_dpi_x);
       let old_scale_factor: f64;
 
+      // The window hasn't been moved to the suggested rect yet, report the monitor it is moving
+      // to now so that `MonitorChanged` is delivered before `ScaleFactorChanged`.
+      update_current_monitor(
+        window,
+        MonitorFromRect(lparam.0 as *const RECT, MONITOR_DEFAULTTONEAREST),
+        subclass_input,
+      );
+
       let (allow_resize, is_decorated) = {
         let mut window_state = subclass_input.window_state.lock();
+        // The window keeps its scale factor, and its physical size with it.
+        if window_state.fixed_scale_factor {
+          result = ProcResult::Value(LRESULT(0));
+          return;
+        }
         old_scale_factor = window_state.scale_factor;
         window_state.scale_factor = new_scale_factor;
 
@@ -2130,16 +3057,57 @@ unsafe fn public_window_callback_inner<T: 'static>(
       }
     }
 
+    win32wm::WM_GETOBJECT => {
+      // Unlocked for the handler to be able to use the window.
+      let handler = subclass_input
+        .window_state
+        .lock()
+        .wm_getobject_handler
+        .clone();
+      if let Some(value) = handler.and_then(|handler| handler(wparam.0, lparam.0)) {
+        result = ProcResult::Value(LRESULT(value));
+      }
+    }
+
     win32wm::WM_NCHITTEST => {
       let window_state = subclass_input.window_state.lock();
       let window_flags = window_state.window_flags();
 
-      // Allow resizing unmaximized non-fullscreen undecorated window
-      if !window_flags.contains(WindowFlags::MARKER_DECORATIONS)
+      let outside_hittest_region =
+        window_state
+          .cursor_hittest_region
+          .as_ref()
+          .map_or(false, |region| {
+            let mut point = POINT {
+              x: util::GET_X_LPARAM(lparam) as i32,
+              y: util::GET_Y_LPARAM(lparam) as i32,
+            };
+            ScreenToClient(window, &mut point);
+            let point = PhysicalPosition::new(point.x, point.y);
+            !region
+              .iter()
+              .any(|rect| rect.contains(point, window_state.scale_factor))
+          });
+
+      if outside_hittest_region {
+        // Let the cursor event fall through to the window underneath.
+        result = ProcResult::Value(LRESULT(HTTRANSPARENT as i32 as _));
+      } else if let Some(callback) = window_state.hit_test_callback.clone() {
+        // Unlocked for the callback to be able to use the window.
+        drop(window_state);
+        let mut point = POINT {
+          x: util::GET_X_LPARAM(lparam) as i32,
+          y: util::GET_Y_LPARAM(lparam) as i32,
+        };
+        ScreenToClient(window, &mut point);
+        let hit = callback(PhysicalPosition::new(point.x as f64, point.y as f64));
+        result = ProcResult::Value(LRESULT(hit.to_win32() as _));
+      } else if !window_flags.contains(WindowFlags::MARKER_DECORATIONS)
         && window_flags.contains(WindowFlags::RESIZABLE)
         && window_state.fullscreen.is_none()
         && !util::is_maximized(window).unwrap_or(false)
       {
+        // Allow resizing unmaximized non-fullscreen undecorated window
         // cursor location
         let (cx, cy) = (
           util::GET_X_LPARAM(lparam) as i32,
@@ -2185,9 +3153,47 @@ unsafe fn public_window_callback_inner<T: 'static>(
           f.set(WindowFlags::MARKER_RETAIN_STATE_ON_SIZE, wparam.0 != 0)
         });
         result = ProcResult::Value(LRESULT(0));
+      } else if msg == *UPDATE_OCCLUSION_MSG_ID {
+        update_occluded(window, subclass_input);
+        result = ProcResult::Value(LRESULT(0));
+      } else if msg == *ACCESSIBILITY_ACTION_MSG_ID {
+        // A message may find the requests of the next ones queued already.
+        let requests = mem::take(&mut *subclass_input.accessibility_actions.lock());
+        for request in requests {
+          subclass_input.send_event(Event::AccessibilityAction {
+            window_id: RootWindowId(WindowId(window.0)),
+            request,
+          });
+        }
+        result = ProcResult::Value(LRESULT(0));
+      } else if msg == *DRAG_FINISHED_MSG_ID {
+        let drag_result = match wparam.0 {
+          1 => DragResult::Dropped(DragOperation::Copy),
+          2 => DragResult::Dropped(DragOperation::Move),
+          _ => DragResult::Cancelled,
+        };
+        subclass_input.send_event(Event::WindowEvent {
+          window_id: RootWindowId(WindowId(window.0)),
+          event: WindowEvent::DragFinished(drag_result),
+        });
+        result = ProcResult::Value(LRESULT(0));
+      } else if msg == *SET_IME_ALLOWED_MSG_ID {
+        let events = {
+          let mut window_state = subclass_input.window_state.lock();
+          window_state.ime_handler.set_allowed(wparam.0 != 0)
+        };
+        for event in events {
+          subclass_input.send_event(Event::WindowEvent {
+            window_id: RootWindowId(WindowId(window.0)),
+            event,
+          });
+        }
+        result = ProcResult::Value(LRESULT(0));
       } else if msg == *S_U_TASKBAR_RESTART {
         let window_state = subclass_input.window_state.lock();
-        set_skip_taskbar(window, window_state.skip_taskbar);
+        if let Err(e) = set_skip_taskbar(window, window_state.skip_taskbar) {
+          warn!("Failed to restore the taskbar state: {}", e);
+        }
       }
     }
   };
@@ -2204,6 +3210,30 @@ unsafe fn public_window_callback_inner<T: 'static>(
   }
 }
 
+/// Returns the rect of `WM_MOVING` or `WM_SIZING` when the outline of the window is dragged
+/// instead of the window itself, at most once per frame.
+unsafe fn outline_drag_rect<T: 'static>(
+  lparam: LPARAM,
+  subclass_input: &SubclassInput<T>,
+) -> Option<RECT> {
+  // Reports are coalesced so that a fast mouse doesn't flood the event loop.
+  const REPORT_INTERVAL: Duration = Duration::from_millis(16);
+
+  if is_show_window_contents_while_dragging_enabled() {
+    return None;
+  }
+  let now = Instant::now();
+  let mut w = subclass_input.window_state.lock();
+  if w
+    .size_move_report
+    .map_or(false, |last| now.duration_since(last) < REPORT_INTERVAL)
+  {
+    return None;
+  }
+  w.size_move_report = Some(now);
+  Some(*(lparam.0 as *const RECT))
+}
+
 fn is_show_window_contents_while_dragging_enabled() -> bool {
   let mut is_enabled: BOOL = BOOL(0);
   let result = unsafe {
@@ -2234,6 +3264,10 @@ unsafe extern "system" fn thread_event_target_callback<T: 'static>(
   // the git blame and history would be preserved.
   let callback = || match msg {
     win32wm::WM_NCDESTROY => {
+      for notification in &subclass_input.power_notifications {
+        let _ = UnregisterPowerSettingNotification(*notification);
+      }
+      let _ = WTSUnRegisterSessionNotification(window);
       remove_event_target_window_subclass::<T>(window);
       subclass_removed = true;
       RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
@@ -2267,6 +3301,88 @@ unsafe extern "system" fn thread_event_target_callback<T: 'static>(
       DefSubclassProc(window, msg, wparam, lparam)
     }
 
+    win32wm::WM_SETTINGCHANGE => {
+      let prefs = util::accessibility_prefs();
+      if subclass_input.accessibility_prefs.replace(prefs) != prefs {
+        subclass_input.send_event(Event::AccessibilityPreferencesChanged(prefs));
+        RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+      }
+      subclass_input.update_system_appearance(window);
+      DefSubclassProc(window, msg, wparam, lparam)
+    }
+
+    // Monitors were connected, disconnected or changed resolution.
+    win32wm::WM_DISPLAYCHANGE => {
+      subclass_input.update_monitors(window);
+      DefSubclassProc(window, msg, wparam, lparam)
+    }
+
+    // Sent when the accent color changes, `AccentColor` is written by then.
+    win32wm::WM_DWMCOLORIZATIONCOLORCHANGED => {
+      subclass_input.update_system_appearance(window);
+      DefSubclassProc(window, msg, wparam, lparam)
+    }
+
+    win32wm::WM_HOTKEY => {
+      subclass_input.send_event(Event::GlobalShortcut(GlobalShortcutId(wparam.0 as u32)));
+      RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+      LRESULT(0)
+    }
+
+    // Received while a `Clipboard` listens to the updates, see `clipboard::Clipboard::new`.
+    win32wm::WM_CLIPBOARDUPDATE => {
+      subclass_input.send_event(Event::ClipboardUpdated);
+      RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+      LRESULT(0)
+    }
+
+    win32wm::WM_POWERBROADCAST => {
+      if matches!(
+        wparam.0 as u32,
+        PBT_APMPOWERSTATUSCHANGE | PBT_POWERSETTINGCHANGE
+      ) {
+        let info = util::power_info();
+        let previous = subclass_input.power_info.replace(info);
+        // The battery charge alone isn't worth an event.
+        if previous.on_battery != info.on_battery || previous.power_saver != info.power_saver {
+          subclass_input.send_event(Event::PowerInfoChanged(info));
+          RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+        }
+      }
+      DefSubclassProc(window, msg, wparam, lparam)
+    }
+
+    win32wm::WM_WTSSESSION_CHANGE => {
+      let change = match wparam.0 as u32 {
+        WTS_SESSION_LOCK => Some(SessionChange::Locked),
+        WTS_SESSION_UNLOCK => Some(SessionChange::Unlocked),
+        WTS_CONSOLE_DISCONNECT | WTS_REMOTE_DISCONNECT => Some(SessionChange::SwitchedAway),
+        WTS_CONSOLE_CONNECT | WTS_REMOTE_CONNECT => Some(SessionChange::SwitchedBack),
+        _ => None,
+      };
+      if let Some(change) = change {
+        subclass_input.send_event(Event::SessionChanged(change));
+        RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+      }
+      DefSubclassProc(window, msg, wparam, lparam)
+    }
+
+    win32wm::WM_COPYDATA => {
+      let copy_data = &*(lparam.0 as *const COPYDATASTRUCT);
+      match single_instance::decode(copy_data) {
+        Some((args, cwd)) => {
+          subclass_input.send_event(Event::SecondInstance {
+            args,
+            cwd,
+            activation_token: None,
+          });
+          RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+          LRESULT(1)
+        }
+        None => DefSubclassProc(window, msg, wparam, lparam),
+      }
+    }
+
     win32wm::WM_INPUT_DEVICE_CHANGE => {
       let event = match wparam.0 as u32 {
         win32wm::GIDC_ARRIVAL => DeviceEvent::Added,
@@ -2293,12 +3409,58 @@ unsafe extern "system" fn thread_event_target_callback<T: 'static>(
     }
 
     _ if msg == *USER_EVENT_MSG_ID => {
-      if let Ok(event) = subclass_input.user_event_receiver.recv() {
+      // Cleared first, so an event queued while delivering these posts a new message.
+      subclass_input
+        .user_event_posted
+        .store(false, Ordering::Release);
+      while let Ok(event) = subclass_input.user_event_receiver.try_recv() {
         subclass_input.send_event(Event::UserEvent(event));
       }
       RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
       LRESULT(0)
     }
+    _ if msg == *WAKER_MSG_ID => {
+      RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+      LRESULT(0)
+    }
+    _ if msg == *SYSTEM_TRAY_MSG_ID => {
+      if let Some(event) = system_tray::event(lparam.0 as u32) {
+        subclass_input.send_event(Event::SystemTrayEvent {
+          tray_id: SystemTrayId(wparam.0 as u32),
+          event,
+        });
+        RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+      }
+      if lparam.0 as u32 == win32wm::WM_RBUTTONUP {
+        system_tray::show_menu(wparam.0 as u32);
+      }
+      LRESULT(0)
+    }
+    // The items of the menus of the system trays.
+    win32wm::WM_MENUCOMMAND => {
+      if let Some(event) = menu::menu_command(HMENU(lparam.0), wparam.0 as u32) {
+        subclass_input.send_event(event);
+        RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+      }
+      LRESULT(0)
+    }
+    _ if msg == *NOTIFICATION_MSG_ID => {
+      let id = wparam.0 as u32;
+      let message: Box<ToastMessage> = Box::from_raw(lparam.0 as *mut _);
+      notification::forget(id);
+      if let ToastMessage::Activated(action) = *message {
+        subclass_input.send_event(Event::NotificationAction {
+          id: NotificationId(id),
+          action,
+        });
+        RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+      }
+      LRESULT(0)
+    }
+    _ if msg == *S_U_TASKBAR_RESTART => {
+      system_tray::restore();
+      DefSubclassProc(window, msg, wparam, lparam)
+    }
     _ if msg == *EXEC_MSG_ID => {
       let mut function: ThreadExecFn = Box::from_raw(wparam.0 as *mut _);
       function();
@@ -2534,3 +3696,42 @@ unsafe fn handle_raw_input<T: 'static>(
     });
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn pen_from_info_scales_the_pressure_and_keeps_the_tilt() {
+    let mut info = POINTER_PEN_INFO::default();
+    info.pointerInfo.pointerFlags = POINTER_FLAG_INCONTACT;
+    info.penMask = PEN_MASK_PRESSURE | PEN_MASK_TILT_X | PEN_MASK_TILT_Y | PEN_MASK_ROTATION;
+    info.penFlags = PEN_FLAG_BARREL;
+    info.pressure = 512;
+    info.tiltX = -30;
+    info.tiltY = 45;
+    info.rotation = 90;
+
+    let pen = pen_from_info(PenPhase::Moved, PhysicalPosition::new(1.0, 2.0), &info);
+    assert!(pen.in_contact);
+    assert_eq!(pen.pressure, Some(0.5));
+    assert_eq!(pen.tilt, Some((-30.0, 45.0)));
+    assert_eq!(pen.twist, Some(90.0));
+    assert!(pen.barrel_button);
+    assert!(!pen.eraser);
+  }
+
+  #[test]
+  fn pen_from_info_leaves_the_unreported_values_out() {
+    let mut info = POINTER_PEN_INFO::default();
+    info.penFlags = PEN_FLAG_INVERTED;
+    info.pressure = 1024;
+
+    let pen = pen_from_info(PenPhase::Entered, PhysicalPosition::new(0.0, 0.0), &info);
+    assert!(!pen.in_contact);
+    assert_eq!(pen.pressure, None);
+    assert_eq!(pen.tilt, None);
+    assert_eq!(pen.twist, None);
+    assert!(pen.eraser);
+  }
+}
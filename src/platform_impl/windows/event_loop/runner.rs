@@ -14,12 +14,13 @@ use std::{
 use windows::Win32::{
   Foundation::HWND,
   Graphics::Gdi::{RedrawWindow, HRGN, RDW_INTERNALPAINT},
+  UI::WindowsAndMessaging::{GetQueueStatus, QS_ALLINPUT},
 };
 
 use crate::{
   dpi::PhysicalSize,
-  event::{Event, StartCause, WindowEvent},
-  event_loop::ControlFlow,
+  event::{Event, OpenedItem, StartCause, WindowEvent},
+  event_loop::{ControlFlow, IdleDeadline},
   platform_impl::platform::util,
   window::WindowId,
 };
@@ -36,8 +37,9 @@ pub(crate) struct EventLoopRunner<T: 'static> {
 
   event_handler: Cell<Option<Box<dyn FnMut(Event<'_, T>, &mut ControlFlow)>>>,
   event_buffer: RefCell<VecDeque<BufferedEvent<T>>>,
+  idle_callback: RefCell<Option<Box<dyn FnMut(IdleDeadline)>>>,
 
-  owned_windows: Cell<HashSet<isize>>,
+  owned_windows: RefCell<HashSet<isize>>,
 
   panic_error: Cell<Option<PanicError>>,
 }
@@ -77,7 +79,8 @@ impl<T> EventLoopRunner<T> {
       last_events_cleared: Cell::new(Instant::now()),
       event_handler: Cell::new(None),
       event_buffer: RefCell::new(VecDeque::new()),
-      owned_windows: Cell::new(HashSet::new()),
+      idle_callback: RefCell::new(None),
+      owned_windows: RefCell::new(HashSet::new()),
     }
   }
 
@@ -92,6 +95,56 @@ impl<T> EventLoopRunner<T> {
     assert!(old_event_handler.is_none());
   }
 
+  /// Makes `handler` the event handler while `f` runs a nested event loop from inside the event
+  /// handler, see `EventLoopWindowTarget::run_nested_until`. The nested loop starts its own
+  /// iterations, and the one the runner was in resumes once `f` returns or unwinds.
+  pub(crate) unsafe fn run_nested<R>(
+    &self,
+    handler: &mut dyn FnMut(Event<'_, T>, &mut ControlFlow),
+    f: impl FnOnce() -> R,
+  ) -> R {
+    struct Restore<'a, T: 'static> {
+      runner: &'a EventLoopRunner<T>,
+      event_handler: Option<Box<dyn FnMut(Event<'_, T>, &mut ControlFlow)>>,
+      runner_state: RunnerState,
+      control_flow: ControlFlow,
+    }
+
+    impl<T: 'static> Drop for Restore<'_, T> {
+      fn drop(&mut self) {
+        let runner = self.runner;
+        // Drops the nested handler, which borrows from the caller.
+        runner.event_handler.set(self.event_handler.take());
+        runner.runner_state.set(self.runner_state);
+        runner.control_flow.set(self.control_flow);
+        if self.runner_state != RunnerState::Idle {
+          // The nested loop took the `WM_PAINT` that ends the iteration.
+          unsafe {
+            RedrawWindow(
+              runner.thread_msg_target,
+              None,
+              HRGN::default(),
+              RDW_INTERNALPAINT,
+            )
+          };
+        }
+      }
+    }
+
+    let handler: Box<dyn FnMut(Event<'_, T>, &mut ControlFlow) + '_> =
+      Box::new(move |event, control_flow| handler(event, control_flow));
+    let _restore = Restore {
+      runner: self,
+      event_handler: self.event_handler.replace(Some(mem::transmute::<
+        Box<dyn FnMut(Event<'_, T>, &mut ControlFlow) + '_>,
+        Box<dyn FnMut(Event<'_, T>, &mut ControlFlow)>,
+      >(handler))),
+      runner_state: self.runner_state.replace(RunnerState::Idle),
+      control_flow: self.control_flow.replace(ControlFlow::Wait),
+    };
+    f()
+  }
+
   pub(crate) fn reset_runner(&self) {
     let EventLoopRunner {
       thread_msg_target: _,
@@ -102,6 +155,7 @@ impl<T> EventLoopRunner<T> {
       last_events_cleared: _,
       event_handler,
       event_buffer: _,
+      idle_callback: _,
       owned_windows: _,
     } = self;
     runner_state.set(RunnerState::Uninitialized);
@@ -177,25 +231,27 @@ impl<T> EventLoopRunner<T> {
     }
   }
   pub fn register_window(&self, window: HWND) {
-    let mut owned_windows = self.owned_windows.take();
-    owned_windows.insert(window.0);
-    self.owned_windows.set(owned_windows);
+    self.owned_windows.borrow_mut().insert(window.0);
   }
 
   pub fn remove_window(&self, window: HWND) {
-    let mut owned_windows = self.owned_windows.take();
-    owned_windows.remove(&window.0);
-    self.owned_windows.set(owned_windows);
+    self.owned_windows.borrow_mut().remove(&window.0);
+  }
+
+  pub fn has_window(&self, window: HWND) -> bool {
+    self.owned_windows.borrow().contains(&window.0)
   }
 
+  pub fn set_idle_callback(&self, callback: Option<Box<dyn FnMut(IdleDeadline)>>) {
+    *self.idle_callback.borrow_mut() = callback;
+  }
+
+  /// Calls `f` with the windows owned when it's called, `f` may create or destroy windows.
   pub fn owned_windows(&self, mut f: impl FnMut(HWND)) {
-    let mut owned_windows = self.owned_windows.take();
-    for hwnd in &owned_windows {
-      f(HWND(*hwnd));
+    let owned_windows: Vec<isize> = self.owned_windows.borrow().iter().copied().collect();
+    for hwnd in owned_windows {
+      f(HWND(hwnd));
     }
-    let new_owned_windows = self.owned_windows.take();
-    owned_windows.extend(&new_owned_windows);
-    self.owned_windows.set(owned_windows);
   }
 }
 
@@ -317,6 +373,7 @@ impl<T> EventLoopRunner<T> {
         self.call_new_events(true);
         self.call_event_handler(Event::MainEventsCleared);
         self.call_redraw_events_cleared();
+        self.call_idle_callback();
       }
       (Uninitialized, Destroyed) => {
         self.call_new_events(true);
@@ -345,6 +402,7 @@ impl<T> EventLoopRunner<T> {
         warn!("RedrawEventsCleared emitted without explicit MainEventsCleared");
         self.call_event_handler(Event::MainEventsCleared);
         self.call_redraw_events_cleared();
+        self.call_idle_callback();
       }
       (HandlingMainEvents, Destroyed) => {
         self.call_event_handler(Event::MainEventsCleared);
@@ -354,6 +412,7 @@ impl<T> EventLoopRunner<T> {
 
       (HandlingRedrawEvents, Idle) => {
         self.call_redraw_events_cleared();
+        self.call_idle_callback();
       }
       (HandlingRedrawEvents, HandlingMainEvents) => {
         warn!("NewEvents emitted without explicit RedrawEventsCleared");
@@ -380,7 +439,8 @@ impl<T> EventLoopRunner<T> {
         }
       }
       (false, ControlFlow::WaitUntil(requested_resume)) => {
-        if Instant::now() < requested_resume {
+        let now = Instant::now();
+        if now < requested_resume {
           StartCause::WaitCancelled {
             requested_resume: Some(requested_resume),
             start: self.last_events_cleared.get(),
@@ -389,11 +449,18 @@ impl<T> EventLoopRunner<T> {
           StartCause::ResumeTimeReached {
             requested_resume,
             start: self.last_events_cleared.get(),
+            resumed: now,
           }
         }
       }
     };
     self.call_event_handler(Event::NewEvents(start_cause));
+    if init {
+      let items = OpenedItem::from_command_line();
+      if !items.is_empty() {
+        self.call_event_handler(Event::Opened { items });
+      }
+    }
     self.dispatch_buffered_events();
     RedrawWindow(
       self.thread_msg_target,
@@ -407,6 +474,24 @@ impl<T> EventLoopRunner<T> {
     self.call_event_handler(Event::RedrawEventsCleared);
     self.last_events_cleared.set(Instant::now());
   }
+
+  /// Runs the idle callback, unless events are buffered or messages are queued for the thread,
+  /// user events included.
+  unsafe fn call_idle_callback(&self) {
+    let deadline = match IdleDeadline::new(self.control_flow.get()) {
+      Some(deadline) => deadline,
+      None => return,
+    };
+    // The high word holds the kinds of messages currently in the queue.
+    if !self.event_buffer.borrow().is_empty() || GetQueueStatus(QS_ALLINPUT) >> 16 != 0 {
+      return;
+    }
+    self.catch_unwind(|| {
+      if let Some(idle_callback) = &mut *self.idle_callback.borrow_mut() {
+        idle_callback(deadline);
+      }
+    });
+  }
 }
 
 impl<T> BufferedEvent<T> {
@@ -439,12 +524,14 @@ impl<T> BufferedEvent<T> {
         });
 
         if new_inner_size != os_inner_size {
-          util::set_inner_size_physical(
+          if let Err(e) = util::set_inner_size_physical(
             HWND(window_id.0 .0),
             new_inner_size.width as _,
             new_inner_size.height as _,
             true,
-          );
+          ) {
+            warn!("Failed to resize the window to its new inner size: {}", e);
+          }
         }
       }
     }
@@ -0,0 +1,135 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  thread::{self, Thread},
+  time::Duration,
+};
+
+use windows::Win32::{
+  Foundation::HWND,
+  Graphics::{
+    Dwm::DwmFlush,
+    Dxgi::{CreateDXGIFactory1, IDXGIFactory1, IDXGIOutput},
+    Gdi::{
+      MonitorFromWindow, RedrawWindow, HMONITOR, HRGN, MONITOR_DEFAULTTONEAREST, RDW_INTERNALPAINT,
+    },
+  },
+  UI::WindowsAndMessaging::{IsIconic, IsWindow, IsWindowVisible},
+};
+
+/// The requests of a window to the thread of its frame clock.
+#[derive(Default)]
+struct Requests {
+  /// Redraws after each vertical blank, see `Window::request_frame_callbacks`.
+  continuous: AtomicBool,
+  /// Redraws after the next vertical blank, see `Window::request_redraw_synced`.
+  once: AtomicBool,
+  stop: AtomicBool,
+}
+
+/// A thread requesting redraws of a window after the vertical blanks of the monitor it's on. It
+/// sleeps while there's no request, and stops once the window is destroyed.
+pub struct FrameClock {
+  requests: Arc<Requests>,
+  thread: Thread,
+}
+
+impl FrameClock {
+  pub fn spawn(window: HWND) -> Self {
+    let requests = Arc::<Requests>::default();
+    let thread_requests = requests.clone();
+    let thread = thread::spawn(move || unsafe { run(window, &thread_requests) })
+      .thread()
+      .clone();
+    Self { requests, thread }
+  }
+
+  pub fn set_continuous(&self, enabled: bool) {
+    self.requests.continuous.store(enabled, Ordering::Release);
+    self.thread.unpark();
+  }
+
+  pub fn request_once(&self) {
+    self.requests.once.store(true, Ordering::Release);
+    self.thread.unpark();
+  }
+
+  pub fn stop(&self) {
+    self.requests.stop.store(true, Ordering::Release);
+    self.thread.unpark();
+  }
+}
+
+unsafe fn run(window: HWND, requests: &Requests) {
+  let factory = CreateDXGIFactory1::<IDXGIFactory1>().ok();
+  let mut output: Option<(HMONITOR, IDXGIOutput)> = None;
+  while !requests.stop.load(Ordering::Acquire) && IsWindow(window).as_bool() {
+    let continuous = requests.continuous.load(Ordering::Acquire);
+    if !continuous && !requests.once.load(Ordering::Acquire) {
+      // Woken up by the next request, or by `stop`.
+      thread::park();
+      continue;
+    }
+
+    // A hidden window has no refresh to wait for, its synced redraw isn't delayed.
+    let shown = IsWindowVisible(window).as_bool() && !IsIconic(window).as_bool();
+    if shown || continuous {
+      let monitor = MonitorFromWindow(window, MONITOR_DEFAULTTONEAREST);
+      if output.as_ref().map(|(output_monitor, _)| *output_monitor) != Some(monitor) {
+        output = factory
+          .as_ref()
+          .and_then(|factory| find_output(factory, monitor))
+          .map(|output| (monitor, output));
+      }
+
+      let waited = match &output {
+        Some((_, monitor_output)) => monitor_output.WaitForVBlank().is_ok(),
+        None => false,
+      };
+      if !waited {
+        // The outputs changed, find the one of the monitor again next time.
+        output = None;
+        // Waits for the next composition of the desktop, at the refresh rate of the primary
+        // monitor.
+        if DwmFlush().is_err() {
+          thread::sleep(Duration::from_millis(16));
+        }
+      }
+    }
+
+    if requests.stop.load(Ordering::Acquire) {
+      break;
+    }
+    let once = requests.once.swap(false, Ordering::AcqRel);
+    if once
+      || (requests.continuous.load(Ordering::Acquire)
+        && IsWindowVisible(window).as_bool()
+        && !IsIconic(window).as_bool())
+    {
+      RedrawWindow(window, None, HRGN::default(), RDW_INTERNALPAINT);
+    }
+  }
+}
+
+unsafe fn find_output(factory: &IDXGIFactory1, monitor: HMONITOR) -> Option<IDXGIOutput> {
+  let mut adapter_index = 0;
+  while let Ok(adapter) = factory.EnumAdapters1(adapter_index) {
+    let mut output_index = 0;
+    while let Ok(output) = adapter.EnumOutputs(output_index) {
+      if output
+        .GetDesc()
+        .map_or(false, |desc| desc.Monitor == monitor)
+      {
+        return Some(output);
+      }
+      output_index += 1;
+    }
+    adapter_index += 1;
+  }
+  None
+}
@@ -2,18 +2,34 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{fmt, io, iter::once, mem, os::windows::ffi::OsStrExt, path::Path, sync::Arc};
+use std::{
+  fmt,
+  fs::File,
+  io::{self, BufWriter},
+  iter::once,
+  mem,
+  os::windows::ffi::OsStrExt,
+  path::Path,
+  sync::Arc,
+};
 
 use windows::{
   core::PCWSTR,
   Win32::{
-    Foundation::{HMODULE, HWND, LPARAM, WPARAM},
+    Foundation::{FALSE, HMODULE, HWND, LPARAM, WPARAM},
+    Graphics::Gdi::{
+      CreateBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDIBits, GetObjectW, BITMAP,
+      BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HDC,
+    },
     System::LibraryLoader::*,
     UI::WindowsAndMessaging::*,
   },
 };
 
-use crate::{dpi::PhysicalSize, icon::*};
+use crate::{
+  dpi::{PhysicalPosition, PhysicalSize},
+  icon::*,
+};
 
 impl Pixel {
   fn to_bgra(&mut self) {
@@ -48,6 +64,45 @@ impl RgbaIcon {
       handle.map_err(|_| BadIcon::OsError(io::Error::last_os_error()))?,
     ))
   }
+
+  /// Creates a cursor showing the icon, with `hotspot` under the pointer.
+  pub fn into_windows_cursor(self, hotspot: PhysicalPosition<u32>) -> io::Result<WinCursor> {
+    let mut bgra = self.rgba;
+    for pixel in bgra.chunks_exact_mut(PIXEL_SIZE) {
+      pixel.swap(0, 2);
+    }
+    let (width, height) = (self.width as i32, self.height as i32);
+    // The alpha channel of the color bitmap is the transparency of the cursor, the mask is only
+    // required. Its rows are aligned on 16 bits.
+    let mask = vec![u8::MAX; (((width + 15) / 16) * 2 * height) as usize];
+    unsafe {
+      let color_bitmap = CreateBitmap(width, height, 1, 32, Some(bgra.as_ptr() as _));
+      let mask_bitmap = CreateBitmap(width, height, 1, 1, Some(mask.as_ptr() as _));
+      let icon_info = ICONINFO {
+        fIcon: FALSE,
+        xHotspot: hotspot.x,
+        yHotspot: hotspot.y,
+        hbmMask: mask_bitmap,
+        hbmColor: color_bitmap,
+      };
+      let handle = CreateIconIndirect(&icon_info).map_err(|_| io::Error::last_os_error());
+      DeleteObject(color_bitmap);
+      DeleteObject(mask_bitmap);
+      Ok(WinCursor(Arc::new(RaiiIcon { handle: handle? })))
+    }
+  }
+}
+
+/// A cursor made from an image, destroyed once the last window showing it drops it.
+#[derive(Debug, Clone)]
+pub struct WinCursor(Arc<RaiiIcon>);
+
+unsafe impl Send for WinCursor {}
+
+impl WinCursor {
+  pub fn as_raw_handle(&self) -> HCURSOR {
+    HCURSOR(self.0.handle.0)
+  }
 }
 
 #[non_exhaustive]
@@ -65,6 +120,8 @@ struct RaiiIcon {
 #[derive(Clone)]
 pub struct WinIcon {
   inner: Arc<RaiiIcon>,
+  /// A separate `ICON_SMALL` picked from an icon set.
+  small: Option<Arc<RaiiIcon>>,
 }
 
 unsafe impl Send for WinIcon {}
@@ -74,6 +131,14 @@ impl WinIcon {
     self.inner.handle
   }
 
+  /// The handle of the small icon, which is the big one unless it was picked from an icon set.
+  pub fn as_raw_small_handle(&self) -> HICON {
+    self
+      .small
+      .as_ref()
+      .map_or(self.inner.handle, |small| small.handle)
+  }
+
   pub fn from_path<P: AsRef<Path>>(
     path: P,
     size: Option<PhysicalSize<u32>>,
@@ -128,20 +193,104 @@ impl WinIcon {
     rgba_icon.into_windows_icon()
   }
 
+  pub fn from_rgba_set(icons: Vec<RgbaIcon>) -> Result<Self, BadIcon> {
+    let icons = validate_icon_set(icons)?;
+    let (small_size, big_size) =
+      unsafe { (GetSystemMetrics(SM_CXSMICON), GetSystemMetrics(SM_CXICON)) };
+    let small = best_icon_for_size(&icons, small_size as u32);
+    let big = best_icon_for_size(&icons, big_size as u32);
+
+    let mut icon = big.clone().into_windows_icon()?;
+    if !std::ptr::eq(small, big) {
+      icon.small = Some(small.clone().into_windows_icon()?.inner);
+    }
+    Ok(icon)
+  }
+
   pub fn set_for_window(&self, hwnd: HWND, icon_type: IconType) {
+    let handle = match (&icon_type, &self.small) {
+      (IconType::Small, Some(small)) => small.handle,
+      _ => self.as_raw_handle(),
+    };
+    // The previous icon is owned by its `WinIcon`, which destroys it once the window state
+    // drops it.
     unsafe {
-      SendMessageW(
-        hwnd,
-        WM_SETICON,
-        WPARAM(icon_type as _),
-        LPARAM(self.as_raw_handle().0),
-      );
+      SendMessageW(hwnd, WM_SETICON, WPARAM(icon_type as _), LPARAM(handle.0));
+    }
+  }
+
+  /// Writes the big icon to a PNG file, for the APIs taking an image file.
+  pub fn write_to_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+    let (width, height, rgba) = unsafe { self.to_rgba() }?;
+    let png = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(png), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+      .write_header()
+      .and_then(|mut writer| writer.write_image_data(&rgba))
+      .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+  }
+
+  /// Reads the color bitmap of the big icon, as `(width, height, RGBA)`.
+  unsafe fn to_rgba(&self) -> io::Result<(u32, u32, Vec<u8>)> {
+    let mut icon_info = ICONINFO::default();
+    GetIconInfo(self.as_raw_handle(), &mut icon_info)?;
+    let mut bitmap = BITMAP::default();
+    GetObjectW(
+      icon_info.hbmColor,
+      mem::size_of::<BITMAP>() as i32,
+      Some(&mut bitmap as *mut BITMAP as _),
+    );
+    let (width, height) = (bitmap.bmWidth, bitmap.bmHeight);
+
+    let mut info = BITMAPINFO {
+      bmiHeader: BITMAPINFOHEADER {
+        biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+        biWidth: width,
+        // Negative for rows ordered from the top.
+        biHeight: -height,
+        biPlanes: 1,
+        biBitCount: 32,
+        biCompression: BI_RGB.0,
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let mut bgra = vec![0u8; (width * height * 4).max(0) as usize];
+    let dc = CreateCompatibleDC(HDC::default());
+    let lines = GetDIBits(
+      dc,
+      icon_info.hbmColor,
+      0,
+      height as u32,
+      Some(bgra.as_mut_ptr() as _),
+      &mut info,
+      DIB_RGB_COLORS,
+    );
+    let error = io::Error::last_os_error();
+    DeleteDC(dc);
+    DeleteObject(icon_info.hbmColor);
+    DeleteObject(icon_info.hbmMask);
+    if lines == 0 {
+      return Err(error);
+    }
+
+    // The icons without an alpha channel are opaque, their transparency is in the mask.
+    let opaque = bgra.chunks_exact(4).all(|pixel| pixel[3] == 0);
+    for pixel in bgra.chunks_exact_mut(4) {
+      pixel.swap(0, 2);
+      if opaque {
+        pixel[3] = u8::MAX;
+      }
     }
+    Ok((width as u32, height as u32, bgra))
   }
 
   fn from_handle(handle: HICON) -> Self {
     Self {
       inner: Arc::new(RaiiIcon { handle }),
+      small: None,
     }
   }
 }
@@ -0,0 +1,117 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::mem;
+
+use windows::Win32::UI::{Input::KeyboardAndMouse::*, WindowsAndMessaging::*};
+
+use crate::{
+  error::{ExternalError, NotSupportedError},
+  event::{ElementState, MouseButton, MouseScrollDelta},
+  platform::inject::SyntheticInput,
+};
+
+pub fn inject_input(input: SyntheticInput) -> Result<(), ExternalError> {
+  let inputs = match input {
+    SyntheticInput::Key { code, state } => {
+      let scancode = code
+        .to_scancode()
+        .ok_or(ExternalError::InvalidInput("the key has no scancode"))?;
+      let mut flags = KEYEVENTF_SCANCODE;
+      if scancode & 0xE000 == 0xE000 {
+        flags |= KEYEVENTF_EXTENDEDKEY;
+      }
+      if state == ElementState::Released {
+        flags |= KEYEVENTF_KEYUP;
+      }
+      vec![keyboard_input((scancode & 0xFF) as u16, flags)]
+    }
+    // Each UTF-16 unit is typed as a `VK_PACKET`, which doesn't depend on the keyboard layout.
+    SyntheticInput::Text(text) => text
+      .encode_utf16()
+      .flat_map(|unit| {
+        [
+          keyboard_input(unit, KEYEVENTF_UNICODE),
+          keyboard_input(unit, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP),
+        ]
+      })
+      .collect(),
+    SyntheticInput::CursorMoved(position) => {
+      // Absolute positions are normalized to 0..65535 over the virtual screen.
+      let (left, top, width, height) = unsafe {
+        (
+          GetSystemMetrics(SM_XVIRTUALSCREEN) as f64,
+          GetSystemMetrics(SM_YVIRTUALSCREEN) as f64,
+          GetSystemMetrics(SM_CXVIRTUALSCREEN).max(2) as f64,
+          GetSystemMetrics(SM_CYVIRTUALSCREEN).max(2) as f64,
+        )
+      };
+      vec![mouse_input(
+        ((position.x - left) * 65535.0 / (width - 1.0)).round() as i32,
+        ((position.y - top) * 65535.0 / (height - 1.0)).round() as i32,
+        0,
+        MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE | MOUSEEVENTF_VIRTUALDESK,
+      )]
+    }
+    SyntheticInput::MouseInput { button, state } => {
+      let pressed = state == ElementState::Pressed;
+      let (flags, data) = match (button, pressed) {
+        (MouseButton::Left, true) => (MOUSEEVENTF_LEFTDOWN, 0),
+        (MouseButton::Left, false) => (MOUSEEVENTF_LEFTUP, 0),
+        (MouseButton::Right, true) => (MOUSEEVENTF_RIGHTDOWN, 0),
+        (MouseButton::Right, false) => (MOUSEEVENTF_RIGHTUP, 0),
+        (MouseButton::Middle, true) => (MOUSEEVENTF_MIDDLEDOWN, 0),
+        (MouseButton::Middle, false) => (MOUSEEVENTF_MIDDLEUP, 0),
+        // `WM_XBUTTONDOWN` reports `XBUTTON1` and `XBUTTON2` as `Other(1)` and `Other(2)`.
+        (MouseButton::Other(xbutton @ (1 | 2)), true) => (MOUSEEVENTF_XDOWN, xbutton as i32),
+        (MouseButton::Other(xbutton @ (1 | 2)), false) => (MOUSEEVENTF_XUP, xbutton as i32),
+        (MouseButton::Other(_), _) => {
+          return Err(ExternalError::InvalidInput(
+            "the mouse button doesn't exist",
+          ))
+        }
+      };
+      vec![mouse_input(0, 0, data, flags)]
+    }
+    SyntheticInput::MouseWheel(MouseScrollDelta::LineDelta(x, y)) => {
+      let mut inputs = Vec::new();
+      if y != 0.0 {
+        let data = (y * WHEEL_DELTA as f32).round() as i32;
+        inputs.push(mouse_input(0, 0, data, MOUSEEVENTF_WHEEL));
+      }
+      if x != 0.0 {
+        let data = (x * WHEEL_DELTA as f32).round() as i32;
+        inputs.push(mouse_input(0, 0, data, MOUSEEVENTF_HWHEEL));
+      }
+      inputs
+    }
+    SyntheticInput::MouseWheel(_) => {
+      return Err(ExternalError::NotSupported(NotSupportedError::new()))
+    }
+  };
+
+  let sent = unsafe { SendInput(&inputs, mem::size_of::<INPUT>() as _) };
+  // The input is blocked by another thread or by UIPI.
+  if sent as usize != inputs.len() {
+    return Err(windows::core::Error::from_win32().into());
+  }
+  Ok(())
+}
+
+fn keyboard_input(scan: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+  let mut input: INPUT = unsafe { mem::zeroed() };
+  input.r#type = INPUT_KEYBOARD;
+  input.Anonymous.ki.wScan = scan;
+  input.Anonymous.ki.dwFlags = flags;
+  input
+}
+
+fn mouse_input(dx: i32, dy: i32, data: i32, flags: MOUSE_EVENT_FLAGS) -> INPUT {
+  let mut input: INPUT = unsafe { mem::zeroed() };
+  input.r#type = INPUT_MOUSE;
+  input.Anonymous.mi.dx = dx;
+  input.Anonymous.mi.dy = dy;
+  input.Anonymous.mi.mouseData = data as _;
+  input.Anonymous.mi.dwFlags = flags;
+  input
+}
@@ -20,7 +20,7 @@ use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
   event::{ElementState, KeyEvent},
-  keyboard::{Key, KeyCode, KeyLocation, NativeKeyCode},
+  keyboard::{Key, KeyCode, KeyLocation, ModifiersState, NativeKeyCode},
   platform_impl::{
     platform::{
       event_loop::ProcResult,
@@ -661,6 +661,8 @@ impl PartialKeyEventInfo {
       location: self.location,
       state: self.key_state,
       repeat: self.is_repeat,
+      // Set by the event loop, which tracks the state reported by `ModifiersChanged`.
+      modifiers: ModifiersState::empty(),
       platform_specific: KeyEventExtra {
         text_with_all_modifiers: char_with_all_modifiers,
         key_without_modifiers: self.key_without_modifiers,
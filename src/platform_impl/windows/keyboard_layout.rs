@@ -17,7 +17,7 @@ use windows::Win32::{
 
 use super::keyboard::ExScancode;
 use crate::{
-  keyboard::{Key, KeyCode, ModifiersState, NativeKeyCode},
+  keyboard::{Key, KeyCode, KeyboardLayout, ModifiersState, NativeKeyCode},
   platform_impl::platform::util,
 };
 
@@ -25,6 +25,16 @@ lazy_static! {
   pub(crate) static ref LAYOUT_CACHE: Mutex<LayoutCache> = Mutex::new(LayoutCache::default());
 }
 
+/// The active keyboard layout of the calling thread, identified by its KLID.
+pub fn keyboard_layout() -> Option<KeyboardLayout> {
+  let mut klid = [0u16; KL_NAMELENGTH as usize];
+  unsafe { GetKeyboardLayoutNameW(&mut klid) }.ok()?;
+  let len = klid.iter().position(|c| *c == 0).unwrap_or(klid.len());
+  Some(KeyboardLayout {
+    id: String::from_utf16_lossy(&klid[..len]),
+  })
+}
+
 fn key_pressed(vkey: VIRTUAL_KEY) -> bool {
   unsafe { (GetKeyState(u32::from(vkey.0) as i32) & (1 << 15)) == (1 << 15) }
 }
@@ -0,0 +1,318 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  cell::{Cell, RefCell},
+  collections::HashMap,
+  mem,
+};
+
+use windows::{
+  core::PCWSTR,
+  Win32::{
+    Foundation::{HWND, POINT},
+    Graphics::Gdi::ClientToScreen,
+    UI::WindowsAndMessaging::*,
+  },
+};
+
+use super::util;
+use crate::{event::Event, menu::MenuId};
+
+/// The `dwItemData` of the items with a check mark.
+const CHECKABLE: usize = 1;
+
+thread_local! {
+  /// The windows by menu bar, to redraw a menu bar after changing one of its submenus.
+  static MENU_BARS: RefCell<HashMap<isize, HWND>> = RefCell::new(HashMap::new());
+}
+
+/// Creates `hmenu` with the `MNS_NOTIFYBYPOS` style, so that choosing an item sends
+/// `WM_MENUCOMMAND` with its position rather than `WM_COMMAND`, whose id only has 16 bits.
+unsafe fn notify_by_position(hmenu: HMENU) -> HMENU {
+  let info = MENUINFO {
+    cbSize: mem::size_of::<MENUINFO>() as u32,
+    fMask: MIM_STYLE,
+    dwStyle: MNS_NOTIFYBYPOS,
+    ..Default::default()
+  };
+  let _ = SetMenuInfo(hmenu, &info);
+  hmenu
+}
+
+/// Redraws the menu bar of the window showing `hmenu`, if it's one.
+fn redraw(hmenu: HMENU) {
+  if let Some(hwnd) = MENU_BARS.with(|bars| bars.borrow().get(&hmenu.0).copied()) {
+    let _ = unsafe { DrawMenuBar(hwnd) };
+  }
+}
+
+/// The position of the item opening `submenu` in `hmenu`.
+fn submenu_position(hmenu: HMENU, submenu: HMENU) -> Option<u32> {
+  let count = unsafe { GetMenuItemCount(hmenu) };
+  (0..count.max(0)).find_map(|i| (unsafe { GetSubMenu(hmenu, i) } == submenu).then(|| i as u32))
+}
+
+pub struct Menu {
+  hmenu: HMENU,
+}
+
+impl Menu {
+  pub fn new() -> Self {
+    let hmenu = unsafe { CreateMenu().unwrap_or_default() };
+    Self {
+      hmenu: unsafe { notify_by_position(hmenu) },
+    }
+  }
+
+  pub fn append_submenu(&self, submenu: &Submenu) {
+    submenu.append_to(self.hmenu);
+  }
+
+  /// Sets the menu as the menu bar of `hwnd`, which owns it from then on. The menu bar it
+  /// replaces is destroyed, as the window would when it's destroyed.
+  pub fn set_for_window(menu: Option<&Menu>, hwnd: HWND) {
+    let hmenu = menu.map_or(HMENU::default(), |menu| menu.hmenu);
+    let previous = unsafe { GetMenu(hwnd) };
+    MENU_BARS.with(|bars| {
+      let mut bars = bars.borrow_mut();
+      bars.retain(|_, window| *window != hwnd);
+      if menu.is_some() {
+        bars.insert(hmenu.0, hwnd);
+      }
+    });
+    unsafe {
+      let _ = SetMenu(hwnd, hmenu);
+      let _ = DrawMenuBar(hwnd);
+      if !previous.is_invalid() && previous != hmenu {
+        let _ = DestroyMenu(previous);
+      }
+    }
+  }
+}
+
+pub struct Submenu {
+  hmenu: HMENU,
+  title: RefCell<String>,
+  enabled: Cell<bool>,
+  parent: Cell<Option<HMENU>>,
+}
+
+impl Submenu {
+  pub fn new(title: &str) -> Self {
+    let hmenu = unsafe { CreatePopupMenu().unwrap_or_default() };
+    Self {
+      hmenu: unsafe { notify_by_position(hmenu) },
+      title: RefCell::new(title.to_owned()),
+      enabled: Cell::new(true),
+      parent: Cell::new(None),
+    }
+  }
+
+  fn append_to(&self, parent: HMENU) {
+    if self.parent.get().is_some() {
+      return;
+    }
+    self.parent.set(Some(parent));
+    let title = util::encode_wide(&*self.title.borrow());
+    let flags = if self.enabled.get() {
+      MF_POPUP | MF_STRING
+    } else {
+      MF_POPUP | MF_STRING | MF_GRAYED
+    };
+    unsafe {
+      let _ = AppendMenuW(
+        parent,
+        flags,
+        self.hmenu.0 as usize,
+        PCWSTR::from_raw(title.as_ptr()),
+      );
+    }
+    redraw(parent);
+  }
+
+  pub fn append_item(&self, item: &MenuItem) {
+    item.append_to(self.hmenu);
+  }
+
+  pub fn append_submenu(&self, submenu: &Submenu) {
+    submenu.append_to(self.hmenu);
+  }
+
+  pub fn append_separator(&self) {
+    unsafe {
+      let _ = AppendMenuW(self.hmenu, MF_SEPARATOR, 0, PCWSTR::null());
+    }
+  }
+
+  pub fn set_title(&self, title: &str) {
+    *self.title.borrow_mut() = title.to_owned();
+    self.update(|parent, position| {
+      let title = util::encode_wide(title);
+      unsafe {
+        let _ = ModifyMenuW(
+          parent,
+          position,
+          MF_BYPOSITION | MF_POPUP | MF_STRING,
+          self.hmenu.0 as usize,
+          PCWSTR::from_raw(title.as_ptr()),
+        );
+      }
+    });
+  }
+
+  pub fn set_enabled(&self, enabled: bool) {
+    self.enabled.set(enabled);
+    self.update(|parent, position| {
+      let flag = if enabled { MF_ENABLED } else { MF_GRAYED };
+      unsafe { EnableMenuItem(parent, position, MF_BYPOSITION | flag) };
+    });
+  }
+
+  fn update(&self, f: impl FnOnce(HMENU, u32)) {
+    if let Some(parent) = self.parent.get() {
+      if let Some(position) = submenu_position(parent, self.hmenu) {
+        f(parent, position);
+        redraw(parent);
+      }
+    }
+  }
+
+  /// Shows the menu at `position`, in physical pixels from the top left of the client area of
+  /// `hwnd`, until it's closed.
+  pub fn pop_up(&self, hwnd: HWND, (x, y): (i32, i32)) {
+    let mut point = POINT { x, y };
+    unsafe { ClientToScreen(hwnd, &mut point) };
+    track_pop_up(self.hmenu, hwnd, point);
+  }
+
+  pub(crate) fn hmenu(&self) -> HMENU {
+    self.hmenu
+  }
+}
+
+/// Shows `hmenu` at `point`, in physical screen coordinates, until it's closed. The chosen item
+/// is reported to `hwnd` with `WM_MENUCOMMAND`.
+pub fn track_pop_up(hmenu: HMENU, hwnd: HWND, point: POINT) {
+  unsafe {
+    // Otherwise the menu doesn't close when clicking elsewhere.
+    SetForegroundWindow(hwnd);
+    TrackPopupMenu(hmenu, TPM_LEFTALIGN, point.x, point.y, 0, hwnd, None);
+  }
+}
+
+pub struct MenuItem {
+  id: u32,
+  title: RefCell<String>,
+  enabled: Cell<bool>,
+  /// `None` for the items without a check mark.
+  checked: Cell<Option<bool>>,
+  parent: Cell<Option<HMENU>>,
+}
+
+impl MenuItem {
+  pub fn new(id: u32, title: &str, checked: Option<bool>) -> Self {
+    Self {
+      id,
+      title: RefCell::new(title.to_owned()),
+      enabled: Cell::new(true),
+      checked: Cell::new(checked),
+      parent: Cell::new(None),
+    }
+  }
+
+  fn append_to(&self, parent: HMENU) {
+    if self.parent.get().is_some() {
+      return;
+    }
+    self.parent.set(Some(parent));
+    let title = util::encode_wide(&*self.title.borrow());
+    let mut flags = MF_STRING;
+    if !self.enabled.get() {
+      flags |= MF_GRAYED;
+    }
+    if self.checked.get() == Some(true) {
+      flags |= MF_CHECKED;
+    }
+    unsafe {
+      let _ = AppendMenuW(
+        parent,
+        flags,
+        self.id as usize,
+        PCWSTR::from_raw(title.as_ptr()),
+      );
+      if self.checked.get().is_some() {
+        let info = MENUITEMINFOW {
+          cbSize: mem::size_of::<MENUITEMINFOW>() as u32,
+          fMask: MIIM_DATA,
+          dwItemData: CHECKABLE,
+          ..Default::default()
+        };
+        let _ = SetMenuItemInfoW(parent, self.id, false, &info);
+      }
+    }
+  }
+
+  pub fn set_title(&self, title: &str) {
+    *self.title.borrow_mut() = title.to_owned();
+    if let Some(parent) = self.parent.get() {
+      let title = util::encode_wide(title);
+      let info = MENUITEMINFOW {
+        cbSize: mem::size_of::<MENUITEMINFOW>() as u32,
+        fMask: MIIM_STRING,
+        dwTypeData: windows::core::PWSTR(title.as_ptr() as *mut _),
+        ..Default::default()
+      };
+      let _ = unsafe { SetMenuItemInfoW(parent, self.id, false, &info) };
+    }
+  }
+
+  pub fn set_enabled(&self, enabled: bool) {
+    self.enabled.set(enabled);
+    if let Some(parent) = self.parent.get() {
+      let flag = if enabled { MF_ENABLED } else { MF_GRAYED };
+      unsafe { EnableMenuItem(parent, self.id, MF_BYCOMMAND | flag) };
+    }
+  }
+
+  pub fn set_checked(&self, checked: bool) {
+    if self.checked.get().is_none() {
+      return;
+    }
+    self.checked.set(Some(checked));
+    if let Some(parent) = self.parent.get() {
+      let flag = if checked { MF_CHECKED } else { MF_UNCHECKED };
+      unsafe { CheckMenuItem(parent, self.id, (MF_BYCOMMAND | flag).0) };
+    }
+  }
+
+  pub fn is_checked(&self) -> bool {
+    match (self.checked.get(), self.parent.get()) {
+      (Some(_), Some(parent)) => unsafe {
+        GetMenuState(parent, self.id, MF_BYCOMMAND) & MF_CHECKED.0 != 0
+      },
+      (checked, _) => checked.unwrap_or(false),
+    }
+  }
+}
+
+/// Handles a `WM_MENUCOMMAND` for the item at `position` in `hmenu`, toggling its check mark.
+pub fn menu_command<T>(hmenu: HMENU, position: u32) -> Option<Event<'static, T>> {
+  let mut info = MENUITEMINFOW {
+    cbSize: mem::size_of::<MENUITEMINFOW>() as u32,
+    fMask: MIIM_ID | MIIM_DATA | MIIM_STATE,
+    ..Default::default()
+  };
+  unsafe { GetMenuItemInfoW(hmenu, position, true, &mut info).ok()? };
+  if info.dwItemData == CHECKABLE {
+    let flag = if info.fState.0 & MFS_CHECKED.0 != 0 {
+      MF_UNCHECKED
+    } else {
+      MF_CHECKED
+    };
+    unsafe { CheckMenuItem(hmenu, position, (MF_BYPOSITION | flag).0) };
+  }
+  Some(Event::MenuEvent {
+    menu_id: MenuId(info.wID),
+  })
+}
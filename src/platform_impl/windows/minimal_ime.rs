@@ -1,16 +1,31 @@
-use std::mem::MaybeUninit;
+use std::{
+  ffi::c_void,
+  mem::{self, MaybeUninit},
+};
 
 use windows::Win32::{
   Foundation::{HWND, LPARAM, LRESULT, WPARAM},
-  UI::WindowsAndMessaging::{self as win32wm, *},
+  Globalization::HIMC,
+  UI::{
+    Input::Ime::{
+      ImmGetCompositionStringW, ImmGetContext, ImmReleaseContext, GCS_COMPSTR, GCS_CURSORPOS,
+      GCS_RESULTSTR, IME_COMPOSITION_STRING, ISC_SHOWUICOMPOSITIONWINDOW,
+    },
+    Shell::DefSubclassProc,
+    WindowsAndMessaging::{self as win32wm, *},
+  },
 };
 
-use crate::platform_impl::platform::event_loop::ProcResult;
+use crate::{
+  event::{Ime, WindowEvent},
+  platform_impl::platform::event_loop::ProcResult,
+};
 
 pub fn is_msg_ime_related(msg_kind: u32) -> bool {
   matches!(
     msg_kind,
-    win32wm::WM_IME_COMPOSITION
+    win32wm::WM_IME_SETCONTEXT
+      | win32wm::WM_IME_COMPOSITION
       | win32wm::WM_IME_COMPOSITIONFULL
       | win32wm::WM_IME_STARTCOMPOSITION
       | win32wm::WM_IME_ENDCOMPOSITION
@@ -25,24 +40,77 @@ pub struct MinimalIme {
   getting_ime_text: bool,
 
   utf16parts: Vec<u16>,
+
+  /// Whether the composition is reported with `WindowEvent::Ime`, see `Window::set_ime_allowed`.
+  allowed: bool,
+
+  /// True if an `Ime::Preedit` with text was reported and not cleared yet.
+  preediting: bool,
 }
 impl Default for MinimalIme {
   fn default() -> Self {
     MinimalIme {
       getting_ime_text: false,
       utf16parts: Vec::with_capacity(16),
+      allowed: false,
+      preediting: false,
     }
   }
 }
 impl MinimalIme {
+  /// Returns the events reporting the change, if `allowed` changed.
+  pub(crate) fn set_allowed(&mut self, allowed: bool) -> Vec<WindowEvent<'static>> {
+    if self.allowed == allowed {
+      return Vec::new();
+    }
+    self.allowed = allowed;
+    if allowed {
+      vec![WindowEvent::Ime(Ime::Enabled)]
+    } else {
+      let mut events = self.clear_preedit();
+      events.push(WindowEvent::Ime(Ime::Disabled));
+      events
+    }
+  }
+
+  fn clear_preedit(&mut self) -> Vec<WindowEvent<'static>> {
+    if mem::take(&mut self.preediting) {
+      vec![WindowEvent::Ime(Ime::Preedit(String::new(), None))]
+    } else {
+      Vec::new()
+    }
+  }
+
   pub(crate) fn process_message(
     &mut self,
     hwnd: HWND,
     msg_kind: u32,
     wparam: WPARAM,
-    _lparam: LPARAM,
+    lparam: LPARAM,
     result: &mut ProcResult,
-  ) -> Option<String> {
+  ) -> Vec<WindowEvent<'static>> {
+    if self.allowed {
+      match msg_kind {
+        win32wm::WM_IME_SETCONTEXT => {
+          // The text being composed is reported rather than drawn by the IME.
+          let lparam = LPARAM(lparam.0 & !(ISC_SHOWUICOMPOSITIONWINDOW as isize));
+          *result = ProcResult::Value(unsafe { DefSubclassProc(hwnd, msg_kind, wparam, lparam) });
+          return Vec::new();
+        }
+        win32wm::WM_IME_STARTCOMPOSITION => {
+          *result = ProcResult::Value(LRESULT(0));
+          return Vec::new();
+        }
+        // Not passed on, otherwise the committed text would also arrive with `WM_CHAR`.
+        win32wm::WM_IME_COMPOSITION => {
+          *result = ProcResult::Value(LRESULT(0));
+          return unsafe { self.composition(hwnd, lparam) };
+        }
+        win32wm::WM_IME_ENDCOMPOSITION => return self.clear_preedit(),
+        _ => (),
+      }
+    }
+
     match msg_kind {
       win32wm::WM_IME_ENDCOMPOSITION => {
         self.getting_ime_text = true;
@@ -74,15 +142,76 @@ impl MinimalIme {
             let result = String::from_utf16(&self.utf16parts).ok();
             self.utf16parts.clear();
             self.getting_ime_text = false;
-            return result;
+            return result
+              .map(WindowEvent::ReceivedImeText)
+              .into_iter()
+              .collect();
           }
         } else {
-          return String::from_utf16(&[wparam.0 as u16]).ok();
+          return String::from_utf16(&[wparam.0 as u16])
+            .ok()
+            .map(WindowEvent::ReceivedImeText)
+            .into_iter()
+            .collect();
         }
       }
       _ => (),
     }
 
-    None
+    Vec::new()
+  }
+
+  /// Reports the strings of a `WM_IME_COMPOSITION` flagged by `lparam`, the committed one first.
+  unsafe fn composition(&mut self, hwnd: HWND, lparam: LPARAM) -> Vec<WindowEvent<'static>> {
+    let flags = lparam.0 as u32;
+    let himc = ImmGetContext(hwnd);
+    let mut events = Vec::new();
+    if flags & GCS_RESULTSTR.0 != 0 {
+      if let Some(text) = composition_string(himc, GCS_RESULTSTR) {
+        events.extend(self.clear_preedit());
+        events.push(WindowEvent::Ime(Ime::Commit(String::from_utf16_lossy(
+          &text,
+        ))));
+      }
+    }
+    if flags & GCS_COMPSTR.0 != 0 {
+      if let Some(text) = composition_string(himc, GCS_COMPSTR) {
+        if text.is_empty() {
+          events.extend(self.clear_preedit());
+        } else {
+          // The cursor is counted in UTF-16 code units.
+          let cursor = if flags & GCS_CURSORPOS.0 != 0 {
+            let cursor = ImmGetCompositionStringW(himc, GCS_CURSORPOS, None, 0) & 0xffff;
+            let cursor = String::from_utf16_lossy(&text[..(cursor as usize).min(text.len())]);
+            Some((cursor.len(), cursor.len()))
+          } else {
+            None
+          };
+          self.preediting = true;
+          events.push(WindowEvent::Ime(Ime::Preedit(
+            String::from_utf16_lossy(&text),
+            cursor,
+          )));
+        }
+      }
+    }
+    ImmReleaseContext(hwnd, himc);
+    events
+  }
+}
+
+/// Returns the composition string of `kind`, `None` if there's none.
+unsafe fn composition_string(himc: HIMC, kind: IME_COMPOSITION_STRING) -> Option<Vec<u16>> {
+  let len = ImmGetCompositionStringW(himc, kind, None, 0);
+  if len < 0 {
+    return None;
   }
+  let mut text = vec![0u16; len as usize / mem::size_of::<u16>()];
+  ImmGetCompositionStringW(
+    himc,
+    kind,
+    Some(text.as_mut_ptr() as *mut c_void),
+    len as u32,
+  );
+  Some(text)
 }
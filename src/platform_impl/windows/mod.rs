@@ -10,13 +10,19 @@ use windows::Win32::{
 };
 
 pub(crate) use self::{
+  clipboard::Clipboard,
   event_loop::{
-    EventLoop, EventLoopProxy, EventLoopWindowTarget, PlatformSpecificEventLoopAttributes,
+    EventLoop, EventLoopProxy, EventLoopWaker, EventLoopWindowTarget,
+    PlatformSpecificEventLoopAttributes,
   },
   icon::WinIcon,
   keycode::{keycode_from_scancode, keycode_to_scancode},
+  menu::{Menu, MenuItem, Submenu},
   monitor::{MonitorHandle, VideoMode},
-  window::Window,
+  notification::Notification,
+  power::PowerAssertion,
+  system_tray::SystemTray,
+  window::{AccessibilityActionSender, Window},
 };
 
 pub use self::icon::WinIcon as PlatformIcon;
@@ -37,12 +43,13 @@ pub struct PlatformSpecificWindowBuilderAttributes {
   pub parent: Parent,
   pub menu: Option<HMENU>,
   pub taskbar_icon: Option<Icon>,
-  pub skip_taskbar: bool,
   pub window_classname: String,
   pub no_redirection_bitmap: bool,
   pub drag_and_drop: bool,
   pub decoration_shadow: bool,
   pub rtl: bool,
+  pub no_background_erase: bool,
+  pub resize_redraw_sync: bool,
 }
 
 impl Default for PlatformSpecificWindowBuilderAttributes {
@@ -53,10 +60,11 @@ impl Default for PlatformSpecificWindowBuilderAttributes {
       taskbar_icon: None,
       no_redirection_bitmap: false,
       drag_and_drop: true,
-      skip_taskbar: false,
       window_classname: "Window Class".to_string(),
       decoration_shadow: true,
       rtl: false,
+      no_background_erase: false,
+      resize_redraw_sync: false,
     }
   }
 }
@@ -147,19 +155,36 @@ impl WindowId {
   pub unsafe fn dummy() -> Self {
     WindowId(0)
   }
+
+  pub fn into_raw(self) -> u64 {
+    self.0 as u64
+  }
+
+  pub fn from_raw(raw: u64) -> Self {
+    WindowId(raw as isize)
+  }
 }
 
 #[macro_use]
 mod util;
+mod clipboard;
 mod dark_mode;
 mod dpi;
+mod drag_source;
 mod drop_handler;
 mod event_loop;
+mod frame_clock;
 mod icon;
+mod inject;
 mod keyboard;
 mod keyboard_layout;
+mod menu;
 mod minimal_ime;
 mod monitor;
+mod notification;
+mod power;
 mod raw_input;
+mod single_instance;
+mod system_tray;
 mod window;
 mod window_state;
@@ -5,7 +5,8 @@
 use windows::{
   core::PCWSTR,
   Win32::{
-    Foundation::{BOOL, HWND, LPARAM, POINT, RECT},
+    Devices::Display::*,
+    Foundation::{BOOL, ERROR_SUCCESS, HWND, LPARAM, POINT, RECT},
     Graphics::Gdi::*,
   },
 };
@@ -15,14 +16,16 @@ use std::{
   io, mem,
 };
 
-use super::util;
+use super::{util, OsError};
 use crate::{
   dpi::{PhysicalPosition, PhysicalSize},
+  error::ExternalError,
   monitor::{MonitorHandle as RootMonitorHandle, VideoMode as RootVideoMode},
   platform_impl::platform::{
     dpi::{dpi_to_scale_factor, get_monitor_dpi},
-    window::Window,
+    window::{bitmap_to_rgba, Window},
   },
+  window::RgbaImage,
 };
 
 #[derive(Clone)]
@@ -171,6 +174,58 @@ pub(crate) fn get_monitor_info(hmonitor: HMONITOR) -> Result<MONITORINFOEXW, io:
   }
 }
 
+/// Looks up the path from the GDI device `device_name`, e.g. `\\.\DISPLAY1`, to the display
+/// target (the monitor itself) in the active display configuration.
+fn display_path(device_name: &str) -> Option<DISPLAYCONFIG_PATH_INFO> {
+  unsafe {
+    let (mut path_count, mut mode_count) = (0, 0);
+    if GetDisplayConfigBufferSizes(QDC_ONLY_ACTIVE_PATHS, &mut path_count, &mut mode_count)
+      != ERROR_SUCCESS
+    {
+      return None;
+    }
+    let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+    let mut modes = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+    if QueryDisplayConfig(
+      QDC_ONLY_ACTIVE_PATHS,
+      &mut path_count,
+      paths.as_mut_ptr(),
+      &mut mode_count,
+      modes.as_mut_ptr(),
+      None,
+    ) != ERROR_SUCCESS
+    {
+      return None;
+    }
+    paths.truncate(path_count as usize);
+
+    paths.into_iter().find(|path| {
+      let mut source = DISPLAYCONFIG_SOURCE_DEVICE_NAME::default();
+      source.header.r#type = DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME;
+      source.header.size = mem::size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>() as u32;
+      source.header.adapterId = path.sourceInfo.adapterId;
+      source.header.id = path.sourceInfo.id;
+      DisplayConfigGetDeviceInfo(&mut source.header) == 0
+        && util::wchar_ptr_to_string(PCWSTR::from_raw(source.viewGdiDeviceName.as_ptr()))
+          == device_name
+    })
+  }
+}
+
+/// Looks up the display target shown by the GDI device `device_name`, see `display_path`.
+fn display_target(device_name: &str) -> Option<DISPLAYCONFIG_TARGET_DEVICE_NAME> {
+  let path = display_path(device_name)?;
+  let mut target = DISPLAYCONFIG_TARGET_DEVICE_NAME::default();
+  target.header.r#type = DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME;
+  target.header.size = mem::size_of::<DISPLAYCONFIG_TARGET_DEVICE_NAME>() as u32;
+  target.header.adapterId = path.targetInfo.adapterId;
+  target.header.id = path.targetInfo.id;
+  if unsafe { DisplayConfigGetDeviceInfo(&mut target.header) } != 0 {
+    return None;
+  }
+  Some(target)
+}
+
 impl MonitorHandle {
   pub(crate) fn new(hmonitor: HMONITOR) -> Self {
     MonitorHandle(hmonitor.0)
@@ -178,7 +233,29 @@ impl MonitorHandle {
 
   #[inline]
   pub fn name(&self) -> Option<String> {
-    let monitor_info = get_monitor_info(self.hmonitor()).unwrap();
+    let device_name = self.device_name()?;
+    let friendly_name = display_target(&device_name)
+      .map(|target| {
+        util::wchar_ptr_to_string(PCWSTR::from_raw(target.monitorFriendlyDeviceName.as_ptr()))
+      })
+      .filter(|name| !name.is_empty());
+    Some(friendly_name.unwrap_or(device_name))
+  }
+
+  pub fn id(&self) -> u64 {
+    let device_name = self.device_name().unwrap_or_default();
+    // The device path looks like `\\?\DISPLAY#DELA0F3#5&2a3e8b6&0&UID4353#{...}`: the EDID
+    // manufacturer and product codes followed by the connector, which survive reconnects.
+    let key = display_target(&device_name)
+      .map(|target| util::wchar_ptr_to_string(PCWSTR::from_raw(target.monitorDevicePath.as_ptr())))
+      .filter(|path| !path.is_empty())
+      .unwrap_or(device_name);
+    crate::monitor::stable_id(key.as_bytes())
+  }
+
+  /// The GDI device name, e.g. `\\.\DISPLAY1`.
+  fn device_name(&self) -> Option<String> {
+    let monitor_info = get_monitor_info(self.hmonitor()).ok()?;
     Some(util::wchar_ptr_to_string(PCWSTR::from_raw(
       monitor_info.szDevice.as_ptr(),
     )))
@@ -186,7 +263,7 @@ impl MonitorHandle {
 
   #[inline]
   pub fn native_identifier(&self) -> String {
-    self.name().unwrap()
+    self.device_name().unwrap()
   }
 
   #[inline]
@@ -196,7 +273,8 @@ impl MonitorHandle {
 
   #[inline]
   pub fn size(&self) -> PhysicalSize<u32> {
-    let monitor_info = get_monitor_info(self.hmonitor()).unwrap();
+    // Empty for a monitor that was disconnected, see `Event::MonitorDisconnected`.
+    let monitor_info = get_monitor_info(self.hmonitor()).unwrap_or_default();
     PhysicalSize {
       width: (monitor_info.monitorInfo.rcMonitor.right - monitor_info.monitorInfo.rcMonitor.left)
         as u32,
@@ -207,7 +285,7 @@ impl MonitorHandle {
 
   #[inline]
   pub fn position(&self) -> PhysicalPosition<i32> {
-    let monitor_info = get_monitor_info(self.hmonitor()).unwrap();
+    let monitor_info = get_monitor_info(self.hmonitor()).unwrap_or_default();
     PhysicalPosition {
       x: monitor_info.monitorInfo.rcMonitor.left,
       y: monitor_info.monitorInfo.rcMonitor.top,
@@ -219,6 +297,40 @@ impl MonitorHandle {
     dpi_to_scale_factor(get_monitor_dpi(self.hmonitor()).unwrap_or(96))
   }
 
+  pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+    let device_name = self.device_name()?;
+    // The rate of the target is exact, e.g. 60000/1001 Hz, unlike the one of the GDI mode.
+    if let Some(path) = display_path(&device_name) {
+      let rate = path.targetInfo.refreshRate;
+      if rate.Numerator != 0 && rate.Denominator != 0 {
+        return Some((u64::from(rate.Numerator) * 1000 / u64::from(rate.Denominator)) as u32);
+      }
+    }
+    let device_name = util::encode_wide(device_name);
+    unsafe {
+      let mut mode: DEVMODEW = mem::zeroed();
+      mode.dmSize = mem::size_of_val(&mode) as u16;
+      if !EnumDisplaySettingsExW(
+        PCWSTR::from_raw(device_name.as_ptr()),
+        ENUM_CURRENT_SETTINGS,
+        &mut mode,
+        ENUM_DISPLAY_SETTINGS_FLAGS(0),
+      )
+      .as_bool()
+      {
+        return None;
+      }
+      // 0 and 1 stand for the default rate of the hardware.
+      (mode.dmDisplayFrequency > 1).then(|| mode.dmDisplayFrequency * 1000)
+    }
+  }
+
+  pub fn capture(&self) -> Result<RgbaImage, ExternalError> {
+    get_monitor_info(self.hmonitor())
+      .and_then(|monitor_info| unsafe { capture_screen(monitor_info.monitorInfo.rcMonitor) })
+      .map_err(|e| ExternalError::Os(os_error!(OsError::IoError(e))))
+  }
+
   #[inline]
   pub fn video_modes(&self) -> impl Iterator<Item = RootVideoMode> {
     // EnumDisplaySettingsExW can return duplicate values (or some of the
@@ -229,7 +341,10 @@ impl MonitorHandle {
 
     loop {
       unsafe {
-        let monitor_info = get_monitor_info(self.hmonitor()).unwrap();
+        let monitor_info = match get_monitor_info(self.hmonitor()) {
+          Ok(monitor_info) => monitor_info,
+          Err(_) => break,
+        };
         let device_name = PCWSTR::from_raw(monitor_info.szDevice.as_ptr());
         let mut mode: DEVMODEW = mem::zeroed();
         mode.dmSize = mem::size_of_val(&mode) as u16;
@@ -263,3 +378,49 @@ impl MonitorHandle {
     modes.into_iter()
   }
 }
+
+/// Copies `rect` of the virtual screen, as the compositor shows it.
+unsafe fn capture_screen(rect: RECT) -> Result<RgbaImage, io::Error> {
+  let width = rect.right - rect.left;
+  let height = rect.bottom - rect.top;
+
+  let screen_dc = GetDC(HWND::default());
+  let dc = CreateCompatibleDC(screen_dc);
+  let bitmap = CreateCompatibleBitmap(screen_dc, width, height);
+  let previous = SelectObject(dc, bitmap);
+  // `CAPTUREBLT` includes the layered windows.
+  let copied = BitBlt(
+    dc,
+    0,
+    0,
+    width,
+    height,
+    screen_dc,
+    rect.left,
+    rect.top,
+    SRCCOPY | CAPTUREBLT,
+  )
+  .as_bool();
+  SelectObject(dc, previous);
+  ReleaseDC(HWND::default(), screen_dc);
+
+  let image = if copied {
+    bitmap_to_rgba(
+      dc,
+      bitmap,
+      width,
+      height,
+      RECT {
+        left: 0,
+        top: 0,
+        right: width,
+        bottom: height,
+      },
+    )
+  } else {
+    Err(io::Error::last_os_error())
+  };
+  DeleteObject(bitmap);
+  DeleteDC(dc);
+  image
+}
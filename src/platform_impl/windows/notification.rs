@@ -0,0 +1,313 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  collections::HashMap,
+  env,
+  fmt::Write,
+  fs,
+  path::{Path, PathBuf},
+  process,
+  sync::{Mutex, PoisonError},
+};
+
+use windows::{
+  core::{ComInterface, IInspectable, HSTRING},
+  Data::Xml::Dom::XmlDocument,
+  Foundation::TypedEventHandler,
+  Win32::{
+    Foundation::{HWND, LPARAM, WPARAM},
+    System::Com::CoTaskMemFree,
+    UI::{Shell::GetCurrentProcessExplicitAppUserModelID, WindowsAndMessaging::PostMessageW},
+  },
+  UI::Notifications::{
+    ToastActivatedEventArgs, ToastDismissalReason, ToastDismissedEventArgs, ToastFailedEventArgs,
+    ToastNotification, ToastNotificationManager, ToastNotifier,
+  },
+};
+
+use super::event_loop::{EventLoopWindowTarget, NOTIFICATION_MSG_ID};
+use crate::{error::ExternalError, notification::NotificationAttributes};
+
+/// The application user model id of Windows PowerShell, which has a shortcut in the start menu,
+/// for the processes without their own.
+const POWERSHELL_APP_ID: &str =
+  "{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe";
+/// The group of the toasts in the history of the action center.
+const TOAST_GROUP: &str = "tao";
+/// The arguments of a toast activated by one of its actions, followed by the id of the action.
+const ACTION_ARGUMENTS: &str = "action=";
+
+/// An event of a toast for the thread of its event loop, boxed in the LPARAM of a
+/// `NOTIFICATION_MSG_ID` message.
+pub(crate) enum ToastMessage {
+  /// The toast was clicked, on one of its actions or not.
+  Activated(Option<String>),
+  /// The toast was removed from the action center, or failed to show.
+  Removed,
+}
+
+/// A toast shown by the process, kept alive for its events until it's activated or removed.
+struct ToastState {
+  notifier: ToastNotifier,
+  toast: ToastNotification,
+  /// The image of the toast, written to a temporary file.
+  icon_path: Option<PathBuf>,
+}
+
+lazy_static! {
+  /// The toasts by id, shared by the threads so that any of them can close a toast.
+  static ref TOASTS: Mutex<HashMap<u32, ToastState>> = Default::default();
+}
+
+pub struct Notification {
+  id: u32,
+}
+
+impl Notification {
+  pub fn new<T>(
+    window_target: &EventLoopWindowTarget<T>,
+    id: u32,
+    attributes: NotificationAttributes,
+  ) -> Result<Self, ExternalError> {
+    let icon_path = attributes.icon.as_ref().and_then(|icon| {
+      let path = env::temp_dir().join(format!("tao-notification-{}-{}.png", process::id(), id));
+      match icon.inner.write_to_png(&path) {
+        Ok(()) => Some(path),
+        Err(e) => {
+          log::warn!("Failed to write the image of the notification: {}", e);
+          None
+        }
+      }
+    });
+
+    let xml = XmlDocument::new()?;
+    xml.LoadXml(&HSTRING::from(toast_xml(&attributes, icon_path.as_deref())))?;
+    let toast = ToastNotification::CreateToastNotification(&xml)?;
+    let tag = HSTRING::from(id.to_string());
+    toast.SetTag(&tag)?;
+    toast.SetGroup(&HSTRING::from(TOAST_GROUP))?;
+
+    // The handlers are called on a thread of the system.
+    let hwnd = window_target.thread_msg_target.0;
+    let post = move |message: ToastMessage| unsafe {
+      let message = Box::into_raw(Box::new(message));
+      if PostMessageW(
+        HWND(hwnd),
+        *NOTIFICATION_MSG_ID,
+        WPARAM(id as _),
+        LPARAM(message as _),
+      )
+      .is_err()
+      {
+        drop(Box::from_raw(message));
+      }
+    };
+    toast.Activated(&TypedEventHandler::new(
+      move |_, args: &Option<IInspectable>| {
+        let arguments = args
+          .as_ref()
+          .and_then(|args| args.cast::<ToastActivatedEventArgs>().ok())
+          .and_then(|args| args.Arguments().ok())
+          .map(|arguments| arguments.to_string_lossy())
+          .unwrap_or_default();
+        let action = arguments.strip_prefix(ACTION_ARGUMENTS).map(Into::into);
+        post(ToastMessage::Activated(action));
+        Ok(())
+      },
+    ))?;
+    // A toast that timed out is still in the action center, and can be clicked there.
+    toast.Dismissed(&TypedEventHandler::new(
+      move |_, args: &Option<ToastDismissedEventArgs>| {
+        let reason = args.as_ref().and_then(|args| args.Reason().ok());
+        if reason != Some(ToastDismissalReason::TimedOut) {
+          post(ToastMessage::Removed);
+        }
+        Ok(())
+      },
+    ))?;
+    toast.Failed(&TypedEventHandler::new(
+      move |_, args: &Option<ToastFailedEventArgs>| {
+        if let Some(error) = args.as_ref().and_then(|args| args.ErrorCode().ok()) {
+          log::warn!("Failed to show the notification: {:?}", error);
+        }
+        post(ToastMessage::Removed);
+        Ok(())
+      },
+    ))?;
+
+    let notifier = ToastNotificationManager::CreateToastNotifierWithId(&app_user_model_id())?;
+    notifier.Show(&toast)?;
+    toasts().insert(
+      id,
+      ToastState {
+        notifier,
+        toast,
+        icon_path,
+      },
+    );
+    Ok(Self { id })
+  }
+
+  pub fn close(&self) {
+    // A toast that was activated is forgotten, but may still be in the action center.
+    let state = toasts().remove(&self.id);
+    if let Some(state) = &state {
+      let _ = state.notifier.Hide(&state.toast);
+    }
+    if let Err(e) = ToastNotificationManager::History().and_then(|history| {
+      history.RemoveGroupedTagWithId(
+        &HSTRING::from(self.id.to_string()),
+        &HSTRING::from(TOAST_GROUP),
+        &app_user_model_id(),
+      )
+    }) {
+      log::warn!(
+        "Failed to remove the notification from the action center: {}",
+        e
+      );
+    }
+    if let Some(state) = state {
+      remove_icon(state);
+    }
+  }
+}
+
+fn toasts() -> std::sync::MutexGuard<'static, HashMap<u32, ToastState>> {
+  TOASTS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Forgets a toast once it was activated or removed.
+pub(crate) fn forget(id: u32) {
+  let state = toasts().remove(&id);
+  if let Some(state) = state {
+    remove_icon(state);
+  }
+}
+
+fn remove_icon(state: ToastState) {
+  if let Some(path) = state.icon_path {
+    let _ = fs::remove_file(path);
+  }
+}
+
+/// The explicit application user model id of the process, or the one of Windows PowerShell.
+fn app_user_model_id() -> HSTRING {
+  unsafe {
+    match GetCurrentProcessExplicitAppUserModelID() {
+      Ok(id) => {
+        let app_id = id.to_hstring();
+        CoTaskMemFree(Some(id.0 as _));
+        app_id.unwrap_or_else(|_| HSTRING::from(POWERSHELL_APP_ID))
+      }
+      Err(_) => HSTRING::from(POWERSHELL_APP_ID),
+    }
+  }
+}
+
+fn toast_xml(attributes: &NotificationAttributes, icon_path: Option<&Path>) -> String {
+  // Empty arguments for a click on the toast itself.
+  let mut xml = String::from(r#"<toast launch=""><visual><binding template="ToastGeneric">"#);
+  let _ = write!(xml, "<text>{}</text>", escape(&attributes.title));
+  if let Some(body) = &attributes.body {
+    let _ = write!(xml, "<text>{}</text>", escape(body));
+  }
+  if let Some(path) = icon_path {
+    let _ = write!(
+      xml,
+      r#"<image placement="appLogoOverride" src="{}"/>"#,
+      escape(&path.to_string_lossy())
+    );
+  }
+  xml.push_str("</binding></visual>");
+  if !attributes.actions.is_empty() {
+    xml.push_str("<actions>");
+    for action in &attributes.actions {
+      let _ = write!(
+        xml,
+        r#"<action content="{}" arguments="{}{}"/>"#,
+        escape(&action.label),
+        ACTION_ARGUMENTS,
+        escape(&action.id)
+      );
+    }
+    xml.push_str("</actions>");
+  }
+  xml.push_str("</toast>");
+  xml
+}
+
+/// Escapes the text and attributes of the toast XML.
+fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for character in text.chars() {
+    match character {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      _ => escaped.push(character),
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::notification::NotificationAction;
+
+  fn attributes(body: Option<&str>, actions: &[(&str, &str)]) -> NotificationAttributes {
+    NotificationAttributes {
+      title: "Title".into(),
+      body: body.map(Into::into),
+      icon: None,
+      actions: actions
+        .iter()
+        .map(|(id, label)| NotificationAction {
+          id: (*id).into(),
+          label: (*label).into(),
+        })
+        .collect(),
+    }
+  }
+
+  #[test]
+  fn escape_replaces_the_xml_special_characters() {
+    assert_eq!(escape("plain text"), "plain text");
+    assert_eq!(
+      escape(r#"<a href="x">Tom & Jerry's</a>"#),
+      "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&apos;s&lt;/a&gt;"
+    );
+    assert_eq!(escape("é ✓"), "é ✓");
+  }
+
+  #[test]
+  fn toast_xml_has_the_title_only() {
+    assert_eq!(
+      toast_xml(&attributes(None, &[]), None),
+      r#"<toast launch=""><visual><binding template="ToastGeneric"><text>Title</text></binding></visual></toast>"#
+    );
+  }
+
+  #[test]
+  fn toast_xml_has_the_body_image_and_actions() {
+    let xml = toast_xml(
+      &attributes(Some("1 < 2"), &[("reply", "Reply"), ("a&b", "\"Both\"")]),
+      Some(Path::new(r"C:\Temp\icon.png")),
+    );
+    assert_eq!(
+      xml,
+      concat!(
+        r#"<toast launch=""><visual><binding template="ToastGeneric">"#,
+        r#"<text>Title</text><text>1 &lt; 2</text>"#,
+        r#"<image placement="appLogoOverride" src="C:\Temp\icon.png"/>"#,
+        r#"</binding></visual><actions>"#,
+        r#"<action content="Reply" arguments="action=reply"/>"#,
+        r#"<action content="&quot;Both&quot;" arguments="action=a&amp;b"/>"#,
+        r#"</actions></toast>"#,
+      )
+    );
+  }
+}
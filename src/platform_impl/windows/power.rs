@@ -0,0 +1,61 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{ffi::OsStr, os::windows::ffi::OsStrExt};
+
+use windows::{
+  core::PWSTR,
+  Win32::{
+    Foundation::{CloseHandle, HANDLE},
+    System::{
+      Power::{
+        PowerClearRequest, PowerCreateRequest, PowerRequestDisplayRequired,
+        PowerRequestSystemRequired, PowerSetRequest,
+      },
+      SystemServices::POWER_REQUEST_CONTEXT_VERSION,
+      Threading::{POWER_REQUEST_CONTEXT_SIMPLE_STRING, REASON_CONTEXT, REASON_CONTEXT_0},
+    },
+  },
+};
+
+use super::event_loop::EventLoopWindowTarget;
+use crate::error::ExternalError;
+
+/// A power request keeping the display and the system on.
+pub struct PowerAssertion {
+  request: HANDLE,
+}
+
+impl PowerAssertion {
+  pub fn keep_display_awake<T: 'static>(
+    _window_target: &EventLoopWindowTarget<T>,
+    reason: &str,
+  ) -> Result<Self, ExternalError> {
+    // The reason is copied when the request is created.
+    let mut reason: Vec<u16> = OsStr::new(reason).encode_wide().chain(Some(0)).collect();
+    let context = REASON_CONTEXT {
+      Version: POWER_REQUEST_CONTEXT_VERSION,
+      Flags: POWER_REQUEST_CONTEXT_SIMPLE_STRING,
+      Reason: REASON_CONTEXT_0 {
+        SimpleReasonString: PWSTR(reason.as_mut_ptr()),
+      },
+    };
+    unsafe {
+      let request = PowerCreateRequest(&context)?;
+      let assertion = Self { request };
+      PowerSetRequest(request, PowerRequestDisplayRequired)?;
+      PowerSetRequest(request, PowerRequestSystemRequired)?;
+      Ok(assertion)
+    }
+  }
+}
+
+impl Drop for PowerAssertion {
+  fn drop(&mut self) {
+    unsafe {
+      let _ = PowerClearRequest(self.request, PowerRequestSystemRequired);
+      let _ = PowerClearRequest(self.request, PowerRequestDisplayRequired);
+      let _ = CloseHandle(self.request);
+    }
+  }
+}
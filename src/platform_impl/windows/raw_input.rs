@@ -2,7 +2,10 @@
 // Copyright 2021-2023 Tauri Programme within The Commons Conservancy
 // SPDX-License-Identifier: Apache-2.0
 
-use std::mem::{self, size_of};
+use std::{
+  mem::{self, size_of},
+  sync::atomic::{AtomicIsize, Ordering},
+};
 
 use windows::Win32::{
   Devices::HumanInterfaceDevice::*,
@@ -119,6 +122,37 @@ pub fn get_raw_input_device_name(handle: HANDLE) -> Option<String> {
   Some(util::wchar_to_string(&name))
 }
 
+/// The thread event target receiving the raw input of the process. Windows sends it to a single
+/// window, so only the first event loop gets the device events while several are running.
+static RAW_INPUT_TARGET: AtomicIsize = AtomicIsize::new(0);
+
+/// Registers `window_handle` for the raw input of the process, unless the one of another event
+/// loop already is. Returns whether it's registered.
+pub fn claim_raw_input(window_handle: HWND) -> bool {
+  let claimed = RAW_INPUT_TARGET
+    .compare_exchange(0, window_handle.0, Ordering::AcqRel, Ordering::Acquire)
+    .is_ok();
+  if claimed {
+    register_all_mice_and_keyboards_for_raw_input(window_handle, Default::default());
+  }
+  claimed
+}
+
+pub fn owns_raw_input(window_handle: HWND) -> bool {
+  RAW_INPUT_TARGET.load(Ordering::Acquire) == window_handle.0
+}
+
+/// Unregisters `window_handle` if it receives the raw input of the process, so the next event
+/// loop created can claim it.
+pub fn release_raw_input(window_handle: HWND) {
+  if RAW_INPUT_TARGET
+    .compare_exchange(window_handle.0, 0, Ordering::AcqRel, Ordering::Acquire)
+    .is_ok()
+  {
+    register_all_mice_and_keyboards_for_raw_input(window_handle, DeviceEventFilter::Always);
+  }
+}
+
 pub fn register_raw_input_devices(devices: &[RAWINPUTDEVICE]) -> bool {
   let device_size = size_of::<RAWINPUTDEVICE>() as u32;
   unsafe { RegisterRawInputDevices(devices, device_size) }.is_ok()
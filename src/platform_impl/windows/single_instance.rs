@@ -0,0 +1,146 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+  env,
+  ffi::OsString,
+  os::windows::ffi::{OsStrExt, OsStringExt},
+  path::PathBuf,
+  process, thread,
+  time::{Duration, Instant},
+};
+
+use windows::{
+  core::PCWSTR,
+  Win32::{
+    Foundation::{CloseHandle, GetLastError, ERROR_ALREADY_EXISTS, HANDLE, HWND, LPARAM, WPARAM},
+    System::{DataExchange::COPYDATASTRUCT, Threading::CreateMutexW},
+    UI::WindowsAndMessaging::{
+      AllowSetForegroundWindow, ChangeWindowMessageFilterEx, FindWindowExW,
+      GetWindowThreadProcessId, SendMessageTimeoutW, SetWindowTextW, MSGFLT_ALLOW,
+      SMTO_ABORTIFHUNG, WM_COPYDATA,
+    },
+  },
+};
+
+use super::util;
+
+/// Identifies the `WM_COPYDATA` messages carrying the command line of a second instance.
+const COPYDATA_ID: usize = 0x7461_6f01;
+/// How long a second instance waits for the first one to create its event target window.
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The named mutex held by the first instance of a single-instance application.
+pub(crate) struct SingleInstanceLock {
+  mutex: HANDLE,
+  window_name: Vec<u16>,
+}
+
+impl SingleInstanceLock {
+  /// Returns the lock if this process is the first instance of `identifier`. Otherwise forwards
+  /// the command line of this process to the event target window, of class `class`, of the
+  /// first instance and exits.
+  pub(crate) unsafe fn acquire(identifier: &str, class: PCWSTR) -> Option<Self> {
+    // Backslashes are reserved for the namespace of the mutex.
+    let mutex_name = util::encode_wide(format!(
+      "Local\\tao-single-instance-{}",
+      identifier.replace('\\', "/")
+    ));
+    let window_name = util::encode_wide(format!("tao-single-instance-{}", identifier));
+    let deadline = Instant::now() + FORWARD_TIMEOUT;
+    loop {
+      // Creating the mutex is atomic, a single one of simultaneous launches creates it.
+      match CreateMutexW(None, false, PCWSTR::from_raw(mutex_name.as_ptr())) {
+        Ok(mutex) if GetLastError() != ERROR_ALREADY_EXISTS => {
+          return Some(Self { mutex, window_name })
+        }
+        Ok(mutex) => {
+          let _ = CloseHandle(mutex);
+        }
+        Err(e) => {
+          warn!("Failed to create the single instance mutex: {}", e);
+          return None;
+        }
+      }
+
+      // The first instance may not have created its window yet, or be exiting and release the
+      // mutex for this process to take over.
+      let target = FindWindowExW(
+        HWND::default(),
+        HWND::default(),
+        class,
+        PCWSTR::from_raw(window_name.as_ptr()),
+      );
+      if target.0 != 0 {
+        forward(target);
+        process::exit(0);
+      }
+      if Instant::now() >= deadline {
+        warn!("The first instance of `{}` didn't answer", identifier);
+        process::exit(0);
+      }
+      thread::sleep(Duration::from_millis(50));
+    }
+  }
+
+  /// Names the event target window of the first instance for the next ones to find it.
+  pub(crate) unsafe fn expose(&self, window: HWND) {
+    let _ = SetWindowTextW(window, PCWSTR::from_raw(self.window_name.as_ptr()));
+    // An elevated first instance still accepts the command line of a regular one.
+    let _ = ChangeWindowMessageFilterEx(window, WM_COPYDATA, MSGFLT_ALLOW, None);
+  }
+}
+
+impl Drop for SingleInstanceLock {
+  fn drop(&mut self) {
+    unsafe {
+      let _ = CloseHandle(self.mutex);
+    }
+  }
+}
+
+unsafe fn forward(target: HWND) {
+  let mut process_id = 0;
+  GetWindowThreadProcessId(target, Some(&mut process_id));
+  // This process was just launched by the user, so it may hand the foreground over.
+  let _ = AllowSetForegroundWindow(process_id);
+
+  let cwd = env::current_dir().unwrap_or_default();
+  let mut data: Vec<u16> = cwd.as_os_str().encode_wide().chain(Some(0)).collect();
+  for arg in env::args_os() {
+    data.extend(arg.encode_wide());
+    data.push(0);
+  }
+  let copy_data = COPYDATASTRUCT {
+    dwData: COPYDATA_ID,
+    cbData: (data.len() * std::mem::size_of::<u16>()) as u32,
+    lpData: data.as_mut_ptr() as *mut _,
+  };
+  SendMessageTimeoutW(
+    target,
+    WM_COPYDATA,
+    WPARAM(0),
+    LPARAM(&copy_data as *const _ as isize),
+    SMTO_ABORTIFHUNG,
+    FORWARD_TIMEOUT.as_millis() as u32,
+    None,
+  );
+}
+
+/// Reads the arguments and the working directory forwarded by a second instance, `None` if the
+/// data comes from something else.
+pub(crate) unsafe fn decode(copy_data: &COPYDATASTRUCT) -> Option<(Vec<OsString>, PathBuf)> {
+  if copy_data.dwData != COPYDATA_ID || copy_data.lpData.is_null() {
+    return None;
+  }
+  let data = std::slice::from_raw_parts(
+    copy_data.lpData as *const u16,
+    copy_data.cbData as usize / std::mem::size_of::<u16>(),
+  );
+  let mut parts = data.split(|c| *c == 0).map(OsString::from_wide);
+  let cwd = PathBuf::from(parts.next()?);
+  let mut args: Vec<OsString> = parts.collect();
+  // The nul terminating the last argument leaves an empty part.
+  args.pop();
+  Some((args, cwd))
+}
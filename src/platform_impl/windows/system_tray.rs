@@ -0,0 +1,170 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{cell::RefCell, collections::HashMap, mem};
+
+use windows::Win32::{
+  Foundation::{HWND, POINT},
+  UI::{
+    Shell::{
+      Shell_NotifyIconW, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NIM_MODIFY,
+      NOTIFYICONDATAW, NOTIFY_ICON_MESSAGE,
+    },
+    WindowsAndMessaging::{self as win32wm, GetCursorPos, HMENU},
+  },
+};
+
+use super::{
+  event_loop::{EventLoopWindowTarget, SYSTEM_TRAY_MSG_ID},
+  menu::{self, Submenu},
+  util, OsError, WinIcon,
+};
+use crate::{
+  dpi::PhysicalPosition,
+  error::ExternalError,
+  event::{MouseButton, SystemTrayEvent},
+  icon::Icon,
+};
+
+/// The icon and tooltip of a system tray, to add it again when the taskbar is recreated.
+struct TrayState {
+  /// The thread event target, which receives the `SYSTEM_TRAY_MSG_ID` messages.
+  hwnd: HWND,
+  icon: WinIcon,
+  tooltip: Option<String>,
+  /// The menu shown on a right click, never destroyed like the other submenus.
+  menu: Option<HMENU>,
+}
+
+impl TrayState {
+  fn notify(&self, message: NOTIFY_ICON_MESSAGE, id: u32) -> bool {
+    let mut data = NOTIFYICONDATAW {
+      cbSize: mem::size_of::<NOTIFYICONDATAW>() as u32,
+      hWnd: self.hwnd,
+      uID: id,
+      uFlags: NIF_MESSAGE | NIF_ICON | NIF_TIP,
+      uCallbackMessage: *SYSTEM_TRAY_MSG_ID,
+      hIcon: self.icon.as_raw_small_handle(),
+      ..Default::default()
+    };
+    if let Some(tooltip) = &self.tooltip {
+      // Truncated to the size of `szTip`, keeping its null terminator.
+      let tooltip = util::encode_wide(tooltip);
+      let len = tooltip.len().min(data.szTip.len()) - 1;
+      data.szTip[..len].copy_from_slice(&tooltip[..len]);
+    }
+    unsafe { Shell_NotifyIconW(message, &data).as_bool() }
+  }
+}
+
+thread_local! {
+  /// The system trays of this thread by id.
+  static TRAYS: RefCell<HashMap<u32, TrayState>> = RefCell::new(HashMap::new());
+}
+
+pub struct SystemTray {
+  id: u32,
+}
+
+impl SystemTray {
+  pub fn new<T>(
+    window_target: &EventLoopWindowTarget<T>,
+    id: u32,
+    icon: Icon,
+    tooltip: Option<String>,
+  ) -> Result<Self, ExternalError> {
+    let state = TrayState {
+      hwnd: window_target.thread_msg_target,
+      icon: icon.inner,
+      tooltip,
+      menu: None,
+    };
+    if !state.notify(NIM_ADD, id) {
+      return Err(ExternalError::Os(os_error!(OsError::CreationError(
+        "Shell_NotifyIconW failed to add the icon"
+      ))));
+    }
+    TRAYS.with(|trays| trays.borrow_mut().insert(id, state));
+    Ok(Self { id })
+  }
+
+  pub fn set_icon(&self, icon: Icon) {
+    self.modify(|state| state.icon = icon.inner);
+  }
+
+  pub fn set_tooltip(&self, tooltip: Option<&str>) {
+    self.modify(|state| state.tooltip = tooltip.map(ToOwned::to_owned));
+  }
+
+  pub fn set_menu(&self, menu: Option<&Submenu>) {
+    TRAYS.with(|trays| {
+      if let Some(state) = trays.borrow_mut().get_mut(&self.id) {
+        state.menu = menu.map(Submenu::hmenu);
+      }
+    });
+  }
+
+  fn modify(&self, f: impl FnOnce(&mut TrayState)) {
+    TRAYS.with(|trays| {
+      if let Some(state) = trays.borrow_mut().get_mut(&self.id) {
+        f(state);
+        if !state.notify(NIM_MODIFY, self.id) {
+          log::warn!("Failed to update the system tray {}", self.id);
+        }
+      }
+    });
+  }
+}
+
+impl Drop for SystemTray {
+  fn drop(&mut self) {
+    if let Some(state) = TRAYS.with(|trays| trays.borrow_mut().remove(&self.id)) {
+      state.notify(NIM_DELETE, self.id);
+    }
+  }
+}
+
+/// Adds the system trays of this thread again, after the taskbar was recreated without them.
+pub fn restore() {
+  TRAYS.with(|trays| {
+    for (id, state) in trays.borrow().iter() {
+      if !state.notify(NIM_ADD, *id) {
+        log::warn!("Failed to restore the system tray {}", id);
+      }
+    }
+  });
+}
+
+/// Shows the menu of the system tray `id` at the cursor, if it has one.
+pub fn show_menu(id: u32) {
+  // Not borrowed while the menu is shown, the messages of its modal loop are still dispatched.
+  let menu = TRAYS.with(|trays| {
+    let trays = trays.borrow();
+    let state = trays.get(&id)?;
+    state.menu.map(|hmenu| (hmenu, state.hwnd))
+  });
+  if let Some((hmenu, hwnd)) = menu {
+    let mut point = POINT::default();
+    if unsafe { GetCursorPos(&mut point) }.is_ok() {
+      menu::track_pop_up(hmenu, hwnd, point);
+    }
+  }
+}
+
+/// Converts the mouse message in the `LPARAM` of a `SYSTEM_TRAY_MSG_ID` message to an event.
+pub fn event(message: u32) -> Option<SystemTrayEvent> {
+  let button = match message {
+    win32wm::WM_LBUTTONUP => Some(MouseButton::Left),
+    win32wm::WM_RBUTTONUP => Some(MouseButton::Right),
+    win32wm::WM_MBUTTONUP => Some(MouseButton::Middle),
+    win32wm::WM_LBUTTONDBLCLK => None,
+    _ => return None,
+  };
+  let mut point = POINT::default();
+  unsafe { GetCursorPos(&mut point).ok()? };
+  let position = PhysicalPosition::new(point.x as f64, point.y as f64);
+  Some(match button {
+    Some(button) => SystemTrayEvent::Click { button, position },
+    None => SystemTrayEvent::DoubleClick { position },
+  })
+}
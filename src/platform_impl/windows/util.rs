@@ -9,21 +9,38 @@ use std::{
   ops::BitAnd,
   os::windows::prelude::OsStrExt,
   slice,
-  sync::atomic::{AtomicBool, Ordering},
+  time::{Duration, Instant},
 };
 
 use crate::{
   dpi::{PhysicalPosition, PhysicalSize},
-  window::CursorIcon,
+  event_loop::{AccessibilityPrefs, PowerInfo, SystemAppearance},
+  window::{Color, CursorIcon, WindowShape},
 };
 
+use super::dark_mode;
+
 use windows::{
-  core::{HRESULT, PCSTR, PCWSTR},
+  core::{w, HRESULT, PCSTR, PCWSTR},
   Win32::{
-    Foundation::{BOOL, FARPROC, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM},
+    Foundation::{BOOL, ERROR_SUCCESS, FARPROC, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM},
     Globalization::lstrlenW,
-    Graphics::Gdi::{ClientToScreen, InvalidateRgn, HMONITOR, HRGN},
-    System::LibraryLoader::*,
+    Graphics::{
+      Dwm::{
+        DwmGetWindowAttribute, DwmSetWindowAttribute, DWMWA_CLOAKED, DWMWA_EXTENDED_FRAME_BOUNDS,
+        DWMWA_WINDOW_CORNER_PREFERENCE, DWMWCP_DEFAULT, DWMWCP_DONOTROUND,
+      },
+      Gdi::{
+        ClientToScreen, CombineRgn, CreateRectRgn, CreateRoundRectRgn, DeleteObject, InvalidateRgn,
+        SetWindowRgn, HMONITOR, HRGN, RGN_OR,
+      },
+    },
+    System::{
+      LibraryLoader::*,
+      Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS},
+      Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD},
+      SystemInformation::GetTickCount,
+    },
     UI::{
       HiDpi::*,
       Input::KeyboardAndMouse::*,
@@ -33,6 +50,16 @@ use windows::{
   },
 };
 
+/// Returns when the message being processed was posted, from `GetMessageTime`, as an `Instant`.
+pub fn message_time() -> Instant {
+  let now = Instant::now();
+  // Both count the milliseconds since startup and wrap around together.
+  let elapsed = unsafe { GetTickCount().wrapping_sub(GetMessageTime() as u32) };
+  now
+    .checked_sub(Duration::from_millis(u64::from(elapsed)))
+    .unwrap_or(now)
+}
+
 pub fn has_flag<T>(bitset: T, flag: T) -> bool
 where
   T: Copy + PartialEq + BitAnd<T, Output = T>,
@@ -67,6 +94,32 @@ pub unsafe fn get_window_rect(hwnd: HWND) -> Option<RECT> {
   GetWindowRect(hwnd, &mut rect).ok().map(|_| rect)
 }
 
+/// Returns the visible bounds of the window frame.
+///
+/// Unlike [`get_window_rect`], this excludes the invisible resize borders that Windows 10 and
+/// later add around decorated windows. Falls back to [`get_window_rect`] when DWM can't be queried.
+pub unsafe fn get_frame_rect(hwnd: HWND) -> Option<RECT> {
+  let mut rect = RECT::default();
+  match DwmGetWindowAttribute(
+    hwnd,
+    DWMWA_EXTENDED_FRAME_BOUNDS,
+    &mut rect as *mut RECT as _,
+    mem::size_of::<RECT>() as u32,
+  ) {
+    Ok(()) => Some(rect),
+    Err(_) => get_window_rect(hwnd),
+  }
+}
+
+/// Returns the offset of the visible frame from the window rect, i.e. the size of the invisible
+/// left and top resize borders.
+pub unsafe fn get_frame_offset(hwnd: HWND) -> (i32, i32) {
+  match (get_window_rect(hwnd), get_frame_rect(hwnd)) {
+    (Some(window), Some(frame)) => (frame.left - window.left, frame.top - window.top),
+    _ => (0, 0),
+  }
+}
+
 pub fn get_client_rect(hwnd: HWND) -> Result<RECT, io::Error> {
   let mut rect = RECT::default();
   let mut top_left = POINT::default();
@@ -96,7 +149,12 @@ pub fn adjust_size(hwnd: HWND, size: PhysicalSize<u32>, is_decorated: bool) -> P
   PhysicalSize::new((rect.right - rect.left) as _, (rect.bottom - rect.top) as _)
 }
 
-pub(crate) fn set_inner_size_physical(window: HWND, x: u32, y: u32, is_decorated: bool) {
+pub(crate) fn set_inner_size_physical(
+  window: HWND,
+  x: u32,
+  y: u32,
+  is_decorated: bool,
+) -> windows::core::Result<()> {
   unsafe {
     let rect = adjust_window_rect(
       window,
@@ -112,7 +170,7 @@ pub(crate) fn set_inner_size_physical(window: HWND, x: u32, y: u32, is_decorated
 
     let outer_x = (rect.right - rect.left).abs();
     let outer_y = (rect.top - rect.bottom).abs();
-    let _ = SetWindowPos(
+    SetWindowPos(
       window,
       HWND::default(),
       0,
@@ -120,9 +178,10 @@ pub(crate) fn set_inner_size_physical(window: HWND, x: u32, y: u32, is_decorated
       outer_x,
       outer_y,
       SWP_ASYNCWINDOWPOS | SWP_NOZORDER | SWP_NOREPOSITION | SWP_NOMOVE | SWP_NOACTIVATE,
-    );
+    )?;
     InvalidateRgn(window, HRGN::default(), BOOL::default());
   }
+  Ok(())
 }
 
 pub fn adjust_window_rect(hwnd: HWND, rect: RECT, is_decorated: bool) -> Option<RECT> {
@@ -164,14 +223,6 @@ pub fn adjust_window_rect_with_styles(
   }
 }
 
-pub fn set_cursor_hidden(hidden: bool) {
-  static HIDDEN: AtomicBool = AtomicBool::new(false);
-  let changed = HIDDEN.swap(hidden, Ordering::SeqCst) ^ hidden;
-  if changed {
-    unsafe { ShowCursor(!hidden) };
-  }
-}
-
 pub fn get_cursor_clip() -> windows::core::Result<RECT> {
   unsafe {
     let mut rect = RECT::default();
@@ -189,6 +240,73 @@ pub fn set_cursor_clip(rect: Option<RECT>) -> windows::core::Result<()> {
   }
 }
 
+/// Clips the window to `shape`, relative to its client area, or removes the clip with `None`.
+pub fn set_window_shape(hwnd: HWND, shape: Option<&WindowShape>, scale_factor: f64) {
+  let region = match (shape, get_client_rect(hwnd), unsafe {
+    get_window_rect(hwnd)
+  }) {
+    (Some(shape), Ok(client), Some(window)) => unsafe {
+      // The region is relative to the window rect, which includes the borders of the client area.
+      let (left, top) = (client.left - window.left, client.top - window.top);
+      let size = PhysicalSize::new(
+        (client.right - client.left) as u32,
+        (client.bottom - client.top) as u32,
+      );
+      Some(match shape {
+        WindowShape::RoundedRect { radius } => {
+          let diameter = (WindowShape::physical_radius(*radius, size, scale_factor) * 2.0) as i32;
+          // The right and bottom edges are excluded from the region.
+          CreateRoundRectRgn(
+            left,
+            top,
+            left + size.width as i32 + 1,
+            top + size.height as i32 + 1,
+            diameter,
+            diameter,
+          )
+        }
+        WindowShape::Region(rects) => {
+          let region = CreateRectRgn(0, 0, 0, 0);
+          for rect in rects {
+            let position = rect.position.to_physical::<i32>(scale_factor);
+            let size = rect.size.to_physical::<i32>(scale_factor);
+            let rect = CreateRectRgn(
+              left + position.x,
+              top + position.y,
+              left + position.x + size.width,
+              top + position.y + size.height,
+            );
+            CombineRgn(region, region, rect, RGN_OR);
+            DeleteObject(rect);
+          }
+          region
+        }
+      })
+    },
+    _ => None,
+  };
+
+  unsafe {
+    // The system owns the region once it's set.
+    if SetWindowRgn(hwnd, region.unwrap_or_default(), true) == 0 {
+      if let Some(region) = region {
+        DeleteObject(region);
+      }
+    }
+    // Windows 11 rounds the corners of the window by itself, which would clip the shape further.
+    let preference = match shape {
+      Some(_) => DWMWCP_DONOTROUND,
+      None => DWMWCP_DEFAULT,
+    };
+    let _ = DwmSetWindowAttribute(
+      hwnd,
+      DWMWA_WINDOW_CORNER_PREFERENCE,
+      &preference as *const _ as _,
+      mem::size_of_val(&preference) as u32,
+    );
+  }
+}
+
 pub fn get_desktop_rect() -> RECT {
   unsafe {
     let left = GetSystemMetrics(SM_XVIRTUALSCREEN);
@@ -210,6 +328,20 @@ pub fn is_visible(window: HWND) -> bool {
   unsafe { IsWindowVisible(window).as_bool() }
 }
 
+/// Returns whether DWM cloaked the window, like while it's on another virtual desktop.
+pub fn is_cloaked(window: HWND) -> bool {
+  let mut cloaked = 0u32;
+  let result = unsafe {
+    DwmGetWindowAttribute(
+      window,
+      DWMWA_CLOAKED,
+      &mut cloaked as *mut u32 as _,
+      mem::size_of::<u32>() as u32,
+    )
+  };
+  result.is_ok() && cloaked != 0
+}
+
 pub fn is_maximized(window: HWND) -> windows::core::Result<bool> {
   let mut placement = WINDOWPLACEMENT {
     length: mem::size_of::<WINDOWPLACEMENT>() as u32,
@@ -225,17 +357,111 @@ pub fn cursor_position() -> windows::core::Result<PhysicalPosition<f64>> {
   Ok((pt.x, pt.y).into())
 }
 
+pub fn accessibility_prefs() -> AccessibilityPrefs {
+  let mut animations = BOOL(1);
+  let _ = unsafe {
+    SystemParametersInfoW(
+      SPI_GETCLIENTAREAANIMATION,
+      0,
+      Some(&mut animations as *mut _ as *mut std::ffi::c_void),
+      SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+    )
+  };
+  // Both are per-user settings of the Settings app, without a documented API.
+  let transparency = registry_dword(
+    w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+    w!("EnableTransparency"),
+  );
+  let color_filter = registry_dword(w!("Software\\Microsoft\\ColorFiltering"), w!("Active"))
+    .filter(|active| *active != 0)
+    .and_then(|_| registry_dword(w!("Software\\Microsoft\\ColorFiltering"), w!("FilterType")));
+
+  AccessibilityPrefs {
+    reduce_motion: !animations.as_bool(),
+    reduce_transparency: transparency == Some(0),
+    high_contrast: dark_mode::is_high_contrast(),
+    // 1 is the inverted filter, 2 the inverted grayscale one.
+    inverted_colors: matches!(color_filter, Some(1) | Some(2)),
+  }
+}
+
+pub fn system_appearance() -> SystemAppearance {
+  // The accent color of the Settings app, in 0xAABBGGRR. Unlike the colorization color of DWM,
+  // it doesn't depend on whether title bars are accented.
+  let accent_color = registry_dword(w!("Software\\Microsoft\\Windows\\DWM"), w!("AccentColor"))
+    .map(|color| {
+      Color::new(
+        color as u8,
+        (color >> 8) as u8,
+        (color >> 16) as u8,
+        u8::MAX,
+      )
+    });
+  SystemAppearance {
+    theme: dark_mode::system_theme(),
+    accent_color,
+    accessibility: accessibility_prefs(),
+  }
+}
+
+pub fn power_info() -> PowerInfo {
+  let mut status = SYSTEM_POWER_STATUS::default();
+  if unsafe { GetSystemPowerStatus(&mut status) }.is_err() {
+    return PowerInfo::default();
+  }
+  // 128 means there is no system battery, 255 that its status is unknown.
+  let has_battery = status.BatteryFlag & 128 == 0 && status.BatteryFlag != 255;
+  PowerInfo {
+    on_battery: match status.ACLineStatus {
+      0 => Some(true),
+      1 => Some(false),
+      _ => None,
+    },
+    percent: if has_battery && status.BatteryLifePercent <= 100 {
+      Some(status.BatteryLifePercent)
+    } else {
+      None
+    },
+    power_saver: status.SystemStatusFlag == 1,
+  }
+}
+
+fn registry_dword(subkey: PCWSTR, value: PCWSTR) -> Option<u32> {
+  let mut data = 0u32;
+  let mut size = mem::size_of::<u32>() as u32;
+  let result = unsafe {
+    RegGetValueW(
+      HKEY_CURRENT_USER,
+      subkey,
+      value,
+      RRF_RT_REG_DWORD,
+      None,
+      Some(&mut data as *mut _ as *mut std::ffi::c_void),
+      Some(&mut size),
+    )
+  };
+  if result == ERROR_SUCCESS {
+    Some(data)
+  } else {
+    None
+  }
+}
+
 impl CursorIcon {
+  // Windows has no system cursor for grab, zoom, cell, copy, alias or context
+  // menu, so those map to the closest shape instead of all collapsing to the
+  // arrow. Keep this match exhaustive so new variants get an explicit choice.
   pub(crate) fn to_windows_cursor(self) -> PCWSTR {
     match self {
       CursorIcon::Arrow | CursorIcon::Default => IDC_ARROW,
-      CursorIcon::Hand => IDC_HAND,
-      CursorIcon::Crosshair => IDC_CROSS,
+      CursorIcon::ContextMenu | CursorIcon::Copy => IDC_ARROW,
+      CursorIcon::Alias => IDC_UPARROW,
+      CursorIcon::Hand | CursorIcon::Grab => IDC_HAND,
+      CursorIcon::Crosshair | CursorIcon::Cell => IDC_CROSS,
+      CursorIcon::ZoomIn | CursorIcon::ZoomOut => IDC_CROSS,
       CursorIcon::Text | CursorIcon::VerticalText => IDC_IBEAM,
       CursorIcon::NotAllowed | CursorIcon::NoDrop => IDC_NO,
-      CursorIcon::Grab | CursorIcon::Grabbing | CursorIcon::Move | CursorIcon::AllScroll => {
-        IDC_SIZEALL
-      }
+      CursorIcon::Grabbing | CursorIcon::Move | CursorIcon::AllScroll => IDC_SIZEALL,
       CursorIcon::EResize | CursorIcon::WResize | CursorIcon::EwResize | CursorIcon::ColResize => {
         IDC_SIZEWE
       }
@@ -247,7 +473,6 @@ impl CursorIcon {
       CursorIcon::Wait => IDC_WAIT,
       CursorIcon::Progress => IDC_APPSTARTING,
       CursorIcon::Help => IDC_HELP,
-      _ => IDC_ARROW, // use arrow for the missing cases.
     }
   }
 }
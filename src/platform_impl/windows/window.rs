@@ -8,10 +8,11 @@ use mem::MaybeUninit;
 use parking_lot::Mutex;
 use std::{
   cell::{Cell, RefCell},
+  collections::VecDeque,
   ffi::OsStr,
   io, mem,
   os::windows::ffi::OsStrExt,
-  sync::Arc,
+  sync::{Arc, Weak},
 };
 
 use crossbeam_channel as channel;
@@ -22,41 +23,104 @@ use windows::{
       self as win32f, HINSTANCE, HMODULE, HWND, LPARAM, LRESULT, POINT, POINTS, RECT, WPARAM,
     },
     Graphics::{
-      Dwm::{DwmEnableBlurBehindWindow, DWM_BB_BLURREGION, DWM_BB_ENABLE, DWM_BLURBEHIND},
+      Dwm::{
+        DwmEnableBlurBehindWindow, DwmFlush, DWM_BB_BLURREGION, DWM_BB_ENABLE, DWM_BLURBEHIND,
+      },
       Gdi::*,
     },
-    System::{Com::*, LibraryLoader::*, Ole::*},
+    Storage::Xps::{PrintWindow, PRINT_WINDOW_FLAGS},
+    System::{
+      Com::*,
+      LibraryLoader::*,
+      Ole::*,
+      Threading::{AttachThreadInput, GetCurrentThreadId},
+    },
     UI::{
       Input::{Ime::*, KeyboardAndMouse::*, Touch::*},
       Shell::{ITaskbarList4 as ITaskbarList, TaskbarList, *},
+      TextServices::{SetInputScope, IS_DEFAULT, IS_NUMBER, IS_PASSWORD},
       WindowsAndMessaging::{self as win32wm, *},
     },
   },
 };
 
 use crate::{
-  dpi::{PhysicalPosition, PhysicalSize, Position, Size},
-  error::{ExternalError, NotSupportedError, OsError as RootOsError},
-  icon::Icon,
+  accessibility::AccessibilityActionRequest,
+  dpi::{PhysicalPosition, PhysicalSize, Position, Rect, Size},
+  error::{ExternalError, NotSupportedError, OsError as RootOsError, WindowOperationError},
+  event_loop::EventLoopClosed,
+  icon::{Icon, RgbaIcon},
   monitor::MonitorHandle as RootMonitorHandle,
+  platform::windows::DropEffect,
   platform_impl::platform::{
     dark_mode::try_window_theme,
     dpi::{dpi_to_scale_factor, hwnd_dpi},
+    drag_source,
     drop_handler::FileDropHandler,
-    event_loop::{self, EventLoopWindowTarget, DESTROY_MSG_ID},
+    event_loop::{
+      self, EventLoopWindowTarget, ACCESSIBILITY_ACTION_MSG_ID, DESTROY_MSG_ID,
+      DRAG_FINISHED_MSG_ID, SET_IME_ALLOWED_MSG_ID,
+    },
+    frame_clock::FrameClock,
     icon::{self, IconType},
+    menu::{Menu, Submenu},
     monitor, util,
-    window_state::{CursorFlags, SavedWindow, WindowFlags, WindowState},
+    window_state::{
+      CursorFlags, SavedWindow, SubclassHandler, WindowFlags, WindowState, WmGetObjectHandler,
+    },
     OsError, Parent, PlatformSpecificWindowBuilderAttributes, WindowId,
   },
   window::{
-    CursorIcon, Fullscreen, ProgressBarState, ProgressState, ResizeDirection, Theme,
-    UserAttentionType, WindowAttributes, WindowSizeConstraints,
+    ActivationToken, Color, CursorGrabMode, CursorIcon, CursorKind, DragData, DragImage,
+    DragOperation, DragResult, FocusResult, Fullscreen, HitTestCallback, HitTestResult, ImePurpose,
+    ProgressBarState, ProgressState, ResizeDirection, RgbaImage, Theme, UserAttentionType,
+    WindowAttributes, WindowLevel, WindowShape, WindowSizeConstraints,
   },
 };
 
 use super::keyboard::{KeyEventBuilder, KEY_EVENT_BUILDERS};
 
+/// The accessibility action requests of a window not delivered yet, dropped with the window even
+/// if its messages were never processed.
+pub(crate) type AccessibilityActions = Arc<Mutex<VecDeque<AccessibilityActionRequest>>>;
+
+/// Posts the accessibility action requests to a window, which may be on another thread.
+#[derive(Clone)]
+pub struct AccessibilityActionSender {
+  hwnd: isize,
+  requests: Weak<Mutex<VecDeque<AccessibilityActionRequest>>>,
+}
+
+impl AccessibilityActionSender {
+  pub fn send(
+    &self,
+    request: AccessibilityActionRequest,
+  ) -> Result<(), EventLoopClosed<AccessibilityActionRequest>> {
+    let requests = match self.requests.upgrade() {
+      Some(requests) => requests,
+      None => return Err(EventLoopClosed(request)),
+    };
+    requests.lock().push_back(request.clone());
+    let posted = unsafe {
+      PostMessageW(
+        HWND(self.hwnd),
+        *ACCESSIBILITY_ACTION_MSG_ID,
+        WPARAM(0),
+        LPARAM(0),
+      )
+    };
+    if posted.is_err() {
+      // The window was destroyed, the requests of other threads may be queued meanwhile.
+      let mut requests = requests.lock();
+      if let Some(index) = requests.iter().position(|queued| *queued == request) {
+        requests.remove(index);
+      }
+      return Err(EventLoopClosed(request));
+    }
+    Ok(())
+  }
+}
+
 /// The Win32 implementation of the main `Window` object.
 pub struct Window {
   /// Main handle for the window.
@@ -65,8 +129,18 @@ pub struct Window {
   /// The current window state.
   window_state: Arc<Mutex<WindowState>>,
 
+  /// The handler of `set_subclass_handler`, apart from the window state, which is locked while
+  /// some messages are sent.
+  subclass_handler: Arc<Mutex<Option<SubclassHandler>>>,
+
+  /// The requests of the `AccessibilityActionSender`s, taken by the window procedure.
+  accessibility_actions: AccessibilityActions,
+
   // The events loop proxy.
   thread_executor: event_loop::EventLoopThreadExecutor,
+
+  /// The window belongs to a headless event loop and is never shown.
+  headless: bool,
 }
 
 impl Window {
@@ -102,6 +176,7 @@ impl Window {
           let file_drop_runner = event_loop.runner_shared.clone();
           let file_drop_handler: IDropTarget = FileDropHandler::new(
             win.window.0,
+            win.window_state.clone(),
             Box::new(move |event| {
               if let Ok(e) = event.map_nonuser_event() {
                 file_drop_runner.send_event(e)
@@ -118,6 +193,8 @@ impl Window {
 
         let subclass_input = event_loop::SubclassInput {
           window_state: win.window_state.clone(),
+          subclass_handler: win.subclass_handler.clone(),
+          accessibility_actions: win.accessibility_actions.clone(),
           event_loop_runner: event_loop.runner_shared.clone(),
           _file_drop_handler: file_drop_handler,
           subclass_removed: Cell::new(false),
@@ -144,29 +221,86 @@ impl Window {
     unsafe { GetWindowTextW(self.window.0, &mut buf) };
     String::from_utf16_lossy(&buf[..len as _])
   }
+  #[inline]
+  pub fn close(&self) {
+    // Posted like the close button does, so the window isn't closed from within a callback.
+    unsafe {
+      let _ = PostMessageW(self.window.0, WM_CLOSE, WPARAM(0), LPARAM(0));
+    }
+  }
+
   #[inline]
   pub fn set_visible(&self, visible: bool) {
+    if visible && self.headless {
+      debug!("`Window::set_visible(true)` is ignored in headless mode");
+      return;
+    }
     let window = self.window.clone();
     let window_state = Arc::clone(&self.window_state);
     self.thread_executor.execute_in_thread(move || {
+      let frame = if visible {
+        None
+      } else {
+        animate_to_minimize_target(window.0, &window_state)
+      };
       WindowState::set_window_flags(window_state.lock(), window.0, |f| {
         f.set(WindowFlags::VISIBLE, visible)
       });
+      // Puts the hidden window back where it was for the next time it's shown.
+      if let Some(frame) = frame {
+        unsafe {
+          let _ = SetWindowPos(
+            window.0,
+            HWND::default(),
+            frame.left,
+            frame.top,
+            frame.right - frame.left,
+            frame.bottom - frame.top,
+            SWP_NOZORDER | SWP_NOACTIVATE | SWP_NOOWNERZORDER,
+          );
+        }
+        window_state.lock().in_minimize_animation = false;
+      }
+      if visible {
+        // `WS_EX_NOACTIVATE` is only needed until the window is first shown, after that clicking
+        // the window must activate it.
+        WindowState::set_window_flags(window_state.lock(), window.0, |f| {
+          f.remove(WindowFlags::MARKER_DONT_FOCUS)
+        });
+        // The shell adds a taskbar button again whenever the window is shown.
+        let skip_taskbar = window_state.lock().skip_taskbar;
+        if skip_taskbar {
+          if let Err(e) = unsafe { set_skip_taskbar(window.0, true) } {
+            warn!("Failed to restore the taskbar state: {}", e);
+          }
+        }
+      }
     });
   }
 
   #[inline]
   pub fn set_focus(&self) {
+    let _ = self.set_focus_with_token(None);
+  }
+
+  pub fn set_focus_with_token(&self, _token: Option<ActivationToken>) -> FocusResult {
     let window = self.window.clone();
     let window_flags = self.window_state.lock().window_flags();
 
     let is_visible = window_flags.contains(WindowFlags::VISIBLE);
     let is_minimized = window_flags.contains(WindowFlags::MINIMIZED);
-    let is_foreground = window.0 == unsafe { GetForegroundWindow() };
+    if !is_visible || is_minimized {
+      return FocusResult::Failed;
+    }
 
-    if is_visible && !is_minimized && !is_foreground {
-      unsafe { force_window_active(window.0) };
+    if window.0 == unsafe { GetForegroundWindow() } || unsafe { force_window_active(window.0) } {
+      return FocusResult::Focused;
     }
+
+    // Windows refused to hand over the foreground, flash the taskbar button like it would
+    // do itself.
+    self.request_user_attention(Some(UserAttentionType::Informational));
+    FocusResult::AttentionRequested
   }
 
   #[inline]
@@ -182,9 +316,29 @@ impl Window {
     }
   }
 
+  pub fn request_redraw_synced(&self) {
+    let window = self.window.0;
+    let mut window_state = self.window_state.lock();
+    window_state
+      .frame_clock
+      .get_or_insert_with(|| FrameClock::spawn(window))
+      .request_once();
+  }
+
+  pub fn request_frame_callbacks(&self, enabled: bool) {
+    let window = self.window.0;
+    let mut window_state = self.window_state.lock();
+    if enabled || window_state.frame_clock.is_some() {
+      window_state
+        .frame_clock
+        .get_or_insert_with(|| FrameClock::spawn(window))
+        .set_continuous(enabled);
+    }
+  }
+
   #[inline]
   pub fn outer_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
-    unsafe { util::get_window_rect(self.window.0) }
+    unsafe { util::get_frame_rect(self.window.0) }
       .map(|rect| Ok(PhysicalPosition::new(rect.left as i32, rect.top as i32)))
       .expect("Unexpected GetWindowRect failure")
   }
@@ -211,11 +365,14 @@ impl Window {
     });
 
     unsafe {
+      // `SetWindowPos` positions the window rect, which includes the invisible resize borders,
+      // so offset by them to place the visible frame at the requested position.
+      let (dx, dy) = util::get_frame_offset(self.window.0);
       let _ = SetWindowPos(
         self.window.0,
         HWND::default(),
-        x as i32,
-        y as i32,
+        x - dx,
+        y - dy,
         0,
         0,
         SWP_ASYNCWINDOWPOS | SWP_NOZORDER | SWP_NOSIZE | SWP_NOACTIVATE,
@@ -238,7 +395,8 @@ impl Window {
 
   #[inline]
   pub fn outer_size(&self) -> PhysicalSize<u32> {
-    unsafe { util::get_window_rect(self.window.0) }
+    // The same bounds as `outer_position`, without the invisible resize borders.
+    unsafe { util::get_frame_rect(self.window.0) }
       .map(|rect| {
         PhysicalSize::new(
           (rect.right - rect.left) as u32,
@@ -249,16 +407,20 @@ impl Window {
   }
 
   #[inline]
-  pub fn set_inner_size(&self, size: Size) {
+  pub fn set_inner_size(&self, size: Size) -> Result<(), WindowOperationError> {
     let scale_factor = self.scale_factor();
-    let (width, height) = size.to_physical::<u32>(scale_factor).into();
-
     let window_state = Arc::clone(&self.window_state);
 
-    let is_decorated = window_state
-      .lock()
-      .window_flags
-      .contains(WindowFlags::MARKER_DECORATIONS);
+    let (is_decorated, size) = {
+      let window_state = window_state.lock();
+      (
+        window_state
+          .window_flags
+          .contains(WindowFlags::MARKER_DECORATIONS),
+        window_state.size_constraints.clamp(size, scale_factor),
+      )
+    };
+    let (width, height) = size.to_physical::<u32>(scale_factor).into();
 
     let window = self.window.clone();
     self.thread_executor.execute_in_thread(move || {
@@ -267,39 +429,39 @@ impl Window {
       });
     });
 
-    util::set_inner_size_physical(self.window.0, width, height, is_decorated);
+    util::set_inner_size_physical(self.window.0, width, height, is_decorated)
+      .map_err(|e| RootOsError::from(e).into())
   }
 
   #[inline]
-  pub fn set_min_inner_size(&self, size: Option<Size>) {
-    {
-      let mut window_state = self.window_state.lock();
-      window_state.size_constraints.min_width = size.map(|s| s.width());
-      window_state.size_constraints.min_height = size.map(|s| s.height());
-    }
-    // Make windows re-check the window size bounds.
-    let size = self.inner_size();
-    self.set_inner_size(size.into());
+  pub fn set_min_inner_size(&self, size: Option<Size>) -> Result<(), ExternalError> {
+    let mut size_constraints = self.window_state.lock().size_constraints;
+    size_constraints.min_width = size.map(|s| s.width());
+    size_constraints.min_height = size.map(|s| s.height());
+    self.set_inner_size_constraints(size_constraints)
   }
 
   #[inline]
-  pub fn set_max_inner_size(&self, size: Option<Size>) {
-    {
-      let mut window_state = self.window_state.lock();
-      window_state.size_constraints.max_width = size.map(|s| s.width());
-      window_state.size_constraints.max_height = size.map(|s| s.height());
-    }
-    // Make windows re-check the window size bounds.
-    let size = self.inner_size();
-    self.set_inner_size(size.into());
+  pub fn set_max_inner_size(&self, size: Option<Size>) -> Result<(), ExternalError> {
+    let mut size_constraints = self.window_state.lock().size_constraints;
+    size_constraints.max_width = size.map(|s| s.width());
+    size_constraints.max_height = size.map(|s| s.height());
+    self.set_inner_size_constraints(size_constraints)
   }
 
   #[inline]
-  pub fn set_inner_size_constraints(&self, constraints: WindowSizeConstraints) {
+  pub fn set_inner_size_constraints(
+    &self,
+    constraints: WindowSizeConstraints,
+  ) -> Result<(), ExternalError> {
+    constraints.validate(self.scale_factor())?;
     self.window_state.lock().size_constraints = constraints;
     // Make windows re-check the window size bounds.
     let size = self.inner_size();
-    self.set_inner_size(size.into());
+    if let Err(e) = self.set_inner_size(size.into()) {
+      warn!("Failed to apply the size constraints: {}", e);
+    }
+    Ok(())
   }
 
   #[inline]
@@ -409,15 +571,59 @@ impl Window {
 
   #[inline]
   pub fn set_cursor_icon(&self, cursor: CursorIcon) {
-    self.window_state.lock().mouse.cursor = cursor;
-    self.thread_executor.execute_in_thread(move || unsafe {
-      let cursor = LoadCursorW(HMODULE::default(), cursor.to_windows_cursor()).unwrap_or_default();
-      SetCursor(cursor);
+    let custom_cursor = {
+      let mut window_state = self.window_state.lock();
+      let custom_cursor = window_state.mouse.custom_cursor.take();
+      if mem::replace(&mut window_state.mouse.cursor, cursor) == cursor && custom_cursor.is_none() {
+        return;
+      }
+      custom_cursor
+    };
+    let window_state = Arc::clone(&self.window_state);
+    self.thread_executor.execute_in_thread(move || {
+      window_state.lock().mouse.refresh_cursor();
+      // Destroyed once it's no longer shown.
+      drop(custom_cursor);
     });
   }
 
   #[inline]
-  pub fn set_cursor_grab(&self, grab: bool) -> Result<(), ExternalError> {
+  pub fn cursor_icon(&self) -> CursorKind {
+    let window_state = self.window_state.lock();
+    if window_state
+      .mouse
+      .cursor_flags()
+      .contains(CursorFlags::HIDDEN)
+    {
+      CursorKind::Hidden
+    } else if window_state.mouse.custom_cursor.is_some() {
+      CursorKind::Custom
+    } else {
+      CursorKind::Icon(window_state.mouse.cursor)
+    }
+  }
+
+  #[inline]
+  pub fn set_custom_cursor(
+    &self,
+    icon: RgbaIcon,
+    hotspot: PhysicalPosition<u32>,
+  ) -> Result<(), ExternalError> {
+    let cursor = icon
+      .into_windows_cursor(hotspot)
+      .map_err(|e| ExternalError::Os(os_error!(OsError::IoError(e))))?;
+    let previous = self.window_state.lock().mouse.custom_cursor.replace(cursor);
+    let window_state = Arc::clone(&self.window_state);
+    self.thread_executor.execute_in_thread(move || {
+      window_state.lock().mouse.refresh_cursor();
+      // Destroyed once it's no longer shown.
+      drop(previous);
+    });
+    Ok(())
+  }
+
+  #[inline]
+  pub fn set_cursor_grab(&self, mode: CursorGrabMode) -> Result<(), ExternalError> {
     let window = self.window.clone();
     let window_state = Arc::clone(&self.window_state);
     let (tx, rx) = channel::unbounded();
@@ -426,7 +632,10 @@ impl Window {
       let result = window_state
         .lock()
         .mouse
-        .set_cursor_flags(window.0, |f| f.set(CursorFlags::GRABBED, grab))
+        .set_cursor_flags(window.0, |f| {
+          f.set(CursorFlags::GRABBED, mode == CursorGrabMode::Confined);
+          f.set(CursorFlags::LOCKED, mode == CursorGrabMode::Locked);
+        })
         .map_err(|e| ExternalError::Os(os_error!(OsError::IoError(e))));
       let _ = tx.send(result);
     });
@@ -452,7 +661,21 @@ impl Window {
 
   #[inline]
   pub fn cursor_position(&self) -> Result<PhysicalPosition<f64>, ExternalError> {
-    util::cursor_position().map_err(Into::into)
+    let mut point = POINT::default();
+    unsafe {
+      GetCursorPos(&mut point)?;
+      if !ScreenToClient(self.window.0, &mut point).as_bool() {
+        return Err(ExternalError::Os(os_error!(OsError::IoError(
+          io::Error::last_os_error()
+        ))));
+      }
+    }
+    Ok((point.x, point.y).into())
+  }
+
+  pub fn capture(&self, outer: bool) -> Result<RgbaImage, ExternalError> {
+    unsafe { capture_window(self.window.0, outer) }
+      .map_err(|e| ExternalError::Os(os_error!(OsError::IoError(e))))
   }
 
   #[inline]
@@ -513,6 +736,24 @@ impl Window {
     self.handle_os_dragging(WPARAM(direction.to_win32() as _))
   }
 
+  pub fn start_drag(&self, data: DragData, image: DragImage) -> Result<(), ExternalError> {
+    let result = unsafe { drag_source::do_drag_drop(&data, &image)? };
+    let wparam = match result {
+      DragResult::Cancelled => 0,
+      DragResult::Dropped(DragOperation::Copy) => 1,
+      DragResult::Dropped(DragOperation::Move) => 2,
+    };
+    unsafe {
+      PostMessageW(
+        self.hwnd(),
+        *DRAG_FINISHED_MSG_ID,
+        WPARAM(wparam),
+        LPARAM(0),
+      )?
+    };
+    Ok(())
+  }
+
   #[inline]
   pub fn set_ignore_cursor_events(&self, ignore: bool) -> Result<(), ExternalError> {
     let window = self.window.clone();
@@ -526,6 +767,93 @@ impl Window {
     Ok(())
   }
 
+  #[inline]
+  pub fn set_cursor_hittest_region(&self, region: Option<&[Rect]>) -> Result<(), ExternalError> {
+    self.window_state.lock().cursor_hittest_region = region.map(<[Rect]>::to_vec);
+    Ok(())
+  }
+
+  #[inline]
+  pub fn set_hit_test_callback(
+    &self,
+    callback: Option<HitTestCallback>,
+  ) -> Result<(), ExternalError> {
+    let mut window_state = self.window_state.lock();
+    window_state.hit_test_callback = callback;
+    window_state.hit_test_pressed = None;
+    Ok(())
+  }
+
+  #[inline]
+  pub fn set_wm_getobject_handler(&self, handler: Option<WmGetObjectHandler>) {
+    self.window_state.lock().wm_getobject_handler = handler;
+  }
+
+  #[inline]
+  pub fn set_drop_effect(&self, effect: DropEffect) {
+    self.window_state.lock().drop_effect = effect;
+  }
+
+  #[inline]
+  pub fn set_subclass_handler(&self, handler: Option<SubclassHandler>) {
+    *self.subclass_handler.lock() = handler;
+  }
+
+  #[inline]
+  pub fn accessibility_action_sender(&self) -> Result<AccessibilityActionSender, ExternalError> {
+    Ok(AccessibilityActionSender {
+      hwnd: self.window.0 .0,
+      requests: Arc::downgrade(&self.accessibility_actions),
+    })
+  }
+
+  #[inline]
+  pub fn set_shape(&self, shape: Option<WindowShape>) -> Result<(), ExternalError> {
+    let window = self.window.clone();
+    let window_state = Arc::clone(&self.window_state);
+    self.thread_executor.execute_in_thread(move || {
+      let scale_factor = {
+        let mut window_state = window_state.lock();
+        window_state.shape = shape.clone();
+        window_state.scale_factor
+      };
+      util::set_window_shape(window.0, shape.as_ref(), scale_factor);
+    });
+    Ok(())
+  }
+
+  #[inline]
+  pub fn set_opacity(&self, opacity: f32) {
+    let window = self.window.clone();
+    let window_state = Arc::clone(&self.window_state);
+    self.window_state.lock().opacity = opacity;
+    self.thread_executor.execute_in_thread(move || {
+      WindowState::set_window_flags(window_state.lock(), window.0, |f| {
+        f.set(WindowFlags::TRANSLUCENT, opacity < 1.0)
+      });
+      // The window may stay layered to ignore cursor events, so the alpha is always set.
+      unsafe {
+        let _ = SetLayeredWindowAttributes(
+          window.0,
+          win32f::COLORREF(0),
+          (opacity * 255.0).round() as u8,
+          LWA_ALPHA,
+        );
+      }
+    });
+  }
+
+  #[inline]
+  pub fn opacity(&self) -> f32 {
+    self.window_state.lock().opacity
+  }
+
+  #[inline]
+  pub fn set_minimize_target(&self, target: Option<Rect>) -> Result<(), ExternalError> {
+    self.window_state.lock().minimize_target = target;
+    Ok(())
+  }
+
   #[inline]
   pub fn id(&self) -> WindowId {
     WindowId(self.window.0 .0)
@@ -571,6 +899,11 @@ impl Window {
     unsafe { IsIconic(self.hwnd()) }.as_bool()
   }
 
+  #[inline]
+  pub fn is_visible_on_all_workspaces(&self) -> bool {
+    false
+  }
+
   #[inline]
   pub fn is_resizable(&self) -> bool {
     let window_state = self.window_state.lock();
@@ -615,7 +948,7 @@ impl Window {
   }
 
   #[inline]
-  pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+  pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) -> Result<(), WindowOperationError> {
     let window = self.window.clone();
     let window_state = Arc::clone(&self.window_state);
 
@@ -624,12 +957,12 @@ impl Window {
 
     match (&old_fullscreen, &fullscreen) {
       // Return if we already in the same fullscreen mode
-      _ if old_fullscreen == fullscreen => return,
+      _ if old_fullscreen == fullscreen => return Ok(()),
       // Return if saved Borderless(monitor) is the same as current monitor when requested fullscreen is Borderless(None)
       (Some(Fullscreen::Borderless(Some(monitor))), Some(Fullscreen::Borderless(None)))
         if monitor.inner == monitor::current_monitor(window.0) =>
       {
-        return
+        return Ok(())
       }
       _ => {}
     }
@@ -700,8 +1033,7 @@ impl Window {
         PeekMessageW(&mut msg, HWND::default(), 0, 0, PM_NOREMOVE);
       }
 
-      // Update window style
-      WindowState::set_window_flags(window_state.lock(), window.0, |f| {
+      let update_flags = |f: &mut WindowFlags| {
         f.set(
           WindowFlags::MARKER_EXCLUSIVE_FULLSCREEN,
           matches!(fullscreen, Some(Fullscreen::Exclusive(_))),
@@ -710,19 +1042,22 @@ impl Window {
           WindowFlags::MARKER_BORDERLESS_FULLSCREEN,
           matches!(fullscreen, Some(Fullscreen::Borderless(_))),
         );
-      });
+      };
 
-      // Update window bounds
+      // Update window style and bounds
       match &fullscreen {
         Some(fullscreen) => {
-          // Save window bounds before entering fullscreen
-          let placement = unsafe {
-            let mut placement = WINDOWPLACEMENT::default();
-            let _ = GetWindowPlacement(window.0, &mut placement);
-            placement
-          };
+          // Save window bounds before entering fullscreen, but not when moving between monitors
+          // or modes as they'd be the fullscreen ones
+          if old_fullscreen.is_none() {
+            let placement = unsafe {
+              let mut placement = WINDOWPLACEMENT::default();
+              let _ = GetWindowPlacement(window.0, &mut placement);
+              placement
+            };
 
-          window_state.lock().saved_window = Some(SavedWindow { placement });
+            window_state.lock().saved_window = Some(SavedWindow { placement });
+          }
 
           let monitor = match &fullscreen {
             Fullscreen::Exclusive(video_mode) => video_mode.monitor(),
@@ -734,21 +1069,22 @@ impl Window {
 
           let position: (i32, i32) = monitor.position().into();
           let size: (u32, u32) = monitor.size().into();
+          let rect = RECT {
+            left: position.0,
+            top: position.1,
+            right: position.0 + size.0 as i32,
+            bottom: position.1 + size.1 as i32,
+          };
 
+          // The new styles and the monitor bounds go in a single `SetWindowPos`, so the window
+          // isn't resized in place first and then moved to the monitor.
+          WindowState::set_window_flags_and_rect(window_state.lock(), window.0, rect, update_flags);
           unsafe {
-            let _ = SetWindowPos(
-              window.0,
-              HWND::default(),
-              position.0,
-              position.1,
-              size.0 as i32,
-              size.1 as i32,
-              SWP_ASYNCWINDOWPOS | SWP_NOZORDER,
-            );
             InvalidateRgn(window.0, HRGN::default(), false);
           }
         }
         None => {
+          WindowState::set_window_flags(window_state.lock(), window.0, update_flags);
           let mut window_state_lock = window_state.lock();
           if let Some(SavedWindow { placement }) = window_state_lock.saved_window.take() {
             drop(window_state_lock);
@@ -764,6 +1100,7 @@ impl Window {
         taskbar_mark_fullscreen(window.0, fullscreen.is_some());
       }
     });
+    Ok(())
   }
 
   #[inline]
@@ -778,6 +1115,89 @@ impl Window {
     });
   }
 
+  #[inline]
+  pub fn raise(&self) -> Result<(), ExternalError> {
+    self.restack(HWND_TOP)
+  }
+
+  #[inline]
+  pub fn lower(&self) -> Result<(), ExternalError> {
+    let always_on_top = self
+      .window_state
+      .lock()
+      .window_flags()
+      .contains(WindowFlags::ALWAYS_ON_TOP);
+    if !always_on_top {
+      return self.restack(HWND_BOTTOM);
+    }
+    // `HWND_BOTTOM` would drop the topmost state, go below the last topmost window instead.
+    let mut last = self.window.0;
+    loop {
+      let next = unsafe { GetWindow(last, GW_HWNDNEXT) };
+      if next == HWND::default()
+        || unsafe { GetWindowLongW(next, GWL_EXSTYLE) } as u32 & WS_EX_TOPMOST.0 == 0
+      {
+        break;
+      }
+      last = next;
+    }
+    if last == self.window.0 {
+      return Ok(());
+    }
+    self.restack(last)
+  }
+
+  #[inline]
+  pub fn restack_above(&self, sibling: &Window) -> Result<(), ExternalError> {
+    // The window is placed right below the one it's inserted after, which is the window right
+    // above the sibling.
+    let above = unsafe { GetWindow(sibling.window.0, GW_HWNDPREV) };
+    if above == self.window.0 || sibling.window.0 == self.window.0 {
+      return Ok(());
+    }
+    self.restack(if above == HWND::default() {
+      HWND_TOP
+    } else {
+      above
+    })
+  }
+
+  fn restack(&self, insert_after: HWND) -> Result<(), ExternalError> {
+    unsafe {
+      SetWindowPos(
+        self.window.0,
+        insert_after,
+        0,
+        0,
+        0,
+        0,
+        SWP_ASYNCWINDOWPOS | SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+      )?;
+    }
+    Ok(())
+  }
+
+  /// Returns the parent to create a window owned by this one.
+  pub(crate) fn as_parent(&self) -> Parent {
+    Parent::OwnedBy(self.window.0)
+  }
+
+  #[inline]
+  pub fn set_parent(&self, parent: Option<&Window>) {
+    if self
+      .window_state
+      .lock()
+      .window_flags
+      .contains(WindowFlags::CHILD)
+    {
+      warn!("Changing the owner of a child window is unsupported");
+      return;
+    }
+    // Despite its name, `GWLP_HWNDPARENT` sets the owner of a top-level window.
+    let owner = parent.map_or(0, |parent| parent.window.0 .0);
+    util::SetWindowLongPtrW(self.window.0, GWLP_HWNDPARENT, owner);
+  }
+
   #[inline]
   pub fn set_always_on_bottom(&self, always_on_bottom: bool) {
     let window = self.window.clone();
@@ -785,21 +1205,38 @@ impl Window {
 
     self.thread_executor.execute_in_thread(move || {
       WindowState::set_window_flags(window_state.lock(), window.0, |f| {
-        f.set(WindowFlags::ALWAYS_ON_BOTTOM, always_on_bottom)
+        f.set(WindowFlags::ALWAYS_ON_BOTTOM, always_on_bottom);
+        if always_on_bottom {
+          f.remove(WindowFlags::ALWAYS_ON_TOP);
+        }
       });
     });
   }
 
   #[inline]
-  pub fn set_always_on_top(&self, always_on_top: bool) {
+  pub fn set_always_on_top(&self, always_on_top: bool) -> Result<(), WindowOperationError> {
     let window = self.window.clone();
     let window_state = Arc::clone(&self.window_state);
 
     self.thread_executor.execute_in_thread(move || {
       WindowState::set_window_flags(window_state.lock(), window.0, |f| {
-        f.set(WindowFlags::ALWAYS_ON_TOP, always_on_top)
+        f.set(WindowFlags::ALWAYS_ON_TOP, always_on_top);
+        if always_on_top {
+          f.remove(WindowFlags::ALWAYS_ON_BOTTOM);
+        }
       });
     });
+    Ok(())
+  }
+
+  #[inline]
+  pub fn set_window_level(&self, level: WindowLevel) {
+    let window = self.window.clone();
+    let window_state = Arc::clone(&self.window_state);
+
+    self.thread_executor.execute_in_thread(move || {
+      WindowState::set_window_flags(window_state.lock(), window.0, |f| f.set_level(level));
+    });
   }
 
   pub fn set_rtl(&self, rtl: bool) {
@@ -844,32 +1281,74 @@ impl Window {
     self.window_state.lock().taskbar_icon = taskbar_icon;
   }
 
-  pub(crate) fn set_ime_position_physical(&self, x: i32, y: i32) {
-    if unsafe { GetSystemMetrics(SM_IMMENABLED) } != 0 {
-      let composition_form = COMPOSITIONFORM {
-        dwStyle: CFS_POINT,
-        ptCurrentPos: POINT { x, y },
-        rcArea: RECT::default(),
-      };
-      unsafe {
-        let himc = ImmGetContext(self.window.0);
-        ImmSetCompositionWindow(himc, &composition_form);
-        ImmReleaseContext(self.window.0, himc);
-      }
+  #[inline]
+  pub fn set_ime_cursor_area(&self, area: Rect) {
+    if unsafe { GetSystemMetrics(SM_IMMENABLED) } == 0 {
+      return;
+    }
+    let scale_factor = self.scale_factor();
+    let (x, y) = area.position.to_physical::<i32>(scale_factor).into();
+    let (width, height): (i32, i32) = area.size.to_physical::<i32>(scale_factor).into();
+    let composition_form = COMPOSITIONFORM {
+      dwStyle: CFS_POINT,
+      ptCurrentPos: POINT { x, y },
+      rcArea: RECT::default(),
+    };
+    // Keeps the candidates from covering the area.
+    let candidate_form = CANDIDATEFORM {
+      dwIndex: 0,
+      dwStyle: CFS_EXCLUDE,
+      ptCurrentPos: POINT { x, y },
+      rcArea: RECT {
+        left: x,
+        top: y,
+        right: x + width,
+        bottom: y + height,
+      },
+    };
+    unsafe {
+      let himc = ImmGetContext(self.window.0);
+      ImmSetCompositionWindow(himc, &composition_form);
+      ImmSetCandidateWindow(himc, &candidate_form);
+      ImmReleaseContext(self.window.0, himc);
     }
   }
 
   #[inline]
-  pub fn set_ime_position(&self, spot: Position) {
-    let (x, y) = spot.to_physical::<i32>(self.scale_factor()).into();
-    self.set_ime_position_physical(x, y);
+  pub fn set_ime_allowed(&self, allowed: bool) {
+    unsafe {
+      let _ = PostMessageW(
+        self.hwnd(),
+        *SET_IME_ALLOWED_MSG_ID,
+        WPARAM(allowed as usize),
+        LPARAM(0),
+      );
+    }
+  }
+
+  #[inline]
+  pub fn set_ime_visible(&self, _visible: bool) -> Result<(), ExternalError> {
+    Err(ExternalError::NotSupported(NotSupportedError::new()))
+  }
+
+  #[inline]
+  pub fn set_ime_purpose(&self, purpose: ImePurpose) -> Result<(), ExternalError> {
+    // The password scope also turns the IME off.
+    let scope = match purpose {
+      ImePurpose::Normal | ImePurpose::Terminal => IS_DEFAULT,
+      ImePurpose::Password => IS_PASSWORD,
+      ImePurpose::Number => IS_NUMBER,
+    };
+    unsafe { SetInputScope(self.window.0, scope) }.map_err(Into::into)
   }
 
   #[inline]
   pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
     let window = self.window.clone();
     let active_window_handle = unsafe { GetActiveWindow() };
-    if window.0 == active_window_handle {
+    let is_active = window.0 == active_window_handle;
+    self.window_state.lock().attention_requested = request_type.is_some() && !is_active;
+    if is_active {
       return;
     }
 
@@ -892,11 +1371,42 @@ impl Window {
     });
   }
 
+  #[inline]
+  pub fn is_attention_requested(&self) -> bool {
+    self.window_state.lock().attention_requested
+  }
+
+  pub fn set_background_color(&self, color: Option<Color>) {
+    // Only the next `WM_ERASEBKGND` uses it, the content already drawn stays.
+    self.window_state.lock().background_color = color;
+  }
+
   #[inline]
   pub fn theme(&self) -> Theme {
     self.window_state.lock().current_theme
   }
 
+  pub fn set_theme(&self, theme: Option<Theme>) {
+    {
+      let mut window_state = self.window_state.lock();
+      if window_state.preferred_theme == theme {
+        return;
+      }
+      window_state.preferred_theme = theme;
+    }
+
+    match theme {
+      Some(theme) => {
+        let theme = try_window_theme(self.hwnd(), Some(theme));
+        self.window_state.lock().current_theme = theme;
+      }
+      // Let `WM_WININICHANGE` pick up the system theme, falling back to the event loop's theme.
+      None => unsafe {
+        SendMessageW(self.hwnd(), WM_WININICHANGE, WPARAM(0), LPARAM(0));
+      },
+    }
+  }
+
   #[inline]
   pub fn reset_dead_keys(&self) {
     // `ToUnicode` consumes the dead-key by default, so we are constructing a fake (but valid)
@@ -928,40 +1438,33 @@ impl Window {
   }
 
   #[inline]
-  pub(crate) fn set_skip_taskbar(&self, skip: bool) {
+  pub fn set_enable(&self, enabled: bool) {
+    unsafe {
+      EnableWindow(self.window.0, enabled);
+    }
+  }
+
+  #[inline]
+  pub fn set_skip_taskbar(&self, skip: bool) -> Result<(), ExternalError> {
     self.window_state.lock().skip_taskbar = skip;
-    unsafe { set_skip_taskbar(self.hwnd(), skip) };
+    unsafe { set_skip_taskbar(self.hwnd(), skip) }?;
+    Ok(())
   }
 
   #[inline]
-  pub fn set_progress_bar(&self, progress: ProgressBarState) {
-    unsafe {
-      let taskbar_list: ITaskbarList = CoCreateInstance(&TaskbarList, None, CLSCTX_SERVER).unwrap();
-      let handle = self.window.0;
-
-      if let Some(state) = progress.state {
-        let taskbar_state = {
-          match state {
-            ProgressState::None => TBPF_NOPROGRESS,
-            ProgressState::Indeterminate => TBPF_INDETERMINATE,
-            ProgressState::Normal => TBPF_NORMAL,
-            ProgressState::Error => TBPF_ERROR,
-            ProgressState::Paused => TBPF_PAUSED,
-          }
-        };
+  pub fn set_menu(&self, menu: Option<&Menu>) {
+    Menu::set_for_window(menu, self.hwnd());
+  }
 
-        taskbar_list
-          .SetProgressState(handle, taskbar_state)
-          .unwrap_or(());
-      }
-      if let Some(value) = progress.progress {
-        let value = if value > 100 { 100 } else { value };
+  #[inline]
+  pub fn show_context_menu(&self, menu: &Submenu, position: Position) {
+    let position: PhysicalPosition<i32> = position.to_physical(self.scale_factor());
+    menu.pop_up(self.hwnd(), (position.x, position.y));
+  }
 
-        taskbar_list
-          .SetProgressValue(handle, value, 100)
-          .unwrap_or(());
-      }
-    }
+  #[inline]
+  pub fn set_progress_bar(&self, progress: ProgressBarState) {
+    unsafe { taskbar_set_progress(self.window.0, progress) };
   }
 
   #[inline]
@@ -1014,6 +1517,62 @@ pub struct WindowWrapper(HWND);
 unsafe impl Sync for WindowWrapper {}
 unsafe impl Send for WindowWrapper {}
 
+/// The number of frames of the animation toward the minimize target, about 200 ms.
+const MINIMIZE_ANIMATION_FRAMES: i32 = 12;
+
+/// Shrinks the window into its minimize target, if the target is still on one of the monitors,
+/// and returns the frame it had before. DWM doesn't animate `DrawAnimatedRects` and has no way to
+/// retarget its own minimize animation, so the window itself is moved. `in_minimize_animation`
+/// stays set until the caller restores the frame, so none of this is reported as `Moved` or
+/// `Resized`.
+fn animate_to_minimize_target(hwnd: HWND, window_state: &Mutex<WindowState>) -> Option<RECT> {
+  let (target, scale_factor) = {
+    let window_state = window_state.lock();
+    (window_state.minimize_target?, window_state.scale_factor)
+  };
+  if !unsafe { IsWindowVisible(hwnd) }.as_bool() || unsafe { IsIconic(hwnd) }.as_bool() {
+    return None;
+  }
+  let (x, y): (i32, i32) = target.position.to_physical::<i32>(scale_factor).into();
+  let (width, height): (i32, i32) = target.size.to_physical::<i32>(scale_factor).into();
+  let to = RECT {
+    left: x,
+    top: y,
+    right: x + width,
+    bottom: y + height,
+  };
+  // Monitors may have changed since the target was set.
+  if unsafe { MonitorFromRect(&to, MONITOR_DEFAULTTONULL) }.is_invalid() {
+    return None;
+  }
+  let from = unsafe { util::get_window_rect(hwnd) }?;
+
+  window_state.lock().in_minimize_animation = true;
+  let lerp = |a: i32, b: i32, frame: i32| a + (b - a) * frame / MINIMIZE_ANIMATION_FRAMES;
+  for frame in 1..=MINIMIZE_ANIMATION_FRAMES {
+    let left = lerp(from.left, to.left, frame);
+    let top = lerp(from.top, to.top, frame);
+    let right = lerp(from.right, to.right, frame);
+    let bottom = lerp(from.bottom, to.bottom, frame);
+    unsafe {
+      let _ = SetWindowPos(
+        hwnd,
+        HWND::default(),
+        left,
+        top,
+        right - left,
+        bottom - top,
+        SWP_NOZORDER | SWP_NOACTIVATE | SWP_NOOWNERZORDER,
+      );
+      // Waits for the frame to be composed, which paces the animation with the display.
+      if DwmFlush().is_err() {
+        std::thread::sleep(std::time::Duration::from_millis(16));
+      }
+    }
+  }
+  Some(from)
+}
+
 unsafe fn init<T: 'static>(
   attributes: WindowAttributes,
   pl_attribs: PlatformSpecificWindowBuilderAttributes,
@@ -1028,8 +1587,7 @@ unsafe fn init<T: 'static>(
     WindowFlags::MARKER_UNDECORATED_SHADOW,
     pl_attribs.decoration_shadow,
   );
-  window_flags.set(WindowFlags::ALWAYS_ON_BOTTOM, attributes.always_on_bottom);
-  window_flags.set(WindowFlags::ALWAYS_ON_TOP, attributes.always_on_top);
+  window_flags.set_level(attributes.window_level);
   window_flags.set(
     WindowFlags::NO_BACK_BUFFER,
     pl_attribs.no_redirection_bitmap,
@@ -1099,8 +1657,9 @@ unsafe fn init<T: 'static>(
     }
   }
 
-  let dpi = hwnd_dpi(real_window.0);
-  let scale_factor = dpi_to_scale_factor(dpi);
+  let scale_factor = attributes
+    .scale_factor_override
+    .unwrap_or_else(|| dpi_to_scale_factor(hwnd_dpi(real_window.0)));
 
   // making the window transparent
   if attributes.transparent && !pl_attribs.no_redirection_bitmap {
@@ -1127,13 +1686,17 @@ unsafe fn init<T: 'static>(
   );
 
   let window_state = {
-    let window_state = WindowState::new(
+    let mut window_state = WindowState::new(
       &attributes,
       None,
       scale_factor,
       current_theme,
       attributes.preferred_theme,
     );
+    window_state.current_monitor = MonitorFromWindow(real_window.0, MONITOR_DEFAULTTONEAREST);
+    window_state.no_background_erase = pl_attribs.no_background_erase;
+    window_state.resize_redraw_sync = pl_attribs.resize_redraw_sync;
+    window_state.fixed_scale_factor = attributes.scale_factor_override.is_some();
     let window_state = Arc::new(Mutex::new(window_state));
     WindowState::set_window_flags(window_state.lock(), real_window.0, |f| *f = window_flags);
     window_state
@@ -1142,20 +1705,27 @@ unsafe fn init<T: 'static>(
   let win = Window {
     window: real_window,
     window_state,
+    subclass_handler: Default::default(),
+    accessibility_actions: Default::default(),
     thread_executor: event_loop.create_thread_executor(),
+    headless: event_loop.headless,
   };
 
   KEY_EVENT_BUILDERS
     .lock()
     .insert(win.id(), KeyEventBuilder::default());
 
-  win.set_skip_taskbar(pl_attribs.skip_taskbar);
+  if let Err(e) = win.set_skip_taskbar(attributes.skip_taskbar) {
+    warn!("Failed to set the taskbar state: {}", e);
+  }
   win.set_window_icon(attributes.window_icon);
   win.set_taskbar_icon(pl_attribs.taskbar_icon);
 
   if attributes.fullscreen.is_some() {
-    win.set_fullscreen(attributes.fullscreen);
-    force_window_active(win.window.0);
+    let _ = win.set_fullscreen(attributes.fullscreen);
+    if attributes.focused {
+      let _ = force_window_active(win.window.0);
+    }
   } else {
     let desired_size = attributes
       .inner_size
@@ -1163,7 +1733,9 @@ unsafe fn init<T: 'static>(
     let size = attributes
       .inner_size_constraints
       .clamp(desired_size, win.scale_factor());
-    win.set_inner_size(size);
+    if let Err(e) = win.set_inner_size(size) {
+      warn!("Failed to set the inner size: {}", e);
+    }
 
     if attributes.maximized {
       // Need to set MAXIMIZED after setting `inner_size` as
@@ -1176,6 +1748,18 @@ unsafe fn init<T: 'static>(
     win.set_content_protection(true);
   }
 
+  if attributes.opacity < 1.0 {
+    win.set_opacity(attributes.opacity);
+  }
+
+  if attributes.position_cascade
+    && attributes.position.is_none()
+    && attributes.fullscreen.is_none()
+    && !attributes.maximized
+  {
+    cascade(&win);
+  }
+
   win.set_visible(attributes.visible);
   win.set_closable(attributes.closable);
 
@@ -1186,6 +1770,110 @@ unsafe fn init<T: 'static>(
   Ok(win)
 }
 
+/// Renders content drawn with DirectComposition too. Missing from the `windows` crate.
+const PW_RENDERFULLCONTENT: PRINT_WINDOW_FLAGS = PRINT_WINDOW_FLAGS(2);
+
+/// Captures the client area or the visible frame of the window by asking it to draw itself into a
+/// bitmap, which also works when the window is occluded.
+unsafe fn capture_window(hwnd: HWND, outer: bool) -> Result<RgbaImage, io::Error> {
+  if IsIconic(hwnd).as_bool() {
+    return Err(io::Error::new(
+      io::ErrorKind::Other,
+      "a minimized window can't be captured",
+    ));
+  }
+
+  let window_rect = util::get_window_rect(hwnd).ok_or_else(io::Error::last_os_error)?;
+  let area = if outer {
+    util::get_frame_rect(hwnd).ok_or_else(io::Error::last_os_error)?
+  } else {
+    util::get_client_rect(hwnd)?
+  };
+  let window_width = window_rect.right - window_rect.left;
+  let window_height = window_rect.bottom - window_rect.top;
+
+  let screen_dc = GetDC(HWND::default());
+  let dc = CreateCompatibleDC(screen_dc);
+  let bitmap = CreateCompatibleBitmap(screen_dc, window_width, window_height);
+  ReleaseDC(HWND::default(), screen_dc);
+
+  let previous = SelectObject(dc, bitmap);
+  let printed = PrintWindow(hwnd, dc, PW_RENDERFULLCONTENT).as_bool();
+  SelectObject(dc, previous);
+
+  let area = RECT {
+    left: area.left - window_rect.left,
+    top: area.top - window_rect.top,
+    right: area.right - window_rect.left,
+    bottom: area.bottom - window_rect.top,
+  };
+  let image = if printed {
+    bitmap_to_rgba(dc, bitmap, window_width, window_height, area)
+  } else {
+    Err(io::Error::last_os_error())
+  };
+  DeleteObject(bitmap);
+  DeleteDC(dc);
+  image
+}
+
+/// Reads the pixels of `area` from a bitmap of `width` by `height` selected out of `dc`, as opaque
+/// RGBA since the alpha channel of the bitmaps drawn by GDI isn't filled.
+pub(crate) unsafe fn bitmap_to_rgba(
+  dc: HDC,
+  bitmap: HBITMAP,
+  width: i32,
+  height: i32,
+  area: RECT,
+) -> Result<RgbaImage, io::Error> {
+  let mut info = BITMAPINFO {
+    bmiHeader: BITMAPINFOHEADER {
+      biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+      biWidth: width,
+      // Negative for rows ordered from the top.
+      biHeight: -height,
+      biPlanes: 1,
+      biBitCount: 32,
+      biCompression: BI_RGB.0,
+      ..Default::default()
+    },
+    ..Default::default()
+  };
+  let mut bgra = vec![0u8; (width * height * 4) as usize];
+  let lines = GetDIBits(
+    dc,
+    bitmap,
+    0,
+    height as u32,
+    Some(bgra.as_mut_ptr() as _),
+    &mut info,
+    DIB_RGB_COLORS,
+  );
+  if lines == 0 {
+    return Err(io::Error::last_os_error());
+  }
+
+  // Crop the bitmap to the requested area, and swap to RGBA.
+  let left = area.left.clamp(0, width) as usize;
+  let top = area.top.clamp(0, height) as usize;
+  let area_width = (area.right - area.left).clamp(0, width - left as i32) as usize;
+  let area_height = (area.bottom - area.top).clamp(0, height - top as i32) as usize;
+  let mut data = Vec::with_capacity(area_width * area_height * 4);
+  for row in top..top + area_height {
+    let start = (row * width as usize + left) * 4;
+    for pixel in bgra[start..start + area_width * 4].chunks_exact(4) {
+      data.extend_from_slice(&[pixel[2], pixel[1], pixel[0], u8::MAX]);
+    }
+  }
+
+  Ok(RgbaImage {
+    width: area_width as u32,
+    height: area_height as u32,
+    stride: area_width as u32 * 4,
+    data,
+  })
+}
+
 unsafe fn register_window_class(window_classname: &str) -> Vec<u16> {
   let class_name = util::encode_wide(window_classname);
 
@@ -1284,6 +1972,44 @@ thread_local! {
     };
 
     static TASKBAR_LIST: RefCell<Option<ITaskbarList2>> = RefCell::new(None);
+
+    static TASKBAR_PROGRESS_LIST: RefCell<Option<ITaskbarList>> = RefCell::new(None);
+
+    /// The windows placed by `cascade` that may still be open, most recent last.
+    static CASCADE: RefCell<Vec<HWND>> = RefCell::new(Vec::new());
+}
+
+/// The offset between cascaded windows in logical pixels, about the height of a title bar like
+/// the cascading of `CW_USEDEFAULT`.
+const CASCADE_STEP: f64 = 30.0;
+
+/// Places the window down and to the right of the last one placed by this function, see
+/// `WindowBuilder::with_position_cascade`.
+unsafe fn cascade(win: &Window) {
+  CASCADE.with(|windows| {
+    let mut windows = windows.borrow_mut();
+    windows.retain(|window| IsWindow(*window).as_bool());
+    if let Some(&last) = windows.last() {
+      let work_area = monitor::get_monitor_info(MonitorFromWindow(last, MONITOR_DEFAULTTONEAREST))
+        .map(|info| info.monitorInfo.rcWork);
+      if let (Some(last_rect), Some(rect), Ok(work_area)) = (
+        util::get_frame_rect(last),
+        util::get_frame_rect(win.window.0),
+        work_area,
+      ) {
+        let step = (CASCADE_STEP * win.scale_factor()).round() as i32;
+        let (mut x, mut y) = (last_rect.left + step, last_rect.top + step);
+        if x + (rect.right - rect.left) > work_area.right
+          || y + (rect.bottom - rect.top) > work_area.bottom
+        {
+          x = work_area.left;
+          y = work_area.top;
+        }
+        win.set_outer_position(PhysicalPosition::new(x, y).into());
+      }
+    }
+    windows.push(win.window.0);
+  });
 }
 
 pub fn com_initialized() {
@@ -1326,10 +2052,77 @@ unsafe fn taskbar_mark_fullscreen(handle: HWND, fullscreen: bool) {
   })
 }
 
-unsafe fn force_window_active(handle: HWND) {
-  // Try to focus the window without the hack first.
+// The taskbar list is cached so that the progress can be updated at a high frequency without
+// creating a new COM object for every update.
+unsafe fn taskbar_set_progress(handle: HWND, progress: ProgressBarState) {
+  com_initialized();
+
+  TASKBAR_PROGRESS_LIST.with(|task_bar_list_ptr| {
+    let mut task_bar_list = task_bar_list_ptr.borrow().clone();
+
+    if task_bar_list.is_none() {
+      let result: windows::core::Result<ITaskbarList> =
+        CoCreateInstance(&TaskbarList, None, CLSCTX_SERVER);
+      if let Ok(created) = result {
+        if let Ok(()) = created.HrInit() {
+          task_bar_list = Some(created);
+        }
+      }
+
+      if task_bar_list.is_none() {
+        return;
+      }
+
+      *task_bar_list_ptr.borrow_mut() = task_bar_list.clone();
+    }
+
+    let task_bar_list = task_bar_list.unwrap();
+
+    // `SetProgressValue` switches an indeterminate or hidden progress bar back to normal, so
+    // the value is only applied to the states that display it.
+    let shows_value = !matches!(
+      progress.state,
+      Some(ProgressState::None) | Some(ProgressState::Indeterminate)
+    );
+    if let (Some(value), true) = (progress.progress, shows_value) {
+      let value = if value > 100 { 100 } else { value };
+      let _ = task_bar_list.SetProgressValue(handle, value, 100);
+    }
+
+    if let Some(state) = progress.state {
+      let taskbar_state = match state {
+        ProgressState::None => TBPF_NOPROGRESS,
+        ProgressState::Indeterminate => TBPF_INDETERMINATE,
+        ProgressState::Normal => TBPF_NORMAL,
+        ProgressState::Error => TBPF_ERROR,
+        ProgressState::Paused => TBPF_PAUSED,
+      };
+      let _ = task_bar_list.SetProgressState(handle, taskbar_state);
+    }
+  })
+}
+
+/// Returns whether the window became the foreground window.
+unsafe fn force_window_active(handle: HWND) -> bool {
+  // Try to focus the window without the hacks first.
   if SetForegroundWindow(handle).as_bool() {
-    return;
+    return true;
+  }
+
+  // Only the thread owning the foreground window may hand it over, so temporarily share its
+  // input state.
+  let foreground_thread = GetWindowThreadProcessId(GetForegroundWindow(), None);
+  let current_thread = GetCurrentThreadId();
+  if foreground_thread != 0
+    && foreground_thread != current_thread
+    && AttachThreadInput(current_thread, foreground_thread, true).as_bool()
+  {
+    let _ = BringWindowToTop(handle);
+    let focused = SetForegroundWindow(handle).as_bool();
+    AttachThreadInput(current_thread, foreground_thread, false);
+    if focused {
+      return true;
+    }
   }
 
   // In some situations, calling SetForegroundWindow could not bring up the window,
@@ -1352,17 +2145,16 @@ unsafe fn force_window_active(handle: HWND) {
   // Simulate a key press and release
   SendInput(&inputs, mem::size_of::<INPUT>() as _);
 
-  SetForegroundWindow(handle);
+  SetForegroundWindow(handle).as_bool()
 }
 
-pub(crate) unsafe fn set_skip_taskbar(hwnd: HWND, skip: bool) {
+pub(crate) unsafe fn set_skip_taskbar(hwnd: HWND, skip: bool) -> windows::core::Result<()> {
   com_initialized();
-  let taskbar_list: ITaskbarList =
-    CoCreateInstance(&TaskbarList, None, CLSCTX_SERVER).expect("failed to create TaskBarList");
+  let taskbar_list: ITaskbarList = CoCreateInstance(&TaskbarList, None, CLSCTX_SERVER)?;
   if skip {
-    taskbar_list.DeleteTab(hwnd).expect("DeleteTab failed");
+    taskbar_list.DeleteTab(hwnd)
   } else {
-    taskbar_list.AddTab(hwnd).expect("AddTab failed");
+    taskbar_list.AddTab(hwnd)
   }
 }
 
@@ -1380,3 +2172,16 @@ impl ResizeDirection {
     }
   }
 }
+
+impl HitTestResult {
+  pub(crate) fn to_win32(&self) -> u32 {
+    match self {
+      HitTestResult::Client => HTCLIENT,
+      HitTestResult::Caption => HTCAPTION,
+      HitTestResult::Minimize => HTMINBUTTON,
+      HitTestResult::Maximize => HTMAXBUTTON,
+      HitTestResult::Close => HTCLOSE,
+      HitTestResult::ResizeEdge(direction) => direction.to_win32(),
+    }
+  }
+}
@@ -3,20 +3,34 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-  dpi::PhysicalPosition,
+  dpi::{PhysicalPosition, Rect},
+  event::{MouseButton, TouchIds},
   icon::Icon,
   keyboard::ModifiersState,
-  platform_impl::platform::{event_loop, minimal_ime::MinimalIme, util},
-  window::{CursorIcon, Fullscreen, Theme, WindowAttributes, WindowSizeConstraints},
+  platform::windows::DropEffect,
+  platform_impl::platform::{
+    event_loop, frame_clock::FrameClock, icon::WinCursor, minimal_ime::MinimalIme, util,
+  },
+  window::{
+    Color, CursorIcon, Fullscreen, HitTestCallback, Theme, WindowAttributes, WindowLevel,
+    WindowShape, WindowSizeConstraints,
+  },
 };
 use parking_lot::MutexGuard;
-use std::io;
+use std::{io, sync::Arc, time::Instant};
 use windows::Win32::{
-  Foundation::{HWND, LPARAM, RECT, WPARAM},
-  Graphics::Gdi::{InvalidateRgn, HRGN},
-  UI::WindowsAndMessaging::*,
+  Foundation::{HMODULE, HWND, LPARAM, RECT, WPARAM},
+  Graphics::Gdi::{InvalidateRgn, HMONITOR, HRGN},
+  UI::{Input::KeyboardAndMouse::GetDoubleClickTime, WindowsAndMessaging::*},
 };
 
+/// Answers a `WM_GETOBJECT` with its WPARAM and LPARAM, or lets tao do it with `None`.
+pub type WmGetObjectHandler = Arc<dyn Fn(usize, isize) -> Option<isize> + Send + Sync>;
+
+/// Answers a message of the window with its HWND, id, WPARAM and LPARAM before tao sees it, or
+/// lets tao handle it with `None`.
+pub type SubclassHandler = Arc<dyn Fn(isize, u32, usize, isize) -> Option<isize> + Send + Sync>;
+
 /// Contains information about states and the window that the callback is going to use.
 pub struct WindowState {
   pub mouse: MouseProperties,
@@ -47,6 +61,67 @@ pub struct WindowState {
   // Used by WM_NCACTIVATE, WM_SETFOCUS and WM_KILLFOCUS
   pub is_active: bool,
   pub is_focused: bool,
+
+  /// Whether the window is flashing because of `request_user_attention`. Cleared on focus.
+  pub attention_requested: bool,
+
+  /// Rectangles of the client area that accept cursor events, used by `WM_NCHITTEST`.
+  pub cursor_hittest_region: Option<Vec<Rect>>,
+  /// The callback of `set_hit_test_callback`, used by `WM_NCHITTEST`.
+  pub hit_test_callback: Option<HitTestCallback>,
+  /// The button of the hit-test callback that the left mouse button was pressed on.
+  pub hit_test_pressed: Option<u32>,
+  /// The handler of `set_wm_getobject_handler`, used by `WM_GETOBJECT`.
+  pub wm_getobject_handler: Option<WmGetObjectHandler>,
+
+  /// The maximized state last reported through `WindowEvent::Maximized`.
+  pub reported_maximized: bool,
+
+  /// The visibility last reported through `WindowEvent::VisibilityChanged`.
+  pub reported_visible: bool,
+
+  /// The occlusion last reported through `WindowEvent::Occluded`.
+  pub reported_occluded: bool,
+
+  /// The fullscreen state last reported through `WindowEvent::FullscreenChanged`.
+  pub reported_fullscreen: Option<Fullscreen>,
+
+  /// The monitor last reported through `WindowEvent::MonitorChanged`.
+  pub current_monitor: HMONITOR,
+
+  /// The screen rectangle that hiding the window animates toward, see `set_minimize_target`.
+  pub minimize_target: Option<Rect>,
+  /// Set while hiding the window shrinks it into the minimize target, the frame changes of the
+  /// animation aren't reported as `Moved` and `Resized` events.
+  pub in_minimize_animation: bool,
+  /// The shape the window is clipped to, applied again when it's resized, see `set_shape`.
+  pub shape: Option<WindowShape>,
+  /// The opacity of the whole window, see `set_opacity`.
+  pub opacity: f32,
+
+  /// Whether the interactive drag started by the last `SC_MOVE` or `SC_SIZE` resizes the window.
+  pub size_move_resize: bool,
+  /// When `Moved` or `Resized` was last sent from an outline drag, to send one per frame at most.
+  pub size_move_report: Option<Instant>,
+
+  /// Ids of the touch contacts, as Windows reuses pointer ids right away.
+  pub touch_ids: TouchIds,
+
+  /// Whether `WM_ERASEBKGND` is skipped, see `with_no_background_erase`.
+  pub no_background_erase: bool,
+  /// The color filled on `WM_ERASEBKGND`, see `Window::set_background_color`.
+  pub background_color: Option<Color>,
+  /// Whether user resizes and moves redraw from inside the modal loop, see
+  /// `with_resize_redraw_sync`.
+  pub resize_redraw_sync: bool,
+  /// Whether `scale_factor` was set with `with_scale_factor_override`, and ignores DPI changes.
+  pub fixed_scale_factor: bool,
+  /// The thread of the frame callbacks and synced redraws, spawned with the first of them.
+  pub frame_clock: Option<FrameClock>,
+  /// Set while such a redraw is delivered, so it can't recurse.
+  pub in_sync_redraw: bool,
+  /// The effect of the drops on the window, see `WindowExtWindows::set_drop_effect`.
+  pub drop_effect: DropEffect,
 }
 
 #[derive(Clone)]
@@ -57,9 +132,23 @@ pub struct SavedWindow {
 #[derive(Clone)]
 pub struct MouseProperties {
   pub cursor: CursorIcon,
+  /// The cursor of `set_custom_cursor`, shown instead of `cursor` while it's set.
+  pub custom_cursor: Option<WinCursor>,
   pub capture_count: u32,
   cursor_flags: CursorFlags,
   pub last_position: Option<PhysicalPosition<f64>>,
+  click: ClickState,
+}
+
+/// The last mouse button press, used to count consecutive clicks since the window class doesn't
+/// use `CS_DBLCLKS`.
+#[derive(Clone, Copy, Default)]
+struct ClickState {
+  button: Option<MouseButton>,
+  count: u32,
+  time: i32,
+  x: i32,
+  y: i32,
 }
 
 bitflags! {
@@ -67,6 +156,11 @@ bitflags! {
         const GRABBED   = 1 << 0;
         const HIDDEN    = 1 << 1;
         const IN_WINDOW = 1 << 2;
+        /// Mirrors the keyboard focus of the window, so a grab is released while another window
+        /// is focused, e.g. after alt-tab.
+        const FOCUSED   = 1 << 3;
+        /// Clips the cursor to a single pixel, keeping it in place. Excludes `GRABBED`.
+        const LOCKED    = 1 << 4;
     }
 }
 bitflags! {
@@ -111,6 +205,9 @@ bitflags! {
 
         const RIGHT_TO_LEFT_LAYOUT = 1 << 22;
 
+        /// The window is made layered to lower its opacity.
+        const TRANSLUCENT = 1 << 23;
+
         const EXCLUSIVE_FULLSCREEN_OR_MASK = WindowFlags::ALWAYS_ON_TOP.bits;
     }
 }
@@ -126,9 +223,11 @@ impl WindowState {
     WindowState {
       mouse: MouseProperties {
         cursor: CursorIcon::default(),
+        custom_cursor: None,
         capture_count: 0,
         cursor_flags: CursorFlags::empty(),
         last_position: None,
+        click: ClickState::default(),
       },
 
       size_constraints: attributes.inner_size_constraints,
@@ -152,9 +251,41 @@ impl WindowState {
       window_flags: WindowFlags::empty(),
       is_active: false,
       is_focused: false,
+      attention_requested: false,
+      cursor_hittest_region: None,
+      hit_test_callback: None,
+      hit_test_pressed: None,
+      wm_getobject_handler: None,
+      reported_maximized: attributes.maximized,
+      reported_visible: attributes.visible,
+      reported_occluded: false,
+      reported_fullscreen: None,
+      current_monitor: HMONITOR::default(),
+      minimize_target: None,
+      in_minimize_animation: false,
+      shape: None,
+      opacity: 1.0,
+      size_move_resize: false,
+      size_move_report: None,
+      touch_ids: TouchIds::default(),
+      no_background_erase: false,
+      background_color: attributes.background_color,
+      resize_redraw_sync: false,
+      fixed_scale_factor: false,
+      frame_clock: None,
+      in_sync_redraw: false,
+      drop_effect: DropEffect::default(),
     }
   }
 
+  /// The color filled on `WM_ERASEBKGND`, matching the theme unless one was set.
+  pub fn background_color(&self) -> Color {
+    self.background_color.unwrap_or(match self.current_theme {
+      Theme::Dark => Color::new(32, 32, 32, 255),
+      Theme::Light => Color::new(255, 255, 255, 255),
+    })
+  }
+
   pub fn window_flags(&self) -> WindowFlags {
     self.window_flags
   }
@@ -168,7 +299,25 @@ impl WindowState {
     let new_flags = this.window_flags;
 
     drop(this);
-    old_flags.apply_diff(window, new_flags);
+    old_flags.apply_diff(window, new_flags, None);
+  }
+
+  /// Like `set_window_flags`, but also moves the window to `rect` in the same `SetWindowPos` that
+  /// applies the new styles.
+  pub fn set_window_flags_and_rect<F>(
+    mut this: MutexGuard<'_, Self>,
+    window: HWND,
+    rect: RECT,
+    f: F,
+  ) where
+    F: FnOnce(&mut WindowFlags),
+  {
+    let old_flags = this.window_flags;
+    f(&mut this.window_flags);
+    let new_flags = this.window_flags;
+
+    drop(this);
+    old_flags.apply_diff(window, new_flags, Some(rect));
   }
 
   pub fn set_window_flags_in_place<F>(&mut self, f: F)
@@ -198,6 +347,45 @@ impl WindowState {
 }
 
 impl MouseProperties {
+  /// Registers a press of `button` at the client position in `lparam` and returns its click count,
+  /// following the system double-click time and rectangle.
+  pub fn press_click(&mut self, button: MouseButton, lparam: LPARAM) -> u32 {
+    let x = i32::from(util::GET_X_LPARAM(lparam));
+    let y = i32::from(util::GET_Y_LPARAM(lparam));
+    let (time, double_click_time, max_dx, max_dy) = unsafe {
+      (
+        GetMessageTime(),
+        GetDoubleClickTime(),
+        GetSystemMetrics(SM_CXDOUBLECLK) / 2,
+        GetSystemMetrics(SM_CYDOUBLECLK) / 2,
+      )
+    };
+
+    let click = &mut self.click;
+    // The message time wraps around, so compare the elapsed time instead.
+    let is_repeat = click.button == Some(button)
+      && time.wrapping_sub(click.time) as u32 <= double_click_time
+      && (x - click.x).abs() <= max_dx
+      && (y - click.y).abs() <= max_dy;
+
+    *click = ClickState {
+      button: Some(button),
+      count: if is_repeat { click.count + 1 } else { 1 },
+      time,
+      x,
+      y,
+    };
+    click.count
+  }
+
+  /// Returns the click count of the press that a release of `button` ends.
+  pub fn release_click(&self, button: MouseButton) -> u32 {
+    match self.click.button == Some(button) {
+      true => self.click.count,
+      false => 1,
+    }
+  }
+
   pub fn cursor_flags(&self) -> CursorFlags {
     self.cursor_flags
   }
@@ -215,12 +403,47 @@ impl MouseProperties {
         return Err(e);
       }
     }
+    self.refresh_cursor();
 
     Ok(())
   }
+
+  /// The cursor shown over the client area, a null cursor while it's hidden.
+  pub fn client_cursor(&self) -> HCURSOR {
+    if self.cursor_flags.is_cursor_hidden() {
+      HCURSOR::default()
+    } else if let Some(cursor) = &self.custom_cursor {
+      cursor.as_raw_handle()
+    } else {
+      unsafe { LoadCursorW(HMODULE::default(), self.cursor.to_windows_cursor()) }
+        .unwrap_or_default()
+    }
+  }
+
+  /// Applies the cursor of the client area if the cursor is over it.
+  ///
+  /// Only the window under the cursor sets it, through `WM_SETCURSOR` otherwise, so a hidden
+  /// cursor shows again over other windows and nothing needs to be balanced like `ShowCursor`.
+  pub fn refresh_cursor(&self) {
+    if self.cursor_flags.contains(CursorFlags::IN_WINDOW) {
+      unsafe { SetCursor(self.client_cursor()) };
+    }
+  }
 }
 
 impl WindowFlags {
+  /// Applies `level`, Windows has nothing above the topmost windows for a screen saver.
+  pub fn set_level(&mut self, level: WindowLevel) {
+    self.set(
+      WindowFlags::ALWAYS_ON_TOP,
+      matches!(level, WindowLevel::AlwaysOnTop | WindowLevel::ScreenSaver),
+    );
+    self.set(
+      WindowFlags::ALWAYS_ON_BOTTOM,
+      level == WindowLevel::AlwaysOnBottom,
+    );
+  }
+
   fn mask(mut self) -> WindowFlags {
     if self.contains(WindowFlags::MARKER_EXCLUSIVE_FULLSCREEN) {
       self |= WindowFlags::EXCLUSIVE_FULLSCREEN_OR_MASK;
@@ -254,6 +477,9 @@ impl WindowFlags {
     if self.contains(WindowFlags::NO_BACK_BUFFER) {
       style_ex |= WS_EX_NOREDIRECTIONBITMAP;
     }
+    if self.contains(WindowFlags::MARKER_DONT_FOCUS) {
+      style_ex |= WS_EX_NOACTIVATE;
+    }
     if self.contains(WindowFlags::CHILD) {
       style |= WS_CHILD; // This is incompatible with WS_POPUP if that gets added eventually.
 
@@ -275,6 +501,9 @@ impl WindowFlags {
     if self.contains(WindowFlags::IGNORE_CURSOR_EVENT) {
       style_ex |= WS_EX_TRANSPARENT | WS_EX_LAYERED;
     }
+    if self.contains(WindowFlags::TRANSLUCENT) {
+      style_ex |= WS_EX_LAYERED;
+    }
     if self.intersects(
       WindowFlags::MARKER_EXCLUSIVE_FULLSCREEN | WindowFlags::MARKER_BORDERLESS_FULLSCREEN,
     ) {
@@ -288,22 +517,28 @@ impl WindowFlags {
   }
 
   /// Adjust the window client rectangle to the return value, if present.
-  fn apply_diff(mut self, window: HWND, mut new: WindowFlags) {
+  fn apply_diff(mut self, window: HWND, mut new: WindowFlags, rect: Option<RECT>) {
     self = self.mask();
     new = new.mask();
 
     let mut diff = self ^ new;
 
-    if diff == WindowFlags::empty() {
+    if diff == WindowFlags::empty() && rect.is_none() {
       return;
     }
 
+    // `ShowWindow` with `SW_MAXIMIZE` or `SW_RESTORE` would show a hidden window, so the
+    // maximized state of a hidden window is only recorded in the flags and applied when it's
+    // shown again.
+    let visible = new.contains(WindowFlags::VISIBLE);
+    let shown = visible && diff.contains(WindowFlags::VISIBLE);
+
     if new.contains(WindowFlags::VISIBLE) {
       unsafe {
         ShowWindow(
           window,
-          if self.contains(WindowFlags::MARKER_DONT_FOCUS) {
-            self.set(WindowFlags::MARKER_DONT_FOCUS, false);
+          // Also covers the marker being removed after the first show.
+          if (self | new).contains(WindowFlags::MARKER_DONT_FOCUS) {
             SW_SHOWNOACTIVATE
           } else {
             SW_SHOW
@@ -312,9 +547,13 @@ impl WindowFlags {
       }
     }
 
-    if diff.contains(WindowFlags::ALWAYS_ON_TOP) {
+    // Showing the window again also restores its place in the topmost band, which some
+    // shell paths drop while the window is hidden.
+    if diff.contains(WindowFlags::ALWAYS_ON_TOP)
+      || (shown && new.contains(WindowFlags::ALWAYS_ON_TOP))
+    {
       unsafe {
-        let _ = SetWindowPos(
+        if let Err(e) = SetWindowPos(
           window,
           match new.contains(WindowFlags::ALWAYS_ON_TOP) {
             true => HWND_TOPMOST,
@@ -325,14 +564,17 @@ impl WindowFlags {
           0,
           0,
           SWP_ASYNCWINDOWPOS | SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
-        );
+        ) {
+          warn!("Failed to change the always on top state: {}", e);
+        }
         InvalidateRgn(window, HRGN::default(), false);
       }
     }
 
-    if diff.contains(WindowFlags::ALWAYS_ON_BOTTOM) {
+    // Leaving the bottom for the top is already covered by `ALWAYS_ON_TOP` above.
+    if diff.contains(WindowFlags::ALWAYS_ON_BOTTOM) && !new.contains(WindowFlags::ALWAYS_ON_TOP) {
       unsafe {
-        let _ = SetWindowPos(
+        if let Err(e) = SetWindowPos(
           window,
           match new.contains(WindowFlags::ALWAYS_ON_BOTTOM) {
             true => HWND_BOTTOM,
@@ -343,12 +585,18 @@ impl WindowFlags {
           0,
           0,
           SWP_ASYNCWINDOWPOS | SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
-        );
+        ) {
+          warn!("Failed to change the always on bottom state: {}", e);
+        }
         InvalidateRgn(window, HRGN::default(), false);
       }
     }
 
-    if diff.contains(WindowFlags::MAXIMIZED) || new.contains(WindowFlags::MAXIMIZED) {
+    if visible
+      && (diff.contains(WindowFlags::MAXIMIZED)
+        || new.contains(WindowFlags::MAXIMIZED)
+        || (shown && unsafe { IsZoomed(window) }.as_bool()))
+    {
       unsafe {
         ShowWindow(
           window,
@@ -397,7 +645,7 @@ impl WindowFlags {
       }
     }
 
-    if diff != WindowFlags::empty() {
+    if diff != WindowFlags::empty() || rect.is_some() {
       let (style, style_ex) = new.to_window_styles();
 
       unsafe {
@@ -414,7 +662,11 @@ impl WindowFlags {
           SetWindowLongW(window, GWL_EXSTYLE, style_ex.0 as i32);
         }
 
-        let mut flags = SWP_NOZORDER | SWP_NOMOVE | SWP_NOSIZE | SWP_FRAMECHANGED;
+        let mut flags = SWP_NOZORDER | SWP_FRAMECHANGED;
+        if rect.is_none() {
+          flags |= SWP_NOMOVE | SWP_NOSIZE;
+        }
+        let rect = rect.unwrap_or_default();
 
         // We generally don't want style changes here to affect window
         // focus, but for fullscreen windows they must be activated
@@ -426,7 +678,17 @@ impl WindowFlags {
         }
 
         // Refresh the window frame
-        let _ = SetWindowPos(window, HWND::default(), 0, 0, 0, 0, flags);
+        if let Err(e) = SetWindowPos(
+          window,
+          HWND::default(),
+          rect.left,
+          rect.top,
+          rect.right - rect.left,
+          rect.bottom - rect.top,
+          flags,
+        ) {
+          warn!("Failed to refresh the window frame: {}", e);
+        }
         SendMessageW(
           window,
           *event_loop::SET_RETAIN_STATE_ON_SIZE_MSG_ID,
@@ -439,22 +701,58 @@ impl WindowFlags {
 }
 
 impl CursorFlags {
+  /// Whether the cursor is hidden over the client area. A grabbed window that lost focus shows it,
+  /// so hiding and grabbing it for pointer-lock style input doesn't leave the user without one.
+  fn is_cursor_hidden(self) -> bool {
+    self.contains(CursorFlags::HIDDEN)
+      && (self.contains(CursorFlags::FOCUSED)
+        || !self.intersects(CursorFlags::GRABBED | CursorFlags::LOCKED))
+  }
+
   fn refresh_os_cursor(self, window: HWND) -> Result<(), io::Error> {
     let client_rect = util::get_client_rect(window)?;
 
-    if util::is_focused(window) {
-      let cursor_clip = match self.contains(CursorFlags::GRABBED) {
-        true => Some(client_rect),
-        false => None,
-      };
-
-      let rect_to_tuple = |rect: RECT| (rect.left, rect.top, rect.right, rect.bottom);
-      let active_cursor_clip = rect_to_tuple(util::get_cursor_clip()?);
-      let desktop_rect = rect_to_tuple(util::get_desktop_rect());
-
-      let active_cursor_clip = match desktop_rect == active_cursor_clip {
-        true => None,
-        false => Some(active_cursor_clip),
+    let rect_to_tuple = |rect: RECT| (rect.left, rect.top, rect.right, rect.bottom);
+    let active_cursor_clip = rect_to_tuple(util::get_cursor_clip()?);
+    let desktop_rect = rect_to_tuple(util::get_desktop_rect());
+
+    let active_cursor_clip = match desktop_rect == active_cursor_clip {
+      true => None,
+      false => Some(active_cursor_clip),
+    };
+    // A lock is a clip to a single pixel of the client area.
+    let is_lock = |(left, top, right, bottom): (i32, i32, i32, i32)| {
+      right - left == 1
+        && bottom - top == 1
+        && (client_rect.left..client_rect.right).contains(&left)
+        && (client_rect.top..client_rect.bottom).contains(&top)
+    };
+
+    if self.contains(CursorFlags::FOCUSED) {
+      let cursor_clip = if self.contains(CursorFlags::LOCKED) {
+        match active_cursor_clip {
+          Some(clip) if is_lock(clip) => Some(RECT {
+            left: clip.0,
+            top: clip.1,
+            right: clip.2,
+            bottom: clip.3,
+          }),
+          _ => {
+            let position = util::cursor_position()?;
+            let x = (position.x as i32).clamp(client_rect.left, client_rect.right - 1);
+            let y = (position.y as i32).clamp(client_rect.top, client_rect.bottom - 1);
+            Some(RECT {
+              left: x,
+              top: y,
+              right: x + 1,
+              bottom: y + 1,
+            })
+          }
+        }
+      } else if self.contains(CursorFlags::GRABBED) {
+        Some(client_rect)
+      } else {
+        None
       };
 
       // We do this check because calling `set_cursor_clip` incessantly will flood the event
@@ -463,13 +761,11 @@ impl CursorFlags {
       if active_cursor_clip != cursor_clip.map(rect_to_tuple) {
         util::set_cursor_clip(cursor_clip)?;
       }
-    }
-
-    let cursor_in_client = self.contains(CursorFlags::IN_WINDOW);
-    if cursor_in_client {
-      util::set_cursor_hidden(self.contains(CursorFlags::HIDDEN));
-    } else {
-      util::set_cursor_hidden(false);
+    } else if active_cursor_clip.map_or(false, |clip| {
+      clip == rect_to_tuple(client_rect) || is_lock(clip)
+    }) {
+      // Release our grab while another window is focused, but not the clip of another window.
+      util::set_cursor_clip(None)?;
     }
 
     Ok(())
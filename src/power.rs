@@ -0,0 +1,42 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! The [`PowerAssertion`] struct, to keep the display awake.
+//!
+//! An assertion lasts until it's dropped, so the display sleeps as usual again once all of them
+//! are released, or when the application exits.
+
+use crate::{error::ExternalError, event_loop::EventLoopWindowTarget, platform_impl};
+
+/// Keeps the display from sleeping, see the [module-level docs](self).
+///
+/// ## Platform-specific
+///
+/// - **Windows:** A power request keeping both the display and the system on.
+/// - **macOS:** An IOKit assertion preventing the idle display sleep, which also prevents the idle
+///   system sleep.
+/// - **Linux:** Inhibits the session from going idle through GTK, which uses the session manager
+///   or the inhibit portal, falling back to `org.freedesktop.ScreenSaver` on the session D-Bus.
+///   The fallback is taken on a thread, so that it doesn't block the event loop, and failing to
+///   take it is only logged.
+/// - **iOS / Android:** Unsupported, creating an assertion returns
+///   [`ExternalError::NotSupported`].
+pub struct PowerAssertion {
+  _assertion: platform_impl::PowerAssertion,
+}
+
+impl PowerAssertion {
+  /// Keeps the display awake until the assertion is dropped.
+  ///
+  /// `reason` is shown by the system tools listing what prevents the display from sleeping,
+  /// e.g. "Remote session in progress".
+  #[inline]
+  pub fn keep_display_awake<T: 'static>(
+    event_loop: &EventLoopWindowTarget<T>,
+    reason: &str,
+  ) -> Result<Self, ExternalError> {
+    platform_impl::PowerAssertion::keep_display_awake(&event_loop.p, reason).map(|assertion| Self {
+      _assertion: assertion,
+    })
+  }
+}
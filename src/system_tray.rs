@@ -0,0 +1,117 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! The [`SystemTray`] struct and associated types.
+//!
+//! A system tray is an icon in the notification area of the taskbar on Windows, the menu bar on
+//! macOS, or the tray of the desktop on Linux. Clicks on it are delivered to the event loop as
+//! [`Event::SystemTrayEvent`].
+//!
+//! A [`Submenu`] can be shown when the icon is right-clicked, see [`SystemTray::set_menu`].
+//!
+//! [`Event::SystemTrayEvent`]: crate::event::Event::SystemTrayEvent
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{
+  error::ExternalError, event_loop::EventLoopWindowTarget, menu::Submenu, platform_impl,
+  window::Icon,
+};
+
+/// Identifies a [`SystemTray`] in the [`Event::SystemTrayEvent`] of its clicks.
+///
+/// [`Event::SystemTrayEvent`]: crate::event::Event::SystemTrayEvent
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct SystemTrayId(pub(crate) u32);
+
+/// Object that allows building a system tray.
+#[derive(Debug, Clone)]
+pub struct SystemTrayBuilder {
+  icon: Icon,
+  tooltip: Option<String>,
+}
+
+impl SystemTrayBuilder {
+  /// Creates a builder for a system tray showing `icon`.
+  #[inline]
+  pub fn new(icon: Icon) -> Self {
+    Self {
+      icon,
+      tooltip: None,
+    }
+  }
+
+  /// Sets the tooltip shown when hovering the icon.
+  #[inline]
+  pub fn with_tooltip<S: Into<String>>(mut self, tooltip: S) -> Self {
+    self.tooltip = Some(tooltip.into());
+    self
+  }
+
+  /// Adds the icon to the system tray.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Needs a tray host implementing `StatusNotifierItem`, like the ones of KDE, of
+  ///   most other desktops and of the AppIndicator extension of GNOME. Returns an
+  ///   [`ExternalError::Os`] without one.
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  pub fn build<T: 'static>(
+    self,
+    window_target: &EventLoopWindowTarget<T>,
+  ) -> Result<SystemTray, ExternalError> {
+    static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let tray = platform_impl::SystemTray::new(&window_target.p, id, self.icon, self.tooltip)?;
+    Ok(SystemTray {
+      id: SystemTrayId(id),
+      tray,
+    })
+  }
+}
+
+/// An icon in the system tray, removed when dropped.
+///
+/// It has to be used on the thread of the event loop that built it.
+pub struct SystemTray {
+  id: SystemTrayId,
+  tray: platform_impl::SystemTray,
+}
+
+impl SystemTray {
+  /// Returns the identifier of the system tray in its events.
+  #[inline]
+  pub fn id(&self) -> SystemTrayId {
+    self.id
+  }
+
+  /// Replaces the icon.
+  #[inline]
+  pub fn set_icon(&self, icon: Icon) {
+    self.tray.set_icon(icon)
+  }
+
+  /// Sets the tooltip shown when hovering the icon, or removes it with `None`.
+  #[inline]
+  pub fn set_tooltip(&self, tooltip: Option<&str>) {
+    self.tray.set_tooltip(tooltip)
+  }
+
+  /// Sets the menu shown when the icon is right-clicked, or removes it with `None`.
+  ///
+  /// The right click is still delivered as a [`SystemTrayEvent::Click`], and choosing an item
+  /// delivers [`Event::MenuEvent`] like in the other menus.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Shown when the tray host asks for the context menu of the item, which most
+  ///   hosts do on a right click, and only on X11. The hosts that only show the menus exported
+  ///   with `com.canonical.dbusmenu`, like the AppIndicator extension of GNOME, don't show it.
+  ///
+  /// [`SystemTrayEvent::Click`]: crate::event::SystemTrayEvent::Click
+  /// [`Event::MenuEvent`]: crate::event::Event::MenuEvent
+  #[inline]
+  pub fn set_menu(&self, menu: Option<&Submenu>) {
+    self.tray.set_menu(menu.map(|menu| &menu.submenu))
+  }
+}
@@ -3,24 +3,34 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! The `Window` struct and associated types.
-use std::fmt;
+use std::{
+  cmp::Reverse,
+  fmt,
+  io::{self, Read},
+  path::PathBuf,
+  sync::Arc,
+};
 
 use crate::{
-  dpi::{LogicalSize, PhysicalPosition, PhysicalSize, Pixel, PixelUnit, Position, Size},
-  error::{ExternalError, NotSupportedError, OsError},
-  event_loop::EventLoopWindowTarget,
+  accessibility::AccessibilityActionSender,
+  dpi::{LogicalSize, PhysicalPosition, PhysicalSize, Pixel, PixelUnit, Position, Rect, Size},
+  error::{ExternalError, NotSupportedError, OsError, WindowOperationError},
+  event_loop::{EventLoopWindowTarget, MotionCoalescing},
+  menu::{Menu, Submenu},
   monitor::{MonitorHandle, VideoMode},
   platform_impl,
+  window_registry::WindowRegistry,
 };
 
-pub use crate::icon::{BadIcon, Icon};
+pub use crate::icon::{BadIcon, Icon, RgbaIcon};
 
 /// Progress State
 #[derive(Debug, Clone, Copy)]
 pub enum ProgressState {
   None,
   Normal,
-  /// **Treated as Normal in linux and macOS**
+  /// Shown as an animated bar on macOS and as a visible progress without a value on Linux.
+  /// The progress value is ignored in this state.
   Indeterminate,
   /// **Treated as Normal in linux**
   Paused,
@@ -37,8 +47,125 @@ pub struct ProgressBarState {
   pub unity_uri: Option<String>,
 }
 
+/// An owned image in RGBA order, with 8 bits per channel and straight alpha, returned by
+/// [`Window::capture`] and [`MonitorHandle::capture`], and taken by [`Window::set_custom_cursor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+  /// The width of the image, in pixels.
+  pub width: u32,
+  /// The height of the image, in pixels.
+  pub height: u32,
+  /// The number of bytes between the start of two rows.
+  pub stride: u32,
+  /// The pixels, row by row from the top.
+  pub data: Vec<u8>,
+}
+
+impl RgbaImage {
+  /// Crops the image to `rect`, relative to its top-left corner, for [`Window::capture_rect`] and
+  /// [`MonitorHandle::capture_rect`].
+  pub(crate) fn crop(&self, rect: Rect, scale_factor: f64) -> Result<RgbaImage, ExternalError> {
+    let position = rect.position.to_physical::<f64>(scale_factor);
+    let size = rect.size.to_physical::<f64>(scale_factor);
+    let left = position.x.round().clamp(0.0, self.width as f64) as u32;
+    let top = position.y.round().clamp(0.0, self.height as f64) as u32;
+    let right = (position.x + size.width)
+      .round()
+      .clamp(0.0, self.width as f64) as u32;
+    let bottom = (position.y + size.height)
+      .round()
+      .clamp(0.0, self.height as f64) as u32;
+    if right <= left || bottom <= top {
+      return Err(ExternalError::InvalidInput(
+        "the rectangle is outside of the captured area",
+      ));
+    }
+
+    let width = right - left;
+    let height = bottom - top;
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for row in top..bottom {
+      let start = (row * self.stride + left * 4) as usize;
+      data.extend_from_slice(&self.data[start..start + (width * 4) as usize]);
+    }
+    Ok(RgbaImage {
+      width,
+      height,
+      stride: width * 4,
+      data,
+    })
+  }
+
+  /// The pixels without the padding at the end of the rows, for [`Window::set_custom_cursor`].
+  pub(crate) fn to_rgba_icon(&self) -> Result<RgbaIcon, ExternalError> {
+    if self.width == 0 || self.height == 0 {
+      return Err(ExternalError::InvalidInput("the image is empty"));
+    }
+    let (row_len, stride) = (self.width as usize * 4, self.stride as usize);
+    if stride < row_len || self.data.len() < stride * (self.height as usize - 1) + row_len {
+      return Err(ExternalError::InvalidInput(
+        "the data of the image is shorter than its size",
+      ));
+    }
+    let mut rgba = Vec::with_capacity(row_len * self.height as usize);
+    for row in 0..self.height as usize {
+      rgba.extend_from_slice(&self.data[row * stride..row * stride + row_len]);
+    }
+    Ok(RgbaIcon {
+      rgba,
+      width: self.width,
+      height: self.height,
+    })
+  }
+}
+
+/// Identifies a capture requested with
+/// [`EventLoopWindowTarget::request_window_capture`] or
+/// [`EventLoopWindowTarget::request_monitor_capture`] in the [`Event::CaptureCompleted`] of its
+/// image.
+///
+/// [`EventLoopWindowTarget::request_window_capture`]: crate::event_loop::EventLoopWindowTarget::request_window_capture
+/// [`EventLoopWindowTarget::request_monitor_capture`]: crate::event_loop::EventLoopWindowTarget::request_monitor_capture
+/// [`Event::CaptureCompleted`]: crate::event::Event::CaptureCompleted
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct CaptureId(pub(crate) u32);
+
+/// A color in RGBA order, with 8 bits per channel and straight alpha.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Color {
+  /// The red channel.
+  pub r: u8,
+  /// The green channel.
+  pub g: u8,
+  /// The blue channel.
+  pub b: u8,
+  /// The alpha channel, `255` being opaque.
+  pub a: u8,
+}
+
+impl Color {
+  /// Creates a color from its channels.
+  pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+    Self { r, g, b, a }
+  }
+}
+
 /// Represents a window.
 ///
+/// `Window` is `Send` and `Sync`. [`Window::request_redraw`], [`Window::id`] and
+/// [`Window::scale_factor`] can be called from any thread on every platform but iOS and Android.
+/// Other methods should be called from the thread running the event loop, except on:
+///
+/// - **Windows:** Methods go through the Win32 window of the event loop thread and can be called
+///   from any thread.
+/// - **macOS:** Methods are dispatched to the main thread and can be called from any thread.
+/// - **Linux:** Setters are forwarded to the event loop thread and can be called from any thread,
+///   but getters which query GTK, such as [`Window::inner_position`] or
+///   [`Window::current_monitor`], must be called from the event loop thread. So must
+///   [`Window::show_context_menu`] and `WindowExtUnix::set_event_hook`, which take values that
+///   aren't `Send`.
+///
 /// # Example
 ///
 /// ```no_run
@@ -64,7 +191,9 @@ pub struct ProgressBarState {
 /// });
 /// ```
 pub struct Window {
-  pub(crate) window: platform_impl::Window,
+  pub(crate) window: Arc<platform_impl::Window>,
+  /// The registry of the event loop, `None` for the window lent out by the registry itself.
+  pub(crate) registry: Option<Arc<WindowRegistry>>,
 }
 
 impl fmt::Debug for Window {
@@ -75,13 +204,20 @@ impl fmt::Debug for Window {
 
 impl Drop for Window {
   fn drop(&mut self) {
+    let registry = match self.registry.take() {
+      Some(registry) => registry,
+      None => return,
+    };
     // If the window is in exclusive fullscreen, we must restore the desktop
     // video mode (generally this would be done on application exit, but
     // closing the window doesn't necessarily always mean application exit,
     // such as when there are multiple windows)
     if let Some(Fullscreen::Exclusive(_)) = self.fullscreen() {
-      self.set_fullscreen(None);
+      let _ = self.window.set_fullscreen(None);
     }
+    // `window_from_id` doesn't find the window anymore once it's dropped, even before the event
+    // loop delivers `WindowEvent::Destroyed`.
+    registry.remove(self.id());
   }
 }
 
@@ -91,6 +227,13 @@ impl Drop for Window {
 ///
 /// Whenever you receive an event specific to a window, this event contains a `WindowId` which you
 /// can then compare to the ids of your windows.
+///
+/// Ids are never reused while the event loop is alive, so an id that was reported as
+/// [`WindowEvent::Destroyed`](crate::event::WindowEvent::Destroyed) can't start referring to a new
+/// window. On Windows this relies on the uniqueness bits the system puts in every `HWND`.
+///
+/// `WindowId` deliberately doesn't implement serde's traits, even with the `serde` feature: it
+/// wraps an opaque platform handle that is meaningless outside of the running process.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct WindowId(pub(crate) platform_impl::WindowId);
 
@@ -104,6 +247,28 @@ impl WindowId {
   pub unsafe fn dummy() -> Self {
     WindowId(platform_impl::WindowId::dummy())
   }
+
+  /// Returns the raw value of the id, e.g. to log it or to hand it over through IPC.
+  ///
+  /// The value is only meaningful in this process, while the event loop that created the window
+  /// is alive.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Android:** Every window is the one of the activity and has the same id.
+  pub fn into_raw(self) -> u64 {
+    self.0.into_raw()
+  }
+
+  /// Rebuilds an id from the value returned by [`WindowId::into_raw`] in this process.
+  ///
+  /// A value that doesn't come from [`WindowId::into_raw`] gives an id that doesn't match any
+  /// window, see [`EventLoopWindowTarget::window_from_id`].
+  ///
+  /// [`EventLoopWindowTarget::window_from_id`]: crate::event_loop::EventLoopWindowTarget::window_from_id
+  pub fn from_raw(raw: u64) -> Self {
+    WindowId(platform_impl::WindowId::from_raw(raw))
+  }
 }
 
 /// Object that allows you to build windows.
@@ -163,6 +328,14 @@ pub struct WindowAttributes {
   /// [`Window::set_outer_position`]: crate::window::Window::set_outer_position
   pub position: Option<Position>,
 
+  /// Whether the window is cascaded from the last window created with this option, when
+  /// `position` is `None`.
+  ///
+  /// See [`WindowBuilder::with_position_cascade`] for details.
+  ///
+  /// The default is `false`.
+  pub position_cascade: bool,
+
   /// Whether the window is resizable or not.
   ///
   /// The default is `true`.
@@ -215,20 +388,29 @@ pub struct WindowAttributes {
   /// The default is `false`.
   pub transparent: bool,
 
+  /// The opacity of the whole window, from `0.0` to `1.0`.
+  ///
+  /// The default is `1.0`.
+  ///
+  /// See [`Window::set_opacity`] for details.
+  pub opacity: f32,
+
+  /// The color filling the window until the application draws its content.
+  ///
+  /// The default is `None`, a color matching the theme of the window.
+  ///
+  /// See [`Window::set_background_color`] for details.
+  pub background_color: Option<Color>,
+
   /// Whether the window should have borders and bars.
   ///
   /// The default is `true`.
   pub decorations: bool,
 
-  /// Whether the window should always be on top of other windows.
-  ///
-  /// The default is `false`.
-  pub always_on_top: bool,
-
-  /// Whether the window should always be on bottom of other windows.
+  /// The level of the window in the stacking order, see [`Window::set_window_level`].
   ///
-  /// The default is `false`.
-  pub always_on_bottom: bool,
+  /// The default is [`WindowLevel::Normal`].
+  pub window_level: WindowLevel,
 
   /// The window icon.
   ///
@@ -257,6 +439,33 @@ pub struct WindowAttributes {
   ///
   /// - **iOS / Android / Windows:** Unsupported.
   pub visible_on_all_workspaces: bool,
+
+  /// Whether the window should be left out of the taskbar.
+  ///
+  /// See [`Window::set_skip_taskbar`] for the platform differences.
+  ///
+  /// The default is `false`.
+  pub skip_taskbar: bool,
+
+  /// How the cursor motions over the window are coalesced, instead of the
+  /// [`EventLoopBuilder::with_motion_coalescing`] of the event loop.
+  ///
+  /// The default is `None`, the coalescing of the event loop.
+  ///
+  /// [`EventLoopBuilder::with_motion_coalescing`]: crate::event_loop::EventLoopBuilder::with_motion_coalescing
+  pub motion_coalescing: Option<MotionCoalescing>,
+
+  /// The state saved with [`Window::state`], applied over the position, size, maximized and
+  /// fullscreen attributes when the window is built, see [`WindowBuilder::with_restored_state`].
+  ///
+  /// The default is `None`.
+  pub restored_state: Option<WindowState>,
+
+  /// The scale factor of the window instead of the one of its monitor, see
+  /// [`WindowBuilder::with_scale_factor_override`].
+  ///
+  /// The default is `None`.
+  pub scale_factor_override: Option<f64>,
 }
 
 impl Default for WindowAttributes {
@@ -266,6 +475,7 @@ impl Default for WindowAttributes {
       inner_size: None,
       inner_size_constraints: Default::default(),
       position: None,
+      position_cascade: false,
       resizable: true,
       minimizable: true,
       maximizable: true,
@@ -275,14 +485,19 @@ impl Default for WindowAttributes {
       fullscreen: None,
       visible: true,
       transparent: false,
+      opacity: 1.0,
+      background_color: None,
       decorations: true,
-      always_on_top: false,
-      always_on_bottom: false,
+      window_level: WindowLevel::Normal,
       window_icon: None,
       preferred_theme: None,
       focused: true,
       content_protection: false,
       visible_on_all_workspaces: false,
+      skip_taskbar: false,
+      motion_coalescing: None,
+      restored_state: None,
+      scale_factor_override: None,
     }
   }
 }
@@ -296,9 +511,9 @@ impl WindowBuilder {
 
   /// Requests the window to be of specific dimensions.
   ///
-  /// See [`Window::set_inner_size`] for details.
+  /// See [`Window::try_set_inner_size`] for details.
   ///
-  /// [`Window::set_inner_size`]: crate::window::Window::set_inner_size
+  /// [`Window::try_set_inner_size`]: crate::window::Window::try_set_inner_size
   #[inline]
   pub fn with_inner_size<S: Into<Size>>(mut self, size: S) -> Self {
     self.window.inner_size = Some(size.into());
@@ -353,6 +568,25 @@ impl WindowBuilder {
     self
   }
 
+  /// Places the window down and to the right of the last window created with this option that's
+  /// still open, like the native cascading of document windows, so that windows opened one after
+  /// another don't overlap exactly. It has no effect when a position is set with
+  /// [`WindowBuilder::with_position`], or on fullscreen and maximized windows.
+  ///
+  /// A window that would get past the bottom or the right of the work area of the monitor starts
+  /// again from its top left corner. Once all the cascaded windows are closed, the next one gets
+  /// the default position again.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Uses `cascadeTopLeftFromPoint:`.
+  /// - **Linux(Wayland) / iOS / Android:** Unsupported.
+  #[inline]
+  pub fn with_position_cascade(mut self, cascade: bool) -> Self {
+    self.window.position_cascade = cascade;
+    self
+  }
+
   /// Sets whether the window is resizable or not.
   ///
   /// See [`Window::set_resizable`] for details.
@@ -410,9 +644,9 @@ impl WindowBuilder {
 
   /// Sets the window fullscreen state.
   ///
-  /// See [`Window::set_fullscreen`] for details.
+  /// See [`Window::try_set_fullscreen`] for details.
   ///
-  /// [`Window::set_fullscreen`]: crate::window::Window::set_fullscreen
+  /// [`Window::try_set_fullscreen`]: crate::window::Window::try_set_fullscreen
   #[inline]
   pub fn with_fullscreen(mut self, fullscreen: Option<Fullscreen>) -> Self {
     self.window.fullscreen = fullscreen;
@@ -430,6 +664,30 @@ impl WindowBuilder {
     self
   }
 
+  /// Restores the position, size, maximized and fullscreen states saved with [`Window::state`],
+  /// like in a previous run of the application.
+  ///
+  /// The state is checked against the monitors when the window is built, so that it never
+  /// comes back off-screen: the window goes back to its monitor if it's still connected, or to
+  /// the monitor showing most of it, or to the primary monitor otherwise. Among monitors sharing
+  /// an id, like identical displays on Linux, it goes to the one showing most of it. It's moved
+  /// and shrunk for its frame to fit in that monitor.
+  ///
+  /// A maximized or fullscreen window is maximized or fullscreen on that monitor, and gets the
+  /// size set with [`WindowBuilder::with_inner_size`] once it's restored. A window that was in
+  /// exclusive fullscreen is restored in borderless fullscreen.
+  ///
+  /// The state is applied over the attributes set on the builder before or after this call.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux(Wayland) / iOS / Android:** The position is ignored.
+  #[inline]
+  pub fn with_restored_state(mut self, state: WindowState) -> Self {
+    self.window.restored_state = Some(state);
+    self
+  }
+
   /// Sets whether the window will be initially hidden or visible.
   ///
   /// See [`Window::set_visible`] for details.
@@ -448,6 +706,25 @@ impl WindowBuilder {
     self
   }
 
+  /// Sets the initial opacity of the whole window, clamped to `0.0..=1.0`.
+  ///
+  /// See [`Window::set_opacity`] for details.
+  #[inline]
+  pub fn with_opacity(mut self, opacity: f32) -> Self {
+    self.window.opacity = opacity.clamp(0.0, 1.0);
+    self
+  }
+
+  /// Sets the color filling the window until the application draws its content, `None` for a
+  /// color matching the theme of the window.
+  ///
+  /// See [`Window::set_background_color`] for details.
+  #[inline]
+  pub fn with_background_color(mut self, color: Option<Color>) -> Self {
+    self.window.background_color = color;
+    self
+  }
+
   /// Sets whether the window should have a border, a title bar, etc.
   ///
   /// See [`Window::set_decorations`] for details.
@@ -466,20 +743,35 @@ impl WindowBuilder {
   /// [`Window::set_always_on_bottom`]: crate::window::Window::set_always_on_bottom
   #[inline]
   pub fn with_always_on_bottom(mut self, always_on_bottom: bool) -> Self {
-    self.window.always_on_top = false;
-    self.window.always_on_bottom = always_on_bottom;
+    self.window.window_level = if always_on_bottom {
+      WindowLevel::AlwaysOnBottom
+    } else {
+      WindowLevel::Normal
+    };
     self
   }
 
   /// Sets whether or not the window will always be on top of other windows.
   ///
-  /// See [`Window::set_always_on_top`] for details.
+  /// See [`Window::try_set_always_on_top`] for details.
   ///
-  /// [`Window::set_always_on_top`]: crate::window::Window::set_always_on_top
+  /// [`Window::try_set_always_on_top`]: crate::window::Window::try_set_always_on_top
   #[inline]
   pub fn with_always_on_top(mut self, always_on_top: bool) -> Self {
-    self.window.always_on_bottom = false;
-    self.window.always_on_top = always_on_top;
+    self.window.window_level = if always_on_top {
+      WindowLevel::AlwaysOnTop
+    } else {
+      WindowLevel::Normal
+    };
+    self
+  }
+
+  /// Sets the level of the window in the stacking order.
+  ///
+  /// See [`Window::set_window_level`] for details.
+  #[inline]
+  pub fn with_window_level(mut self, level: WindowLevel) -> Self {
+    self.window.window_level = level;
     self
   }
 
@@ -522,6 +814,23 @@ impl WindowBuilder {
     self.window.focused = focused;
     self
   }
+
+  /// Whether the window is activated when it's first shown. The default is `true`.
+  ///
+  /// An inactive window appears without taking the keyboard focus from the foreground window,
+  /// which suits notification popups, and doesn't emit `WindowEvent::Focused(true)` when created.
+  /// Clicking it activates it like any other window. This sets the same attribute as
+  /// [`WindowBuilder::with_focused`].
+  ///
+  /// ## Platform-specific:
+  ///
+  /// - **Linux:** The window manager may still focus the window.
+  /// - **Android / iOS:** Unsupported.
+  #[inline]
+  pub fn with_active(mut self, active: bool) -> WindowBuilder {
+    self.window.focused = active;
+    self
+  }
   /// Prevents the window contents from being captured by other apps.
   ///
   /// ## Platform-specific
@@ -535,8 +844,11 @@ impl WindowBuilder {
 
   /// Sets whether the window should be visible on all workspaces.
   ///
+  /// The setting is applied before the window is first shown.
+  ///
   /// ## Platform-specific
   ///
+  /// - **Linux:** Only works on X11.
   /// - **iOS / Android / Windows:** Unsupported.
   #[inline]
   pub fn with_visible_on_all_workspaces(mut self, visible: bool) -> WindowBuilder {
@@ -544,18 +856,114 @@ impl WindowBuilder {
     self
   }
 
+  /// Sets whether the window should be left out of the taskbar.
+  ///
+  /// See [`Window::set_skip_taskbar`] for details. Where it isn't supported, the window is
+  /// created in the taskbar as usual.
+  #[inline]
+  pub fn with_skip_taskbar(mut self, skip: bool) -> WindowBuilder {
+    self.window.skip_taskbar = skip;
+    self
+  }
+
+  /// Sets how the cursor motions over the window are coalesced, for instance
+  /// [`MotionCoalescing::Latest`] to get a single [`WindowEvent::CursorMoved`] per iteration of
+  /// the event loop when a busy event handler would fall behind them. It overrides the
+  /// [`EventLoopBuilder::with_motion_coalescing`] of the event loop for this window, which still
+  /// applies to its `DeviceEvent::MouseMotion`.
+  ///
+  /// [`WindowEvent::CursorMoved`]: crate::event::WindowEvent::CursorMoved
+  /// [`EventLoopBuilder::with_motion_coalescing`]: crate::event_loop::EventLoopBuilder::with_motion_coalescing
+  #[inline]
+  pub fn with_motion_coalescing(mut self, coalescing: MotionCoalescing) -> WindowBuilder {
+    self.window.motion_coalescing = Some(coalescing);
+    self
+  }
+
+  /// Uses `scale_factor` for the window instead of the one of the monitor it's on, e.g. `1.0` to
+  /// show pixel content 1:1.
+  ///
+  /// Logical sizes and positions of the window are converted with it, and moving the window to
+  /// a monitor of another scale factor keeps its physical size instead of emitting
+  /// [`WindowEvent::ScaleFactorChanged`].
+  ///
+  /// ## Panics
+  ///
+  /// Panics if `scale_factor` isn't a finite number greater than zero.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The window still has to be DPI aware, see
+  ///   `EventLoopBuilderExtWindows::with_dpi_awareness`.
+  /// - **iOS:** Sets the `contentScaleFactor` of the view, like `WindowBuilderExtIOS::with_scale_factor`.
+  /// - **macOS / Linux / Android:** Unsupported, the toolkit scales the window. On Linux X11, the
+  ///   scale of every window can be set with `EventLoopBuilderExtUnix::with_gdk_scale`.
+  ///
+  /// [`WindowEvent::ScaleFactorChanged`]: crate::event::WindowEvent::ScaleFactorChanged
+  #[inline]
+  pub fn with_scale_factor_override(mut self, scale_factor: f64) -> WindowBuilder {
+    assert!(
+      crate::dpi::validate_scale_factor(scale_factor),
+      "`WindowBuilder::with_scale_factor_override` received an invalid scale factor"
+    );
+    self.window.scale_factor_override = Some(scale_factor);
+    self
+  }
+
+  /// Creates the window as a child of `parent`, which it's kept above.
+  ///
+  /// This replaces a parent set with the platform extension traits, see [`Window::set_parent`]
+  /// to change it later.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The window is owned by `parent`, like with
+  ///   `WindowBuilderExtWindows::with_owner_window`: it's left out of the taskbar, and minimized
+  ///   and destroyed along with `parent`.
+  /// - **macOS:** The window is added as a child window of `parent`, so it also moves along with
+  ///   it.
+  /// - **Linux:** The window is made transient for `parent`, which the window manager may honor.
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn with_parent(mut self, parent: &Window) -> WindowBuilder {
+    #[cfg(not(any(target_os = "ios", target_os = "android")))]
+    {
+      self.platform_specific.parent = parent.window.as_parent();
+    }
+    #[cfg(any(target_os = "ios", target_os = "android"))]
+    let _ = parent;
+    self
+  }
+
   /// Builds the window.
   ///
   /// Possible causes of error include denied permission, incompatible system, and lack of memory.
   #[inline]
   pub fn build<T: 'static>(
-    self,
+    mut self,
     window_target: &EventLoopWindowTarget<T>,
   ) -> Result<Window, OsError> {
+    if let Some(state) = self.window.restored_state.take() {
+      let monitors: Vec<_> = window_target.available_monitors().collect();
+      let primary = window_target.primary_monitor();
+      state.restore(&mut self.window, &monitors, primary);
+    }
+    let motion_coalescing = self.window.motion_coalescing;
     platform_impl::Window::new(&window_target.p, self.window, self.platform_specific).map(
       |window| {
         window.request_redraw();
-        Window { window }
+        let window = Window {
+          window: Arc::new(window),
+          registry: Some(window_target.window_registry.registry.clone()),
+        };
+        window_target.window_registry.registry.insert(&window);
+        if let Some(coalescing) = motion_coalescing {
+          window_target
+            .window_motion_coalescing
+            .borrow_mut()
+            .insert(window.id(), coalescing);
+        }
+        window
       },
     )
   }
@@ -615,14 +1023,68 @@ impl Window {
   /// * While processing a `RedrawRequested` event that was sent during `MainEventsCleared` or any
   ///   directly subsequent `RedrawRequested` event.
   ///
+  /// This can be called from any thread and wakes the event loop up. Requests are coalesced, so a
+  /// window gets at most one `RedrawRequested` event per iteration of the event loop, however
+  /// many times this is called.
+  ///
   /// ## Platform-specific
   ///
   /// - **iOS:** Can only be called on the main thread.
+  /// - **Linux / macOS:** Requests made while processing `RedrawRequested` are delivered in the next
+  ///   iteration.
   /// - **Android:** Unsupported.
   #[inline]
   pub fn request_redraw(&self) {
     self.window.request_redraw()
   }
+
+  /// Same as [`Window::request_redraw`], but emits the `RedrawRequested` event at the next
+  /// refresh of the monitor the window is on, so that a frame presented in response to it lines
+  /// up with the vertical blank instead of tearing or being held for a whole refresh.
+  ///
+  /// It's the one-shot version of [`Window::request_frame_callbacks`], for a render loop that
+  /// only draws when there's something new, like the frames of a video. It's coalesced with the
+  /// other redraw requests, and falls back to [`Window::request_redraw`] while the window isn't
+  /// shown, since there's no refresh to wait for.
+  ///
+  /// The refresh rate of the monitor is given by [`MonitorHandle::refresh_rate_millihertz`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Uses a `CVDisplayLink`, which stops while the window is fully occluded.
+  /// - **Windows:** Waits for the vertical blank of the monitor with DXGI, or for the
+  ///   composition of the desktop when DXGI can't.
+  /// - **Linux:** Waits for the next tick of the GTK frame clock, which follows the frame
+  ///   callbacks of the compositor on Wayland and the Present extension on X11.
+  /// - **iOS / Android:** Same as [`Window::request_redraw`].
+  #[inline]
+  pub fn request_redraw_synced(&self) {
+    self.window.request_redraw_synced()
+  }
+
+  /// Emits [`Event::RedrawRequested`] for the window on each refresh of its display while
+  /// `enabled`, so that a render loop can present once per frame without
+  /// [`ControlFlow::Poll`] or busy-waiting.
+  ///
+  /// The redraws stop while the window is minimized or hidden, and follow the refresh rate of the
+  /// monitor the window is on. Like the ones of [`Window::request_redraw`], they're coalesced, so
+  /// a slow frame skips refreshes instead of piling them up.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Uses a `CVDisplayLink`, which also stops while the window is fully occluded.
+  /// - **Windows:** Waits for the vertical blanks of the monitor with DXGI, or for the
+  ///   composition of the desktop when DXGI can't.
+  /// - **Linux:** Follows the GTK frame clock, which the compositor can also stop while the
+  ///   window is occluded.
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`Event::RedrawRequested`]: crate::event::Event::RedrawRequested
+  /// [`ControlFlow::Poll`]: crate::event_loop::ControlFlow::Poll
+  #[inline]
+  pub fn request_frame_callbacks(&self, enabled: bool) {
+    self.window.request_frame_callbacks(enabled)
+  }
 }
 
 /// Position and size functions.
@@ -630,13 +1092,16 @@ impl Window {
   /// Returns the position of the top-left hand corner of the window's client area relative to the
   /// top-left hand corner of the desktop.
   ///
-  /// The same conditions that apply to `outer_position` apply to this method.
+  /// The client area is the part of the window below the title bar and inside the borders, i.e.
+  /// the origin of [`Window::inner_size`]. The same conditions that apply to `outer_position`
+  /// apply to this method.
   ///
   /// ## Platform-specific
   ///
   /// - **iOS:** Can only be called on the main thread. Returns the top left coordinates of the
   ///   window's [safe area] in the screen space coordinate system.
   /// - **Android:** Always returns [`NotSupportedError`].
+  /// - **Linux:** Excludes client-side decorations and their shadows.
   ///
   /// [safe area]: https://developer.apple.com/documentation/uikit/uiview/2891103-safeareainsets?language=objc
   #[inline]
@@ -654,11 +1119,19 @@ impl Window {
   /// The coordinates can be negative if the top-left hand corner of the window is outside
   ///  of the visible screen region.
   ///
+  /// This is the point that [`Window::set_outer_position`] moves, so setting a position and
+  /// reading it back returns the same value once the window manager has applied the move.
+  ///
   /// ## Platform-specific
   ///
   /// - **iOS:** Can only be called on the main thread. Returns the top left coordinates of the
   ///   window in the screen space coordinate system.
   /// - **Android:** Always returns [`NotSupportedError`].
+  /// - **Windows:** The top-left corner of the visible frame, excluding the invisible resize
+  ///   borders that Windows 10 and later add around the window.
+  /// - **macOS:** The top-left corner of the window frame, including the title bar.
+  /// - **Linux:** The GTK reference point of the window, i.e. the top-left corner of the window
+  ///   manager frame.
   /// - **Linux(Wayland)**: Has no effect, since Wayland doesn't support a global cordinate system
   #[inline]
   pub fn outer_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
@@ -704,7 +1177,26 @@ impl Window {
   ///
   /// - **iOS / Android:** Unsupported.
   #[inline]
+  #[deprecated = "Deprecated in favor of Window::try_set_inner_size"]
   pub fn set_inner_size<S: Into<Size>>(&self, size: S) {
+    let _ = self.window.set_inner_size(size.into());
+  }
+
+  /// Like [`Window::set_inner_size`], but reports when the size can't be applied.
+  ///
+  /// Returns [`WindowOperationError::InvalidState`] while the window is fullscreen.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Returns [`WindowOperationError::Os`] when `SetWindowPos` fails.
+  /// - **iOS / Android:** Always returns [`WindowOperationError::NotSupported`].
+  #[inline]
+  pub fn try_set_inner_size<S: Into<Size>>(&self, size: S) -> Result<(), WindowOperationError> {
+    if self.fullscreen().is_some() {
+      return Err(WindowOperationError::InvalidState(
+        "the size of a fullscreen window can't be changed",
+      ));
+    }
     self.window.set_inner_size(size.into())
   }
 
@@ -717,6 +1209,8 @@ impl Window {
   ///
   /// - **iOS:** Can only be called on the main thread. Returns the `PhysicalSize` of the window in
   ///   screen space coordinates.
+  /// - **Windows:** The size of the visible frame, excluding the invisible resize borders like
+  ///   [`Window::outer_position`].
   #[inline]
   pub fn outer_size(&self) -> PhysicalSize<u32> {
     self.window.outer_size()
@@ -724,31 +1218,53 @@ impl Window {
 
   /// Sets a minimum dimension size for the window.
   ///
+  /// The size keeps the unit it is given in, so a [`LogicalSize`] minimum is adjusted when the
+  /// scale factor changes. Both interactive resizes and [`Window::try_set_inner_size`] are
+  /// clamped.
+  ///
+  /// Returns [`ExternalError::InvalidInput`] and keeps the previous constraints if the minimum
+  /// is larger than the maximum size.
+  ///
   /// ## Platform-specific
   ///
   /// - **iOS / Android:** Unsupported.
   #[inline]
-  pub fn set_min_inner_size<S: Into<Size>>(&self, min_size: Option<S>) {
+  pub fn set_min_inner_size<S: Into<Size>>(
+    &self,
+    min_size: Option<S>,
+  ) -> Result<(), ExternalError> {
     self.window.set_min_inner_size(min_size.map(|s| s.into()))
   }
 
   /// Sets a maximum dimension size for the window.
   ///
+  /// See [`Window::set_min_inner_size`] for how the size is applied. Returns
+  /// [`ExternalError::InvalidInput`] if the maximum is smaller than the minimum size.
+  ///
   /// ## Platform-specific
   ///
   /// - **iOS / Android:** Unsupported.
   #[inline]
-  pub fn set_max_inner_size<S: Into<Size>>(&self, max_size: Option<S>) {
+  pub fn set_max_inner_size<S: Into<Size>>(
+    &self,
+    max_size: Option<S>,
+  ) -> Result<(), ExternalError> {
     self.window.set_max_inner_size(max_size.map(|s| s.into()))
   }
 
   /// Sets inner size constraints for the window.
   ///
+  /// See [`Window::set_min_inner_size`] for how the constraints are applied. Returns
+  /// [`ExternalError::InvalidInput`] if a minimum is larger than the matching maximum.
+  ///
   /// ## Platform-specific
   ///
   /// - **iOS / Android:** Unsupported.
   #[inline]
-  pub fn set_inner_size_constraints(&self, constraints: WindowSizeConstraints) {
+  pub fn set_inner_size_constraints(
+    &self,
+    constraints: WindowSizeConstraints,
+  ) -> Result<(), ExternalError> {
     self.window.set_inner_size_constraints(constraints)
   }
 }
@@ -777,7 +1293,9 @@ impl Window {
 
   /// Modifies the window's visibility.
   ///
-  /// If `false`, this will hide the window. If `true`, this will show the window.
+  /// If `false`, this will hide the window. If `true`, this will show the window, unless the
+  /// event loop is [headless](crate::event_loop::EventLoopBuilder::with_headless).
+  ///
   /// ## Platform-specific
   ///
   /// - **Android:** Unsupported.
@@ -787,8 +1305,26 @@ impl Window {
     self.window.set_visible(visible)
   }
 
+  /// Asks the window to close, as if the user had clicked its close button.
+  ///
+  /// This emits [`WindowEvent::CloseRequested`](crate::event::WindowEvent::CloseRequested) for
+  /// the window; nothing is destroyed until the `Window` is dropped, so the event handler can veto
+  /// the close by simply ignoring the event. Once the window is dropped,
+  /// [`WindowEvent::Destroyed`](crate::event::WindowEvent::Destroyed) is delivered and no further
+  /// events are sent for its [`WindowId`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn close(&self) {
+    self.window.close()
+  }
+
   /// Bring the window to front and focus.
   ///
+  /// This is the same as [`Window::set_focus_with_token`] without a token, ignoring the result.
+  ///
   /// ## Platform-specific
   ///
   /// - **iOS / Android:** Unsupported.
@@ -797,6 +1333,33 @@ impl Window {
     self.window.set_focus()
   }
 
+  /// Bring the window to front and focus, using `token` to prove that the request comes from a
+  /// user action when given.
+  ///
+  /// Operating systems prevent applications from stealing the focus, so this does the best
+  /// thing allowed and reports what happened. Minimized and hidden windows are left alone and
+  /// [`FocusResult::Failed`] is returned.
+  ///
+  /// A single-instance application forwards the token of the newly launched instance,
+  /// see [`ActivationToken::from_env`], to the running one that calls this.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** If the foreground can't be taken, the taskbar button is flashed and
+  ///   [`FocusResult::AttentionRequested`] is returned. `token` is ignored, the process forwarding
+  ///   the request should call `AllowSetForegroundWindow` with the pid of this process instead.
+  /// - **Linux:** `token` is used as the startup notification id on X11 and as the
+  ///   xdg-activation token on Wayland, without one GTK requests a token itself. The request is
+  ///   asynchronous and the window manager may still deny it, [`FocusResult::Focused`] only
+  ///   means that it was sent.
+  /// - **macOS:** Unhides the application, orders the window front and makes it key. `token` is
+  ///   ignored.
+  /// - **iOS / Android:** Unsupported, always returns [`FocusResult::Failed`].
+  #[inline]
+  pub fn set_focus_with_token(&self, token: Option<ActivationToken>) -> FocusResult {
+    self.window.set_focus_with_token(token)
+  }
+
   /// Is window active and focused?
   ///
   /// ## Platform-specific
@@ -879,6 +1442,9 @@ impl Window {
   ///
   /// ## Platform-specific
   ///
+  /// - **Linux:** The window manager applies the change asynchronously, so
+  ///   [`Window::inner_size`] keeps the old size until the `Resized` event with the new size
+  ///   arrives. `Maximized` is emitted after that `Resized`.
   /// - **iOS / Android:** Unsupported.
   #[inline]
   pub fn set_maximized(&self, maximized: bool) {
@@ -889,6 +1455,8 @@ impl Window {
   ///
   /// ## Platform-specific
   ///
+  /// - **Linux:** Returns the requested state while a [`Window::set_maximized`] request is
+  ///   pending, until the window manager applies or rejects it.
   /// - **iOS / Android:** Unsupported.
   #[inline]
   pub fn is_maximized(&self) -> bool {
@@ -966,6 +1534,12 @@ impl Window {
 
   /// Sets the window to fullscreen or back.
   ///
+  /// The transition happens asynchronously and [`WindowEvent::FullscreenChanged`] is emitted once
+  /// it completes. Requests made before that are coalesced, so only the last one is applied, and
+  /// [`Window::fullscreen`] reflects it right away.
+  ///
+  /// [`WindowEvent::FullscreenChanged`]: crate::event::WindowEvent::FullscreenChanged
+  ///
   /// ## Platform-specific
   ///
   /// - **macOS:** `Fullscreen::Exclusive` provides true exclusive mode with a
@@ -982,15 +1556,56 @@ impl Window {
   ///   The dock and the menu bar are always disabled in fullscreen mode.
   /// - **iOS:** Can only be called on the main thread.
   /// - **Windows:** Screen saver is disabled in fullscreen mode.
-  /// - **Linux:** The window will only fullscreen to current monitor no matter which enum variant.
+  /// - **Linux:** `Fullscreen::Exclusive` changes the video mode with RandR on X11, and the desktop
+  ///   video mode is restored when leaving fullscreen or when the window is destroyed. The mode
+  ///   can't be larger than the current screen. Wayland doesn't expose video modes.
   /// - **Android:** Unsupported.
   #[inline]
+  #[deprecated = "Deprecated in favor of Window::try_set_fullscreen"]
   pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+    let _ = self.window.set_fullscreen(fullscreen);
+  }
+
+  /// Like [`Window::set_fullscreen`], but reports when the request can't be applied.
+  ///
+  /// Returns [`WindowOperationError::InvalidState`] when the monitor of `fullscreen` is no
+  /// longer connected.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Returns [`WindowOperationError::InvalidState`] while the window is in simple
+  ///   fullscreen, see `WindowExtMacOS::set_simple_fullscreen`, and [`WindowOperationError::Os`]
+  ///   when the video mode of `Fullscreen::Exclusive` can't be set.
+  /// - **iOS:** Returns [`WindowOperationError::NotSupported`] for `None`.
+  /// - **Android:** Always returns [`WindowOperationError::NotSupported`].
+  #[inline]
+  pub fn try_set_fullscreen(
+    &self,
+    fullscreen: Option<Fullscreen>,
+  ) -> Result<(), WindowOperationError> {
+    let monitor = match &fullscreen {
+      Some(Fullscreen::Exclusive(video_mode)) => Some(video_mode.monitor()),
+      Some(Fullscreen::Borderless(monitor)) => monitor.clone(),
+      None => None,
+    };
+    if let Some(monitor) = monitor {
+      if !self
+        .available_monitors()
+        .any(|available| available == monitor)
+      {
+        return Err(WindowOperationError::InvalidState(
+          "the monitor is no longer connected",
+        ));
+      }
+    }
     self.window.set_fullscreen(fullscreen)
   }
 
   /// Gets the window's current fullscreen state.
   ///
+  /// While a transition is in progress, this is the state last requested through
+  /// [`Window::try_set_fullscreen`] rather than the one shown on screen.
+  ///
   /// ## Platform-specific
   ///
   /// - **iOS:** Can only be called on the main thread.
@@ -1000,6 +1615,23 @@ impl Window {
     self.window.fullscreen()
   }
 
+  /// Returns the position, size, monitor, maximized and fullscreen states of the window, to
+  /// save them and restore them later with [`WindowBuilder::with_restored_state`].
+  ///
+  /// With the `serde` feature, the state can be serialized, like in the settings of the
+  /// application.
+  #[inline]
+  pub fn state(&self) -> WindowState {
+    WindowState {
+      position: self.outer_position().unwrap_or_default(),
+      size: self.inner_size(),
+      outer_size: self.outer_size(),
+      monitor: self.current_monitor().map(|monitor| monitor.id()),
+      maximized: self.is_maximized(),
+      fullscreen: self.fullscreen().is_some(),
+    }
+  }
+
   /// Turn window decorations on or off.
   ///
   /// ## Platform-specific
@@ -1012,104 +1644,366 @@ impl Window {
     self.window.set_decorations(decorations)
   }
 
+  /// Sets the level of the window in the stacking order, replacing the one of
+  /// [`Window::try_set_always_on_top`] and [`Window::set_always_on_bottom`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows / Linux:** [`WindowLevel::ScreenSaver`] is the same as
+  ///   [`WindowLevel::AlwaysOnTop`], which already stays above the applications in fullscreen
+  ///   on Windows. On Linux, levels depend on the window manager honoring `_NET_WM_STATE_ABOVE`
+  ///   and `_NET_WM_STATE_BELOW`.
+  /// - **macOS:** [`WindowLevel::ScreenSaver`] is `NSScreenSaverWindowLevel`, and the window is
+  ///   allowed in the spaces of the applications in fullscreen. To follow the user to them, also
+  ///   make it visible on all workspaces with [`Window::set_visible_on_all_workspaces`].
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_window_level(&self, level: WindowLevel) {
+    self.window.set_window_level(level)
+  }
+
   /// Change whether or not the window will always be below other windows.
   ///
+  /// This is mutually exclusive with [`Window::try_set_always_on_top`]: enabling one disables the other.
+  ///
   /// ## Platform-specific
   ///
   /// - **Windows**: There is no guarantee that the window will be the bottom most but it will try to be.
+  /// - **macOS:** The window is placed at the desktop window level, below the desktop icons.
+  /// - **Linux:** Depends on the window manager honoring `_NET_WM_STATE_BELOW`.
   /// - **iOS / Android:** Unsupported.
   #[inline]
   pub fn set_always_on_bottom(&self, always_on_bottom: bool) {
     self.window.set_always_on_bottom(always_on_bottom)
   }
 
-  /// Change whether or not the window will always be on top of other windows.
+  /// Moves the window above the other windows of its level, without activating or focusing it.
+  ///
+  /// Windows that are always on top, see [`Window::try_set_always_on_top`], stay above it.
   ///
   /// ## Platform-specific
   ///
-  /// - **iOS / Android:** Unsupported.
+  /// - **Linux:** Depends on the window manager honoring the request.
+  /// - **Linux (Wayland) / iOS / Android:** Always returns an [`ExternalError::NotSupported`].
   #[inline]
-  pub fn set_always_on_top(&self, always_on_top: bool) {
-    self.window.set_always_on_top(always_on_top)
+  pub fn raise(&self) -> Result<(), ExternalError> {
+    self.window.raise()
   }
 
-  /// Sets the window icon. On Windows and Linux, this is typically the small icon in the top-left
-  /// corner of the title bar.
+  /// Moves the window below the other windows of its level, without activating or focusing it.
   ///
-  /// ## Platform-specific
+  /// Windows that are always on bottom, see [`Window::set_always_on_bottom`], stay below it.
   ///
-  /// - **iOS / Android / macOS:** Unsupported.
+  /// ## Platform-specific
   ///
-  /// On Windows, this sets `ICON_SMALL`. The base size for a window icon is 16x16, but it's
-  /// recommended to account for screen scaling and pick a multiple of that, i.e. 32x32.
+  /// - **Linux:** Depends on the window manager honoring the request.
+  /// - **Linux (Wayland) / iOS / Android:** Always returns an [`ExternalError::NotSupported`].
   #[inline]
-  pub fn set_window_icon(&self, window_icon: Option<Icon>) {
-    self.window.set_window_icon(window_icon)
+  pub fn lower(&self) -> Result<(), ExternalError> {
+    self.window.lower()
   }
 
-  /// Sets location of IME candidate box in client area coordinates relative to the top left.
+  /// Moves the window right above `sibling`, without activating or focusing either of them.
+  ///
+  /// The window doesn't leave its level, so a window that is always on top stays above `sibling`
+  /// if that one isn't, and the other way around.
   ///
   /// ## Platform-specific
   ///
-  /// - **iOS / Android:** Unsupported.
+  /// - **Linux:** Depends on the window manager honoring the request.
+  /// - **Linux (Wayland) / iOS / Android:** Always returns an [`ExternalError::NotSupported`].
   #[inline]
-  pub fn set_ime_position<P: Into<Position>>(&self, position: P) {
-    self.window.set_ime_position(position.into())
+  pub fn restack_above(&self, sibling: &Window) -> Result<(), ExternalError> {
+    self.window.restack_above(&sibling.window)
   }
 
-  /// Sets the taskbar progress state.
+  /// Sets the parent the window stays above, or detaches it from its parent with `None`, see
+  /// [`WindowBuilder::with_parent`].
   ///
   /// ## Platform-specific
   ///
-  /// - **Linux / macOS**: Progress bar is app-wide and not specific to this window. Only supported desktop environments with `libunity` (e.g. GNOME).
+  /// - **Windows:** Changes the owner of the window, unsupported for a window created as a child
+  ///   with `WindowBuilderExtWindows::with_parent_window`.
   /// - **iOS / Android:** Unsupported.
   #[inline]
-  pub fn set_progress_bar(&self, _progress: ProgressBarState) {
-    #[cfg(any(
-      windows,
-      target_os = "linux",
-      target_os = "dragonfly",
-      target_os = "freebsd",
-      target_os = "netbsd",
-      target_os = "openbsd",
-      target_os = "macos",
-    ))]
-    self.window.set_progress_bar(_progress)
+  pub fn set_parent(&self, parent: Option<&Window>) {
+    self.window.set_parent(parent.map(|parent| &*parent.window))
   }
 
-  /// Requests user attention to the window, this has no effect if the application
-  /// is already focused. How requesting for user attention manifests is platform dependent,
-  /// see `UserAttentionType` for details.
+  /// Change whether or not the window will always be on top of other windows.
   ///
-  /// Providing `None` will unset the request for user attention. Unsetting the request for
-  /// user attention might not be done automatically by the WM when the window receives input.
+  /// This is mutually exclusive with [`Window::set_always_on_bottom`]: enabling one disables the other.
   ///
   /// ## Platform-specific
   ///
   /// - **iOS / Android:** Unsupported.
-  /// - **macOS:** `None` has no effect.
-  /// - **Linux:** Urgency levels have the same effect.
   #[inline]
-  pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
-    self.window.request_user_attention(request_type)
+  #[deprecated = "Deprecated in favor of Window::try_set_always_on_top"]
+  pub fn set_always_on_top(&self, always_on_top: bool) {
+    let _ = self.window.set_always_on_top(always_on_top);
   }
 
-  /// Returns the current window theme.
+  /// Like [`Window::set_always_on_top`], but reports when the backend can't keep the window on
+  /// top.
   ///
   /// ## Platform-specific
   ///
-  /// - **iOS / Android:** Unsupported.
+  /// - **Linux (Wayland) / iOS / Android:** Always returns [`WindowOperationError::NotSupported`].
   #[inline]
-  pub fn theme(&self) -> Theme {
-    self.window.theme()
+  pub fn try_set_always_on_top(&self, always_on_top: bool) -> Result<(), WindowOperationError> {
+    self.window.set_always_on_top(always_on_top)
   }
 
-  /// Prevents the window contents from being captured by other apps.
+  /// Sets the window icon. On Windows and Linux, this is typically the small icon in the top-left
+  /// corner of the title bar.
   ///
   /// ## Platform-specific
   ///
-  /// - **iOS / Android / Linux:** Unsupported.
-  pub fn set_content_protection(&self, #[allow(unused)] enabled: bool) {
+  /// - **iOS / Android / macOS:** Unsupported.
+  ///
+  /// On Windows, this sets `ICON_SMALL`. The base size for a window icon is 16x16, but it's
+  /// recommended to account for screen scaling and pick a multiple of that, i.e. 32x32.
+  #[inline]
+  pub fn set_window_icon(&self, window_icon: Option<Icon>) {
+    self.window.set_window_icon(window_icon)
+  }
+
+  /// Sets location of IME candidate box in client area coordinates relative to the top left.
+  ///
+  /// Same as [`Window::set_ime_cursor_area`] with an empty area at `position`.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_ime_position<P: Into<Position>>(&self, position: P) {
+    self.set_ime_cursor_area(Rect::new(position, PhysicalSize::new(0, 0)))
+  }
+
+  /// Sets the area of the text cursor, or of the text being composed, in client area
+  /// coordinates relative to the top left. The IME shows its candidates next to it without
+  /// covering it.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_ime_cursor_area(&self, area: Rect) {
+    self.window.set_ime_cursor_area(area)
+  }
+
+  /// Sets whether the composition of the input method editor is reported with
+  /// [`WindowEvent::Ime`], delivering [`Ime::Enabled`] or [`Ime::Disabled`] when it changes.
+  /// Disabled by default.
+  ///
+  /// While enabled, the text being composed is delivered with [`Ime::Preedit`] for the
+  /// application to draw it, rather than shown by the IME, and the composed text is delivered
+  /// with [`Ime::Commit`] rather than [`WindowEvent::ReceivedImeText`]. Either way, text typed
+  /// without composing is still delivered with [`WindowEvent::ReceivedImeText`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The IME keeps drawing the text being composed until the window is activated
+  ///   again.
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`WindowEvent::Ime`]: crate::event::WindowEvent::Ime
+  /// [`WindowEvent::ReceivedImeText`]: crate::event::WindowEvent::ReceivedImeText
+  /// [`Ime::Enabled`]: crate::event::Ime::Enabled
+  /// [`Ime::Disabled`]: crate::event::Ime::Disabled
+  /// [`Ime::Preedit`]: crate::event::Ime::Preedit
+  /// [`Ime::Commit`]: crate::event::Ime::Commit
+  #[inline]
+  pub fn set_ime_allowed(&self, allowed: bool) {
+    self.window.set_ime_allowed(allowed)
+  }
+
+  /// Hints the input method and the soft keyboard about the kind of text the focused input takes.
+  ///
+  /// Call it again whenever the focus moves to another kind of input rendered in the same window,
+  /// e.g. from a username field to a password field.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** [`ImePurpose::Password`] and [`ImePurpose::Number`] restrict input to roman input
+  ///   sources, and [`ImePurpose::Password`] enables secure event input while the window is key.
+  ///   [`ImePurpose::Terminal`] is the same as [`ImePurpose::Normal`].
+  /// - **Windows:** Sets the input scope, returns an [`ExternalError::Os`] if it can't be set.
+  ///   [`ImePurpose::Terminal`] is the same as [`ImePurpose::Normal`].
+  /// - **iOS / Android:** Unsupported, returns an [`ExternalError::NotSupported`].
+  #[inline]
+  pub fn set_ime_purpose(&self, purpose: ImePurpose) -> Result<(), ExternalError> {
+    self.window.set_ime_purpose(purpose)
+  }
+
+  /// Shows or hides the soft keyboard.
+  ///
+  /// [`WindowEvent::ImeVisibilityChanged`](crate::event::WindowEvent::ImeVisibilityChanged) is
+  /// sent once the keyboard is actually shown or hidden.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Android:** Returns an [`ExternalError::Os`] if the Java VM can't be reached.
+  /// - **Windows / Linux / macOS / iOS:** Always returns an [`ExternalError::NotSupported`].
+  #[inline]
+  pub fn set_ime_visible(&self, visible: bool) -> Result<(), ExternalError> {
+    self.window.set_ime_visible(visible)
+  }
+
+  /// Sets the taskbar progress state.
+  ///
+  /// Setting the state to [`ProgressState::None`] removes the progress bar entirely. Fields set to
+  /// `None` keep their previous value, and the progress bar can be updated at a high frequency.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux / macOS**: Progress bar is app-wide and not specific to this window. Only supported desktop environments with `libunity` (e.g. GNOME).
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_progress_bar(&self, _progress: ProgressBarState) {
+    #[cfg(any(
+      windows,
+      target_os = "linux",
+      target_os = "dragonfly",
+      target_os = "freebsd",
+      target_os = "netbsd",
+      target_os = "openbsd",
+      target_os = "macos",
+    ))]
+    self.window.set_progress_bar(_progress)
+  }
+
+  /// Sets the menu bar of the window, or removes it with `None`.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Sets the menu bar of the application, shared by all its windows. `None` leaves
+  ///   it as it is.
+  /// - **Windows:** The window owns its menu bar, the one it replaces is destroyed with its
+  ///   submenus and items, which do nothing from then on.
+  /// - **Linux:** The menu bar is packed at the top of the default vertical `gtk::Box`, nothing
+  ///   happens if it was disabled with `WindowBuilderExtUnix::with_default_vbox`.
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_menu(&self, menu: Option<&Menu>) {
+    self.window.set_menu(menu.map(|menu| &menu.menu))
+  }
+
+  /// Shows `menu` as a context menu at `position`, relative to the top left of the client area.
+  ///
+  /// Choosing an item delivers its [`Event::MenuEvent`] as usual.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows / macOS:** Returns once the menu is closed.
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`Event::MenuEvent`]: crate::event::Event::MenuEvent
+  #[inline]
+  pub fn show_context_menu<P: Into<Position>>(&self, menu: &Submenu, position: P) {
+    self
+      .window
+      .show_context_menu(&menu.submenu, position.into())
+  }
+
+  /// Requests user attention to the window, this has no effect if the application
+  /// is already focused. How requesting for user attention manifests is platform dependent,
+  /// see `UserAttentionType` for details.
+  ///
+  /// Providing `None` will cancel a pending request for user attention. A pending request is
+  /// also cancelled when the window gains focus.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  /// - **macOS:** The request is made for the whole application, not for this window.
+  /// - **Linux:** Urgency levels have the same effect.
+  #[inline]
+  pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+    self.window.request_user_attention(request_type)
+  }
+
+  /// Resets the dead key state of the keyboard.
+  ///
+  /// This is useful when a dead key is bound to trigger an action. Then this function can be
+  /// called to reset the dead key state so that follow-up text input won't be affected by the
+  /// dead key. It is safe to call from the `KeyboardInput` handler of the dead key itself.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn reset_dead_keys(&self) {
+    self.window.reset_dead_keys()
+  }
+
+  /// Returns whether a request made with [`Window::request_user_attention`] is still pending.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported, always returns `false`.
+  #[inline]
+  pub fn is_attention_requested(&self) -> bool {
+    self.window.is_attention_requested()
+  }
+
+  /// Returns the current window theme.
+  ///
+  /// This is the effective theme, i.e. the theme set with [`Window::set_theme`] if any.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn theme(&self) -> Theme {
+    self.window.theme()
+  }
+
+  /// Forces a theme for the window, or makes it follow the system theme again if `None` was provided.
+  ///
+  /// [`WindowEvent::ThemeChanged`] is not emitted for system theme changes while a theme is forced.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows**: Falls back to the theme set with [`EventLoopBuilderExtWindows::with_theme`]
+  ///   when `None` is provided.
+  /// - **Linux**: The theme is app-wide, since GTK settings are shared by every window.
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`WindowEvent::ThemeChanged`]: crate::event::WindowEvent::ThemeChanged
+  /// [`EventLoopBuilderExtWindows::with_theme`]: crate::platform::windows::EventLoopBuilderExtWindows::with_theme
+  #[allow(rustdoc::broken_intra_doc_links)]
+  #[inline]
+  pub fn set_theme(&self, theme: Option<Theme>) {
+    self.window.set_theme(theme)
+  }
+
+  /// Enables or disables mouse and keyboard input to the window.
+  ///
+  /// This is useful for modal-style dialogs: disable the parent while the dialog is shown.
+  /// A disabled window still receives [`WindowEvent::Resized`], [`WindowEvent::Moved`] and
+  /// [`Event::RedrawRequested`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** Make the dialog transient for the disabled window so it stays on top of it.
+  /// - **macOS:** A disabled window also refuses to become the key or main window.
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`WindowEvent::Resized`]: crate::event::WindowEvent::Resized
+  /// [`WindowEvent::Moved`]: crate::event::WindowEvent::Moved
+  /// [`Event::RedrawRequested`]: crate::event::Event::RedrawRequested
+  #[inline]
+  pub fn set_enable(&self, enabled: bool) {
+    self.window.set_enable(enabled)
+  }
+
+  /// Prevents the window contents from being captured by other apps.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android / Linux:** Unsupported.
+  pub fn set_content_protection(&self, #[allow(unused)] enabled: bool) {
     #[cfg(any(target_os = "macos", target_os = "windows"))]
     self.window.set_content_protection(enabled);
   }
@@ -1118,17 +2012,58 @@ impl Window {
   ///
   /// ## Platform-specific
   ///
-  /// - **iOS / Android / Windows:** Unsupported.
+  /// - **Linux:** Only works on X11, where it asks the window manager to make the window sticky.
+  ///   This is a no-op on Wayland.
+  /// - **iOS / Android / Windows:** Unsupported. Windows has no public API to pin a window to all
+  ///   virtual desktops.
   pub fn set_visible_on_all_workspaces(&self, #[allow(unused)] visible: bool) {
     #[cfg(any(target_os = "macos", target_os = "linux"))]
     self.window.set_visible_on_all_workspaces(visible)
   }
+
+  /// Returns whether the window is visible on all workspaces.
+  ///
+  /// This reports the state the window manager actually applied, which may differ from what was
+  /// requested through [`Window::set_visible_on_all_workspaces`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Linux:** The state is updated asynchronously once the window manager acknowledges the
+  ///   request. Always `false` on Wayland.
+  /// - **iOS / Android / Windows:** Unsupported, always returns `false`.
+  #[inline]
+  pub fn is_visible_on_all_workspaces(&self) -> bool {
+    self.window.is_visible_on_all_workspaces()
+  }
+
+  /// Sets whether the window is left out of the taskbar.
+  ///
+  /// Returns [`ExternalError::NotSupported`] where this can't be honored, so that the application
+  /// can offer another way back to the window.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Removes the button from the taskbar through `ITaskbarList`. It is added back
+  ///   if Explorer restarts while the window is shown.
+  /// - **Linux:** Sets the skip taskbar and skip pager hints. Unsupported on Wayland, which has no
+  ///   such hints.
+  /// - **macOS:** The dock only shows applications, not windows, so this is approximated by
+  ///   leaving the window out of the Window menu and of the keyboard window cycle. Use an
+  ///   accessory activation policy to hide the whole application from the dock.
+  /// - **iOS / Android:** Unsupported.
+  #[inline]
+  pub fn set_skip_taskbar(&self, skip: bool) -> Result<(), ExternalError> {
+    self.window.set_skip_taskbar(skip)
+  }
 }
 
 /// Cursor functions.
 impl Window {
   /// Modifies the cursor icon of the window.
   ///
+  /// The icon is kept while the cursor is hidden, and shown again with it. Setting the icon the
+  /// window already has does nothing.
+  ///
   /// ## Platform-specific
   ///
   /// - **iOS / Android:** Unsupported.
@@ -1137,39 +2072,86 @@ impl Window {
     self.window.set_cursor_icon(cursor);
   }
 
+  /// Returns the cursor shown over the window, as last set by [`Window::set_cursor_icon`],
+  /// [`Window::set_custom_cursor`] and [`Window::set_cursor_visible`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Always returns [`CursorKind::Icon`] with the default icon.
+  #[inline]
+  pub fn cursor_icon(&self) -> CursorKind {
+    self.window.cursor_icon()
+  }
+
+  /// Shows `image` as the cursor over the window, with the pixel at `hotspot` under the pointer.
+  ///
+  /// Like an icon, the image is kept while the cursor is hidden, and [`Window::set_cursor_icon`]
+  /// shows a system cursor again. It's shown at its size in physical pixels, and
+  /// [`Window::cursor_icon`] returns [`CursorKind::Custom`] while it's set.
+  ///
+  /// Returns an [`ExternalError::InvalidInput`] if the image is empty, if its data is shorter
+  /// than its size, or if `hotspot` is outside of it.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  #[inline]
+  pub fn set_custom_cursor(
+    &self,
+    image: &RgbaImage,
+    hotspot: PhysicalPosition<u32>,
+  ) -> Result<(), ExternalError> {
+    let icon = image.to_rgba_icon()?;
+    if hotspot.x >= image.width || hotspot.y >= image.height {
+      return Err(ExternalError::InvalidInput(
+        "the hotspot is outside of the image",
+      ));
+    }
+    self.window.set_custom_cursor(icon, hotspot)
+  }
+
   /// Changes the position of the cursor in window coordinates.
   ///
   /// ## Platform-specific
   ///
+  /// - **Linux (Wayland):** Always returns an [`ExternalError::NotSupported`].
   /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
   #[inline]
   pub fn set_cursor_position<P: Into<Position>>(&self, position: P) -> Result<(), ExternalError> {
     self.window.set_cursor_position(position.into())
   }
 
-  /// Grabs the cursor, preventing it from leaving the window.
+  /// Grabs the cursor, confining it to the window or locking it in place, or releases it with
+  /// [`CursorGrabMode::None`].
   ///
   /// There's no guarantee that the cursor will be hidden. You should
   /// hide it by yourself if you want so.
   ///
+  /// The grab is released while the window doesn't have focus, and applied again when it
+  /// regains it.
+  ///
   /// ## Platform-specific
   ///
-  /// - **macOS:** This locks the cursor in a fixed location, which looks visually awkward.
+  /// - **macOS:** [`CursorGrabMode::Confined`] returns an [`ExternalError::NotSupported`].
+  /// - **Linux:** Returns an [`ExternalError::NotSupported`] on Wayland. The grab is applied by
+  ///   the event loop, a grab refused by the X server is only logged.
   /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
   #[inline]
-  pub fn set_cursor_grab(&self, grab: bool) -> Result<(), ExternalError> {
-    self.window.set_cursor_grab(grab)
+  pub fn set_cursor_grab(&self, mode: CursorGrabMode) -> Result<(), ExternalError> {
+    self.window.set_cursor_grab(mode)
   }
 
   /// Modifies the cursor's visibility.
   ///
   /// If `false`, this will hide the cursor. If `true`, this will show the cursor.
   ///
+  /// The cursor is only hidden while it's over this window, and shows again when it leaves the
+  /// window or the window loses focus. Calling this repeatedly with the same value has no effect,
+  /// and hiding it in one window doesn't affect the others.
+  ///
   /// ## Platform-specific
   ///
-  /// - **Windows:** The cursor is only hidden within the confines of the window.
-  /// - **macOS:** The cursor is hidden as long as the window has input focus, even if the cursor is
-  ///   outside of the window.
+  /// - **Windows:** While the cursor is grabbed, it is hidden as long as the window has focus.
   /// - **iOS / Android:** Unsupported.
   #[inline]
   pub fn set_cursor_visible(&self, visible: bool) {
@@ -1203,6 +2185,25 @@ impl Window {
     self.window.drag_resize_window(direction)
   }
 
+  /// Starts an OS drag of `data` out of the window, with `image` under the cursor.
+  ///
+  /// Call this from the handler of a [`WindowEvent::MouseInput`] press while the button is still
+  /// held. The drop target can copy or move the data, and the outcome is reported with
+  /// [`WindowEvent::DragFinished`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Returns once the drop is done, as the drag runs its own modal loop.
+  /// - **macOS:** Returns an [`ExternalError::Os`] if the current event isn't a mouse event.
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  ///
+  /// [`WindowEvent::MouseInput`]: crate::event::WindowEvent::MouseInput
+  /// [`WindowEvent::DragFinished`]: crate::event::WindowEvent::DragFinished
+  #[inline]
+  pub fn start_drag(&self, data: DragData, image: DragImage) -> Result<(), ExternalError> {
+    self.window.start_drag(data, image)
+  }
+
   /// Modifies whether the window catches cursor events.
   ///
   /// If `true`, the events are passed through the window such that any other window behind it receives them.
@@ -1216,15 +2217,265 @@ impl Window {
     self.window.set_ignore_cursor_events(ignore)
   }
 
-  /// Returns the current cursor position
+  /// Restricts the area of the window that catches cursor events to the given rectangles.
+  ///
+  /// Cursor events outside of every rectangle pass through the window to whatever is behind it.
+  /// Rectangles are relative to the top-left corner of the client area. Logical rectangles follow
+  /// scale factor changes. Passing `None` restores the default where the whole window catches
+  /// cursor events. The region is cheap to update, so it can be refreshed every frame.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Events outside the region are only forwarded to windows owned by the same thread.
+  /// - **macOS:** Only affects the content view; events outside the region are not delivered to
+  ///   windows of other applications.
+  /// - **Linux:** Physical rectangles are converted using the scale factor at the time of the call.
+  ///   The region shares the window input shape with [`Window::set_ignore_cursor_events`], so the
+  ///   most recent call wins.
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`]
+  #[inline]
+  pub fn set_cursor_hittest_region(&self, region: Option<&[Rect]>) -> Result<(), ExternalError> {
+    self.window.set_cursor_hittest_region(region)
+  }
+
+  /// Lets `callback` tell which part of the window chrome, drawn by the application, is under the
+  /// cursor, so the system handles it like its own title bar, buttons and borders.
+  ///
+  /// This replaces calling [`Window::drag_window`] from cursor events: a
+  /// [`HitTestResult::Caption`] moves the window when dragged and maximizes it when double
+  /// clicked, and a right click on it opens the window menu. Clicks released on
+  /// [`HitTestResult::Minimize`], [`HitTestResult::Maximize`] and [`HitTestResult::Close`]
+  /// minimize the window, toggle its maximized state and request it to close. Cursor events are
+  /// only delivered for [`HitTestResult::Client`].
+  ///
+  /// `callback` gets the cursor position relative to the top-left corner of the client area. It
+  /// runs on the event loop thread whenever the system asks for the hit-test, possibly on every
+  /// cursor move, so it must be cheap. It's called outside of the event handler, and it can call
+  /// other methods of the window. The region of [`Window::set_cursor_hittest_region`] is
+  /// applied first.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** The results go through `WM_NCHITTEST`, so the system drags the window and
+  ///   [`HitTestResult::Maximize`] shows the snap layouts of Windows 11.
+  /// - **macOS:** A double click on [`HitTestResult::Caption`] zooms, minimizes or does nothing,
+  ///   as chosen in the Desktop & Dock settings. A window dragged by a
+  ///   [`HitTestResult::ResizeEdge`] is resized by tao rather than the system, it still gets
+  ///   [`WindowEvent::DragResizeStarted`](crate::event::WindowEvent::DragResizeStarted) and
+  ///   `DragResizeEnded`. There is no window menu.
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  #[inline]
+  pub fn set_hit_test_callback<F>(&self, callback: F) -> Result<(), ExternalError>
+  where
+    F: Fn(PhysicalPosition<f64>) -> HitTestResult + Send + Sync + 'static,
+  {
+    self.window.set_hit_test_callback(Some(Arc::new(callback)))
+  }
+
+  /// Removes the callback of [`Window::set_hit_test_callback`], the whole window is the client
+  /// area again.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  #[inline]
+  pub fn clear_hit_test_callback(&self) -> Result<(), ExternalError> {
+    self.window.set_hit_test_callback(None)
+  }
+
+  /// Returns a sender delivering the action requests of an accessibility adapter as
+  /// [`Event::AccessibilityAction`] events of the window, see the [`accessibility`] module.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  ///
+  /// [`Event::AccessibilityAction`]: crate::event::Event::AccessibilityAction
+  /// [`accessibility`]: crate::accessibility
+  #[inline]
+  pub fn accessibility_action_sender(&self) -> Result<AccessibilityActionSender, ExternalError> {
+    Ok(AccessibilityActionSender {
+      window_id: self.id(),
+      sender: self.window.accessibility_action_sender()?,
+    })
+  }
+
+  /// Clips the window to `shape`, or makes it rectangular again with `None`.
+  ///
+  /// Outside of the shape, the window is transparent and cursor events pass through it to
+  /// whatever is behind it. The shape follows resizes and scale factor changes. This is meant for
+  /// undecorated windows drawing their own chrome, such as rounded corners on systems that don't
+  /// round windows by themselves.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Edges of the shape are not antialiased. The rounding of Windows 11 is
+  ///   disabled while a shape is set, so that it doesn't clip the shape further.
+  /// - **macOS:** The window is made non-opaque with a clear background while a shape is set.
+  /// - **Linux:** Rounded corners are approximated by rows of pixels. The shape replaces the input
+  ///   region of [`Window::set_cursor_hittest_region`] and [`Window::set_ignore_cursor_events`].
+  ///   On Wayland, the window must be created transparent for the outside of the shape not to be
+  ///   drawn.
+  /// - **iOS / Android:** Always returns an [`ExternalError::NotSupported`].
+  #[inline]
+  pub fn set_shape(&self, shape: Option<WindowShape>) -> Result<(), ExternalError> {
+    self.window.set_shape(shape)
+  }
+
+  /// Sets the opacity of the whole window, clamped to `0.0..=1.0`.
+  ///
+  /// This fades the window, its decorations included, without redrawing its content, e.g. to
+  /// dim an inactive overlay. It combines with the per-pixel alpha of a window created with
+  /// [`WindowBuilder::with_transparent`], which is multiplied by the opacity. Windows with this
+  /// window as their parent or owner keep their own opacity.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Child windows created with
+  ///   [`WindowBuilderExtWindows::with_parent_window`](crate::platform::windows::WindowBuilderExtWindows::with_parent_window)
+  ///   are drawn inside their parent, and fade along with it.
+  /// - **Linux:** Needs a compositing window manager, the opacity is ignored without one.
+  /// - **Android:** Unsupported.
+  #[inline]
+  pub fn set_opacity(&self, opacity: f32) {
+    self.window.set_opacity(opacity.clamp(0.0, 1.0))
+  }
+
+  /// Returns the opacity of the whole window, see [`Window::set_opacity`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Android:** Always returns `1.0`.
+  #[inline]
+  pub fn opacity(&self) -> f32 {
+    self.window.opacity()
+  }
+
+  /// Sets the color filling the window until the application draws its content, or picks one
+  /// matching the theme of the window with `None`, so windows with a dark theme don't flash white
+  /// before their first frame.
+  ///
+  /// The color only fills what the application hasn't drawn yet, the whole window before its
+  /// first frame and the area uncovered by a resize. Content already rendered stays until the
+  /// application redraws it, so this can follow a [`WindowEvent::ThemeChanged`]. Windows
+  /// created with [`WindowBuilder::with_transparent`] keep their transparent background.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Filled on `WM_ERASEBKGND`, which
+  ///   [`WindowBuilderExtWindows::with_no_background_erase`](crate::platform::windows::WindowBuilderExtWindows::with_no_background_erase)
+  ///   disables. The alpha channel is ignored.
+  /// - **macOS:** The background color of the `NSWindow`, `None` is the system window background
+  ///   color which follows the theme.
+  /// - **Linux:** The background of the GTK window, `None` is the one of the GTK theme. Windows
+  ///   created with
+  ///   [`WindowBuilderExtUnix::with_app_paintable`](crate::platform::unix::WindowBuilderExtUnix::with_app_paintable)
+  ///   paint their own background.
+  /// - **iOS / Android:** Unsupported.
+  ///
+  /// [`WindowEvent::ThemeChanged`]: crate::event::WindowEvent::ThemeChanged
+  #[allow(rustdoc::broken_intra_doc_links)]
+  #[inline]
+  pub fn set_background_color(&self, color: Option<Color>) {
+    self.window.set_background_color(color)
+  }
+
+  /// Sets the screen rectangle that hiding the window with [`Window::set_visible`] animates
+  /// toward, such as the tray icon, so hiding looks like minimizing into it. `None` hides the
+  /// window without animation.
+  ///
+  /// The rectangle is in screen coordinates, logical rectangles are converted with the scale
+  /// factor of the window when it's hidden. The animation is skipped if the rectangle isn't on
+  /// any monitor by then. The window is back at its frame when it's shown again, and the frame
+  /// changes of the animation aren't reported as [`WindowEvent::Moved`] or
+  /// [`WindowEvent::Resized`].
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Shrinks the window into the rectangle over about 200 ms, blocking the thread
+  ///   of the window. Also available as
+  ///   [`WindowExtWindows::set_iconic_preview_rect`](crate::platform::windows::WindowExtWindows::set_iconic_preview_rect).
+  /// - **macOS:** Zooms the window into the rectangle, blocking the main thread. Also available as
+  ///   [`WindowExtMacOS::set_miniaturize_proxy_rect`](crate::platform::macos::WindowExtMacOS::set_miniaturize_proxy_rect).
+  /// - **Linux / iOS / Android:** Unsupported, returns [`ExternalError::NotSupported`].
+  ///
+  /// [`WindowEvent::Moved`]: crate::event::WindowEvent::Moved
+  /// [`WindowEvent::Resized`]: crate::event::WindowEvent::Resized
+  #[inline]
+  pub fn set_minimize_target(&self, target: Option<Rect>) -> Result<(), ExternalError> {
+    self.window.set_minimize_target(target)
+  }
+
+  /// Returns the current cursor position relative to the top-left corner of the client area, in
+  /// the same coordinates as [`WindowEvent::CursorMoved`].
+  ///
+  /// The cursor doesn't need to be over the window. Use
+  /// [`EventLoopWindowTarget::cursor_position`] for desktop coordinates.
   ///
   /// ## Platform-specific
   ///
-  /// - **iOS / Android / Linux(Wayland)**: Unsupported, returns `0,0`.
+  /// - **Linux:** The position is relative to the GDK window, which includes client-side
+  ///   decorations like `CursorMoved`.
+  /// - **Linux(Wayland):** Returns an error when the cursor isn't over this window.
+  /// - **iOS / Android**: Unsupported, returns `0,0`.
+  ///
+  /// [`WindowEvent::CursorMoved`]: crate::event::WindowEvent::CursorMoved
+  /// [`EventLoopWindowTarget::cursor_position`]: crate::event_loop::EventLoopWindowTarget::cursor_position
   #[inline]
   pub fn cursor_position(&self) -> Result<PhysicalPosition<f64>, ExternalError> {
     self.window.cursor_position()
   }
+
+  /// Captures the content of the client area, in physical pixels.
+  ///
+  /// Works for windows covered by other windows where the platform allows it, but not for
+  /// minimized windows. Use [`Window::capture_outer`] to include the window frame.
+  ///
+  /// This blocks until the pixels are read back, use
+  /// [`EventLoopWindowTarget::request_window_capture`] to get them through the event loop
+  /// instead.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Uses `PrintWindow`, content drawn without going through the window
+  ///   compositor may be missing.
+  /// - **macOS:** Uses `CGWindowListCreateImage`, deprecated since macOS 14, as ScreenCaptureKit
+  ///   isn't used. Capturing the windows of the application itself doesn't require the screen
+  ///   recording permission.
+  /// - **Linux:** Only supported on X11, returns [`ExternalError::NotSupported`] on Wayland.
+  /// - **iOS / Android:** Unsupported, returns [`ExternalError::NotSupported`].
+  /// - **headless:** The image is filled with the background color of the window.
+  ///
+  /// [`EventLoopWindowTarget::request_window_capture`]: crate::event_loop::EventLoopWindowTarget::request_window_capture
+  #[inline]
+  pub fn capture(&self) -> Result<RgbaImage, ExternalError> {
+    self.window.capture(false)
+  }
+
+  /// Same as [`Window::capture`], but includes the window frame.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **Windows:** Excludes the invisible resize borders around the frame.
+  /// - **Linux:** The frame drawn by the window manager is read from the screen, so windows
+  ///   covering it show up in the capture.
+  #[inline]
+  pub fn capture_outer(&self) -> Result<RgbaImage, ExternalError> {
+    self.window.capture(true)
+  }
+
+  /// Same as [`Window::capture`], but only captures `rect`, relative to the top-left corner of the
+  /// client area.
+  ///
+  /// The part of `rect` outside of the client area is left out of the image, and
+  /// [`ExternalError::InvalidInput`] is returned when nothing is left.
+  #[inline]
+  pub fn capture_rect(&self, rect: Rect) -> Result<RgbaImage, ExternalError> {
+    self
+      .window
+      .capture(false)?
+      .crop(rect, self.window.scale_factor())
+  }
 }
 
 /// Monitor info functions.
@@ -1254,17 +2505,21 @@ impl Window {
   /// Returns the list of all the monitors available on the system.
   ///
   /// This is the same as `EventLoopWindowTarget::available_monitors`, and is provided for convenience.
+  /// Monitors are returned in the same stable order, primary first.
   ///
   /// ## Platform-specific
   ///
   /// **iOS:** Can only be called on the main thread.
   #[inline]
   pub fn available_monitors(&self) -> impl Iterator<Item = MonitorHandle> {
-    self
+    let mut monitors: Vec<_> = self
       .window
       .available_monitors()
       .into_iter()
       .map(|inner| MonitorHandle { inner })
+      .collect();
+    crate::monitor::sort_monitors(&mut monitors, self.primary_monitor().as_ref());
+    monitors.into_iter()
   }
 
   /// Returns the primary monitor of the system.
@@ -1322,6 +2577,17 @@ impl rwh_06::HasDisplayHandle for Window {
 }
 
 /// Describes the appearance of the mouse cursor.
+///
+/// When a platform has no exact cursor for a variant, the closest available
+/// shape is used rather than the default arrow.
+///
+/// ## Platform-specific
+///
+/// - **Windows:** `Grab` uses the hand, `Grabbing` the move cursor, `Cell`, `ZoomIn` and `ZoomOut`
+///   the crosshair, `Alias` the up arrow, and `Copy` and `ContextMenu` the arrow.
+/// - **macOS:** Cursors loaded from private or system resources fall back to a public `NSCursor`
+///   when unavailable.
+/// - **Linux:** Tries the CSS cursor name, then legacy X11 cursor names, then the left pointer.
 #[non_exhaustive]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -1387,6 +2653,195 @@ impl Default for CursorIcon {
   }
 }
 
+/// How [`Window::set_cursor_grab`] holds the cursor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CursorGrabMode {
+  /// The cursor moves freely.
+  None,
+  /// The cursor moves but can't leave the window.
+  ///
+  /// ## Platform-specific
+  ///
+  /// - **macOS:** Unsupported, there's no API to confine the cursor.
+  /// - **Linux:** The cursor is warped back into the window when it leaves it, on X11 only.
+  Confined,
+  /// The cursor stays where it is, the motions of the mouse are still reported by
+  /// [`DeviceEvent::MouseMotion`].
+  ///
+  /// [`DeviceEvent::MouseMotion`]: crate::event::DeviceEvent::MouseMotion
+  Locked,
+}
+
+impl Default for CursorGrabMode {
+  fn default() -> Self {
+    CursorGrabMode::None
+  }
+}
+
+/// The cursor shown over a window, see [`Window::cursor_icon`].
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CursorKind {
+  /// One of the system cursors.
+  Icon(CursorIcon),
+  /// The cursor is hidden, see [`Window::set_cursor_visible`].
+  Hidden,
+  /// An image, see [`Window::set_custom_cursor`].
+  Custom,
+}
+
+impl Default for CursorKind {
+  fn default() -> Self {
+    CursorKind::Icon(CursorIcon::Default)
+  }
+}
+
+/// The placement of a window, see [`Window::state`] and [`WindowBuilder::with_restored_state`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WindowState {
+  /// The outer position of the window, see [`Window::outer_position`].
+  pub position: PhysicalPosition<i32>,
+  /// The inner size of the window, see [`Window::inner_size`].
+  pub size: PhysicalSize<u32>,
+  /// The outer size of the window, see [`Window::outer_size`], for its frame to stay on the
+  /// monitor.
+  pub outer_size: PhysicalSize<u32>,
+  /// The [`MonitorHandle::id`] of the monitor the window is on.
+  pub monitor: Option<u64>,
+  /// Whether the window is maximized.
+  pub maximized: bool,
+  /// Whether the window is fullscreen, in any mode.
+  pub fullscreen: bool,
+}
+
+impl WindowState {
+  /// Applies the state to `attributes`, in the monitor it's restored on.
+  fn restore(
+    &self,
+    attributes: &mut WindowAttributes,
+    monitors: &[MonitorHandle],
+    primary: Option<MonitorHandle>,
+  ) {
+    let areas: Vec<_> = monitors
+      .iter()
+      .map(|monitor| (monitor.id(), monitor.position(), monitor.size()))
+      .collect();
+    let monitor = self
+      .monitor_index(&areas)
+      .map(|index| monitors[index].clone())
+      .or(primary)
+      .or_else(|| monitors.first().cloned());
+
+    attributes.maximized = self.maximized;
+    attributes.fullscreen = None;
+    let monitor = match monitor {
+      Some(monitor) => monitor,
+      None => {
+        attributes.position = Some(self.position.into());
+        attributes.inner_size = Some(self.size.into());
+        return;
+      }
+    };
+    let monitor_position = monitor.position();
+    let monitor_size = monitor.size();
+    if self.fullscreen {
+      attributes.fullscreen = Some(Fullscreen::Borderless(Some(monitor)));
+    }
+    if self.maximized || self.fullscreen {
+      // Their size is the one of the monitor, the window only needs to be on it.
+      attributes.position = Some(monitor_position.into());
+      return;
+    }
+
+    let (position, size) = self.clamp(monitor_position, monitor_size);
+    attributes.position = Some(position.into());
+    attributes.inner_size = Some(size.into());
+  }
+
+  /// The index of the monitor to restore the window on among `monitors`, given by their id,
+  /// position and size, or `None` to fall back to the primary one.
+  ///
+  /// Its own monitor wins, and among the monitors sharing its id, like identical displays on
+  /// Linux, the one showing most of the window. Without any, the one showing most of the window.
+  fn monitor_index(
+    &self,
+    monitors: &[(u64, PhysicalPosition<i32>, PhysicalSize<u32>)],
+  ) -> Option<usize> {
+    monitors
+      .iter()
+      .enumerate()
+      .map(|(index, (id, position, size))| {
+        (
+          Some(*id) == self.monitor,
+          self.overlap(*position, *size),
+          index,
+        )
+      })
+      .filter(|(own, overlap, _)| *own || *overlap > 0)
+      // The first one wins a tie.
+      .max_by_key(|(own, overlap, index)| (*own, *overlap, Reverse(*index)))
+      .map(|(_, _, index)| index)
+  }
+
+  /// The outer position and inner size of the window restored on the monitor at
+  /// `monitor_position`: it's shrunk until its frame fits the monitor, then moved onto it.
+  fn clamp(
+    &self,
+    monitor_position: PhysicalPosition<i32>,
+    monitor_size: PhysicalSize<u32>,
+  ) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    // The decorations around the inner size, across both sides.
+    let frame_width = self.outer_size.width.saturating_sub(self.size.width);
+    let frame_height = self.outer_size.height.saturating_sub(self.size.height);
+    let size = PhysicalSize::new(
+      self
+        .size
+        .width
+        .min(monitor_size.width.saturating_sub(frame_width)),
+      self
+        .size
+        .height
+        .min(monitor_size.height.saturating_sub(frame_height)),
+    );
+    let position = PhysicalPosition::new(
+      self
+        .position
+        .x
+        .min(
+          monitor_position.x + monitor_size.width.saturating_sub(size.width + frame_width) as i32,
+        )
+        .max(monitor_position.x),
+      self
+        .position
+        .y
+        .min(
+          monitor_position.y
+            + monitor_size
+              .height
+              .saturating_sub(size.height + frame_height) as i32,
+        )
+        .max(monitor_position.y),
+    );
+    (position, size)
+  }
+
+  /// The area of the monitor at `position` covered by the window, in square physical pixels.
+  fn overlap(&self, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) -> u64 {
+    let left = i64::from(self.position.x).max(position.x.into());
+    let top = i64::from(self.position.y).max(position.y.into());
+    let right = (i64::from(self.position.x) + i64::from(self.outer_size.width))
+      .min(i64::from(position.x) + i64::from(size.width));
+    let bottom = (i64::from(self.position.y) + i64::from(self.outer_size.height))
+      .min(i64::from(position.y) + i64::from(size.height));
+    if right > left && bottom > top {
+      ((right - left) * (bottom - top)) as u64
+    } else {
+      0
+    }
+  }
+}
+
 /// Fullscreen modes.
 #[non_exhaustive]
 #[allow(clippy::large_enum_variant)]
@@ -1400,6 +2855,7 @@ pub enum Fullscreen {
 
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Theme {
   Light,
   Dark,
@@ -1411,6 +2867,47 @@ impl Default for Theme {
   }
 }
 
+/// The outcome of [`Window::set_focus_with_token`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusResult {
+  /// The window was brought to the foreground and focused.
+  Focused,
+  /// The window could not take the foreground, the user was asked to switch to it instead.
+  AttentionRequested,
+  /// The window could not be focused.
+  Failed,
+}
+
+/// A token proving that a focus request originates from a user action, such as launching the
+/// application.
+///
+/// On Wayland this is an xdg-activation token, on X11 a startup notification id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActivationToken(pub(crate) String);
+
+impl ActivationToken {
+  /// Creates a token from its raw string representation, e.g. received from another process.
+  pub fn from_raw(token: String) -> Self {
+    Self(token)
+  }
+
+  /// Returns the token handed to this process by its launcher, read from the
+  /// `XDG_ACTIVATION_TOKEN` or `DESKTOP_STARTUP_ID` environment variables.
+  pub fn from_env() -> Option<Self> {
+    ["XDG_ACTIVATION_TOKEN", "DESKTOP_STARTUP_ID"]
+      .iter()
+      .filter_map(|key| std::env::var(key).ok())
+      .find(|token| !token.is_empty())
+      .map(Self)
+  }
+
+  /// Returns the raw string representation of the token.
+  pub fn into_raw(self) -> String {
+    self.0
+  }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum UserAttentionType {
@@ -1430,6 +2927,26 @@ impl Default for UserAttentionType {
   }
 }
 
+/// The kind of text the focused input of a window takes, see [`Window::set_ime_purpose`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImePurpose {
+  /// Regular text.
+  Normal,
+  /// A password. Input methods don't predict or learn from it, and the text isn't shown.
+  Password,
+  /// Digits, with a numeric layout on the soft keyboard.
+  Number,
+  /// A terminal, where input methods don't correct or predict the text.
+  Terminal,
+}
+
+impl Default for ImePurpose {
+  fn default() -> Self {
+    ImePurpose::Normal
+  }
+}
+
 /// Window size constraints
 #[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Default)]
 pub struct WindowSizeConstraints {
@@ -1545,6 +3062,42 @@ impl WindowSizeConstraints {
     let max_size: PhysicalSize<f64> = self.max_size_physical(scale_factor);
     Size::clamp(desired_size, min_size.into(), max_size.into(), scale_factor)
   }
+
+  /// Returns an error if a minimum is larger than the matching maximum at `scale_factor`.
+  pub(crate) fn validate(&self, scale_factor: f64) -> Result<(), ExternalError> {
+    let larger = |min: Option<PixelUnit>, max: Option<PixelUnit>| match (min, max) {
+      (Some(min), Some(max)) => {
+        min.to_physical::<f64>(scale_factor).value > max.to_physical::<f64>(scale_factor).value
+      }
+      _ => false,
+    };
+    if larger(self.min_width, self.max_width) || larger(self.min_height, self.max_height) {
+      return Err(ExternalError::InvalidInput(
+        "the minimum inner size is larger than the maximum inner size",
+      ));
+    }
+    Ok(())
+  }
+}
+
+/// The level of a window in the stacking order, see [`Window::set_window_level`].
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WindowLevel {
+  /// Below the other windows.
+  AlwaysOnBottom,
+  /// Among the other windows.
+  Normal,
+  /// Above the other windows.
+  AlwaysOnTop,
+  /// Above the other windows, including the always on top ones where the system allows it.
+  ScreenSaver,
+}
+
+impl Default for WindowLevel {
+  fn default() -> Self {
+    WindowLevel::Normal
+  }
 }
 
 /// Defines the orientation that a window resize will be performed.
@@ -1560,6 +3113,245 @@ pub enum ResizeDirection {
   West,
 }
 
+/// The part of a window under the cursor, returned by the callback of
+/// [`Window::set_hit_test_callback`].
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HitTestResult {
+  /// The content of the window, which gets the cursor events.
+  Client,
+  /// The title bar, which moves the window.
+  Caption,
+  /// The minimize button.
+  Minimize,
+  /// The maximize button.
+  Maximize,
+  /// The close button.
+  Close,
+  /// The border of the window, which resizes it in the given direction.
+  ResizeEdge(ResizeDirection),
+}
+
+pub(crate) type HitTestCallback =
+  Arc<dyn Fn(PhysicalPosition<f64>) -> HitTestResult + Send + Sync + 'static>;
+
+/// The data offered by a drag started with [`Window::start_drag`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DragData {
+  /// Paths of files, dropped as the files themselves.
+  Files(Vec<PathBuf>),
+  /// UTF-8 text.
+  Text(String),
+}
+
+/// The preview shown under the cursor during a drag started with [`Window::start_drag`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DragImage {
+  pub(crate) image: RgbaIcon,
+  pub(crate) hotspot: PhysicalPosition<u32>,
+}
+
+impl DragImage {
+  /// Creates a `DragImage` from 32bpp RGBA data, where `hotspot` is the pixel of the image kept
+  /// under the cursor.
+  ///
+  /// The length of `rgba` must be divisible by 4, and `width * height` must equal
+  /// `rgba.len() / 4`. Otherwise, this will return a `BadIcon` error.
+  pub fn from_rgba(
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+    hotspot: PhysicalPosition<u32>,
+  ) -> Result<Self, BadIcon> {
+    Ok(Self::new(
+      RgbaIcon::from_rgba(rgba, width, height)?,
+      hotspot,
+    ))
+  }
+
+  /// Creates a `DragImage` from an [`RgbaIcon`], where `hotspot` is the pixel of the image kept
+  /// under the cursor. The hotspot is clamped to the image.
+  pub fn new(image: RgbaIcon, hotspot: PhysicalPosition<u32>) -> Self {
+    let hotspot = PhysicalPosition::new(
+      hotspot.x.min(image.width.saturating_sub(1)),
+      hotspot.y.min(image.height.saturating_sub(1)),
+    );
+    Self { image, hotspot }
+  }
+
+  /// The image.
+  pub fn image(&self) -> &RgbaIcon {
+    &self.image
+  }
+
+  /// The pixel of the image kept under the cursor.
+  pub fn hotspot(&self) -> PhysicalPosition<u32> {
+    self.hotspot
+  }
+}
+
+/// A format of the data dragged over a window, see [`WindowEvent::DragOver`].
+///
+/// [`WindowEvent::DragOver`]: crate::event::WindowEvent::DragOver
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DropFormat {
+  /// Files with a path, dropped as [`WindowEvent::DroppedFile`].
+  ///
+  /// [`WindowEvent::DroppedFile`]: crate::event::WindowEvent::DroppedFile
+  Files,
+  /// Text, dropped as [`DropData::Text`].
+  Text,
+  /// HTML, dropped as [`DropData::Html`].
+  Html,
+  /// Files without a path, like the attachments of an email, dropped as
+  /// [`DropData::VirtualFile`].
+  VirtualFiles,
+}
+
+/// Data other than files with a path dropped into a window, see [`WindowEvent::DroppedData`].
+///
+/// [`WindowEvent::DroppedData`]: crate::event::WindowEvent::DroppedData
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DropData {
+  /// Text.
+  Text(String),
+  /// An HTML fragment, like the selection of a web page.
+  Html(String),
+  /// A file without a path, like an attachment of an email.
+  VirtualFile {
+    /// The name of the file, relative to the dragged folder for the files of a folder.
+    name: String,
+    /// The contents of the file, read from the source of the drag when they're opened.
+    contents: VirtualFileContents,
+  },
+}
+
+/// The contents of a [`DropData::VirtualFile`], read from the source of the drag when they're
+/// opened, so that the drop doesn't wait for them and a large file isn't held in memory.
+///
+/// The source of the drag is kept until every clone of the contents is dropped. Sources that
+/// support it, like Outlook, are told that the drop is still being handled until then.
+///
+/// ## Platform-specific
+///
+/// - **Windows:** The contents are opened on a thread where COM is initialized, like the thread
+///   of the event loop. A file the source gives as a storage, like an Outlook message, is read
+///   into memory as a compound file when it's opened.
+#[derive(Clone)]
+pub struct VirtualFileContents {
+  open: Arc<dyn Fn() -> io::Result<Box<dyn Read>> + Send + Sync>,
+}
+
+impl VirtualFileContents {
+  #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+  pub(crate) fn new(open: impl Fn() -> io::Result<Box<dyn Read>> + Send + Sync + 'static) -> Self {
+    Self {
+      open: Arc::new(open),
+    }
+  }
+
+  /// Opens the contents for reading from their start. Each call opens them again.
+  ///
+  /// The reader stays on the thread that opened it.
+  pub fn open(&self) -> io::Result<Box<dyn Read>> {
+    (self.open)()
+  }
+
+  /// Reads all the contents into memory.
+  pub fn read_to_end(&self) -> io::Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    self.open()?.read_to_end(&mut contents)?;
+    Ok(contents)
+  }
+}
+
+impl fmt::Debug for VirtualFileContents {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("VirtualFileContents")
+      .finish_non_exhaustive()
+  }
+}
+
+/// Clones are equal, they read the same file.
+impl PartialEq for VirtualFileContents {
+  fn eq(&self, other: &Self) -> bool {
+    Arc::as_ptr(&self.open) as *const u8 == Arc::as_ptr(&other.open) as *const u8
+  }
+}
+
+impl Eq for VirtualFileContents {}
+
+/// What the drop target did with the data of a drag.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DragOperation {
+  /// The data was copied. The source keeps it.
+  Copy,
+  /// The data was moved. The source should remove it.
+  Move,
+}
+
+/// The outcome of a drag started with [`Window::start_drag`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DragResult {
+  /// A drop target accepted the data.
+  Dropped(DragOperation),
+  /// The drag was cancelled, or dropped where the data isn't accepted.
+  Cancelled,
+}
+
+/// The shape that clips a window, see [`Window::set_shape`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WindowShape {
+  /// The client area with its corners rounded by `radius`, in logical pixels.
+  RoundedRect { radius: f64 },
+  /// The union of the rectangles, relative to the top-left corner of the client area. Logical
+  /// rectangles follow scale factor changes.
+  Region(Vec<Rect>),
+}
+
+impl WindowShape {
+  /// The radius of [`WindowShape::RoundedRect`] in physical pixels for a client area of `size`,
+  /// at most half of its smallest side.
+  pub(crate) fn physical_radius(radius: f64, size: PhysicalSize<u32>, scale_factor: f64) -> f64 {
+    let max = size.width.min(size.height) as f64 / 2.0;
+    (radius * scale_factor).max(0.0).min(max)
+  }
+
+  /// Returns whether `point`, relative to the top-left corner of a client area of `size`, lies
+  /// within the shape, using `scale_factor` to convert between logical and physical pixels.
+  pub fn contains<P: Into<Position>>(
+    &self,
+    point: P,
+    size: PhysicalSize<u32>,
+    scale_factor: f64,
+  ) -> bool {
+    let point = point.into().to_physical::<f64>(scale_factor);
+    match self {
+      WindowShape::RoundedRect { radius } => {
+        let (width, height) = (size.width as f64, size.height as f64);
+        if point.x < 0.0 || point.y < 0.0 || point.x >= width || point.y >= height {
+          return false;
+        }
+        // Outside of the corners, the closest point of the inner rectangle is the point itself.
+        let radius = Self::physical_radius(*radius, size, scale_factor);
+        let dx = point.x - point.x.max(radius).min(width - radius);
+        let dy = point.y - point.y.max(radius).min(height - radius);
+        dx * dx + dy * dy <= radius * radius
+      }
+      WindowShape::Region(rects) => rects.iter().any(|rect| rect.contains(point, scale_factor)),
+    }
+  }
+}
+
+#[cfg_attr(tao_headless, allow(dead_code))]
 pub(crate) fn hit_test(
   (left, top, right, bottom): (i32, i32, i32, i32),
   cx: i32,
@@ -1594,3 +3386,94 @@ pub(crate) fn hit_test(
     _ => None,
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::WindowState;
+  use crate::dpi::{PhysicalPosition, PhysicalSize};
+
+  const TWIN: u64 = 7;
+
+  fn state(position: (i32, i32), monitor: Option<u64>) -> WindowState {
+    WindowState {
+      position: PhysicalPosition::new(position.0, position.1),
+      size: PhysicalSize::new(800, 600),
+      outer_size: PhysicalSize::new(820, 640),
+      monitor,
+      maximized: false,
+      fullscreen: false,
+    }
+  }
+
+  fn monitors() -> Vec<(u64, PhysicalPosition<i32>, PhysicalSize<u32>)> {
+    vec![
+      (
+        1,
+        PhysicalPosition::new(-1280, 0),
+        PhysicalSize::new(1280, 1024),
+      ),
+      (
+        TWIN,
+        PhysicalPosition::new(0, 0),
+        PhysicalSize::new(1920, 1080),
+      ),
+      (
+        TWIN,
+        PhysicalPosition::new(1920, 0),
+        PhysicalSize::new(1920, 1080),
+      ),
+    ]
+  }
+
+  #[test]
+  fn identical_monitors_pick_the_one_showing_most_of_the_window() {
+    let monitors = monitors();
+    assert_eq!(
+      state((2000, 100), Some(TWIN)).monitor_index(&monitors),
+      Some(2)
+    );
+    assert_eq!(
+      state((100, 100), Some(TWIN)).monitor_index(&monitors),
+      Some(1)
+    );
+    // Off all of them, the first one with the id.
+    assert_eq!(
+      state((9000, 100), Some(TWIN)).monitor_index(&monitors),
+      Some(1)
+    );
+  }
+
+  #[test]
+  fn own_monitor_wins_over_the_overlap() {
+    let monitors = monitors();
+    assert_eq!(
+      state((2000, 100), Some(1)).monitor_index(&monitors),
+      Some(0)
+    );
+    assert_eq!(state((-600, 100), None).monitor_index(&monitors), Some(0));
+    assert_eq!(state((9000, 100), Some(2)).monitor_index(&monitors), None);
+  }
+
+  #[test]
+  fn frame_stays_on_the_monitor() {
+    let monitor = (
+      PhysicalPosition::new(1920, 0),
+      PhysicalSize::new(1920, 1080),
+    );
+
+    // The outer size is 820x640, so the frame reaches the bottom right corner.
+    let (position, size) = state((3500, 900), None).clamp(monitor.0, monitor.1);
+    assert_eq!(size, PhysicalSize::new(800, 600));
+    assert_eq!(position, PhysicalPosition::new(3020, 440));
+
+    // Shrunk for the frame, not only the inner size, to fit.
+    let tall = WindowState {
+      size: PhysicalSize::new(800, 1080),
+      outer_size: PhysicalSize::new(820, 1120),
+      ..state((-50, -50), None)
+    };
+    let (position, size) = tall.clamp(monitor.0, monitor.1);
+    assert_eq!(size, PhysicalSize::new(800, 1040));
+    assert_eq!(position, PhysicalPosition::new(1920, 0));
+  }
+}
@@ -0,0 +1,157 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! The windows of an event loop by id, behind
+//! [`EventLoopWindowTarget::window_from_id`](crate::event_loop::EventLoopWindowTarget::window_from_id).
+//!
+//! The registry keeps a second `Window` of each window, sharing its platform window, and lends it
+//! out while the event handler runs. A window dropped by the application leaves the registry at
+//! once, but its entry is only freed once the outermost event handler returned, since the handler
+//! may still hold it.
+
+use std::{
+  collections::HashMap,
+  mem,
+  sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::{
+  event::Event,
+  event_loop::{ControlFlow, EventLoopWindowTarget},
+  window::{Window, WindowId},
+};
+
+#[derive(Default)]
+struct State {
+  /// Boxed, so that the windows lent out don't move when the map grows.
+  windows: HashMap<WindowId, Box<Window>>,
+  /// The windows dropped while the event handler runs, still boxed since they may be lent out.
+  #[allow(clippy::vec_box)]
+  retired: Vec<Box<Window>>,
+  /// How many calls of the event handler are on the stack.
+  depth: usize,
+}
+
+#[derive(Default)]
+pub(crate) struct WindowRegistry {
+  state: Mutex<State>,
+}
+
+impl WindowRegistry {
+  fn state(&self) -> std::sync::MutexGuard<'_, State> {
+    self.state.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  pub(crate) fn insert(&self, window: &Window) {
+    let shared = Window {
+      window: window.window.clone(),
+      registry: None,
+    };
+    self.state().windows.insert(window.id(), Box::new(shared));
+  }
+
+  pub(crate) fn remove(&self, window_id: WindowId) {
+    let mut state = self.state();
+    let window = state.windows.remove(&window_id);
+    match window {
+      Some(window) if state.depth > 0 => state.retired.push(window),
+      // Dropped after the lock, the platform window may be released with it.
+      window => {
+        drop(state);
+        drop(window);
+      }
+    }
+  }
+
+  /// The window lent out to the running event handler.
+  fn get(&self, window_id: WindowId) -> Option<*const Window> {
+    let state = self.state();
+    if state.depth == 0 {
+      return None;
+    }
+    state
+      .windows
+      .get(&window_id)
+      .map(|window| &**window as *const Window)
+  }
+
+  fn enter(&self) {
+    self.state().depth += 1;
+  }
+
+  fn leave(&self) {
+    let retired = {
+      let mut state = self.state();
+      state.depth -= 1;
+      if state.depth > 0 {
+        return;
+      }
+      mem::take(&mut state.retired)
+    };
+    drop(retired);
+  }
+}
+
+/// The registry of an `EventLoopWindowTarget`.
+///
+/// Only the target handed to the event handler looks windows up: a clone of it may be kept past
+/// the handler, e.g. in a spawned future, and with it the windows it lent out.
+pub(crate) struct RegistryHandle {
+  pub(crate) registry: Arc<WindowRegistry>,
+  lends: bool,
+}
+
+impl Default for RegistryHandle {
+  fn default() -> Self {
+    Self {
+      registry: Default::default(),
+      lends: true,
+    }
+  }
+}
+
+impl Clone for RegistryHandle {
+  fn clone(&self) -> Self {
+    Self {
+      registry: self.registry.clone(),
+      lends: false,
+    }
+  }
+}
+
+impl RegistryHandle {
+  pub(crate) fn get(&self, window_id: WindowId) -> Option<&Window> {
+    if !self.lends {
+      return None;
+    }
+    // SAFETY: `get` only lends windows while the event handler runs, which borrows `self` for at
+    // most that call, and an entry removed meanwhile is kept until the outermost call returns.
+    self
+      .registry
+      .get(window_id)
+      .map(|window| unsafe { &*window })
+  }
+}
+
+/// Lends the windows of the registry to `handler` while it runs.
+pub(crate) fn wrap_handler<T, F>(
+  mut handler: F,
+) -> impl FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow)
+where
+  F: FnMut(Event<'_, T>, &EventLoopWindowTarget<T>, &mut ControlFlow),
+{
+  struct Lending<'a>(&'a WindowRegistry);
+
+  impl Drop for Lending<'_> {
+    fn drop(&mut self) {
+      self.0.leave();
+    }
+  }
+
+  move |event, target, control_flow| {
+    let registry = &target.window_registry.registry;
+    registry.enter();
+    let _lending = Lending(registry);
+    handler(event, target, control_flow)
+  }
+}
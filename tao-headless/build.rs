@@ -0,0 +1,7 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+fn main() {
+  // The sources are the ones of `tao`, this selects the synthetic backend in them.
+  println!("cargo:rustc-cfg=tao_headless");
+}
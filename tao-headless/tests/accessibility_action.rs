@@ -0,0 +1,66 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that an `AccessibilityActionSender` delivers the requests of another thread to the
+//! event loop, in the synthetic desktop of `tao-headless`.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+use std::{
+  thread,
+  time::{Duration, Instant},
+};
+use tao::{
+  event::{Event, StartCause},
+  event_loop::{ControlFlow, EventLoop},
+  platform::run_return::EventLoopExtRunReturn,
+  window::WindowBuilder,
+};
+
+/// Stands for the request type of an adapter, which tao doesn't know.
+#[derive(Debug, PartialEq)]
+struct Focus(u64);
+
+fn main() {
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+  let closed = WindowBuilder::new().build(&event_loop).unwrap();
+  let sender = window.accessibility_action_sender().unwrap();
+  assert_eq!(sender.window_id(), window.id());
+
+  let closed_sender = closed.accessibility_action_sender().unwrap();
+  drop(closed);
+  assert!(closed_sender.send(Focus(0)).is_err());
+
+  let mut recorded = Vec::new();
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, _, control_flow| {
+    if Instant::now() > deadline {
+      panic!("the accessibility action requests weren't delivered in time");
+    }
+    match event {
+      Event::NewEvents(StartCause::Init) => {
+        *control_flow = ControlFlow::Wait;
+        let sender = sender.clone();
+        thread::spawn(move || {
+          sender.send(Focus(1)).unwrap();
+          sender.send(Focus(2)).unwrap();
+        });
+      }
+      Event::AccessibilityAction { window_id, request } => {
+        assert_eq!(window_id, window.id());
+        assert!(request.downcast_ref::<String>().is_none());
+        recorded.push(request.downcast_ref::<Focus>().unwrap().0);
+        if recorded.len() == 2 {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      _ => (),
+    }
+  });
+
+  assert_eq!(recorded, [1, 2]);
+
+  drop(event_loop);
+  assert!(sender.send(Focus(3)).is_err());
+}
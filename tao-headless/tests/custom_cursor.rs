@@ -0,0 +1,67 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `Window::set_custom_cursor` validates its image and hotspot, and that the cursor
+//! it sets is reported until a system cursor is set again, in the synthetic desktop of
+//! `tao-headless`.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+use tao::{
+  dpi::PhysicalPosition,
+  error::ExternalError,
+  event_loop::EventLoop,
+  window::{CursorIcon, CursorKind, RgbaImage, WindowBuilder},
+};
+
+fn image(width: u32, height: u32, stride: u32) -> RgbaImage {
+  RgbaImage {
+    width,
+    height,
+    stride,
+    data: vec![u8::MAX; (stride * height) as usize],
+  }
+}
+
+fn main() {
+  let event_loop = EventLoop::new();
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+  let hotspot = PhysicalPosition::new(0, 0);
+
+  for (image, hotspot) in [
+    (image(0, 0, 0), hotspot),
+    // The stride is shorter than a row.
+    (image(16, 16, 32), hotspot),
+    (image(16, 16, 64), PhysicalPosition::new(16, 0)),
+    (image(16, 16, 64), PhysicalPosition::new(0, 16)),
+  ] {
+    assert!(matches!(
+      window.set_custom_cursor(&image, hotspot),
+      Err(ExternalError::InvalidInput(_))
+    ));
+  }
+  let mut short = image(16, 16, 64);
+  short.data.pop();
+  assert!(matches!(
+    window.set_custom_cursor(&short, hotspot),
+    Err(ExternalError::InvalidInput(_))
+  ));
+  assert_eq!(window.cursor_icon(), CursorKind::Icon(CursorIcon::Default));
+
+  // The last row doesn't need the padding of the others.
+  let mut padded = image(16, 16, 80);
+  padded.data.truncate(80 * 15 + 64);
+  window
+    .set_custom_cursor(&padded, PhysicalPosition::new(15, 15))
+    .unwrap();
+  assert_eq!(window.cursor_icon(), CursorKind::Custom);
+
+  window.set_cursor_visible(false);
+  assert_eq!(window.cursor_icon(), CursorKind::Hidden);
+  window.set_cursor_visible(true);
+  assert_eq!(window.cursor_icon(), CursorKind::Custom);
+
+  // Setting the icon the window had before replaces the image.
+  window.set_cursor_icon(CursorIcon::Default);
+  assert_eq!(window.cursor_icon(), CursorKind::Icon(CursorIcon::Default));
+}
@@ -0,0 +1,112 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `EventLoopExtInject::inject_input` delivers the keyboard to the focused window and
+//! the mouse to the window under the cursor, in the synthetic desktop of `tao-headless`.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+use std::time::{Duration, Instant};
+use tao::{
+  dpi::{PhysicalPosition, PhysicalSize},
+  event::{ElementState, Event, Ime, MouseButton, MouseScrollDelta, StartCause, WindowEvent},
+  event_loop::{ControlFlow, EventLoop},
+  keyboard::KeyCode,
+  platform::{
+    inject::{EventLoopExtInject, SyntheticInput},
+    run_return::EventLoopExtRunReturn,
+  },
+  window::{WindowBuilder, WindowId},
+};
+
+#[derive(Debug, PartialEq)]
+enum Recorded {
+  Key(WindowId, KeyCode, ElementState),
+  Text(WindowId, String),
+  CursorMoved(WindowId, PhysicalPosition<f64>),
+  MouseInput(WindowId, MouseButton, ElementState),
+  MouseWheel(WindowId, MouseScrollDelta),
+}
+
+fn main() {
+  let mut event_loop = EventLoop::new();
+  let below = WindowBuilder::new()
+    .with_position(PhysicalPosition::new(0, 0))
+    .with_inner_size(PhysicalSize::new(400, 300))
+    .build(&event_loop)
+    .unwrap();
+  // Built last, so it has the focus.
+  let focused = WindowBuilder::new()
+    .with_position(PhysicalPosition::new(500, 0))
+    .with_inner_size(PhysicalSize::new(400, 300))
+    .build(&event_loop)
+    .unwrap();
+  assert!(focused.is_focused());
+
+  let inputs = [
+    SyntheticInput::CursorMoved(PhysicalPosition::new(110.0, 20.0)),
+    SyntheticInput::MouseInput {
+      button: MouseButton::Left,
+      state: ElementState::Pressed,
+    },
+    SyntheticInput::MouseWheel(MouseScrollDelta::LineDelta(0.0, -2.0)),
+    SyntheticInput::Key {
+      code: KeyCode::KeyA,
+      state: ElementState::Pressed,
+    },
+    SyntheticInput::Text("é!".into()),
+    // Outside of the windows, the cursor moves without any event.
+    SyntheticInput::CursorMoved(PhysicalPosition::new(450.0, 20.0)),
+    SyntheticInput::CursorMoved(PhysicalPosition::new(520.0, 30.0)),
+  ];
+  let expected = [
+    Recorded::CursorMoved(below.id(), PhysicalPosition::new(110.0, 20.0)),
+    Recorded::MouseInput(below.id(), MouseButton::Left, ElementState::Pressed),
+    Recorded::MouseWheel(below.id(), MouseScrollDelta::LineDelta(0.0, -2.0)),
+    Recorded::Key(focused.id(), KeyCode::KeyA, ElementState::Pressed),
+    Recorded::Text(focused.id(), "é!".into()),
+    Recorded::CursorMoved(focused.id(), PhysicalPosition::new(20.0, 30.0)),
+  ];
+
+  let mut recorded = Vec::new();
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, target, control_flow| {
+    if Instant::now() > deadline {
+      panic!("the injected input wasn't delivered in time");
+    }
+    match event {
+      Event::NewEvents(StartCause::Init) => {
+        *control_flow = ControlFlow::Wait;
+        for input in inputs.iter().cloned() {
+          target.inject_input(input).unwrap();
+        }
+      }
+      Event::WindowEvent {
+        window_id, event, ..
+      } => {
+        recorded.push(match event {
+          WindowEvent::KeyboardInput { event, .. } => {
+            Recorded::Key(window_id, event.physical_key, event.state)
+          }
+          WindowEvent::Ime(Ime::Commit(text)) => Recorded::Text(window_id, text),
+          WindowEvent::CursorMoved { position, .. } => Recorded::CursorMoved(window_id, position),
+          WindowEvent::MouseInput { button, state, .. } => {
+            Recorded::MouseInput(window_id, button, state)
+          }
+          WindowEvent::MouseWheel { delta, .. } => Recorded::MouseWheel(window_id, delta),
+          _ => return,
+        });
+        if recorded.len() == expected.len() {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      _ => (),
+    }
+  });
+
+  assert_eq!(recorded, expected);
+  assert_eq!(
+    focused.cursor_position().unwrap(),
+    PhysicalPosition::new(20.0, 30.0)
+  );
+}
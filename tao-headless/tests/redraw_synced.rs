@@ -0,0 +1,84 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `Window::request_redraw_synced` delivers a single `RedrawRequested` for the
+//! window, and that `MonitorHandle::refresh_rate_millihertz` follows the monitors, in the
+//! synthetic desktop of `tao-headless`.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+use std::time::{Duration, Instant};
+use tao::{
+  dpi::{PhysicalPosition, PhysicalSize},
+  event::Event,
+  event_loop::{ControlFlow, EventLoopBuilder},
+  headless::VirtualMonitor,
+  platform::run_return::EventLoopExtRunReturn,
+  window::WindowBuilder,
+};
+
+fn main() {
+  let mut fast = VirtualMonitor::new(
+    "Fast",
+    PhysicalPosition::new(1920, 0),
+    PhysicalSize::new(2560, 1440),
+    1.0,
+  );
+  fast.refresh_rate = 144;
+  let mut event_loop = EventLoopBuilder::new()
+    .with_virtual_monitors(vec![
+      VirtualMonitor::new(
+        "Slow",
+        PhysicalPosition::new(0, 0),
+        PhysicalSize::new(1920, 1080),
+        1.0,
+      ),
+      fast,
+    ])
+    .build();
+
+  let rates: Vec<_> = event_loop
+    .available_monitors()
+    .map(|monitor| monitor.refresh_rate_millihertz())
+    .collect();
+  assert_eq!(rates, [Some(60_000), Some(144_000)]);
+
+  let window = WindowBuilder::new()
+    .with_position(PhysicalPosition::new(2000, 100))
+    .build(&event_loop)
+    .unwrap();
+  assert_eq!(
+    window
+      .current_monitor()
+      .and_then(|monitor| monitor.refresh_rate_millihertz()),
+    Some(144_000)
+  );
+
+  let mut redraws = 0;
+  let mut iterations = 0;
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, _, control_flow| {
+    *control_flow = ControlFlow::Poll;
+    if Instant::now() > deadline {
+      panic!("the synced redraw wasn't delivered in time");
+    }
+    match event {
+      Event::NewEvents(_) if iterations == 0 => {
+        window.request_redraw_synced();
+        window.request_redraw_synced();
+      }
+      Event::RedrawRequested(id) => {
+        assert_eq!(id, window.id());
+        redraws += 1;
+      }
+      Event::RedrawEventsCleared => {
+        iterations += 1;
+        if iterations == 3 {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      _ => (),
+    }
+  });
+  assert_eq!(redraws, 1, "the synced redraws weren't coalesced");
+}
@@ -0,0 +1,153 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks the synthetic backend of `tao-headless`: its monitors, the events of a window
+//! moved to a monitor with another scale factor, injected events and the timers that don't wait.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+use std::time::{Duration, Instant};
+use tao::{
+  dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+  event::{Event, StartCause, WindowEvent},
+  event_loop::{ControlFlow, EventLoopBuilder},
+  headless::{self, VirtualMonitor},
+  keyboard::ModifiersState,
+  platform::run_return::EventLoopExtRunReturn,
+  window::WindowBuilder,
+};
+
+#[derive(Debug, PartialEq)]
+enum Recorded {
+  Focused(bool),
+  Moved(PhysicalPosition<i32>),
+  Resized(PhysicalSize<u32>),
+  ScaleFactorChanged(f64, PhysicalSize<u32>),
+  MonitorChanged(Option<String>),
+  CursorMoved(PhysicalPosition<f64>),
+  Redraw,
+}
+
+fn main() {
+  let mut event_loop = EventLoopBuilder::new()
+    .with_virtual_monitors(vec![
+      VirtualMonitor::new(
+        "Left",
+        PhysicalPosition::new(0, 0),
+        PhysicalSize::new(1920, 1080),
+        1.0,
+      ),
+      VirtualMonitor::new(
+        "Right",
+        PhysicalPosition::new(1920, 0),
+        PhysicalSize::new(2560, 1440),
+        2.0,
+      ),
+    ])
+    .build();
+
+  let names: Vec<_> = event_loop
+    .available_monitors()
+    .map(|monitor| monitor.name())
+    .collect();
+  assert_eq!(names, [Some("Left".into()), Some("Right".into())]);
+  assert_eq!(
+    event_loop
+      .primary_monitor()
+      .and_then(|monitor| monitor.name()),
+    Some("Left".into())
+  );
+
+  let window = WindowBuilder::new()
+    .with_position(PhysicalPosition::new(100, 100))
+    .with_inner_size(LogicalSize::new(800, 600))
+    .build(&event_loop)
+    .unwrap();
+  assert_eq!(window.inner_size(), PhysicalSize::new(800, 600));
+  assert_eq!(window.outer_size(), window.inner_size());
+  assert_eq!(window.scale_factor(), 1.0);
+  assert!(window.is_focused());
+
+  let proxy = event_loop.create_proxy();
+  let mut recorded = Vec::new();
+  let mut step = 0;
+  let mut timer = None;
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, _, control_flow| {
+    if Instant::now() > deadline {
+      panic!("the event loop didn't deliver its events in time");
+    }
+    match event {
+      Event::NewEvents(StartCause::Init) => *control_flow = ControlFlow::Wait,
+      Event::NewEvents(StartCause::ResumeTimeReached { .. }) => timer = Some(Instant::now()),
+      Event::WindowEvent { event, .. } => recorded.push(match event {
+        WindowEvent::Focused(focused) => Recorded::Focused(focused),
+        WindowEvent::Moved(position) => Recorded::Moved(position),
+        WindowEvent::Resized(size) => Recorded::Resized(size),
+        WindowEvent::ScaleFactorChanged {
+          scale_factor,
+          new_inner_size,
+        } => Recorded::ScaleFactorChanged(scale_factor, *new_inner_size),
+        WindowEvent::MonitorChanged(monitor) => Recorded::MonitorChanged(monitor.name()),
+        WindowEvent::CursorMoved { position, .. } => Recorded::CursorMoved(position),
+        event => panic!("unexpected event {:?}", event),
+      }),
+      Event::RedrawRequested(_) => recorded.push(Recorded::Redraw),
+      Event::MainEventsCleared => {
+        step += 1;
+        match step {
+          1 => window.set_outer_position(PhysicalPosition::new(2000, 100)),
+          2 => {
+            proxy
+              .inject(
+                window.id(),
+                WindowEvent::Resized(PhysicalSize::new(640, 480)),
+              )
+              .unwrap();
+            #[allow(deprecated)]
+            let event = WindowEvent::CursorMoved {
+              device_id: headless::device_id(),
+              position: PhysicalPosition::new(10.0, 20.0),
+              timestamp: Instant::now(),
+              modifiers: ModifiersState::empty(),
+            };
+            proxy.inject(window.id(), event).unwrap();
+          }
+          3 => {
+            // Far in the future, the timer still fires right away.
+            let requested_resume = Instant::now() + Duration::from_secs(3600);
+            *control_flow = ControlFlow::WaitUntil(requested_resume);
+          }
+          _ => *control_flow = ControlFlow::Exit,
+        }
+      }
+      _ => (),
+    }
+  });
+
+  assert_eq!(
+    recorded,
+    [
+      Recorded::Focused(true),
+      Recorded::Redraw,
+      Recorded::Moved(PhysicalPosition::new(2000, 100)),
+      Recorded::ScaleFactorChanged(2.0, PhysicalSize::new(1600, 1200)),
+      Recorded::Resized(PhysicalSize::new(1600, 1200)),
+      Recorded::MonitorChanged(Some("Right".into())),
+      Recorded::Resized(PhysicalSize::new(640, 480)),
+      Recorded::CursorMoved(PhysicalPosition::new(10.0, 20.0)),
+    ]
+  );
+  assert!(timer.unwrap() < deadline);
+  assert_eq!(window.scale_factor(), 2.0);
+  assert_eq!(window.inner_size(), PhysicalSize::new(640, 480));
+  assert_eq!(
+    window.cursor_position().unwrap(),
+    PhysicalPosition::new(10.0, 20.0)
+  );
+  assert_eq!(
+    window.current_monitor().and_then(|monitor| monitor.name()),
+    Some("Right".into())
+  );
+}
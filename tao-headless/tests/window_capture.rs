@@ -0,0 +1,111 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `Window::capture_rect` crops the capture of the client area, and that
+//! `EventLoopWindowTarget::request_window_capture` delivers the same images through the event
+//! loop, in the synthetic desktop of `tao-headless` where windows are filled with their
+//! background color.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+use std::time::{Duration, Instant};
+use tao::{
+  dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Rect},
+  error::ExternalError,
+  event::{Event, StartCause},
+  event_loop::{ControlFlow, EventLoop},
+  platform::run_return::EventLoopExtRunReturn,
+  window::{Color, WindowBuilder},
+};
+
+fn main() {
+  let mut event_loop = EventLoop::new();
+  let color = Color::new(10, 20, 30, 255);
+  let window = WindowBuilder::new()
+    .with_inner_size(PhysicalSize::new(200, 100))
+    .with_background_color(Some(color))
+    .build(&event_loop)
+    .unwrap();
+  let pixel = [color.r, color.g, color.b, color.a];
+
+  let image = window.capture().unwrap();
+  assert_eq!((image.width, image.height, image.stride), (200, 100, 800));
+  assert!(image.data.chunks_exact(4).all(|p| p == pixel));
+
+  let image = window
+    .capture_rect(Rect::new(
+      PhysicalPosition::new(10, 20),
+      PhysicalSize::new(30, 40),
+    ))
+    .unwrap();
+  assert_eq!((image.width, image.height, image.stride), (30, 40, 120));
+  assert_eq!(image.data.len(), 30 * 40 * 4);
+  assert!(image.data.chunks_exact(4).all(|p| p == pixel));
+
+  // Logical units follow the scale factor of the window.
+  let scale_factor = window.scale_factor();
+  let image = window
+    .capture_rect(Rect::new(
+      LogicalPosition::new(0.0, 0.0),
+      LogicalSize::new(50.0 / scale_factor, 25.0 / scale_factor),
+    ))
+    .unwrap();
+  assert_eq!((image.width, image.height), (50, 25));
+
+  // The part outside of the client area is left out.
+  let image = window
+    .capture_rect(Rect::new(
+      PhysicalPosition::new(-10, 90),
+      PhysicalSize::new(40, 40),
+    ))
+    .unwrap();
+  assert_eq!((image.width, image.height), (30, 10));
+
+  assert!(matches!(
+    window.capture_rect(Rect::new(
+      PhysicalPosition::new(200, 0),
+      PhysicalSize::new(10, 10),
+    )),
+    Err(ExternalError::InvalidInput(_))
+  ));
+
+  // Requested captures are taken in the next iteration, in order.
+  let rect = Rect::new(PhysicalPosition::new(10, 20), PhysicalSize::new(30, 40));
+  let mut requested = Vec::new();
+  let mut completed = Vec::new();
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, target, control_flow| {
+    *control_flow = ControlFlow::Wait;
+    if Instant::now() > deadline {
+      panic!("the captures weren't delivered in time");
+    }
+    match event {
+      Event::NewEvents(StartCause::Init) => {
+        requested.push(target.request_window_capture(&window, None));
+        requested.push(target.request_window_capture(&window, Some(rect)));
+        let monitor = window.current_monitor().unwrap();
+        requested.push(target.request_monitor_capture(&monitor, None));
+        assert!(completed.is_empty(), "a capture was delivered right away");
+      }
+      Event::CaptureCompleted { id, image } => {
+        completed.push((id, image));
+        if completed.len() == requested.len() {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      _ => (),
+    }
+  });
+  let ids: Vec<_> = completed.iter().map(|(id, _)| *id).collect();
+  assert_eq!(ids, requested);
+  assert_eq!(completed[0].1.as_ref(), Some(&window.capture().unwrap()));
+  assert_eq!(
+    completed[1].1.as_ref(),
+    Some(&window.capture_rect(rect).unwrap())
+  );
+  // Monitors can't be captured in the synthetic desktop.
+  assert_eq!(completed[2].1, None);
+
+  window.set_minimized(true);
+  assert!(window.capture().is_err());
+}
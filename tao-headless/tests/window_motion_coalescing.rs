@@ -0,0 +1,83 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that a window built with `WindowBuilder::with_motion_coalescing` gets a single
+//! `CursorMoved` with the last position per iteration, while the other windows still get every
+//! motion.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+use std::time::{Duration, Instant};
+use tao::{
+  dpi::PhysicalPosition,
+  event::{Event, StartCause, WindowEvent},
+  event_loop::{ControlFlow, EventLoop, MotionCoalescing},
+  headless,
+  keyboard::ModifiersState,
+  platform::run_return::EventLoopExtRunReturn,
+  window::WindowBuilder,
+};
+
+fn main() {
+  let mut event_loop = EventLoop::new();
+  let coalesced = WindowBuilder::new()
+    .with_motion_coalescing(MotionCoalescing::Latest)
+    .build(&event_loop)
+    .unwrap();
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+
+  let proxy = event_loop.create_proxy();
+  let mut coalesced_moves = Vec::new();
+  let mut moves = Vec::new();
+  let mut step = 0;
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, _, control_flow| {
+    if Instant::now() > deadline {
+      panic!("the cursor motions weren't delivered in time");
+    }
+    match event {
+      Event::NewEvents(StartCause::Init) => *control_flow = ControlFlow::Wait,
+      Event::WindowEvent {
+        window_id,
+        event: WindowEvent::CursorMoved { position, .. },
+        ..
+      } => {
+        if window_id == coalesced.id() {
+          coalesced_moves.push(position);
+        } else {
+          moves.push(position);
+        }
+      }
+      Event::MainEventsCleared => {
+        step += 1;
+        if step == 1 {
+          for x in 1..=3 {
+            for window_id in [coalesced.id(), window.id()] {
+              #[allow(deprecated)]
+              let event = WindowEvent::CursorMoved {
+                device_id: headless::device_id(),
+                position: PhysicalPosition::new(x as f64, 0.0),
+                timestamp: Instant::now(),
+                modifiers: ModifiersState::empty(),
+              };
+              proxy.inject(window_id, event).unwrap();
+            }
+          }
+        } else {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      _ => (),
+    }
+  });
+
+  assert_eq!(coalesced_moves, [PhysicalPosition::new(3.0, 0.0)]);
+  assert_eq!(
+    moves,
+    [
+      PhysicalPosition::new(1.0, 0.0),
+      PhysicalPosition::new(2.0, 0.0),
+      PhysicalPosition::new(3.0, 0.0),
+    ]
+  );
+}
@@ -0,0 +1,118 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `Window::state` is restored by `WindowBuilder::with_restored_state` on its
+//! monitor, and on another monitor once its monitor is gone, in the synthetic desktop of
+//! `tao-headless`.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+use tao::{
+  dpi::{PhysicalPosition, PhysicalSize},
+  event_loop::EventLoopBuilder,
+  headless::VirtualMonitor,
+  window::{Fullscreen, WindowBuilder, WindowState},
+};
+
+fn main() {
+  let event_loop = EventLoopBuilder::new()
+    .with_virtual_monitors(vec![
+      VirtualMonitor::new(
+        "Left",
+        PhysicalPosition::new(0, 0),
+        PhysicalSize::new(1920, 1080),
+        1.0,
+      ),
+      VirtualMonitor::new(
+        "Right",
+        PhysicalPosition::new(1920, 0),
+        PhysicalSize::new(1280, 1024),
+        1.0,
+      ),
+    ])
+    .build();
+  let right = event_loop
+    .available_monitors()
+    .find(|monitor| monitor.name().as_deref() == Some("Right"))
+    .unwrap();
+
+  let window = WindowBuilder::new()
+    .with_position(PhysicalPosition::new(2000, 100))
+    .with_inner_size(PhysicalSize::new(800, 600))
+    .build(&event_loop)
+    .unwrap();
+  let state = window.state();
+  assert_eq!(
+    state,
+    WindowState {
+      position: PhysicalPosition::new(2000, 100),
+      size: PhysicalSize::new(800, 600),
+      outer_size: PhysicalSize::new(800, 600),
+      monitor: Some(right.id()),
+      maximized: false,
+      fullscreen: false,
+    }
+  );
+  drop(window);
+
+  let restored = WindowBuilder::new()
+    .with_restored_state(state)
+    .build(&event_loop)
+    .unwrap();
+  assert_eq!(restored.state(), state);
+  drop(restored);
+
+  // A monitor that was unplugged, right of the others: the window goes to the primary monitor.
+  let unplugged = WindowState {
+    position: PhysicalPosition::new(4000, 1200),
+    size: PhysicalSize::new(2560, 800),
+    outer_size: PhysicalSize::new(2560, 800),
+    monitor: Some(1),
+    maximized: false,
+    fullscreen: false,
+  };
+  let moved = WindowBuilder::new()
+    .with_restored_state(unplugged)
+    .build(&event_loop)
+    .unwrap();
+  assert_eq!(
+    moved.outer_position().unwrap(),
+    PhysicalPosition::new(0, 280)
+  );
+  assert_eq!(moved.inner_size(), PhysicalSize::new(1920, 800));
+  drop(moved);
+
+  // The monitor showing most of the window is picked when its own is unknown.
+  let overlapping = WindowState {
+    position: PhysicalPosition::new(1800, 900),
+    monitor: None,
+    ..unplugged
+  };
+  let moved = WindowBuilder::new()
+    .with_restored_state(WindowState {
+      size: PhysicalSize::new(400, 300),
+      outer_size: PhysicalSize::new(400, 300),
+      ..overlapping
+    })
+    .build(&event_loop)
+    .unwrap();
+  assert_eq!(moved.current_monitor(), Some(right.clone()));
+  assert_eq!(
+    moved.outer_position().unwrap(),
+    PhysicalPosition::new(1920, 724)
+  );
+  drop(moved);
+
+  let fullscreen = WindowBuilder::new()
+    .with_restored_state(WindowState {
+      fullscreen: true,
+      ..state
+    })
+    .build(&event_loop)
+    .unwrap();
+  assert_eq!(
+    fullscreen.fullscreen(),
+    Some(Fullscreen::Borderless(Some(right)))
+  );
+  assert!(fullscreen.state().fullscreen);
+}
@@ -0,0 +1,85 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that an `AccessibilityActionSender` delivers the requests of another thread to the
+//! event loop in order, with the window of the sender.
+//!
+//! This needs a real desktop session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::{
+    thread,
+    time::{Duration, Instant},
+  };
+  use tao::{
+    event::{Event, StartCause},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::WindowBuilder,
+  };
+
+  /// Stands for the request type of an adapter, which tao doesn't know.
+  #[derive(Debug, PartialEq)]
+  struct Focus(u64);
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  {
+    if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+      eprintln!("skipping accessibility_action: no display");
+      return;
+    }
+  }
+
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+  let sender = window.accessibility_action_sender().unwrap();
+  assert_eq!(sender.window_id(), window.id());
+
+  let mut recorded = Vec::new();
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, _, control_flow| {
+    if Instant::now() > deadline {
+      panic!("the accessibility action requests weren't delivered in time");
+    }
+    match event {
+      Event::NewEvents(StartCause::Init) => {
+        *control_flow = ControlFlow::Wait;
+        let sender = sender.clone();
+        thread::spawn(move || {
+          for i in 1..=3 {
+            sender.send(Focus(i)).unwrap();
+          }
+        });
+      }
+      Event::AccessibilityAction { window_id, request } => {
+        assert_eq!(window_id, window.id());
+        recorded.push(request.downcast_ref::<Focus>().unwrap().0);
+        if recorded.len() == 3 {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      _ => (),
+    }
+  });
+  assert_eq!(recorded, [1, 2, 3]);
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
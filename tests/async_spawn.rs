@@ -0,0 +1,114 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that the futures spawned with `EventLoopExtAsync::spawn` run on the event loop, are
+//! polled again when woken up from another thread, and can spawn other futures. It uses a
+//! headless event loop, so it also runs without a display server on Linux.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+  };
+  use tao::{
+    event::{Event, StartCause},
+    event_loop::{ControlFlow, EventLoopBuilder},
+    platform::{executor::EventLoopExtAsync, run_return::EventLoopExtRunReturn},
+  };
+
+  /// A value sent from another thread, which wakes the task waiting on it.
+  #[derive(Default)]
+  struct Slot {
+    value: Option<u32>,
+    waker: Option<Waker>,
+  }
+
+  struct Receive(Arc<Mutex<Slot>>);
+
+  impl Future for Receive {
+    type Output = u32;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+      let mut slot = self.0.lock().unwrap();
+      match slot.value.take() {
+        Some(value) => Poll::Ready(value),
+        None => {
+          slot.waker = Some(cx.waker().clone());
+          Poll::Pending
+        }
+      }
+    }
+  }
+
+  let mut event_loop = EventLoopBuilder::new().with_headless(true).build();
+  // Not `Send`: the futures stay on the thread of the event loop.
+  let log = Rc::new(RefCell::new(Vec::new()));
+  let slot = Arc::new(Mutex::new(Slot::default()));
+
+  let task_log = log.clone();
+  let receive = Receive(slot.clone());
+  event_loop.spawn(async move {
+    task_log.borrow_mut().push("started");
+    assert_eq!(receive.await, 42);
+    task_log.borrow_mut().push("received");
+  });
+  assert!(log.borrow().is_empty());
+
+  let sender = thread::spawn(move || {
+    thread::sleep(Duration::from_millis(100));
+    let mut slot = slot.lock().unwrap();
+    slot.value = Some(42);
+    slot.waker.take().expect("the task wasn't polled").wake();
+  });
+
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, target, control_flow| {
+    if Instant::now() > deadline {
+      panic!("the spawned futures didn't complete in time");
+    }
+    match event {
+      Event::NewEvents(StartCause::Init) => *control_flow = ControlFlow::Wait,
+      Event::MainEventsCleared => {
+        let done = log.borrow().last() == Some(&"received");
+        if done && !log.borrow().contains(&"nested") {
+          let task_log = log.clone();
+          target.spawn(async move { task_log.borrow_mut().push("nested") });
+        } else if log.borrow().last() == Some(&"nested") {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      _ => (),
+    }
+  });
+  sender.join().unwrap();
+
+  assert_eq!(*log.borrow(), ["started", "received", "nested"]);
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -0,0 +1,93 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `set_cursor_position` warps the cursor relative to the window: after warping it
+//! to the center of the window, the next `CursorMoved` is within a pixel of it.
+//!
+//! This needs a real desktop session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    dpi::{LogicalSize, PhysicalPosition},
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::WindowBuilder,
+  };
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  {
+    // Wayland only warps the cursor of a locked pointer, so only X11 can be tested.
+    if std::env::var_os("DISPLAY").is_none() {
+      eprintln!("skipping cursor_position: no X11 display");
+      return;
+    }
+    std::env::set_var("GDK_BACKEND", "x11");
+  }
+
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new()
+    .with_inner_size(LogicalSize::new(400, 300))
+    .build(&event_loop)
+    .unwrap();
+
+  // Gives the window manager the time to map and place the window before warping.
+  let warp_at = Instant::now() + Duration::from_millis(500);
+  let deadline = warp_at + Duration::from_secs(5);
+  let mut center = None;
+  let mut moved = None;
+  event_loop.run_return(|event, _, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(50));
+
+    match event {
+      Event::WindowEvent {
+        event: WindowEvent::CursorMoved { position, .. },
+        ..
+      } if center.is_some() => {
+        moved = Some(position);
+        *control_flow = ControlFlow::Exit;
+      }
+      Event::MainEventsCleared if center.is_none() && Instant::now() > warp_at => {
+        let size = window.inner_size();
+        let position = PhysicalPosition::new(size.width as f64 / 2.0, size.height as f64 / 2.0);
+        window.set_cursor_position(position).unwrap();
+        center = Some(position);
+      }
+      Event::MainEventsCleared if Instant::now() > deadline => {
+        *control_flow = ControlFlow::Exit;
+      }
+      _ => (),
+    }
+  });
+
+  let center = center.expect("the cursor wasn't warped");
+  let moved = moved.expect("no CursorMoved after warping the cursor");
+  assert!(
+    (moved.x - center.x).abs() <= 1.0 && (moved.y - center.y).abs() <= 1.0,
+    "CursorMoved({:?}) isn't within a pixel of {:?}",
+    moved,
+    center
+  );
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
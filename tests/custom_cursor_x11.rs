@@ -0,0 +1,85 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `Window::set_custom_cursor` sets the image as the cursor of the GDK window once
+//! the event loop handles the request, with its hotspot.
+//!
+//! This needs a real X11 session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use gtk::{gdk::CursorType, prelude::*};
+  use std::time::{Duration, Instant};
+  use tao::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::Event,
+    event_loop::{ControlFlow, EventLoop},
+    platform::{run_return::EventLoopExtRunReturn, unix::WindowExtUnix},
+    window::{CursorKind, RgbaImage, WindowBuilder},
+  };
+
+  if std::env::var_os("DISPLAY").is_none() {
+    eprintln!("skipping custom_cursor_x11: no X11 display");
+    return;
+  }
+  std::env::set_var("GDK_BACKEND", "x11");
+
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new()
+    .with_inner_size(PhysicalSize::new(200, 100))
+    .build(&event_loop)
+    .unwrap();
+  let image = RgbaImage {
+    width: 16,
+    height: 16,
+    stride: 64,
+    data: vec![u8::MAX; 64 * 16],
+  };
+  let hotspot = PhysicalPosition::new(3, 5);
+  window.set_custom_cursor(&image, hotspot).unwrap();
+  assert_eq!(window.cursor_icon(), CursorKind::Custom);
+
+  // The hotspot of a cursor made from a surface is in logical pixels of the surface.
+  let scale = window.scale_factor();
+  let expected = (hotspot.x as f64 / scale, hotspot.y as f64 / scale);
+  let custom_cursor_set = || {
+    let gdk_window = window
+      .gtk_window()
+      .window()
+      .expect("the window isn't realized");
+    gdk_window.cursor().map_or(false, |cursor| {
+      let (surface, x, y) = cursor.surface();
+      cursor.cursor_type() == CursorType::CursorIsPixmap && surface.is_some() && (x, y) == expected
+    })
+  };
+
+  let deadline = Instant::now() + Duration::from_secs(5);
+  event_loop.run_return(|event, _, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(50));
+    if let Event::MainEventsCleared = event {
+      if custom_cursor_set() || Instant::now() > deadline {
+        *control_flow = ControlFlow::Exit;
+      }
+    }
+  });
+
+  assert!(
+    custom_cursor_set(),
+    "the image with its hotspot isn't the cursor of the window"
+  );
+}
+
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -0,0 +1,188 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that a window embedded into a foreign X11 window with
+//! `WindowBuilderExtUnix::with_embed_parent` is a child of it and follows its size, that its
+//! `_XEMBED_INFO` follows its visibility, and that `WindowExtUnix::set_embed_parent` makes it a
+//! top-level window and embeds it again. It's skipped without an X11 display.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+#[cfg(target_os = "linux")]
+fn main() {
+  embed::check();
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main() {}
+
+#[cfg(target_os = "linux")]
+mod embed {
+  use std::{
+    mem::MaybeUninit,
+    os::raw::{c_int, c_uchar, c_uint, c_ulong},
+    ptr,
+    time::{Duration, Instant},
+  };
+  use tao::{
+    event::Event,
+    event_loop::{ControlFlow, EventLoop},
+    platform::{
+      run_return::EventLoopExtRunReturn,
+      unix::{EmbedParent, WindowBuilderExtUnix, WindowExtUnix},
+    },
+    window::WindowBuilder,
+  };
+  use x11_dl::xlib;
+
+  /// A connection of the test itself, to create the parent and look at the embedded window.
+  struct Connection {
+    xlib: xlib::Xlib,
+    display: *mut xlib::Display,
+  }
+
+  impl Connection {
+    fn parent_of(&self, window: c_ulong) -> c_ulong {
+      let (mut root, mut parent) = (0, 0);
+      let mut children = ptr::null_mut();
+      let mut count: c_uint = 0;
+      unsafe {
+        (self.xlib.XQueryTree)(
+          self.display,
+          window,
+          &mut root,
+          &mut parent,
+          &mut children,
+          &mut count,
+        );
+        if !children.is_null() {
+          (self.xlib.XFree)(children as *mut _);
+        }
+      }
+      parent
+    }
+
+    fn size_of(&self, window: c_ulong) -> (c_int, c_int) {
+      let mut attributes = MaybeUninit::<xlib::XWindowAttributes>::uninit();
+      unsafe {
+        (self.xlib.XGetWindowAttributes)(self.display, window, attributes.as_mut_ptr());
+        let attributes = attributes.assume_init();
+        (attributes.width, attributes.height)
+      }
+    }
+
+    /// The flags of `_XEMBED_INFO`, `None` without the property.
+    fn xembed_flags(&self, window: c_ulong) -> Option<c_ulong> {
+      let mut actual_type = 0;
+      let mut format = 0;
+      let (mut count, mut remaining) = (0, 0);
+      let mut data: *mut c_uchar = ptr::null_mut();
+      unsafe {
+        let atom = (self.xlib.XInternAtom)(
+          self.display,
+          b"_XEMBED_INFO\0".as_ptr() as *const _,
+          xlib::False,
+        );
+        (self.xlib.XGetWindowProperty)(
+          self.display,
+          window,
+          atom,
+          0,
+          2,
+          xlib::False,
+          atom,
+          &mut actual_type,
+          &mut format,
+          &mut count,
+          &mut remaining,
+          &mut data,
+        );
+        if data.is_null() {
+          return None;
+        }
+        let flags = (count == 2).then(|| *(data as *const c_ulong).add(1));
+        (self.xlib.XFree)(data as *mut _);
+        flags
+      }
+    }
+  }
+
+  impl Drop for Connection {
+    fn drop(&mut self) {
+      unsafe { (self.xlib.XCloseDisplay)(self.display) };
+    }
+  }
+
+  /// Runs the event loop for a while, for the requests to reach the X server.
+  fn settle(event_loop: &mut EventLoop<()>) {
+    let deadline = Instant::now() + Duration::from_millis(500);
+    event_loop.run_return(|event, _, control_flow| {
+      *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(10));
+      if let Event::MainEventsCleared = event {
+        if Instant::now() > deadline {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+    });
+  }
+
+  pub fn check() {
+    if std::env::var_os("DISPLAY").is_none() {
+      eprintln!("no X11 display, skipping embed_parent");
+      return;
+    }
+    std::env::set_var("GDK_BACKEND", "x11");
+    let mut event_loop = EventLoop::new();
+
+    let xlib = xlib::Xlib::open().expect("failed to load Xlib");
+    let display = unsafe { (xlib.XOpenDisplay)(ptr::null()) };
+    assert!(!display.is_null(), "failed to open the X11 display");
+    let connection = Connection { xlib, display };
+    let parent = unsafe {
+      let root = (connection.xlib.XDefaultRootWindow)(display);
+      let parent = (connection.xlib.XCreateSimpleWindow)(display, root, 0, 0, 200, 150, 0, 0, 0);
+      (connection.xlib.XMapWindow)(display, parent);
+      (connection.xlib.XSync)(display, xlib::False);
+      parent
+    };
+
+    let window = WindowBuilder::new()
+      .with_embed_parent(EmbedParent::Xlib(parent as u64))
+      .build(&event_loop)
+      .unwrap();
+    settle(&mut event_loop);
+    let xid = window.xid().expect("an X11 window has an id") as c_ulong;
+    assert_eq!(connection.parent_of(xid), parent);
+    assert_eq!(connection.size_of(xid), (200, 150));
+    assert_eq!(connection.xembed_flags(xid), Some(1));
+
+    unsafe {
+      (connection.xlib.XResizeWindow)(display, parent, 300, 100);
+      (connection.xlib.XSync)(display, xlib::False);
+    }
+    settle(&mut event_loop);
+    assert_eq!(connection.size_of(xid), (300, 100));
+
+    window.set_visible(false);
+    settle(&mut event_loop);
+    assert_eq!(connection.xembed_flags(xid), Some(0));
+    window.set_visible(true);
+    settle(&mut event_loop);
+    assert_eq!(connection.xembed_flags(xid), Some(1));
+
+    window.set_embed_parent(None).unwrap();
+    settle(&mut event_loop);
+    assert_ne!(connection.parent_of(xid), parent);
+    assert_eq!(connection.xembed_flags(xid), None);
+
+    window
+      .set_embed_parent(Some(EmbedParent::Xlib(parent as u64)))
+      .unwrap();
+    settle(&mut event_loop);
+    assert_eq!(connection.parent_of(xid), parent);
+    assert_eq!(connection.xembed_flags(xid), Some(1));
+
+    unsafe { (connection.xlib.XDestroyWindow)(display, parent) };
+  }
+}
@@ -0,0 +1,138 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `WindowExtUnix::set_event_hook` sees the key presses of a window before tao does:
+//! a key press the hook handles isn't reported as `KeyboardInput`, and once the hook is removed,
+//! the next one is.
+//!
+//! This needs a real X11 session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+  };
+  use tao::{
+    dpi::{LogicalSize, PhysicalPosition},
+    event::{ElementState, Event, KeyEvent, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::{
+      inject::{EventLoopExtInject, SyntheticInput},
+      run_return::EventLoopExtRunReturn,
+      unix::WindowExtUnix,
+    },
+    window::WindowBuilder,
+  };
+
+  // Injecting input needs XTest, so only X11 can be tested.
+  if std::env::var_os("DISPLAY").is_none() {
+    eprintln!("skipping event_hook_x11: no X11 display");
+    return;
+  }
+  std::env::set_var("GDK_BACKEND", "x11");
+
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new()
+    .with_inner_size(LogicalSize::new(400, 300))
+    .build(&event_loop)
+    .unwrap();
+
+  let hooked = Rc::new(Cell::new(0));
+  let hook_presses = hooked.clone();
+  window.set_event_hook(Some(Box::new(move |_, event| {
+    if event.event_type() == gtk::gdk::EventType::KeyPress {
+      hook_presses.set(hook_presses.get() + 1);
+      true
+    } else {
+      false
+    }
+  })));
+
+  // Gives the window manager the time to map and place the window before pointing at it.
+  let point_at = Instant::now() + Duration::from_millis(500);
+  let deadline = point_at + Duration::from_secs(5);
+  let mut pointed = false;
+  let mut typed = 0;
+  let mut unhooked_at = None;
+  let mut text = String::new();
+  event_loop.run_return(|event, target, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(50));
+
+    match event {
+      Event::WindowEvent {
+        event:
+          WindowEvent::KeyboardInput {
+            event:
+              KeyEvent {
+                state: ElementState::Pressed,
+                text: Some(pressed),
+                ..
+              },
+            ..
+          },
+        ..
+      } => {
+        text.push_str(pressed);
+        *control_flow = ControlFlow::Exit;
+      }
+      Event::MainEventsCleared if !pointed && Instant::now() > point_at => {
+        // Without a window manager, the focus follows the pointer.
+        let position = window.inner_position().unwrap();
+        let size = window.inner_size();
+        target
+          .inject_input(SyntheticInput::CursorMoved(PhysicalPosition::new(
+            (position.x + size.width as i32 / 2) as f64,
+            (position.y + size.height as i32 / 2) as f64,
+          )))
+          .unwrap();
+        window.set_focus();
+        pointed = true;
+      }
+      Event::MainEventsCleared if pointed && typed == 0 && window.is_focused() => {
+        target
+          .inject_input(SyntheticInput::Text("a".into()))
+          .unwrap();
+        typed = 1;
+      }
+      Event::MainEventsCleared if typed == 1 && hooked.get() > 0 => {
+        // The hook is replaced once the event loop handles the request.
+        window.set_event_hook(None);
+        unhooked_at = Some(Instant::now());
+        typed = 2;
+      }
+      Event::MainEventsCleared
+        if typed == 2 && unhooked_at.unwrap().elapsed() > Duration::from_millis(200) =>
+      {
+        target
+          .inject_input(SyntheticInput::Text("b".into()))
+          .unwrap();
+        typed = 3;
+      }
+      Event::MainEventsCleared if Instant::now() > deadline => {
+        *control_flow = ControlFlow::Exit;
+      }
+      _ => (),
+    }
+  });
+
+  assert!(typed > 0, "the window never got the focus");
+  assert_eq!(hooked.get(), 1, "the hook didn't see the key press");
+  assert_eq!(text, "b", "tao saw the key press the hook handled");
+}
+
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -0,0 +1,91 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `EventLoopWindowTarget::exit_with_code` ends the event loop with its code even if
+//! the handler sets another `ControlFlow` afterwards, that the rest of the iteration and a single
+//! `LoopDestroyed` are still delivered, and that the next run starts over.
+//!
+//! `exit` can only be called from the thread of the event loop: the target is neither `Send`
+//! nor `Sync`, so calling it from another thread doesn't compile.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    event::{Event, StartCause},
+    event_loop::{ControlFlow, EventLoopBuilder},
+    platform::run_return::EventLoopExtRunReturn,
+  };
+
+  // Headless, so that it also runs without a display server on Linux.
+  let mut event_loop = EventLoopBuilder::new().with_headless(true).build();
+
+  let mut main_events_cleared_after_exit = false;
+  let mut loop_destroyed = 0;
+  let deadline = Instant::now() + Duration::from_secs(10);
+  let code = event_loop.run_return(|event, target, control_flow| {
+    if Instant::now() > deadline {
+      panic!("the event loop didn't exit in time");
+    }
+    match event {
+      Event::NewEvents(StartCause::Init) => {
+        assert!(!target.is_exiting());
+        target.exit_with_code(3);
+        // Only the first code is kept.
+        target.exit_with_code(4);
+        assert!(target.is_exiting());
+        *control_flow = ControlFlow::Poll;
+      }
+      Event::MainEventsCleared => {
+        assert!(target.is_exiting());
+        main_events_cleared_after_exit = true;
+        *control_flow = ControlFlow::Wait;
+      }
+      Event::LoopDestroyed => loop_destroyed += 1,
+      _ => (),
+    }
+  });
+
+  assert_eq!(code, 3);
+  assert!(
+    main_events_cleared_after_exit,
+    "the iteration stopped at the exit"
+  );
+  assert_eq!(loop_destroyed, 1);
+
+  // An exit set through the `ControlFlow` is reported as well, and the previous one is forgotten.
+  let mut exiting_at_init = None;
+  let code = event_loop.run_return(|event, target, control_flow| match event {
+    Event::NewEvents(StartCause::Init) => {
+      exiting_at_init = Some(target.is_exiting());
+      *control_flow = ControlFlow::ExitWithCode(5);
+    }
+    Event::LoopDestroyed => assert!(target.is_exiting()),
+    _ => (),
+  });
+
+  assert_eq!(exiting_at_init, Some(false));
+  assert_eq!(code, 5);
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
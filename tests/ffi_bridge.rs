@@ -0,0 +1,101 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that the payloads of `tao_post_user_payload` are delivered in order after
+//! `NewEvents`, that the bridge stays open across calls of `run_return`, delivering what was
+//! posted in between, and that it closes once the event loop is dropped. It uses a headless
+//! event loop, so it also runs without a display server on Linux.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+#[cfg(all(
+  feature = "ffi-bridge",
+  any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  )
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    event::{Event, StartCause},
+    event_loop::{ControlFlow, EventLoop, EventLoopBuilder},
+    ffi_bridge::{tao_post_user_payload, TAO_BRIDGE_CLOSED, TAO_BRIDGE_INVALID, TAO_BRIDGE_OK},
+    platform::run_return::EventLoopExtRunReturn,
+  };
+
+  fn post(payload: &[u8]) -> i32 {
+    unsafe { tao_post_user_payload(payload.as_ptr(), payload.len()) }
+  }
+
+  /// Runs the event loop until the payloads posted before are delivered, posting `during` from
+  /// the handler, and returns every payload delivered.
+  fn run(event_loop: &mut EventLoop<()>, during: &'static [u8]) -> Vec<Vec<u8>> {
+    let mut payloads = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    event_loop.run_return(|event, _, control_flow| {
+      *control_flow = ControlFlow::Wait;
+      if Instant::now() > deadline {
+        panic!("the payloads weren't delivered in time");
+      }
+      match event {
+        Event::NewEvents(StartCause::Init) => assert_eq!(post(during), TAO_BRIDGE_OK),
+        Event::NativeMessage(payload) => {
+          let last = payload == during;
+          payloads.push(payload);
+          if last {
+            *control_flow = ControlFlow::Exit;
+          }
+        }
+        _ => (),
+      }
+    });
+    payloads
+  }
+
+  // Headless, so that it also runs without a display server on Linux.
+  let mut event_loop = EventLoopBuilder::new()
+    .with_headless(true)
+    .with_ffi_bridge(true)
+    .build();
+
+  assert_eq!(
+    unsafe { tao_post_user_payload(std::ptr::null(), 1) },
+    TAO_BRIDGE_INVALID
+  );
+  assert_eq!(post(b"first"), TAO_BRIDGE_OK);
+  assert_eq!(post(b""), TAO_BRIDGE_OK);
+  assert_eq!(
+    run(&mut event_loop, b"during first run"),
+    vec![b"first".to_vec(), Vec::new(), b"during first run".to_vec()]
+  );
+
+  // `LoopDestroyed` of the first run didn't close the bridge.
+  assert_eq!(post(b"between runs"), TAO_BRIDGE_OK);
+  assert_eq!(
+    run(&mut event_loop, b"during second run"),
+    vec![b"between runs".to_vec(), b"during second run".to_vec()]
+  );
+
+  drop(event_loop);
+  assert_eq!(post(b"after drop"), TAO_BRIDGE_CLOSED);
+}
+
+#[cfg(not(all(
+  feature = "ffi-bridge",
+  any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  )
+)))]
+fn main() {}
@@ -0,0 +1,118 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that toggling fullscreen faster than the transitions complete ends in the last
+//! requested state, and that `Window::fullscreen` reflects each request right away.
+//!
+//! This needs a real desktop session with a window manager and runs on the main thread, so it
+//! uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::{Fullscreen, Window, WindowBuilder},
+  };
+
+  const TOGGLES: usize = 10;
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping fullscreen: no display");
+    return;
+  }
+
+  fn toggle(window: &Window) {
+    let fullscreen = window.fullscreen().is_none();
+    window
+      .try_set_fullscreen(fullscreen.then(|| Fullscreen::Borderless(None)))
+      .unwrap();
+    assert_eq!(
+      window.fullscreen().is_some(),
+      fullscreen,
+      "fullscreen() doesn't reflect the pending request"
+    );
+  }
+
+  /// Runs the event loop until the fullscreen state has been stable for a while, and returns the
+  /// states reported through `FullscreenChanged`.
+  fn settle(
+    event_loop: &mut EventLoop<()>,
+    window: &Window,
+    mut toggles: usize,
+  ) -> Vec<Option<Fullscreen>> {
+    let mut changes = Vec::new();
+    let mut last_change = Instant::now();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    event_loop.run_return(|event, _, control_flow| {
+      *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(10));
+      match event {
+        Event::WindowEvent {
+          event: WindowEvent::FullscreenChanged(fullscreen),
+          ..
+        } => {
+          changes.push(fullscreen);
+          last_change = Instant::now();
+        }
+        Event::MainEventsCleared => {
+          // One toggle per iteration lands them in the middle of the previous transitions.
+          if toggles > 0 {
+            toggle(window);
+            toggles -= 1;
+            last_change = Instant::now();
+          } else if last_change.elapsed() > Duration::from_millis(1500) || Instant::now() > deadline
+          {
+            *control_flow = ControlFlow::Exit;
+          }
+        }
+        _ => (),
+      }
+    });
+    changes
+  }
+
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+  settle(&mut event_loop, &window, 0);
+
+  // An even number of toggles goes back to the windowed state.
+  let changes = settle(&mut event_loop, &window, TOGGLES);
+  eprintln!("fullscreen: {} toggles, reported {:?}", TOGGLES, changes);
+  assert_eq!(window.fullscreen(), None);
+  if let Some(last) = changes.last() {
+    assert_eq!(*last, None, "the last reported state isn't windowed");
+  }
+
+  // The window isn't stuck in a transition afterwards.
+  toggle(&window);
+  let changes = settle(&mut event_loop, &window, 0);
+  assert!(window.fullscreen().is_some());
+  assert!(
+    matches!(changes.last(), Some(Some(Fullscreen::Borderless(_)))),
+    "entering fullscreen wasn't reported: {:?}",
+    changes
+  );
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
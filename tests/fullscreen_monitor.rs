@@ -0,0 +1,105 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that going borderless fullscreen on a given monitor moves and resizes the window in a
+//! single step, reporting one `Moved` and one `Resized`, and ends up on that monitor.
+//!
+//! Another monitor than the window's is used when there is one. This needs a real desktop session
+//! with a window manager and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::{Fullscreen, WindowBuilder},
+  };
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping fullscreen_monitor: no display");
+    return;
+  }
+
+  /// Runs the event loop until no window event came for a while, and returns how many `Moved`
+  /// and `Resized` events were received.
+  fn settle(event_loop: &mut EventLoop<()>) -> (usize, usize) {
+    let (mut moved, mut resized) = (0, 0);
+    let mut last_event = Instant::now();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    event_loop.run_return(|event, _, control_flow| {
+      *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(10));
+      match event {
+        Event::WindowEvent { event, .. } => {
+          match event {
+            WindowEvent::Moved(_) => moved += 1,
+            WindowEvent::Resized(_) => resized += 1,
+            _ => (),
+          }
+          last_event = Instant::now();
+        }
+        Event::MainEventsCleared => {
+          if last_event.elapsed() > Duration::from_millis(1500) || Instant::now() > deadline {
+            *control_flow = ControlFlow::Exit;
+          }
+        }
+        _ => (),
+      }
+    });
+    (moved, resized)
+  }
+
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+  settle(&mut event_loop);
+
+  let current = window.current_monitor();
+  let target = window
+    .available_monitors()
+    .find(|monitor| Some(monitor) != current.as_ref())
+    .or(current)
+    .expect("no monitor");
+  let already_there = window.outer_position().unwrap() == target.position();
+
+  window
+    .try_set_fullscreen(Some(Fullscreen::Borderless(Some(target.clone()))))
+    .unwrap();
+  let (moved, resized) = settle(&mut event_loop);
+  eprintln!(
+    "fullscreen_monitor: {:?}, {} moves, {} resizes",
+    target.name(),
+    moved,
+    resized
+  );
+
+  assert_eq!(window.current_monitor(), Some(target.clone()));
+  assert_eq!(window.outer_position().unwrap(), target.position());
+  assert_eq!(resized, 1, "the window wasn't resized in a single step");
+  assert_eq!(
+    moved,
+    if already_there { 0 } else { 1 },
+    "the window wasn't moved in a single step"
+  );
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
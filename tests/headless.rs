@@ -0,0 +1,89 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that a headless event loop delivers the init, timer and user events, and that its
+//! windows stay hidden. Unlike the other tests, it also runs without a display server on Linux,
+//! where creating a window is expected to fail instead.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    event::{Event, StartCause},
+    event_loop::{ControlFlow, EventLoopBuilder},
+    platform::run_return::EventLoopExtRunReturn,
+    window::WindowBuilder,
+  };
+
+  let mut event_loop = EventLoopBuilder::<u32>::with_user_event()
+    .with_headless(true)
+    .build();
+  assert!(event_loop.is_headless());
+
+  #[cfg(any(target_os = "windows", target_os = "macos"))]
+  let has_display = true;
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  let has_display =
+    std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some();
+
+  let window = WindowBuilder::new().with_visible(true).build(&event_loop);
+  match &window {
+    Ok(window) => {
+      window.set_visible(true);
+      assert!(!window.is_visible(), "a headless window was shown");
+    }
+    Err(e) => assert!(!has_display, "failed to create a window: {}", e),
+  }
+
+  let proxy = event_loop.create_proxy();
+  let mut init = false;
+  let mut timer = false;
+  let mut user_event = None;
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, _, control_flow| {
+    if Instant::now() > deadline {
+      panic!("the headless event loop didn't deliver its events in time");
+    }
+    match event {
+      Event::NewEvents(StartCause::Init) => {
+        init = true;
+        *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(50));
+      }
+      Event::NewEvents(StartCause::ResumeTimeReached { .. }) if !timer => {
+        timer = true;
+        proxy.send_event(7).unwrap();
+        *control_flow = ControlFlow::Wait;
+      }
+      Event::UserEvent(value) => user_event = Some(value),
+      Event::MainEventsCleared if user_event.is_some() => *control_flow = ControlFlow::Exit,
+      _ => (),
+    }
+  });
+
+  assert!(init, "no init event");
+  assert!(timer, "the timer didn't fire");
+  assert_eq!(user_event, Some(7));
+  drop(window);
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -0,0 +1,113 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that hiding and showing a maximized or fullscreen window keeps it in that state, and
+//! that the cycles don't report any state change.
+//!
+//! This needs a real desktop session with a window manager and runs on the main thread, so it
+//! uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::{Fullscreen, Window, WindowBuilder},
+  };
+
+  const CYCLES: usize = 5;
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping hide_show_state: no display");
+    return;
+  }
+
+  /// Runs the event loop until no event arrived for a while, and returns the maximized and
+  /// fullscreen changes reported meanwhile.
+  fn settle(event_loop: &mut EventLoop<()>) -> Vec<String> {
+    let mut changes = Vec::new();
+    let mut last_event = Instant::now();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    event_loop.run_return(|event, _, control_flow| {
+      *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(10));
+      match event {
+        Event::WindowEvent { event, .. } => {
+          match event {
+            WindowEvent::Maximized(_) | WindowEvent::FullscreenChanged(_) => {
+              changes.push(format!("{:?}", event))
+            }
+            _ => (),
+          }
+          last_event = Instant::now();
+        }
+        Event::MainEventsCleared
+          if last_event.elapsed() > Duration::from_millis(1000) || Instant::now() > deadline =>
+        {
+          *control_flow = ControlFlow::Exit
+        }
+        _ => (),
+      }
+    });
+    changes
+  }
+
+  fn cycle(event_loop: &mut EventLoop<()>, window: &Window, check: impl Fn(&Window) -> bool) {
+    settle(event_loop);
+    assert!(check(window), "the window didn't reach the state");
+    for i in 0..CYCLES {
+      window.set_visible(false);
+      let hidden = settle(event_loop);
+      window.set_visible(true);
+      let shown = settle(event_loop);
+      assert!(window.is_visible());
+      assert!(check(window), "cycle {}: the state was lost", i);
+      assert!(
+        hidden.is_empty() && shown.is_empty(),
+        "cycle {}: spurious changes while hidden {:?} and shown {:?}",
+        i,
+        hidden,
+        shown
+      );
+    }
+  }
+
+  let mut event_loop = EventLoop::new();
+
+  let window = WindowBuilder::new()
+    .with_maximized(true)
+    .build(&event_loop)
+    .unwrap();
+  cycle(&mut event_loop, &window, Window::is_maximized);
+  drop(window);
+
+  let window = WindowBuilder::new()
+    .with_fullscreen(Some(Fullscreen::Borderless(None)))
+    .build(&event_loop)
+    .unwrap();
+  cycle(&mut event_loop, &window, |window| {
+    window.fullscreen().is_some()
+  });
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -0,0 +1,96 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that a window built while the event loop delivers `StartCause::Init` reports the
+//! requested size at the right scale factor right away, and that its first `Resized` has its
+//! final size rather than a placeholder.
+//!
+//! This needs a real desktop session, X11 or Wayland, and runs on the main thread, so it uses
+//! `harness = false`.
+
+#[cfg(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    dpi::LogicalSize,
+    event::{Event, StartCause, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::WindowBuilder,
+  };
+
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping init_window_geometry: no display");
+    return;
+  }
+
+  let mut event_loop = EventLoop::new();
+  let requested = LogicalSize::new(320, 240);
+
+  let mut window = None;
+  let mut first_resized = None;
+  let deadline = Instant::now() + Duration::from_secs(5);
+  event_loop.run_return(|event, target, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(50));
+    match event {
+      Event::NewEvents(StartCause::Init) => {
+        let built = WindowBuilder::new()
+          .with_inner_size(requested)
+          .build(target)
+          .unwrap();
+        let scale_factor = built.scale_factor();
+        assert!(
+          scale_factor.is_finite() && scale_factor >= 1.0,
+          "scale_factor() is {} before the window is mapped",
+          scale_factor
+        );
+        assert_eq!(
+          built.inner_size(),
+          requested.to_physical(scale_factor),
+          "inner_size() isn't the requested size before the window is mapped"
+        );
+        window = Some(built);
+      }
+      Event::WindowEvent {
+        event: WindowEvent::Resized(size),
+        ..
+      } => {
+        first_resized.get_or_insert(size);
+      }
+      Event::MainEventsCleared if first_resized.is_some() || Instant::now() > deadline => {
+        *control_flow = ControlFlow::Exit;
+      }
+      _ => (),
+    }
+  });
+
+  let window = window.expect("no StartCause::Init");
+  let size = first_resized.expect("no Resized event");
+  assert!(
+    size.width > 1 && size.height > 1,
+    "the first Resized has the placeholder size {:?}",
+    size
+  );
+  assert_eq!(
+    size,
+    requested.to_physical(window.scale_factor()),
+    "the first Resized doesn't have the requested size"
+  );
+  assert_eq!(window.inner_size(), size);
+}
+
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -0,0 +1,111 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `EventLoopExtInject::inject_input` types `SyntheticInput::Text` through `XTest`,
+//! pressing Shift for the characters of the upper level: the text of the `KeyboardInput` events
+//! of the focused window is the injected text.
+//!
+//! This needs a real X11 session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    dpi::{LogicalSize, PhysicalPosition},
+    event::{ElementState, Event, KeyEvent, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::{
+      inject::{EventLoopExtInject, SyntheticInput},
+      run_return::EventLoopExtRunReturn,
+    },
+    window::WindowBuilder,
+  };
+
+  // Wayland isn't supported, so only X11 can be tested.
+  if std::env::var_os("DISPLAY").is_none() {
+    eprintln!("skipping input_injection_x11: no X11 display");
+    return;
+  }
+  std::env::set_var("GDK_BACKEND", "x11");
+
+  // Letters and punctuation of both levels of the layouts of the Latin alphabet.
+  const TEXT: &str = "aB1!";
+
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new()
+    .with_inner_size(LogicalSize::new(400, 300))
+    .build(&event_loop)
+    .unwrap();
+
+  // Gives the window manager the time to map and place the window before pointing at it.
+  let point_at = Instant::now() + Duration::from_millis(500);
+  let deadline = point_at + Duration::from_secs(5);
+  let mut pointed = false;
+  let mut typed = false;
+  let mut text = String::new();
+  event_loop.run_return(|event, target, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(50));
+
+    match event {
+      Event::WindowEvent {
+        event:
+          WindowEvent::KeyboardInput {
+            event:
+              KeyEvent {
+                state: ElementState::Pressed,
+                text: Some(typed),
+                ..
+              },
+            ..
+          },
+        ..
+      } => {
+        text.push_str(typed);
+        if text.len() >= TEXT.len() {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      Event::MainEventsCleared if !pointed && Instant::now() > point_at => {
+        // Without a window manager, the focus follows the pointer.
+        let position = window.inner_position().unwrap();
+        let size = window.inner_size();
+        target
+          .inject_input(SyntheticInput::CursorMoved(PhysicalPosition::new(
+            (position.x + size.width as i32 / 2) as f64,
+            (position.y + size.height as i32 / 2) as f64,
+          )))
+          .unwrap();
+        window.set_focus();
+        pointed = true;
+      }
+      Event::MainEventsCleared if pointed && !typed && window.is_focused() => {
+        target
+          .inject_input(SyntheticInput::Text(TEXT.into()))
+          .unwrap();
+        typed = true;
+      }
+      Event::MainEventsCleared if Instant::now() > deadline => {
+        *control_flow = ControlFlow::Exit;
+      }
+      _ => (),
+    }
+  });
+
+  assert!(typed, "the window never got the focus");
+  assert_eq!(text, TEXT);
+}
+
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
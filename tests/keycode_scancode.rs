@@ -0,0 +1,184 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `KeyCode` maps to a scancode and back, and that `key_for_keycode` follows the
+//! active layout: US, German (Z and Y swapped) and French (AZERTY). On X11 the three layouts are
+//! loaded as the groups of one keymap and locked in turn, which `setxkbmap` is needed for. The
+//! layouts are skipped without it, or without an X11 display.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+use tao::keyboard::KeyCode;
+
+fn main() {
+  keycode_scancode_round_trip();
+  physical_keys_have_distinct_scancodes();
+  #[cfg(all(target_os = "linux", not(tao_headless)))]
+  layouts::check();
+}
+
+fn keycode_scancode_round_trip() {
+  // `KeyCode` names a physical key, so the keys that move around on German (QWERTZ) and
+  // French (AZERTY) layouts must map to the same scancode regardless of the active layout.
+  let codes = [
+    KeyCode::KeyA,
+    KeyCode::KeyM,
+    KeyCode::KeyQ,
+    KeyCode::KeyW,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit1,
+    KeyCode::Semicolon,
+    KeyCode::Comma,
+    KeyCode::Enter,
+    KeyCode::ShiftLeft,
+    KeyCode::ArrowLeft,
+  ];
+  for code in codes {
+    let scancode = code
+      .to_scancode()
+      .unwrap_or_else(|| panic!("{:?} has no scancode", code));
+    assert_eq!(KeyCode::from_scancode(scancode), code);
+  }
+}
+
+fn physical_keys_have_distinct_scancodes() {
+  let y = KeyCode::KeyY.to_scancode();
+  let z = KeyCode::KeyZ.to_scancode();
+  let q = KeyCode::KeyQ.to_scancode();
+  let a = KeyCode::KeyA.to_scancode();
+  assert_ne!(y, z);
+  assert_ne!(q, a);
+}
+
+#[cfg(all(target_os = "linux", not(tao_headless)))]
+mod layouts {
+  use std::{os::raw::c_uint, process::Command, ptr};
+  use tao::{
+    event_loop::EventLoop,
+    keyboard::{Key, KeyCode},
+  };
+  use x11_dl::xlib;
+
+  /// `XkbUseCoreKbd`.
+  const XKB_USE_CORE_KBD: c_uint = 0x0100;
+
+  /// The layouts in the order of their groups, with the characters of the keys they move around.
+  const LAYOUTS: [(&str, [(KeyCode, &str); 7]); 3] = [
+    (
+      "us",
+      [
+        (KeyCode::KeyQ, "q"),
+        (KeyCode::KeyA, "a"),
+        (KeyCode::KeyW, "w"),
+        (KeyCode::KeyZ, "z"),
+        (KeyCode::KeyY, "y"),
+        (KeyCode::KeyM, "m"),
+        (KeyCode::Semicolon, ";"),
+      ],
+    ),
+    (
+      "de",
+      [
+        (KeyCode::KeyQ, "q"),
+        (KeyCode::KeyA, "a"),
+        (KeyCode::KeyW, "w"),
+        (KeyCode::KeyZ, "y"),
+        (KeyCode::KeyY, "z"),
+        (KeyCode::KeyM, "m"),
+        (KeyCode::Semicolon, "ö"),
+      ],
+    ),
+    (
+      "fr",
+      [
+        (KeyCode::KeyQ, "a"),
+        (KeyCode::KeyA, "q"),
+        (KeyCode::KeyW, "z"),
+        (KeyCode::KeyZ, "w"),
+        (KeyCode::KeyY, "y"),
+        (KeyCode::KeyM, ","),
+        (KeyCode::Semicolon, "m"),
+      ],
+    ),
+  ];
+
+  /// Puts back the layouts of the display, also when a check fails.
+  struct RestoreLayouts {
+    layout: String,
+    variant: String,
+  }
+
+  impl Drop for RestoreLayouts {
+    fn drop(&mut self) {
+      let _ = Command::new("setxkbmap")
+        .args(["-layout", &self.layout, "-variant", &self.variant])
+        .status();
+    }
+  }
+
+  pub fn check() {
+    if std::env::var_os("DISPLAY").is_none() {
+      eprintln!("no X11 display, skipping the layouts");
+      return;
+    }
+    let query = match Command::new("setxkbmap").arg("-query").output() {
+      Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).into_owned(),
+      _ => {
+        eprintln!("`setxkbmap` is unavailable, skipping the layouts");
+        return;
+      }
+    };
+    let field = |name: &str| {
+      query
+        .lines()
+        .find_map(|line| line.strip_prefix(name))
+        .map_or_else(String::new, |value| value.trim().to_string())
+    };
+    let _restore = RestoreLayouts {
+      layout: field("layout:"),
+      variant: field("variant:"),
+    };
+
+    let layouts = LAYOUTS
+      .iter()
+      .map(|(layout, _)| *layout)
+      .collect::<Vec<_>>()
+      .join(",");
+    let status = Command::new("setxkbmap")
+      .args(["-layout", &layouts, "-variant", ",,"])
+      .status();
+    assert!(
+      status.map_or(false, |status| status.success()),
+      "failed to load the layouts {}",
+      layouts
+    );
+
+    // Created after the switch, so GDK loads the keymap with the three groups. The group is only
+    // read on X11.
+    std::env::set_var("GDK_BACKEND", "x11");
+    let event_loop = EventLoop::new();
+
+    let xlib = xlib::Xlib::open().expect("failed to load Xlib");
+    unsafe {
+      let display = (xlib.XOpenDisplay)(ptr::null());
+      assert!(!display.is_null(), "failed to open the X11 display");
+      for (group, (layout, keys)) in LAYOUTS.iter().enumerate() {
+        (xlib.XkbLockGroup)(display, XKB_USE_CORE_KBD, group as c_uint);
+        (xlib.XSync)(display, xlib::False);
+        for (code, character) in keys {
+          assert_eq!(
+            event_loop.key_for_keycode(*code),
+            Some(Key::Character(character)),
+            "{:?} on the {} layout",
+            code,
+            layout
+          );
+        }
+      }
+      (xlib.XkbLockGroup)(display, XKB_USE_CORE_KBD, 0);
+      (xlib.XCloseDisplay)(display);
+    }
+  }
+}
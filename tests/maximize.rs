@@ -0,0 +1,114 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that a window maximized before it is shown reports its final size once.
+//!
+//! This needs a real desktop session with a window manager and runs on the main thread, so it
+//! uses `harness = false`.
+
+#[cfg(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    dpi::PhysicalSize,
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::WindowBuilder,
+  };
+
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping maximize: no display");
+    return;
+  }
+
+  let mut event_loop = EventLoop::new();
+  let initial_size = PhysicalSize::new(400, 300);
+  let window = WindowBuilder::new()
+    .with_inner_size(initial_size)
+    .with_visible(false)
+    .build(&event_loop)
+    .unwrap();
+
+  window.set_maximized(true);
+  assert!(
+    window.is_maximized(),
+    "is_maximized() doesn't reflect the pending request"
+  );
+  window.set_visible(true);
+
+  let mut resized = Vec::new();
+  let mut maximized = None;
+  let deadline = Instant::now() + Duration::from_secs(5);
+  event_loop.run_return(|event, _, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(50));
+    match event {
+      Event::WindowEvent {
+        event: WindowEvent::Resized(size),
+        ..
+      } => resized.push(size),
+      Event::WindowEvent {
+        event: WindowEvent::Maximized(true),
+        ..
+      } => {
+        maximized.get_or_insert((resized.len(), Instant::now()));
+      }
+      Event::MainEventsCleared => {
+        // Give the window manager some time to send anything after the maximized state.
+        let settled = maximized.map_or(false, |(_, at): (usize, Instant)| {
+          at.elapsed() > Duration::from_millis(500)
+        });
+        if settled || Instant::now() > deadline {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      _ => (),
+    }
+  });
+
+  let (maximized_after, _) = maximized.expect("no Maximized(true) event");
+  assert!(window.is_maximized());
+  let size = window.inner_size();
+  let monitor = window.current_monitor().expect("no current monitor").size();
+
+  assert!(
+    maximized_after > 0,
+    "Maximized was emitted before the first Resized"
+  );
+  assert_eq!(
+    resized[0], size,
+    "the first Resized doesn't have the maximized size: {:?}",
+    resized
+  );
+  assert_eq!(
+    resized.iter().filter(|&&s| s == size).count(),
+    1,
+    "the final size was reported more than once: {:?}",
+    resized
+  );
+  assert!(
+    size.width > initial_size.width
+      && size.height > initial_size.height
+      && size.width <= monitor.width
+      && size.height <= monitor.height,
+    "maximized size {:?} doesn't fit the work area of the {:?} monitor",
+    size,
+    monitor
+  );
+}
+
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -0,0 +1,206 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks with synthesized input that `ModifiersChanged` comes right before the press of a
+//! modifier key and right after its release, and that `KeyEvent::modifiers` agrees with it.
+//!
+//! Synthesized input may not reach the window, e.g. without the accessibility permission on
+//! macOS or on Wayland, in which case the test is skipped. This needs a real desktop session and
+//! runs on the main thread, so it uses `harness = false`.
+
+#[cfg(all(
+  not(tao_headless),
+  any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  )
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    event::{ElementState, Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    keyboard::{KeyCode, ModifiersState},
+    platform::run_return::EventLoopExtRunReturn,
+    window::{Window, WindowBuilder},
+  };
+
+  #[derive(Debug, PartialEq)]
+  enum Recorded {
+    Modifiers(ModifiersState),
+    Key(KeyCode, ElementState, ModifiersState),
+  }
+
+  #[cfg(target_os = "windows")]
+  fn send_keys(_window: &Window) {
+    use windows::Win32::UI::Input::KeyboardAndMouse::*;
+
+    let key = |vk: VIRTUAL_KEY, scancode: u16, pressed: bool| INPUT {
+      r#type: INPUT_KEYBOARD,
+      Anonymous: INPUT_0 {
+        ki: KEYBDINPUT {
+          wVk: vk,
+          wScan: scancode,
+          dwFlags: if pressed {
+            KEYBD_EVENT_FLAGS(0)
+          } else {
+            KEYEVENTF_KEYUP
+          },
+          time: 0,
+          dwExtraInfo: 0,
+        },
+      },
+    };
+    let inputs = [
+      key(VK_LSHIFT, 0x2a, true),
+      key(VK_A, 0x1e, true),
+      key(VK_A, 0x1e, false),
+      key(VK_LSHIFT, 0x2a, false),
+    ];
+    unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+  }
+
+  #[cfg(target_os = "macos")]
+  fn send_keys(_window: &Window) {
+    use core_graphics::{
+      event::{CGEvent, CGEventFlags, CGEventTapLocation},
+      event_source::{CGEventSource, CGEventSourceStateID},
+    };
+
+    const SHIFT: u16 = 56;
+    const A: u16 = 0;
+    let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState).unwrap();
+    for (keycode, pressed, flags) in [
+      (SHIFT, true, CGEventFlags::CGEventFlagShift),
+      (A, true, CGEventFlags::CGEventFlagShift),
+      (A, false, CGEventFlags::CGEventFlagShift),
+      (SHIFT, false, CGEventFlags::CGEventFlagNull),
+    ] {
+      let event = CGEvent::new_keyboard_event(source.clone(), keycode, pressed).unwrap();
+      event.set_flags(flags);
+      event.post(CGEventTapLocation::HID);
+    }
+  }
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  fn send_keys(window: &Window) {
+    use gtk::{
+      gdk::{self, EventType, ModifierType},
+      prelude::WidgetExt,
+    };
+    use tao::platform::unix::WindowExtUnix;
+
+    const SHIFT_L: u32 = 0xffe1;
+    const A: u32 = 0x0041;
+    let gdk_window = window.gtk_window().window().unwrap();
+    for (keyval, event_type, modifiers) in [
+      (SHIFT_L, EventType::KeyPress, ModifierType::empty()),
+      (A, EventType::KeyPress, ModifierType::SHIFT_MASK),
+      (A, EventType::KeyRelease, ModifierType::SHIFT_MASK),
+      (SHIFT_L, EventType::KeyRelease, ModifierType::SHIFT_MASK),
+    ] {
+      gdk::test_simulate_key(&gdk_window, -1, -1, keyval, modifiers, event_type);
+    }
+  }
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping modifiers_order: no display");
+    return;
+  }
+
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+  window.set_focus();
+
+  let mut recorded = Vec::new();
+  let mut sent = false;
+  let mut done = false;
+  let start = Instant::now();
+  let deadline = start + Duration::from_secs(5);
+  event_loop.run_return(|event, _, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(10));
+    match event {
+      Event::WindowEvent {
+        event: WindowEvent::Focused(true),
+        ..
+      } if !sent => {
+        send_keys(&window);
+        sent = true;
+      }
+      Event::WindowEvent {
+        event: WindowEvent::ModifiersChanged(modifiers),
+        ..
+      } if sent => {
+        recorded.push(Recorded::Modifiers(modifiers));
+        // The release of Shift is the last synthesized input.
+        done = modifiers.is_empty() && recorded.iter().any(|r| matches!(r, Recorded::Key(..)));
+      }
+      Event::WindowEvent {
+        event: WindowEvent::KeyboardInput { event, .. },
+        ..
+      } if sent => {
+        recorded.push(Recorded::Key(
+          event.physical_key,
+          event.state,
+          event.modifiers,
+        ));
+      }
+      Event::MainEventsCleared => {
+        // Some window managers don't report the focus of a window that was just mapped.
+        if !sent && start.elapsed() > Duration::from_secs(1) {
+          send_keys(&window);
+          sent = true;
+        }
+        if done || Instant::now() > deadline {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      _ => (),
+    }
+  });
+
+  if !recorded.iter().any(|r| matches!(r, Recorded::Key(..))) {
+    eprintln!("skipping modifiers_order: the synthesized input wasn't delivered");
+    return;
+  }
+
+  // Losing or gaining the focus may report that no modifier is held.
+  while recorded.first() == Some(&Recorded::Modifiers(ModifiersState::empty())) {
+    recorded.remove(0);
+  }
+  eprintln!("modifiers_order: {:?}", recorded);
+
+  let shift = ModifiersState::SHIFT;
+  assert_eq!(
+    recorded,
+    [
+      Recorded::Modifiers(shift),
+      Recorded::Key(KeyCode::ShiftLeft, ElementState::Pressed, shift),
+      Recorded::Key(KeyCode::KeyA, ElementState::Pressed, shift),
+      Recorded::Key(KeyCode::KeyA, ElementState::Released, shift),
+      Recorded::Key(KeyCode::ShiftLeft, ElementState::Released, shift),
+      Recorded::Modifiers(ModifiersState::empty()),
+    ]
+  );
+}
+
+#[cfg(any(
+  tao_headless,
+  not(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  ))
+))]
+fn main() {}
@@ -0,0 +1,66 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `available_monitors` lists the primary monitor first and the others by position,
+//! that the order and the monitor ids are the same on every call, and that every monitor has a
+//! name.
+//!
+//! This needs a real desktop session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use tao::event_loop::EventLoop;
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping monitor_order: no display");
+    return;
+  }
+
+  let event_loop = EventLoop::<()>::new();
+  let monitors: Vec<_> = event_loop.available_monitors().collect();
+  assert!(!monitors.is_empty(), "no monitors");
+
+  if let Some(primary) = event_loop.primary_monitor() {
+    assert_eq!(monitors[0], primary, "the primary monitor isn't first");
+  }
+  for pair in monitors[1..].windows(2) {
+    let (a, b) = (pair[0].position(), pair[1].position());
+    assert!(
+      (a.x, a.y) <= (b.x, b.y),
+      "monitors aren't sorted by position"
+    );
+  }
+  for monitor in &monitors {
+    let name = monitor.name().expect("a connected monitor has a name");
+    assert!(!name.is_empty(), "empty monitor name");
+  }
+
+  let again: Vec<_> = event_loop.available_monitors().collect();
+  assert_eq!(monitors, again, "the order changed between calls");
+  assert_eq!(
+    monitors.iter().map(|m| m.id()).collect::<Vec<_>>(),
+    again.iter().map(|m| m.id()).collect::<Vec<_>>(),
+    "monitor ids changed between calls"
+  );
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
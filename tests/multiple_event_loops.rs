@@ -0,0 +1,115 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that an event loop runs on another thread, with its own windows and user events,
+//! while the one of the main thread exists, and that only the first receives the device events.
+//! On Linux, where GTK only runs on one thread, checks that `try_build` refuses to create it.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+#[cfg(all(not(tao_headless), target_os = "windows"))]
+fn main() {
+  use std::{
+    thread,
+    time::{Duration, Instant},
+  };
+  use tao::{
+    error::ExternalError,
+    event::Event,
+    event_loop::{ControlFlow, DeviceEventFilter, EventLoop, EventLoopBuilder},
+    platform::{run_return::EventLoopExtRunReturn, windows::EventLoopBuilderExtWindows},
+    window::WindowBuilder,
+  };
+
+  fn run_until_user_event(event_loop: &mut EventLoop<u32>) -> u32 {
+    let mut received = None;
+    let deadline = Instant::now() + Duration::from_secs(10);
+    event_loop.run_return(|event, _, control_flow| {
+      if Instant::now() > deadline {
+        panic!("the user event wasn't delivered in time");
+      }
+      *control_flow = ControlFlow::Wait;
+      if let Event::UserEvent(value) = event {
+        received = Some(value);
+        *control_flow = ControlFlow::Exit;
+      }
+    });
+    received.unwrap()
+  }
+
+  let mut main_loop = EventLoopBuilder::<u32>::with_user_event().build();
+  assert!(main_loop
+    .set_device_event_filter(DeviceEventFilter::Unfocused)
+    .is_ok());
+  let main_proxy = main_loop.create_proxy();
+
+  let helper = thread::spawn(move || {
+    let mut event_loop = EventLoopBuilder::<u32>::with_user_event()
+      .with_any_thread(true)
+      .build();
+    let window = WindowBuilder::new()
+      .with_title("helper")
+      .with_visible(false)
+      .build(&event_loop)
+      .unwrap();
+    assert!(matches!(
+      event_loop.set_device_event_filter(DeviceEventFilter::Never),
+      Err(ExternalError::NotSupported(_))
+    ));
+    event_loop.create_proxy().send_event(1).unwrap();
+    assert_eq!(run_until_user_event(&mut event_loop), 1);
+    assert_eq!(window.title(), "helper");
+    main_proxy.send_event(2).unwrap();
+  });
+
+  assert_eq!(run_until_user_event(&mut main_loop), 2);
+  helper.join().unwrap();
+}
+
+#[cfg(all(
+  not(tao_headless),
+  any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  )
+))]
+fn main() {
+  use std::thread;
+  use tao::{
+    error::ExternalError, event_loop::EventLoopBuilder, platform::unix::EventLoopBuilderExtUnix,
+  };
+
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping multiple_event_loops: no display");
+    return;
+  }
+
+  let _main_loop = EventLoopBuilder::new().build();
+  let helper = thread::spawn(|| {
+    EventLoopBuilder::new()
+      .with_any_thread(true)
+      .try_build()
+      .map(drop)
+  });
+  assert!(matches!(
+    helper.join().unwrap(),
+    Err(ExternalError::NotSupported(_))
+  ));
+}
+
+#[cfg(not(all(
+  not(tao_headless),
+  any(
+    target_os = "windows",
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  )
+)))]
+fn main() {}
@@ -0,0 +1,72 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `EventLoopProxy::try_send` stops at the capacity of the event loop, accepts
+//! events again once some are delivered, and reports a closed event loop. It uses a headless
+//! event loop, so it also runs without a display server on Linux.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    event::Event,
+    event_loop::{ControlFlow, EventLoopBuilder, TrySendError},
+    platform::run_return::EventLoopExtRunReturn,
+  };
+
+  let mut event_loop = EventLoopBuilder::<u32>::with_user_event()
+    .with_headless(true)
+    .with_user_event_capacity(2)
+    .build();
+  let proxy = event_loop.create_proxy();
+  assert_eq!(proxy.try_send(1), Ok(()));
+  assert_eq!(proxy.try_send(2), Ok(()));
+  assert_eq!(proxy.try_send(3), Err(TrySendError::Full(3)));
+
+  let mut received = Vec::new();
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, _, control_flow| {
+    if Instant::now() > deadline {
+      panic!("the user events weren't delivered in time");
+    }
+    *control_flow = ControlFlow::Wait;
+    match event {
+      Event::UserEvent(value) => {
+        received.push(value);
+        // The delivered event frees its slot.
+        if value == 1 {
+          assert_eq!(proxy.try_send(4), Ok(()));
+          assert_eq!(proxy.try_send(5), Err(TrySendError::Full(5)));
+        }
+      }
+      Event::MainEventsCleared if received.len() == 3 => *control_flow = ControlFlow::Exit,
+      _ => (),
+    }
+  });
+  assert_eq!(received, [1, 2, 4]);
+
+  drop(event_loop);
+  assert_eq!(proxy.try_send(6), Err(TrySendError::Closed(6)));
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
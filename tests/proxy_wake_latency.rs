@@ -0,0 +1,94 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that a user event sent from another thread wakes an event loop waiting in
+//! `ControlFlow::Wait` right away, with a p99 latency from `EventLoopProxy::send_event` to the
+//! event handler under a millisecond. It uses a headless event loop, so it also runs without a
+//! display server on Linux.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::{
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+  };
+  use tao::{
+    event::Event,
+    event_loop::{ControlFlow, EventLoopBuilder},
+    platform::run_return::EventLoopExtRunReturn,
+  };
+
+  const ITERATIONS: usize = 500;
+  const MAX_P99_LATENCY: Duration = Duration::from_millis(1);
+
+  // Headless, so that it also runs without a display server on Linux.
+  let mut event_loop = EventLoopBuilder::<Instant>::with_user_event()
+    .with_headless(true)
+    .build();
+  let proxy = event_loop.create_proxy();
+  let (ack_tx, ack_rx) = mpsc::channel();
+
+  let sender = thread::spawn(move || {
+    for _ in 0..ITERATIONS {
+      // Gives the event loop the time to park before each event.
+      thread::sleep(Duration::from_millis(2));
+      proxy.send_event(Instant::now()).unwrap();
+      ack_rx.recv().unwrap();
+    }
+  });
+
+  let mut latencies = Vec::with_capacity(ITERATIONS);
+  let deadline = Instant::now() + Duration::from_secs(30);
+  event_loop.run_return(|event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+    if Instant::now() > deadline {
+      panic!("the user events weren't delivered in time");
+    }
+    if let Event::UserEvent(sent) = event {
+      latencies.push(sent.elapsed());
+      ack_tx.send(()).unwrap();
+      if latencies.len() == ITERATIONS {
+        *control_flow = ControlFlow::Exit;
+      }
+    }
+  });
+  sender.join().unwrap();
+
+  latencies.sort();
+  let p99 = latencies[ITERATIONS * 99 / 100];
+  eprintln!(
+    "proxy_wake_latency: median {:?}, p99 {:?}, max {:?}",
+    latencies[ITERATIONS / 2],
+    p99,
+    latencies[ITERATIONS - 1]
+  );
+  assert!(
+    p99 < MAX_P99_LATENCY,
+    "p99 wake latency {:?} isn't under {:?}",
+    p99,
+    MAX_P99_LATENCY
+  );
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
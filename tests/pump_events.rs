@@ -0,0 +1,130 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `EventLoopExtPumpEvents::pump_events` runs one iteration per call: with the
+//! pending events, after waiting for one, or right away with a zero timeout, and that an exit
+//! delivers `LoopDestroyed` once before the next call starts over. It uses a headless event loop,
+//! so it also runs without a display server on Linux.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::{thread, time::Duration};
+  use tao::{
+    event::{Event, StartCause},
+    event_loop::{ControlFlow, EventLoopBuilder},
+    platform::pump_events::{EventLoopExtPumpEvents, PumpStatus},
+  };
+
+  #[derive(Debug, PartialEq)]
+  enum Recorded {
+    Init,
+    Poll,
+    WaitCancelled,
+    User(u32),
+    Cleared,
+    Destroyed,
+  }
+
+  let mut event_loop = EventLoopBuilder::<u32>::with_user_event()
+    .with_headless(true)
+    .build();
+  let proxy = event_loop.create_proxy();
+  let mut recorded = Vec::new();
+  let mut exit = false;
+  let mut pump = |timeout, recorded: &mut Vec<Recorded>, exit: bool| {
+    recorded.clear();
+    event_loop.pump_events(timeout, |event, target, control_flow| {
+      recorded.push(match event {
+        Event::NewEvents(StartCause::Init) => {
+          *control_flow = ControlFlow::Wait;
+          Recorded::Init
+        }
+        Event::NewEvents(StartCause::Poll) => Recorded::Poll,
+        Event::NewEvents(StartCause::WaitCancelled { .. }) => Recorded::WaitCancelled,
+        Event::NewEvents(cause) => panic!("unexpected start cause {:?}", cause),
+        Event::UserEvent(value) => Recorded::User(value),
+        Event::MainEventsCleared => {
+          if exit {
+            target.exit_with_code(5);
+          }
+          return;
+        }
+        Event::RedrawEventsCleared => Recorded::Cleared,
+        Event::LoopDestroyed => Recorded::Destroyed,
+        _ => return,
+      })
+    })
+  };
+
+  // The first call starts the event loop, without waiting.
+  let status = pump(None, &mut recorded, exit);
+  assert_eq!(status, PumpStatus::Continue);
+  assert_eq!(recorded, [Recorded::Init, Recorded::Cleared]);
+
+  // The pending events are dispatched right away.
+  proxy.send_event(1).unwrap();
+  let status = pump(None, &mut recorded, exit);
+  assert_eq!(status, PumpStatus::Continue);
+  assert_eq!(
+    recorded,
+    [Recorded::Poll, Recorded::User(1), Recorded::Cleared]
+  );
+
+  // Without any, the call waits for one.
+  let sender = thread::spawn(move || {
+    thread::sleep(Duration::from_millis(100));
+    proxy.send_event(2).unwrap();
+  });
+  let status = pump(None, &mut recorded, exit);
+  sender.join().unwrap();
+  assert_eq!(status, PumpStatus::Continue);
+  assert_eq!(
+    recorded,
+    [
+      Recorded::WaitCancelled,
+      Recorded::User(2),
+      Recorded::Cleared
+    ]
+  );
+
+  // A zero timeout runs an iteration without waiting.
+  let status = pump(Some(Duration::ZERO), &mut recorded, exit);
+  assert_eq!(status, PumpStatus::Continue);
+  assert_eq!(recorded, [Recorded::Poll, Recorded::Cleared]);
+
+  exit = true;
+  let status = pump(Some(Duration::ZERO), &mut recorded, exit);
+  assert_eq!(status, PumpStatus::Exit(5));
+  assert_eq!(
+    recorded,
+    [Recorded::Poll, Recorded::Cleared, Recorded::Destroyed]
+  );
+
+  // The next call starts over.
+  exit = false;
+  let status = pump(Some(Duration::ZERO), &mut recorded, exit);
+  assert_eq!(status, PumpStatus::Continue);
+  assert_eq!(recorded, [Recorded::Init, Recorded::Cleared]);
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -0,0 +1,117 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `Window::request_redraw` can be called from another thread, and that requests are
+//! coalesced into at most one `RedrawRequested` per window and iteration.
+//!
+//! This needs a real desktop session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::{
+    sync::{
+      atomic::{AtomicBool, AtomicUsize, Ordering},
+      Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+  };
+  use tao::{
+    event::Event,
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::{Window, WindowBuilder},
+  };
+
+  const REQUESTS: usize = 1000;
+  const INTERVAL: Duration = Duration::from_millis(1);
+
+  fn assert_send_sync<T: Send + Sync>() {}
+  assert_send_sync::<Window>();
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping redraw: no display");
+    return;
+  }
+
+  let mut event_loop = EventLoop::new();
+  let window = Arc::new(WindowBuilder::new().build(&event_loop).unwrap());
+
+  let sent = Arc::new(AtomicUsize::new(0));
+  let done = Arc::new(AtomicBool::new(false));
+  let requester = {
+    let window = window.clone();
+    let sent = sent.clone();
+    let done = done.clone();
+    thread::spawn(move || {
+      for _ in 0..REQUESTS {
+        window.request_redraw();
+        sent.fetch_add(1, Ordering::Relaxed);
+        thread::sleep(INTERVAL);
+      }
+      done.store(true, Ordering::Release);
+    })
+  };
+
+  let mut redraws = 0;
+  let mut redraws_this_iteration = 0;
+  let mut redrawn_after_done = false;
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, _, control_flow| {
+    // The loop only wakes up for events, so every redraw comes from the other thread or the OS.
+    *control_flow = ControlFlow::Wait;
+    match event {
+      Event::NewEvents(_) => redraws_this_iteration = 0,
+      Event::RedrawRequested(id) => {
+        assert_eq!(id, window.id());
+        redraws_this_iteration += 1;
+        assert_eq!(
+          redraws_this_iteration, 1,
+          "the window was redrawn more than once in a single iteration"
+        );
+        redraws += 1;
+        if done.load(Ordering::Acquire) {
+          redrawn_after_done = true;
+        }
+      }
+      Event::RedrawEventsCleared => {
+        if redrawn_after_done {
+          *control_flow = ControlFlow::Exit;
+        } else if Instant::now() > deadline {
+          panic!("the last redraw requests weren't delivered in time");
+        } else if done.load(Ordering::Acquire) {
+          // Wake up again in case the last request was delivered before `done` was set.
+          window.request_redraw();
+        }
+      }
+      _ => (),
+    }
+  });
+  requester.join().unwrap();
+
+  let sent = sent.load(Ordering::Relaxed);
+  eprintln!("redraw: {} requests, {} redraws", sent, redraws);
+  assert_eq!(sent, REQUESTS);
+  assert!(redraws > 0, "no redraw was delivered");
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -0,0 +1,94 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `Window::request_redraw_synced` paces the redraws of a shown window with the
+//! frame clock: an event loop that polls and asks for a synced redraw after each one gets about
+//! as many redraws as the monitor has refreshes, not as many as it has iterations.
+//!
+//! This needs a real X11 session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    dpi::PhysicalSize,
+    event::Event,
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::WindowBuilder,
+  };
+
+  if std::env::var_os("DISPLAY").is_none() {
+    eprintln!("skipping redraw_synced_x11: no X11 display");
+    return;
+  }
+  std::env::set_var("GDK_BACKEND", "x11");
+
+  const MEASURED: Duration = Duration::from_secs(1);
+
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new()
+    .with_inner_size(PhysicalSize::new(200, 100))
+    .build(&event_loop)
+    .unwrap();
+
+  // Gives the window manager the time to map the window, for the frame clock to tick.
+  let start = Instant::now() + Duration::from_millis(500);
+  let mut requested = false;
+  let mut iterations = 0u32;
+  let mut redraws = 0u32;
+  event_loop.run_return(|event, _, control_flow| {
+    *control_flow = ControlFlow::Poll;
+    let now = Instant::now();
+    match event {
+      Event::RedrawRequested(id) if now > start => {
+        assert_eq!(id, window.id());
+        redraws += 1;
+        requested = false;
+      }
+      Event::MainEventsCleared if now > start + MEASURED => {
+        *control_flow = ControlFlow::Exit;
+      }
+      Event::MainEventsCleared if now > start => {
+        iterations += 1;
+        if !requested {
+          // Coalesced into one redraw.
+          window.request_redraw_synced();
+          window.request_redraw_synced();
+          requested = true;
+        }
+      }
+      _ => (),
+    }
+  });
+
+  let refresh_rate = window
+    .current_monitor()
+    .and_then(|monitor| monitor.refresh_rate_millihertz())
+    .unwrap_or(60_000);
+  let refreshes = (refresh_rate as u128 * MEASURED.as_millis() / 1_000_000) as u32;
+  assert!(redraws > 0, "no synced redraw was delivered");
+  // Some slack for the redraws GTK asks for itself, like the first expose of the window.
+  assert!(
+    redraws <= refreshes * 3 / 2 + 5,
+    "{} redraws in {} iterations for {} refreshes",
+    redraws,
+    iterations,
+    refreshes
+  );
+}
+
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -0,0 +1,134 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that reopening the application, as clicking its Dock icon does, deminiaturizes the
+//! window focused last with `ReopenBehavior::ShowLastFocusedWindow`, falling back to another
+//! window once that one is destroyed, and that the Apple event handler can skip it.
+//!
+//! This needs a window server and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(target_os = "macos")]
+fn main() {
+  use objc::{
+    class, msg_send,
+    runtime::{Object, BOOL, NO, YES},
+    sel, sel_impl,
+  };
+  use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+  };
+  use tao::{
+    event::{Event, StartCause, WindowEvent},
+    event_loop::{ControlFlow, EventLoopBuilder},
+    platform::{
+      macos::{
+        AppleEvent, EventLoopBuilderExtMacOS, EventLoopWindowTargetExtMacOS, ReopenBehavior,
+      },
+      run_return::EventLoopExtRunReturn,
+    },
+    window::WindowBuilder,
+  };
+
+  /// Reopens the application the way AppKit does, and returns whether AppKit should reopen it
+  /// itself.
+  fn reopen() -> bool {
+    unsafe {
+      let app: *mut Object = msg_send![class!(NSApplication), sharedApplication];
+      let delegate: *mut Object = msg_send![app, delegate];
+      let handled: BOOL =
+        msg_send![delegate, applicationShouldHandleReopen: app hasVisibleWindows: NO];
+      handled == YES
+    }
+  }
+
+  #[derive(Debug, PartialEq)]
+  enum Step {
+    Focusing,
+    Minimizing,
+    Restoring,
+  }
+
+  let skip = Rc::new(Cell::new(false));
+  let skip_ = skip.clone();
+  let mut event_loop = EventLoopBuilder::new()
+    .with_apple_event_handler(move |event| {
+      !(skip_.get() && matches!(event, AppleEvent::Reopen { .. }))
+    })
+    .build();
+
+  let mut first = None;
+  let mut second = None;
+  let mut step = Step::Focusing;
+  let mut second_focused = false;
+  let mut reopened = false;
+  let started = Instant::now();
+  let deadline = started + Duration::from_secs(10);
+  event_loop.run_return(|event, target, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(50));
+    if Instant::now() > deadline {
+      panic!("stuck at {:?}", step);
+    }
+
+    match event {
+      Event::NewEvents(StartCause::Init) => {
+        target.set_default_reopen_behavior(ReopenBehavior::ShowLastFocusedWindow);
+        first = Some(
+          WindowBuilder::new()
+            .with_title("first")
+            .build(target)
+            .unwrap(),
+        );
+        let window = WindowBuilder::new()
+          .with_title("second")
+          .build(target)
+          .unwrap();
+        window.set_focus();
+        second = Some(window);
+      }
+      Event::WindowEvent {
+        event: WindowEvent::Focused(true),
+        window_id,
+        ..
+      } if Some(window_id) == second.as_ref().map(|window| window.id()) => {
+        second_focused = true;
+      }
+      Event::Reopen {
+        has_visible_windows,
+      } => {
+        assert!(!has_visible_windows);
+        reopened = true;
+      }
+      Event::MainEventsCleared => {
+        let first = first.as_ref().unwrap();
+        match step {
+          // The second window may never become key in a session without an active user, the
+          // first one is restored either way.
+          Step::Focusing if second_focused || Instant::now() > started + Duration::from_secs(2) => {
+            // The window focused last is destroyed before the application is reopened.
+            first.set_minimized(true);
+            second = None;
+            step = Step::Minimizing;
+          }
+          Step::Minimizing if first.is_minimized() => {
+            skip.set(true);
+            assert!(reopen(), "the skipped reopen was handled by Tao");
+            assert!(first.is_minimized(), "the skipped reopen restored a window");
+            skip.set(false);
+            assert!(!reopen(), "the reopen wasn't handled by Tao");
+            step = Step::Restoring;
+          }
+          Step::Restoring if !first.is_minimized() && reopened => {
+            *control_flow = ControlFlow::Exit;
+          }
+          _ => (),
+        }
+      }
+      _ => (),
+    }
+  });
+}
+
+#[cfg(not(target_os = "macos"))]
+fn main() {}
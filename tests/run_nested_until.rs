@@ -0,0 +1,149 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `EventLoopWindowTarget::run_nested_until` delivers the events of the nested loop
+//! to its handler in full iterations, redraws included, while the event handler waits, that it
+//! blocks until another thread sends an event, that it polls the spawned futures and frees the
+//! slots of the user events it delivers, and that an exit asked by the nested handler ends both
+//! loops. It uses a headless event loop, so it also runs without a display server on Linux.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::{
+    cell::Cell,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+  };
+  use tao::{
+    event::{Event, StartCause},
+    event_loop::{ControlFlow, EventLoopBuilder, TrySendError},
+    platform::{executor::EventLoopExtAsync, run_return::EventLoopExtRunReturn},
+    window::WindowBuilder,
+  };
+
+  // Headless, so that it also runs without a display server on Linux.
+  let mut event_loop = EventLoopBuilder::<u32>::with_user_event()
+    .with_headless(true)
+    .with_user_event_capacity(1)
+    .build();
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+  let window_id = window.id();
+  let proxy = event_loop.create_proxy();
+
+  let in_nested = Cell::new(false);
+  let mut exited_nested = false;
+  let deadline = Instant::now() + Duration::from_secs(10);
+  let exit_code = event_loop.run_return(|event, target, control_flow| {
+    assert!(
+      !in_nested.get(),
+      "the event handler was called from the nested loop"
+    );
+    if Instant::now() > deadline {
+      panic!("the nested loops didn't end in time");
+    }
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::NewEvents(StartCause::Init) => {
+        let polled = Rc::new(Cell::new(false));
+        let polled_ = polled.clone();
+        target.spawn(async move { polled_.set(true) });
+        assert_eq!(proxy.try_send(1), Ok(()));
+        assert_eq!(proxy.try_send(2), Err(TrySendError::Full(2)));
+        window.request_redraw();
+
+        let mut events = Vec::new();
+        let done = Cell::new(false);
+        let mut sender = None;
+        in_nested.set(true);
+        let result = target.run_nested_until(
+          || done.get(),
+          |event, _, _| {
+            match event {
+              // Its slot is free once it's delivered, and the nested loop waits for the next one.
+              Event::UserEvent(1) => {
+                let proxy = proxy.clone();
+                sender = Some(thread::spawn(move || {
+                  thread::sleep(Duration::from_millis(50));
+                  assert_eq!(proxy.try_send(2), Ok(()));
+                }));
+              }
+              Event::UserEvent(2) => done.set(true),
+              _ => (),
+            }
+            events.push(event.to_static().unwrap());
+          },
+        );
+        in_nested.set(false);
+        assert!(result.is_ok());
+        sender.unwrap().join().unwrap();
+        assert!(polled.get(), "the spawned future wasn't polled");
+
+        let position = |wanted: &Event<'static, u32>| {
+          events
+            .iter()
+            .position(|event| event == wanted)
+            .unwrap_or_else(|| panic!("{:?} wasn't delivered to the nested loop", wanted))
+        };
+        let first = position(&Event::UserEvent(1));
+        let redraw = position(&Event::RedrawRequested(window_id));
+        let second = position(&Event::UserEvent(2));
+        assert!(matches!(events[0], Event::NewEvents(_)));
+        assert!(first < redraw && redraw < second);
+        // A new iteration started once the second user event arrived.
+        assert!(events[redraw..second]
+          .iter()
+          .any(|event| matches!(event, Event::NewEvents(_))));
+        assert_eq!(
+          events
+            .iter()
+            .filter(|event| matches!(event, Event::NewEvents(_)))
+            .count(),
+          events
+            .iter()
+            .filter(|event| matches!(event, Event::RedrawEventsCleared))
+            .count(),
+          "an iteration of the nested loop didn't end"
+        );
+        // Delivered, so its slot is free again.
+        assert_eq!(proxy.try_send(3), Ok(()));
+      }
+      Event::UserEvent(3) => {
+        in_nested.set(true);
+        let result = target.run_nested_until(
+          || false,
+          |_, _, control_flow| *control_flow = ControlFlow::ExitWithCode(7),
+        );
+        in_nested.set(false);
+        assert!(result.is_ok());
+        exited_nested = true;
+      }
+      _ => (),
+    }
+  });
+  assert!(exited_nested);
+  assert_eq!(exit_code, 7);
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -27,3 +27,15 @@ fn ids_send() {
   needs_send::<tao::event::DeviceId>();
   needs_send::<tao::monitor::MonitorHandle>();
 }
+
+#[test]
+fn power_assertion_send() {
+  // ensures that `PowerAssertion` implements `Send`, so it can be released from any thread
+  needs_send::<tao::power::PowerAssertion>();
+}
+
+#[test]
+fn notification_send() {
+  // ensures that `Notification` implements `Send`, so it can be closed from any thread
+  needs_send::<tao::notification::Notification>();
+}
@@ -4,20 +4,52 @@
 
 #![cfg(feature = "serde")]
 
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt::Debug;
 use tao::{
-  dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize},
-  event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase},
+  dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Rect},
+  event::{ElementState, MouseButton, MouseScrollDelta, OpenedItem, TouchPhase},
   keyboard::{Key, KeyCode, KeyLocation, ModifiersState},
-  window::CursorIcon,
+  window::{CursorIcon, DropFormat, Theme, WindowState},
 };
 
 #[allow(dead_code)]
 fn needs_serde<S: Serialize + Deserialize<'static>>() {}
 
+#[derive(Serialize, Deserialize)]
+struct Wrapper<T> {
+  value: T,
+}
+
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: T) -> String {
+  let serialized = toml::to_string(&Wrapper { value: &value }).unwrap();
+  let Wrapper {
+    value: deserialized,
+  } = toml::from_str::<Wrapper<T>>(&serialized).unwrap();
+  assert_eq!(deserialized, value);
+  serialized
+}
+
 #[test]
 fn window_serde() {
   needs_serde::<CursorIcon>();
+  needs_serde::<Theme>();
+  needs_serde::<WindowState>();
+  needs_serde::<DropFormat>();
+}
+
+#[test]
+fn window_round_trip() {
+  assert_eq!(round_trip(CursorIcon::Hand), "value = \"Hand\"\n");
+  assert_eq!(round_trip(Theme::Dark), "value = \"Dark\"\n");
+  round_trip(WindowState {
+    position: PhysicalPosition::new(-1920, 40),
+    size: PhysicalSize::new(800, 600),
+    outer_size: PhysicalSize::new(820, 640),
+    monitor: Some(0x1234_5678),
+    maximized: true,
+    fullscreen: false,
+  });
 }
 
 #[test]
@@ -26,12 +58,25 @@ fn events_serde() {
   needs_serde::<ElementState>();
   needs_serde::<MouseButton>();
   needs_serde::<MouseScrollDelta>();
+  needs_serde::<OpenedItem>();
   needs_serde::<Key>();
   needs_serde::<KeyCode>();
   needs_serde::<KeyLocation>();
   needs_serde::<ModifiersState>();
 }
 
+#[test]
+fn keyboard_round_trip() {
+  assert_eq!(round_trip(KeyCode::KeyA), "value = \"KeyA\"\n");
+  assert_eq!(round_trip(KeyCode::ArrowLeft), "value = \"ArrowLeft\"\n");
+  assert_eq!(round_trip(KeyLocation::Numpad), "value = \"Numpad\"\n");
+  round_trip(ModifiersState::SHIFT | ModifiersState::SUPER);
+  round_trip(ModifiersState::empty());
+
+  let modifiers: Wrapper<ModifiersState> = toml::from_str("[value]\ncontrol_key = true").unwrap();
+  assert_eq!(modifiers.value, ModifiersState::CONTROL);
+}
+
 #[test]
 fn dpi_serde() {
   needs_serde::<LogicalPosition<f64>>();
@@ -39,4 +84,16 @@ fn dpi_serde() {
   needs_serde::<PhysicalPosition<f64>>();
   needs_serde::<LogicalSize<f64>>();
   needs_serde::<PhysicalSize<u32>>();
+  needs_serde::<Rect>();
+}
+
+#[test]
+fn dpi_round_trip() {
+  round_trip(LogicalPosition::new(1.5, -2.0));
+  round_trip(PhysicalPosition::new(-10, 20));
+  round_trip(LogicalSize::new(800.0, 600.0));
+  assert_eq!(
+    round_trip(PhysicalSize::new(1920_u32, 1080_u32)),
+    "[value]\nwidth = 1920\nheight = 1080\n"
+  );
 }
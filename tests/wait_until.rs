@@ -0,0 +1,91 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `ControlFlow::WaitUntil` resumes close to the requested time.
+//!
+//! This needs a real desktop session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    event::{Event, StartCause},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+  };
+
+  const ITERATIONS: usize = 1000;
+  const WAIT: Duration = Duration::from_millis(3);
+  const MAX_P95_ERROR: Duration = Duration::from_millis(2);
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping wait_until: no display");
+    return;
+  }
+
+  let mut event_loop = EventLoop::new();
+  let mut errors = Vec::with_capacity(ITERATIONS);
+
+  event_loop.run_return(|event, _, control_flow| match event {
+    Event::NewEvents(StartCause::Init) => {
+      *control_flow = ControlFlow::WaitUntil(Instant::now() + WAIT);
+    }
+    Event::NewEvents(StartCause::ResumeTimeReached {
+      requested_resume,
+      resumed,
+      ..
+    }) => {
+      let now = Instant::now();
+      assert!(
+        resumed >= requested_resume,
+        "resumed {:?} before the requested time",
+        requested_resume - resumed
+      );
+      assert!(resumed <= now, "resumed {:?} in the future", resumed - now);
+
+      errors.push(resumed - requested_resume);
+      *control_flow = if errors.len() == ITERATIONS {
+        ControlFlow::Exit
+      } else {
+        ControlFlow::WaitUntil(now + WAIT)
+      };
+    }
+    _ => (),
+  });
+
+  errors.sort();
+  let p95 = errors[ITERATIONS * 95 / 100];
+  eprintln!(
+    "wait_until: median {:?}, p95 {:?}, max {:?}",
+    errors[ITERATIONS / 2],
+    p95,
+    errors[ITERATIONS - 1]
+  );
+  assert!(
+    p95 <= MAX_P95_ERROR,
+    "p95 wake-up error {:?} exceeds {:?}",
+    p95,
+    MAX_P95_ERROR
+  );
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
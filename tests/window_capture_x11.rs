@@ -0,0 +1,100 @@
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `EventLoopWindowTarget::request_window_capture` delivers the client area of a
+//! window, filled with its background color, as `Event::CaptureCompleted`, and that the capture
+//! of a rectangle of it has the size of the rectangle.
+//!
+//! This needs a real X11 session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    dpi::{PhysicalPosition, PhysicalSize, Rect},
+    event::Event,
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::{Color, WindowBuilder},
+  };
+
+  // Wayland isn't supported, so only X11 can be tested.
+  if std::env::var_os("DISPLAY").is_none() {
+    eprintln!("skipping window_capture_x11: no X11 display");
+    return;
+  }
+  std::env::set_var("GDK_BACKEND", "x11");
+
+  let color = Color::new(200, 40, 90, 255);
+  let mut event_loop = EventLoop::new();
+  let window = WindowBuilder::new()
+    .with_inner_size(PhysicalSize::new(200, 100))
+    .with_background_color(Some(color))
+    .build(&event_loop)
+    .unwrap();
+  let rect = Rect::new(PhysicalPosition::new(10, 20), PhysicalSize::new(30, 40));
+
+  // Gives the window manager the time to map the window, and GTK to draw it, before capturing.
+  let capture_at = Instant::now() + Duration::from_millis(500);
+  let deadline = capture_at + Duration::from_secs(5);
+  let mut requested = Vec::new();
+  let mut completed = Vec::new();
+  event_loop.run_return(|event, target, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(50));
+
+    match event {
+      Event::CaptureCompleted { id, image } => {
+        completed.push((id, image));
+        if completed.len() == requested.len() {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      Event::MainEventsCleared if requested.is_empty() && Instant::now() > capture_at => {
+        requested.push(target.request_window_capture(&window, None));
+        requested.push(target.request_window_capture(&window, Some(rect)));
+      }
+      Event::MainEventsCleared if Instant::now() > deadline => {
+        *control_flow = ControlFlow::Exit;
+      }
+      _ => (),
+    }
+  });
+
+  assert!(!requested.is_empty(), "the captures weren't requested");
+  assert_eq!(
+    completed.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+    requested,
+    "the captures weren't delivered in order"
+  );
+  let image = completed[0].1.as_ref().expect("the window wasn't captured");
+  assert_eq!(image.data.len(), (image.stride * image.height) as usize);
+  let size = window.inner_size();
+  assert_eq!((image.width, image.height), (size.width, size.height));
+  let center = (image.height / 2 * image.stride + image.width / 2 * 4) as usize;
+  assert_eq!(
+    &image.data[center..center + 4],
+    &[color.r, color.g, color.b, color.a],
+    "the center of the capture isn't the background color"
+  );
+
+  let image = completed[1]
+    .1
+    .as_ref()
+    .expect("the rectangle wasn't captured");
+  assert_eq!((image.width, image.height), (30, 40));
+}
+
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
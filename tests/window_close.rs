@@ -0,0 +1,122 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `Window::close` goes through `CloseRequested` and can be vetoed, that `Destroyed`
+//! is the last event of a window even with events still pending when it's dropped, and that the
+//! id of a destroyed window isn't given to a new one.
+//!
+//! This needs a real desktop session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::WindowBuilder,
+  };
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping window_close: no display");
+    return;
+  }
+
+  let mut event_loop = EventLoop::new();
+  let first = WindowBuilder::new().build(&event_loop).unwrap();
+  let first_id = first.id();
+  first.close();
+
+  let mut first = Some(first);
+  let mut second = None;
+  let mut close_requests = 0;
+  let mut first_destroyed = false;
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, event_loop, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(100));
+    if Instant::now() > deadline {
+      panic!("the windows weren't closed in time");
+    }
+
+    match event {
+      Event::WindowEvent {
+        window_id, event, ..
+      } if window_id == first_id => {
+        assert!(
+          !first_destroyed,
+          "{:?} was delivered after `Destroyed`",
+          event
+        );
+        match event {
+          WindowEvent::CloseRequested => {
+            close_requests += 1;
+            let window = first
+              .as_ref()
+              .expect("the dropped window was requested to close");
+            if close_requests == 1 {
+              // Veto, the window must stay open until it's asked again.
+              window.close();
+            } else {
+              // Leave a redraw pending to check it isn't delivered after `Destroyed`.
+              window.request_redraw();
+              first = None;
+            }
+          }
+          WindowEvent::Destroyed => {
+            assert!(
+              first.is_none(),
+              "the window was destroyed while still alive"
+            );
+            first_destroyed = true;
+
+            let window = WindowBuilder::new().build(event_loop).unwrap();
+            assert_ne!(
+              window.id(),
+              first_id,
+              "the id of a destroyed window was reused"
+            );
+            window.close();
+            second = Some(window);
+          }
+          _ => (),
+        }
+      }
+      Event::RedrawRequested(window_id) if window_id == first_id => {
+        assert!(!first_destroyed, "a redraw was delivered after `Destroyed`");
+      }
+      Event::WindowEvent {
+        window_id,
+        event: WindowEvent::CloseRequested,
+        ..
+      } if second.as_ref().map(|w| w.id()) == Some(window_id) => second = None,
+      Event::WindowEvent {
+        event: WindowEvent::Destroyed,
+        ..
+      } if first_destroyed => *control_flow = ControlFlow::Exit,
+      _ => (),
+    }
+  });
+
+  assert_eq!(close_requests, 2);
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
@@ -0,0 +1,109 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `EventLoopWindowTarget::window_from_id` finds the windows of the event loop while
+//! the handler runs, that the window it returns is the one the application built, and that it
+//! doesn't find a window anymore once it's dropped, before its `Destroyed` is delivered.
+//!
+//! This runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    event::{Event, StartCause, WindowEvent},
+    event_loop::{ControlFlow, EventLoopBuilder},
+    platform::run_return::EventLoopExtRunReturn,
+    window::{WindowBuilder, WindowId},
+  };
+
+  // Headless, so that it also runs without a display server on Linux.
+  let mut event_loop = EventLoopBuilder::new().with_headless(true).build();
+  let first = WindowBuilder::new()
+    .with_title("first")
+    .build(&event_loop)
+    .unwrap();
+  let second = WindowBuilder::new()
+    .with_title("second")
+    .build(&event_loop)
+    .unwrap();
+  let first_id = first.id();
+  let second_id = second.id();
+  assert!(
+    event_loop.window_from_id(first_id).is_none(),
+    "a window was lent out before the event loop ran"
+  );
+
+  let mut first = Some(first);
+  let mut first_destroyed = false;
+  let deadline = Instant::now() + Duration::from_secs(10);
+  event_loop.run_return(|event, target, control_flow| {
+    *control_flow = ControlFlow::Wait;
+    if Instant::now() > deadline {
+      panic!("the window wasn't destroyed in time");
+    }
+
+    match event {
+      Event::NewEvents(StartCause::Init) => {
+        let window = target.window_from_id(first_id).expect("first window");
+        assert_eq!(window.id(), first_id);
+        assert_eq!(window.title(), "first");
+        window.set_title("renamed");
+        assert_eq!(first.as_ref().unwrap().title(), "renamed");
+        assert_eq!(
+          target
+            .window_from_id(second_id)
+            .map(|window| window.title()),
+          Some("second".to_string())
+        );
+        assert!(target
+          .window_from_id(WindowId::from_raw(first_id.into_raw().wrapping_add(1000)))
+          .is_none());
+        assert!(
+          target.clone().window_from_id(first_id).is_none(),
+          "a clone of the target lent out a window"
+        );
+
+        let window = target.window_from_id(first_id).expect("first window");
+        first = None;
+        // Still valid until the handler returns, even though the application dropped it.
+        assert_eq!(window.id(), first_id);
+        assert!(target.window_from_id(first_id).is_none());
+      }
+      Event::WindowEvent {
+        window_id,
+        event: WindowEvent::Destroyed,
+        ..
+      } if window_id == first_id => {
+        assert!(target.window_from_id(first_id).is_none());
+        assert!(target.window_from_id(second_id).is_some());
+        first_destroyed = true;
+        *control_flow = ControlFlow::Exit;
+      }
+      _ => (),
+    }
+  });
+
+  assert!(first_destroyed);
+  drop(second);
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
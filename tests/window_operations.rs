@@ -0,0 +1,95 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks which window operations report `NotSupported` and which succeed, on X11 and Wayland,
+//! and that a fullscreen window refuses to be resized.
+//!
+//! This needs a real desktop session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(all(
+  not(tao_headless),
+  any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  )
+))]
+fn main() {
+  use tao::{
+    dpi::{LogicalPosition, LogicalSize},
+    error::{ExternalError, WindowOperationError},
+    event_loop::EventLoop,
+    platform::unix::EventLoopWindowTargetExtUnix,
+    window::{CursorGrabMode, Fullscreen, WindowBuilder},
+  };
+
+  if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+    eprintln!("skipping window_operations: no display");
+    return;
+  }
+
+  let event_loop = EventLoop::new();
+  let window = WindowBuilder::new().build(&event_loop).unwrap();
+  let wayland = event_loop.is_wayland();
+
+  // The cursor can only be grabbed on X11, releasing it always succeeds.
+  let cursor_grab = window.set_cursor_grab(CursorGrabMode::Confined);
+  if wayland {
+    assert!(matches!(cursor_grab, Err(ExternalError::NotSupported(_))));
+  }
+  assert!(window.set_cursor_grab(CursorGrabMode::None).is_ok());
+
+  let always_on_top = window.try_set_always_on_top(true);
+  let cursor_position = window.set_cursor_position(LogicalPosition::new(10.0, 10.0));
+  if wayland {
+    assert!(matches!(
+      always_on_top,
+      Err(WindowOperationError::NotSupported(_))
+    ));
+    assert!(matches!(
+      cursor_position,
+      Err(ExternalError::NotSupported(_))
+    ));
+  } else {
+    assert!(
+      always_on_top.is_ok(),
+      "always on top failed: {:?}",
+      always_on_top
+    );
+    assert!(
+      cursor_position.is_ok(),
+      "cursor warp failed: {:?}",
+      cursor_position
+    );
+  }
+
+  window
+    .try_set_inner_size(LogicalSize::new(300.0, 200.0))
+    .unwrap();
+  window
+    .try_set_fullscreen(Some(Fullscreen::Borderless(None)))
+    .unwrap();
+  assert!(matches!(
+    window.try_set_inner_size(LogicalSize::new(400.0, 300.0)),
+    Err(WindowOperationError::InvalidState(_))
+  ));
+  window.try_set_fullscreen(None).unwrap();
+  window
+    .try_set_inner_size(LogicalSize::new(400.0, 300.0))
+    .unwrap();
+}
+
+#[cfg(any(
+  tao_headless,
+  not(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+  ))
+))]
+fn main() {}
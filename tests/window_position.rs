@@ -0,0 +1,124 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `set_outer_position` followed by `outer_position` round-trips.
+//!
+//! This needs a real desktop session and runs on the main thread, so it uses `harness = false`.
+
+#[cfg(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+))]
+fn main() {
+  use std::time::{Duration, Instant};
+  use tao::{
+    dpi::PhysicalPosition,
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
+    window::WindowBuilder,
+  };
+
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  {
+    // Wayland has no global coordinate system, so only X11 can be tested.
+    if std::env::var_os("DISPLAY").is_none() {
+      eprintln!("skipping window_position: no X11 display");
+      return;
+    }
+    std::env::set_var("GDK_BACKEND", "x11");
+  }
+
+  let mut event_loop = EventLoop::new();
+  let target = PhysicalPosition::new(200, 150);
+
+  let mut windows: Vec<_> = [true, false]
+    .iter()
+    .map(|&decorations| {
+      let window = WindowBuilder::new()
+        .with_decorations(decorations)
+        .build(&event_loop)
+        .unwrap();
+      window.set_outer_position(target);
+      (decorations, window, None)
+    })
+    .collect();
+
+  let near = |a: PhysicalPosition<i32>, b: PhysicalPosition<i32>| {
+    (a.x - b.x).abs() <= 1 && (a.y - b.y).abs() <= 1
+  };
+
+  let deadline = Instant::now() + Duration::from_secs(5);
+  event_loop.run_return(|event, _, control_flow| {
+    *control_flow = ControlFlow::WaitUntil(Instant::now() + Duration::from_millis(50));
+
+    match event {
+      Event::WindowEvent {
+        window_id,
+        event: WindowEvent::Moved(position),
+        ..
+      } => {
+        for (_, window, moved) in windows.iter_mut() {
+          if window.id() == window_id {
+            *moved = Some(position);
+          }
+        }
+      }
+      Event::MainEventsCleared => {
+        let settled = windows
+          .iter()
+          .all(|(_, window, _)| near(window.outer_position().unwrap(), target));
+        if settled || Instant::now() > deadline {
+          *control_flow = ControlFlow::Exit;
+        }
+      }
+      _ => (),
+    }
+  });
+
+  for (decorations, window, moved) in &windows {
+    let outer = window.outer_position().unwrap();
+    let inner = window.inner_position().unwrap();
+    assert!(
+      near(outer, target),
+      "decorations: {}, outer_position {:?} != {:?}",
+      decorations,
+      outer,
+      target
+    );
+    // The client area is never above or left of the frame.
+    assert!(
+      inner.x >= outer.x - 1 && inner.y >= outer.y - 1,
+      "decorations: {}, inner_position {:?} is outside outer_position {:?}",
+      decorations,
+      inner,
+      outer
+    );
+    if let Some(moved) = moved {
+      assert!(
+        near(*moved, outer),
+        "decorations: {}, Moved({:?}) != outer_position {:?}",
+        decorations,
+        moved,
+        outer
+      );
+    }
+  }
+}
+
+#[cfg(not(any(
+  target_os = "windows",
+  target_os = "macos",
+  target_os = "linux",
+  target_os = "dragonfly",
+  target_os = "freebsd",
+  target_os = "netbsd",
+  target_os = "openbsd"
+)))]
+fn main() {}
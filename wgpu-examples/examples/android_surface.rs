@@ -0,0 +1,171 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Keeps a wgpu surface in step with the native window, so rendering survives going to the home
+//! screen and back on Android.
+//!
+//! Android destroys the native window of the activity when it goes to the background and creates a
+//! new one when it comes back, with `Event::SurfaceDestroyed` and `Event::SurfaceCreated`. The
+//! `wgpu::Surface` is built on the native window, so it's dropped on the first and created again on
+//! the second; the device outlives both. Other platforms never emit the two events and keep the
+//! surface created at start.
+//!
+//! On Android, the example is built as a `cdylib` whose activity is `com.example.MainActivity`.
+//! `android_binding!` exports the functions the activity calls, and `main` runs once it's created.
+
+use std::sync::Arc;
+
+use tao::{
+  event::{Event, WindowEvent},
+  event_loop::{ControlFlow, EventLoop},
+  window::{Window, WindowBuilder},
+};
+
+/// The surface of the window, `None` while there is no native window.
+fn create_surface(
+  instance: &wgpu::Instance,
+  window: &Arc<Window>,
+) -> Option<wgpu::Surface<'static>> {
+  #[cfg(target_os = "android")]
+  {
+    use tao::platform::android::WindowExtAndroid;
+    window.native_window()?;
+  }
+  instance.create_surface(window.clone()).ok()
+}
+
+/// Configures the surface for the size of the window.
+fn configure(
+  surface: &wgpu::Surface,
+  window: &Window,
+  adapter: &wgpu::Adapter,
+  device: &wgpu::Device,
+) -> Option<wgpu::SurfaceConfiguration> {
+  let size = window.inner_size();
+  let config = surface.get_default_config(adapter, size.width.max(1), size.height.max(1))?;
+  surface.configure(device, &config);
+  Some(config)
+}
+
+#[cfg(target_os = "android")]
+fn _start_app() {
+  tao::android_binding!(com, example, MainActivity, setup, main);
+}
+
+#[cfg(target_os = "android")]
+unsafe fn setup(
+  _package: &str,
+  _env: tao::platform::android::prelude::JNIEnv,
+  _looper: &tao::platform::android::prelude::ndk::looper::ForeignLooper,
+  _activity: tao::platform::android::prelude::GlobalRef,
+) {
+}
+
+fn main() {
+  env_logger::init();
+  let event_loop = EventLoop::new();
+  let window = Arc::new(
+    WindowBuilder::new()
+      .with_title("A surface that follows the native window")
+      .build(&event_loop)
+      .unwrap(),
+  );
+
+  let instance = wgpu::Instance::default();
+  // The native window may already exist if the activity was created before the event loop.
+  let mut surface = create_surface(&instance, &window);
+  let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+    compatible_surface: surface.as_ref(),
+    ..Default::default()
+  }))
+  .expect("no adapter can draw to the window");
+  let (device, queue) = pollster::block_on(adapter.request_device(
+    &wgpu::DeviceDescriptor {
+      label: None,
+      required_features: wgpu::Features::empty(),
+      required_limits: wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits()),
+    },
+    None,
+  ))
+  .unwrap();
+
+  let mut config = surface
+    .as_ref()
+    .and_then(|surface| configure(surface, &window, &adapter, &device));
+
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::SurfaceCreated(_) => {
+        surface = create_surface(&instance, &window);
+        config = surface
+          .as_ref()
+          .and_then(|surface| configure(surface, &window, &adapter, &device));
+        window.request_redraw();
+      }
+      // The native window is gone once this event returns, the surface must be dropped now.
+      Event::SurfaceDestroyed(_) => {
+        surface = None;
+        config = None;
+      }
+      Event::WindowEvent {
+        event: WindowEvent::CloseRequested,
+        ..
+      } => *control_flow = ControlFlow::Exit,
+      Event::WindowEvent {
+        event: WindowEvent::Resized(size),
+        ..
+      } => {
+        if size.width > 0 && size.height > 0 {
+          config = surface
+            .as_ref()
+            .and_then(|surface| configure(surface, &window, &adapter, &device));
+          window.request_redraw();
+        }
+      }
+      Event::RedrawRequested(_) => {
+        let (surface, config) = match (&surface, &config) {
+          (Some(surface), Some(config)) => (surface, config),
+          _ => return,
+        };
+        let frame = match surface.get_current_texture() {
+          Ok(frame) => frame,
+          Err(e) => {
+            eprintln!("dropped a frame: {}", e);
+            surface.configure(&device, config);
+            return;
+          }
+        };
+        let view = frame
+          .texture
+          .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder =
+          device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+          label: None,
+          color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+              load: wgpu::LoadOp::Clear(wgpu::Color {
+                r: 0.1,
+                g: 0.4,
+                b: 0.7,
+                a: 1.0,
+              }),
+              store: wgpu::StoreOp::Store,
+            },
+          })],
+          depth_stencil_attachment: None,
+          timestamp_writes: None,
+          occlusion_query_set: None,
+        });
+        queue.submit(Some(encoder.finish()));
+        frame.present();
+      }
+      _ => (),
+    }
+  });
+}
@@ -0,0 +1,109 @@
+// Copyright 2014-2021 The winit contributors
+// Copyright 2021-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+
+//! Clears a window with wgpu, which takes the window through the `HasWindowHandle` and
+//! `HasDisplayHandle` traits of raw-window-handle 0.6 (the `rwh_06` feature of tao).
+
+use std::sync::Arc;
+
+use tao::{
+  event::{Event, WindowEvent},
+  event_loop::{ControlFlow, EventLoop},
+  window::WindowBuilder,
+};
+
+fn main() {
+  env_logger::init();
+  let event_loop = EventLoop::new();
+  let window = Arc::new(
+    WindowBuilder::new()
+      .with_title("A window drawn with wgpu")
+      .build(&event_loop)
+      .unwrap(),
+  );
+
+  let instance = wgpu::Instance::default();
+  // The surface keeps a clone of the window, so it can't outlive it.
+  let surface = instance.create_surface(window.clone()).unwrap();
+  let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+    compatible_surface: Some(&surface),
+    ..Default::default()
+  }))
+  .expect("no adapter can draw to the window");
+  let (device, queue) = pollster::block_on(adapter.request_device(
+    &wgpu::DeviceDescriptor {
+      label: None,
+      required_features: wgpu::Features::empty(),
+      required_limits: wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits()),
+    },
+    None,
+  ))
+  .unwrap();
+
+  let size = window.inner_size();
+  let mut config = surface
+    .get_default_config(&adapter, size.width.max(1), size.height.max(1))
+    .unwrap();
+  surface.configure(&device, &config);
+
+  event_loop.run(move |event, _, control_flow| {
+    *control_flow = ControlFlow::Wait;
+
+    match event {
+      Event::WindowEvent {
+        event: WindowEvent::CloseRequested,
+        ..
+      } => *control_flow = ControlFlow::Exit,
+      Event::WindowEvent {
+        event: WindowEvent::Resized(size),
+        ..
+      } => {
+        // A surface of size zero is invalid, a minimized window keeps the last one.
+        if size.width > 0 && size.height > 0 {
+          config.width = size.width;
+          config.height = size.height;
+          surface.configure(&device, &config);
+          window.request_redraw();
+        }
+      }
+      Event::RedrawRequested(_) => {
+        let frame = match surface.get_current_texture() {
+          Ok(frame) => frame,
+          Err(e) => {
+            eprintln!("dropped a frame: {}", e);
+            surface.configure(&device, &config);
+            return;
+          }
+        };
+        let view = frame
+          .texture
+          .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder =
+          device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+          label: None,
+          color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+              load: wgpu::LoadOp::Clear(wgpu::Color {
+                r: 0.1,
+                g: 0.4,
+                b: 0.7,
+                a: 1.0,
+              }),
+              store: wgpu::StoreOp::Store,
+            },
+          })],
+          depth_stencil_attachment: None,
+          timestamp_writes: None,
+          occlusion_query_set: None,
+        });
+        queue.submit(Some(encoder.finish()));
+        frame.present();
+      }
+      _ => (),
+    }
+  });
+}